@@ -0,0 +1,101 @@
+// Decoding LUDA program accounts from raw account data, so they can be read with any RPC
+// client or indexer. Each decoder checks the account's kind and reads exactly the payload
+// behind its length prefix; profile summaries have neither.
+
+use luda::addressing::IndexAccount;
+use luda::bond::FailBond;
+use luda::config::Config;
+use luda::discriminator::Discriminated;
+use luda::errors::DLUError;
+use luda::leaderboard::Leaderboard;
+use luda::messaging::MessageLog;
+use luda::migrations::decode_account;
+use luda::offer::Offer;
+use luda::onetimekeys::KeyManager;
+use luda::profile_summary::ProfileSummary;
+use luda::quote::Quote;
+use luda::region::RegionIndex;
+use luda::request::Request;
+use luda::review::Review;
+use luda::route::{CarrierRoute, RouteIndex};
+use luda::shipment::Shipment;
+use luda::stake::CarrierStake;
+use luda::stats::Stats;
+use luda::user::User;
+use luda::watchlist::Watchlist;
+
+pub fn decode_config(data: &[u8]) -> Result<Config, DLUError> {
+    decode_account(data, Config::KIND, Config::deserialize)
+}
+
+pub fn decode_stats(data: &[u8]) -> Result<Stats, DLUError> {
+    decode_account(data, Stats::KIND, Stats::deserialize)
+}
+
+pub fn decode_user(data: &[u8]) -> Result<User, DLUError> {
+    decode_account(data, User::KIND, |input| Ok(User::deserialize(input)?))
+}
+
+pub fn decode_profile_summary(data: &[u8]) -> Result<ProfileSummary, DLUError> {
+    ProfileSummary::unpack(data)
+}
+
+pub fn decode_index(data: &[u8]) -> Result<IndexAccount, DLUError> {
+    decode_account(data, IndexAccount::KIND, |input| Ok(IndexAccount::deserialize(input)?))
+}
+
+pub fn decode_offer(data: &[u8]) -> Result<Offer, DLUError> {
+    decode_account(data, Offer::KIND, Offer::deserialize)
+}
+
+pub fn decode_request(data: &[u8]) -> Result<Request, DLUError> {
+    decode_account(data, Request::KIND, Request::deserialize)
+}
+
+pub fn decode_shipment(data: &[u8]) -> Result<Shipment, DLUError> {
+    decode_account(data, Shipment::KIND, Shipment::deserialize)
+}
+
+pub fn decode_bond(data: &[u8]) -> Result<FailBond, DLUError> {
+    decode_account(data, FailBond::KIND, FailBond::deserialize)
+}
+
+pub fn decode_stake(data: &[u8]) -> Result<CarrierStake, DLUError> {
+    decode_account(data, CarrierStake::KIND, CarrierStake::deserialize)
+}
+
+pub fn decode_review(data: &[u8]) -> Result<Review, DLUError> {
+    decode_account(data, Review::KIND, Review::deserialize)
+}
+
+pub fn decode_quote(data: &[u8]) -> Result<Quote, DLUError> {
+    decode_account(data, Quote::KIND, Quote::deserialize)
+}
+
+pub fn decode_region_index(data: &[u8]) -> Result<RegionIndex, DLUError> {
+    decode_account(data, RegionIndex::KIND, RegionIndex::deserialize)
+}
+
+pub fn decode_message_log(data: &[u8]) -> Result<MessageLog, DLUError> {
+    decode_account(data, MessageLog::KIND, MessageLog::deserialize)
+}
+
+pub fn decode_watchlist(data: &[u8]) -> Result<Watchlist, DLUError> {
+    decode_account(data, Watchlist::KIND, Watchlist::deserialize)
+}
+
+pub fn decode_leaderboard(data: &[u8]) -> Result<Leaderboard, DLUError> {
+    decode_account(data, Leaderboard::KIND, Leaderboard::deserialize)
+}
+
+pub fn decode_key_manager(data: &[u8]) -> Result<KeyManager, DLUError> {
+    decode_account(data, KeyManager::KIND, KeyManager::deserialize)
+}
+
+pub fn decode_carrier_route(data: &[u8]) -> Result<CarrierRoute, DLUError> {
+    decode_account(data, CarrierRoute::KIND, CarrierRoute::deserialize)
+}
+
+pub fn decode_route_index(data: &[u8]) -> Result<RouteIndex, DLUError> {
+    decode_account(data, RouteIndex::KIND, RouteIndex::deserialize)
+}
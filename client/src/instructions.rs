@@ -0,0 +1,2692 @@
+// Typed builders for every `DLUInstruction`.
+//
+// Each builder takes the keys callers actually choose (users, token accounts, the vaults
+// escrow sits in) and derives the program's PDAs itself, then lays the accounts out in the
+// order documented on the instruction. They fail only when the instruction cannot be
+// encoded, with `DLUError::SerializationFailed`.
+
+use solana_program::{
+    bpf_loader_upgradeable,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    system_program,
+    sysvar,
+};
+use luda::addressing::EntityType;
+use luda::archive;
+use luda::arbiter::DisputeKind;
+use luda::badge::{self, BadgeTier, TOKEN_METADATA_PROGRAM_ID};
+use luda::compression::{CompressedOffer, ACCOUNT_COMPRESSION_PROGRAM_ID, NOOP_PROGRAM_ID};
+use luda::errors::DLUError;
+use luda::holdback::HoldbackTerms;
+use luda::instruction::DLUInstruction;
+use luda::leaderboard::LeaderboardKind;
+use luda::location::Location;
+use luda::offer::{OfferKind, PriceTier, MAX_PRICE_TIERS};
+use luda::onetimekeys;
+use luda::region::REGION_GEOHASH_LEN;
+use luda::stream::PaymentStream;
+use luda::timelock::GovernanceAction;
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use crate::pda;
+
+pub use luda::keeper::{
+    batch_expire_instruction, crank_expire_instruction, create_expiry_thread_instruction, CrankAccounts, ExpiryBatchEntry,
+};
+
+/// Accounts of the side listing an offer, request, or shipment.
+pub struct ListAccounts {
+    pub entity: Pubkey,
+    pub lister_user_account: Pubkey,
+    pub lister: Pubkey,
+    pub lister_account: Pubkey,  // Token account the deposit is taken from.
+    pub escrow_account: Pubkey,  // Vault of the payment mint, from `pda::derive_vault_address`.
+    pub rent_payer: Option<Pubkey>,  // Pays a new RegionIndex's rent instead of the lister.
+}
+
+/// Accounts of the side accepting an offer, request, or shipment.
+pub struct AcceptAccounts {
+    pub entity: Pubkey,
+    pub acceptor_user_account: Pubkey,
+    pub acceptor: Pubkey,
+    pub acceptor_account: Pubkey,  // Token account the deposit is taken from.
+    pub acceptor_authority: Pubkey,
+    pub escrow_account: Pubkey,  // Vault of the payment mint, from `pda::derive_vault_address`.
+}
+
+/// Accounts of both sides of an accepted deal or shipment. For shipments the seller is
+/// the sender and the buyer the carrier.
+pub struct SettleAccounts {
+    pub entity: Pubkey,
+    pub seller_user_account: Pubkey,
+    pub seller: Pubkey,
+    pub seller_account: Pubkey,
+    pub buyer_user_account: Pubkey,
+    pub buyer: Pubkey,
+    pub buyer_account: Pubkey,
+    pub escrow_account: Pubkey,  // Vault of the payment mint, from `pda::derive_vault_address`.
+    pub payment_mint: Pubkey,  // Picks the treasury the protocol fee goes to.
+}
+
+/// Accounts of the lister's own escrowed deposit, used to edit, cancel, and relist.
+pub struct ListerEscrowAccounts {
+    pub entity: Pubkey,
+    pub lister_user_account: Pubkey,
+    pub lister: Pubkey,
+    pub lister_account: Pubkey,
+    pub escrow_account: Pubkey,  // Vault of the payment mint, from `pda::derive_vault_address`.
+}
+
+/// Accounts of the NFT an NFT offer sells, moved into its escrow on listing and relisting.
+pub struct NftListAccounts {
+    pub seller_nft_account: Pubkey,
+    pub nft_mint: Pubkey,
+    pub nft_token_program: Pubkey,  // SPL Token or Token-2022, whichever owns the mint.
+    pub rent_payer: Option<Pubkey>,  // Pays a new NFT escrow's rent instead of the seller, such as a relayer.
+}
+
+/// Accounts of a fail claim. The penalty account is the DLU mint when penalties are burned.
+pub struct FailAccounts {
+    pub entity: Pubkey,
+    pub accused_user_account: Pubkey,
+    pub accused: Pubkey,
+    pub accused_account: Pubkey,
+    pub claimant: Pubkey,
+    pub claimant_account: Pubkey,
+    pub escrow_account: Pubkey,  // Vault of the payment mint, from `pda::derive_vault_address`.
+    pub penalty_account: Pubkey,
+    pub rent_payer: Option<Pubkey>,  // Pays the FailBond's rent instead of the claimant, such as a relayer.
+}
+
+/// Token accounts the bond of an appealed ruling is paid out to on settlement.
+pub struct AppealPayoutAccounts {
+    pub appellant_account: Pubkey,
+    pub panelist_accounts: Vec<Pubkey>,  // Of the panelists who voted with the majority, in panel order.
+}
+
+fn build(
+    program_id: &Pubkey,
+    instruction: &DLUInstruction,
+    accounts: Vec<AccountMeta>,
+) -> Result<Instruction, DLUError> {
+    Ok(Instruction::new_with_bytes(*program_id, &instruction.pack()?, accounts))
+}
+
+fn governance_instruction(
+    program_id: &Pubkey,
+    governance: &Pubkey,
+    instruction: &DLUInstruction,
+) -> Result<Instruction, DLUError> {
+    build(program_id, instruction, vec![
+        AccountMeta::new(pda::config(program_id), false),
+        AccountMeta::new_readonly(*governance, true),
+    ])
+}
+
+// CONFIG
+
+/// Initializes the config, stats, arbiter registry and timelock queue, signed by the
+/// program's upgrade authority, who pays the rent.
+pub fn initialize_config(
+    program_id: &Pubkey,
+    upgrade_authority: &Pubkey,
+    governance: Pubkey,
+    tvl_cap: u64,
+    terms_hash: [u8; 32],
+) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::InitializeConfig { governance, tvl_cap, terms_hash }, vec![
+        AccountMeta::new(pda::config(program_id), false),
+        AccountMeta::new(pda::stats(program_id), false),
+        AccountMeta::new(pda::arbiter_registry(program_id), false),
+        AccountMeta::new(pda::timelock_queue(program_id), false),
+        AccountMeta::new(*upgrade_authority, true),
+        AccountMeta::new_readonly(bpf_loader_upgradeable::get_program_data_address(program_id), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ])
+}
+
+pub fn raise_tvl_cap(program_id: &Pubkey, governance: &Pubkey, new_cap: u64) -> Result<Instruction, DLUError> {
+    governance_instruction(program_id, governance, &DLUInstruction::RaiseTvlCap { new_cap })
+}
+
+pub fn set_capabilities(program_id: &Pubkey, governance: &Pubkey, capabilities: u64) -> Result<Instruction, DLUError> {
+    governance_instruction(program_id, governance, &DLUInstruction::SetCapabilities { capabilities })
+}
+
+pub fn migrate_escrow(
+    program_id: &Pubkey,
+    governance: &Pubkey,
+    entity_type: EntityType,
+    entity: &Pubkey,
+    legacy_escrow_account: &Pubkey,
+    legacy_escrow_authority: &Pubkey,
+    payment_mint: &Pubkey,
+) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::MigrateEscrow { entity_type }, vec![
+        AccountMeta::new_readonly(pda::config(program_id), false),
+        AccountMeta::new_readonly(*governance, true),
+        AccountMeta::new(*entity, false),
+        AccountMeta::new(*legacy_escrow_account, false),
+        AccountMeta::new_readonly(*legacy_escrow_authority, true),
+        AccountMeta::new(pda::derive_vault_address(program_id, payment_mint).0, false),
+        AccountMeta::new_readonly(pda::vault_registry(program_id), false),
+    ])
+}
+
+pub fn publish_terms(program_id: &Pubkey, governance: &Pubkey, terms_hash: [u8; 32]) -> Result<Instruction, DLUError> {
+    governance_instruction(program_id, governance, &DLUInstruction::PublishTerms { terms_hash })
+}
+
+pub fn set_reputation_half_life(
+    program_id: &Pubkey,
+    governance: &Pubkey,
+    half_life: u64,
+) -> Result<Instruction, DLUError> {
+    governance_instruction(program_id, governance, &DLUInstruction::SetReputationHalfLife { half_life })
+}
+
+// USERS
+
+/// Creates a user, optionally referred by `referrer`, and the owner's profile summary and
+/// index; pass the DLU mint and the token program owning it, SPL Token or Token-2022, to
+/// also create the owner's associated token account when it doesn't exist yet. Rent is paid by `rent_payer`,
+/// such as a relayer onboarding an owner without SOL, or else by the owner.
+#[allow(clippy::too_many_arguments)]
+pub fn create_user(
+    program_id: &Pubkey,
+    user_account: &Pubkey,
+    owner: &Pubkey,
+    username: String,
+    terms_hash: [u8; 32],
+    referrer: Option<&Pubkey>,
+    dlu_mint: Option<(&Pubkey, &Pubkey)>,
+    rent_payer: Option<&Pubkey>,
+) -> Result<Instruction, DLUError> {
+    let mut metas = vec![
+        AccountMeta::new(*user_account, false),
+        AccountMeta::new_readonly(*owner, true),
+        AccountMeta::new_readonly(pda::config(program_id), false),
+        AccountMeta::new(pda::summary(program_id, owner), false),
+        AccountMeta::new(pda::stats(program_id), false),
+        AccountMeta::new(pda::index(program_id, owner), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    pay_rent(&mut metas, 1, rent_payer);
+    if let Some((mint, token_program)) = dlu_mint {
+        metas.extend([
+            AccountMeta::new(get_associated_token_address_with_program_id(owner, mint, token_program), false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        ]);
+    }
+    build(program_id, &DLUInstruction::CreateUser { username, terms_hash, referrer: referrer.copied() }, metas)
+}
+
+/// Any space the profile outgrows is paid by `rent_payer`, or else by the owner.
+pub fn update_user_profile(
+    program_id: &Pubkey,
+    user_account: &Pubkey,
+    owner: &Pubkey,
+    display_name: Option<String>,
+    metadata_uri: Option<String>,
+    contact_hints: Option<String>,
+    rent_payer: Option<&Pubkey>,
+) -> Result<Instruction, DLUError> {
+    let mut metas = vec![
+        AccountMeta::new(*user_account, false),
+        AccountMeta::new_readonly(*owner, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    pay_rent(&mut metas, 1, rent_payer);
+    build(program_id, &DLUInstruction::UpdateUserProfile { display_name, metadata_uri, contact_hints }, metas)
+}
+
+pub fn reaccept_terms(
+    program_id: &Pubkey,
+    user_account: &Pubkey,
+    owner: &Pubkey,
+    terms_hash: [u8; 32],
+) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::ReacceptTerms { terms_hash }, vec![
+        AccountMeta::new(*user_account, false),
+        AccountMeta::new_readonly(*owner, true),
+        AccountMeta::new_readonly(pda::config(program_id), false),
+    ])
+}
+
+// OFFERS
+
+/// `nft` lists an NFT offer; otherwise `barter_insurance` lists a barter and `inventory`
+/// the maximum quantity and price tiers of stock, which both take a zero `payment`.
+/// `meeting_point_hash` hides the exact meeting point until it is revealed.
+/// `holdback` keeps a warranty share of the payment in escrow after completion.
+#[allow(clippy::too_many_arguments)]
+pub fn list_offer(
+    program_id: &Pubkey,
+    accounts: &ListAccounts,
+    id: u64,
+    goodsorservice_name: String,
+    goodsorservice_description: String,
+    payment: u64,
+    meeting_point: Location,
+    meeting_datetime: i64,
+    nft: Option<&NftListAccounts>,
+    barter_insurance: Option<u64>,
+    inventory: Option<(u32, [PriceTier; MAX_PRICE_TIERS])>,
+    stream_duration: Option<i64>,
+    meeting_point_hash: Option<[u8; 32]>,
+    holdback: Option<HoldbackTerms>,
+) -> Result<Instruction, DLUError> {
+    let kind = match (nft, barter_insurance, inventory, stream_duration) {
+        (Some(nft), ..) => OfferKind::Nft { mint: nft.nft_mint },
+        (None, Some(insurance), ..) => OfferKind::Barter { insurance },
+        (None, None, Some((max_quantity, tiers)), _) => OfferKind::Inventory { max_quantity, tiers, quantity: 0 },
+        (None, None, None, Some(duration)) => OfferKind::Stream(PaymentStream::new(duration)),
+        (None, None, None, None) => OfferKind::Described,
+    };
+    let region = meeting_point.region();
+    let instruction = DLUInstruction::ListOffer {
+        id, goodsorservice_name, goodsorservice_description, payment, meeting_point, meeting_datetime, kind,
+        meeting_point_hash, holdback,
+    };
+    let mut metas = list_metas(program_id, accounts);
+    metas.extend([
+        AccountMeta::new_readonly(pda::config(program_id), false),
+        AccountMeta::new(pda::stats(program_id), false),
+        AccountMeta::new(pda::index(program_id, &accounts.lister), false),
+    ]);
+    if let Some(nft) = nft {
+        metas.extend(nft_deposit_metas(program_id, &accounts.entity, nft));
+        pay_rent(&mut metas, 2, nft.rent_payer.as_ref());
+    }
+    push_region(&mut metas, program_id, region, 2, accounts.rent_payer.as_ref());
+    build(program_id, &instruction, metas)
+}
+
+/// `quantity` is 1 unless the offer sells inventory.
+pub fn accept_offer(
+    program_id: &Pubkey,
+    accounts: &AcceptAccounts,
+    seller: &Pubkey,
+    quantity: u32,
+) -> Result<Instruction, DLUError> {
+    let mut metas = accept_metas(program_id, accounts);
+    metas.push(AccountMeta::new(pda::index(program_id, seller), false));
+    build(program_id, &DLUInstruction::AcceptOffer { quantity, promo_code: None }, metas)
+}
+
+/// `nonce` is the offer's current nonce. `buyer_nft_account` receives the NFT of an NFT offer.
+pub fn complete_offer(
+    program_id: &Pubkey,
+    accounts: &SettleAccounts,
+    buyer_key: String,
+    seller_key: String,
+    nonce: u64,
+    buyer_nft_account: Option<&Pubkey>,
+) -> Result<Instruction, DLUError> {
+    let mut metas = complete_metas(program_id, accounts);
+    metas.extend(nft_release_metas(program_id, &accounts.entity, buyer_nft_account));
+    build(program_id, &DLUInstruction::CompleteOffer { buyer_key, seller_key, nonce }, metas)
+}
+
+/// `seller_nft_account` gets back the NFT of an NFT offer.
+pub fn fail_offer(
+    program_id: &Pubkey,
+    accounts: &FailAccounts,
+    seller_key: String,
+    nonce: u64,
+    seller_nft_account: Option<&Pubkey>,
+) -> Result<Instruction, DLUError> {
+    let mut metas = fail_metas(program_id, accounts, true);
+    metas.extend(nft_release_metas(program_id, &accounts.entity, seller_nft_account));
+    build(program_id, &DLUInstruction::FailOffer { seller_key, nonce }, metas)
+}
+
+/// `seller_nft_account` gets back the NFT of an NFT offer.
+pub fn expire_offer(
+    program_id: &Pubkey,
+    accounts: &SettleAccounts,
+    seller_nft_account: Option<&Pubkey>,
+) -> Result<Instruction, DLUError> {
+    let mut metas = expire_metas(program_id, accounts);
+    metas.extend(nft_release_metas(program_id, &accounts.entity, seller_nft_account));
+    build(program_id, &DLUInstruction::ExpireOffer, metas)
+}
+
+pub fn update_offer(
+    program_id: &Pubkey,
+    accounts: &ListerEscrowAccounts,
+    goodsorservice_description: Option<String>,
+    meeting_point: Option<Location>,
+    meeting_datetime: Option<i64>,
+    payment: Option<u64>,
+) -> Result<Instruction, DLUError> {
+    let instruction = DLUInstruction::UpdateOffer { goodsorservice_description, meeting_point, meeting_datetime, payment };
+    build(program_id, &instruction, vec![
+        AccountMeta::new(accounts.entity, false),
+        AccountMeta::new(accounts.lister_user_account, false),
+        AccountMeta::new_readonly(accounts.lister, true),
+        AccountMeta::new(accounts.lister_account, false),
+        AccountMeta::new(accounts.escrow_account, false),
+        AccountMeta::new_readonly(pda::vault_registry(program_id), false),
+        AccountMeta::new_readonly(pda::config(program_id), false),
+        AccountMeta::new(pda::stats(program_id), false),
+    ])
+}
+
+/// `seller_nft_account` gets back the NFT of an NFT offer.
+pub fn cancel_offer(
+    program_id: &Pubkey,
+    accounts: &ListerEscrowAccounts,
+    seller_nft_account: Option<&Pubkey>,
+) -> Result<Instruction, DLUError> {
+    let mut metas = cancel_metas(program_id, accounts);
+    metas.push(AccountMeta::new(pda::index(program_id, &accounts.lister), false));
+    metas.extend(nft_release_metas(program_id, &accounts.entity, seller_nft_account));
+    build(program_id, &DLUInstruction::CancelOffer, metas)
+}
+
+/// Cancels up to `max_count` listed offers; `offers` must follow the order of the
+/// seller's IndexAccount.
+pub fn cancel_all_listed(
+    program_id: &Pubkey,
+    seller: &Pubkey,
+    seller_account: &Pubkey,
+    escrow_account: &Pubkey,
+    offers: &[Pubkey],
+    max_count: u8,
+) -> Result<Instruction, DLUError> {
+    let mut metas = vec![
+        AccountMeta::new_readonly(*seller, true),
+        AccountMeta::new(pda::index(program_id, seller), false),
+        AccountMeta::new(*seller_account, false),
+        AccountMeta::new(*escrow_account, false),
+        AccountMeta::new_readonly(pda::vault_registry(program_id), false),
+        AccountMeta::new(pda::stats(program_id), false),
+    ];
+    metas.extend(offers.iter().map(|offer| AccountMeta::new(*offer, false)));
+    build(program_id, &DLUInstruction::CancelAllListed { max_count }, metas)
+}
+
+pub fn relist_offer(
+    program_id: &Pubkey,
+    accounts: &ListerEscrowAccounts,
+    nft: Option<&NftListAccounts>,
+) -> Result<Instruction, DLUError> {
+    let mut metas = relist_metas(program_id, accounts);
+    metas.push(AccountMeta::new(pda::index(program_id, &accounts.lister), false));
+    if let Some(nft) = nft {
+        metas.extend(nft_deposit_metas(program_id, &accounts.entity, nft));
+        pay_rent(&mut metas, 2, nft.rent_payer.as_ref());
+    }
+    build(program_id, &DLUInstruction::RelistOffer, metas)
+}
+
+// REQUESTS
+
+#[allow(clippy::too_many_arguments)]
+pub fn list_request(
+    program_id: &Pubkey,
+    accounts: &ListAccounts,
+    id: u64,
+    goodsorservice_name: String,
+    goodsorservice_description: String,
+    payment: u64,
+    meeting_point: Location,
+    meeting_datetime: i64,
+    meeting_point_hash: Option<[u8; 32]>,
+) -> Result<Instruction, DLUError> {
+    let region = meeting_point.region();
+    let instruction = DLUInstruction::ListRequest {
+        id, goodsorservice_name, goodsorservice_description, payment, meeting_point, meeting_datetime,
+        meeting_point_hash,
+    };
+    let mut metas = list_metas(program_id, accounts);
+    metas.extend([
+        AccountMeta::new_readonly(pda::config(program_id), false),
+        AccountMeta::new(pda::stats(program_id), false),
+    ]);
+    push_region(&mut metas, program_id, region, 2, accounts.rent_payer.as_ref());
+    build(program_id, &instruction, metas)
+}
+
+pub fn accept_request(program_id: &Pubkey, accounts: &AcceptAccounts) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::AcceptRequest, accept_metas(program_id, accounts))
+}
+
+pub fn complete_request(
+    program_id: &Pubkey,
+    accounts: &SettleAccounts,
+    buyer_key: String,
+    seller_key: String,
+    nonce: u64,
+) -> Result<Instruction, DLUError> {
+    let instruction = DLUInstruction::CompleteRequest { buyer_key, seller_key, nonce };
+    build(program_id, &instruction, complete_metas(program_id, accounts))
+}
+
+pub fn fail_request(
+    program_id: &Pubkey,
+    accounts: &FailAccounts,
+    seller_key: String,
+    nonce: u64,
+) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::FailRequest { seller_key, nonce }, fail_metas(program_id, accounts, true))
+}
+
+pub fn expire_request(program_id: &Pubkey, accounts: &SettleAccounts) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::ExpireRequest, expire_metas(program_id, accounts))
+}
+
+pub fn cancel_request(program_id: &Pubkey, accounts: &ListerEscrowAccounts) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::CancelRequest, cancel_metas(program_id, accounts))
+}
+
+pub fn relist_request(program_id: &Pubkey, accounts: &ListerEscrowAccounts) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::RelistRequest, relist_metas(program_id, accounts))
+}
+
+// SHIPMENTS
+
+#[allow(clippy::too_many_arguments)]
+pub fn list_shipment(
+    program_id: &Pubkey,
+    accounts: &ListAccounts,
+    recipient_user_account: &Pubkey,
+    id: u64,
+    items_name: String,
+    quantity: u32,
+    payment: u64,
+    insurance: u64,
+    pickup_point: Location,
+    pickup_datetime: i64,
+    drop_off_point: Location,
+    drop_off_datetime: i64,
+) -> Result<Instruction, DLUError> {
+    let region = pickup_point.region();
+    let instruction = DLUInstruction::ListShipment {
+        id, items_name, quantity, payment, insurance, pickup_point, pickup_datetime, drop_off_point, drop_off_datetime,
+    };
+    let mut metas = list_metas(program_id, accounts);
+    metas.extend([
+        AccountMeta::new_readonly(*recipient_user_account, false),
+        AccountMeta::new_readonly(pda::config(program_id), false),
+        AccountMeta::new(pda::stats(program_id), false),
+    ]);
+    push_region(&mut metas, program_id, region, 2, accounts.rent_payer.as_ref());
+    build(program_id, &instruction, metas)
+}
+
+/// Accepts a shipment; pass `staked` when its payment is above the stake threshold.
+pub fn accept_shipment(program_id: &Pubkey, accounts: &AcceptAccounts, staked: bool) -> Result<Instruction, DLUError> {
+    let mut metas = accept_metas(program_id, accounts);
+    if staked {
+        metas.push(AccountMeta::new(pda::derive_stake_address(program_id, &accounts.acceptor).0, false));
+    }
+    build(program_id, &DLUInstruction::AcceptShipment, metas)
+}
+
+pub fn complete_shipment(
+    program_id: &Pubkey,
+    accounts: &SettleAccounts,
+    carrier_key: String,
+    recipient_key: String,
+    proof_hash: Option<[u8; 32]>,
+    nonce: u64,
+) -> Result<Instruction, DLUError> {
+    let instruction = DLUInstruction::CompleteShipment { carrier_key, recipient_key, proof_hash, nonce };
+    build(program_id, &instruction, complete_metas(program_id, accounts))
+}
+
+pub fn fail_shipment(
+    program_id: &Pubkey,
+    accounts: &FailAccounts,
+    sender_key: String,
+    nonce: u64,
+) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::FailShipment { sender_key, nonce }, fail_metas(program_id, accounts, false))
+}
+
+pub fn expire_shipment(program_id: &Pubkey, accounts: &SettleAccounts) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::ExpireShipment, expire_metas(program_id, accounts))
+}
+
+pub fn cancel_shipment(program_id: &Pubkey, accounts: &ListerEscrowAccounts) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::CancelShipment, cancel_metas(program_id, accounts))
+}
+
+pub fn relist_shipment(program_id: &Pubkey, accounts: &ListerEscrowAccounts) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::RelistShipment, relist_metas(program_id, accounts))
+}
+
+// REFUNDS
+
+pub fn issue_goodwill_refund(
+    program_id: &Pubkey,
+    entity_type: EntityType,
+    accounts: &SettleAccounts,
+    amount: u64,
+) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::IssueGoodwillRefund { entity_type, amount }, vec![
+        AccountMeta::new_readonly(accounts.entity, false),
+        AccountMeta::new(accounts.seller_user_account, false),
+        AccountMeta::new_readonly(accounts.seller, true),
+        AccountMeta::new(accounts.buyer_user_account, false),
+        AccountMeta::new(accounts.seller_account, false),
+        AccountMeta::new(accounts.buyer_account, false),
+        AccountMeta::new(pda::summary(program_id, &accounts.seller), false),
+        AccountMeta::new_readonly(pda::config(program_id), false),
+    ])
+}
+
+// BONDS
+
+pub fn set_fail_bond(program_id: &Pubkey, governance: &Pubkey, amount: u64) -> Result<Instruction, DLUError> {
+    governance_instruction(program_id, governance, &DLUInstruction::SetFailBond { amount })
+}
+
+pub fn dispute_fail(program_id: &Pubkey, entity: &Pubkey, accused: &Pubkey) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::DisputeFail, vec![
+        AccountMeta::new_readonly(*entity, false),
+        AccountMeta::new(pda::bond(program_id, entity), false),
+        AccountMeta::new_readonly(*accused, true),
+    ])
+}
+
+pub fn resolve_fail_dispute(
+    program_id: &Pubkey,
+    governance: &Pubkey,
+    entity: &Pubkey,
+    escrow_account: &Pubkey,
+    recipient_account: &Pubkey,
+    fail_justified: bool,
+) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::ResolveFailDispute { fail_justified }, vec![
+        AccountMeta::new_readonly(pda::config(program_id), false),
+        AccountMeta::new_readonly(*governance, true),
+        AccountMeta::new_readonly(*entity, false),
+        AccountMeta::new(pda::bond(program_id, entity), false),
+        AccountMeta::new(*escrow_account, false),
+        AccountMeta::new_readonly(pda::vault_registry(program_id), false),
+        AccountMeta::new(*recipient_account, false),
+        AccountMeta::new(pda::stats(program_id), false),
+    ])
+}
+
+pub fn reclaim_fail_bond(
+    program_id: &Pubkey,
+    entity: &Pubkey,
+    claimant: &Pubkey,
+    claimant_account: &Pubkey,
+    escrow_account: &Pubkey,
+) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::ReclaimFailBond, vec![
+        AccountMeta::new_readonly(*entity, false),
+        AccountMeta::new(pda::bond(program_id, entity), false),
+        AccountMeta::new_readonly(*claimant, true),
+        AccountMeta::new(*claimant_account, false),
+        AccountMeta::new(*escrow_account, false),
+        AccountMeta::new_readonly(pda::vault_registry(program_id), false),
+        AccountMeta::new(pda::stats(program_id), false),
+    ])
+}
+
+// FEES
+
+pub fn set_fee(
+    program_id: &Pubkey,
+    governance: &Pubkey,
+    fee_bps: u16,
+    exempt_credible: bool,
+) -> Result<Instruction, DLUError> {
+    governance_instruction(program_id, governance, &DLUInstruction::SetFee { fee_bps, exempt_credible })
+}
+
+pub fn set_penalty_share(program_id: &Pubkey, governance: &Pubkey, share_bps: u16) -> Result<Instruction, DLUError> {
+    governance_instruction(program_id, governance, &DLUInstruction::SetPenaltyShare { share_bps })
+}
+
+pub fn withdraw_treasury(
+    program_id: &Pubkey,
+    governance: &Pubkey,
+    mint: &Pubkey,
+    destination_account: &Pubkey,
+    amount: u64,
+) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::WithdrawTreasury { amount }, vec![
+        AccountMeta::new_readonly(pda::config(program_id), false),
+        AccountMeta::new_readonly(*governance, true),
+        AccountMeta::new(pda::derive_treasury_address(program_id, mint).0, false),
+        AccountMeta::new(*destination_account, false),
+    ])
+}
+
+// STAKING
+
+pub fn set_stake_requirements(
+    program_id: &Pubkey,
+    governance: &Pubkey,
+    stake_threshold: u64,
+    min_stake: u64,
+) -> Result<Instruction, DLUError> {
+    governance_instruction(program_id, governance, &DLUInstruction::SetStakeRequirements { stake_threshold, min_stake })
+}
+
+/// A first stake creates the stake account, whose rent is paid by `rent_payer`, or else by
+/// the carrier.
+pub fn stake(
+    program_id: &Pubkey,
+    carrier: &Pubkey,
+    carrier_account: &Pubkey,
+    escrow_account: &Pubkey,
+    amount: u64,
+    rent_payer: Option<&Pubkey>,
+) -> Result<Instruction, DLUError> {
+    let mut metas = vec![
+        AccountMeta::new(pda::derive_stake_address(program_id, carrier).0, false),
+        AccountMeta::new_readonly(*carrier, true),
+        AccountMeta::new(*carrier_account, false),
+        AccountMeta::new(*escrow_account, false),
+        AccountMeta::new_readonly(pda::vault_registry(program_id), false),
+        AccountMeta::new_readonly(pda::config(program_id), false),
+        AccountMeta::new(pda::stats(program_id), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    pay_rent(&mut metas, 1, rent_payer);
+    build(program_id, &DLUInstruction::Stake { amount }, metas)
+}
+
+pub fn unstake(
+    program_id: &Pubkey,
+    carrier: &Pubkey,
+    carrier_account: &Pubkey,
+    escrow_account: &Pubkey,
+    amount: u64,
+) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::Unstake { amount }, vec![
+        AccountMeta::new(pda::derive_stake_address(program_id, carrier).0, false),
+        AccountMeta::new_readonly(*carrier, true),
+        AccountMeta::new(*carrier_account, false),
+        AccountMeta::new(*escrow_account, false),
+        AccountMeta::new_readonly(pda::vault_registry(program_id), false),
+        AccountMeta::new(pda::stats(program_id), false),
+    ])
+}
+
+pub fn slash(
+    program_id: &Pubkey,
+    shipment: &Pubkey,
+    carrier: &Pubkey,
+    escrow_account: &Pubkey,
+    penalty_account: &Pubkey,
+) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::Slash, vec![
+        AccountMeta::new(*shipment, false),
+        AccountMeta::new_readonly(pda::bond(program_id, shipment), false),
+        AccountMeta::new(pda::derive_stake_address(program_id, carrier).0, false),
+        AccountMeta::new(*escrow_account, false),
+        AccountMeta::new_readonly(pda::vault_registry(program_id), false),
+        AccountMeta::new(*penalty_account, false),
+        AccountMeta::new_readonly(pda::config(program_id), false),
+        AccountMeta::new(pda::stats(program_id), false),
+    ])
+}
+
+// REVIEWS
+
+/// The review's account is created with it, its rent paid by `rent_payer`, or else by the
+/// reviewer.
+#[allow(clippy::too_many_arguments)]
+pub fn submit_review(
+    program_id: &Pubkey,
+    entity_type: EntityType,
+    entity: &Pubkey,
+    deal_id: u64,
+    reviewer: &Pubkey,
+    reviewee_user_account: &Pubkey,
+    reviewee: &Pubkey,
+    rating: u8,
+    review_hash: [u8; 32],
+    rent_payer: Option<&Pubkey>,
+) -> Result<Instruction, DLUError> {
+    let mut metas = vec![
+        AccountMeta::new_readonly(*entity, false),
+        AccountMeta::new_readonly(*reviewer, true),
+        AccountMeta::new(pda::derive_review_address(program_id, deal_id, reviewer).0, false),
+        AccountMeta::new(*reviewee_user_account, false),
+        AccountMeta::new(pda::summary(program_id, reviewee), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    pay_rent(&mut metas, 1, rent_payer);
+    build(program_id, &DLUInstruction::SubmitReview { entity_type, rating, review_hash }, metas)
+}
+
+// BADGES
+
+pub fn sync_badge(
+    program_id: &Pubkey,
+    user_account: &Pubkey,
+    user: &Pubkey,
+    tier: BadgeTier,
+    rent_payer: &Pubkey,
+) -> Result<Instruction, DLUError> {
+    let mint = pda::derive_badge_mint_address(program_id, tier).0;
+    build(program_id, &DLUInstruction::SyncBadge { tier }, vec![
+        AccountMeta::new_readonly(*user_account, false),
+        AccountMeta::new(mint, false),
+        AccountMeta::new(pda::derive_badge_address(program_id, user, tier).0, false),
+        AccountMeta::new(badge::metadata_address(&mint), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(TOKEN_METADATA_PROGRAM_ID, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new(*rent_payer, true),
+    ])
+}
+
+// KEEPERS
+
+pub fn set_crank_bounty(program_id: &Pubkey, governance: &Pubkey, bounty: u64) -> Result<Instruction, DLUError> {
+    governance_instruction(program_id, governance, &DLUInstruction::SetCrankBounty { bounty })
+}
+
+// WALLETS
+
+/// Deposits into the user's wallet; pass the DLU mint when it charges a Token-2022 transfer
+/// fee, which the owner then pays on top of `amount`.
+pub fn deposit_dlu(
+    program_id: &Pubkey,
+    user_account: &Pubkey,
+    owner: &Pubkey,
+    owner_token_account: &Pubkey,
+    amount: u64,
+    fee_mint: Option<&Pubkey>,
+) -> Result<Instruction, DLUError> {
+    let metas = wallet_metas(program_id, user_account, owner, owner_token_account, fee_mint);
+    build(program_id, &DLUInstruction::DepositDLU { amount }, metas)
+}
+
+/// Withdraws from the user's wallet; pass the DLU mint when it charges a Token-2022 transfer
+/// fee, which is then withheld from what the owner receives. A user governed by a multisig
+/// passes its `cosigners`, none when the amount is below its high value.
+pub fn withdraw_dlu(
+    program_id: &Pubkey,
+    user_account: &Pubkey,
+    owner: &Pubkey,
+    owner_token_account: &Pubkey,
+    amount: u64,
+    cosigners: Option<&[Pubkey]>,
+    fee_mint: Option<&Pubkey>,
+) -> Result<Instruction, DLUError> {
+    let mut metas = wallet_metas(program_id, user_account, owner, owner_token_account, None);
+    if let Some(cosigners) = cosigners {
+        metas.extend(multisig_metas(program_id, owner, cosigners));
+    }
+    metas.extend(fee_mint.map(|mint| AccountMeta::new_readonly(*mint, false)));
+    build(program_id, &DLUInstruction::WithdrawDLU { amount }, metas)
+}
+
+pub fn reconcile_wallet(program_id: &Pubkey, user_account: &Pubkey, owner: &Pubkey) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::ReconcileWallet, vec![
+        AccountMeta::new(*user_account, false),
+        AccountMeta::new_readonly(pda::derive_wallet_address(program_id, owner).0, false),
+    ])
+}
+
+fn wallet_metas(
+    program_id: &Pubkey,
+    user_account: &Pubkey,
+    owner: &Pubkey,
+    owner_token_account: &Pubkey,
+    fee_mint: Option<&Pubkey>,
+) -> Vec<AccountMeta> {
+    let mut metas = vec![
+        AccountMeta::new(*user_account, false),
+        AccountMeta::new_readonly(*owner, true),
+        AccountMeta::new(*owner_token_account, false),
+        AccountMeta::new(pda::derive_wallet_address(program_id, owner).0, false),
+    ];
+    metas.extend(fee_mint.map(|mint| AccountMeta::new_readonly(*mint, false)));
+    metas
+}
+
+// QUOTES
+
+#[allow(clippy::too_many_arguments)]
+pub fn submit_quote(
+    program_id: &Pubkey,
+    request: &Pubkey,
+    seller_user_account: &Pubkey,
+    seller: &Pubkey,
+    seller_account: &Pubkey,
+    escrow_account: &Pubkey,
+    price: u64,
+    note: String,
+) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::SubmitQuote { price, note }, vec![
+        AccountMeta::new_readonly(*request, false),
+        AccountMeta::new(pda::quote(program_id, request, seller), false),
+        AccountMeta::new_readonly(*seller_user_account, false),
+        AccountMeta::new_readonly(*seller, true),
+        AccountMeta::new(*seller_account, false),
+        AccountMeta::new(*escrow_account, false),
+        AccountMeta::new_readonly(pda::vault_registry(program_id), false),
+        AccountMeta::new_readonly(pda::config(program_id), false),
+        AccountMeta::new(pda::stats(program_id), false),
+    ])
+}
+
+/// `accounts` are the buyer's, as for canceling the request; `seller` identifies the quote.
+pub fn select_quote(
+    program_id: &Pubkey,
+    accounts: &ListerEscrowAccounts,
+    seller_user_account: &Pubkey,
+    seller: &Pubkey,
+) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::SelectQuote, vec![
+        AccountMeta::new(accounts.entity, false),
+        AccountMeta::new_readonly(accounts.lister, true),
+        AccountMeta::new(accounts.lister_account, false),
+        AccountMeta::new(accounts.escrow_account, false),
+        AccountMeta::new_readonly(pda::vault_registry(program_id), false),
+        AccountMeta::new(pda::quote(program_id, &accounts.entity, seller), false),
+        AccountMeta::new(*seller_user_account, false),
+        AccountMeta::new_readonly(pda::config(program_id), false),
+        AccountMeta::new(pda::stats(program_id), false),
+    ])
+}
+
+pub fn withdraw_quote(
+    program_id: &Pubkey,
+    request: &Pubkey,
+    seller: &Pubkey,
+    seller_account: &Pubkey,
+    escrow_account: &Pubkey,
+) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::WithdrawQuote, vec![
+        AccountMeta::new(pda::quote(program_id, request, seller), false),
+        AccountMeta::new_readonly(*seller, true),
+        AccountMeta::new(*seller_account, false),
+        AccountMeta::new(*escrow_account, false),
+        AccountMeta::new_readonly(pda::vault_registry(program_id), false),
+        AccountMeta::new(pda::stats(program_id), false),
+    ])
+}
+
+// MATCHING
+
+/// `matcher_account` receives the finder's fee.
+#[allow(clippy::too_many_arguments)]
+pub fn match_request_to_offer(
+    program_id: &Pubkey,
+    offer: &Pubkey,
+    request: &Pubkey,
+    seller: &Pubkey,
+    buyer_user_account: &Pubkey,
+    buyer_account: &Pubkey,
+    escrow_account: &Pubkey,
+    matcher_account: &Pubkey,
+) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::MatchRequestToOffer, vec![
+        AccountMeta::new(*offer, false),
+        AccountMeta::new(*request, false),
+        AccountMeta::new(*buyer_user_account, false),
+        AccountMeta::new(*buyer_account, false),
+        AccountMeta::new(*escrow_account, false),
+        AccountMeta::new_readonly(pda::vault_registry(program_id), false),
+        AccountMeta::new(*matcher_account, false),
+        AccountMeta::new_readonly(pda::config(program_id), false),
+        AccountMeta::new(pda::stats(program_id), false),
+        AccountMeta::new(pda::index(program_id, seller), false),
+    ])
+}
+
+// MEETING POINTS
+
+/// `meeting_point` and `salt` must be the ones the `meeting_point_hash` was computed from
+/// with `Location::commitment`.
+pub fn reveal_meeting_point(
+    program_id: &Pubkey,
+    entity_type: EntityType,
+    deal: &Pubkey,
+    lister: &Pubkey,
+    meeting_point: Location,
+    salt: [u8; 32],
+) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::RevealMeetingPoint { entity_type, meeting_point, salt }, vec![
+        AccountMeta::new(*deal, false),
+        AccountMeta::new_readonly(*lister, true),
+    ])
+}
+
+// MESSAGING
+
+/// `payload` must already be encrypted to the counterparty's key. The deal's first message
+/// creates its log, whose rent is paid by `rent_payer`, or else by the sender.
+pub fn send_message(
+    program_id: &Pubkey,
+    entity_type: EntityType,
+    entity: &Pubkey,
+    sender: &Pubkey,
+    payload: Vec<u8>,
+    rent_payer: Option<&Pubkey>,
+) -> Result<Instruction, DLUError> {
+    let mut metas = vec![
+        AccountMeta::new_readonly(*entity, false),
+        AccountMeta::new(pda::message_log(program_id, entity), false),
+        AccountMeta::new_readonly(*sender, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    pay_rent(&mut metas, 2, rent_payer);
+    build(program_id, &DLUInstruction::SendMessage { entity_type, payload }, metas)
+}
+
+// PICKUPS
+
+/// `sender_key` is the key the sender hands the carrier at pickup. With a `session` key
+/// the carrier delegated the shipment to, it signs instead of the carrier.
+pub fn confirm_pickup(
+    program_id: &Pubkey,
+    shipment: &Pubkey,
+    carrier: &Pubkey,
+    session: Option<&Pubkey>,
+    sender_key: String,
+) -> Result<Instruction, DLUError> {
+    let mut metas = vec![AccountMeta::new(*shipment, false)];
+    metas.extend(signer_or_session_metas(program_id, carrier, session));
+    build(program_id, &DLUInstruction::ConfirmPickup { sender_key }, metas)
+}
+
+// DELIVERY PROOFS
+
+/// `proof_hash` is the digest of the delivery photo or signature, which stays off chain.
+pub fn submit_proof(
+    program_id: &Pubkey,
+    shipment: &Pubkey,
+    carrier: &Pubkey,
+    proof_hash: [u8; 32],
+) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::SubmitProof { proof_hash }, vec![
+        AccountMeta::new(*shipment, false),
+        AccountMeta::new_readonly(*carrier, true),
+    ])
+}
+
+// RETURNS
+
+/// `accounts` name the return shipment and the original recipient, who lists and pays for
+/// it. `pickup_region` is the `Location::region` of the original shipment's drop-off point,
+/// where the return is picked up.
+#[allow(clippy::too_many_arguments)]
+pub fn create_return_shipment(
+    program_id: &Pubkey,
+    accounts: &ListAccounts,
+    original: &Pubkey,
+    sender_user_account: &Pubkey,
+    id: u64,
+    payment: u64,
+    pickup_datetime: i64,
+    drop_off_datetime: i64,
+    pickup_region: Option<[u8; REGION_GEOHASH_LEN]>,
+) -> Result<Instruction, DLUError> {
+    let instruction = DLUInstruction::CreateReturnShipment { id, payment, pickup_datetime, drop_off_datetime };
+    let mut metas = list_metas(program_id, accounts);
+    metas.insert(1, AccountMeta::new(*original, false));
+    metas.extend([
+        AccountMeta::new_readonly(*sender_user_account, false),
+        AccountMeta::new_readonly(pda::config(program_id), false),
+        AccountMeta::new(pda::stats(program_id), false),
+    ]);
+    push_region(&mut metas, program_id, pickup_region, 3, accounts.rent_payer.as_ref());
+    build(program_id, &instruction, metas)
+}
+
+// HOLDBACKS
+
+pub fn release_holdback(
+    program_id: &Pubkey,
+    offer: &Pubkey,
+    seller: &Pubkey,
+    seller_account: &Pubkey,
+    escrow_account: &Pubkey,
+) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::ReleaseHoldback, vec![
+        AccountMeta::new(*offer, false),
+        AccountMeta::new_readonly(*seller, true),
+        AccountMeta::new(*seller_account, false),
+        AccountMeta::new(*escrow_account, false),
+        AccountMeta::new_readonly(pda::vault_registry(program_id), false),
+        AccountMeta::new(pda::stats(program_id), false),
+    ])
+}
+
+pub fn dispute_holdback(program_id: &Pubkey, offer: &Pubkey, buyer: &Pubkey) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::DisputeHoldback, vec![
+        AccountMeta::new(*offer, false),
+        AccountMeta::new_readonly(*buyer, true),
+    ])
+}
+
+/// `recipient_account` is the buyer's token account when ruling the goods `defective` and
+/// the seller's otherwise.
+pub fn resolve_holdback_dispute(
+    program_id: &Pubkey,
+    governance: &Pubkey,
+    offer: &Pubkey,
+    escrow_account: &Pubkey,
+    recipient_account: &Pubkey,
+    defective: bool,
+) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::ResolveHoldbackDispute { defective }, vec![
+        AccountMeta::new_readonly(pda::config(program_id), false),
+        AccountMeta::new_readonly(*governance, true),
+        AccountMeta::new(*offer, false),
+        AccountMeta::new(*escrow_account, false),
+        AccountMeta::new_readonly(pda::vault_registry(program_id), false),
+        AccountMeta::new(*recipient_account, false),
+        AccountMeta::new(pda::stats(program_id), false),
+    ])
+}
+
+// CHARGEBACKS
+
+pub fn set_chargeback_window(program_id: &Pubkey, governance: &Pubkey, window: i64) -> Result<Instruction, DLUError> {
+    governance_instruction(program_id, governance, &DLUInstruction::SetChargebackWindow { window })
+}
+
+pub fn open_chargeback(program_id: &Pubkey, offer: &Pubkey, buyer: &Pubkey) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::OpenChargeback, vec![
+        AccountMeta::new(*offer, false),
+        AccountMeta::new_readonly(*buyer, true),
+    ])
+}
+
+pub fn release_seller_insurance(
+    program_id: &Pubkey,
+    offer: &Pubkey,
+    seller: &Pubkey,
+    seller_account: &Pubkey,
+    escrow_account: &Pubkey,
+) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::ReleaseSellerInsurance, vec![
+        AccountMeta::new(*offer, false),
+        AccountMeta::new_readonly(*seller, true),
+        AccountMeta::new(*seller_account, false),
+        AccountMeta::new(*escrow_account, false),
+        AccountMeta::new_readonly(pda::vault_registry(program_id), false),
+        AccountMeta::new(pda::stats(program_id), false),
+    ])
+}
+
+/// `recipient_account` is the buyer's token account when the claim is `upheld` and the
+/// seller's otherwise.
+pub fn resolve_chargeback(
+    program_id: &Pubkey,
+    governance: &Pubkey,
+    offer: &Pubkey,
+    escrow_account: &Pubkey,
+    recipient_account: &Pubkey,
+    upheld: bool,
+) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::ResolveChargeback { upheld }, vec![
+        AccountMeta::new_readonly(pda::config(program_id), false),
+        AccountMeta::new_readonly(*governance, true),
+        AccountMeta::new(*offer, false),
+        AccountMeta::new(*escrow_account, false),
+        AccountMeta::new_readonly(pda::vault_registry(program_id), false),
+        AccountMeta::new(*recipient_account, false),
+        AccountMeta::new(pda::stats(program_id), false),
+    ])
+}
+
+// ARBITRATION
+
+pub fn set_arbitration_terms(
+    program_id: &Pubkey,
+    governance: &Pubkey,
+    min_stake: u64,
+    fee: u64,
+) -> Result<Instruction, DLUError> {
+    governance_instruction(program_id, governance, &DLUInstruction::SetArbitrationTerms { min_stake, fee })
+}
+
+/// A first registration creates the arbiter account, whose rent is paid by `rent_payer`, or
+/// else by the owner.
+pub fn register_arbiter(
+    program_id: &Pubkey,
+    user_account: &Pubkey,
+    owner: &Pubkey,
+    owner_account: &Pubkey,
+    escrow_account: &Pubkey,
+    amount: u64,
+    rent_payer: Option<&Pubkey>,
+) -> Result<Instruction, DLUError> {
+    let mut metas = vec![
+        AccountMeta::new(pda::arbiter(program_id, owner), false),
+        AccountMeta::new(pda::arbiter_registry(program_id), false),
+        AccountMeta::new_readonly(*user_account, false),
+        AccountMeta::new_readonly(*owner, true),
+        AccountMeta::new(*owner_account, false),
+        AccountMeta::new(*escrow_account, false),
+        AccountMeta::new_readonly(pda::vault_registry(program_id), false),
+        AccountMeta::new_readonly(pda::config(program_id), false),
+        AccountMeta::new(pda::stats(program_id), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    pay_rent(&mut metas, 3, rent_payer);
+    build(program_id, &DLUInstruction::RegisterArbiter { amount }, metas)
+}
+
+pub fn deregister_arbiter(program_id: &Pubkey, owner: &Pubkey) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::DeregisterArbiter, vec![
+        AccountMeta::new(pda::arbiter(program_id, owner), false),
+        AccountMeta::new(pda::arbiter_registry(program_id), false),
+        AccountMeta::new_readonly(*owner, true),
+    ])
+}
+
+pub fn withdraw_arbiter_stake(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    owner_account: &Pubkey,
+    escrow_account: &Pubkey,
+) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::WithdrawArbiterStake, vec![
+        AccountMeta::new(pda::arbiter(program_id, owner), false),
+        AccountMeta::new_readonly(*owner, true),
+        AccountMeta::new(*owner_account, false),
+        AccountMeta::new(*escrow_account, false),
+        AccountMeta::new_readonly(pda::vault_registry(program_id), false),
+        AccountMeta::new(pda::stats(program_id), false),
+    ])
+}
+
+/// `rent_payer` funds the arbitration account on the first draw.
+pub fn assign_arbiter(
+    program_id: &Pubkey,
+    offer: &Pubkey,
+    kind: DisputeKind,
+    rent_payer: &Pubkey,
+) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::AssignArbiter { kind }, vec![
+        AccountMeta::new_readonly(*offer, false),
+        AccountMeta::new(pda::arbitration(program_id, offer, kind), false),
+        AccountMeta::new_readonly(pda::arbiter_registry(program_id), false),
+        AccountMeta::new_readonly(sysvar::slot_hashes::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new(*rent_payer, true),
+    ])
+}
+
+/// The fee is paid from the treasury of `payment_mint`.
+pub fn rule_dispute(
+    program_id: &Pubkey,
+    arbiter: &Pubkey,
+    offer: &Pubkey,
+    arbiter_account: &Pubkey,
+    payment_mint: &Pubkey,
+    kind: DisputeKind,
+    for_buyer: bool,
+) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::RuleDispute { kind, for_buyer }, vec![
+        AccountMeta::new_readonly(pda::config(program_id), false),
+        AccountMeta::new_readonly(*arbiter, true),
+        AccountMeta::new(pda::arbiter(program_id, arbiter), false),
+        AccountMeta::new_readonly(pda::arbiter_registry(program_id), false),
+        AccountMeta::new_readonly(*offer, false),
+        AccountMeta::new(pda::arbitration(program_id, offer, kind), false),
+        AccountMeta::new(*arbiter_account, false),
+        AccountMeta::new(pda::derive_treasury_address(program_id, payment_mint).0, false),
+    ])
+}
+
+// APPEALS
+
+pub fn set_appeal_bond(program_id: &Pubkey, governance: &Pubkey, bond: u64) -> Result<Instruction, DLUError> {
+    governance_instruction(program_id, governance, &DLUInstruction::SetAppealBond { bond })
+}
+
+/// The appeal's account is created with it, its rent paid by `rent_payer`, or else by the
+/// appellant.
+pub fn appeal_ruling(
+    program_id: &Pubkey,
+    offer: &Pubkey,
+    appellant: &Pubkey,
+    appellant_account: &Pubkey,
+    escrow_account: &Pubkey,
+    kind: DisputeKind,
+    rent_payer: Option<&Pubkey>,
+) -> Result<Instruction, DLUError> {
+    let mut metas = vec![
+        AccountMeta::new_readonly(*offer, false),
+        AccountMeta::new(pda::arbitration(program_id, offer, kind), false),
+        AccountMeta::new(pda::appeal(program_id, offer, kind), false),
+        AccountMeta::new_readonly(pda::arbiter_registry(program_id), false),
+        AccountMeta::new_readonly(sysvar::slot_hashes::id(), false),
+        AccountMeta::new_readonly(*appellant, true),
+        AccountMeta::new(*appellant_account, false),
+        AccountMeta::new(*escrow_account, false),
+        AccountMeta::new_readonly(pda::vault_registry(program_id), false),
+        AccountMeta::new_readonly(pda::config(program_id), false),
+        AccountMeta::new(pda::stats(program_id), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    pay_rent(&mut metas, 5, rent_payer);
+    build(program_id, &DLUInstruction::AppealRuling { kind }, metas)
+}
+
+pub fn vote_appeal(
+    program_id: &Pubkey,
+    offer: &Pubkey,
+    panelist: &Pubkey,
+    kind: DisputeKind,
+    for_buyer: bool,
+) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::VoteAppeal { kind, for_buyer }, vec![
+        AccountMeta::new_readonly(*offer, false),
+        AccountMeta::new_readonly(pda::arbitration(program_id, offer, kind), false),
+        AccountMeta::new(pda::appeal(program_id, offer, kind), false),
+        AccountMeta::new_readonly(*panelist, true),
+    ])
+}
+
+/// `recipient_account` is the winning party's token account. `appeal` is required once
+/// the ruling was appealed.
+pub fn settle_dispute(
+    program_id: &Pubkey,
+    offer: &Pubkey,
+    escrow_account: &Pubkey,
+    recipient_account: &Pubkey,
+    kind: DisputeKind,
+    appeal: Option<&AppealPayoutAccounts>,
+) -> Result<Instruction, DLUError> {
+    let mut metas = vec![
+        AccountMeta::new(*offer, false),
+        AccountMeta::new_readonly(pda::arbitration(program_id, offer, kind), false),
+        AccountMeta::new(*escrow_account, false),
+        AccountMeta::new_readonly(pda::vault_registry(program_id), false),
+        AccountMeta::new(*recipient_account, false),
+        AccountMeta::new(pda::stats(program_id), false),
+    ];
+    if let Some(appeal) = appeal {
+        metas.push(AccountMeta::new(pda::appeal(program_id, offer, kind), false));
+        metas.push(AccountMeta::new(appeal.appellant_account, false));
+        metas.extend(appeal.panelist_accounts.iter().map(|account| AccountMeta::new(*account, false)));
+    }
+    build(program_id, &DLUInstruction::SettleDispute { kind }, metas)
+}
+
+/// `penalty_account` is the stake mint when penalties are burned.
+pub fn slash_arbiter(
+    program_id: &Pubkey,
+    offer: &Pubkey,
+    arbiter: &Pubkey,
+    escrow_account: &Pubkey,
+    penalty_account: &Pubkey,
+    kind: DisputeKind,
+) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::SlashArbiter { kind }, vec![
+        AccountMeta::new_readonly(*offer, false),
+        AccountMeta::new_readonly(pda::arbitration(program_id, offer, kind), false),
+        AccountMeta::new(pda::appeal(program_id, offer, kind), false),
+        AccountMeta::new(pda::arbiter(program_id, arbiter), false),
+        AccountMeta::new(*escrow_account, false),
+        AccountMeta::new_readonly(pda::vault_registry(program_id), false),
+        AccountMeta::new(*penalty_account, false),
+        AccountMeta::new_readonly(pda::config(program_id), false),
+        AccountMeta::new(pda::stats(program_id), false),
+    ])
+}
+
+// SETTLEMENTS
+
+/// Who signs a settlement: both parties of the offer, or a registered arbiter.
+pub enum SettlementSigners {
+    Mutual { seller: Pubkey, buyer: Pubkey },
+    Arbiter(Pubkey),
+}
+
+/// `nft_recipient_account` receives the NFT of an NFT offer: the buyer's when the seller
+/// gets any share, the seller's otherwise.
+#[allow(clippy::too_many_arguments)]
+pub fn settle_deal(
+    program_id: &Pubkey,
+    offer: &Pubkey,
+    escrow_account: &Pubkey,
+    seller_account: &Pubkey,
+    buyer_account: &Pubkey,
+    signers: &SettlementSigners,
+    seller_bps: u16,
+    buyer_bps: u16,
+    nonce: u64,
+    nft_recipient_account: Option<&Pubkey>,
+) -> Result<Instruction, DLUError> {
+    let mut metas = vec![
+        AccountMeta::new(*offer, false),
+        AccountMeta::new(*escrow_account, false),
+        AccountMeta::new_readonly(pda::vault_registry(program_id), false),
+        AccountMeta::new(*seller_account, false),
+        AccountMeta::new(*buyer_account, false),
+        AccountMeta::new(pda::stats(program_id), false),
+    ];
+    match signers {
+        SettlementSigners::Mutual { seller, buyer } => {
+            metas.push(AccountMeta::new_readonly(*seller, true));
+            metas.push(AccountMeta::new_readonly(*buyer, true));
+        }
+        SettlementSigners::Arbiter(arbiter) => {
+            metas.push(AccountMeta::new_readonly(*arbiter, true));
+            metas.push(AccountMeta::new_readonly(pda::arbiter_registry(program_id), false));
+        }
+    }
+    metas.extend(nft_release_metas(program_id, offer, nft_recipient_account));
+    build(program_id, &DLUInstruction::SettleDeal { seller_bps, buyer_bps, nonce }, metas)
+}
+
+// SESSION KEYS
+
+/// `entities` are the deals and shipments the session key may act on. The first
+/// authorization of a session key creates its delegation, whose rent is paid by
+/// `rent_payer`, or else by the owner.
+pub fn create_session(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    session: &Pubkey,
+    entities: Vec<Pubkey>,
+    expires_at: i64,
+    rent_payer: Option<&Pubkey>,
+) -> Result<Instruction, DLUError> {
+    let mut metas = vec![
+        AccountMeta::new_readonly(*owner, true),
+        AccountMeta::new(pda::delegation(program_id, owner, session), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    pay_rent(&mut metas, 0, rent_payer);
+    build(program_id, &DLUInstruction::CreateSession { session: *session, entities, expires_at }, metas)
+}
+
+pub fn revoke_session(program_id: &Pubkey, owner: &Pubkey, session: &Pubkey) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::RevokeSession { session: *session }, vec![
+        AccountMeta::new_readonly(*owner, true),
+        AccountMeta::new(pda::delegation(program_id, owner, session), false),
+    ])
+}
+
+// ORGANIZATIONS
+
+/// The organization's rent is paid by `rent_payer`, or else by the owner.
+pub fn create_organization(program_id: &Pubkey, owner: &Pubkey, rent_payer: Option<&Pubkey>) -> Result<Instruction, DLUError> {
+    let mut metas = vec![
+        AccountMeta::new_readonly(*owner, true),
+        AccountMeta::new(pda::organization(program_id, owner), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    pay_rent(&mut metas, 0, rent_payer);
+    build(program_id, &DLUInstruction::CreateOrganization, metas)
+}
+
+/// `roles` combines the `MEMBER_*` flags; none removes the member.
+pub fn set_member(program_id: &Pubkey, owner: &Pubkey, member: &Pubkey, roles: u8) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::SetMember { member: *member, roles }, vec![
+        AccountMeta::new_readonly(*owner, true),
+        AccountMeta::new(pda::organization(program_id, owner), false),
+    ])
+}
+
+/// Has a `member` of `party`'s organization sign an instruction built for `party`, such as
+/// listing, accepting, failing, or confirming a pickup, and passes the organization last.
+/// Token accounts and their authorities stay as built, so build with the member's own.
+pub fn as_member(program_id: &Pubkey, mut instruction: Instruction, party: &Pubkey, member: &Pubkey) -> Instruction {
+    if let Some(signer) = instruction.accounts.iter_mut().find(|meta| meta.pubkey == *party && meta.is_signer) {
+        signer.pubkey = *member;
+    }
+    instruction.accounts.push(AccountMeta::new_readonly(pda::organization(program_id, party), false));
+    instruction
+}
+
+// MULTISIGS
+
+/// The multisig's rent is paid by `rent_payer`, or else by the owner.
+pub fn create_multisig(
+    program_id: &Pubkey,
+    user_account: &Pubkey,
+    owner: &Pubkey,
+    threshold: u8,
+    signers: Vec<Pubkey>,
+    high_value: u64,
+    rent_payer: Option<&Pubkey>,
+) -> Result<Instruction, DLUError> {
+    let mut metas = vec![
+        AccountMeta::new(*user_account, false),
+        AccountMeta::new_readonly(*owner, true),
+        AccountMeta::new(pda::multisig(program_id, owner), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    pay_rent(&mut metas, 1, rent_payer);
+    build(program_id, &DLUInstruction::CreateMultisig { threshold, signers, high_value }, metas)
+}
+
+/// `approvers` are current signers of the multisig reaching its threshold.
+pub fn update_multisig(
+    program_id: &Pubkey,
+    user_account: &Pubkey,
+    owner: &Pubkey,
+    threshold: u8,
+    signers: Vec<Pubkey>,
+    high_value: u64,
+    approvers: &[Pubkey],
+) -> Result<Instruction, DLUError> {
+    let mut metas = vec![
+        AccountMeta::new_readonly(*user_account, false),
+        AccountMeta::new(pda::multisig(program_id, owner), false),
+    ];
+    metas.extend(approvers.iter().map(|approver| AccountMeta::new_readonly(*approver, true)));
+    build(program_id, &DLUInstruction::UpdateMultisig { threshold, signers, high_value }, metas)
+}
+
+/// Passes the multisig governing `party` and its `cosigners` last on a listing built for
+/// `party`, after any organization. No cosigners are needed below its high value.
+pub fn with_multisig(program_id: &Pubkey, mut instruction: Instruction, party: &Pubkey, cosigners: &[Pubkey]) -> Instruction {
+    instruction.accounts.extend(multisig_metas(program_id, party, cosigners));
+    instruction
+}
+
+fn multisig_metas(program_id: &Pubkey, owner: &Pubkey, cosigners: &[Pubkey]) -> Vec<AccountMeta> {
+    let mut metas = vec![AccountMeta::new_readonly(pda::multisig(program_id, owner), false)];
+    metas.extend(cosigners.iter().map(|cosigner| AccountMeta::new_readonly(*cosigner, true)));
+    metas
+}
+
+// MODERATION
+
+pub fn set_user_banned(
+    program_id: &Pubkey,
+    governance: &Pubkey,
+    user_account: &Pubkey,
+    banned: bool,
+) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::SetUserBanned { banned }, vec![
+        AccountMeta::new_readonly(pda::config(program_id), false),
+        AccountMeta::new_readonly(*governance, true),
+        AccountMeta::new(*user_account, false),
+    ])
+}
+
+// KYC
+
+pub fn set_kyc_threshold(program_id: &Pubkey, governance: &Pubkey, threshold: u64) -> Result<Instruction, DLUError> {
+    governance_instruction(program_id, governance, &DLUInstruction::SetKycThreshold { threshold })
+}
+
+/// Approves `attestor`, or withdraws its approval when not `approved`. Governance pays the
+/// rent of the AttestorRegistry the first approval creates.
+pub fn set_attestor(
+    program_id: &Pubkey,
+    governance: &Pubkey,
+    attestor: &Pubkey,
+    approved: bool,
+) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::SetAttestor { attestor: *attestor, approved }, vec![
+        AccountMeta::new_readonly(pda::config(program_id), false),
+        AccountMeta::new(*governance, true),
+        AccountMeta::new(pda::attestor_registry(program_id), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ])
+}
+
+/// An `expires_at` that has passed revokes the attestation. The user's first attestation
+/// creates its account, whose rent is paid by `rent_payer`, or else by the attestor.
+pub fn attest(
+    program_id: &Pubkey,
+    attestor: &Pubkey,
+    user_account: &Pubkey,
+    owner: &Pubkey,
+    expires_at: i64,
+    rent_payer: Option<&Pubkey>,
+) -> Result<Instruction, DLUError> {
+    let mut metas = vec![
+        AccountMeta::new_readonly(pda::attestor_registry(program_id), false),
+        AccountMeta::new_readonly(*attestor, true),
+        AccountMeta::new_readonly(*user_account, false),
+        AccountMeta::new(pda::attestation(program_id, owner), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    pay_rent(&mut metas, 1, rent_payer);
+    build(program_id, &DLUInstruction::Attest { expires_at }, metas)
+}
+
+/// Passes the attestor registry and the attestations of `parties`, in the order the
+/// instruction documents, last on an acceptance paying more than the KYC threshold.
+pub fn with_attestations(program_id: &Pubkey, mut instruction: Instruction, parties: &[Pubkey]) -> Instruction {
+    instruction.accounts.push(AccountMeta::new_readonly(pda::attestor_registry(program_id), false));
+    instruction.accounts.extend(
+        parties.iter().map(|party| AccountMeta::new_readonly(pda::attestation(program_id, party), false)),
+    );
+    instruction
+}
+
+// REFERRALS
+
+pub fn set_referral_share(program_id: &Pubkey, governance: &Pubkey, share_bps: u16) -> Result<Instruction, DLUError> {
+    governance_instruction(program_id, governance, &DLUInstruction::SetReferralShare { share_bps })
+}
+
+pub fn claim_referral_rewards(
+    program_id: &Pubkey,
+    referrer: &Pubkey,
+    referrer_account: &Pubkey,
+    mint: &Pubkey,
+) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::ClaimReferralRewards, vec![
+        AccountMeta::new(pda::referral(program_id, referrer, mint), false),
+        AccountMeta::new_readonly(*referrer, true),
+        AccountMeta::new(pda::derive_treasury_address(program_id, mint).0, false),
+        AccountMeta::new(*referrer_account, false),
+    ])
+}
+
+/// Passes the rewards account of `referrer` in `mint` last on a completion whose payee
+/// they referred, when the completion collects a fee. `rent_payer` signs and funds the
+/// account on the first credit.
+pub fn with_referral_rewards(
+    program_id: &Pubkey,
+    mut instruction: Instruction,
+    referrer: &Pubkey,
+    mint: &Pubkey,
+    rent_payer: &Pubkey,
+) -> Instruction {
+    instruction.accounts.extend([
+        AccountMeta::new(pda::referral(program_id, referrer, mint), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new(*rent_payer, true),
+    ]);
+    instruction
+}
+
+// PROMO CODES
+
+/// `code_hash` is `promo_code_hash` of the offer and the code; zero `uses` withdraws it.
+/// The offer's first code creates its account, whose rent is paid by `rent_payer`, or else
+/// by the seller.
+pub fn set_promo_code(
+    program_id: &Pubkey,
+    offer: &Pubkey,
+    seller: &Pubkey,
+    code_hash: [u8; 32],
+    discount_percent: u8,
+    uses: u32,
+    rent_payer: Option<&Pubkey>,
+) -> Result<Instruction, DLUError> {
+    let mut metas = vec![
+        AccountMeta::new_readonly(*offer, false),
+        AccountMeta::new_readonly(*seller, true),
+        AccountMeta::new(pda::promo(program_id, offer), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    pay_rent(&mut metas, 1, rent_payer);
+    build(program_id, &DLUInstruction::SetPromoCode { code_hash, discount_percent, uses }, metas)
+}
+
+/// Presents the promo `code` on an `accept_offer` or `deposit_and_accept` of `offer`,
+/// passing the offer's promo codes last. Leaves any other instruction alone.
+pub fn with_promo_code(
+    program_id: &Pubkey,
+    instruction: Instruction,
+    offer: &Pubkey,
+    code: String,
+) -> Result<Instruction, DLUError> {
+    let accepted = match DLUInstruction::unpack(&instruction.data) {
+        Ok(DLUInstruction::AcceptOffer { quantity, .. }) => DLUInstruction::AcceptOffer { quantity, promo_code: Some(code) },
+        Ok(DLUInstruction::DepositAndAccept { amount, quantity, .. }) => {
+            DLUInstruction::DepositAndAccept { amount, quantity, promo_code: Some(code) }
+        }
+        _ => return Ok(instruction),
+    };
+    let mut accounts = instruction.accounts;
+    accounts.push(AccountMeta::new(pda::promo(program_id, offer), false));
+    build(program_id, &accepted, accounts)
+}
+
+// SCHEDULED LISTINGS
+
+/// Relisting clears the schedule.
+pub fn schedule_listing(
+    program_id: &Pubkey,
+    entity_type: EntityType,
+    entity: &Pubkey,
+    lister: &Pubkey,
+    activate_at: i64,
+    delist_at: Option<i64>,
+) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::ScheduleListing { entity_type, activate_at, delist_at }, vec![
+        AccountMeta::new(*entity, false),
+        AccountMeta::new_readonly(*lister, true),
+    ])
+}
+
+// STREAMING PAYMENTS
+
+/// Pass the offer's `payment_mint` to pick the treasury the protocol fee goes to.
+pub fn withdraw_stream(
+    program_id: &Pubkey,
+    offer: &Pubkey,
+    seller_user_account: &Pubkey,
+    seller: &Pubkey,
+    seller_account: &Pubkey,
+    escrow_account: &Pubkey,
+    payment_mint: &Pubkey,
+) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::WithdrawStream, vec![
+        AccountMeta::new(*offer, false),
+        AccountMeta::new_readonly(*seller_user_account, false),
+        AccountMeta::new_readonly(*seller, true),
+        AccountMeta::new(*seller_account, false),
+        AccountMeta::new(*escrow_account, false),
+        AccountMeta::new_readonly(pda::vault_registry(program_id), false),
+        AccountMeta::new_readonly(pda::config(program_id), false),
+        AccountMeta::new(pda::derive_treasury_address(program_id, payment_mint).0, false),
+        AccountMeta::new(pda::stats(program_id), false),
+    ])
+}
+
+/// `party` is the offer's seller or buyer.
+pub fn stop_stream(program_id: &Pubkey, offer: &Pubkey, party: &Pubkey) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::StopStream, vec![
+        AccountMeta::new(*offer, false),
+        AccountMeta::new_readonly(*party, true),
+    ])
+}
+
+// WATCHLISTS
+
+/// Follows `user` for `follower`, or unfollows them. The first follow or watch creates the
+/// follower's watchlist, whose rent is paid by `rent_payer`, or else by the follower.
+pub fn follow_user(
+    program_id: &Pubkey,
+    follower: &Pubkey,
+    user: &Pubkey,
+    follow: bool,
+    rent_payer: Option<&Pubkey>,
+) -> Result<Instruction, DLUError> {
+    let mut metas = vec![
+        AccountMeta::new_readonly(*follower, true),
+        AccountMeta::new(pda::watchlist(program_id, follower), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    pay_rent(&mut metas, 0, rent_payer);
+    build(program_id, &DLUInstruction::FollowUser { user: *user, follow }, metas)
+}
+
+/// Watches `offer` for `watcher`, or stops watching it, creating the watcher's watchlist
+/// like `follow_user`.
+pub fn watch_offer(
+    program_id: &Pubkey,
+    watcher: &Pubkey,
+    offer: &Pubkey,
+    watch: bool,
+    rent_payer: Option<&Pubkey>,
+) -> Result<Instruction, DLUError> {
+    let mut metas = vec![
+        AccountMeta::new_readonly(*watcher, true),
+        AccountMeta::new(pda::watchlist(program_id, watcher), false),
+        AccountMeta::new_readonly(*offer, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    pay_rent(&mut metas, 0, rent_payer);
+    build(program_id, &DLUInstruction::WatchOffer { watch }, metas)
+}
+
+// LEADERBOARDS
+
+/// Passes the leaderboards of the given kinds last on a completion, or a shipment fail, to
+/// rank its seller or carrier: `SELLER_LEADERBOARDS` for offers and requests,
+/// `CARRIER_LEADERBOARDS` for shipments. `rent_payer` signs and funds each leaderboard on
+/// its first ranking.
+pub fn with_leaderboards(
+    program_id: &Pubkey,
+    mut instruction: Instruction,
+    kinds: &[LeaderboardKind],
+    rent_payer: &Pubkey,
+) -> Instruction {
+    instruction.accounts.extend(kinds.iter().map(|kind| AccountMeta::new(pda::leaderboard(program_id, *kind), false)));
+    instruction.accounts.extend([
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new(*rent_payer, true),
+    ]);
+    instruction
+}
+
+// COMPRESSED LISTINGS
+
+/// `tree` must already be allocated for the depth and buffer size, owned by the account
+/// compression program.
+pub fn create_listing_tree(
+    program_id: &Pubkey,
+    tree: &Pubkey,
+    max_depth: u32,
+    max_buffer_size: u32,
+) -> Result<Instruction, DLUError> {
+    let mut metas = tree_metas(program_id, tree);
+    metas.extend(compression_metas());
+    build(program_id, &DLUInstruction::CreateListingTree { max_depth, max_buffer_size }, metas)
+}
+
+/// `accounts.entity` is the listing tree.
+pub fn list_compressed_offer(
+    program_id: &Pubkey,
+    accounts: &ListAccounts,
+    id: u64,
+    goodsorservice_name: String,
+    goodsorservice_description: String,
+    payment: u64,
+    meeting_point: Location,
+    meeting_datetime: i64,
+) -> Result<Instruction, DLUError> {
+    let mut metas = tree_metas(program_id, &accounts.entity);
+    metas.extend([
+        AccountMeta::new_readonly(accounts.lister_user_account, false),
+        AccountMeta::new_readonly(accounts.lister, true),
+        AccountMeta::new(accounts.lister_account, false),
+        AccountMeta::new(accounts.escrow_account, false),
+        AccountMeta::new_readonly(pda::vault_registry(program_id), false),
+        AccountMeta::new_readonly(pda::config(program_id), false),
+        AccountMeta::new(pda::stats(program_id), false),
+    ]);
+    metas.extend(compression_metas());
+    let instruction = DLUInstruction::ListCompressedOffer {
+        id, goodsorservice_name, goodsorservice_description, payment, meeting_point, meeting_datetime,
+    };
+    build(program_id, &instruction, metas)
+}
+
+/// `accounts.entity` is the empty offer account the listing materializes into. The
+/// `listing` comes from its `CompressedOfferListed` event, and `root`, `index`, and
+/// `proof` (from the leaf up) from an indexer of the tree.
+pub fn accept_compressed_offer(
+    program_id: &Pubkey,
+    tree: &Pubkey,
+    accounts: &AcceptAccounts,
+    seller_user_account: &Pubkey,
+    listing: CompressedOffer,
+    root: [u8; 32],
+    index: u32,
+    proof: &[[u8; 32]],
+) -> Result<Instruction, DLUError> {
+    let mut metas = tree_metas(program_id, tree);
+    metas.extend([
+        AccountMeta::new(accounts.entity, false),
+        AccountMeta::new_readonly(*seller_user_account, false),
+        AccountMeta::new(accounts.acceptor_user_account, false),
+        AccountMeta::new_readonly(accounts.acceptor, true),
+        AccountMeta::new(accounts.acceptor_account, false),
+        AccountMeta::new(accounts.escrow_account, false),
+        AccountMeta::new_readonly(pda::vault_registry(program_id), false),
+        AccountMeta::new_readonly(accounts.acceptor_authority, true),
+        AccountMeta::new_readonly(pda::config(program_id), false),
+        AccountMeta::new(pda::stats(program_id), false),
+        AccountMeta::new(pda::index(program_id, &listing.seller), false),
+    ]);
+    metas.extend(compression_metas());
+    metas.extend(proof_metas(proof));
+    build(program_id, &DLUInstruction::AcceptCompressedOffer { listing, root, index }, metas)
+}
+
+/// `accounts.entity` is the listing tree; see `accept_compressed_offer` for the rest.
+pub fn cancel_compressed_offer(
+    program_id: &Pubkey,
+    accounts: &ListerEscrowAccounts,
+    listing: CompressedOffer,
+    root: [u8; 32],
+    index: u32,
+    proof: &[[u8; 32]],
+) -> Result<Instruction, DLUError> {
+    let mut metas = tree_metas(program_id, &accounts.entity);
+    metas.extend([
+        AccountMeta::new_readonly(accounts.lister_user_account, false),
+        AccountMeta::new_readonly(accounts.lister, true),
+        AccountMeta::new(accounts.lister_account, false),
+        AccountMeta::new(accounts.escrow_account, false),
+        AccountMeta::new_readonly(pda::vault_registry(program_id), false),
+        AccountMeta::new(pda::stats(program_id), false),
+    ]);
+    metas.extend(compression_metas());
+    metas.extend(proof_metas(proof));
+    build(program_id, &DLUInstruction::CancelCompressedOffer { listing, root, index }, metas)
+}
+
+fn tree_metas(program_id: &Pubkey, tree: &Pubkey) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new(*tree, false),
+        AccountMeta::new_readonly(pda::listing_tree_authority(program_id, tree), false),
+    ]
+}
+
+fn compression_metas() -> [AccountMeta; 2] {
+    [
+        AccountMeta::new_readonly(ACCOUNT_COMPRESSION_PROGRAM_ID, false),
+        AccountMeta::new_readonly(NOOP_PROGRAM_ID, false),
+    ]
+}
+
+fn proof_metas(proof: &[[u8; 32]]) -> impl Iterator<Item = AccountMeta> + '_ {
+    proof.iter().map(|node| AccountMeta::new_readonly(Pubkey::new_from_array(*node), false))
+}
+
+// ARCHIVES
+
+/// Keep the `entities`' account data from before the archive: proving one later takes
+/// the hash of its data and the batch's leaves, built with `luda::archive`.
+/// The history's growth is paid by `rent_payer`, or else by the lister.
+pub fn archive_batch(
+    program_id: &Pubkey,
+    lister: &Pubkey,
+    entity_type: EntityType,
+    entities: &[Pubkey],
+    rent_payer: Option<&Pubkey>,
+) -> Result<Instruction, DLUError> {
+    let mut metas = vec![
+        AccountMeta::new(*lister, true),
+        AccountMeta::new(pda::history(program_id, lister), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    metas.extend(rent_payer.map(|payer| AccountMeta::new(*payer, true)));
+    metas.extend(entities.iter().map(|entity| AccountMeta::new(*entity, false)));
+    build(program_id, &DLUInstruction::ArchiveBatch { entity_type }, metas)
+}
+
+/// `data` is the entity's account data as it was archived, and `proof` its
+/// `luda::archive::merkle_proof` in the batch.
+pub fn verify_archived_entity(
+    program_id: &Pubkey,
+    lister: &Pubkey,
+    batch: u32,
+    entity: &Pubkey,
+    data: &[u8],
+    index: u32,
+    proof: Vec<[u8; 32]>,
+) -> Result<Instruction, DLUError> {
+    let instruction =
+        DLUInstruction::VerifyArchivedEntity { lister: *lister, batch, entity: *entity, data_hash: archive::data_hash(data), index, proof };
+    build(program_id, &instruction, vec![AccountMeta::new_readonly(pda::history(program_id, lister), false)])
+}
+
+// ACCOUNT GROWTH
+
+/// Transfers what an account holding `lamports` lacks of the rent of `new_len` bytes,
+/// ahead of an instruction whose data outgrows the account, or None if it has enough.
+/// The program grows offers, requests, shipments, users, and indexes as needed, but only
+/// within the rent they already hold.
+pub fn fund_growth(payer: &Pubkey, account: &Pubkey, lamports: u64, new_len: usize) -> Option<Instruction> {
+    let shortfall = Rent::default().minimum_balance(new_len).saturating_sub(lamports);
+    (shortfall > 0).then(|| system_instruction::transfer(payer, account, shortfall))
+}
+
+// ONE-TIME KEYS
+
+/// Commits the hash of the `party`'s one-time `key`, derived with `nonce`; only the hash
+/// leaves the client, so keep the key to hand over at the meeting, pickup, or delivery.
+/// The entity's first commit creates its KeyManager, whose rent is paid by `rent_payer`, or
+/// else by the party.
+pub fn commit_key(
+    program_id: &Pubkey,
+    entity_type: EntityType,
+    entity: &Pubkey,
+    party: &Pubkey,
+    key: &str,
+    nonce: u64,
+    rent_payer: Option<&Pubkey>,
+) -> Result<Instruction, DLUError> {
+    let instruction = DLUInstruction::CommitKey { entity_type, key_hash: onetimekeys::hash_key(key), nonce };
+    let mut accounts = vec![
+        AccountMeta::new(*entity, false),
+        AccountMeta::new_readonly(*party, true),
+        AccountMeta::new(pda::key_manager(program_id, entity), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    pay_rent(&mut accounts, 1, rent_payer);
+    build(program_id, &instruction, accounts)
+}
+
+/// Replaces both one-time keys of an accepted offer or request with new ones, derived with
+/// `nonce`; the seller and the buyer both sign, and only the hashes leave the client. A new
+/// KeyManager's rent is paid by `rent_payer`, or else by the seller.
+#[allow(clippy::too_many_arguments)]
+pub fn regenerate_keys(
+    program_id: &Pubkey,
+    entity_type: EntityType,
+    deal: &Pubkey,
+    seller: &Pubkey,
+    buyer: &Pubkey,
+    seller_key: &str,
+    buyer_key: &str,
+    nonce: u64,
+    rent_payer: Option<&Pubkey>,
+) -> Result<Instruction, DLUError> {
+    let instruction = DLUInstruction::RegenerateKeys {
+        entity_type,
+        seller_key_hash: onetimekeys::hash_key(seller_key),
+        buyer_key_hash: onetimekeys::hash_key(buyer_key),
+        nonce,
+    };
+    let mut accounts = vec![
+        AccountMeta::new(*deal, false),
+        AccountMeta::new_readonly(*seller, true),
+        AccountMeta::new_readonly(*buyer, true),
+        AccountMeta::new(pda::key_manager(program_id, deal), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    pay_rent(&mut accounts, 1, rent_payer);
+    build(program_id, &instruction, accounts)
+}
+
+// STALE LISTINGS
+
+/// Refunds the lister of a listing no one accepted in time into `lister_account`. Needs no
+/// signer; `seller_nft_account` gets back the NFT of an NFT offer.
+pub fn reclaim_stale_listing(
+    program_id: &Pubkey,
+    entity_type: EntityType,
+    entity: &Pubkey,
+    payment_mint: &Pubkey,
+    lister_account: &Pubkey,
+    seller_nft_account: Option<&Pubkey>,
+) -> Result<Instruction, DLUError> {
+    let mut accounts = vec![
+        AccountMeta::new(*entity, false),
+        AccountMeta::new(pda::derive_vault_address(program_id, payment_mint).0, false),
+        AccountMeta::new_readonly(pda::vault_registry(program_id), false),
+        AccountMeta::new(*lister_account, false),
+        AccountMeta::new(pda::stats(program_id), false),
+    ];
+    accounts.extend(nft_release_metas(program_id, entity, seller_nft_account));
+    build(program_id, &DLUInstruction::ReclaimStaleListing { entity_type }, accounts)
+}
+
+// RESCHEDULING
+
+/// Moves the meeting or drop-off time of an accepted entity to `deadline`, signed by the
+/// seller or sender and the buyer or carrier.
+pub fn extend_deadline(
+    program_id: &Pubkey,
+    entity_type: EntityType,
+    entity: &Pubkey,
+    seller_or_sender: &Pubkey,
+    buyer_or_carrier: &Pubkey,
+    deadline: i64,
+) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::ExtendDeadline { entity_type, deadline }, vec![
+        AccountMeta::new(*entity, false),
+        AccountMeta::new_readonly(*seller_or_sender, true),
+        AccountMeta::new_readonly(*buyer_or_carrier, true),
+    ])
+}
+
+// RECONCILIATION
+
+/// Checks the vault of `payment_mint` covers what the stats record escrowed in it, flagging
+/// the entity if not.
+pub fn verify_escrow(
+    program_id: &Pubkey,
+    entity_type: EntityType,
+    entity: &Pubkey,
+    payment_mint: &Pubkey,
+) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::VerifyEscrow { entity_type }, vec![
+        AccountMeta::new(*entity, false),
+        AccountMeta::new_readonly(pda::derive_vault_address(program_id, payment_mint).0, false),
+        AccountMeta::new_readonly(pda::vault_registry(program_id), false),
+        AccountMeta::new_readonly(pda::stats(program_id), false),
+    ])
+}
+
+// EMERGENCY RESOLUTIONS
+
+/// Proposes to unwind a stuck entity, executable by `emergency_resolve` once the timelock passes.
+pub fn propose_emergency_resolve(
+    program_id: &Pubkey,
+    governance: &Pubkey,
+    entity_type: EntityType,
+    entity: &Pubkey,
+) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::ProposeEmergencyResolve { entity_type }, vec![
+        AccountMeta::new_readonly(pda::config(program_id), false),
+        AccountMeta::new(*governance, true),
+        AccountMeta::new_readonly(*entity, false),
+        AccountMeta::new(pda::emergency_resolution(program_id, entity), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ])
+}
+
+/// Returns a stuck entity's escrow from the vault of `payment_mint` to its depositors. Offers
+/// selling an NFT also pass `seller_nft_account` to return the NFT to.
+#[allow(clippy::too_many_arguments)]
+pub fn emergency_resolve(
+    program_id: &Pubkey,
+    governance: &Pubkey,
+    entity_type: EntityType,
+    entity: &Pubkey,
+    payment_mint: &Pubkey,
+    seller_or_sender_account: &Pubkey,
+    buyer_or_carrier_account: &Pubkey,
+    seller_nft_account: Option<&Pubkey>,
+) -> Result<Instruction, DLUError> {
+    let mut accounts = vec![
+        AccountMeta::new_readonly(pda::config(program_id), false),
+        AccountMeta::new_readonly(*governance, true),
+        AccountMeta::new(*entity, false),
+        AccountMeta::new(pda::emergency_resolution(program_id, entity), false),
+        AccountMeta::new(pda::derive_vault_address(program_id, payment_mint).0, false),
+        AccountMeta::new_readonly(pda::vault_registry(program_id), false),
+        AccountMeta::new(*seller_or_sender_account, false),
+        AccountMeta::new(*buyer_or_carrier_account, false),
+        AccountMeta::new(pda::stats(program_id), false),
+    ];
+    accounts.extend(nft_release_metas(program_id, entity, seller_nft_account));
+    build(program_id, &DLUInstruction::EmergencyResolve { entity_type }, accounts)
+}
+
+/// Withdraws governance's proposal to unwind `entity`.
+pub fn cancel_emergency_resolve(
+    program_id: &Pubkey,
+    governance: &Pubkey,
+    entity: &Pubkey,
+) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::CancelEmergencyResolve, vec![
+        AccountMeta::new_readonly(pda::config(program_id), false),
+        AccountMeta::new_readonly(*governance, true),
+        AccountMeta::new_readonly(*entity, false),
+        AccountMeta::new(pda::emergency_resolution(program_id, entity), false),
+    ])
+}
+
+// TIMELOCKED GOVERNANCE
+
+fn timelock_instruction(
+    program_id: &Pubkey,
+    governance: &Pubkey,
+    instruction: &DLUInstruction,
+) -> Result<Instruction, DLUError> {
+    build(program_id, instruction, vec![
+        AccountMeta::new_readonly(pda::config(program_id), false),
+        AccountMeta::new_readonly(*governance, true),
+        AccountMeta::new(pda::timelock_queue(program_id), false),
+    ])
+}
+
+/// Queues a config update, executable with `execute_governance_action` once the timelock passes.
+pub fn queue_governance_action(
+    program_id: &Pubkey,
+    governance: &Pubkey,
+    action: GovernanceAction,
+) -> Result<Instruction, DLUError> {
+    timelock_instruction(program_id, governance, &DLUInstruction::QueueGovernanceAction { action })
+}
+
+pub fn cancel_governance_action(program_id: &Pubkey, governance: &Pubkey, id: u64) -> Result<Instruction, DLUError> {
+    timelock_instruction(program_id, governance, &DLUInstruction::CancelGovernanceAction { id })
+}
+
+/// Applies the queued config update `id`, which anyone can do once its timelock passed.
+pub fn execute_governance_action(program_id: &Pubkey, id: u64) -> Result<Instruction, DLUError> {
+    execute_governance_action_with(program_id, id, vec![])
+}
+
+/// Executes the queued treasury withdrawal `id` into the destination token account.
+pub fn execute_withdraw_treasury(
+    program_id: &Pubkey,
+    id: u64,
+    mint: &Pubkey,
+    destination_account: &Pubkey,
+) -> Result<Instruction, DLUError> {
+    execute_governance_action_with(program_id, id, vec![
+        AccountMeta::new(pda::derive_treasury_address(program_id, mint).0, false),
+        AccountMeta::new(*destination_account, false),
+    ])
+}
+
+/// Executes the queued vault registration `id`, with the rent payer funding the new vault.
+pub fn execute_register_vault(
+    program_id: &Pubkey,
+    id: u64,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+    rent_payer: &Pubkey,
+) -> Result<Instruction, DLUError> {
+    execute_governance_action_with(program_id, id, vec![
+        AccountMeta::new(*rent_payer, true),
+        AccountMeta::new(pda::vault_registry(program_id), false),
+        AccountMeta::new(pda::derive_vault_address(program_id, mint).0, false),
+        AccountMeta::new(pda::derive_treasury_address(program_id, mint).0, false),
+        AccountMeta::new(pda::penalty_vault(program_id, mint), false),
+        AccountMeta::new(pda::rewards_vault(program_id, mint), false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(*token_program, false),
+    ])
+}
+
+/// Executes the queued escrow migration `id`, signed by the legacy escrow's authority.
+pub fn execute_migrate_escrow(
+    program_id: &Pubkey,
+    id: u64,
+    entity: &Pubkey,
+    legacy_escrow_account: &Pubkey,
+    legacy_escrow_authority: &Pubkey,
+    payment_mint: &Pubkey,
+) -> Result<Instruction, DLUError> {
+    execute_governance_action_with(program_id, id, vec![
+        AccountMeta::new(*entity, false),
+        AccountMeta::new(*legacy_escrow_account, false),
+        AccountMeta::new_readonly(*legacy_escrow_authority, true),
+        AccountMeta::new(pda::derive_vault_address(program_id, payment_mint).0, false),
+        AccountMeta::new_readonly(pda::vault_registry(program_id), false),
+    ])
+}
+
+fn execute_governance_action_with(program_id: &Pubkey, id: u64, accounts: Vec<AccountMeta>) -> Result<Instruction, DLUError> {
+    let mut metas = vec![
+        AccountMeta::new(pda::config(program_id), false),
+        AccountMeta::new(pda::timelock_queue(program_id), false),
+    ];
+    metas.extend(accounts);
+    build(program_id, &DLUInstruction::ExecuteGovernanceAction { id }, metas)
+}
+
+// DAO VOTING
+
+pub fn initialize_dao(
+    program_id: &Pubkey,
+    governance: &Pubkey,
+    vote_mint: Pubkey,
+    quorum: u64,
+    proposal_threshold: u64,
+    voting_period: i64,
+) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::InitializeDao { vote_mint, quorum, proposal_threshold, voting_period }, vec![
+        AccountMeta::new_readonly(pda::config(program_id), false),
+        AccountMeta::new(*governance, true),
+        AccountMeta::new(pda::dao(program_id), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ])
+}
+
+fn vote_lock_metas(program_id: &Pubkey, owner: &Pubkey, owner_account: &Pubkey, vote_mint: &Pubkey) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new_readonly(pda::dao(program_id), false),
+        AccountMeta::new(pda::vote_lock(program_id, owner), false),
+        AccountMeta::new_readonly(*owner, true),
+        AccountMeta::new(*owner_account, false),
+        AccountMeta::new(pda::derive_vault_address(program_id, vote_mint).0, false),
+        AccountMeta::new_readonly(pda::vault_registry(program_id), false),
+    ]
+}
+
+/// Locks `amount` of the vote mint from `owner_account` to vote with. The owner's first lock
+/// creates their VoteLock, whose rent is paid by `rent_payer`, or else by the owner.
+pub fn lock_votes(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    owner_account: &Pubkey,
+    vote_mint: &Pubkey,
+    amount: u64,
+    rent_payer: Option<&Pubkey>,
+) -> Result<Instruction, DLUError> {
+    let mut metas = vote_lock_metas(program_id, owner, owner_account, vote_mint);
+    metas.push(AccountMeta::new_readonly(system_program::id(), false));
+    pay_rent(&mut metas, 2, rent_payer);
+    build(program_id, &DLUInstruction::LockVotes { amount }, metas)
+}
+
+/// Returns `amount` of locked tokens to `owner_account` once the owner's votes have closed.
+pub fn unlock_votes(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    owner_account: &Pubkey,
+    vote_mint: &Pubkey,
+    amount: u64,
+) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::UnlockVotes { amount }, vote_lock_metas(program_id, owner, owner_account, vote_mint))
+}
+
+/// Opens a vote on `action`. `proposal_id` is the DAO's `next_proposal_id`. The proposal's
+/// rent is paid by `rent_payer`, or else by the proposer.
+pub fn create_proposal(
+    program_id: &Pubkey,
+    proposer: &Pubkey,
+    proposal_id: u64,
+    action: GovernanceAction,
+    rent_payer: Option<&Pubkey>,
+) -> Result<Instruction, DLUError> {
+    let mut metas = vec![
+        AccountMeta::new(pda::dao(program_id), false),
+        AccountMeta::new(pda::proposal(program_id, proposal_id), false),
+        AccountMeta::new_readonly(pda::vote_lock(program_id, proposer), false),
+        AccountMeta::new_readonly(*proposer, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    pay_rent(&mut metas, 3, rent_payer);
+    build(program_id, &DLUInstruction::CreateProposal { action }, metas)
+}
+
+/// The vote's record is created with it, its rent paid by `rent_payer`, or else by the voter.
+pub fn cast_vote(
+    program_id: &Pubkey,
+    voter: &Pubkey,
+    proposal_id: u64,
+    support: bool,
+    rent_payer: Option<&Pubkey>,
+) -> Result<Instruction, DLUError> {
+    let proposal = pda::proposal(program_id, proposal_id);
+    let mut metas = vec![
+        AccountMeta::new(proposal, false),
+        AccountMeta::new(pda::vote_lock(program_id, voter), false),
+        AccountMeta::new_readonly(*voter, true),
+        AccountMeta::new(pda::vote_record(program_id, &proposal, voter), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    pay_rent(&mut metas, 2, rent_payer);
+    build(program_id, &DLUInstruction::CastVote { support }, metas)
+}
+
+/// Closes voting on a proposal, queuing its update in the timelock if it passed.
+pub fn finalize_proposal(program_id: &Pubkey, proposal_id: u64) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::FinalizeProposal, vec![
+        AccountMeta::new_readonly(pda::dao(program_id), false),
+        AccountMeta::new(pda::proposal(program_id, proposal_id), false),
+        AccountMeta::new(pda::timelock_queue(program_id), false),
+    ])
+}
+
+// PENALTY POOL
+
+pub fn set_penalty_burn_share(
+    program_id: &Pubkey,
+    governance: &Pubkey,
+    share_bps: u16,
+) -> Result<Instruction, DLUError> {
+    governance_instruction(program_id, governance, &DLUInstruction::SetPenaltyBurnShare { share_bps })
+}
+
+/// Burns the config's share of the penalties that reached the penalty vault of `mint` and
+/// credits the rest to enrolled carrier stakes. `rent_payer` signs and funds the pool on the
+/// first distribution.
+pub fn distribute_penalties(program_id: &Pubkey, mint: &Pubkey, rent_payer: &Pubkey) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::DistributePenalties, vec![
+        AccountMeta::new_readonly(pda::config(program_id), false),
+        AccountMeta::new(pda::penalty_pool(program_id, mint), false),
+        AccountMeta::new(pda::penalty_vault(program_id, mint), false),
+        AccountMeta::new(*mint, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new(*rent_payer, true),
+    ])
+}
+
+/// Pays `carrier` their share of the penalty pool of `mint` and enrolls their current stake.
+/// The first claim creates the carrier's rewards account, whose rent is paid by
+/// `rent_payer`, or else by the carrier.
+pub fn claim_penalty_rewards(
+    program_id: &Pubkey,
+    carrier: &Pubkey,
+    carrier_account: &Pubkey,
+    mint: &Pubkey,
+    rent_payer: Option<&Pubkey>,
+) -> Result<Instruction, DLUError> {
+    let mut metas = vec![
+        AccountMeta::new(pda::penalty_pool(program_id, mint), false),
+        AccountMeta::new(pda::penalty_rewards(program_id, mint, carrier), false),
+        AccountMeta::new_readonly(pda::derive_stake_address(program_id, carrier).0, false),
+        AccountMeta::new_readonly(*carrier, true),
+        AccountMeta::new(pda::penalty_vault(program_id, mint), false),
+        AccountMeta::new(*carrier_account, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    pay_rent(&mut metas, 3, rent_payer);
+    build(program_id, &DLUInstruction::ClaimPenaltyRewards, metas)
+}
+
+// FAUCET
+
+/// Mints test DLU to the owner's associated token account of `mint`, created beforehand
+/// under `token_program`, on clusters running the faucet.
+pub fn request_faucet(
+    program_id: &Pubkey,
+    user_account: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::RequestFaucet, vec![
+        AccountMeta::new(*user_account, false),
+        AccountMeta::new_readonly(*owner, true),
+        AccountMeta::new_readonly(pda::config(program_id), false),
+        AccountMeta::new(get_associated_token_address_with_program_id(owner, mint, token_program), false),
+        AccountMeta::new(*mint, false),
+        AccountMeta::new_readonly(pda::faucet(program_id), false),
+    ])
+}
+
+// COMPLETION REWARDS
+
+pub fn set_completion_rewards(
+    program_id: &Pubkey,
+    governance: &Pubkey,
+    reward: u64,
+    half_life: u64,
+    emission_cap: u64,
+) -> Result<Instruction, DLUError> {
+    governance_instruction(program_id, governance, &DLUInstruction::SetCompletionRewards { reward, half_life, emission_cap })
+}
+
+/// Passes the completion rewards of `payment_mint` on a completion, required once the config
+/// schedules rewards. Add them before any leaderboards. `rent_payer` signs and funds the
+/// emission on the first reward.
+pub fn with_completion_rewards(
+    program_id: &Pubkey,
+    mut instruction: Instruction,
+    payment_mint: &Pubkey,
+    rent_payer: &Pubkey,
+) -> Instruction {
+    instruction.accounts.extend([
+        AccountMeta::new(pda::reward_emission(program_id, payment_mint), false),
+        AccountMeta::new(pda::rewards_vault(program_id, payment_mint), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new(*rent_payer, true),
+    ]);
+    instruction
+}
+
+// FUNDED ACCEPTANCE
+
+/// Deposits `amount` from the buyer's token account into their wallet and accepts `offer`
+/// out of the wallet in one instruction; a zero `amount` pays with what the wallet holds.
+/// `quantity` is 1 unless the offer sells inventory.
+#[allow(clippy::too_many_arguments)]
+pub fn deposit_and_accept(
+    program_id: &Pubkey,
+    offer: &Pubkey,
+    buyer_user_account: &Pubkey,
+    buyer: &Pubkey,
+    buyer_token_account: &Pubkey,
+    escrow_account: &Pubkey,
+    seller: &Pubkey,
+    amount: u64,
+    quantity: u32,
+) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::DepositAndAccept { amount, quantity, promo_code: None }, vec![
+        AccountMeta::new(*offer, false),
+        AccountMeta::new(*buyer_user_account, false),
+        AccountMeta::new_readonly(*buyer, true),
+        AccountMeta::new(*buyer_token_account, false),
+        AccountMeta::new(pda::derive_wallet_address(program_id, buyer).0, false),
+        AccountMeta::new(*escrow_account, false),
+        AccountMeta::new_readonly(pda::vault_registry(program_id), false),
+        AccountMeta::new_readonly(pda::config(program_id), false),
+        AccountMeta::new(pda::stats(program_id), false),
+        AccountMeta::new(pda::index(program_id, seller), false),
+    ])
+}
+
+/// Creates and registers the escrow vault of `mint`, whose token program is `token_program`.
+/// Governance pays its rent, and that of the VaultRegistry the first registration creates.
+pub fn register_vault(
+    program_id: &Pubkey,
+    governance: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::RegisterVault, vec![
+        AccountMeta::new_readonly(pda::config(program_id), false),
+        AccountMeta::new(*governance, true),
+        AccountMeta::new(pda::vault_registry(program_id), false),
+        AccountMeta::new(pda::derive_vault_address(program_id, mint).0, false),
+        AccountMeta::new(pda::derive_treasury_address(program_id, mint).0, false),
+        AccountMeta::new(pda::penalty_vault(program_id, mint), false),
+        AccountMeta::new(pda::rewards_vault(program_id, mint), false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(*token_program, false),
+    ])
+}
+
+// SHIPMENT PRICING
+
+pub fn set_shipping_rates(
+    program_id: &Pubkey,
+    governance: &Pubkey,
+    base_rate: u64,
+    rate_per_km: u64,
+    rate_per_kg: u64,
+) -> Result<Instruction, DLUError> {
+    governance_instruction(program_id, governance, &DLUInstruction::SetShippingRates { base_rate, rate_per_km, rate_per_kg })
+}
+
+/// Records the measurements of a listed `shipment`, quoting it at the config's current rates.
+pub fn measure_shipment(
+    program_id: &Pubkey,
+    shipment: &Pubkey,
+    sender: &Pubkey,
+    weight_grams: Option<u32>,
+    volume_cm3: Option<u32>,
+    distance_km: Option<u32>,
+) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::MeasureShipment { weight_grams, volume_cm3, distance_km }, vec![
+        AccountMeta::new(*shipment, false),
+        AccountMeta::new_readonly(*sender, true),
+        AccountMeta::new_readonly(pda::config(program_id), false),
+    ])
+}
+
+// CARRIER ROUTES
+
+/// Publishes route `route_id` of `carrier` from the `origin` region to `destination`, or
+/// updates its `days` and `capacity_grams`. The carrier pays the rent of a new route.
+#[allow(clippy::too_many_arguments)]
+pub fn publish_route(
+    program_id: &Pubkey,
+    carrier: &Pubkey,
+    carrier_user_account: &Pubkey,
+    route_id: u32,
+    origin: [u8; REGION_GEOHASH_LEN],
+    destination: [u8; REGION_GEOHASH_LEN],
+    days: u8,
+    capacity_grams: u64,
+) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::PublishRoute { route_id, origin, destination, days, capacity_grams }, vec![
+        AccountMeta::new(*carrier, true),
+        AccountMeta::new_readonly(*carrier_user_account, false),
+        AccountMeta::new(pda::carrier_route(program_id, carrier, route_id), false),
+        AccountMeta::new(pda::route_index(program_id, &origin), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ])
+}
+
+/// Closes route `route_id` of `carrier`, which leaves the `origin` region.
+pub fn close_route(
+    program_id: &Pubkey,
+    carrier: &Pubkey,
+    route_id: u32,
+    origin: &[u8; REGION_GEOHASH_LEN],
+) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::CloseRoute { route_id }, vec![
+        AccountMeta::new(*carrier, true),
+        AccountMeta::new(pda::carrier_route(program_id, carrier, route_id), false),
+        AccountMeta::new(pda::route_index(program_id, origin), false),
+    ])
+}
+
+/// Lists a listed `shipment` along the carrier route at `route`.
+pub fn reference_route(
+    program_id: &Pubkey,
+    shipment: &Pubkey,
+    sender: &Pubkey,
+    route: &Pubkey,
+) -> Result<Instruction, DLUError> {
+    build(program_id, &DLUInstruction::ReferenceRoute, vec![
+        AccountMeta::new(*shipment, false),
+        AccountMeta::new_readonly(*sender, true),
+        AccountMeta::new_readonly(*route, false),
+    ])
+}
+
+// Account layouts shared by offers, requests, and shipments.
+
+fn list_metas(program_id: &Pubkey, accounts: &ListAccounts) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new(accounts.entity, false),
+        AccountMeta::new_readonly(accounts.lister_user_account, false),
+        AccountMeta::new_readonly(accounts.lister, true),
+        AccountMeta::new(accounts.lister_account, false),
+        AccountMeta::new(accounts.escrow_account, false),
+        AccountMeta::new_readonly(pda::vault_registry(program_id), false),
+    ]
+}
+
+fn nft_deposit_metas(program_id: &Pubkey, offer: &Pubkey, nft: &NftListAccounts) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new(nft.seller_nft_account, false),
+        AccountMeta::new(pda::nft_escrow(program_id, offer), false),
+        AccountMeta::new_readonly(nft.nft_mint, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(nft.nft_token_program, false),
+    ]
+}
+
+/// Appends `rent_payer` as the instruction's rent payer, or else makes the signer at
+/// `signer_index` writable to pay the rent itself. Relayers pass themselves so users
+/// without SOL only sign.
+fn pay_rent(metas: &mut Vec<AccountMeta>, signer_index: usize, rent_payer: Option<&Pubkey>) {
+    match rent_payer {
+        Some(payer) => metas.push(AccountMeta::new(*payer, true)),
+        None => metas[signer_index].is_writable = true,
+    }
+}
+
+/// Appends the RegionIndex account a listing in `region` is announced in, if its location
+/// has a geohash, with the system program creating the index on the region's first listing.
+fn push_region(
+    metas: &mut Vec<AccountMeta>,
+    program_id: &Pubkey,
+    region: Option<[u8; REGION_GEOHASH_LEN]>,
+    signer_index: usize,
+    rent_payer: Option<&Pubkey>,
+) {
+    let Some(prefix) = region else {
+        return;
+    };
+    metas.push(AccountMeta::new(pda::region(program_id, &prefix), false));
+    metas.push(AccountMeta::new_readonly(system_program::id(), false));
+    pay_rent(metas, signer_index, rent_payer);
+}
+
+/// The owner signing, or their session key followed by its delegation.
+fn signer_or_session_metas(program_id: &Pubkey, owner: &Pubkey, session: Option<&Pubkey>) -> Vec<AccountMeta> {
+    match session {
+        Some(session) => vec![
+            AccountMeta::new_readonly(*session, true),
+            AccountMeta::new_readonly(pda::delegation(program_id, owner, session), false),
+        ],
+        None => vec![AccountMeta::new_readonly(*owner, true)],
+    }
+}
+
+fn nft_release_metas(program_id: &Pubkey, offer: &Pubkey, recipient_nft_account: Option<&Pubkey>) -> Vec<AccountMeta> {
+    recipient_nft_account
+        .map(|recipient_nft_account| {
+            vec![
+                AccountMeta::new(pda::nft_escrow(program_id, offer), false),
+                AccountMeta::new(*recipient_nft_account, false),
+            ]
+        })
+        .unwrap_or_default()
+}
+
+fn accept_metas(program_id: &Pubkey, accounts: &AcceptAccounts) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new(accounts.entity, false),
+        AccountMeta::new(accounts.acceptor_user_account, false),
+        AccountMeta::new_readonly(accounts.acceptor, true),
+        AccountMeta::new(accounts.acceptor_account, false),
+        AccountMeta::new(accounts.escrow_account, false),
+        AccountMeta::new_readonly(pda::vault_registry(program_id), false),
+        AccountMeta::new_readonly(accounts.acceptor_authority, true),
+        AccountMeta::new_readonly(pda::config(program_id), false),
+        AccountMeta::new(pda::stats(program_id), false),
+    ]
+}
+
+fn complete_metas(program_id: &Pubkey, accounts: &SettleAccounts) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new(accounts.entity, false),
+        AccountMeta::new(accounts.seller_user_account, false),
+        AccountMeta::new(accounts.buyer_user_account, false),
+        AccountMeta::new(accounts.seller_account, false),
+        AccountMeta::new(accounts.buyer_account, false),
+        AccountMeta::new(accounts.escrow_account, false),
+        AccountMeta::new_readonly(pda::vault_registry(program_id), false),
+        AccountMeta::new(pda::stats(program_id), false),
+        AccountMeta::new(pda::summary(program_id, &accounts.seller), false),
+        AccountMeta::new(pda::summary(program_id, &accounts.buyer), false),
+        AccountMeta::new_readonly(pda::config(program_id), false),
+        AccountMeta::new(pda::derive_treasury_address(program_id, &accounts.payment_mint).0, false),
+    ]
+}
+
+fn fail_metas(program_id: &Pubkey, accounts: &FailAccounts, refunds_accused: bool) -> Vec<AccountMeta> {
+    let mut metas = vec![
+        AccountMeta::new(accounts.entity, false),
+        AccountMeta::new(accounts.accused_user_account, false),
+        AccountMeta::new(accounts.escrow_account, false),
+        AccountMeta::new_readonly(pda::vault_registry(program_id), false),
+        AccountMeta::new(accounts.penalty_account, false),
+        AccountMeta::new(pda::stats(program_id), false),
+        AccountMeta::new(pda::summary(program_id, &accounts.accused), false),
+        AccountMeta::new_readonly(pda::config(program_id), false),
+        AccountMeta::new_readonly(accounts.claimant, true),
+        AccountMeta::new(accounts.claimant_account, false),
+        AccountMeta::new(pda::bond(program_id, &accounts.entity), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    pay_rent(&mut metas, 8, accounts.rent_payer.as_ref());
+    // Failed deals give the buyer their payment back; failed shipments keep the carrier's deposit.
+    if refunds_accused {
+        metas.push(AccountMeta::new(accounts.accused_account, false));
+    }
+    metas
+}
+
+fn expire_metas(program_id: &Pubkey, accounts: &SettleAccounts) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new(accounts.entity, false),
+        AccountMeta::new(accounts.escrow_account, false),
+        AccountMeta::new_readonly(pda::vault_registry(program_id), false),
+        AccountMeta::new(accounts.seller_account, false),
+        AccountMeta::new(accounts.buyer_account, false),
+        AccountMeta::new(pda::stats(program_id), false),
+    ]
+}
+
+fn cancel_metas(program_id: &Pubkey, accounts: &ListerEscrowAccounts) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new(accounts.entity, false),
+        AccountMeta::new_readonly(accounts.lister, true),
+        AccountMeta::new(accounts.lister_account, false),
+        AccountMeta::new(accounts.escrow_account, false),
+        AccountMeta::new_readonly(pda::vault_registry(program_id), false),
+        AccountMeta::new(pda::stats(program_id), false),
+    ]
+}
+
+fn relist_metas(program_id: &Pubkey, accounts: &ListerEscrowAccounts) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new(accounts.entity, false),
+        AccountMeta::new(accounts.lister_user_account, false),
+        AccountMeta::new_readonly(accounts.lister, true),
+        AccountMeta::new(accounts.lister_account, false),
+        AccountMeta::new(accounts.escrow_account, false),
+        AccountMeta::new_readonly(pda::vault_registry(program_id), false),
+        AccountMeta::new_readonly(pda::config(program_id), false),
+        AccountMeta::new(pda::stats(program_id), false),
+    ]
+}
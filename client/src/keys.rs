@@ -0,0 +1,89 @@
+// Deterministic one-time keys, so a party can derive their key again from their wallet
+// instead of storing a random one that can be lost.
+//
+// A key is HKDF-SHA256 over the party's signature of `KEY_DERIVATION_MESSAGE`, salted
+// with `onetimekeys::key_seed` of the deal or shipment id, the party, and the nonce the
+// key is committed with. Ed25519 signatures are deterministic, so the same wallet always
+// signs the same bytes, and the signature never leaves the client: only the hash of the
+// derived key goes on chain, with the nonce the KeyManager records for deriving it again.
+//
+// Keys handed over in person can instead be short key codes: ten base32 digits with a
+// checksum, shown as a QR code or typed in two groups of five.
+
+use hkdf::Hkdf;
+use sha2::Sha256;
+use std::str::FromStr;
+use solana_program::pubkey::Pubkey;
+use luda::errors::DLUError;
+use luda::onetimekeys::{self, KEY_CODE_BYTES};
+
+/// Message a party's wallet signs to obtain the input key material of their one-time keys.
+/// Changing it changes every derived key, so it carries a version.
+pub const KEY_DERIVATION_MESSAGE: &[u8] = b"LUDA one-time key derivation v1";
+
+/// Domain the derived key material is expanded for.
+const KEY_INFO: &[u8] = b"luda-one-time-key";
+
+/// Domain the derived key code is expanded for.
+const KEY_CODE_INFO: &[u8] = b"luda-key-code";
+
+/// Scheme of the QR codes key codes are shown as, followed by the entity and the code.
+pub const KEY_CODE_QR_PREFIX: &str = "luda:key:";
+
+/// Bytes of key material in a derived key, which is hex encoded.
+pub const KEY_BYTES: usize = 16;
+
+/// Derives the one-time key of `party` for the deal or shipment with `entity_id`, from the
+/// party's `signature` of `KEY_DERIVATION_MESSAGE` and the `nonce` it is committed with.
+pub fn derive_key(signature: &[u8; 64], entity_id: u64, party: &Pubkey, nonce: u64) -> String {
+    let seed = onetimekeys::key_seed(entity_id, party, nonce);
+    let mut key = [0; KEY_BYTES];
+    Hkdf::<Sha256>::new(Some(&seed), signature)
+        .expand(KEY_INFO, &mut key)
+        .expect("KEY_BYTES is a valid HKDF-SHA256 output length");
+    key.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Derives the short key code of `party` the way `derive_key` derives their key, in the
+/// canonical form to commit.
+pub fn derive_key_code(signature: &[u8; 64], entity_id: u64, party: &Pubkey, nonce: u64) -> String {
+    let seed = onetimekeys::key_seed(entity_id, party, nonce);
+    let mut payload = [0; KEY_CODE_BYTES];
+    Hkdf::<Sha256>::new(Some(&seed), signature)
+        .expand(KEY_CODE_INFO, &mut payload)
+        .expect("KEY_CODE_BYTES is a valid HKDF-SHA256 output length");
+    onetimekeys::encode_key_code(&payload)
+}
+
+/// Splits a canonical key code into two groups of five for display, like `7K3QD-XW9PA`.
+pub fn format_key_code(code: &str) -> String {
+    if code.len() <= 5 || !code.is_char_boundary(5) {
+        return code.to_string();
+    }
+    let (first, second) = code.split_at(5);
+    format!("{}-{}", first, second)
+}
+
+/// Returns the canonical form of a typed key code, failing with `InvalidKeyCode` on a typo.
+pub fn parse_key_code(typed: &str) -> Result<String, DLUError> {
+    onetimekeys::decode_key_code(typed).map(|payload| onetimekeys::encode_key_code(&payload))
+}
+
+/// Returns the text of the QR code showing a key code for an offer, request or shipment.
+pub fn key_code_qr(entity: &Pubkey, code: &str) -> String {
+    format!("{}{}:{}", KEY_CODE_QR_PREFIX, entity, code)
+}
+
+/// Reads a scanned QR code back into its entity and canonical key code.
+pub fn parse_key_code_qr(scanned: &str) -> Result<(Pubkey, String), DLUError> {
+    let rest = scanned.strip_prefix(KEY_CODE_QR_PREFIX).ok_or(DLUError::InvalidKeyCode)?;
+    let (entity, code) = rest.split_once(':').ok_or(DLUError::InvalidKeyCode)?;
+    let entity = Pubkey::from_str(entity).map_err(|_| DLUError::InvalidKeyCode)?;
+    Ok((entity, parse_key_code(code)?))
+}
+
+/// Returns the nonce of the next key `party` commits, one past the highest the KeyManager
+/// recorded for them, since the KeyManager refuses nonces it has seen.
+pub fn next_nonce(key_manager: &onetimekeys::KeyManager, party: &Pubkey) -> u64 {
+    key_manager.keys.iter().filter(|key| key.party == *party).map(|key| key.nonce.saturating_add(1)).max().unwrap_or(0)
+}
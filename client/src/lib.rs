@@ -0,0 +1,15 @@
+// Client-side helpers for the LUDA program: typed instruction builders, PDA derivation,
+// and fetching and decoding program accounts.
+
+pub mod accounts;     // Decoding program accounts
+pub mod instructions; // Typed builders for every DLUInstruction
+pub mod keys;         // Deriving one-time keys from a wallet signature
+pub mod lookup;       // Address lookup tables and v0 messages
+pub mod pda;          // Program-derived addresses, mirroring the program's addressing
+pub mod relay;        // Sponsored messages paid by a relayer, with durable nonces
+#[cfg(feature = "rpc")]
+pub mod rpc;          // Fetching program accounts over RPC
+#[cfg(feature = "wasm-bindgen")]
+pub mod wasm;         // JavaScript bindings for browser wallets
+
+pub use luda;
@@ -0,0 +1,82 @@
+// Address lookup tables for the accounts every LUDA transaction repeats, so v0 transactions
+// reference them by a one-byte index instead of their 32-byte key.
+
+use solana_address_lookup_table_program::instruction::{create_lookup_table, extend_lookup_table};
+use solana_program::{
+    address_lookup_table_account::AddressLookupTableAccount,
+    hash::Hash,
+    instruction::Instruction,
+    message::{v0, CompileError, VersionedMessage},
+    pubkey::Pubkey,
+    system_program,
+};
+use crate::pda;
+
+/// Most addresses one extension adds, keeping the transaction carrying it under the packet size.
+pub const MAX_ADDRESSES_PER_EXTEND: usize = 20;
+
+/// The program's static accounts: the program itself, the token, associated token and system
+/// programs, the config, stats and vault registry, and for each mint in `payment_mints` the mint with its
+/// treasury, vault, penalty vault and reward accounts.
+pub fn static_addresses(program_id: &Pubkey, payment_mints: &[Pubkey]) -> Vec<Pubkey> {
+    let mut addresses = vec![
+        *program_id,
+        spl_token::id(),
+        spl_token_2022::id(),
+        spl_associated_token_account::id(),
+        system_program::id(),
+        pda::config(program_id),
+        pda::stats(program_id),
+        pda::vault_registry(program_id),
+    ];
+    for mint in payment_mints {
+        addresses.extend([
+            *mint,
+            pda::derive_treasury_address(program_id, mint).0,
+            pda::derive_vault_address(program_id, mint).0,
+            pda::penalty_vault(program_id, mint),
+            pda::reward_emission(program_id, mint),
+            pda::rewards_vault(program_id, mint),
+        ]);
+    }
+    addresses
+}
+
+/// Builds the instructions creating a lookup table of the program's static accounts, owned
+/// by `authority` and funded by `payer`. `recent_slot` must be a recent finalized slot and
+/// picks the table's address, returned with the instructions.
+///
+/// The first instruction creates the table and the others extend it; send the extensions in
+/// their own transactions when they don't fit together. A table is usable one slot after its
+/// last extension.
+pub fn create_static_lookup_table(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+    recent_slot: u64,
+    payment_mints: &[Pubkey],
+) -> (Pubkey, Vec<Instruction>) {
+    let (create, table) = create_lookup_table(*authority, *payer, recent_slot);
+    let mut instructions = vec![create];
+    for chunk in static_addresses(program_id, payment_mints).chunks(MAX_ADDRESSES_PER_EXTEND) {
+        instructions.push(extend_lookup_table(table, *authority, Some(*payer), chunk.to_vec()));
+    }
+    (table, instructions)
+}
+
+/// The lookup table at `table` once it holds the program's static accounts, for compiling
+/// messages without fetching it.
+pub fn static_lookup_table(table: &Pubkey, program_id: &Pubkey, payment_mints: &[Pubkey]) -> AddressLookupTableAccount {
+    AddressLookupTableAccount { key: *table, addresses: static_addresses(program_id, payment_mints) }
+}
+
+/// Builds a v0 message paid by `payer` that loads the accounts found in `tables` from them
+/// instead of listing them. Signers and invoked programs always stay in the message.
+pub fn v0_message(
+    instructions: &[Instruction],
+    payer: &Pubkey,
+    tables: &[AddressLookupTableAccount],
+    blockhash: &Hash,
+) -> Result<VersionedMessage, CompileError> {
+    Ok(VersionedMessage::V0(v0::Message::try_compile(payer, instructions, tables, *blockhash)?))
+}
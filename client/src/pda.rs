@@ -0,0 +1,178 @@
+// Program-derived addresses used by the LUDA program.
+//
+// These are the program's own derivation helpers, re-exported so clients can never drift
+// from the seeds the program checks.
+
+use solana_program::pubkey::Pubkey;
+use luda::arbiter::DisputeKind;
+use luda::leaderboard::LeaderboardKind;
+use luda::region::REGION_GEOHASH_LEN;
+
+pub use luda::addressing::{
+    derive_address, derive_appeal_address, derive_arbiter_address, derive_arbiter_registry_address, derive_attestation_address, derive_attestor_registry_address, derive_referral_address, derive_promo_address, derive_watchlist_address, derive_leaderboard_address, derive_listing_tree_authority_address, derive_history_address, derive_key_manager_address, derive_emergency_address, derive_timelock_address, derive_vault_registry_address, derive_carrier_route_address, derive_route_index_address, derive_dao_address, derive_proposal_address, derive_vote_lock_address, derive_vote_record_address, derive_penalty_pool_address, derive_penalty_vault_address, derive_penalty_rewards_address, derive_faucet_address, derive_reward_emission_address, derive_rewards_vault_address, derive_arbitration_address, derive_badge_address, derive_badge_mint_address, derive_bond_address, derive_config_address, derive_delegation_address,
+    derive_index_address, derive_message_log_address, derive_multisig_address, derive_nft_escrow_address, derive_organization_address, derive_profile_summary_address, derive_quote_address,
+    derive_region_address, derive_review_address, derive_stake_address, derive_stats_address, derive_treasury_address,
+    derive_vault_address, derive_wallet_address,
+    ENTITY_OFFER, ENTITY_REQUEST, ENTITY_SHIPMENT,
+};
+pub use luda::keeper::derive_expiry_thread_address;
+
+pub(crate) fn config(program_id: &Pubkey) -> Pubkey {
+    derive_config_address(program_id).0
+}
+
+pub(crate) fn stats(program_id: &Pubkey) -> Pubkey {
+    derive_stats_address(program_id).0
+}
+
+pub(crate) fn summary(program_id: &Pubkey, user: &Pubkey) -> Pubkey {
+    derive_profile_summary_address(program_id, user).0
+}
+
+pub(crate) fn index(program_id: &Pubkey, user: &Pubkey) -> Pubkey {
+    derive_index_address(program_id, user).0
+}
+
+pub(crate) fn bond(program_id: &Pubkey, entity: &Pubkey) -> Pubkey {
+    derive_bond_address(program_id, entity).0
+}
+
+pub(crate) fn nft_escrow(program_id: &Pubkey, offer: &Pubkey) -> Pubkey {
+    derive_nft_escrow_address(program_id, offer).0
+}
+
+pub(crate) fn quote(program_id: &Pubkey, request: &Pubkey, seller: &Pubkey) -> Pubkey {
+    derive_quote_address(program_id, request, seller).0
+}
+
+pub(crate) fn region(program_id: &Pubkey, prefix: &[u8; REGION_GEOHASH_LEN]) -> Pubkey {
+    derive_region_address(program_id, prefix).0
+}
+
+pub(crate) fn message_log(program_id: &Pubkey, entity: &Pubkey) -> Pubkey {
+    derive_message_log_address(program_id, entity).0
+}
+
+pub(crate) fn arbiter(program_id: &Pubkey, owner: &Pubkey) -> Pubkey {
+    derive_arbiter_address(program_id, owner).0
+}
+
+pub(crate) fn arbiter_registry(program_id: &Pubkey) -> Pubkey {
+    derive_arbiter_registry_address(program_id).0
+}
+
+pub(crate) fn arbitration(program_id: &Pubkey, entity: &Pubkey, kind: DisputeKind) -> Pubkey {
+    derive_arbitration_address(program_id, entity, kind).0
+}
+
+pub(crate) fn appeal(program_id: &Pubkey, entity: &Pubkey, kind: DisputeKind) -> Pubkey {
+    derive_appeal_address(program_id, &arbitration(program_id, entity, kind)).0
+}
+
+pub(crate) fn delegation(program_id: &Pubkey, owner: &Pubkey, session: &Pubkey) -> Pubkey {
+    derive_delegation_address(program_id, owner, session).0
+}
+
+pub(crate) fn organization(program_id: &Pubkey, owner: &Pubkey) -> Pubkey {
+    derive_organization_address(program_id, owner).0
+}
+
+pub(crate) fn multisig(program_id: &Pubkey, owner: &Pubkey) -> Pubkey {
+    derive_multisig_address(program_id, owner).0
+}
+
+pub(crate) fn attestor_registry(program_id: &Pubkey) -> Pubkey {
+    derive_attestor_registry_address(program_id).0
+}
+
+pub(crate) fn attestation(program_id: &Pubkey, owner: &Pubkey) -> Pubkey {
+    derive_attestation_address(program_id, owner).0
+}
+
+pub(crate) fn referral(program_id: &Pubkey, referrer: &Pubkey, mint: &Pubkey) -> Pubkey {
+    derive_referral_address(program_id, referrer, mint).0
+}
+
+pub(crate) fn promo(program_id: &Pubkey, offer: &Pubkey) -> Pubkey {
+    derive_promo_address(program_id, offer).0
+}
+
+pub(crate) fn watchlist(program_id: &Pubkey, owner: &Pubkey) -> Pubkey {
+    derive_watchlist_address(program_id, owner).0
+}
+
+pub(crate) fn leaderboard(program_id: &Pubkey, kind: LeaderboardKind) -> Pubkey {
+    derive_leaderboard_address(program_id, kind).0
+}
+
+pub(crate) fn listing_tree_authority(program_id: &Pubkey, tree: &Pubkey) -> Pubkey {
+    derive_listing_tree_authority_address(program_id, tree).0
+}
+
+pub(crate) fn history(program_id: &Pubkey, lister: &Pubkey) -> Pubkey {
+    derive_history_address(program_id, lister).0
+}
+
+pub(crate) fn key_manager(program_id: &Pubkey, entity: &Pubkey) -> Pubkey {
+    derive_key_manager_address(program_id, entity).0
+}
+
+pub(crate) fn emergency_resolution(program_id: &Pubkey, entity: &Pubkey) -> Pubkey {
+    derive_emergency_address(program_id, entity).0
+}
+
+pub(crate) fn timelock_queue(program_id: &Pubkey) -> Pubkey {
+    derive_timelock_address(program_id).0
+}
+
+pub(crate) fn dao(program_id: &Pubkey) -> Pubkey {
+    derive_dao_address(program_id).0
+}
+
+pub(crate) fn proposal(program_id: &Pubkey, id: u64) -> Pubkey {
+    derive_proposal_address(program_id, id).0
+}
+
+pub(crate) fn vote_lock(program_id: &Pubkey, owner: &Pubkey) -> Pubkey {
+    derive_vote_lock_address(program_id, owner).0
+}
+
+pub(crate) fn vote_record(program_id: &Pubkey, proposal: &Pubkey, voter: &Pubkey) -> Pubkey {
+    derive_vote_record_address(program_id, proposal, voter).0
+}
+
+pub(crate) fn penalty_pool(program_id: &Pubkey, mint: &Pubkey) -> Pubkey {
+    derive_penalty_pool_address(program_id, mint).0
+}
+
+pub(crate) fn penalty_vault(program_id: &Pubkey, mint: &Pubkey) -> Pubkey {
+    derive_penalty_vault_address(program_id, mint).0
+}
+
+pub(crate) fn penalty_rewards(program_id: &Pubkey, mint: &Pubkey, carrier: &Pubkey) -> Pubkey {
+    derive_penalty_rewards_address(program_id, mint, carrier).0
+}
+
+pub(crate) fn faucet(program_id: &Pubkey) -> Pubkey {
+    derive_faucet_address(program_id).0
+}
+
+pub(crate) fn reward_emission(program_id: &Pubkey, mint: &Pubkey) -> Pubkey {
+    derive_reward_emission_address(program_id, mint).0
+}
+
+pub(crate) fn rewards_vault(program_id: &Pubkey, mint: &Pubkey) -> Pubkey {
+    derive_rewards_vault_address(program_id, mint).0
+}
+
+pub(crate) fn vault_registry(program_id: &Pubkey) -> Pubkey {
+    derive_vault_registry_address(program_id).0
+}
+
+pub(crate) fn carrier_route(program_id: &Pubkey, carrier: &Pubkey, route_id: u32) -> Pubkey {
+    derive_carrier_route_address(program_id, carrier, route_id).0
+}
+
+pub(crate) fn route_index(program_id: &Pubkey, origin: &[u8; REGION_GEOHASH_LEN]) -> Pubkey {
+    derive_route_index_address(program_id, origin).0
+}
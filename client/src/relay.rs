@@ -0,0 +1,33 @@
+// Sponsored transactions for users without SOL: a relayer pays the fees, and the rent of
+// instructions given it as their rent payer, while users only sign the message.
+
+use solana_program::{hash::Hash, instruction::Instruction, message::Message, pubkey::Pubkey};
+
+/// A durable nonce account the relayer controls, standing in for a recent blockhash so a
+/// sponsored message stays valid while users take their time to sign it.
+pub struct DurableNonce {
+    pub account: Pubkey,
+    pub authority: Pubkey,  // Signs the nonce advance, normally the relayer.
+}
+
+/// Builds a message paid by `fee_payer`, so no instruction's signers need SOL for fees.
+/// `blockhash` is a recent blockhash, or with a `nonce` the value the nonce account
+/// currently stores; the message then starts by advancing the nonce and stays valid until
+/// it is advanced again.
+pub fn sponsored_message(instructions: &[Instruction], fee_payer: &Pubkey, blockhash: &Hash, nonce: Option<&DurableNonce>) -> Message {
+    match nonce {
+        Some(nonce) => {
+            let mut message = Message::new_with_nonce(instructions.to_vec(), Some(fee_payer), &nonce.account, &nonce.authority);
+            message.recent_blockhash = *blockhash;
+            message
+        }
+        None => Message::new_with_blockhash(instructions, Some(fee_payer), blockhash),
+    }
+}
+
+/// Returns the keys besides the fee payer that must sign the message, such as the users
+/// behind its instructions and the nonce authority.
+pub fn required_signers(message: &Message) -> Vec<Pubkey> {
+    let signers = message.header.num_required_signatures as usize;
+    message.account_keys.iter().take(signers).skip(1).copied().collect()
+}
@@ -0,0 +1,133 @@
+// Fetching LUDA program accounts through `RpcClient`.
+
+use solana_address_lookup_table_program::state::AddressLookupTable;
+use solana_client::{client_error::ClientError as RpcError, rpc_client::RpcClient};
+use solana_program::address_lookup_table_account::AddressLookupTableAccount;
+use solana_program::instruction::InstructionError;
+use solana_program::pubkey::Pubkey;
+use thiserror::Error;
+use luda::addressing::IndexAccount;
+use luda::bond::FailBond;
+use luda::config::Config;
+use luda::errors::DLUError;
+use luda::leaderboard::{Leaderboard, LeaderboardKind};
+use luda::messaging::MessageLog;
+use luda::offer::Offer;
+use luda::onetimekeys::KeyManager;
+use luda::profile_summary::ProfileSummary;
+use luda::region::{RegionIndex, REGION_GEOHASH_LEN};
+use luda::request::Request;
+use luda::review::Review;
+use luda::route::{CarrierRoute, RouteIndex};
+use luda::shipment::Shipment;
+use luda::stake::CarrierStake;
+use luda::stats::Stats;
+use luda::user::User;
+use luda::watchlist::Watchlist;
+use crate::accounts::*;
+use crate::pda;
+
+/// Errors returned while fetching accounts.
+#[derive(Error, Debug)]
+pub enum ClientError {
+    #[error("RPC Request Failed: {0}")]
+    Rpc(#[from] RpcError),
+
+    #[error("Account Decoding Failed: {0}")]
+    Decode(#[from] DLUError),
+
+    #[error("Lookup Table Decoding Failed: {0}")]
+    LookupTable(#[from] InstructionError),
+}
+
+fn fetch<T>(client: &RpcClient, key: &Pubkey, decode: fn(&[u8]) -> Result<T, DLUError>) -> Result<T, ClientError> {
+    let data = client.get_account_data(key)?;
+    Ok(decode(&data)?)
+}
+
+pub fn fetch_config(client: &RpcClient, program_id: &Pubkey) -> Result<Config, ClientError> {
+    fetch(client, &pda::config(program_id), decode_config)
+}
+
+pub fn fetch_stats(client: &RpcClient, program_id: &Pubkey) -> Result<Stats, ClientError> {
+    fetch(client, &pda::stats(program_id), decode_stats)
+}
+
+pub fn fetch_user(client: &RpcClient, user_account: &Pubkey) -> Result<User, ClientError> {
+    fetch(client, user_account, decode_user)
+}
+
+pub fn fetch_profile_summary(client: &RpcClient, program_id: &Pubkey, user: &Pubkey) -> Result<ProfileSummary, ClientError> {
+    fetch(client, &pda::summary(program_id, user), decode_profile_summary)
+}
+
+pub fn fetch_index(client: &RpcClient, program_id: &Pubkey, user: &Pubkey) -> Result<IndexAccount, ClientError> {
+    fetch(client, &pda::index(program_id, user), decode_index)
+}
+
+pub fn fetch_offer(client: &RpcClient, offer: &Pubkey) -> Result<Offer, ClientError> {
+    fetch(client, offer, decode_offer)
+}
+
+pub fn fetch_request(client: &RpcClient, request: &Pubkey) -> Result<Request, ClientError> {
+    fetch(client, request, decode_request)
+}
+
+pub fn fetch_shipment(client: &RpcClient, shipment: &Pubkey) -> Result<Shipment, ClientError> {
+    fetch(client, shipment, decode_shipment)
+}
+
+pub fn fetch_bond(client: &RpcClient, program_id: &Pubkey, entity: &Pubkey) -> Result<FailBond, ClientError> {
+    fetch(client, &pda::bond(program_id, entity), decode_bond)
+}
+
+pub fn fetch_stake(client: &RpcClient, program_id: &Pubkey, carrier: &Pubkey) -> Result<CarrierStake, ClientError> {
+    fetch(client, &pda::derive_stake_address(program_id, carrier).0, decode_stake)
+}
+
+pub fn fetch_review(client: &RpcClient, program_id: &Pubkey, deal_id: u64, reviewer: &Pubkey) -> Result<Review, ClientError> {
+    fetch(client, &pda::derive_review_address(program_id, deal_id, reviewer).0, decode_review)
+}
+
+pub fn fetch_region_index(client: &RpcClient, program_id: &Pubkey, prefix: &[u8; REGION_GEOHASH_LEN]) -> Result<RegionIndex, ClientError> {
+    fetch(client, &pda::region(program_id, prefix), decode_region_index)
+}
+
+/// Fetches the messages the parties of an offer, request or shipment exchanged.
+pub fn fetch_message_log(client: &RpcClient, program_id: &Pubkey, entity: &Pubkey) -> Result<MessageLog, ClientError> {
+    fetch(client, &pda::message_log(program_id, entity), decode_message_log)
+}
+
+/// Fetches the users a wallet follows and the offers it watches.
+pub fn fetch_watchlist(client: &RpcClient, program_id: &Pubkey, owner: &Pubkey) -> Result<Watchlist, ClientError> {
+    fetch(client, &pda::watchlist(program_id, owner), decode_watchlist)
+}
+
+/// Fetches the ranking of a leaderboard, best first.
+pub fn fetch_leaderboard(client: &RpcClient, program_id: &Pubkey, kind: LeaderboardKind) -> Result<Leaderboard, ClientError> {
+    fetch(client, &pda::leaderboard(program_id, kind), decode_leaderboard)
+}
+
+/// Fetches the one-time keys committed for an offer, request or shipment, with the nonces
+/// `keys::derive_key` derives them again from.
+pub fn fetch_key_manager(client: &RpcClient, program_id: &Pubkey, entity: &Pubkey) -> Result<KeyManager, ClientError> {
+    fetch(client, &pda::key_manager(program_id, entity), decode_key_manager)
+}
+
+pub fn fetch_carrier_route(client: &RpcClient, program_id: &Pubkey, carrier: &Pubkey, route_id: u32) -> Result<CarrierRoute, ClientError> {
+    fetch(client, &pda::carrier_route(program_id, carrier, route_id), decode_carrier_route)
+}
+
+/// Fetches the carrier routes leaving a region, for senders looking for a carrier already
+/// traveling their way.
+pub fn fetch_route_index(client: &RpcClient, program_id: &Pubkey, origin: &[u8; REGION_GEOHASH_LEN]) -> Result<RouteIndex, ClientError> {
+    fetch(client, &pda::route_index(program_id, origin), decode_route_index)
+}
+
+/// Fetches a lookup table, such as the one `lookup::create_static_lookup_table` creates, to
+/// compile v0 messages with.
+pub fn fetch_lookup_table(client: &RpcClient, table: &Pubkey) -> Result<AddressLookupTableAccount, ClientError> {
+    let data = client.get_account_data(table)?;
+    let state = AddressLookupTable::deserialize(&data)?;
+    Ok(AddressLookupTableAccount { key: *table, addresses: state.addresses.to_vec() })
+}
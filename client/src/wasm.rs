@@ -0,0 +1,2117 @@
+// JavaScript bindings for browser wallets, enabled by the `wasm-bindgen` feature.
+//
+// Keys are passed as base58 strings, amounts as `bigint`, and instructions come back with
+// `programId`, `keys`, and `data` shaped like a web3.js `TransactionInstruction`, so the
+// frontend can build and sign transactions without a backend serializing Borsh for it.
+
+use std::str::FromStr;
+use js_sys::{Array, Object, Reflect, Uint8Array};
+use solana_program::borsh::BorshDeserialize;
+use solana_program::{instruction::Instruction, pubkey::Pubkey};
+use wasm_bindgen::prelude::*;
+use luda::addressing::{EntityType, ENTITY_OFFER, ENTITY_REQUEST, ENTITY_SHIPMENT};
+use luda::arbiter::DisputeKind;
+use luda::compression::CompressedOffer;
+use luda::deal::{Deal, DealRole, Location};
+use luda::errors::DLUError;
+use luda::holdback::HoldbackTerms;
+use luda::keeper::{self, CrankAccounts, ExpiryBatchEntry};
+use luda::leaderboard::{LeaderboardKind, CARRIER_LEADERBOARDS, SELLER_LEADERBOARDS};
+use luda::offer::{price_tiers, OfferKind, PriceTier, MAX_PRICE_TIERS};
+use luda::pricing::{self, ShippingRates};
+use luda::profile_summary::ProfileSummary;
+use luda::promo;
+use luda::region::REGION_GEOHASH_LEN;
+use luda::timelock::GovernanceAction;
+use crate::accounts;
+use crate::instructions::{self, AcceptAccounts, ListAccounts, ListerEscrowAccounts, NftListAccounts, SettleAccounts};
+use crate::keys;
+use crate::pda;
+
+/// An instruction ready to be added to a transaction.
+#[wasm_bindgen(js_name = Instruction)]
+pub struct JsInstruction(Instruction);
+
+#[wasm_bindgen(js_class = Instruction)]
+impl JsInstruction {
+    #[wasm_bindgen(getter, js_name = programId)]
+    pub fn program_id(&self) -> String {
+        self.0.program_id.to_string()
+    }
+
+    /// Accounts as `{ pubkey, isSigner, isWritable }` objects, in instruction order.
+    #[wasm_bindgen(getter)]
+    pub fn keys(&self) -> Array {
+        self.0
+            .accounts
+            .iter()
+            .map(|meta| {
+                let key = Object::new();
+                set(&key, "pubkey", meta.pubkey.to_string());
+                set(&key, "isSigner", meta.is_signer);
+                set(&key, "isWritable", meta.is_writable);
+                JsValue::from(key)
+            })
+            .collect()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn data(&self) -> Vec<u8> {
+        self.0.data.clone()
+    }
+}
+
+fn set(object: &Object, key: &str, value: impl Into<JsValue>) {
+    // Setting a property on a plain object cannot fail.
+    let _ = Reflect::set(object, &JsValue::from_str(key), &value.into());
+}
+
+fn key(value: &str) -> Result<Pubkey, JsError> {
+    Pubkey::from_str(value).map_err(|_| JsError::new(&format!("Invalid Public Key: {}", value)))
+}
+
+fn hash(value: &[u8]) -> Result<[u8; 32], JsError> {
+    value.try_into().map_err(|_| JsError::new("Hashes Must Be 32 Bytes"))
+}
+
+/// Pairs the minimum quantities and unit prices of an inventory offer's tiers.
+fn inventory(
+    max_quantity: Option<u32>,
+    min_quantities: Option<Vec<u32>>,
+    unit_prices: Option<Vec<u64>>,
+) -> Result<Option<(u32, [PriceTier; MAX_PRICE_TIERS])>, JsError> {
+    let invalid = || JsError::new(&DLUError::InvalidPriceTiers.to_string());
+    match (max_quantity, min_quantities, unit_prices) {
+        (Some(max_quantity), Some(min_quantities), Some(unit_prices)) if min_quantities.len() == unit_prices.len() => {
+            let tiers: Vec<PriceTier> = min_quantities.into_iter()
+                .zip(unit_prices)
+                .map(|(min_quantity, unit_price)| PriceTier { min_quantity, unit_price })
+                .collect();
+            Ok(Some((max_quantity, price_tiers(&tiers).map_err(|_| invalid())?)))
+        }
+        (None, None, None) => Ok(None),
+        _ => Err(invalid()),
+    }
+}
+
+fn entity_type(value: &str) -> Result<EntityType, JsError> {
+    match value {
+        ENTITY_OFFER => Ok(EntityType::Offer),
+        ENTITY_REQUEST => Ok(EntityType::Request),
+        ENTITY_SHIPMENT => Ok(EntityType::Shipment),
+        _ => Err(JsError::new(&DLUError::InvalidEntityType.to_string())),
+    }
+}
+
+fn dispute_kind(value: &str) -> Result<DisputeKind, JsError> {
+    match value {
+        "holdback" => Ok(DisputeKind::Holdback),
+        "chargeback" => Ok(DisputeKind::Chargeback),
+        _ => Err(JsError::new(&format!("Invalid Dispute Kind: {}", value))),
+    }
+}
+
+fn leaderboard_kind(value: &str) -> Result<LeaderboardKind, JsError> {
+    match value {
+        "sellerVolume" => Ok(LeaderboardKind::SellerVolume),
+        "sellerStreak" => Ok(LeaderboardKind::SellerStreak),
+        "carrierVolume" => Ok(LeaderboardKind::CarrierVolume),
+        "carrierStreak" => Ok(LeaderboardKind::CarrierStreak),
+        _ => Err(JsError::new(&format!("Invalid Leaderboard Kind: {}", value))),
+    }
+}
+
+fn decode_error(error: DLUError) -> JsError {
+    JsError::new(&error.to_string())
+}
+
+fn settle_accounts(keys: &[&str; 8], payment_mint: &str) -> Result<SettleAccounts, JsError> {
+    Ok(SettleAccounts {
+        entity: key(keys[0])?,
+        seller_user_account: key(keys[1])?,
+        seller: key(keys[2])?,
+        seller_account: key(keys[3])?,
+        buyer_user_account: key(keys[4])?,
+        buyer: key(keys[5])?,
+        buyer_account: key(keys[6])?,
+        escrow_account: key(keys[7])?,
+        payment_mint: key(payment_mint)?,
+    })
+}
+
+fn list_accounts(keys: &[&str; 5], rent_payer: Option<String>) -> Result<ListAccounts, JsError> {
+    Ok(ListAccounts {
+        entity: key(keys[0])?,
+        lister_user_account: key(keys[1])?,
+        lister: key(keys[2])?,
+        lister_account: key(keys[3])?,
+        escrow_account: key(keys[4])?,
+        rent_payer: optional_key(rent_payer)?,
+    })
+}
+
+fn accept_accounts(keys: &[&str; 6]) -> Result<AcceptAccounts, JsError> {
+    Ok(AcceptAccounts {
+        entity: key(keys[0])?,
+        acceptor_user_account: key(keys[1])?,
+        acceptor: key(keys[2])?,
+        acceptor_account: key(keys[3])?,
+        acceptor_authority: key(keys[4])?,
+        escrow_account: key(keys[5])?,
+    })
+}
+
+/// Without a token program the NFT mint is taken to be a classic SPL Token mint.
+fn nft_list_accounts(
+    seller_nft_account: Option<String>,
+    nft_mint: Option<String>,
+    nft_token_program: Option<String>,
+    rent_payer: Option<String>,
+) -> Result<Option<NftListAccounts>, JsError> {
+    let (Some(seller_nft_account), Some(nft_mint)) = (seller_nft_account, nft_mint) else {
+        return Ok(None);
+    };
+    Ok(Some(NftListAccounts {
+        seller_nft_account: key(&seller_nft_account)?,
+        nft_mint: key(&nft_mint)?,
+        nft_token_program: nft_token_program.as_deref().map(key).transpose()?.unwrap_or_else(spl_token::id),
+        rent_payer: optional_key(rent_payer)?,
+    }))
+}
+
+/// Builds a location, pinned to `geohash` when one is given so the listing is announced
+/// in its region.
+fn location(country: &str, town: String, address: String, geohash: Option<String>) -> Result<Location, JsError> {
+    let location = Location::new(country, town, address).map_err(decode_error)?;
+    match geohash {
+        Some(geohash) => location.with_geohash(&geohash).map_err(decode_error),
+        None => Ok(location),
+    }
+}
+
+fn optional_key(value: Option<String>) -> Result<Option<Pubkey>, JsError> {
+    value.as_deref().map(key).transpose()
+}
+
+fn key_array(values: &Array) -> Result<Vec<Pubkey>, JsError> {
+    values.iter().map(|value| key(&value.as_string().unwrap_or_default())).collect()
+}
+
+fn proof_nodes(nodes: &[String]) -> Result<Vec<[u8; 32]>, JsError> {
+    nodes.iter().map(|node| Ok(key(node)?.to_bytes())).collect()
+}
+
+fn lister_escrow_accounts(entity: &str, lister: &str, lister_account: &str, escrow_account: &str) -> Result<ListerEscrowAccounts, JsError> {
+    Ok(ListerEscrowAccounts {
+        entity: key(entity)?,
+        // Canceling never touches the user account.
+        lister_user_account: Pubkey::default(),
+        lister: key(lister)?,
+        lister_account: key(lister_account)?,
+        escrow_account: key(escrow_account)?,
+    })
+}
+
+// USERS
+
+#[allow(clippy::too_many_arguments)]
+#[wasm_bindgen(js_name = createUser)]
+pub fn create_user(
+    program_id: &str,
+    user_account: &str,
+    owner: &str,
+    username: String,
+    terms_hash: &[u8],
+    referrer: Option<String>,
+    dlu_mint: Option<String>,
+    dlu_token_program: Option<String>,
+    rent_payer: Option<String>,
+) -> Result<JsInstruction, JsError> {
+    let dlu_mint = dlu_mint.as_deref().map(key).transpose()?;
+    // Without a token program the mint is taken to be a classic SPL Token mint.
+    let token_program = dlu_token_program.as_deref().map(key).transpose()?.unwrap_or_else(spl_token::id);
+    let dlu_mint = dlu_mint.as_ref().map(|mint| (mint, &token_program));
+    let referrer = optional_key(referrer)?;
+    let rent_payer = optional_key(rent_payer)?;
+    let instruction = instructions::create_user(
+        &key(program_id)?,
+        &key(user_account)?,
+        &key(owner)?,
+        username,
+        hash(terms_hash)?,
+        referrer.as_ref(),
+        dlu_mint,
+        rent_payer.as_ref(),
+    ).map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+// OFFERS
+
+#[allow(clippy::too_many_arguments)]
+#[wasm_bindgen(js_name = listOffer)]
+pub fn list_offer(
+    program_id: &str,
+    offer: &str,
+    seller_user_account: &str,
+    seller: &str,
+    seller_account: &str,
+    escrow_account: &str,
+    id: u64,
+    name: String,
+    description: String,
+    payment: u64,
+    country: String,
+    town: String,
+    address: String,
+    meeting_datetime: i64,
+    seller_nft_account: Option<String>,
+    nft_mint: Option<String>,
+    nft_token_program: Option<String>,
+    barter_insurance: Option<u64>,
+    geohash: Option<String>,
+    meeting_point_hash: Option<Vec<u8>>,
+    holdback_bps: Option<u16>,
+    holdback_window: Option<i64>,
+    max_quantity: Option<u32>,
+    tier_min_quantities: Option<Vec<u32>>,
+    tier_unit_prices: Option<Vec<u64>>,
+    stream_duration: Option<i64>,
+    nft_rent_payer: Option<String>,
+    region_rent_payer: Option<String>,
+) -> Result<JsInstruction, JsError> {
+    let accounts = list_accounts(&[offer, seller_user_account, seller, seller_account, escrow_account], region_rent_payer)?;
+    let holdback = match (holdback_bps, holdback_window) {
+        (Some(bps), Some(window)) => Some(HoldbackTerms { bps, window }),
+        (None, None) => None,
+        _ => return Err(JsError::new(&DLUError::InvalidHoldbackTerms.to_string())),
+    };
+    let meeting_point = location(&country, town, address, geohash)?;
+    let nft = nft_list_accounts(seller_nft_account, nft_mint, nft_token_program, nft_rent_payer)?;
+    let inventory = inventory(max_quantity, tier_min_quantities, tier_unit_prices)?;
+    let instruction = instructions::list_offer(
+        &key(program_id)?, &accounts, id, name, description, payment, meeting_point, meeting_datetime, nft.as_ref(),
+        barter_insurance,
+        inventory,
+        stream_duration,
+        meeting_point_hash.as_deref().map(hash).transpose()?,
+        holdback,
+    ).map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+#[allow(clippy::too_many_arguments)]
+#[wasm_bindgen(js_name = acceptOffer)]
+pub fn accept_offer(
+    program_id: &str,
+    offer: &str,
+    buyer_user_account: &str,
+    buyer: &str,
+    buyer_account: &str,
+    buyer_authority: &str,
+    escrow_account: &str,
+    seller: &str,
+    quantity: u32,
+) -> Result<JsInstruction, JsError> {
+    let accounts = accept_accounts(&[offer, buyer_user_account, buyer, buyer_account, buyer_authority, escrow_account])?;
+    Ok(JsInstruction(instructions::accept_offer(&key(program_id)?, &accounts, &key(seller)?, quantity).map_err(decode_error)?))
+}
+
+/// `keys` are the offer, the seller's user account, wallet and token account, the
+/// buyer's user account, wallet and token account, and the vault of the payment mint.
+/// `nonce` is the offer's current nonce. `buyer_nft_account` receives the NFT of an NFT offer.
+#[wasm_bindgen(js_name = completeOffer)]
+pub fn complete_offer(
+    program_id: &str,
+    keys: Vec<String>,
+    payment_mint: &str,
+    buyer_key: String,
+    seller_key: String,
+    nonce: u64,
+    buyer_nft_account: Option<String>,
+) -> Result<JsInstruction, JsError> {
+    let accounts = settle_accounts(&eight(&keys)?, payment_mint)?;
+    let buyer_nft_account = optional_key(buyer_nft_account)?;
+    let instruction =
+        instructions::complete_offer(&key(program_id)?, &accounts, buyer_key, seller_key, nonce, buyer_nft_account.as_ref())
+            .map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+/// `seller_nft_account` gets back the NFT of an NFT offer.
+#[wasm_bindgen(js_name = cancelOffer)]
+pub fn cancel_offer(
+    program_id: &str,
+    offer: &str,
+    seller: &str,
+    seller_account: &str,
+    escrow_account: &str,
+    seller_nft_account: Option<String>,
+) -> Result<JsInstruction, JsError> {
+    let accounts = lister_escrow_accounts(offer, seller, seller_account, escrow_account)?;
+    let seller_nft_account = optional_key(seller_nft_account)?;
+    Ok(JsInstruction(instructions::cancel_offer(&key(program_id)?, &accounts, seller_nft_account.as_ref()).map_err(decode_error)?))
+}
+
+// REQUESTS
+
+#[allow(clippy::too_many_arguments)]
+#[wasm_bindgen(js_name = listRequest)]
+pub fn list_request(
+    program_id: &str,
+    request: &str,
+    buyer_user_account: &str,
+    buyer: &str,
+    buyer_account: &str,
+    escrow_account: &str,
+    id: u64,
+    name: String,
+    description: String,
+    payment: u64,
+    country: String,
+    town: String,
+    address: String,
+    meeting_datetime: i64,
+    geohash: Option<String>,
+    meeting_point_hash: Option<Vec<u8>>,
+    region_rent_payer: Option<String>,
+) -> Result<JsInstruction, JsError> {
+    let accounts = list_accounts(&[request, buyer_user_account, buyer, buyer_account, escrow_account], region_rent_payer)?;
+    let meeting_point = location(&country, town, address, geohash)?;
+    let instruction = instructions::list_request(
+        &key(program_id)?, &accounts, id, name, description, payment, meeting_point, meeting_datetime,
+        meeting_point_hash.as_deref().map(hash).transpose()?,
+    ).map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+#[wasm_bindgen(js_name = acceptRequest)]
+pub fn accept_request(
+    program_id: &str,
+    request: &str,
+    seller_user_account: &str,
+    seller: &str,
+    seller_account: &str,
+    seller_authority: &str,
+    escrow_account: &str,
+) -> Result<JsInstruction, JsError> {
+    let accounts = accept_accounts(&[request, seller_user_account, seller, seller_account, seller_authority, escrow_account])?;
+    Ok(JsInstruction(instructions::accept_request(&key(program_id)?, &accounts).map_err(decode_error)?))
+}
+
+/// `keys` are laid out as for `completeOffer`.
+#[wasm_bindgen(js_name = completeRequest)]
+pub fn complete_request(
+    program_id: &str,
+    keys: Vec<String>,
+    payment_mint: &str,
+    buyer_key: String,
+    seller_key: String,
+    nonce: u64,
+) -> Result<JsInstruction, JsError> {
+    let accounts = settle_accounts(&eight(&keys)?, payment_mint)?;
+    Ok(JsInstruction(instructions::complete_request(&key(program_id)?, &accounts, buyer_key, seller_key, nonce).map_err(decode_error)?))
+}
+
+#[wasm_bindgen(js_name = cancelRequest)]
+pub fn cancel_request(program_id: &str, request: &str, buyer: &str, buyer_account: &str, escrow_account: &str) -> Result<JsInstruction, JsError> {
+    let accounts = lister_escrow_accounts(request, buyer, buyer_account, escrow_account)?;
+    Ok(JsInstruction(instructions::cancel_request(&key(program_id)?, &accounts).map_err(decode_error)?))
+}
+
+#[allow(clippy::too_many_arguments)]
+#[wasm_bindgen(js_name = submitQuote)]
+pub fn submit_quote(
+    program_id: &str,
+    request: &str,
+    seller_user_account: &str,
+    seller: &str,
+    seller_account: &str,
+    escrow_account: &str,
+    price: u64,
+    note: String,
+) -> Result<JsInstruction, JsError> {
+    let instruction = instructions::submit_quote(
+        &key(program_id)?, &key(request)?, &key(seller_user_account)?, &key(seller)?, &key(seller_account)?,
+        &key(escrow_account)?, price, note,
+    ).map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+#[allow(clippy::too_many_arguments)]
+#[wasm_bindgen(js_name = selectQuote)]
+pub fn select_quote(
+    program_id: &str,
+    request: &str,
+    buyer: &str,
+    buyer_account: &str,
+    escrow_account: &str,
+    seller_user_account: &str,
+    seller: &str,
+) -> Result<JsInstruction, JsError> {
+    let accounts = lister_escrow_accounts(request, buyer, buyer_account, escrow_account)?;
+    let instruction = instructions::select_quote(&key(program_id)?, &accounts, &key(seller_user_account)?, &key(seller)?)
+        .map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+// MEETING POINTS
+
+/// Hash to list a deal with so its exact meeting point stays hidden. Keep the point and
+/// the `salt`, which should be 32 random bytes, to reveal it once the deal is accepted.
+#[wasm_bindgen(js_name = meetingPointCommitment)]
+pub fn meeting_point_commitment(
+    country: String,
+    town: String,
+    address: String,
+    geohash: Option<String>,
+    salt: &[u8],
+) -> Result<Vec<u8>, JsError> {
+    let meeting_point = location(&country, town, address, geohash)?;
+    Ok(meeting_point.commitment(&hash(salt)?).map_err(decode_error)?.to_vec())
+}
+
+#[allow(clippy::too_many_arguments)]
+#[wasm_bindgen(js_name = revealMeetingPoint)]
+pub fn reveal_meeting_point(
+    program_id: &str,
+    entity_type_name: &str,
+    deal: &str,
+    lister: &str,
+    country: String,
+    town: String,
+    address: String,
+    geohash: Option<String>,
+    salt: &[u8],
+) -> Result<JsInstruction, JsError> {
+    let meeting_point = location(&country, town, address, geohash)?;
+    let instruction = instructions::reveal_meeting_point(
+        &key(program_id)?,
+        entity_type(entity_type_name)?,
+        &key(deal)?,
+        &key(lister)?,
+        meeting_point,
+        hash(salt)?,
+    ).map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+// SHIPMENTS
+
+/// `points` are the pickup country, town, and address followed by the drop-off ones.
+#[allow(clippy::too_many_arguments)]
+#[wasm_bindgen(js_name = listShipment)]
+pub fn list_shipment(
+    program_id: &str,
+    shipment: &str,
+    sender_user_account: &str,
+    sender: &str,
+    sender_account: &str,
+    escrow_account: &str,
+    recipient_user_account: &str,
+    id: u64,
+    items_name: String,
+    quantity: u32,
+    payment: u64,
+    insurance: u64,
+    points: Vec<String>,
+    pickup_datetime: i64,
+    drop_off_datetime: i64,
+    pickup_geohash: Option<String>,
+    region_rent_payer: Option<String>,
+) -> Result<JsInstruction, JsError> {
+    let [pickup_country, pickup_town, pickup_address, drop_off_country, drop_off_town, drop_off_address]: [String; 6] =
+        points.try_into().map_err(|_| JsError::new("Expected Six Location Parts"))?;
+    let accounts = list_accounts(&[shipment, sender_user_account, sender, sender_account, escrow_account], region_rent_payer)?;
+    let instruction = instructions::list_shipment(
+        &key(program_id)?,
+        &accounts,
+        &key(recipient_user_account)?,
+        id,
+        items_name,
+        quantity,
+        payment,
+        insurance,
+        location(&pickup_country, pickup_town, pickup_address, pickup_geohash)?,
+        pickup_datetime,
+        location(&drop_off_country, drop_off_town, drop_off_address, None)?,
+        drop_off_datetime,
+    ).map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+#[allow(clippy::too_many_arguments)]
+#[wasm_bindgen(js_name = acceptShipment)]
+pub fn accept_shipment(
+    program_id: &str,
+    shipment: &str,
+    carrier_user_account: &str,
+    carrier: &str,
+    carrier_account: &str,
+    carrier_authority: &str,
+    escrow_account: &str,
+    staked: bool,
+) -> Result<JsInstruction, JsError> {
+    let accounts = accept_accounts(&[shipment, carrier_user_account, carrier, carrier_account, carrier_authority, escrow_account])?;
+    Ok(JsInstruction(instructions::accept_shipment(&key(program_id)?, &accounts, staked).map_err(decode_error)?))
+}
+
+/// `keys` are laid out as for `completeOffer`, with the sender as seller and the carrier as buyer.
+#[wasm_bindgen(js_name = completeShipment)]
+pub fn complete_shipment(
+    program_id: &str,
+    keys: Vec<String>,
+    payment_mint: &str,
+    carrier_key: String,
+    recipient_key: String,
+    proof_hash: Option<Vec<u8>>,
+    nonce: u64,
+) -> Result<JsInstruction, JsError> {
+    let accounts = settle_accounts(&eight(&keys)?, payment_mint)?;
+    let proof_hash = proof_hash.as_deref().map(hash).transpose()?;
+    let instruction = instructions::complete_shipment(&key(program_id)?, &accounts, carrier_key, recipient_key, proof_hash, nonce)
+        .map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+#[wasm_bindgen(js_name = cancelShipment)]
+pub fn cancel_shipment(program_id: &str, shipment: &str, sender: &str, sender_account: &str, escrow_account: &str) -> Result<JsInstruction, JsError> {
+    let accounts = lister_escrow_accounts(shipment, sender, sender_account, escrow_account)?;
+    Ok(JsInstruction(instructions::cancel_shipment(&key(program_id)?, &accounts).map_err(decode_error)?))
+}
+
+/// `session` is the carrier's session key, when it signs instead of the carrier.
+#[wasm_bindgen(js_name = confirmPickup)]
+pub fn confirm_pickup(
+    program_id: &str,
+    shipment: &str,
+    carrier: &str,
+    session: Option<String>,
+    sender_key: String,
+) -> Result<JsInstruction, JsError> {
+    let instruction = instructions::confirm_pickup(
+        &key(program_id)?,
+        &key(shipment)?,
+        &key(carrier)?,
+        optional_key(session)?.as_ref(),
+        sender_key,
+    ).map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+/// `drop_off_geohash` is the geohash of the original shipment's drop-off point, where the
+/// return is picked up, if it has one.
+#[allow(clippy::too_many_arguments)]
+#[wasm_bindgen(js_name = createReturnShipment)]
+pub fn create_return_shipment(
+    program_id: &str,
+    return_shipment: &str,
+    original: &str,
+    recipient_user_account: &str,
+    recipient: &str,
+    recipient_account: &str,
+    escrow_account: &str,
+    sender_user_account: &str,
+    id: u64,
+    payment: u64,
+    pickup_datetime: i64,
+    drop_off_datetime: i64,
+    drop_off_geohash: Option<String>,
+    region_rent_payer: Option<String>,
+) -> Result<JsInstruction, JsError> {
+    let accounts =
+        list_accounts(&[return_shipment, recipient_user_account, recipient, recipient_account, escrow_account], region_rent_payer)?;
+    let instruction = instructions::create_return_shipment(
+        &key(program_id)?,
+        &accounts,
+        &key(original)?,
+        &key(sender_user_account)?,
+        id,
+        payment,
+        pickup_datetime,
+        drop_off_datetime,
+        drop_off_geohash.as_deref().and_then(region_prefix),
+    ).map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+#[wasm_bindgen(js_name = submitProof)]
+pub fn submit_proof(program_id: &str, shipment: &str, carrier: &str, proof_hash: &[u8]) -> Result<JsInstruction, JsError> {
+    let instruction = instructions::submit_proof(&key(program_id)?, &key(shipment)?, &key(carrier)?, hash(proof_hash)?)
+        .map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+// MESSAGING
+
+/// `payload` must already be encrypted to the counterparty's key.
+#[wasm_bindgen(js_name = sendMessage)]
+pub fn send_message(
+    program_id: &str,
+    entity_type_name: &str,
+    entity: &str,
+    sender: &str,
+    payload: Vec<u8>,
+    rent_payer: Option<String>,
+) -> Result<JsInstruction, JsError> {
+    let rent_payer = optional_key(rent_payer)?;
+    let instruction = instructions::send_message(
+        &key(program_id)?,
+        entity_type(entity_type_name)?,
+        &key(entity)?,
+        &key(sender)?,
+        payload,
+        rent_payer.as_ref(),
+    ).map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+// HOLDBACKS
+
+#[wasm_bindgen(js_name = releaseHoldback)]
+pub fn release_holdback(
+    program_id: &str,
+    offer: &str,
+    seller: &str,
+    seller_account: &str,
+    escrow_account: &str,
+) -> Result<JsInstruction, JsError> {
+    let instruction = instructions::release_holdback(
+        &key(program_id)?,
+        &key(offer)?,
+        &key(seller)?,
+        &key(seller_account)?,
+        &key(escrow_account)?,
+    ).map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+#[wasm_bindgen(js_name = disputeHoldback)]
+pub fn dispute_holdback(program_id: &str, offer: &str, buyer: &str) -> Result<JsInstruction, JsError> {
+    Ok(JsInstruction(instructions::dispute_holdback(&key(program_id)?, &key(offer)?, &key(buyer)?).map_err(decode_error)?))
+}
+
+// CHARGEBACKS
+
+#[wasm_bindgen(js_name = openChargeback)]
+pub fn open_chargeback(program_id: &str, offer: &str, buyer: &str) -> Result<JsInstruction, JsError> {
+    Ok(JsInstruction(instructions::open_chargeback(&key(program_id)?, &key(offer)?, &key(buyer)?).map_err(decode_error)?))
+}
+
+#[wasm_bindgen(js_name = releaseSellerInsurance)]
+pub fn release_seller_insurance(
+    program_id: &str,
+    offer: &str,
+    seller: &str,
+    seller_account: &str,
+    escrow_account: &str,
+) -> Result<JsInstruction, JsError> {
+    let instruction = instructions::release_seller_insurance(
+        &key(program_id)?,
+        &key(offer)?,
+        &key(seller)?,
+        &key(seller_account)?,
+        &key(escrow_account)?,
+    ).map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+// ARBITRATION
+
+#[wasm_bindgen(js_name = registerArbiter)]
+pub fn register_arbiter(
+    program_id: &str,
+    user_account: &str,
+    owner: &str,
+    owner_account: &str,
+    escrow_account: &str,
+    amount: u64,
+    rent_payer: Option<String>,
+) -> Result<JsInstruction, JsError> {
+    let rent_payer = optional_key(rent_payer)?;
+    let instruction = instructions::register_arbiter(
+        &key(program_id)?,
+        &key(user_account)?,
+        &key(owner)?,
+        &key(owner_account)?,
+        &key(escrow_account)?,
+        amount,
+        rent_payer.as_ref(),
+    ).map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+/// `kind` is `"holdback"` or `"chargeback"`.
+#[wasm_bindgen(js_name = assignArbiter)]
+pub fn assign_arbiter(program_id: &str, offer: &str, kind: &str, rent_payer: &str) -> Result<JsInstruction, JsError> {
+    let instruction = instructions::assign_arbiter(&key(program_id)?, &key(offer)?, dispute_kind(kind)?, &key(rent_payer)?);
+    Ok(JsInstruction(instruction.map_err(decode_error)?))
+}
+
+#[wasm_bindgen(js_name = ruleDispute)]
+pub fn rule_dispute(
+    program_id: &str,
+    arbiter: &str,
+    offer: &str,
+    arbiter_account: &str,
+    payment_mint: &str,
+    kind: &str,
+    for_buyer: bool,
+) -> Result<JsInstruction, JsError> {
+    let instruction = instructions::rule_dispute(
+        &key(program_id)?,
+        &key(arbiter)?,
+        &key(offer)?,
+        &key(arbiter_account)?,
+        &key(payment_mint)?,
+        dispute_kind(kind)?,
+        for_buyer,
+    ).map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+// APPEALS
+
+#[wasm_bindgen(js_name = appealRuling)]
+pub fn appeal_ruling(
+    program_id: &str,
+    offer: &str,
+    appellant: &str,
+    appellant_account: &str,
+    escrow_account: &str,
+    kind: &str,
+    rent_payer: Option<String>,
+) -> Result<JsInstruction, JsError> {
+    let rent_payer = optional_key(rent_payer)?;
+    let instruction = instructions::appeal_ruling(
+        &key(program_id)?,
+        &key(offer)?,
+        &key(appellant)?,
+        &key(appellant_account)?,
+        &key(escrow_account)?,
+        dispute_kind(kind)?,
+        rent_payer.as_ref(),
+    ).map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+#[wasm_bindgen(js_name = voteAppeal)]
+pub fn vote_appeal(program_id: &str, offer: &str, panelist: &str, kind: &str, for_buyer: bool) -> Result<JsInstruction, JsError> {
+    let instruction = instructions::vote_appeal(&key(program_id)?, &key(offer)?, &key(panelist)?, dispute_kind(kind)?, for_buyer)
+        .map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+/// `appellant_account` and `panelist_accounts`, an array of base58 keys, are only read
+/// when the ruling was appealed.
+#[allow(clippy::too_many_arguments)]
+#[wasm_bindgen(js_name = settleDispute)]
+pub fn settle_dispute(
+    program_id: &str,
+    offer: &str,
+    escrow_account: &str,
+    recipient_account: &str,
+    kind: &str,
+    appellant_account: Option<String>,
+    panelist_accounts: Array,
+) -> Result<JsInstruction, JsError> {
+    let appeal = match optional_key(appellant_account)? {
+        Some(appellant_account) => {
+            let panelist_accounts = key_array(&panelist_accounts)?;
+            Some(instructions::AppealPayoutAccounts { appellant_account, panelist_accounts })
+        }
+        None => None,
+    };
+    let instruction = instructions::settle_dispute(
+        &key(program_id)?,
+        &key(offer)?,
+        &key(escrow_account)?,
+        &key(recipient_account)?,
+        dispute_kind(kind)?,
+        appeal.as_ref(),
+    ).map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+// SETTLEMENTS
+
+/// Both `seller` and `buyer` sign the settlement unless an `arbiter` is given.
+#[allow(clippy::too_many_arguments)]
+#[wasm_bindgen(js_name = settleDeal)]
+pub fn settle_deal(
+    program_id: &str,
+    offer: &str,
+    escrow_account: &str,
+    seller_account: &str,
+    buyer_account: &str,
+    seller: &str,
+    buyer: &str,
+    arbiter: Option<String>,
+    seller_bps: u16,
+    buyer_bps: u16,
+    nonce: u64,
+    nft_recipient_account: Option<String>,
+) -> Result<JsInstruction, JsError> {
+    let signers = match optional_key(arbiter)? {
+        Some(arbiter) => instructions::SettlementSigners::Arbiter(arbiter),
+        None => instructions::SettlementSigners::Mutual { seller: key(seller)?, buyer: key(buyer)? },
+    };
+    let instruction = instructions::settle_deal(
+        &key(program_id)?,
+        &key(offer)?,
+        &key(escrow_account)?,
+        &key(seller_account)?,
+        &key(buyer_account)?,
+        &signers,
+        seller_bps,
+        buyer_bps,
+        nonce,
+        optional_key(nft_recipient_account)?.as_ref(),
+    ).map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+// SESSION KEYS
+
+/// `entities` is an array of base58 keys of the deals and shipments the session key may
+/// act on.
+#[wasm_bindgen(js_name = createSession)]
+pub fn create_session(
+    program_id: &str,
+    owner: &str,
+    session: &str,
+    entities: Array,
+    expires_at: i64,
+    rent_payer: Option<String>,
+) -> Result<JsInstruction, JsError> {
+    let entities = key_array(&entities)?;
+    let rent_payer = optional_key(rent_payer)?;
+    let instruction =
+        instructions::create_session(&key(program_id)?, &key(owner)?, &key(session)?, entities, expires_at, rent_payer.as_ref())
+            .map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+#[wasm_bindgen(js_name = revokeSession)]
+pub fn revoke_session(program_id: &str, owner: &str, session: &str) -> Result<JsInstruction, JsError> {
+    Ok(JsInstruction(instructions::revoke_session(&key(program_id)?, &key(owner)?, &key(session)?).map_err(decode_error)?))
+}
+
+// ORGANIZATIONS
+
+#[wasm_bindgen(js_name = createOrganization)]
+pub fn create_organization(program_id: &str, owner: &str, rent_payer: Option<String>) -> Result<JsInstruction, JsError> {
+    let rent_payer = optional_key(rent_payer)?;
+    let instruction = instructions::create_organization(&key(program_id)?, &key(owner)?, rent_payer.as_ref()).map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+#[wasm_bindgen(js_name = setMember)]
+pub fn set_member(program_id: &str, owner: &str, member: &str, roles: u8) -> Result<JsInstruction, JsError> {
+    Ok(JsInstruction(instructions::set_member(&key(program_id)?, &key(owner)?, &key(member)?, roles).map_err(decode_error)?))
+}
+
+/// Has a `member` of `party`'s organization sign an instruction built for `party`.
+#[wasm_bindgen(js_name = asMember)]
+pub fn as_member(program_id: &str, instruction: JsInstruction, party: &str, member: &str) -> Result<JsInstruction, JsError> {
+    Ok(JsInstruction(instructions::as_member(&key(program_id)?, instruction.0, &key(party)?, &key(member)?)))
+}
+
+// MULTISIGS
+
+/// `signers` is an array of base58 keys.
+#[wasm_bindgen(js_name = createMultisig)]
+pub fn create_multisig(
+    program_id: &str,
+    user_account: &str,
+    owner: &str,
+    threshold: u8,
+    signers: Array,
+    high_value: u64,
+    rent_payer: Option<String>,
+) -> Result<JsInstruction, JsError> {
+    let rent_payer = optional_key(rent_payer)?;
+    Ok(JsInstruction(instructions::create_multisig(
+        &key(program_id)?,
+        &key(user_account)?,
+        &key(owner)?,
+        threshold,
+        key_array(&signers)?,
+        high_value,
+        rent_payer.as_ref(),
+    ).map_err(decode_error)?))
+}
+
+/// `signers` and `approvers` are arrays of base58 keys; the approvers reach the current
+/// threshold.
+#[wasm_bindgen(js_name = updateMultisig)]
+pub fn update_multisig(
+    program_id: &str,
+    user_account: &str,
+    owner: &str,
+    threshold: u8,
+    signers: Array,
+    high_value: u64,
+    approvers: Array,
+) -> Result<JsInstruction, JsError> {
+    Ok(JsInstruction(instructions::update_multisig(
+        &key(program_id)?,
+        &key(user_account)?,
+        &key(owner)?,
+        threshold,
+        key_array(&signers)?,
+        high_value,
+        &key_array(&approvers)?,
+    ).map_err(decode_error)?))
+}
+
+/// Passes the multisig governing `party` and the `cosigners`, an array of base58 keys, last
+/// on a listing built for `party`.
+#[wasm_bindgen(js_name = withMultisig)]
+pub fn with_multisig(program_id: &str, instruction: JsInstruction, party: &str, cosigners: Array) -> Result<JsInstruction, JsError> {
+    let cosigners = key_array(&cosigners)?;
+    Ok(JsInstruction(instructions::with_multisig(&key(program_id)?, instruction.0, &key(party)?, &cosigners)))
+}
+
+// KYC
+
+#[wasm_bindgen]
+pub fn attest(
+    program_id: &str,
+    attestor: &str,
+    user_account: &str,
+    owner: &str,
+    expires_at: i64,
+    rent_payer: Option<String>,
+) -> Result<JsInstruction, JsError> {
+    let rent_payer = optional_key(rent_payer)?;
+    let instruction =
+        instructions::attest(&key(program_id)?, &key(attestor)?, &key(user_account)?, &key(owner)?, expires_at, rent_payer.as_ref())
+            .map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+/// Passes the attestations of `parties`, an array of base58 keys, last on an acceptance
+/// paying more than the KYC threshold.
+#[wasm_bindgen(js_name = withAttestations)]
+pub fn with_attestations(program_id: &str, instruction: JsInstruction, parties: Array) -> Result<JsInstruction, JsError> {
+    let parties = key_array(&parties)?;
+    Ok(JsInstruction(instructions::with_attestations(&key(program_id)?, instruction.0, &parties)))
+}
+
+// REFERRALS
+
+#[wasm_bindgen(js_name = claimReferralRewards)]
+pub fn claim_referral_rewards(program_id: &str, referrer: &str, referrer_account: &str, mint: &str) -> Result<JsInstruction, JsError> {
+    let instruction = instructions::claim_referral_rewards(&key(program_id)?, &key(referrer)?, &key(referrer_account)?, &key(mint)?)
+        .map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+/// Passes the rewards account of `referrer` in `mint` last on a completion whose payee
+/// they referred, when the completion collects a fee. `rentPayer` signs and funds the
+/// account on the first credit.
+#[wasm_bindgen(js_name = withReferralRewards)]
+pub fn with_referral_rewards(
+    program_id: &str,
+    instruction: JsInstruction,
+    referrer: &str,
+    mint: &str,
+    rent_payer: &str,
+) -> Result<JsInstruction, JsError> {
+    let instruction = instructions::with_referral_rewards(&key(program_id)?, instruction.0, &key(referrer)?, &key(mint)?, &key(rent_payer)?);
+    Ok(JsInstruction(instruction))
+}
+
+// PROMO CODES
+
+/// Hashes `code` the way `setPromoCode` expects for `offer`.
+#[wasm_bindgen(js_name = promoCodeHash)]
+pub fn promo_code_hash(offer: &str, code: &str) -> Result<Vec<u8>, JsError> {
+    Ok(promo::promo_code_hash(&key(offer)?, code).to_vec())
+}
+
+#[wasm_bindgen(js_name = setPromoCode)]
+pub fn set_promo_code(
+    program_id: &str,
+    offer: &str,
+    seller: &str,
+    code_hash: Vec<u8>,
+    discount_percent: u8,
+    uses: u32,
+    rent_payer: Option<String>,
+) -> Result<JsInstruction, JsError> {
+    let code_hash = hash(&code_hash)?;
+    let rent_payer = optional_key(rent_payer)?;
+    let instruction = instructions::set_promo_code(
+        &key(program_id)?,
+        &key(offer)?,
+        &key(seller)?,
+        code_hash,
+        discount_percent,
+        uses,
+        rent_payer.as_ref(),
+    ).map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+/// Presents the promo `code` on an `acceptOffer` or `depositAndAccept` of `offer`.
+#[wasm_bindgen(js_name = withPromoCode)]
+pub fn with_promo_code(program_id: &str, instruction: JsInstruction, offer: &str, code: String) -> Result<JsInstruction, JsError> {
+    Ok(JsInstruction(instructions::with_promo_code(&key(program_id)?, instruction.0, &key(offer)?, code).map_err(decode_error)?))
+}
+
+// SCHEDULED LISTINGS
+
+/// `entity_type_name` is `offer` or `request`.
+#[wasm_bindgen(js_name = scheduleListing)]
+pub fn schedule_listing(
+    program_id: &str,
+    entity_type_name: &str,
+    entity: &str,
+    lister: &str,
+    activate_at: i64,
+    delist_at: Option<i64>,
+) -> Result<JsInstruction, JsError> {
+    let entity_type = entity_type(entity_type_name)?;
+    let instruction = instructions::schedule_listing(&key(program_id)?, entity_type, &key(entity)?, &key(lister)?, activate_at, delist_at)
+        .map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+// STREAMING PAYMENTS
+
+#[allow(clippy::too_many_arguments)]
+#[wasm_bindgen(js_name = withdrawStream)]
+pub fn withdraw_stream(
+    program_id: &str,
+    offer: &str,
+    seller_user_account: &str,
+    seller: &str,
+    seller_account: &str,
+    escrow_account: &str,
+    payment_mint: &str,
+) -> Result<JsInstruction, JsError> {
+    let instruction = instructions::withdraw_stream(
+        &key(program_id)?,
+        &key(offer)?,
+        &key(seller_user_account)?,
+        &key(seller)?,
+        &key(seller_account)?,
+        &key(escrow_account)?,
+        &key(payment_mint)?,
+    ).map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+#[wasm_bindgen(js_name = stopStream)]
+pub fn stop_stream(program_id: &str, offer: &str, party: &str) -> Result<JsInstruction, JsError> {
+    Ok(JsInstruction(instructions::stop_stream(&key(program_id)?, &key(offer)?, &key(party)?).map_err(decode_error)?))
+}
+
+// WATCHLISTS
+
+#[wasm_bindgen(js_name = followUser)]
+pub fn follow_user(program_id: &str, follower: &str, user: &str, follow: bool, rent_payer: Option<String>) -> Result<JsInstruction, JsError> {
+    let rent_payer = optional_key(rent_payer)?;
+    let instruction =
+        instructions::follow_user(&key(program_id)?, &key(follower)?, &key(user)?, follow, rent_payer.as_ref()).map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+#[wasm_bindgen(js_name = watchOffer)]
+pub fn watch_offer(program_id: &str, watcher: &str, offer: &str, watch: bool, rent_payer: Option<String>) -> Result<JsInstruction, JsError> {
+    let rent_payer = optional_key(rent_payer)?;
+    let instruction =
+        instructions::watch_offer(&key(program_id)?, &key(watcher)?, &key(offer)?, watch, rent_payer.as_ref()).map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+// LEADERBOARDS
+
+/// Passes the leaderboards of `role`, "seller" or "carrier", last on a completion, or a
+/// shipment fail, to rank the seller or carrier. `rent_payer` funds a leaderboard's first
+/// ranking.
+#[wasm_bindgen(js_name = withLeaderboards)]
+pub fn with_leaderboards(program_id: &str, instruction: JsInstruction, role: &str, rent_payer: &str) -> Result<JsInstruction, JsError> {
+    let kinds = match role {
+        "seller" => SELLER_LEADERBOARDS,
+        "carrier" => CARRIER_LEADERBOARDS,
+        _ => return Err(JsError::new(&format!("Invalid Leaderboard Role: {}", role))),
+    };
+    Ok(JsInstruction(instructions::with_leaderboards(&key(program_id)?, instruction.0, &kinds, &key(rent_payer)?)))
+}
+
+// COMPRESSED LISTINGS
+
+#[wasm_bindgen(js_name = createListingTree)]
+pub fn create_listing_tree(program_id: &str, tree: &str, max_depth: u32, max_buffer_size: u32) -> Result<JsInstruction, JsError> {
+    Ok(JsInstruction(instructions::create_listing_tree(&key(program_id)?, &key(tree)?, max_depth, max_buffer_size).map_err(decode_error)?))
+}
+
+#[allow(clippy::too_many_arguments)]
+#[wasm_bindgen(js_name = listCompressedOffer)]
+pub fn list_compressed_offer(
+    program_id: &str,
+    tree: &str,
+    seller_user_account: &str,
+    seller: &str,
+    seller_account: &str,
+    escrow_account: &str,
+    id: u64,
+    name: String,
+    description: String,
+    payment: u64,
+    country: String,
+    town: String,
+    address: String,
+    meeting_datetime: i64,
+    geohash: Option<String>,
+) -> Result<JsInstruction, JsError> {
+    let accounts = list_accounts(&[tree, seller_user_account, seller, seller_account, escrow_account], None)?;
+    let meeting_point = location(&country, town, address, geohash)?;
+    let instruction = instructions::list_compressed_offer(
+        &key(program_id)?, &accounts, id, name, description, payment, meeting_point, meeting_datetime,
+    ).map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+/// `listing` is the Borsh-encoded listing of its `CompressedOfferListed` event; `root`
+/// and the `proof` nodes, from the leaf up, are base58 as tree indexers return them.
+#[allow(clippy::too_many_arguments)]
+#[wasm_bindgen(js_name = acceptCompressedOffer)]
+pub fn accept_compressed_offer(
+    program_id: &str,
+    tree: &str,
+    offer: &str,
+    seller_user_account: &str,
+    buyer_user_account: &str,
+    buyer: &str,
+    buyer_account: &str,
+    buyer_authority: &str,
+    escrow_account: &str,
+    listing: &[u8],
+    root: &str,
+    index: u32,
+    proof: Vec<String>,
+) -> Result<JsInstruction, JsError> {
+    let accounts = accept_accounts(&[offer, buyer_user_account, buyer, buyer_account, buyer_authority, escrow_account])?;
+    let listing = CompressedOffer::deserialize(&mut &listing[..]).map_err(decode_error)?;
+    let proof = proof_nodes(&proof)?;
+    let instruction = instructions::accept_compressed_offer(
+        &key(program_id)?, &key(tree)?, &accounts, &key(seller_user_account)?, listing, key(root)?.to_bytes(), index, &proof,
+    ).map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+/// See `acceptCompressedOffer` for `listing`, `root`, and `proof`.
+#[allow(clippy::too_many_arguments)]
+#[wasm_bindgen(js_name = cancelCompressedOffer)]
+pub fn cancel_compressed_offer(
+    program_id: &str,
+    tree: &str,
+    seller_user_account: &str,
+    seller: &str,
+    seller_account: &str,
+    escrow_account: &str,
+    listing: &[u8],
+    root: &str,
+    index: u32,
+    proof: Vec<String>,
+) -> Result<JsInstruction, JsError> {
+    let mut accounts = lister_escrow_accounts(tree, seller, seller_account, escrow_account)?;
+    // The listing's seller is checked against the user account.
+    accounts.lister_user_account = key(seller_user_account)?;
+    let listing = CompressedOffer::deserialize(&mut &listing[..]).map_err(decode_error)?;
+    let proof = proof_nodes(&proof)?;
+    let instruction = instructions::cancel_compressed_offer(&key(program_id)?, &accounts, listing, key(root)?.to_bytes(), index, &proof)
+        .map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+// ARCHIVES
+
+#[wasm_bindgen(js_name = archiveBatch)]
+pub fn archive_batch(
+    program_id: &str,
+    lister: &str,
+    entity_type_name: &str,
+    entities: Vec<String>,
+    rent_payer: Option<String>,
+) -> Result<JsInstruction, JsError> {
+    let entities = entities.iter().map(|entity| key(entity)).collect::<Result<Vec<_>, JsError>>()?;
+    let rent_payer = optional_key(rent_payer)?;
+    let instruction =
+        instructions::archive_batch(&key(program_id)?, &key(lister)?, entity_type(entity_type_name)?, &entities, rent_payer.as_ref())
+            .map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+/// `data` is the entity's account data as it was archived, and the `proof` nodes are
+/// base58, from the leaf up.
+#[wasm_bindgen(js_name = verifyArchivedEntity)]
+pub fn verify_archived_entity(
+    program_id: &str,
+    lister: &str,
+    batch: u32,
+    entity: &str,
+    data: &[u8],
+    index: u32,
+    proof: Vec<String>,
+) -> Result<JsInstruction, JsError> {
+    let proof = proof_nodes(&proof)?;
+    let instruction = instructions::verify_archived_entity(&key(program_id)?, &key(lister)?, batch, &key(entity)?, data, index, proof)
+        .map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+// ONE-TIME KEYS
+
+/// The key is hashed before it goes into the instruction.
+#[wasm_bindgen(js_name = commitKey)]
+pub fn commit_key(
+    program_id: &str,
+    entity_type_name: &str,
+    entity: &str,
+    party: &str,
+    one_time_key: &str,
+    nonce: u64,
+    rent_payer: Option<String>,
+) -> Result<JsInstruction, JsError> {
+    let rent_payer = optional_key(rent_payer)?;
+    let instruction = instructions::commit_key(
+        &key(program_id)?,
+        entity_type(entity_type_name)?,
+        &key(entity)?,
+        &key(party)?,
+        one_time_key,
+        nonce,
+        rent_payer.as_ref(),
+    ).map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+/// Both keys are hashed before they go into the instruction.
+#[allow(clippy::too_many_arguments)]
+#[wasm_bindgen(js_name = regenerateKeys)]
+pub fn regenerate_keys(
+    program_id: &str,
+    entity_type_name: &str,
+    deal: &str,
+    seller: &str,
+    buyer: &str,
+    seller_key: &str,
+    buyer_key: &str,
+    nonce: u64,
+    rent_payer: Option<String>,
+) -> Result<JsInstruction, JsError> {
+    let rent_payer = optional_key(rent_payer)?;
+    let instruction = instructions::regenerate_keys(
+        &key(program_id)?,
+        entity_type(entity_type_name)?,
+        &key(deal)?,
+        &key(seller)?,
+        &key(buyer)?,
+        seller_key,
+        buyer_key,
+        nonce,
+        rent_payer.as_ref(),
+    ).map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+/// Bytes the party's wallet signs, with `signMessage`, to derive their one-time keys.
+#[wasm_bindgen(js_name = keyDerivationMessage)]
+pub fn key_derivation_message() -> Vec<u8> {
+    keys::KEY_DERIVATION_MESSAGE.to_vec()
+}
+
+/// The party's one-time key for the deal or shipment with `entity_id`, derived from their
+/// 64-byte signature of the key derivation message and the nonce it is committed with.
+#[wasm_bindgen(js_name = deriveOneTimeKey)]
+pub fn derive_one_time_key(signature: &[u8], entity_id: u64, party: &str, nonce: u64) -> Result<String, JsError> {
+    let signature = signature.try_into().map_err(|_| JsError::new("Signatures Must Be 64 Bytes"))?;
+    Ok(keys::derive_key(signature, entity_id, &key(party)?, nonce))
+}
+
+/// Nonce of the next key a party commits, read from the KeyManager account data.
+#[wasm_bindgen(js_name = nextKeyNonce)]
+pub fn next_key_nonce(key_manager_data: &[u8], party: &str) -> Result<u64, JsError> {
+    let key_manager = accounts::decode_key_manager(key_manager_data).map_err(decode_error)?;
+    Ok(keys::next_nonce(&key_manager, &key(party)?))
+}
+
+/// The party's short key code, derived like `deriveOneTimeKey`, in the form to commit.
+#[wasm_bindgen(js_name = deriveKeyCode)]
+pub fn derive_key_code(signature: &[u8], entity_id: u64, party: &str, nonce: u64) -> Result<String, JsError> {
+    let signature = signature.try_into().map_err(|_| JsError::new("Signatures Must Be 64 Bytes"))?;
+    Ok(keys::derive_key_code(signature, entity_id, &key(party)?, nonce))
+}
+
+/// A key code split into two groups of five for display.
+#[wasm_bindgen(js_name = formatKeyCode)]
+pub fn format_key_code(code: &str) -> String {
+    keys::format_key_code(code)
+}
+
+/// The canonical form of a typed key code; throws on a typo.
+#[wasm_bindgen(js_name = parseKeyCode)]
+pub fn parse_key_code(typed: &str) -> Result<String, JsError> {
+    keys::parse_key_code(typed).map_err(decode_error)
+}
+
+/// Text of the QR code showing a key code for an offer, request, or shipment.
+#[wasm_bindgen(js_name = keyCodeQr)]
+pub fn key_code_qr(entity: &str, code: &str) -> Result<String, JsError> {
+    Ok(keys::key_code_qr(&key(entity)?, code))
+}
+
+/// The `entity` and canonical `code` of a scanned key code QR.
+#[wasm_bindgen(js_name = parseKeyCodeQr)]
+pub fn parse_key_code_qr(scanned: &str) -> Result<Object, JsError> {
+    let (entity, code) = keys::parse_key_code_qr(scanned).map_err(decode_error)?;
+    let object = Object::new();
+    set(&object, "entity", entity.to_string());
+    set(&object, "code", code);
+    Ok(object)
+}
+
+// STALE LISTINGS
+
+#[wasm_bindgen(js_name = reclaimStaleListing)]
+pub fn reclaim_stale_listing(
+    program_id: &str,
+    entity_type_name: &str,
+    entity: &str,
+    payment_mint: &str,
+    lister_account: &str,
+    seller_nft_account: Option<String>,
+) -> Result<JsInstruction, JsError> {
+    let instruction = instructions::reclaim_stale_listing(
+        &key(program_id)?,
+        entity_type(entity_type_name)?,
+        &key(entity)?,
+        &key(payment_mint)?,
+        &key(lister_account)?,
+        optional_key(seller_nft_account)?.as_ref(),
+    ).map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+// RESCHEDULING
+
+#[wasm_bindgen(js_name = extendDeadline)]
+pub fn extend_deadline(
+    program_id: &str,
+    entity_type_name: &str,
+    entity: &str,
+    seller_or_sender: &str,
+    buyer_or_carrier: &str,
+    deadline: i64,
+) -> Result<JsInstruction, JsError> {
+    let instruction = instructions::extend_deadline(
+        &key(program_id)?,
+        entity_type(entity_type_name)?,
+        &key(entity)?,
+        &key(seller_or_sender)?,
+        &key(buyer_or_carrier)?,
+        deadline,
+    ).map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+// RECONCILIATION
+
+#[wasm_bindgen(js_name = verifyEscrow)]
+pub fn verify_escrow(program_id: &str, entity_type_name: &str, entity: &str, payment_mint: &str) -> Result<JsInstruction, JsError> {
+    let instruction =
+        instructions::verify_escrow(&key(program_id)?, entity_type(entity_type_name)?, &key(entity)?, &key(payment_mint)?)
+            .map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+// EMERGENCY RESOLUTIONS
+
+#[wasm_bindgen(js_name = proposeEmergencyResolve)]
+pub fn propose_emergency_resolve(program_id: &str, governance: &str, entity_type_name: &str, entity: &str) -> Result<JsInstruction, JsError> {
+    let instruction =
+        instructions::propose_emergency_resolve(&key(program_id)?, &key(governance)?, entity_type(entity_type_name)?, &key(entity)?)
+            .map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+#[allow(clippy::too_many_arguments)]
+#[wasm_bindgen(js_name = emergencyResolve)]
+pub fn emergency_resolve(
+    program_id: &str,
+    governance: &str,
+    entity_type_name: &str,
+    entity: &str,
+    payment_mint: &str,
+    seller_or_sender_account: &str,
+    buyer_or_carrier_account: &str,
+    seller_nft_account: Option<String>,
+) -> Result<JsInstruction, JsError> {
+    let instruction = instructions::emergency_resolve(
+        &key(program_id)?,
+        &key(governance)?,
+        entity_type(entity_type_name)?,
+        &key(entity)?,
+        &key(payment_mint)?,
+        &key(seller_or_sender_account)?,
+        &key(buyer_or_carrier_account)?,
+        optional_key(seller_nft_account)?.as_ref(),
+    ).map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+#[wasm_bindgen(js_name = cancelEmergencyResolve)]
+pub fn cancel_emergency_resolve(program_id: &str, governance: &str, entity: &str) -> Result<JsInstruction, JsError> {
+    Ok(JsInstruction(instructions::cancel_emergency_resolve(&key(program_id)?, &key(governance)?, &key(entity)?).map_err(decode_error)?))
+}
+
+// TIMELOCKED GOVERNANCE
+
+/// `action` is the Borsh-encoded `GovernanceAction` to queue.
+#[wasm_bindgen(js_name = queueGovernanceAction)]
+pub fn queue_governance_action(program_id: &str, governance: &str, action: &[u8]) -> Result<JsInstruction, JsError> {
+    let action = GovernanceAction::try_from_slice(action).map_err(|_| decode_error(DLUError::DeserializationFailed))?;
+    Ok(JsInstruction(instructions::queue_governance_action(&key(program_id)?, &key(governance)?, action).map_err(decode_error)?))
+}
+
+#[wasm_bindgen(js_name = cancelGovernanceAction)]
+pub fn cancel_governance_action(program_id: &str, governance: &str, id: u64) -> Result<JsInstruction, JsError> {
+    Ok(JsInstruction(instructions::cancel_governance_action(&key(program_id)?, &key(governance)?, id).map_err(decode_error)?))
+}
+
+#[wasm_bindgen(js_name = executeGovernanceAction)]
+pub fn execute_governance_action(program_id: &str, id: u64) -> Result<JsInstruction, JsError> {
+    Ok(JsInstruction(instructions::execute_governance_action(&key(program_id)?, id).map_err(decode_error)?))
+}
+
+// DAO VOTING
+
+#[wasm_bindgen(js_name = initializeDao)]
+pub fn initialize_dao(
+    program_id: &str,
+    governance: &str,
+    vote_mint: &str,
+    quorum: u64,
+    proposal_threshold: u64,
+    voting_period: i64,
+) -> Result<JsInstruction, JsError> {
+    let instruction =
+        instructions::initialize_dao(&key(program_id)?, &key(governance)?, key(vote_mint)?, quorum, proposal_threshold, voting_period)
+            .map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+#[wasm_bindgen(js_name = lockVotes)]
+pub fn lock_votes(
+    program_id: &str,
+    owner: &str,
+    owner_account: &str,
+    vote_mint: &str,
+    amount: u64,
+    rent_payer: Option<String>,
+) -> Result<JsInstruction, JsError> {
+    let rent_payer = optional_key(rent_payer)?;
+    let instruction = instructions::lock_votes(
+        &key(program_id)?,
+        &key(owner)?,
+        &key(owner_account)?,
+        &key(vote_mint)?,
+        amount,
+        rent_payer.as_ref(),
+    ).map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+#[wasm_bindgen(js_name = unlockVotes)]
+pub fn unlock_votes(program_id: &str, owner: &str, owner_account: &str, vote_mint: &str, amount: u64) -> Result<JsInstruction, JsError> {
+    let instruction = instructions::unlock_votes(&key(program_id)?, &key(owner)?, &key(owner_account)?, &key(vote_mint)?, amount)
+        .map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+/// `action` is the Borsh-encoded `GovernanceAction` to vote on.
+#[wasm_bindgen(js_name = createProposal)]
+pub fn create_proposal(
+    program_id: &str,
+    proposer: &str,
+    proposal_id: u64,
+    action: &[u8],
+    rent_payer: Option<String>,
+) -> Result<JsInstruction, JsError> {
+    let action = GovernanceAction::try_from_slice(action).map_err(|_| decode_error(DLUError::DeserializationFailed))?;
+    let rent_payer = optional_key(rent_payer)?;
+    let instruction = instructions::create_proposal(&key(program_id)?, &key(proposer)?, proposal_id, action, rent_payer.as_ref())
+        .map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+#[wasm_bindgen(js_name = castVote)]
+pub fn cast_vote(program_id: &str, voter: &str, proposal_id: u64, support: bool, rent_payer: Option<String>) -> Result<JsInstruction, JsError> {
+    let rent_payer = optional_key(rent_payer)?;
+    let instruction =
+        instructions::cast_vote(&key(program_id)?, &key(voter)?, proposal_id, support, rent_payer.as_ref()).map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+#[wasm_bindgen(js_name = finalizeProposal)]
+pub fn finalize_proposal(program_id: &str, proposal_id: u64) -> Result<JsInstruction, JsError> {
+    Ok(JsInstruction(instructions::finalize_proposal(&key(program_id)?, proposal_id).map_err(decode_error)?))
+}
+
+// PENALTY POOL
+
+#[wasm_bindgen(js_name = distributePenalties)]
+pub fn distribute_penalties(program_id: &str, mint: &str, rent_payer: &str) -> Result<JsInstruction, JsError> {
+    Ok(JsInstruction(instructions::distribute_penalties(&key(program_id)?, &key(mint)?, &key(rent_payer)?).map_err(decode_error)?))
+}
+
+#[wasm_bindgen(js_name = claimPenaltyRewards)]
+pub fn claim_penalty_rewards(
+    program_id: &str,
+    carrier: &str,
+    carrier_account: &str,
+    mint: &str,
+    rent_payer: Option<String>,
+) -> Result<JsInstruction, JsError> {
+    let rent_payer = optional_key(rent_payer)?;
+    let instruction = instructions::claim_penalty_rewards(
+        &key(program_id)?,
+        &key(carrier)?,
+        &key(carrier_account)?,
+        &key(mint)?,
+        rent_payer.as_ref(),
+    ).map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+// FAUCET
+
+#[wasm_bindgen(js_name = requestFaucet)]
+pub fn request_faucet(
+    program_id: &str,
+    user_account: &str,
+    owner: &str,
+    dlu_mint: &str,
+    dlu_token_program: Option<String>,
+) -> Result<JsInstruction, JsError> {
+    // Without a token program the mint is taken to be a classic SPL Token mint.
+    let token_program = dlu_token_program.as_deref().map(key).transpose()?.unwrap_or_else(spl_token::id);
+    let instruction =
+        instructions::request_faucet(&key(program_id)?, &key(user_account)?, &key(owner)?, &key(dlu_mint)?, &token_program)
+            .map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+// COMPLETION REWARDS
+
+/// Passes the completion rewards of `payment_mint` on a completion, required once the config
+/// schedules rewards. Add them before any leaderboards. `rentPayer` signs and funds the
+/// emission on the first reward.
+#[wasm_bindgen(js_name = withCompletionRewards)]
+pub fn with_completion_rewards(
+    program_id: &str,
+    instruction: JsInstruction,
+    payment_mint: &str,
+    rent_payer: &str,
+) -> Result<JsInstruction, JsError> {
+    let instruction = instructions::with_completion_rewards(&key(program_id)?, instruction.0, &key(payment_mint)?, &key(rent_payer)?);
+    Ok(JsInstruction(instruction))
+}
+
+// FUNDED ACCEPTANCE
+
+/// Deposits `amount` into the buyer's wallet and accepts `offer` out of it in one instruction.
+#[allow(clippy::too_many_arguments)]
+#[wasm_bindgen(js_name = depositAndAccept)]
+pub fn deposit_and_accept(
+    program_id: &str,
+    offer: &str,
+    buyer_user_account: &str,
+    buyer: &str,
+    buyer_token_account: &str,
+    escrow_account: &str,
+    seller: &str,
+    amount: u64,
+    quantity: u32,
+) -> Result<JsInstruction, JsError> {
+    let instruction = instructions::deposit_and_accept(
+        &key(program_id)?,
+        &key(offer)?,
+        &key(buyer_user_account)?,
+        &key(buyer)?,
+        &key(buyer_token_account)?,
+        &key(escrow_account)?,
+        &key(seller)?,
+        amount,
+        quantity,
+    ).map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+// SHIPMENT PRICING
+
+/// Returns the payment the config's rates suggest for a shipment, as `measureShipment`
+/// records it. Measurements left out are not charged for.
+#[wasm_bindgen(js_name = suggestedShipmentPayment)]
+pub fn suggested_shipment_payment(
+    base_rate: u64,
+    rate_per_km: u64,
+    rate_per_kg: u64,
+    weight_grams: Option<u32>,
+    volume_cm3: Option<u32>,
+    distance_km: Option<u32>,
+) -> Result<u64, JsError> {
+    let rates = ShippingRates { base_rate, rate_per_km, rate_per_kg };
+    pricing::suggested_payment(&rates, weight_grams, volume_cm3, distance_km).map_err(decode_error)
+}
+
+#[wasm_bindgen(js_name = measureShipment)]
+pub fn measure_shipment(
+    program_id: &str,
+    shipment: &str,
+    sender: &str,
+    weight_grams: Option<u32>,
+    volume_cm3: Option<u32>,
+    distance_km: Option<u32>,
+) -> Result<JsInstruction, JsError> {
+    let instruction =
+        instructions::measure_shipment(&key(program_id)?, &key(shipment)?, &key(sender)?, weight_grams, volume_cm3, distance_km)
+            .map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+// CARRIER ROUTES
+
+/// `origin` and `destination` are geohashes of the regions the route runs between, and
+/// `days` the weekdays it runs on, Monday at bit 0.
+#[allow(clippy::too_many_arguments)]
+#[wasm_bindgen(js_name = publishRoute)]
+pub fn publish_route(
+    program_id: &str,
+    carrier: &str,
+    carrier_user_account: &str,
+    route_id: u32,
+    origin: &str,
+    destination: &str,
+    days: u8,
+    capacity_grams: u64,
+) -> Result<JsInstruction, JsError> {
+    let instruction = instructions::publish_route(
+        &key(program_id)?,
+        &key(carrier)?,
+        &key(carrier_user_account)?,
+        route_id,
+        route_region(origin)?,
+        route_region(destination)?,
+        days,
+        capacity_grams,
+    ).map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+#[wasm_bindgen(js_name = closeRoute)]
+pub fn close_route(program_id: &str, carrier: &str, route_id: u32, origin: &str) -> Result<JsInstruction, JsError> {
+    Ok(JsInstruction(instructions::close_route(&key(program_id)?, &key(carrier)?, route_id, &route_region(origin)?).map_err(decode_error)?))
+}
+
+#[wasm_bindgen(js_name = referenceRoute)]
+pub fn reference_route(program_id: &str, shipment: &str, sender: &str, route: &str) -> Result<JsInstruction, JsError> {
+    Ok(JsInstruction(instructions::reference_route(&key(program_id)?, &key(shipment)?, &key(sender)?, &key(route)?).map_err(decode_error)?))
+}
+
+/// Region prefix of a route end, which must be precise enough to name one.
+fn route_region(geohash: &str) -> Result<[u8; REGION_GEOHASH_LEN], JsError> {
+    region_prefix(geohash).ok_or_else(|| JsError::new(&DLUError::InvalidRoute.to_string()))
+}
+
+// REVIEWS
+
+#[allow(clippy::too_many_arguments)]
+#[wasm_bindgen(js_name = submitReview)]
+pub fn submit_review(
+    program_id: &str,
+    entity_type_name: &str,
+    entity: &str,
+    deal_id: u64,
+    reviewer: &str,
+    reviewee_user_account: &str,
+    reviewee: &str,
+    rating: u8,
+    review_hash: &[u8],
+    rent_payer: Option<String>,
+) -> Result<JsInstruction, JsError> {
+    let rent_payer = optional_key(rent_payer)?;
+    let instruction = instructions::submit_review(
+        &key(program_id)?,
+        entity_type(entity_type_name)?,
+        &key(entity)?,
+        deal_id,
+        &key(reviewer)?,
+        &key(reviewee_user_account)?,
+        &key(reviewee)?,
+        rating,
+        hash(review_hash)?,
+        rent_payer.as_ref(),
+    ).map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+// KEEPERS
+
+#[allow(clippy::too_many_arguments)]
+#[wasm_bindgen(js_name = crankExpire)]
+pub fn crank_expire(
+    program_id: &str,
+    entity_type_name: &str,
+    entity: &str,
+    payment_mint: &str,
+    seller_or_sender_account: &str,
+    buyer_or_carrier_account: &str,
+    keeper_account: &str,
+    seller_nft_account: Option<String>,
+) -> Result<JsInstruction, JsError> {
+    let accounts = CrankAccounts {
+        entity_type: entity_type(entity_type_name)?,
+        entity: key(entity)?,
+        payment_mint: key(payment_mint)?,
+        seller_or_sender_account: key(seller_or_sender_account)?,
+        buyer_or_carrier_account: key(buyer_or_carrier_account)?,
+        keeper_account: key(keeper_account)?,
+        seller_nft_account: optional_key(seller_nft_account)?,
+    };
+    let instruction = keeper::crank_expire_instruction(&key(program_id)?, &accounts).map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+/// `keys` are, for every one of `ids`, the entity and the seller's or sender's and the
+/// buyer's or carrier's token accounts.
+#[wasm_bindgen(js_name = batchExpire)]
+pub fn batch_expire(
+    program_id: &str,
+    entity_type_name: &str,
+    payment_mint: &str,
+    keeper_account: &str,
+    ids: Vec<u64>,
+    keys: Vec<String>,
+) -> Result<JsInstruction, JsError> {
+    if keys.len() != 3 * ids.len() {
+        return Err(JsError::new("Expected Three Keys Per ID"));
+    }
+    let entries = ids
+        .into_iter()
+        .zip(keys.chunks(3))
+        .map(|(id, keys)| {
+            Ok(ExpiryBatchEntry {
+                id,
+                entity: key(&keys[0])?,
+                seller_or_sender_account: key(&keys[1])?,
+                buyer_or_carrier_account: key(&keys[2])?,
+            })
+        })
+        .collect::<Result<Vec<_>, JsError>>()?;
+    let instruction = keeper::batch_expire_instruction(
+        &key(program_id)?,
+        entity_type(entity_type_name)?,
+        &key(payment_mint)?,
+        &key(keeper_account)?,
+        &entries,
+    )
+    .map_err(decode_error)?;
+    Ok(JsInstruction(instruction))
+}
+
+fn eight(keys: &[String]) -> Result<[&str; 8], JsError> {
+    let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+    keys.try_into().map_err(|_| JsError::new("Expected Eight Keys"))
+}
+
+// DECODING
+
+#[wasm_bindgen(js_name = decodeUser)]
+pub fn decode_user(data: &[u8]) -> Result<Object, JsError> {
+    let user = accounts::decode_user(data).map_err(decode_error)?;
+    let object = Object::new();
+    set(&object, "username", user.username.as_str());
+    set(&object, "pubkey", user.pubkey.to_string());
+    set(&object, "status", format!("{:?}", user.status));
+    set(&object, "totalDeals", user.total_deals);
+    set(&object, "successfulDeals", user.successful_deals);
+    set(&object, "totalShipments", user.total_shipments);
+    set(&object, "successfulShipments", user.successful_shipments);
+    set(&object, "averageRating", user.average_rating());
+    set(&object, "ratingCount", user.rating_count);
+    set(&object, "displayName", user.display_name.as_str());
+    set(&object, "metadataUri", user.metadata_uri.as_str());
+    set(&object, "contactHints", user.contact_hints.as_str());
+    Ok(object)
+}
+
+#[wasm_bindgen(js_name = decodeProfileSummary)]
+pub fn decode_profile_summary(data: &[u8]) -> Result<Object, JsError> {
+    let summary: ProfileSummary = accounts::decode_profile_summary(data).map_err(decode_error)?;
+    let object = Object::new();
+    set(&object, "status", summary.status);
+    set(&object, "user", summary.user.to_string());
+    set(&object, "totalDeals", summary.total_deals);
+    set(&object, "successfulDeals", summary.successful_deals);
+    set(&object, "totalShipments", summary.total_shipments);
+    set(&object, "successfulShipments", summary.successful_shipments);
+    set(&object, "rating", summary.rating);
+    set(&object, "ratingCount", summary.rating_count);
+    set(&object, "lastUpdatedSlot", summary.last_updated_slot);
+    Ok(object)
+}
+
+fn deal_object<R: DealRole>(deal: &Deal<R>) -> Object {
+    let object = Object::new();
+    set(&object, "id", deal.id());
+    set(&object, "status", format!("{:?}", deal.status()));
+    set(&object, "lister", deal.lister_pubkey().to_string());
+    set(&object, "generation", deal.generation());
+    set(&object, "escrowedAmount", deal.escrowed_amount());
+    set(&object, "escrowFlagged", deal.is_escrow_flagged());
+    set(&object, "paymentMint", deal.payment_mint().to_string());
+    set(&object, "meetingPointHidden", deal.is_meeting_point_hidden());
+    let (activate_at, delist_at) = deal.listing_window();
+    set(&object, "activateAt", activate_at);
+    if let Some(delist_at) = delist_at {
+        set(&object, "delistAt", delist_at);
+    }
+    if let Some(holdback) = deal.holdback() {
+        set(&object, "holdbackStatus", format!("{:?}", holdback.status));
+        set(&object, "holdbackAmount", holdback.amount);
+        set(&object, "holdbackReleaseAt", holdback.release_at);
+    }
+    if let Some(chargeback) = deal.chargeback() {
+        set(&object, "chargebackStatus", format!("{:?}", chargeback.status));
+        set(&object, "chargebackClosesAt", chargeback.closes_at);
+    }
+    if let Some((seller, buyer)) = deal.parties() {
+        set(&object, "seller", seller.to_string());
+        set(&object, "buyer", buyer.to_string());
+    }
+    // Status transitions, oldest first.
+    let audit_log = Array::new();
+    for entry in deal.audit_log().chronological() {
+        let transition = Object::new();
+        set(&transition, "status", format!("{:?}", entry.status));
+        set(&transition, "slot", entry.slot);
+        set(&transition, "actor", entry.actor.to_string());
+        set(&transition, "amount", entry.amount);
+        audit_log.push(&transition);
+    }
+    set(&object, "auditLog", audit_log);
+    set(&object, "auditRecorded", deal.audit_log().recorded);
+    object
+}
+
+#[wasm_bindgen(js_name = decodeOffer)]
+pub fn decode_offer(data: &[u8]) -> Result<Object, JsError> {
+    let offer = accounts::decode_offer(data).map_err(decode_error)?;
+    let object = deal_object(&*offer);
+    match offer.kind() {
+        OfferKind::Nft { mint } => set(&object, "nftMint", mint.to_string()),
+        OfferKind::Barter { insurance } => set(&object, "barterInsurance", insurance),
+        OfferKind::Inventory { max_quantity, quantity, .. } => {
+            set(&object, "maxQuantity", max_quantity);
+            set(&object, "quantity", quantity);
+        }
+        OfferKind::Stream(stream) => {
+            set(&object, "streamDuration", stream.duration);
+            set(&object, "streamWithdrawn", stream.withdrawn);
+            if let Some(stopped_at) = stream.stopped_at {
+                set(&object, "streamStoppedAt", stopped_at);
+            }
+        }
+        OfferKind::Described => {}
+    }
+    Ok(object)
+}
+
+#[wasm_bindgen(js_name = decodeRequest)]
+pub fn decode_request(data: &[u8]) -> Result<Object, JsError> {
+    let request = accounts::decode_request(data).map_err(decode_error)?;
+    Ok(deal_object(&*request))
+}
+
+#[wasm_bindgen(js_name = decodeShipment)]
+pub fn decode_shipment(data: &[u8]) -> Result<Object, JsError> {
+    let shipment = accounts::decode_shipment(data).map_err(decode_error)?;
+    let object = Object::new();
+    set(&object, "id", shipment.id());
+    set(&object, "sender", shipment.sender_pubkey().to_string());
+    if let Some(carrier) = shipment.carrier_pubkey() {
+        set(&object, "carrier", carrier.to_string());
+    }
+    if let Some(picked_up_at) = shipment.picked_up_at() {
+        set(&object, "pickedUpAt", picked_up_at);
+    }
+    set(&object, "deliveryDeadline", shipment.delivery_deadline());
+    if let Some(return_of) = shipment.return_of() {
+        set(&object, "returnOf", return_of);
+    }
+    if let Some(proof_hash) = shipment.delivery_proof() {
+        set(&object, "deliveryProof", Uint8Array::from(&proof_hash[..]));
+    }
+    set(&object, "payment", shipment.payment());
+    set(&object, "paymentMint", shipment.payment_mint().to_string());
+    set(&object, "completed", shipment.is_completed());
+    set(&object, "escrowedAmount", shipment.escrowed_amount());
+    set(&object, "escrowFlagged", shipment.is_escrow_flagged());
+    Ok(object)
+}
+
+/// Address of the region index listings at `geohash` are announced in, or nothing when the
+/// geohash is too short to name a region.
+#[wasm_bindgen(js_name = regionAddress)]
+pub fn region_address(program_id: &str, geohash: &str) -> Result<Option<String>, JsError> {
+    let program_id = key(program_id)?;
+    Ok(region_prefix(geohash).map(|prefix| pda::derive_region_address(&program_id, &prefix).0.to_string()))
+}
+
+/// Geohash prefix of the region `geohash` lies in, when it is precise enough to name one.
+fn region_prefix(geohash: &str) -> Option<[u8; REGION_GEOHASH_LEN]> {
+    geohash.to_ascii_lowercase().as_bytes().get(..REGION_GEOHASH_LEN)?.try_into().ok()
+}
+
+/// Listings of a region index, each with its `address`, `entityType`, and `listedAt`.
+#[wasm_bindgen(js_name = decodeRegionIndex)]
+pub fn decode_region_index(data: &[u8]) -> Result<Array, JsError> {
+    let index = accounts::decode_region_index(data).map_err(decode_error)?;
+    let listings = Array::new();
+    for listing in index.listings {
+        let object = Object::new();
+        set(&object, "address", listing.address.to_string());
+        set(&object, "entityType", format!("{:?}", listing.entity_type));
+        set(&object, "listedAt", listing.listed_at);
+        listings.push(&object);
+    }
+    Ok(listings)
+}
+
+/// Address of route `route_id` of a carrier.
+#[wasm_bindgen(js_name = carrierRouteAddress)]
+pub fn carrier_route_address(program_id: &str, carrier: &str, route_id: u32) -> Result<String, JsError> {
+    Ok(pda::derive_carrier_route_address(&key(program_id)?, &key(carrier)?, route_id).0.to_string())
+}
+
+/// Address of the route index of the routes leaving the region `geohash` lies in, or
+/// nothing when the geohash is too short to name a region.
+#[wasm_bindgen(js_name = routeIndexAddress)]
+pub fn route_index_address(program_id: &str, geohash: &str) -> Result<Option<String>, JsError> {
+    let program_id = key(program_id)?;
+    Ok(region_prefix(geohash).map(|prefix| pda::derive_route_index_address(&program_id, &prefix).0.to_string()))
+}
+
+/// Routes of a route index, each with its `route` address, `carrier`, `destination`
+/// region, `days`, and `capacityGrams`.
+#[wasm_bindgen(js_name = decodeRouteIndex)]
+pub fn decode_route_index(data: &[u8]) -> Result<Array, JsError> {
+    let index = accounts::decode_route_index(data).map_err(decode_error)?;
+    let routes = Array::new();
+    for entry in index.routes {
+        let object = Object::new();
+        set(&object, "route", entry.route.to_string());
+        set(&object, "carrier", entry.carrier.to_string());
+        set(&object, "destination", String::from_utf8_lossy(&entry.destination).into_owned());
+        set(&object, "days", entry.days);
+        set(&object, "capacityGrams", entry.capacity_grams);
+        routes.push(&object);
+    }
+    Ok(routes)
+}
+
+/// Address of the message log of an offer, request or shipment.
+#[wasm_bindgen(js_name = messageLogAddress)]
+pub fn message_log_address(program_id: &str, entity: &str) -> Result<String, JsError> {
+    Ok(pda::derive_message_log_address(&key(program_id)?, &key(entity)?).0.to_string())
+}
+
+/// Address of the watchlist of a wallet.
+#[wasm_bindgen(js_name = watchlistAddress)]
+pub fn watchlist_address(program_id: &str, owner: &str) -> Result<String, JsError> {
+    Ok(pda::derive_watchlist_address(&key(program_id)?, &key(owner)?).0.to_string())
+}
+
+/// Address of a leaderboard: "sellerVolume", "sellerStreak", "carrierVolume", or "carrierStreak".
+#[wasm_bindgen(js_name = leaderboardAddress)]
+pub fn leaderboard_address(program_id: &str, kind: &str) -> Result<String, JsError> {
+    Ok(pda::derive_leaderboard_address(&key(program_id)?, leaderboard_kind(kind)?).0.to_string())
+}
+
+/// Address of the KeyManager of an offer, request, or shipment.
+#[wasm_bindgen(js_name = keyManagerAddress)]
+pub fn key_manager_address(program_id: &str, entity: &str) -> Result<String, JsError> {
+    Ok(pda::key_manager(&key(program_id)?, &key(entity)?).to_string())
+}
+
+/// Entries of a leaderboard, best first, each with its `user` and `score`.
+#[wasm_bindgen(js_name = decodeLeaderboard)]
+pub fn decode_leaderboard(data: &[u8]) -> Result<Array, JsError> {
+    let leaderboard = accounts::decode_leaderboard(data).map_err(decode_error)?;
+    let entries = Array::new();
+    for entry in leaderboard.entries {
+        let object = Object::new();
+        set(&object, "user", entry.user.to_string());
+        set(&object, "score", entry.score);
+        entries.push(&object);
+    }
+    Ok(entries)
+}
+
+/// The `followed` users and `watched` offers of a watchlist.
+#[wasm_bindgen(js_name = decodeWatchlist)]
+pub fn decode_watchlist(data: &[u8]) -> Result<Object, JsError> {
+    let watchlist = accounts::decode_watchlist(data).map_err(decode_error)?;
+    let keys = |keys: Vec<Pubkey>| keys.iter().map(|key| JsValue::from_str(&key.to_string())).collect::<Array>();
+    let object = Object::new();
+    set(&object, "followed", keys(watchlist.followed));
+    set(&object, "watched", keys(watchlist.watched));
+    Ok(object)
+}
+
+/// Messages of a message log, oldest first, each with its `sender`, `sentAt`, and
+/// still-encrypted `payload`.
+#[wasm_bindgen(js_name = decodeMessageLog)]
+pub fn decode_message_log(data: &[u8]) -> Result<Array, JsError> {
+    let log = accounts::decode_message_log(data).map_err(decode_error)?;
+    let messages = Array::new();
+    let (newer, older) = log.messages.split_at(log.next as usize);
+    for message in older.iter().chain(newer) {
+        let object = Object::new();
+        set(&object, "sender", message.sender.to_string());
+        set(&object, "sentAt", message.sent_at);
+        set(&object, "payload", Uint8Array::from(&message.payload[..]));
+        messages.push(&object);
+    }
+    Ok(messages)
+}
+
+pub fn decode_stats(data: &[u8]) -> Result<Object, JsError> {
+    let stats = accounts::decode_stats(data).map_err(decode_error)?;
+    let object = Object::new();
+    set(&object, "totalValueLocked", stats.total_value_locked);
+    set(&object, "tvlEpoch", stats.tvl_epoch);
+    set(&object, "epochPeakValueLocked", stats.epoch_peak_value_locked);
+    set(&object, "usersCreated", stats.users_created);
+    set(&object, "listings", stats.listings);
+    set(&object, "completions", stats.completions);
+    set(&object, "failures", stats.failures);
+    set(&object, "volumeEscrowed", stats.volume_escrowed);
+    set(&object, "volumeReleased", stats.volume_released);
+    set(&object, "penalties", stats.penalties);
+    let vault_locks = Array::new();
+    for lock in stats.vault_locks {
+        let entry = Object::new();
+        set(&entry, "vault", lock.vault.to_string());
+        set(&entry, "amount", lock.amount);
+        vault_locks.push(&entry);
+    }
+    set(&object, "vaultLocks", vault_locks);
+    Ok(object)
+}
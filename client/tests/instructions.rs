@@ -0,0 +1,226 @@
+use luda::compression::{CompressedOffer, ACCOUNT_COMPRESSION_PROGRAM_ID};
+use luda::deal::Location;
+use luda::instruction::DLUInstruction;
+use luda_client::instructions::{self, SettleAccounts};
+use luda_client::lookup;
+use luda_client::pda;
+use luda_client::relay::{self, DurableNonce};
+use solana_program::hash::Hash;
+use solana_program::message::VersionedMessage;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_program;
+
+fn settle_accounts() -> SettleAccounts {
+    SettleAccounts {
+        entity: Pubkey::new_unique(),
+        seller_user_account: Pubkey::new_unique(),
+        seller: Pubkey::new_unique(),
+        seller_account: Pubkey::new_unique(),
+        buyer_user_account: Pubkey::new_unique(),
+        buyer: Pubkey::new_unique(),
+        buyer_account: Pubkey::new_unique(),
+        escrow_account: Pubkey::new_unique(),
+        payment_mint: Pubkey::new_unique(),
+    }
+}
+
+#[test]
+fn complete_offer_lays_out_documented_accounts() {
+    let program_id = Pubkey::new_unique();
+    let accounts = settle_accounts();
+    let instruction = instructions::complete_offer(&program_id, &accounts, "buyer".into(), "seller".into(), 2, None).unwrap();
+
+    assert_eq!(instruction.accounts.len(), 12);
+    assert_eq!(instruction.accounts[6].pubkey, pda::derive_vault_registry_address(&program_id).0);
+    assert!(!instruction.accounts.iter().any(|meta| meta.is_signer));
+    assert_eq!(instruction.accounts[8].pubkey, pda::derive_profile_summary_address(&program_id, &accounts.seller).0);
+    assert_eq!(instruction.accounts[11].pubkey, pda::derive_treasury_address(&program_id, &accounts.payment_mint).0);
+    assert!(matches!(
+        DLUInstruction::unpack(&instruction.data).unwrap(),
+        DLUInstruction::CompleteOffer { buyer_key, seller_key, nonce: 2 } if buyer_key == "buyer" && seller_key == "seller"
+    ));
+}
+
+#[test]
+fn nft_offers_release_from_the_offer_nft_escrow() {
+    let program_id = Pubkey::new_unique();
+    let accounts = settle_accounts();
+    let buyer_nft_account = Pubkey::new_unique();
+    let instruction =
+        instructions::complete_offer(&program_id, &accounts, "buyer".into(), "seller".into(), 2, Some(&buyer_nft_account)).unwrap();
+
+    assert_eq!(instruction.accounts.len(), 14);
+    assert_eq!(instruction.accounts[12].pubkey, pda::derive_nft_escrow_address(&program_id, &accounts.entity).0);
+    assert_eq!(instruction.accounts[13].pubkey, buyer_nft_account);
+}
+
+#[test]
+fn staked_shipments_pass_the_carrier_stake() {
+    let program_id = Pubkey::new_unique();
+    let accounts = instructions::AcceptAccounts {
+        entity: Pubkey::new_unique(),
+        acceptor_user_account: Pubkey::new_unique(),
+        acceptor: Pubkey::new_unique(),
+        acceptor_account: Pubkey::new_unique(),
+        acceptor_authority: Pubkey::new_unique(),
+        escrow_account: Pubkey::new_unique(),
+    };
+
+    assert_eq!(instructions::accept_shipment(&program_id, &accounts, false).unwrap().accounts.len(), 9);
+    let staked = instructions::accept_shipment(&program_id, &accounts, true).unwrap();
+    assert_eq!(staked.accounts[9].pubkey, pda::derive_stake_address(&program_id, &accounts.acceptor).0);
+}
+
+#[test]
+fn members_sign_for_their_organization() {
+    let program_id = Pubkey::new_unique();
+    let carrier = Pubkey::new_unique();
+    let member = Pubkey::new_unique();
+    let accounts = instructions::AcceptAccounts {
+        entity: Pubkey::new_unique(),
+        acceptor_user_account: Pubkey::new_unique(),
+        acceptor: carrier,
+        acceptor_account: Pubkey::new_unique(),
+        acceptor_authority: member,
+        escrow_account: Pubkey::new_unique(),
+    };
+
+    let accept = instructions::accept_shipment(&program_id, &accounts, true).unwrap();
+    let instruction = instructions::as_member(&program_id, accept, &carrier, &member);
+    assert_eq!(instruction.accounts.len(), 11);
+    assert!(instruction.accounts[2].pubkey == member && instruction.accounts[2].is_signer);
+    assert_eq!(instruction.accounts[9].pubkey, pda::derive_stake_address(&program_id, &carrier).0);
+    assert_eq!(instruction.accounts[10].pubkey, pda::derive_organization_address(&program_id, &carrier).0);
+}
+
+#[test]
+fn multisig_cosigners_come_before_the_fee_mint() {
+    let program_id = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let cosigner = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+
+    let withdraw = instructions::withdraw_dlu(
+        &program_id, &Pubkey::new_unique(), &owner, &Pubkey::new_unique(), 1, Some(&[cosigner]), Some(&mint),
+    ).unwrap();
+    assert_eq!(withdraw.accounts.len(), 7);
+    assert_eq!(withdraw.accounts[4].pubkey, pda::derive_multisig_address(&program_id, &owner).0);
+    assert!(withdraw.accounts[5].pubkey == cosigner && withdraw.accounts[5].is_signer);
+    assert_eq!(withdraw.accounts[6].pubkey, mint);
+}
+
+#[test]
+fn promo_codes_ride_on_the_offer_acceptance() {
+    let program_id = Pubkey::new_unique();
+    let seller = Pubkey::new_unique();
+    let accounts = instructions::AcceptAccounts {
+        entity: Pubkey::new_unique(),
+        acceptor_user_account: Pubkey::new_unique(),
+        acceptor: Pubkey::new_unique(),
+        acceptor_account: Pubkey::new_unique(),
+        acceptor_authority: Pubkey::new_unique(),
+        escrow_account: Pubkey::new_unique(),
+    };
+
+    let accept = instructions::accept_offer(&program_id, &accounts, &seller, 12).unwrap();
+    let instruction = instructions::with_promo_code(&program_id, accept, &accounts.entity, "SPRING".to_string()).unwrap();
+    assert_eq!(instruction.accounts.len(), 11);
+    assert_eq!(instruction.accounts[10].pubkey, pda::derive_promo_address(&program_id, &accounts.entity).0);
+    assert!(matches!(
+        DLUInstruction::unpack(&instruction.data).unwrap(),
+        DLUInstruction::AcceptOffer { quantity: 12, promo_code: Some(code) } if code == "SPRING"
+    ));
+}
+
+#[test]
+fn compressed_acceptances_pass_the_proof_after_the_compression_programs() {
+    let program_id = Pubkey::new_unique();
+    let tree = Pubkey::new_unique();
+    let accounts = instructions::AcceptAccounts {
+        entity: Pubkey::new_unique(),
+        acceptor_user_account: Pubkey::new_unique(),
+        acceptor: Pubkey::new_unique(),
+        acceptor_account: Pubkey::new_unique(),
+        acceptor_authority: Pubkey::new_unique(),
+        escrow_account: Pubkey::new_unique(),
+    };
+    let listing = CompressedOffer {
+        seller: Pubkey::new_unique(),
+        id: 1,
+        goodsorservice_name: "Bike".to_string(),
+        goodsorservice_description: "City bike".to_string(),
+        payment: 1_000,
+        payment_mint: Pubkey::new_unique(),
+        meeting_point: Location::new("DE", "Berlin".to_string(), "Alexanderplatz 1".to_string()).unwrap(),
+        meeting_datetime: 0,
+        listed_slot: 9,
+    };
+    let proof = [[1; 32], [2; 32]];
+
+    let instruction = instructions::accept_compressed_offer(
+        &program_id, &tree, &accounts, &Pubkey::new_unique(), listing.clone(), [3; 32], 5, &proof,
+    ).unwrap();
+    assert_eq!(instruction.accounts.len(), 17);
+    assert_eq!(instruction.accounts[1].pubkey, pda::derive_listing_tree_authority_address(&program_id, &tree).0);
+    assert_eq!(instruction.accounts[12].pubkey, pda::derive_index_address(&program_id, &listing.seller).0);
+    assert_eq!(instruction.accounts[13].pubkey, ACCOUNT_COMPRESSION_PROGRAM_ID);
+    assert_eq!(instruction.accounts[16].pubkey, Pubkey::new_from_array([2; 32]));
+    assert!(matches!(
+        DLUInstruction::unpack(&instruction.data).unwrap(),
+        DLUInstruction::AcceptCompressedOffer { listing: decoded, root: [3, ..], index: 5 } if decoded == listing
+    ));
+}
+
+#[test]
+fn relayers_pay_for_users_who_only_sign() {
+    let program_id = Pubkey::new_unique();
+    let (owner, relayer, mint) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let dlu_mint = Some((&mint, &spl_token::id()));
+    let create = |rent_payer| {
+        instructions::create_user(&program_id, &Pubkey::new_unique(), &owner, "user".into(), [0; 32], None, dlu_mint, rent_payer).unwrap()
+    };
+
+    let unsponsored = create(None);
+    assert_eq!(unsponsored.accounts.len(), 11);
+    assert!(unsponsored.accounts[1].is_writable);
+
+    let sponsored = create(Some(&relayer));
+    assert!(!sponsored.accounts[1].is_writable);
+    assert_eq!(sponsored.accounts[7].pubkey, relayer);
+    assert!(sponsored.accounts[7].is_signer && sponsored.accounts[7].is_writable);
+
+    // With a durable nonce the relayer pays the fees and authorizes the nonce advance first.
+    let nonce = DurableNonce { account: Pubkey::new_unique(), authority: relayer };
+    let message = relay::sponsored_message(&[sponsored], &relayer, &Hash::new_unique(), Some(&nonce));
+    assert_eq!(message.account_keys[0], relayer);
+    assert_eq!(message.account_keys[message.instructions[0].program_id_index as usize], system_program::id());
+    assert_eq!(relay::required_signers(&message), vec![owner]);
+}
+
+#[test]
+fn v0_completions_load_the_static_accounts_from_the_lookup_table() {
+    let program_id = Pubkey::new_unique();
+    let accounts = settle_accounts();
+    let mints = [accounts.payment_mint];
+    let (table, setup) = lookup::create_static_lookup_table(&program_id, &accounts.buyer, &accounts.buyer, 1, &mints);
+    assert_eq!(setup.len(), 2);
+
+    let instruction = instructions::complete_offer(&program_id, &accounts, "buyer".into(), "seller".into(), 2, None).unwrap();
+    let tables = [lookup::static_lookup_table(&table, &program_id, &mints)];
+    let VersionedMessage::V0(message) = lookup::v0_message(&[instruction], &accounts.buyer, &tables, &Hash::new_unique()).unwrap() else {
+        panic!("expected a v0 message");
+    };
+
+    // The invoked program stays in the message; the config, stats, registry, vault and treasury come from the table.
+    assert!(message.account_keys.contains(&program_id));
+    let addresses = &tables[0].addresses;
+    let position = |key: Pubkey| addresses.iter().position(|address| *address == key).unwrap() as u8;
+    let lookups = &message.address_table_lookups[0];
+    assert_eq!(lookups.account_key, table);
+    let registry = pda::derive_vault_registry_address(&program_id).0;
+    assert_eq!(lookups.readonly_indexes.len(), 2);
+    assert!(lookups.readonly_indexes.contains(&position(pda::config(&program_id))));
+    assert!(lookups.readonly_indexes.contains(&position(registry)));
+    assert!(lookups.writable_indexes.contains(&position(pda::stats(&program_id))));
+    assert!(!message.account_keys.contains(&pda::config(&program_id)));
+}
@@ -0,0 +1,45 @@
+use luda::errors::DLUError;
+use luda::onetimekeys::{KeyManager, KEY_CODE_LEN};
+use luda_client::keys::{self, KEY_BYTES};
+use solana_program::pubkey::Pubkey;
+
+#[test]
+fn keys_derive_again_from_the_same_signature_and_nonce() {
+    let party = Pubkey::new_unique();
+    let signature = [7; 64];
+    let key = keys::derive_key(&signature, 42, &party, 0);
+    assert_eq!(key.len(), 2 * KEY_BYTES);
+    assert_eq!(keys::derive_key(&signature, 42, &party, 0), key);
+
+    // Another deal, party, nonce, or wallet gives an unrelated key.
+    assert_ne!(keys::derive_key(&signature, 43, &party, 0), key);
+    assert_ne!(keys::derive_key(&signature, 42, &Pubkey::new_unique(), 0), key);
+    assert_ne!(keys::derive_key(&signature, 42, &party, 1), key);
+    assert_ne!(keys::derive_key(&[8; 64], 42, &party, 0), key);
+}
+
+#[test]
+fn the_next_nonce_follows_the_party_s_recorded_keys() {
+    let (party, other) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mut manager = KeyManager::default();
+    assert_eq!(keys::next_nonce(&manager, &party), 0);
+    manager.record(&party, 0, [1; 32]).unwrap();
+    manager.record(&other, 5, [2; 32]).unwrap();
+    manager.record(&party, 3, [3; 32]).unwrap();
+    assert_eq!(keys::next_nonce(&manager, &party), 4);
+}
+
+#[test]
+fn key_codes_survive_display_typing_and_qr_scans() {
+    let entity = Pubkey::new_unique();
+    let code = keys::derive_key_code(&[7; 64], 42, &Pubkey::new_unique(), 0);
+    assert_eq!(code.len(), KEY_CODE_LEN);
+
+    let shown = keys::format_key_code(&code);
+    assert_eq!(keys::parse_key_code(&shown.to_lowercase()), Ok(code.clone()));
+    assert_eq!(keys::parse_key_code_qr(&keys::key_code_qr(&entity, &code)), Ok((entity, code.clone())));
+
+    // A mistyped digit fails the checksum instead of counting as a wrong key.
+    let typo = if code.starts_with('A') { code.replacen('A', "B", 1) } else { format!("A{}", &code[1..]) };
+    assert_eq!(keys::parse_key_code(&typo), Err(DLUError::InvalidKeyCode));
+}
@@ -0,0 +1,140 @@
+// Runs arbitrary instructions through the whole processor against mocked accounts. Run
+// from programs/luda with `cargo +nightly fuzz run dispatch`.
+//
+// Accounts are laid out in the runtime's input format and read back with the program's
+// own entrypoint deserializer, so duplicates share their data and reallocation works as
+// it does on-chain.
+#![no_main]
+
+use std::mem::size_of;
+use std::sync::Once;
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use luda::addressing::{
+    derive_config_address, derive_index_address, derive_profile_summary_address, derive_stats_address,
+    derive_treasury_address, derive_vault_address,
+};
+use solana_program::clock::Clock;
+use solana_program::entrypoint::{self, BPF_ALIGN_OF_U128, MAX_PERMITTED_DATA_INCREASE, NON_DUP_MARKER, SUCCESS};
+use solana_program::program_stubs::{set_syscall_stubs, SyscallStubs};
+use solana_program::pubkey::Pubkey;
+use solana_program::rent::Rent;
+use solana_program::system_program;
+
+const PROGRAM_ID: Pubkey = Pubkey::new_from_array([7; 32]);
+
+#[derive(Arbitrary, Debug)]
+struct FuzzAccount {
+    duplicate_of: Option<u8>,  // Repeats an earlier account, as a transaction may.
+    key: u8,
+    owner: u8,
+    is_signer: bool,
+    is_writable: bool,
+    lamports: u64,
+    data: Vec<u8>,
+}
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    accounts: Vec<FuzzAccount>,
+    instruction_data: Vec<u8>,
+}
+
+/// Keys the resolver checks for, so inputs can get past the account validation.
+fn key_pool() -> Vec<Pubkey> {
+    let user = Pubkey::new_from_array([1; 32]);
+    let mint = Pubkey::new_from_array([4; 32]);
+    vec![
+        derive_config_address(&PROGRAM_ID).0,
+        derive_stats_address(&PROGRAM_ID).0,
+        derive_vault_address(&PROGRAM_ID, &mint).0,
+        derive_treasury_address(&PROGRAM_ID, &mint).0,
+        derive_profile_summary_address(&PROGRAM_ID, &user).0,
+        derive_index_address(&PROGRAM_ID, &user).0,
+        user,
+        Pubkey::new_from_array([2; 32]),
+        Pubkey::new_from_array([3; 32]),
+        mint,
+        system_program::id(),
+        spl_token::id(),
+    ]
+}
+
+fn owner_pool() -> [Pubkey; 3] {
+    [PROGRAM_ID, spl_token::id(), system_program::id()]
+}
+
+/// Serializes the accounts and instruction data the way the runtime passes them to a
+/// program, returning an 8-byte aligned buffer.
+fn serialize(input: &Input) -> Vec<u64> {
+    let keys = key_pool();
+    let owners = owner_pool();
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(&(input.accounts.len() as u64).to_le_bytes());
+    for (i, account) in input.accounts.iter().enumerate() {
+        match account.duplicate_of {
+            Some(original) if (original as usize) < i => {
+                bytes.push(original);
+                bytes.extend_from_slice(&[0; 7]);
+            }
+            _ => {
+                bytes.extend_from_slice(&[NON_DUP_MARKER, account.is_signer as u8, account.is_writable as u8, 0]);
+                bytes.extend_from_slice(&[0; size_of::<u32>()]);  // Original data length, filled in on deserialize.
+                bytes.extend_from_slice(keys[account.key as usize % keys.len()].as_ref());
+                bytes.extend_from_slice(owners[account.owner as usize % owners.len()].as_ref());
+                bytes.extend_from_slice(&account.lamports.to_le_bytes());
+                bytes.extend_from_slice(&(account.data.len() as u64).to_le_bytes());
+                bytes.extend_from_slice(&account.data);
+                bytes.resize(bytes.len() + MAX_PERMITTED_DATA_INCREASE, 0);
+                bytes.resize(bytes.len() + (BPF_ALIGN_OF_U128 - bytes.len() % BPF_ALIGN_OF_U128) % BPF_ALIGN_OF_U128, 0);
+                bytes.extend_from_slice(&0u64.to_le_bytes());  // Rent epoch.
+            }
+        }
+    }
+    bytes.extend_from_slice(&(input.instruction_data.len() as u64).to_le_bytes());
+    bytes.extend_from_slice(&input.instruction_data);
+    bytes.extend_from_slice(PROGRAM_ID.as_ref());
+
+    let mut aligned = vec![0u64; (bytes.len() + 7) / 8];
+    for (word, chunk) in aligned.iter_mut().zip(bytes.chunks(8)) {
+        let mut padded = [0; 8];
+        padded[..chunk.len()].copy_from_slice(chunk);
+        *word = u64::from_le_bytes(padded);
+    }
+    aligned
+}
+
+/// Serves the Clock and Rent sysvars and silences logging; cross-program invocations
+/// fall through to the default stub, which does nothing.
+struct FuzzStubs;
+
+impl SyscallStubs for FuzzStubs {
+    fn sol_log(&self, _message: &str) {}
+
+    fn sol_log_data(&self, _fields: &[&[u8]]) {}
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = Clock { slot: 1_000, unix_timestamp: 1_700_000_000, ..Clock::default() };
+        unsafe { *(var_addr as *mut Clock) = clock };
+        SUCCESS
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        SUCCESS
+    }
+}
+
+static STUBS: Once = Once::new();
+
+fuzz_target!(|input: Input| {
+    STUBS.call_once(|| {
+        set_syscall_stubs(Box::new(FuzzStubs));
+    });
+
+    // Anything may be rejected, but only with an error.
+    let mut buffer = serialize(&input);
+    let (program_id, accounts, instruction_data) = unsafe { entrypoint::deserialize(buffer.as_mut_ptr() as *mut u8) };
+    let _ = luda::process_instruction(program_id, &accounts, instruction_data);
+});
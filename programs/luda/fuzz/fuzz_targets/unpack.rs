@@ -0,0 +1,15 @@
+// Feeds arbitrary bytes into instruction decoding. Run from programs/luda with
+// `cargo +nightly fuzz run unpack`.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use luda::instruction::DLUInstruction;
+
+fuzz_target!(|data: &[u8]| {
+    // Malformed input must be rejected with an error, never a panic. Whatever decodes
+    // must be the canonical encoding of its instruction, or more byte strings could carry
+    // the same instruction past client-side checks.
+    if let Ok(instruction) = DLUInstruction::unpack(data) {
+        assert!(instruction.pack().unwrap() == data);
+    }
+});
@@ -1,10 +1,134 @@
 use solana_program::pubkey::Pubkey;
+use solana_program::borsh::{BorshSerialize, BorshDeserialize};
+use crate::arbiter::DisputeKind;
+use crate::badge::BadgeTier;
+use crate::leaderboard::LeaderboardKind;
+use crate::constants::MAX_ENTITY_ID_LEN;
+use crate::region::REGION_GEOHASH_LEN;
 
 // Constants representing different entity types in the system.
 pub const ENTITY_OFFER: &str = "offer";
 pub const ENTITY_REQUEST: &str = "request";
 pub const ENTITY_SHIPMENT: &str = "shipment";
 
+// Seeds of the program-wide singleton accounts.
+pub const CONFIG_SEED: &[u8] = b"config";
+pub const STATS_SEED: &[u8] = b"stats";
+
+// Seed of the program-owned escrow vault token accounts, one per payment mint.
+pub const VAULT_SEED: &[u8] = b"vault";
+
+// Seed of the per-user profile summary accounts.
+pub const PROFILE_SUMMARY_SEED: &[u8] = b"profile";
+
+// Seed of the per-user index accounts.
+pub const INDEX_SEED: &[u8] = b"index";
+
+// Seed of the per-entity fail bond accounts.
+pub const BOND_SEED: &[u8] = b"bond";
+
+// Seed of the program-owned treasury token accounts collecting protocol fees, one per payment mint.
+pub const TREASURY_SEED: &[u8] = b"treasury";
+
+// Seed of the per-carrier stake accounts.
+pub const STAKE_SEED: &[u8] = b"stake";
+
+// Seed of the per-deal, per-reviewer review accounts.
+pub const REVIEW_SEED: &[u8] = b"review";
+
+// Seeds of the per-tier badge mints and the per-user badge token accounts.
+pub const BADGE_MINT_SEED: &[u8] = b"badge_mint";
+pub const BADGE_SEED: &[u8] = b"badge";
+
+// Seed of the per-user wallet token accounts holding DLU deposited with the program.
+pub const WALLET_SEED: &[u8] = b"wallet";
+
+// Seed of the per-offer token accounts holding the NFT an offer sells.
+pub const NFT_ESCROW_SEED: &[u8] = b"nft_escrow";
+
+// Seed of the per-request, per-seller quote accounts.
+pub const QUOTE_SEED: &[u8] = b"quote";
+
+// Seed of the per-geohash-region listing indices.
+pub const REGION_SEED: &[u8] = b"region";
+
+// Seed of the per-deal and per-shipment message logs.
+pub const MESSAGES_SEED: &[u8] = b"messages";
+
+// Seeds of the per-user arbiter accounts, the arbiter registry, and the per-dispute arbitrations.
+pub const ARBITER_SEED: &[u8] = b"arbiter";
+pub const ARBITER_REGISTRY_SEED: &[u8] = b"arbiters";
+pub const ARBITRATION_SEED: &[u8] = b"arbitration";
+
+// Seed of the appeals against arbitration rulings.
+pub const APPEAL_SEED: &[u8] = b"appeal";
+
+// Seed of the delegations users grant to session keys.
+pub const DELEGATION_SEED: &[u8] = b"delegation";
+
+// Seed of organizations with member wallets.
+pub const ORGANIZATION_SEED: &[u8] = b"organization";
+
+// Seed of the multisigs governing shared users.
+pub const MULTISIG_SEED: &[u8] = b"multisig";
+
+// Seeds of the attestor registry and the per-user KYC attestations.
+pub const ATTESTOR_REGISTRY_SEED: &[u8] = b"attestors";
+pub const ATTESTATION_SEED: &[u8] = b"attestation";
+
+// Seed of the referral rewards referrers earn per mint.
+pub const REFERRAL_SEED: &[u8] = b"referral";
+
+// Seed of the promo codes attached to an offer.
+pub const PROMO_SEED: &[u8] = b"promo";
+
+// Seed of the users and offers a wallet follows.
+pub const WATCHLIST_SEED: &[u8] = b"watchlist";
+
+// Seed of the leaderboards ranking sellers and carriers.
+pub const LEADERBOARD_SEED: &[u8] = b"leaderboard";
+
+// Seed of the authorities of compressed listing trees.
+pub const LISTING_TREE_SEED: &[u8] = b"listing_tree";
+
+// Seed of the histories of archived entities.
+pub const HISTORY_SEED: &[u8] = b"history";
+
+// Seed of the KeyManagers of deals and shipments.
+pub const KEY_MANAGER_SEED: &[u8] = b"key_manager";
+
+// Seed of governance's emergency resolutions of deals and shipments.
+pub const EMERGENCY_SEED: &[u8] = b"emergency";
+
+// Seed of the queue of timelocked governance actions.
+pub const TIMELOCK_SEED: &[u8] = b"timelock";
+
+// Seeds of the DAO, its proposals, and the locks and votes of its voters.
+pub const DAO_SEED: &[u8] = b"dao";
+pub const PROPOSAL_SEED: &[u8] = b"proposal";
+pub const VOTE_LOCK_SEED: &[u8] = b"vote_lock";
+pub const VOTE_SEED: &[u8] = b"vote";
+
+// Seeds of the penalty pools streaming penalties to carrier stakers, their token accounts, and
+// the per-carrier rewards accounting, one of each per mint.
+pub const PENALTY_POOL_SEED: &[u8] = b"penalty_pool";
+pub const PENALTY_VAULT_SEED: &[u8] = b"penalty_vault";
+pub const PENALTY_REWARDS_SEED: &[u8] = b"penalty_rewards";
+
+// Seeds of the completion reward emissions of each mint and the token accounts funding them.
+pub const REWARD_EMISSION_SEED: &[u8] = b"reward_emission";
+pub const REWARDS_VAULT_SEED: &[u8] = b"rewards_vault";
+
+// Seed of the mint authority of the DLU faucet on test clusters.
+pub const FAUCET_SEED: &[u8] = b"faucet";
+
+// Seed of the registry of the payment mints' escrow vaults.
+pub const VAULT_REGISTRY_SEED: &[u8] = b"vault_registry";
+
+// Seeds of the routes carriers travel and the per-origin-region indices listing them.
+pub const CARRIER_ROUTE_SEED: &[u8] = b"carrier_route";
+pub const ROUTE_INDEX_SEED: &[u8] = b"route_index";
+
 /// Derives an address based on the provided program_id, entity type, and entity ID.
 pub fn derive_address(program_id: &Pubkey, entity_type: &str, entity_id: &str) -> Result<Pubkey, &'static str> {
     // Validate entity type
@@ -14,7 +138,7 @@ pub fn derive_address(program_id: &Pubkey, entity_type: &str, entity_id: &str) -
     }
 
     // Validate entity ID length to avoid too long seeds
-    if entity_id.len() > 32 {
+    if entity_id.len() > MAX_ENTITY_ID_LEN {
         return Err("Entity ID too long");
     }
 
@@ -22,12 +146,254 @@ pub fn derive_address(program_id: &Pubkey, entity_type: &str, entity_id: &str) -
     Pubkey::create_with_seed(program_id, &seed, &program_id).map_err(|_| "Failed to derive address")
 }
 
+/// Derives the address of the program-wide Config account.
+pub fn derive_config_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CONFIG_SEED], program_id)
+}
+
+/// Derives the address of the program-wide Stats account.
+pub fn derive_stats_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[STATS_SEED], program_id)
+}
+
+/// Derives the address of the escrow vault token account holding a mint, which is also its
+/// own authority.
+pub fn derive_vault_address(program_id: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[VAULT_SEED, mint.as_ref()], program_id)
+}
+
+/// Derives the address of a user's ProfileSummary account.
+pub fn derive_profile_summary_address(program_id: &Pubkey, user: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[PROFILE_SUMMARY_SEED, user.as_ref()], program_id)
+}
+
+/// Derives the address of a user's IndexAccount.
+pub fn derive_index_address(program_id: &Pubkey, user: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[INDEX_SEED, user.as_ref()], program_id)
+}
+
+/// Derives the address of the FailBond posted against an entity.
+pub fn derive_bond_address(program_id: &Pubkey, entity: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[BOND_SEED, entity.as_ref()], program_id)
+}
+
+/// Derives the address of the treasury token account holding a mint, which is also its own
+/// authority.
+pub fn derive_treasury_address(program_id: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[TREASURY_SEED, mint.as_ref()], program_id)
+}
+
+/// Derives the address of a carrier's CarrierStake account.
+pub fn derive_stake_address(program_id: &Pubkey, carrier: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[STAKE_SEED, carrier.as_ref()], program_id)
+}
+
+/// Derives the address of the Review a user left for a deal.
+pub fn derive_review_address(program_id: &Pubkey, deal_id: u64, reviewer: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[REVIEW_SEED, &deal_id.to_le_bytes(), reviewer.as_ref()], program_id)
+}
+
+/// Derives the address of the mint of a badge tier, which is also its mint authority.
+pub fn derive_badge_mint_address(program_id: &Pubkey, tier: BadgeTier) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[BADGE_MINT_SEED, &[tier as u8]], program_id)
+}
+
+/// Derives the address of a user's badge token account for a tier, which is its own owner.
+pub fn derive_badge_address(program_id: &Pubkey, user: &Pubkey, tier: BadgeTier) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[BADGE_SEED, user.as_ref(), &[tier as u8]], program_id)
+}
+
+/// Derives the address of a user's wallet token account, which is also its own authority.
+pub fn derive_wallet_address(program_id: &Pubkey, user: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[WALLET_SEED, user.as_ref()], program_id)
+}
+
+/// Derives the address of the token account holding the NFT an offer sells, which is also
+/// its own authority.
+pub fn derive_nft_escrow_address(program_id: &Pubkey, offer: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[NFT_ESCROW_SEED, offer.as_ref()], program_id)
+}
+
+/// Derives the address of the Quote a seller submitted for a request.
+pub fn derive_quote_address(program_id: &Pubkey, request: &Pubkey, seller: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[QUOTE_SEED, request.as_ref(), seller.as_ref()], program_id)
+}
+
+/// Derives the address of the RegionIndex of a geohash region.
+pub fn derive_region_address(program_id: &Pubkey, prefix: &[u8; REGION_GEOHASH_LEN]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[REGION_SEED, prefix], program_id)
+}
+
+/// Derives the address of the MessageLog of an offer, request or shipment.
+pub fn derive_message_log_address(program_id: &Pubkey, entity: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[MESSAGES_SEED, entity.as_ref()], program_id)
+}
+
+/// Derives the address of a user's Arbiter account.
+pub fn derive_arbiter_address(program_id: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[ARBITER_SEED, owner.as_ref()], program_id)
+}
+
+/// Derives the address of the ArbiterRegistry.
+pub fn derive_arbiter_registry_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[ARBITER_REGISTRY_SEED], program_id)
+}
+
+/// Derives the address of the Arbitration of a dispute of the given kind over an entity.
+pub fn derive_arbitration_address(program_id: &Pubkey, entity: &Pubkey, kind: DisputeKind) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[ARBITRATION_SEED, entity.as_ref(), &[kind as u8]], program_id)
+}
+
+/// Derives the address of the Appeal against the ruling of an Arbitration.
+pub fn derive_appeal_address(program_id: &Pubkey, arbitration: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[APPEAL_SEED, arbitration.as_ref()], program_id)
+}
+
+/// Derives the address of the Delegation an owner granted to a session key.
+pub fn derive_delegation_address(program_id: &Pubkey, owner: &Pubkey, session: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[DELEGATION_SEED, owner.as_ref(), session.as_ref()], program_id)
+}
+
+/// Derives the address of the Organization of a user.
+pub fn derive_organization_address(program_id: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[ORGANIZATION_SEED, owner.as_ref()], program_id)
+}
+
+/// Derives the address of the Multisig governing a user.
+pub fn derive_multisig_address(program_id: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[MULTISIG_SEED, owner.as_ref()], program_id)
+}
+
+/// Derives the address of the AttestorRegistry.
+pub fn derive_attestor_registry_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[ATTESTOR_REGISTRY_SEED], program_id)
+}
+
+/// Derives the address of the Attestation of a user.
+pub fn derive_attestation_address(program_id: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[ATTESTATION_SEED, owner.as_ref()], program_id)
+}
+
+/// Derives the address of the ReferralRewards a referrer earns in a mint.
+pub fn derive_referral_address(program_id: &Pubkey, referrer: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[REFERRAL_SEED, referrer.as_ref(), mint.as_ref()], program_id)
+}
+
+/// Derives the address of the PromoCodes of an offer.
+pub fn derive_promo_address(program_id: &Pubkey, offer: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[PROMO_SEED, offer.as_ref()], program_id)
+}
+
+/// Derives the address of the Watchlist of a wallet.
+pub fn derive_watchlist_address(program_id: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[WATCHLIST_SEED, owner.as_ref()], program_id)
+}
+
+/// Derives the address of the Leaderboard of the given kind.
+pub fn derive_leaderboard_address(program_id: &Pubkey, kind: LeaderboardKind) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[LEADERBOARD_SEED, &[kind as u8]], program_id)
+}
+
+/// Derives the authority of a compressed listing tree, which the program signs as.
+pub fn derive_listing_tree_authority_address(program_id: &Pubkey, tree: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[LISTING_TREE_SEED, tree.as_ref()], program_id)
+}
+
+/// Derives the address of the History of the entities a lister archived.
+pub fn derive_history_address(program_id: &Pubkey, lister: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[HISTORY_SEED, lister.as_ref()], program_id)
+}
+
+/// Derives the address of the KeyManager of a deal or shipment.
+pub fn derive_key_manager_address(program_id: &Pubkey, entity: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[KEY_MANAGER_SEED, entity.as_ref()], program_id)
+}
+
+/// Derives the address of the EmergencyResolution of a deal or shipment.
+pub fn derive_emergency_address(program_id: &Pubkey, entity: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[EMERGENCY_SEED, entity.as_ref()], program_id)
+}
+
+/// Derives the address of the TimelockQueue of governance actions.
+pub fn derive_timelock_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[TIMELOCK_SEED], program_id)
+}
+
+/// Derives the address of the Dao.
+pub fn derive_dao_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[DAO_SEED], program_id)
+}
+
+/// Derives the address of a Proposal of the DAO.
+pub fn derive_proposal_address(program_id: &Pubkey, id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[PROPOSAL_SEED, &id.to_le_bytes()], program_id)
+}
+
+/// Derives the address of the VoteLock of a voter.
+pub fn derive_vote_lock_address(program_id: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[VOTE_LOCK_SEED, owner.as_ref()], program_id)
+}
+
+/// Derives the address of the VoteRecord of a voter on a proposal.
+pub fn derive_vote_record_address(program_id: &Pubkey, proposal: &Pubkey, voter: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[VOTE_SEED, proposal.as_ref(), voter.as_ref()], program_id)
+}
+
+/// Derives the address of the PenaltyPool of a mint.
+pub fn derive_penalty_pool_address(program_id: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[PENALTY_POOL_SEED, mint.as_ref()], program_id)
+}
+
+/// Derives the address of the token account holding the penalty pool of a mint, which is also
+/// its own authority. Penalties in the mint should be sent here.
+pub fn derive_penalty_vault_address(program_id: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[PENALTY_VAULT_SEED, mint.as_ref()], program_id)
+}
+
+/// Derives the address of a carrier's PenaltyRewards in the pool of a mint.
+pub fn derive_penalty_rewards_address(program_id: &Pubkey, mint: &Pubkey, carrier: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[PENALTY_REWARDS_SEED, mint.as_ref(), carrier.as_ref()], program_id)
+}
+
+/// Derives the address of the RewardEmission of a mint.
+pub fn derive_reward_emission_address(program_id: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[REWARD_EMISSION_SEED, mint.as_ref()], program_id)
+}
+
+/// Derives the address of the token account paying the completion rewards of a mint, which
+/// is also its own authority. Governance funds the rewards by sending tokens here.
+pub fn derive_rewards_vault_address(program_id: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[REWARDS_VAULT_SEED, mint.as_ref()], program_id)
+}
+
+/// Derives the address of the faucet, which test clusters make the mint authority of DLU.
+pub fn derive_faucet_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[FAUCET_SEED], program_id)
+}
+
+/// Derives the address of the VaultRegistry.
+pub fn derive_vault_registry_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[VAULT_REGISTRY_SEED], program_id)
+}
+
+/// Derives the address of one of a carrier's CarrierRoutes.
+pub fn derive_carrier_route_address(program_id: &Pubkey, carrier: &Pubkey, route_id: u32) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CARRIER_ROUTE_SEED, carrier.as_ref(), &route_id.to_le_bytes()], program_id)
+}
+
+/// Derives the address of the RouteIndex of the routes leaving a geohash region.
+pub fn derive_route_index_address(program_id: &Pubkey, origin: &[u8; REGION_GEOHASH_LEN]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[ROUTE_INDEX_SEED, origin], program_id)
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq)]
 pub enum EntityType {
     Offer,
     Request,
     Shipment,
 }
 
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
 pub struct AcceptedEntity {
     entity_address: Pubkey,
     entity_type: EntityType,
@@ -36,6 +402,10 @@ pub struct AcceptedEntity {
     recipient: Option<Pubkey>, // This field will be Some(Pubkey) for shipments and None for offers/requests.
 }
 
+/// Per-user lists of the entities a user listed, grouped by lifecycle stage.
+///
+/// Lives at the PDA `["index", user_pubkey]`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
 pub struct IndexAccount {
     pub active_offers: Vec<Pubkey>,
     pub accepted_offers: Vec<AcceptedEntity>,
@@ -81,8 +451,10 @@ impl IndexAccount {
         self.remove_offer(offer_address);
         let accepted_offer = AcceptedEntity {
             entity_address: *offer_address,
-            seller: *seller,
+            entity_type: EntityType::Offer,
+            seller_or_sender: *seller,
             buyer_or_carrier: *buyer,
+            recipient: None,
         };
         self.accepted_offers.push(accepted_offer);
     }
@@ -96,10 +468,6 @@ impl IndexAccount {
 	}
 
 	// REQUESTS
-	pub fn add_request(&mut self, request_address: Pubkey) {
-		self.active_requests.push(request_address);
-	}
-
 	pub fn remove_request(&mut self, request_address: &Pubkey) {
 		self.active_requests.retain(|&x| x != *request_address);
 	}
@@ -108,8 +476,10 @@ impl IndexAccount {
 		self.remove_request(request_address);
 		let accepted_request = AcceptedEntity {
 			entity_address: *request_address,
-			seller: *seller,
+			entity_type: EntityType::Request,
+			seller_or_sender: *seller,
 			buyer_or_carrier: *buyer,
+			recipient: None,
 		};
 		self.accepted_requests.push(accepted_request);
 	}
@@ -143,11 +513,12 @@ impl IndexAccount {
 
 	pub fn accept_shipment(&mut self, shipment_address: &Pubkey, sender: &Pubkey, carrier: &Pubkey, recipient: &Pubkey) {
 		self.remove_shipment(shipment_address);
-		let accepted_shipment = AcceptedShipment {
-			shipment_address: *shipment_address,
-			sender: *sender,
-			carrier: *carrier,
-			recipient: *recipient,
+		let accepted_shipment = AcceptedEntity {
+			entity_address: *shipment_address,
+			entity_type: EntityType::Shipment,
+			seller_or_sender: *sender,
+			buyer_or_carrier: *carrier,
+			recipient: Some(*recipient),
 		};
 		self.accepted_shipments.push(accepted_shipment);
 	}
@@ -160,4 +531,14 @@ impl IndexAccount {
 		self.remove_shipment(shipment_address);
 	}
 
+    /// Serializes the index into a vector of bytes.
+    pub fn serialize(&self) -> Result<Vec<u8>, &'static str> {
+        self.try_to_vec().map_err(|_| "Failed to serialize IndexAccount")
+    }
+
+    /// Deserializes an index from a slice of bytes.
+    pub fn deserialize(input: &mut &[u8]) -> Result<Self, &'static str> {
+        <Self as BorshDeserialize>::deserialize(input).map_err(|_| "Failed to deserialize IndexAccount")
+    }
+
 }
@@ -0,0 +1,154 @@
+use solana_program::pubkey::Pubkey;
+use solana_program::borsh::{BorshSerialize, BorshDeserialize};
+use crate::errors::DLUError;
+use crate::time;
+
+/// Arbiters drawn to re-rule an appealed dispute.
+pub const APPEAL_PANEL_SIZE: usize = 3;
+
+/// Represents the current status of an appeal.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, PartialEq, Debug)]
+pub enum AppealStatus {
+    Pending,     // The panel is still voting.
+    Upheld,      // The panel's majority agreed with the appealed ruling.
+    Overturned,  // The panel's majority reversed the appealed ruling.
+    Lapsed,      // The panel reached no majority in time, so the appealed ruling stands.
+}
+
+/// Appeal of an arbiter's ruling by the losing party.
+///
+/// Within `APPEAL_WINDOW` of the ruling the losing party posts the appeal bond and a panel
+/// of `APPEAL_PANEL_SIZE` other arbiters is drawn to vote on the dispute again; the
+/// majority decides. On settlement half the bond goes to the winning side and the rest is
+/// shared by the panelists who voted with the majority, while a lapsed appeal returns the
+/// bond to the appellant.
+///
+/// Lives at the PDA `["appeal", arbitration_pubkey]`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct Appeal {
+    pub arbitration: Pubkey,
+    pub appellant: Pubkey,
+    pub bond: u64,
+    pub filed_at: i64,
+    pub appealed_ruling: bool,  // Whether the appealed ruling was for the buyer.
+    pub panel: [Pubkey; APPEAL_PANEL_SIZE],
+    pub votes: [Option<bool>; APPEAL_PANEL_SIZE],  // Whether each panelist voted for the buyer.
+    pub status: AppealStatus,
+    pub arbiter_slashed: bool,  // Whether the overturned arbiter's stake was slashed.
+}
+
+impl Appeal {
+    /// Records an appeal filed at `now` against a ruling for the buyer when `appealed_ruling`.
+    pub fn file(
+        arbitration: Pubkey,
+        appellant: Pubkey,
+        bond: u64,
+        appealed_ruling: bool,
+        panel: [Pubkey; APPEAL_PANEL_SIZE],
+        now: i64,
+    ) -> Self {
+        Appeal {
+            arbitration,
+            appellant,
+            bond,
+            filed_at: now,
+            appealed_ruling,
+            panel,
+            votes: [None; APPEAL_PANEL_SIZE],
+            status: AppealStatus::Pending,
+            arbiter_slashed: false,
+        }
+    }
+
+    /// Returns true once the panel has had `ARBITRATION_TIMEOUT` to vote.
+    pub fn voting_closed(&self, now: i64) -> bool {
+        now >= self.filed_at.saturating_add(time::ARBITRATION_TIMEOUT)
+    }
+
+    /// Records a panelist's vote.
+    pub fn vote(&mut self, panelist: &Pubkey, for_buyer: bool, now: i64) -> Result<(), DLUError> {
+        if self.status != AppealStatus::Pending {
+            return Err(DLUError::AppealSettled);
+        }
+        if self.voting_closed(now) {
+            return Err(DLUError::DisputeWindowClosed);
+        }
+        let seat = self.panel.iter().position(|p| p == panelist).ok_or(DLUError::NotOnPanel)?;
+        if self.votes[seat].is_some() {
+            return Err(DLUError::AlreadyVoted);
+        }
+        self.votes[seat] = Some(for_buyer);
+        Ok(())
+    }
+
+    /// Returns the panel's decision once a majority voted the same way.
+    pub fn majority(&self) -> Option<bool> {
+        let count = |side: bool| self.votes.iter().filter(|v| **v == Some(side)).count();
+        if count(true) > APPEAL_PANEL_SIZE / 2 {
+            Some(true)
+        } else if count(false) > APPEAL_PANEL_SIZE / 2 {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
+    /// Closes the appeal, returning whether the dispute is settled for the buyer. Fails
+    /// while the panel can still reach a majority.
+    pub fn settle(&mut self, now: i64) -> Result<bool, DLUError> {
+        if self.status != AppealStatus::Pending {
+            return Err(DLUError::AppealSettled);
+        }
+        let (status, for_buyer) = match self.majority() {
+            Some(decision) if decision == self.appealed_ruling => (AppealStatus::Upheld, decision),
+            Some(decision) => (AppealStatus::Overturned, decision),
+            None if self.voting_closed(now) => (AppealStatus::Lapsed, self.appealed_ruling),
+            None => return Err(DLUError::AppealPending),
+        };
+        self.status = status;
+        Ok(for_buyer)
+    }
+
+    /// Returns the panelists who voted with the majority of a settled appeal.
+    pub fn majority_panelists(&self) -> Vec<Pubkey> {
+        match self.majority() {
+            Some(decision) => self.panel.iter()
+                .zip(self.votes.iter())
+                .filter(|(_, vote)| **vote == Some(decision))
+                .map(|(panelist, _)| *panelist)
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Splits the bond of a decided appeal into the winning side's share and the share of
+    /// each majority panelist. Rounding dust goes to the winning side.
+    pub fn bond_shares(&self) -> (u64, u64) {
+        let panelists = self.majority_panelists().len() as u64;
+        if panelists == 0 {
+            return (self.bond, 0);
+        }
+        let each = self.bond / 2 / panelists;
+        (self.bond - each * panelists, each)
+    }
+
+    /// Marks the overturned arbiter as slashed, at most once.
+    pub fn mark_arbiter_slashed(&mut self) -> Result<(), DLUError> {
+        if self.status != AppealStatus::Overturned || self.arbiter_slashed {
+            return Err(DLUError::IncorrectState);
+        }
+        self.arbiter_slashed = true;
+        Ok(())
+    }
+
+    /// Serializes the appeal into a vector of bytes.
+    pub fn serialize(&self) -> Result<Vec<u8>, DLUError> {
+        self.try_to_vec().map_err(|_| DLUError::SerializationFailed)
+    }
+
+    /// Deserializes an appeal from a slice of bytes.
+    pub fn deserialize(input: &mut &[u8]) -> Result<Self, DLUError> {
+        // Accounts are sized for a full vote, so the tail is zero padding until then.
+        <Self as BorshDeserialize>::deserialize(input).map_err(|_| DLUError::DeserializationFailed)
+    }
+}
@@ -0,0 +1,283 @@
+use solana_program::account_info::AccountInfo;
+use solana_program::hash::hashv;
+use solana_program::pubkey::Pubkey;
+use solana_program::borsh::{BorshSerialize, BorshDeserialize};
+use crate::errors::DLUError;
+use crate::safe_math;
+use crate::time;
+
+/// Most arbiters the registry can hold at once.
+pub const MAX_ARBITERS: usize = 32;
+
+/// DLU a user stakes to rule on disputes as an arbiter.
+///
+/// The stake stays locked while the arbiter is registered and for `ARBITER_COOLDOWN`
+/// after they leave, so rulings they made can still be slashed when overturned on appeal.
+///
+/// Lives at the PDA `["arbiter", owner_pubkey]`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct Arbiter {
+    pub owner: Pubkey,
+    pub mint: Pubkey,         // Payment mint the stake is held in, in the vault of that mint.
+    pub stake: u64,
+    pub registered: bool,
+    pub cooldown_until: i64,  // The stake can be withdrawn from this timestamp on, once deregistered.
+    pub rulings: u32,
+    pub overturned: u32,      // Rulings reversed on appeal.
+    pub fees_earned: u64,
+    pub total_slashed: u64,
+}
+
+impl Arbiter {
+    /// Creates an unregistered arbiter with no stake, to be held in `mint`.
+    pub fn new(owner: Pubkey, mint: Pubkey) -> Self {
+        Arbiter {
+            owner,
+            mint,
+            stake: 0,
+            registered: false,
+            cooldown_until: 0,
+            rulings: 0,
+            overturned: 0,
+            fees_earned: 0,
+            total_slashed: 0,
+        }
+    }
+
+    /// Adds to the stake and registers the arbiter once it covers `min_stake`.
+    pub fn register(&mut self, amount: u64, min_stake: u64) -> Result<(), DLUError> {
+        if amount == 0 {
+            return Err(DLUError::InvalidStakeAmount);
+        }
+        self.stake = safe_math::add(self.stake, amount)?;
+        if self.stake < min_stake {
+            return Err(DLUError::InsufficientStake);
+        }
+        self.registered = true;
+        Ok(())
+    }
+
+    /// Stops the arbiter from being drawn, starting the cooldown before they can withdraw.
+    pub fn deregister(&mut self, now: i64) -> Result<(), DLUError> {
+        if !self.registered {
+            return Err(DLUError::ArbiterNotRegistered);
+        }
+        self.registered = false;
+        self.cooldown_until = now.saturating_add(time::ARBITER_COOLDOWN);
+        Ok(())
+    }
+
+    /// Withdraws the whole stake of a deregistered arbiter once the cooldown has ended,
+    /// returning the amount.
+    pub fn withdraw(&mut self, now: i64) -> Result<u64, DLUError> {
+        if self.registered || now < self.cooldown_until {
+            return Err(DLUError::StakeCoolingDown);
+        }
+        if self.stake == 0 {
+            return Err(DLUError::InvalidStakeAmount);
+        }
+        Ok(std::mem::take(&mut self.stake))
+    }
+
+    /// Records a ruling and the fee paid for it.
+    pub fn record_ruling(&mut self, fee: u64) -> Result<(), DLUError> {
+        self.rulings = self.rulings.saturating_add(1);
+        self.fees_earned = safe_math::add(self.fees_earned, fee)?;
+        Ok(())
+    }
+
+    /// Takes up to `amount` from the stake for a ruling overturned on appeal, returning how
+    /// much was slashed.
+    pub fn slash_overturned(&mut self, amount: u64) -> Result<u64, DLUError> {
+        let slashed = amount.min(self.stake);
+        self.stake -= slashed;
+        self.overturned = self.overturned.saturating_add(1);
+        self.total_slashed = safe_math::add(self.total_slashed, slashed)?;
+        Ok(slashed)
+    }
+
+    /// Serializes the arbiter into a vector of bytes.
+    pub fn serialize(&self) -> Result<Vec<u8>, DLUError> {
+        self.try_to_vec().map_err(|_| DLUError::SerializationFailed)
+    }
+
+    /// Deserializes an arbiter from a slice of bytes.
+    pub fn deserialize(input: &mut &[u8]) -> Result<Self, DLUError> {
+        <Self as BorshDeserialize>::deserialize(input).map_err(|_| DLUError::DeserializationFailed)
+    }
+}
+
+/// Registered arbiters disputes are drawn from.
+///
+/// Lives at the PDA `["arbiters"]`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default)]
+pub struct ArbiterRegistry {
+    pub arbiters: Vec<Pubkey>,
+}
+
+impl ArbiterRegistry {
+    /// Adds an arbiter to the pool disputes are drawn from.
+    pub fn add(&mut self, arbiter: Pubkey) -> Result<(), DLUError> {
+        if self.contains(&arbiter) {
+            return Err(DLUError::ArbiterAlreadyRegistered);
+        }
+        if self.arbiters.len() >= MAX_ARBITERS {
+            return Err(DLUError::ArbiterRegistryFull);
+        }
+        self.arbiters.push(arbiter);
+        Ok(())
+    }
+
+    /// Removes an arbiter from the pool.
+    pub fn remove(&mut self, arbiter: &Pubkey) -> Result<(), DLUError> {
+        let position = self.arbiters.iter().position(|a| a == arbiter).ok_or(DLUError::ArbiterNotRegistered)?;
+        self.arbiters.swap_remove(position);
+        Ok(())
+    }
+
+    /// Returns true if the arbiter is in the pool.
+    pub fn contains(&self, arbiter: &Pubkey) -> bool {
+        self.arbiters.contains(arbiter)
+    }
+
+    /// Draws an arbiter for the dispute over `entity` from a recent slot hash, skipping the
+    /// parties to the dispute.
+    ///
+    /// Slot hashes are known to validators a few slots ahead, so the draw only keeps the
+    /// parties from choosing their arbiter; it is not unbiasable randomness.
+    pub fn draw(&self, slot_hash: &[u8; 32], entity: &Pubkey, parties: &[Pubkey]) -> Result<Pubkey, DLUError> {
+        Ok(self.draw_panel(slot_hash, entity, parties, 1)?[0])
+    }
+
+    /// Draws `size` distinct arbiters the same way, failing unless enough of them are not
+    /// among `excluded`.
+    pub fn draw_panel(
+        &self,
+        slot_hash: &[u8; 32],
+        entity: &Pubkey,
+        excluded: &[Pubkey],
+        size: usize,
+    ) -> Result<Vec<Pubkey>, DLUError> {
+        let seed = hashv(&[slot_hash, entity.as_ref()]).to_bytes();
+        let mut start = [0; 8];
+        start.copy_from_slice(&seed[..8]);
+        let start = u64::from_le_bytes(start) as usize;
+        let count = self.arbiters.len();
+        let panel: Vec<Pubkey> = (0..count)
+            .map(|offset| self.arbiters[(start.wrapping_add(offset)) % count])
+            .filter(|arbiter| !excluded.contains(arbiter))
+            .take(size)
+            .collect();
+        if panel.len() < size {
+            return Err(DLUError::NoArbiterAvailable);
+        }
+        Ok(panel)
+    }
+
+    /// Serializes the registry into a vector of bytes.
+    pub fn serialize(&self) -> Result<Vec<u8>, DLUError> {
+        self.try_to_vec().map_err(|_| DLUError::SerializationFailed)
+    }
+
+    /// Deserializes a registry from a slice of bytes.
+    pub fn deserialize(input: &mut &[u8]) -> Result<Self, DLUError> {
+        // Accounts are sized for a full registry, so the tail may be zero padding.
+        <Self as BorshDeserialize>::deserialize(input).map_err(|_| DLUError::DeserializationFailed)
+    }
+}
+
+/// Kinds of dispute over a completed offer that arbiters rule on.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, PartialEq, Debug)]
+pub enum DisputeKind {
+    Holdback,    // The buyer disputed the goods within the holdback window.
+    Chargeback,  // The buyer charged the offer back within the chargeback window.
+}
+
+/// The arbiter drawn for a dispute, and their ruling once made.
+///
+/// Lives at the PDA `["arbitration", entity_pubkey, kind]`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct Arbitration {
+    pub entity: Pubkey,
+    pub kind: DisputeKind,
+    pub arbiter: Pubkey,
+    pub assigned_at: i64,
+    pub ruled_at: i64,
+    pub ruling: Option<bool>,  // Whether the arbiter ruled for the buyer.
+    pub appealed: bool,
+}
+
+impl Arbitration {
+    /// Assigns a freshly drawn arbiter to the dispute.
+    pub fn assign(entity: Pubkey, kind: DisputeKind, arbiter: Pubkey, now: i64) -> Self {
+        Arbitration { entity, kind, arbiter, assigned_at: now, ruled_at: 0, ruling: None, appealed: false }
+    }
+
+    /// Returns true if the dispute may be drawn again: its arbiter has not ruled and either
+    /// left the registry or let `ARBITRATION_TIMEOUT` pass.
+    pub fn can_reassign(&self, registry: &ArbiterRegistry, now: i64) -> bool {
+        self.ruling.is_none()
+            && (!registry.contains(&self.arbiter) || now >= self.assigned_at.saturating_add(time::ARBITRATION_TIMEOUT))
+    }
+
+    /// Records the ruling of the assigned arbiter at `now`, opening the appeal window.
+    pub fn rule(&mut self, arbiter: &Pubkey, for_buyer: bool, now: i64) -> Result<(), DLUError> {
+        if *arbiter != self.arbiter {
+            return Err(DLUError::NotAssignedArbiter);
+        }
+        if self.ruling.is_some() {
+            return Err(DLUError::AlreadyRuled);
+        }
+        self.ruling = Some(for_buyer);
+        self.ruled_at = now;
+        Ok(())
+    }
+
+    /// Returns the ruling, failing if the arbiter has not ruled yet.
+    pub fn ruling(&self) -> Result<bool, DLUError> {
+        self.ruling.ok_or(DLUError::NotRuled)
+    }
+
+    /// Returns true while the losing party can still appeal the ruling.
+    pub fn in_appeal_window(&self, now: i64) -> bool {
+        self.ruling.is_some() && now < self.ruled_at.saturating_add(time::APPEAL_WINDOW)
+    }
+
+    /// Records the appeal of the ruling by `appellant`, who must have lost it, returning
+    /// the ruling appealed.
+    pub fn appeal(&mut self, appellant: &Pubkey, seller: &Pubkey, buyer: &Pubkey, now: i64) -> Result<bool, DLUError> {
+        let ruling = self.ruling()?;
+        let loser = if ruling { seller } else { buyer };
+        if appellant != loser {
+            return Err(DLUError::NotAuthorized);
+        }
+        if self.appealed {
+            return Err(DLUError::AppealSettled);
+        }
+        if !self.in_appeal_window(now) {
+            return Err(DLUError::DisputeWindowClosed);
+        }
+        self.appealed = true;
+        Ok(ruling)
+    }
+
+    /// Serializes the arbitration into a vector of bytes.
+    pub fn serialize(&self) -> Result<Vec<u8>, DLUError> {
+        self.try_to_vec().map_err(|_| DLUError::SerializationFailed)
+    }
+
+    /// Deserializes an arbitration from a slice of bytes.
+    pub fn deserialize(input: &mut &[u8]) -> Result<Self, DLUError> {
+        // Accounts are sized for a ruling, so the tail is zero padding until one is made.
+        <Self as BorshDeserialize>::deserialize(input).map_err(|_| DLUError::DeserializationFailed)
+    }
+}
+
+/// Returns the most recent hash of the SlotHashes sysvar, which is laid out as a `u64`
+/// count followed by `(slot, hash)` entries, newest first.
+pub fn recent_slot_hash(slot_hashes: &AccountInfo) -> Result<[u8; 32], DLUError> {
+    let data = slot_hashes.data.borrow();
+    data.get(16..48)
+        .and_then(|hash| hash.try_into().ok())
+        .ok_or(DLUError::DeserializationFailed)
+}
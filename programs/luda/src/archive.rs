@@ -0,0 +1,118 @@
+use solana_program::hash::{hash, hashv};
+use solana_program::pubkey::Pubkey;
+use solana_program::borsh::{BorshSerialize, BorshDeserialize};
+use crate::addressing::EntityType;
+use crate::errors::DLUError;
+use crate::time::SECONDS_PER_DAY;
+
+/// Most entities a single `ArchiveBatch` archives.
+pub const MAX_ARCHIVE_BATCH: usize = 16;
+/// Time after their meeting or drop-off an entity must wait before it can be archived,
+/// which leaves the parties time to review it.
+pub const ARCHIVE_DELAY: i64 = 30 * SECONDS_PER_DAY;
+
+/// Node paired with the last one of a level that has an odd number of nodes.
+const EMPTY_NODE: [u8; 32] = [0; 32];
+
+/// A batch of entities archived together, committed to by the root of a Merkle tree with
+/// one leaf per entity.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct ArchivedBatch {
+    pub entity_type: EntityType,
+    pub root: [u8; 32],
+    pub count: u8,
+    pub slot: u64,
+}
+
+/// Batches of finished entities a lister archived, oldest first.
+///
+/// Archiving closes the entity accounts, so the history is all that is left of them on
+/// chain: anyone holding an entity's last account data, which indexers keep from the
+/// `BatchArchived` event's slot, can prove what happened to it with `VerifyArchivedEntity`.
+/// The history grows by one batch per archive, paid for by the lister.
+///
+/// Lives at the PDA `["history", lister_pubkey]`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default)]
+pub struct History {
+    pub batches: Vec<ArchivedBatch>,
+}
+
+impl History {
+    /// Appends a batch, returning its number.
+    pub fn archive(&mut self, batch: ArchivedBatch) -> u32 {
+        self.batches.push(batch);
+        (self.batches.len() - 1) as u32
+    }
+
+    /// Checks that `proof`, from the leaf up, shows the entity with `data_hash` at `index`
+    /// of the numbered batch.
+    pub fn verify(&self, batch: u32, entity: &Pubkey, data_hash: &[u8; 32], index: u32, proof: &[[u8; 32]]) -> Result<(), DLUError> {
+        let batch = self.batches.get(batch as usize).ok_or(DLUError::InvalidArchiveProof)?;
+        if index >= batch.count as u32 || !verify(&batch.root, &leaf(entity, data_hash), index, proof) {
+            return Err(DLUError::InvalidArchiveProof);
+        }
+        Ok(())
+    }
+
+    /// Serializes the history into a vector of bytes.
+    pub fn serialize(&self) -> Result<Vec<u8>, DLUError> {
+        self.try_to_vec().map_err(|_| DLUError::SerializationFailed)
+    }
+
+    /// Deserializes a history from a slice of bytes.
+    pub fn deserialize(input: &mut &[u8]) -> Result<Self, DLUError> {
+        // A fresh history account is all zeros, which reads as no batches.
+        <Self as BorshDeserialize>::deserialize(input).map_err(|_| DLUError::DeserializationFailed)
+    }
+}
+
+/// Returns the hash of an entity's account data as it was archived.
+pub fn data_hash(data: &[u8]) -> [u8; 32] {
+    hash(data).to_bytes()
+}
+
+/// Returns the leaf an archived entity is committed to with.
+pub fn leaf(entity: &Pubkey, data_hash: &[u8; 32]) -> [u8; 32] {
+    // Leaves and inner nodes are prefixed apart, so no inner node passes for a leaf.
+    hashv(&[&[0], entity.as_ref(), data_hash]).to_bytes()
+}
+
+fn node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    hashv(&[&[1], left, right]).to_bytes()
+}
+
+/// Returns the next level up of a tree.
+fn parents(level: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    level.chunks(2).map(|pair| node(&pair[0], pair.get(1).unwrap_or(&EMPTY_NODE))).collect()
+}
+
+/// Returns the Merkle root of the given leaves.
+pub fn merkle_root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = parents(&level);
+    }
+    level.first().copied().unwrap_or(EMPTY_NODE)
+}
+
+/// Returns the proof of the leaf at `index`, from the leaf up.
+pub fn merkle_proof(leaves: &[[u8; 32]], mut index: usize) -> Vec<[u8; 32]> {
+    let mut proof = Vec::new();
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        proof.push(level.get(index ^ 1).copied().unwrap_or(EMPTY_NODE));
+        level = parents(&level);
+        index /= 2;
+    }
+    proof
+}
+
+/// Returns true if `proof`, from the leaf up, shows `leaf` at `index` under `root`.
+pub fn verify(root: &[u8; 32], leaf: &[u8; 32], mut index: u32, proof: &[[u8; 32]]) -> bool {
+    let mut current = *leaf;
+    for sibling in proof {
+        current = if index % 2 == 0 { node(&current, sibling) } else { node(sibling, &current) };
+        index /= 2;
+    }
+    index == 0 && current == *root
+}
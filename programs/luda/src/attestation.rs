@@ -0,0 +1,94 @@
+use solana_program::pubkey::Pubkey;
+use solana_program::borsh::{BorshSerialize, BorshDeserialize};
+use crate::errors::DLUError;
+
+/// Most attestors the registry can hold.
+pub const MAX_ATTESTORS: usize = 16;
+
+/// Keys governance approved to attest users' identity checks.
+///
+/// Lives at the PDA `["attestors"]`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default)]
+pub struct AttestorRegistry {
+    pub attestors: Vec<Pubkey>,
+}
+
+impl AttestorRegistry {
+    /// Approves an attestor.
+    pub fn add(&mut self, attestor: Pubkey) -> Result<(), DLUError> {
+        if self.contains(&attestor) {
+            return Err(DLUError::AttestorAlreadyRegistered);
+        }
+        if self.attestors.len() >= MAX_ATTESTORS {
+            return Err(DLUError::AttestorRegistryFull);
+        }
+        self.attestors.push(attestor);
+        Ok(())
+    }
+
+    /// Withdraws an attestor's approval, which also voids every attestation they wrote.
+    pub fn remove(&mut self, attestor: &Pubkey) -> Result<(), DLUError> {
+        let position = self.attestors.iter().position(|a| a == attestor).ok_or(DLUError::AttestorNotRegistered)?;
+        self.attestors.swap_remove(position);
+        Ok(())
+    }
+
+    /// Returns true if the attestor is approved.
+    pub fn contains(&self, attestor: &Pubkey) -> bool {
+        self.attestors.contains(attestor)
+    }
+
+    /// Serializes the registry into a vector of bytes.
+    pub fn serialize(&self) -> Result<Vec<u8>, DLUError> {
+        self.try_to_vec().map_err(|_| DLUError::SerializationFailed)
+    }
+
+    /// Deserializes a registry from a slice of bytes.
+    pub fn deserialize(input: &mut &[u8]) -> Result<Self, DLUError> {
+        // Accounts are sized for a full registry, so the tail may be zero padding.
+        <Self as BorshDeserialize>::deserialize(input).map_err(|_| DLUError::DeserializationFailed)
+    }
+}
+
+/// An approved attestor's statement that a user passed their identity checks.
+///
+/// Deals paying more than the config's KYC threshold can only be accepted while both
+/// parties carry a valid attestation: one that has not expired and whose attestor is still
+/// approved. Attestors renew an attestation by writing a later expiry and revoke it by
+/// writing one that has passed.
+///
+/// Lives at the PDA `["attestation", owner_pubkey]`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct Attestation {
+    pub user: Pubkey,  // Key of the attested user account.
+    pub attestor: Pubkey,
+    pub expires_at: i64,
+}
+
+impl Attestation {
+    /// Records an attestation written by an approved attestor.
+    pub fn attest(registry: &AttestorRegistry, attestor: Pubkey, user: Pubkey, expires_at: i64) -> Result<Self, DLUError> {
+        if !registry.contains(&attestor) {
+            return Err(DLUError::AttestorNotRegistered);
+        }
+        Ok(Attestation { user, attestor, expires_at })
+    }
+
+    /// Checks that the attestation verifies `user` at `now`.
+    pub fn check(&self, registry: &AttestorRegistry, user: &Pubkey, now: i64) -> Result<(), DLUError> {
+        if self.user != *user || now >= self.expires_at || !registry.contains(&self.attestor) {
+            return Err(DLUError::AttestationRequired);
+        }
+        Ok(())
+    }
+
+    /// Serializes the attestation into a vector of bytes.
+    pub fn serialize(&self) -> Result<Vec<u8>, DLUError> {
+        self.try_to_vec().map_err(|_| DLUError::SerializationFailed)
+    }
+
+    /// Deserializes an attestation from a slice of bytes.
+    pub fn deserialize(input: &mut &[u8]) -> Result<Self, DLUError> {
+        <Self as BorshDeserialize>::deserialize(input).map_err(|_| DLUError::DeserializationFailed)
+    }
+}
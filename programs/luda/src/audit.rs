@@ -0,0 +1,49 @@
+use solana_program::pubkey::Pubkey;
+use solana_program::borsh::{BorshSerialize, BorshDeserialize};
+use crate::deal::DealStatus;
+
+/// Status transitions a deal's audit log keeps before the newest start replacing the oldest.
+pub const MAX_AUDIT_ENTRIES: usize = 16;
+
+/// A status a deal moved into.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct AuditEntry {
+    pub status: DealStatus,
+    pub slot: u64,
+    pub actor: Pubkey,  // Signer of the instruction that moved the deal.
+    pub amount: u64,  // Held in escrow for the deal once it moved.
+}
+
+/// Status transitions of a deal, so auditors and arbiters can reconstruct what happened
+/// to it without archived transaction history.
+///
+/// The program appends an entry whenever it saves a deal whose status changed, the
+/// listing included, and never rewrites one. The log is a ring of the
+/// `MAX_AUDIT_ENTRIES` most recent transitions, and `recorded` counts every transition
+/// ever appended, so readers can tell how many rolled out.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct AuditLog {
+    pub recorded: u32,
+    pub entries: Vec<AuditEntry>,
+}
+
+impl AuditLog {
+    /// Appends an entry, replacing the oldest one once the log is full.
+    pub fn record(&mut self, entry: AuditEntry) {
+        if self.entries.len() < MAX_AUDIT_ENTRIES {
+            self.entries.push(entry);
+        } else {
+            self.entries[self.recorded as usize % MAX_AUDIT_ENTRIES] = entry;
+        }
+        self.recorded = self.recorded.saturating_add(1);
+    }
+
+    /// Returns the entries the log still holds, oldest first.
+    pub fn chronological(&self) -> Vec<AuditEntry> {
+        if self.entries.len() < MAX_AUDIT_ENTRIES {
+            return self.entries.clone();
+        }
+        let (newer, older) = self.entries.split_at(self.recorded as usize % MAX_AUDIT_ENTRIES);
+        older.iter().chain(newer).copied().collect()
+    }
+}
@@ -0,0 +1,154 @@
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    system_program,
+    sysvar::Sysvar,
+};
+use solana_program::borsh::{BorshSerialize, BorshDeserialize};
+use spl_token::state::Mint;
+use crate::errors::DLUError;
+use crate::user::UserStatus;
+
+/// Id of the Metaplex Token Metadata program that describes the badge mints.
+pub const TOKEN_METADATA_PROGRAM_ID: Pubkey = pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+/// Tag of Token Metadata's `CreateMetadataAccountV3` instruction.
+const CREATE_METADATA_ACCOUNT_V3: u8 = 33;
+/// Symbol of every badge in its Metaplex metadata.
+pub const BADGE_SYMBOL: &str = "LUDA";
+
+/// Reputation tiers a user can hold a badge for.
+///
+/// Each tier has its own SPL mint at the PDA `["badge_mint", tier]`, which is also its own
+/// mint authority and the update authority of its Metaplex metadata; the tier's first badge
+/// creates both. A user's badge is a token account at the PDA `["badge", user_pubkey, tier]`
+/// owned by itself, so only the program can move it: the badge can't be transferred, but
+/// wallets and other programs can verify it by address.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, PartialEq, Debug)]
+pub enum BadgeTier {
+    Reliable,
+    Credible,
+}
+
+impl BadgeTier {
+    /// Returns true if a user with the given status should hold this tier's badge.
+    pub fn is_earned_by(self, status: UserStatus) -> bool {
+        match self {
+            BadgeTier::Reliable => status == UserStatus::Reliable,
+            BadgeTier::Credible => status == UserStatus::Credible,
+        }
+    }
+
+    /// Returns the name of the tier's badge in its Metaplex metadata.
+    pub fn badge_name(self) -> &'static str {
+        match self {
+            BadgeTier::Reliable => "LUDA Reliable",
+            BadgeTier::Credible => "LUDA Credible",
+        }
+    }
+}
+
+/// Returns the address of the Metaplex metadata account of a badge mint.
+pub fn metadata_address(mint: &Pubkey) -> Pubkey {
+    let seeds: &[&[u8]] = &[b"metadata", TOKEN_METADATA_PROGRAM_ID.as_ref(), mint.as_ref()];
+    Pubkey::find_program_address(seeds, &TOKEN_METADATA_PROGRAM_ID).0
+}
+
+/// Creates a tier's mint at its PDA, without decimals and with the mint as its own mint
+/// authority. The payer funds its rent.
+pub fn create_badge_mint<'a>(payer_info: &AccountInfo<'a>, mint_info: &AccountInfo<'a>, mint_seeds: &[&[u8]]) -> ProgramResult {
+    let create_instruction = system_instruction::create_account(
+        payer_info.key,
+        mint_info.key,
+        Rent::get()?.minimum_balance(Mint::LEN),
+        Mint::LEN as u64,
+        &spl_token::id(),
+    );
+    invoke_signed(&create_instruction, &[payer_info.clone(), mint_info.clone()], &[mint_seeds])?;
+
+    let initialize_instruction = spl_token::instruction::initialize_mint2(&spl_token::id(), mint_info.key, mint_info.key, None, 0)?;
+    invoke(&initialize_instruction, &[mint_info.clone()])
+}
+
+/// Creates the Metaplex metadata of a tier's mint through Token Metadata, signing as the
+/// mint. The metadata is immutable and names the tier on chain, with no off-chain URI.
+pub fn create_badge_metadata<'a>(
+    tier: BadgeTier,
+    metadata_info: &AccountInfo<'a>,
+    mint_info: &AccountInfo<'a>,
+    payer_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    metadata_program_info: &AccountInfo<'a>,
+    mint_seeds: &[&[u8]],
+) -> ProgramResult {
+    let mut data = vec![CREATE_METADATA_ACCOUNT_V3];
+    (tier.badge_name().to_string(), BADGE_SYMBOL.to_string(), String::new(), 0u16)
+        .serialize(&mut data)
+        .map_err(|_| DLUError::SerializationFailed)?;
+    // No creators, collection or uses, not mutable, and no collection details.
+    data.extend([0, 0, 0, 0, 0]);
+
+    let instruction = Instruction {
+        program_id: TOKEN_METADATA_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*metadata_info.key, false),
+            AccountMeta::new_readonly(*mint_info.key, false),
+            AccountMeta::new_readonly(*mint_info.key, true),  // Mint authority
+            AccountMeta::new(*payer_info.key, true),
+            AccountMeta::new_readonly(*mint_info.key, true),  // Update authority
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data,
+    };
+    let infos = [
+        metadata_info.clone(),
+        mint_info.clone(),
+        payer_info.clone(),
+        system_program_info.clone(),
+        metadata_program_info.clone(),
+    ];
+    invoke_signed(&instruction, &infos, &[mint_seeds])
+}
+
+/// Mints a badge into a user's badge account, signing as the tier's mint.
+pub fn mint_badge(
+    mint_info: &AccountInfo,
+    badge_account_info: &AccountInfo,
+    mint_seeds: &[&[u8]],
+) -> Result<(), ProgramError> {
+    let mint_instruction = spl_token::instruction::mint_to(
+        &spl_token::id(),
+        mint_info.key,
+        badge_account_info.key,
+        mint_info.key,
+        &[],
+        1,
+    )?;
+
+    invoke_signed(&mint_instruction, &[mint_info.clone(), badge_account_info.clone()], &[mint_seeds])
+}
+
+/// Burns the badge held in a user's badge account, signing as the badge account.
+pub fn burn_badge(
+    badge_account_info: &AccountInfo,
+    mint_info: &AccountInfo,
+    badge_seeds: &[&[u8]],
+) -> Result<(), ProgramError> {
+    let burn_instruction = spl_token::instruction::burn(
+        &spl_token::id(),
+        badge_account_info.key,
+        mint_info.key,
+        badge_account_info.key,
+        &[],
+        1,
+    )?;
+
+    invoke_signed(&burn_instruction, &[badge_account_info.clone(), mint_info.clone()], &[badge_seeds])
+}
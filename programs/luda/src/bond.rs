@@ -0,0 +1,126 @@
+use solana_program::pubkey::Pubkey;
+use solana_program::borsh::{BorshSerialize, BorshDeserialize};
+use crate::addressing::EntityType;
+use crate::errors::DLUError;
+use crate::time;
+
+/// Represents the current status of a fail bond.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, PartialEq, Debug)]
+pub enum BondStatus {
+    Posted,
+    Disputed,
+    Returned,
+    Forfeited,
+}
+
+/// Bond posted by whoever fails a deal or shipment.
+///
+/// The accused party may dispute the fail within `FAIL_DISPUTE_WINDOW`. An undisputed
+/// bond is returned to the claimant once the window has passed; a disputed one goes to
+/// the claimant if the fail is found justified and to the accused otherwise.
+///
+/// Lives at the PDA `["bond", entity_pubkey]`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct FailBond {
+    pub entity_type: EntityType,
+    pub entity: Pubkey,    // Offer, request, or shipment that was failed.
+    pub claimant: Pubkey,  // User who failed the entity and posted the bond.
+    pub accused: Pubkey,   // User penalized by the fail.
+    pub amount: u64,
+    pub posted_at: i64,
+    pub status: BondStatus,
+}
+
+impl FailBond {
+    /// Records a bond posted at `now`.
+    pub fn post(
+        entity_type: EntityType,
+        entity: Pubkey,
+        claimant: Pubkey,
+        accused: Pubkey,
+        amount: u64,
+        now: i64,
+    ) -> Self {
+        FailBond {
+            entity_type,
+            entity,
+            claimant,
+            accused,
+            amount,
+            posted_at: now,
+            status: BondStatus::Posted,
+        }
+    }
+
+    /// Returns true while the accused can still dispute the fail.
+    pub fn in_dispute_window(&self, now: i64) -> bool {
+        now <= self.posted_at.saturating_add(time::FAIL_DISPUTE_WINDOW)
+    }
+
+    /// Returns true once the fail stands: it was found justified, or the dispute window
+    /// passed without the accused disputing it.
+    pub fn fail_upheld(&self, now: i64) -> bool {
+        match self.status {
+            BondStatus::Returned => true,
+            BondStatus::Posted => !self.in_dispute_window(now),
+            BondStatus::Disputed | BondStatus::Forfeited => false,
+        }
+    }
+
+    /// Disputes the fail on behalf of the accused.
+    pub fn dispute(&mut self, accused: &Pubkey, now: i64) -> Result<(), DLUError> {
+        if self.status != BondStatus::Posted {
+            return Err(DLUError::BondSettled);
+        }
+        if *accused != self.accused {
+            return Err(DLUError::NotAuthorized);
+        }
+        if !self.in_dispute_window(now) {
+            return Err(DLUError::DisputeWindowClosed);
+        }
+
+        self.status = BondStatus::Disputed;
+        Ok(())
+    }
+
+    /// Settles a disputed bond, returning the user it is paid out to.
+    pub fn resolve(&mut self, fail_justified: bool) -> Result<Pubkey, DLUError> {
+        if self.status != BondStatus::Disputed {
+            return Err(DLUError::BondNotDisputed);
+        }
+
+        if fail_justified {
+            self.status = BondStatus::Returned;
+            Ok(self.claimant)
+        } else {
+            self.status = BondStatus::Forfeited;
+            Ok(self.accused)
+        }
+    }
+
+    /// Returns an undisputed bond to the claimant once the dispute window has passed.
+    pub fn reclaim(&mut self, claimant: &Pubkey, now: i64) -> Result<(), DLUError> {
+        if self.status != BondStatus::Posted {
+            return Err(DLUError::BondSettled);
+        }
+        if *claimant != self.claimant {
+            return Err(DLUError::NotAuthorized);
+        }
+        if self.in_dispute_window(now) {
+            return Err(DLUError::DisputeWindowOpen);
+        }
+
+        self.status = BondStatus::Returned;
+        Ok(())
+    }
+
+    /// Serializes the bond into a vector of bytes.
+    pub fn serialize(&self) -> Result<Vec<u8>, DLUError> {
+        self.try_to_vec().map_err(|_| DLUError::SerializationFailed)
+    }
+
+    /// Deserializes a bond from a slice of bytes.
+    pub fn deserialize(input: &mut &[u8]) -> Result<Self, DLUError> {
+        <Self as BorshDeserialize>::deserialize(input).map_err(|_| DLUError::DeserializationFailed)
+    }
+}
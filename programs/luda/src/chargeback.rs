@@ -0,0 +1,78 @@
+use solana_program::borsh::{BorshSerialize, BorshDeserialize};
+use crate::errors::DLUError;
+
+/// Longest chargeback window governance can set, in seconds.
+pub const MAX_CHARGEBACK_WINDOW: i64 = 30 * 86_400;
+
+/// Represents the current status of a chargeback.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, PartialEq, Debug)]
+pub enum ChargebackStatus {
+    Open,      // The window is open and the buyer has not claimed.
+    Claimed,   // The buyer claimed, freezing the insurance until arbitration rules.
+    Released,  // Paid out to the seller.
+    Upheld,    // Paid out to the buyer, the claim being upheld.
+}
+
+/// Seller's insurance of a completed offer kept in escrow while the buyer can still
+/// charge the deal back.
+///
+/// On completion the seller is paid, but their insurance stays in escrow for the window
+/// governance configured. Within it the buyer can open a chargeback claim, freezing the
+/// insurance until arbitration rules on it; otherwise the seller claims it once the window
+/// has passed.
+#[derive(BorshSerialize, BorshDeserialize, Clone, PartialEq, Debug)]
+pub struct Chargeback {
+    pub insurance: u64,
+    pub closes_at: i64,
+    pub status: ChargebackStatus,
+}
+
+impl Chargeback {
+    /// Opens a chargeback window of `window` seconds over the seller's `insurance` at `now`.
+    pub fn open(insurance: u64, now: i64, window: i64) -> Self {
+        Chargeback { insurance, closes_at: now.saturating_add(window), status: ChargebackStatus::Open }
+    }
+
+    /// Freezes the insurance on the buyer's claim, while the window is open.
+    pub fn claim(&mut self, now: i64) -> Result<(), DLUError> {
+        if self.status != ChargebackStatus::Open {
+            return Err(DLUError::ChargebackNotOpen);
+        }
+        if now >= self.closes_at {
+            return Err(DLUError::DisputeWindowClosed);
+        }
+        self.status = ChargebackStatus::Claimed;
+        Ok(())
+    }
+
+    /// Releases the insurance to the seller once the window has closed without a claim,
+    /// returning the amount.
+    pub fn release(&mut self, now: i64) -> Result<u64, DLUError> {
+        if self.status != ChargebackStatus::Open {
+            return Err(DLUError::ChargebackNotOpen);
+        }
+        if now < self.closes_at {
+            return Err(DLUError::DisputeWindowOpen);
+        }
+        self.status = ChargebackStatus::Released;
+        Ok(self.insurance)
+    }
+
+    /// Settles a claim, paying the insurance to the buyer when it is `upheld` and back to
+    /// the seller otherwise. Returns the amount.
+    pub fn resolve(&mut self, upheld: bool) -> Result<u64, DLUError> {
+        if self.status != ChargebackStatus::Claimed {
+            return Err(DLUError::ChargebackNotClaimed);
+        }
+        self.status = if upheld { ChargebackStatus::Upheld } else { ChargebackStatus::Released };
+        Ok(self.insurance)
+    }
+
+    /// Returns the amount still in escrow.
+    pub fn escrowed_amount(&self) -> u64 {
+        match self.status {
+            ChargebackStatus::Open | ChargebackStatus::Claimed => self.insurance,
+            _ => 0,
+        }
+    }
+}
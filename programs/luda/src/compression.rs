@@ -0,0 +1,116 @@
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    hash::hash,
+    instruction::{AccountMeta, Instruction},
+    program::invoke_signed,
+    pubkey,
+    pubkey::Pubkey,
+};
+use solana_program::borsh::{BorshSerialize, BorshDeserialize};
+use crate::errors::DLUError;
+use crate::location::Location;
+
+/// Id of the SPL Account Compression program that keeps the trees of compressed listings.
+pub const ACCOUNT_COMPRESSION_PROGRAM_ID: Pubkey = pubkey!("cmtDvXumGCrqC1Age74AVPhSRVXJMd8PJS91L8KbNCK");
+/// Id of the SPL Noop program the compression program logs tree changes through.
+pub const NOOP_PROGRAM_ID: Pubkey = pubkey!("noopb9bkMVfRPU8AsbpTUg8AQkHtKwMYZiFUjNRtMmV");
+
+/// Leaf of a listing that was accepted or canceled.
+pub const EMPTY_LEAF: [u8; 32] = [0; 32];
+
+/// A described offer listed as a leaf of a listing tree instead of in its own account.
+///
+/// The tree only stores the leaf hash, so listing costs no rent; indexers rebuild the
+/// listing from the `CompressedOfferListed` event. The seller's insurance is escrowed as
+/// for any offer. Accepting the listing proves the leaf against the tree, empties it, and
+/// materializes the offer into a full account, from where it follows the usual lifecycle.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct CompressedOffer {
+    pub seller: Pubkey,
+    pub id: u64,
+    pub goodsorservice_name: String,
+    pub goodsorservice_description: String,
+    pub payment: u64,
+    pub payment_mint: Pubkey,
+    pub meeting_point: Location,
+    pub meeting_datetime: i64,
+    pub listed_slot: u64,  // Also keeps identical listings of a seller apart.
+}
+
+impl CompressedOffer {
+    /// Returns the leaf hash the listing is stored as.
+    pub fn leaf(&self) -> Result<[u8; 32], DLUError> {
+        let data = self.try_to_vec().map_err(|_| DLUError::SerializationFailed)?;
+        Ok(hash(&data).to_bytes())
+    }
+
+    /// Deserializes a listing from a slice of bytes, such as the Borsh-encoded listing of
+    /// its `CompressedOfferListed` event.
+    pub fn deserialize(input: &mut &[u8]) -> Result<Self, DLUError> {
+        <Self as BorshDeserialize>::deserialize(input).map_err(|_| DLUError::DeserializationFailed)
+    }
+}
+
+/// Accounts every call into the compression program on a listing tree takes.
+pub struct TreeAccounts<'a, 'b> {
+    pub tree: &'a AccountInfo<'b>,
+    pub authority: &'a AccountInfo<'b>,  // Tree authority PDA, which the program signs as.
+    pub compression_program: &'a AccountInfo<'b>,
+    pub noop_program: &'a AccountInfo<'b>,
+}
+
+/// Initializes an allocated listing tree, with the tree authority PDA as its authority.
+pub fn init_tree(accounts: &TreeAccounts, authority_seeds: &[&[u8]], max_depth: u32, max_buffer_size: u32) -> ProgramResult {
+    let mut data = hash(b"global:init_empty_merkle_tree").to_bytes()[..8].to_vec();
+    (max_depth, max_buffer_size).serialize(&mut data).map_err(|_| DLUError::SerializationFailed)?;
+    invoke_tree(accounts, &[], authority_seeds, data)
+}
+
+/// Appends a leaf to a listing tree.
+pub fn append_leaf(accounts: &TreeAccounts, authority_seeds: &[&[u8]], leaf: [u8; 32]) -> ProgramResult {
+    let mut data = hash(b"global:append").to_bytes()[..8].to_vec();
+    leaf.serialize(&mut data).map_err(|_| DLUError::SerializationFailed)?;
+    invoke_tree(accounts, &[], authority_seeds, data)
+}
+
+/// Empties the leaf at `index` of a listing tree. The compression program fails unless
+/// the `proof` nodes show that `leaf` sits there under `root`, or under a root the tree
+/// has changed from since.
+pub fn remove_leaf<'b>(
+    accounts: &TreeAccounts<'_, 'b>,
+    proof: &[AccountInfo<'b>],
+    authority_seeds: &[&[u8]],
+    root: [u8; 32],
+    leaf: [u8; 32],
+    index: u32,
+) -> ProgramResult {
+    let mut data = hash(b"global:replace_leaf").to_bytes()[..8].to_vec();
+    (root, leaf, EMPTY_LEAF, index).serialize(&mut data).map_err(|_| DLUError::SerializationFailed)?;
+    invoke_tree(accounts, proof, authority_seeds, data)
+}
+
+/// Calls the compression program on a listing tree, signing as its authority.
+fn invoke_tree<'b>(
+    accounts: &TreeAccounts<'_, 'b>,
+    proof: &[AccountInfo<'b>],
+    authority_seeds: &[&[u8]],
+    data: Vec<u8>,
+) -> ProgramResult {
+    let mut metas = vec![
+        AccountMeta::new(*accounts.tree.key, false),
+        AccountMeta::new_readonly(*accounts.authority.key, true),
+        AccountMeta::new_readonly(*accounts.noop_program.key, false),
+    ];
+    metas.extend(proof.iter().map(|node| AccountMeta::new_readonly(*node.key, false)));
+    let instruction = Instruction { program_id: ACCOUNT_COMPRESSION_PROGRAM_ID, accounts: metas, data };
+
+    let mut infos = vec![
+        accounts.tree.clone(),
+        accounts.authority.clone(),
+        accounts.noop_program.clone(),
+        accounts.compression_program.clone(),
+    ];
+    infos.extend(proof.iter().cloned());
+    invoke_signed(&instruction, &infos, &[authority_seeds])
+}
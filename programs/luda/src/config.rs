@@ -0,0 +1,375 @@
+use solana_program::pubkey::Pubkey;
+use solana_program::borsh::{BorshSerialize, BorshDeserialize};
+use crate::chargeback::MAX_CHARGEBACK_WINDOW;
+use crate::errors::DLUError;
+use crate::pricing::ShippingRates;
+use crate::safe_math;
+use crate::time;
+use crate::user::{Role, User, UserStatus};
+
+// Capability bits gating features that can be toggled per environment.
+pub const CAP_ESCROW_MIGRATION: u64 = 1 << 0;
+pub const CAP_AUCTIONS: u64 = 1 << 1;
+pub const CAP_SWAPS: u64 = 1 << 2;
+pub const CAP_CROSS_CHAIN: u64 = 1 << 3;
+pub const CAP_BURN_PENALTIES: u64 = 1 << 4;  // Burn fail penalties instead of collecting them.
+pub const CAP_TIMELOCKED_GOVERNANCE: u64 = 1 << 5;  // Update the config only through the timelock queue.
+pub const CAP_TEST_CLUSTER: u64 = 1 << 6;  // Running on devnet or testnet, where the DLU faucet is open.
+
+/// Bond a claimant posts when failing a deal or shipment, until governance changes it.
+pub const DEFAULT_FAIL_BOND: u64 = 1_000_000;
+
+/// Highest protocol fee governance may set, in basis points of the payment.
+pub const MAX_FEE_BPS: u16 = 1_000;
+
+/// Shipment payment above which the carrier needs an active stake, until governance changes it.
+pub const DEFAULT_STAKE_THRESHOLD: u64 = 100_000_000;
+
+/// Stake a carrier needs to accept shipments above the threshold, until governance changes it.
+pub const DEFAULT_MIN_STAKE: u64 = 10_000_000;
+
+/// Slots after which a deal's weight in a user's reputation halves, until governance changes it.
+pub const DEFAULT_REPUTATION_HALF_LIFE: u64 = 180 * time::SLOTS_PER_DAY;
+
+/// Share of the offender's insurance paid to the wronged party on a fail, in basis points,
+/// until governance changes it. The rest goes to the penalty account.
+pub const DEFAULT_PENALTY_SHARE_BPS: u16 = 5_000;
+
+/// DLU paid out of an expired escrow to whoever cranks its expiry, until governance changes it.
+pub const DEFAULT_CRANK_BOUNTY: u64 = 100_000;
+
+/// Seconds after completion the buyer of an offer can charge it back, until governance
+/// changes it. Completions are final until then.
+pub const DEFAULT_CHARGEBACK_WINDOW: i64 = 0;
+
+/// Stake a user needs to register as an arbiter, until governance changes it.
+pub const DEFAULT_ARBITER_MIN_STAKE: u64 = 50_000_000;
+
+/// Paid from the treasury to an arbiter for each dispute they rule on, until governance
+/// changes it.
+pub const DEFAULT_ARBITRATION_FEE: u64 = 500_000;
+
+/// Bond the losing party posts to appeal a ruling, until governance changes it.
+pub const DEFAULT_APPEAL_BOND: u64 = 2_000_000;
+
+/// Deal payment above which both parties need a KYC attestation, until governance changes
+/// it. No deal needs one until then.
+pub const DEFAULT_KYC_THRESHOLD: u64 = u64::MAX;
+
+/// Share of the protocol fees of referred users paid to their referrer, in basis points,
+/// until governance changes it.
+pub const DEFAULT_REFERRAL_SHARE_BPS: u16 = 2_000;
+
+/// Seconds over which the completion reward halves, until governance changes it.
+pub const DEFAULT_COMPLETION_REWARD_HALF_LIFE: u64 = 365 * time::SECONDS_PER_DAY as u64;
+
+/// Program-wide settings controlled by the governance key.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct Config {
+    pub governance: Pubkey,  // Key allowed to change the config.
+    pub tvl_cap: u64,        // Maximum amount of DLU that may be locked in escrow at once.
+    pub tvl_epoch: u32,      // Incremented every time governance changes the cap.
+    pub capabilities: u64,   // Bitmap of enabled CAP_* features.
+    pub terms_hash: [u8; 32],  // Hash of the current terms-of-use document.
+    pub terms_version: u32,  // Incremented every time new terms are published.
+    pub fail_bond: u64,      // Bond required to fail a deal or shipment.
+    pub fee_bps: u16,        // Protocol fee taken from the payment of completed deals and shipments.
+    pub fee_exempt_credible: bool,  // Whether Credible payees are spared the fee.
+    pub penalty_share_bps: u16,  // Share of the offender's insurance paid to the wronged party on a fail.
+    pub stake_threshold: u64,  // Shipment payment above which the carrier must be staked.
+    pub min_stake: u64,      // Stake required above the threshold, and the most slashed per failed shipment.
+    pub reputation_half_life: u64,  // Slots after which an operation's weight in reputation halves.
+    pub crank_bounty: u64,   // Paid to keepers cranking the expiry of overdue entities.
+    pub chargeback_window: i64,  // Seconds the seller's insurance stays in escrow after an offer completes.
+    pub arbiter_min_stake: u64,  // Stake required to register as an arbiter.
+    pub arbitration_fee: u64,  // Paid to arbiters per ruling, as far as the treasury covers it.
+    pub appeal_bond: u64,    // Posted to appeal a ruling, and the most slashed from an overturned arbiter.
+    pub kyc_threshold: u64,  // Deal payment above which both parties must be attested to accept.
+    pub referral_share_bps: u16,  // Share of a referred payee's fee credited to their referrer.
+    pub penalty_burn_bps: u16,  // Share of each penalty pool distribution burned instead of paid to stakers.
+    pub completion_reward: u64,  // Paid to each party of a completion when rewards start; zero pays none.
+    pub completion_reward_half_life: u64,  // Seconds over which the completion reward halves.
+    pub completion_emission_cap: u64,  // Most ever paid out in completion rewards per mint.
+    pub shipping_rates: ShippingRates,  // Rates suggested shipment payments are computed from.
+}
+
+impl Config {
+    /// Creates a new config with the initial TVL cap and terms of use.
+    pub fn new(governance: Pubkey, tvl_cap: u64, terms_hash: [u8; 32]) -> Self {
+        Config {
+            governance,
+            tvl_cap,
+            tvl_epoch: 0,
+            capabilities: 0,  // Every gated feature starts disabled.
+            terms_hash,
+            terms_version: 1,
+            fail_bond: DEFAULT_FAIL_BOND,
+            fee_bps: 0,
+            fee_exempt_credible: false,
+            penalty_share_bps: DEFAULT_PENALTY_SHARE_BPS,
+            stake_threshold: DEFAULT_STAKE_THRESHOLD,
+            min_stake: DEFAULT_MIN_STAKE,
+            reputation_half_life: DEFAULT_REPUTATION_HALF_LIFE,
+            crank_bounty: DEFAULT_CRANK_BOUNTY,
+            chargeback_window: DEFAULT_CHARGEBACK_WINDOW,
+            arbiter_min_stake: DEFAULT_ARBITER_MIN_STAKE,
+            arbitration_fee: DEFAULT_ARBITRATION_FEE,
+            appeal_bond: DEFAULT_APPEAL_BOND,
+            kyc_threshold: DEFAULT_KYC_THRESHOLD,
+            referral_share_bps: DEFAULT_REFERRAL_SHARE_BPS,
+            penalty_burn_bps: 0,  // Penalties all go to stakers until governance burns a share.
+            completion_reward: 0,  // Completions earn nothing until governance schedules rewards.
+            completion_reward_half_life: DEFAULT_COMPLETION_REWARD_HALF_LIFE,
+            completion_emission_cap: 0,
+            shipping_rates: ShippingRates::default(),  // Nothing is suggested until governance sets rates.
+        }
+    }
+
+    /// Publishes a new terms-of-use document, requiring users to accept it again before listing.
+    pub fn publish_terms(&mut self, authority: &Pubkey, terms_hash: [u8; 32]) -> Result<(), DLUError> {
+        self.check_governance(authority)?;
+        self.terms_hash = terms_hash;
+        self.terms_version += 1;
+        Ok(())
+    }
+
+    /// Ensures the given hash matches the current terms, returning their version.
+    pub fn check_terms_hash(&self, terms_hash: &[u8; 32]) -> Result<u32, DLUError> {
+        if *terms_hash != self.terms_hash {
+            return Err(DLUError::TermsMismatch);
+        }
+        Ok(self.terms_version)
+    }
+
+    /// Ensures a user who accepted the given terms version may keep listing.
+    pub fn check_terms_accepted(&self, accepted_version: u32) -> Result<(), DLUError> {
+        if accepted_version != self.terms_version {
+            return Err(DLUError::TermsNotAccepted);
+        }
+        Ok(())
+    }
+
+    /// Sets the bond required from claimants failing a deal or shipment.
+    pub fn set_fail_bond(&mut self, authority: &Pubkey, fail_bond: u64) -> Result<(), DLUError> {
+        self.check_governance(authority)?;
+        self.fail_bond = fail_bond;
+        Ok(())
+    }
+
+    /// Sets the protocol fee rate and whether Credible payees are exempt from it.
+    pub fn set_fee(&mut self, authority: &Pubkey, fee_bps: u16, exempt_credible: bool) -> Result<(), DLUError> {
+        self.check_governance(authority)?;
+        if fee_bps > MAX_FEE_BPS {
+            return Err(DLUError::InvalidFeeRate);
+        }
+        self.fee_bps = fee_bps;
+        self.fee_exempt_credible = exempt_credible;
+        Ok(())
+    }
+
+    /// Returns the protocol fee owed on a payment released to the given payee, whose
+    /// reputation in the role they are paid for decides the exemption.
+    pub fn fee_for(&self, payment: u64, payee: &User, role: Role) -> Result<u64, DLUError> {
+        if self.fee_exempt_credible && payee.role_status(role) == UserStatus::Credible {
+            return Ok(0);
+        }
+        safe_math::bps(payment, self.fee_bps)
+    }
+
+    /// Sets how fast past operations fade from users' reputation.
+    pub fn set_reputation_half_life(&mut self, authority: &Pubkey, half_life: u64) -> Result<(), DLUError> {
+        self.check_governance(authority)?;
+        if half_life == 0 {
+            return Err(DLUError::InvalidHalfLife);
+        }
+        self.reputation_half_life = half_life;
+        Ok(())
+    }
+
+    /// Sets the bounty paid to keepers for cranking an expiry; it is capped per entity at
+    /// the insurance.
+    pub fn set_crank_bounty(&mut self, authority: &Pubkey, bounty: u64) -> Result<(), DLUError> {
+        self.check_governance(authority)?;
+        self.crank_bounty = bounty;
+        Ok(())
+    }
+
+    /// Sets how long after completion the buyer of an offer can charge it back; zero makes
+    /// completions final.
+    pub fn set_chargeback_window(&mut self, authority: &Pubkey, window: i64) -> Result<(), DLUError> {
+        self.check_governance(authority)?;
+        if !(0..=MAX_CHARGEBACK_WINDOW).contains(&window) {
+            return Err(DLUError::InvalidChargebackWindow);
+        }
+        self.chargeback_window = window;
+        Ok(())
+    }
+
+    /// Sets the bond posted to appeal a ruling.
+    pub fn set_appeal_bond(&mut self, authority: &Pubkey, bond: u64) -> Result<(), DLUError> {
+        self.check_governance(authority)?;
+        self.appeal_bond = bond;
+        Ok(())
+    }
+
+    /// Sets the deal payment above which both parties need a valid KYC attestation.
+    pub fn set_kyc_threshold(&mut self, authority: &Pubkey, threshold: u64) -> Result<(), DLUError> {
+        self.check_governance(authority)?;
+        self.kyc_threshold = threshold;
+        Ok(())
+    }
+
+    /// Sets the stake arbiters register with and the fee they earn per ruling.
+    pub fn set_arbitration_terms(&mut self, authority: &Pubkey, min_stake: u64, fee: u64) -> Result<(), DLUError> {
+        self.check_governance(authority)?;
+        self.arbiter_min_stake = min_stake;
+        self.arbitration_fee = fee;
+        Ok(())
+    }
+
+    /// Sets the share of the offender's insurance paid to the wronged party on a fail.
+    pub fn set_penalty_share(&mut self, authority: &Pubkey, share_bps: u16) -> Result<(), DLUError> {
+        self.check_governance(authority)?;
+        if share_bps as u64 > safe_math::BPS_DENOMINATOR {
+            return Err(DLUError::InvalidPenaltyShare);
+        }
+        self.penalty_share_bps = share_bps;
+        Ok(())
+    }
+
+    /// Sets the share of referred payees' fees credited to their referrers.
+    pub fn set_referral_share(&mut self, authority: &Pubkey, share_bps: u16) -> Result<(), DLUError> {
+        self.check_governance(authority)?;
+        if share_bps as u64 > safe_math::BPS_DENOMINATOR {
+            return Err(DLUError::InvalidReferralShare);
+        }
+        self.referral_share_bps = share_bps;
+        Ok(())
+    }
+
+    /// Sets the share of each penalty pool distribution that is burned.
+    pub fn set_penalty_burn_share(&mut self, authority: &Pubkey, share_bps: u16) -> Result<(), DLUError> {
+        self.check_governance(authority)?;
+        if share_bps as u64 > safe_math::BPS_DENOMINATOR {
+            return Err(DLUError::InvalidPenaltyShare);
+        }
+        self.penalty_burn_bps = share_bps;
+        Ok(())
+    }
+
+    /// Schedules the rewards paid to both parties of every completion: `reward` each at first,
+    /// halving over every `half_life` seconds, up to `emission_cap` in total.
+    pub fn set_completion_rewards(
+        &mut self,
+        authority: &Pubkey,
+        reward: u64,
+        half_life: u64,
+        emission_cap: u64,
+    ) -> Result<(), DLUError> {
+        self.check_governance(authority)?;
+        if half_life == 0 {
+            return Err(DLUError::InvalidRewardSchedule);
+        }
+        self.completion_reward = reward;
+        self.completion_reward_half_life = half_life;
+        self.completion_emission_cap = emission_cap;
+        Ok(())
+    }
+
+    /// Sets the rates shipment payments are suggested from.
+    pub fn set_shipping_rates(&mut self, authority: &Pubkey, rates: ShippingRates) -> Result<(), DLUError> {
+        self.check_governance(authority)?;
+        self.shipping_rates = rates;
+        Ok(())
+    }
+
+    /// Returns the referrer's share of a collected fee.
+    pub fn referral_share(&self, fee: u64) -> Result<u64, DLUError> {
+        safe_math::bps(fee, self.referral_share_bps)
+    }
+
+    /// Splits the offender's insurance into the wronged party's share and the penalty.
+    pub fn split_penalty(&self, insurance: u64) -> Result<(u64, u64), DLUError> {
+        let share = safe_math::bps(insurance, self.penalty_share_bps)?;
+        Ok((share, safe_math::sub(insurance, share)?))
+    }
+
+    /// Sets the shipment payment above which carriers must be staked, and the stake required.
+    pub fn set_stake_requirements(&mut self, authority: &Pubkey, stake_threshold: u64, min_stake: u64) -> Result<(), DLUError> {
+        self.check_governance(authority)?;
+        self.stake_threshold = stake_threshold;
+        self.min_stake = min_stake;
+        Ok(())
+    }
+
+    /// Returns true if carrying a shipment with the given payment requires a stake.
+    pub fn requires_stake(&self, payment: u64) -> bool {
+        payment > self.stake_threshold
+    }
+
+    /// Returns true if accepting a deal paying `payment` needs both parties attested.
+    pub fn requires_attestation(&self, payment: u64) -> bool {
+        payment > self.kyc_threshold
+    }
+
+    /// Returns true if the given capability bit is enabled.
+    pub fn has_capability(&self, capability: u64) -> bool {
+        self.capabilities & capability == capability
+    }
+
+    /// Ensures the given capability bit is enabled.
+    pub fn require_capability(&self, capability: u64) -> Result<(), DLUError> {
+        if !self.has_capability(capability) {
+            return Err(DLUError::FeatureDisabled);
+        }
+        Ok(())
+    }
+
+    /// Replaces the capability bitmap.
+    pub fn set_capabilities(&mut self, authority: &Pubkey, capabilities: u64) -> Result<(), DLUError> {
+        self.check_governance(authority)?;
+        self.capabilities = capabilities;
+        Ok(())
+    }
+
+    /// Ensures governance may still update the config directly rather than through the
+    /// timelock queue.
+    pub fn check_direct_update(&self) -> Result<(), DLUError> {
+        if self.has_capability(CAP_TIMELOCKED_GOVERNANCE) {
+            return Err(DLUError::GovernanceTimelocked);
+        }
+        Ok(())
+    }
+
+    /// Ensures the given key is the governance key.
+    pub fn check_governance(&self, authority: &Pubkey) -> Result<(), DLUError> {
+        if *authority != self.governance {
+            return Err(DLUError::NotAuthorized);
+        }
+        Ok(())
+    }
+
+    /// Raises the TVL cap and starts a new epoch.
+    pub fn raise_tvl_cap(&mut self, authority: &Pubkey, new_cap: u64) -> Result<(), DLUError> {
+        self.check_governance(authority)?;
+
+        // The cap can only grow; lowering it could strand deals that are already funded.
+        if new_cap <= self.tvl_cap {
+            return Err(DLUError::InvalidOperation);
+        }
+
+        self.tvl_cap = new_cap;
+        self.tvl_epoch += 1;
+        Ok(())
+    }
+
+    /// Serializes the config into a vector of bytes.
+    pub fn serialize(&self) -> Result<Vec<u8>, DLUError> {
+        self.try_to_vec().map_err(|_| DLUError::SerializationFailed)
+    }
+
+    /// Deserializes a config from a slice of bytes.
+    pub fn deserialize(input: &mut &[u8]) -> Result<Self, DLUError> {
+        <Self as BorshDeserialize>::deserialize(input).map_err(|_| DLUError::DeserializationFailed)
+    }
+}
@@ -0,0 +1,197 @@
+use crate::config::Config;
+use crate::stats::Stats;
+
+// Values owned by other modules are re-exported so SDKs and indexers can depend on a
+// single path instead of hard-coding them.
+
+// PDA seeds.
+pub use crate::addressing::{
+    APPEAL_SEED, ARBITER_REGISTRY_SEED, ARBITER_SEED, ARBITRATION_SEED, ATTESTATION_SEED, ATTESTOR_REGISTRY_SEED,
+    BADGE_MINT_SEED, BADGE_SEED, BOND_SEED, CARRIER_ROUTE_SEED, CONFIG_SEED, DAO_SEED, DELEGATION_SEED, EMERGENCY_SEED, FAUCET_SEED, HISTORY_SEED, INDEX_SEED, KEY_MANAGER_SEED, LEADERBOARD_SEED, LISTING_TREE_SEED, MESSAGES_SEED, MULTISIG_SEED, ORGANIZATION_SEED, PENALTY_POOL_SEED,
+    PENALTY_REWARDS_SEED, PENALTY_VAULT_SEED, PROFILE_SUMMARY_SEED, PROMO_SEED, PROPOSAL_SEED, QUOTE_SEED, REFERRAL_SEED, REGION_SEED, REVIEW_SEED, REWARDS_VAULT_SEED, REWARD_EMISSION_SEED, ROUTE_INDEX_SEED, STAKE_SEED, STATS_SEED, TIMELOCK_SEED, TREASURY_SEED, VAULT_SEED,
+    VAULT_REGISTRY_SEED, VOTE_LOCK_SEED, VOTE_SEED, WATCHLIST_SEED,
+};
+
+// Seed prefixes of entity accounts derived with `derive_address`.
+pub use crate::addressing::{ENTITY_OFFER, ENTITY_REQUEST, ENTITY_SHIPMENT};
+
+// Capability bits.
+pub use crate::config::{CAP_AUCTIONS, CAP_BURN_PENALTIES, CAP_CROSS_CHAIN, CAP_ESCROW_MIGRATION, CAP_SWAPS, CAP_TEST_CLUSTER, CAP_TIMELOCKED_GOVERNANCE};
+
+// Fee defaults.
+pub use crate::config::{
+    DEFAULT_APPEAL_BOND, DEFAULT_ARBITER_MIN_STAKE, DEFAULT_ARBITRATION_FEE, DEFAULT_CHARGEBACK_WINDOW, DEFAULT_COMPLETION_REWARD_HALF_LIFE, DEFAULT_CRANK_BOUNTY, DEFAULT_FAIL_BOND, DEFAULT_KYC_THRESHOLD, DEFAULT_MIN_STAKE, DEFAULT_PENALTY_SHARE_BPS, DEFAULT_REFERRAL_SHARE_BPS, DEFAULT_REPUTATION_HALF_LIFE, DEFAULT_STAKE_THRESHOLD,
+    MAX_FEE_BPS,
+};
+pub use crate::deal::FINDER_FEE_BPS;
+pub use crate::safe_math::BPS_DENOMINATOR;
+pub use crate::pricing::CM3_PER_VOLUMETRIC_GRAM;
+
+// Time windows, in seconds.
+pub use crate::time::{
+    APPEAL_WINDOW, ARBITER_COOLDOWN, ARBITRATION_TIMEOUT, EXPIRY_GRACE_PERIOD, FAIL_DISPUTE_WINDOW, SLOTS_PER_DAY, STAKE_COOLDOWN,
+};
+pub use crate::chargeback::MAX_CHARGEBACK_WINDOW;
+
+// Reputation.
+pub use crate::user::{REPUTATION_SCALE, ROLE_COUNT};
+pub use crate::review::{MAX_RATING, MIN_RATING};
+
+// Profile field lengths, in bytes.
+pub use crate::user::{MAX_CONTACT_HINTS_LEN, MAX_DISPLAY_NAME_LEN, MAX_METADATA_URI_LEN};
+pub use crate::quote::MAX_QUOTE_NOTE_LEN;
+
+// Location field lengths, in bytes.
+pub use crate::location::{MAX_ADDRESS_LEN, MAX_GEOHASH_LEN, MAX_TOWN_LEN};
+
+// Listing field lengths, in bytes.
+pub use crate::validation::{MAX_DESCRIPTION_LEN, MAX_ITEM_NAME_LEN, MAX_USERNAME_LEN};
+
+// Regional discovery.
+pub use crate::region::{MAX_REGION_LISTINGS, REGION_GEOHASH_LEN};
+pub use crate::route::{MAX_REGION_ROUTES, ROUTE_DAYS_MASK};
+
+// Messaging.
+pub use crate::messaging::{MAX_MESSAGES, MAX_MESSAGE_LEN};
+
+// Arbitration.
+pub use crate::arbiter::MAX_ARBITERS;
+pub use crate::appeal::APPEAL_PANEL_SIZE;
+
+// Session keys.
+pub use crate::delegation::{MAX_SESSION_DURATION, MAX_SESSION_ENTITIES};
+
+// Organizations.
+pub use crate::organization::{ALL_MEMBER_ROLES, MAX_MEMBERS, MEMBER_ACCEPTOR, MEMBER_FINISHER, MEMBER_LISTER};
+
+// Multisigs.
+pub use crate::multisig::MAX_MULTISIG_SIGNERS;
+
+// KYC attestations.
+pub use crate::attestation::MAX_ATTESTORS;
+
+// Promo codes.
+pub use crate::promo::MAX_PROMO_CODES;
+
+// Inventory offers.
+pub use crate::offer::MAX_PRICE_TIERS;
+
+// Watchlists.
+pub use crate::watchlist::{MAX_FOLLOWED_USERS, MAX_WATCHED_OFFERS};
+
+// Leaderboards.
+pub use crate::leaderboard::LEADERBOARD_ENTRIES;
+
+// Audit logs.
+pub use crate::audit::MAX_AUDIT_ENTRIES;
+
+// Archives.
+pub use crate::archive::{ARCHIVE_DELAY, MAX_ARCHIVE_BATCH};
+
+// Keepers.
+pub use crate::keeper::MAX_EXPIRY_BATCH;
+
+// Escrow vaults.
+pub use crate::vault_registry::MAX_REGISTERED_VAULTS;
+
+// Warranty holdbacks.
+pub use crate::holdback::{MAX_HOLDBACK_BPS, MAX_HOLDBACK_WINDOW};
+
+// Paging.
+pub use crate::instruction::MAX_CANCEL_PER_CALL;
+
+// Fixed account sizes, in bytes. Each counts the payload; accounts other than profile
+// summaries hold it behind their kind and a length prefix, so they are allocated with
+// `account_space`.
+pub use crate::migrations::{account_space, ACCOUNT_HEADER_LEN, LENGTH_PREFIX_LEN};
+pub use crate::profile_summary::PROFILE_SUMMARY_SIZE;
+pub use crate::onetimekeys::KEY_MANAGER_SIZE;
+pub use crate::emergency::EMERGENCY_RESOLUTION_SIZE;
+pub use crate::timelock::TIMELOCK_QUEUE_SIZE;
+pub use crate::dao::{DAO_SIZE, PROPOSAL_SIZE, VOTE_LOCK_SIZE, VOTE_RECORD_SIZE};
+pub use crate::penalty_pool::{PENALTY_POOL_SIZE, PENALTY_REWARDS_SIZE};
+pub use crate::emission::REWARD_EMISSION_SIZE;
+pub use crate::vault_registry::VAULT_REGISTRY_SIZE;
+pub use crate::route::{CARRIER_ROUTE_SIZE, ROUTE_INDEX_SIZE};
+pub const CONFIG_SIZE: usize = 32 + 8 + 4 + 8 + 32 + 4 + 8 + 2 + 1 + 2 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 2 + 2 + 8 + 8 + 8 + 3 * 8;
+pub const STATS_SIZE: usize = 8 + 4 + 8 + 7 * 8 + 4 + crate::vault_registry::MAX_REGISTERED_VAULTS * (32 + 8);
+pub const FAIL_BOND_SIZE: usize = 1 + 32 + 32 + 32 + 8 + 8 + 1;
+pub const CARRIER_STAKE_SIZE: usize = 32 + 32 + 8 + 8 + 8;
+pub const REVIEW_SIZE: usize = 1 + 8 + 32 + 32 + 1 + 32 + 8;
+pub const QUOTE_SIZE: usize = 32 + 32 + 32 + 8 + 4 + MAX_QUOTE_NOTE_LEN + 4 + 8 + 1;
+pub const REGION_INDEX_SIZE: usize = REGION_GEOHASH_LEN + 2 + 4 + MAX_REGION_LISTINGS * (32 + 1 + 8);
+pub const MESSAGE_LOG_SIZE: usize = 32 + 2 + 4 + MAX_MESSAGES * (32 + 8 + 4 + MAX_MESSAGE_LEN);
+pub const ARBITER_SIZE: usize = 32 + 32 + 8 + 1 + 8 + 4 + 4 + 8 + 8;
+pub const ARBITER_REGISTRY_SIZE: usize = 4 + MAX_ARBITERS * 32;
+pub const ARBITRATION_SIZE: usize = 32 + 1 + 32 + 8 + 8 + 2 + 1;
+pub const APPEAL_SIZE: usize = 32 + 32 + 8 + 8 + 1 + APPEAL_PANEL_SIZE * (32 + 2) + 1 + 1;
+pub const DELEGATION_SIZE: usize = 32 + 32 + 8 + 4 + MAX_SESSION_ENTITIES * 32;
+pub const ORGANIZATION_SIZE: usize = 32 + 4 + MAX_MEMBERS * (32 + 1);
+pub const MULTISIG_SIZE: usize = 32 + 1 + 4 + MAX_MULTISIG_SIGNERS * 32 + 8;
+pub const ATTESTOR_REGISTRY_SIZE: usize = 4 + MAX_ATTESTORS * 32;
+pub const ATTESTATION_SIZE: usize = 32 + 32 + 8;
+pub const REFERRAL_REWARDS_SIZE: usize = 32 + 32 + 8 + 8;
+pub const PROMO_CODES_SIZE: usize = 4 + MAX_PROMO_CODES * (32 + 1 + 4);
+pub const WATCHLIST_SIZE: usize = 4 + MAX_FOLLOWED_USERS * 32 + 4 + MAX_WATCHED_OFFERS * 32;
+pub const LEADERBOARD_SIZE: usize = 4 + LEADERBOARD_ENTRIES * (32 + 8);
+// Index accounts are created with this much room and grow past it once topped up.
+pub const INDEX_SIZE: usize = 2_048;
+
+/// Longest entity ID accepted by `derive_address`; longer IDs would overflow the seed.
+pub const MAX_ENTITY_ID_LEN: usize = 32;
+
+/// Limits in effect for the current config, combining fixed values with governance overrides.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limits {
+    pub tvl_cap: u64,
+    pub fail_bond: u64,
+    pub fee_bps: u16,
+    pub penalty_share_bps: u16,
+    pub stake_threshold: u64,
+    pub min_stake: u64,
+    pub reputation_half_life: u64,
+    pub crank_bounty: u64,
+    pub chargeback_window: i64,
+    pub arbiter_min_stake: u64,
+    pub arbitration_fee: u64,
+    pub appeal_bond: u64,
+    pub kyc_threshold: u64,
+    pub referral_share_bps: u16,
+    pub penalty_burn_bps: u16,
+    pub completion_reward: u64,
+    pub completion_reward_half_life: u64,
+    pub completion_emission_cap: u64,
+    pub max_entity_id_len: usize,
+    pub max_cancel_per_call: u8,
+}
+
+/// Returns the limits in effect under the given config.
+pub fn limits(config: &Config) -> Limits {
+    Limits {
+        tvl_cap: config.tvl_cap,
+        fail_bond: config.fail_bond,
+        fee_bps: config.fee_bps,
+        penalty_share_bps: config.penalty_share_bps,
+        stake_threshold: config.stake_threshold,
+        min_stake: config.min_stake,
+        reputation_half_life: config.reputation_half_life,
+        crank_bounty: config.crank_bounty,
+        chargeback_window: config.chargeback_window,
+        arbiter_min_stake: config.arbiter_min_stake,
+        arbitration_fee: config.arbitration_fee,
+        appeal_bond: config.appeal_bond,
+        kyc_threshold: config.kyc_threshold,
+        referral_share_bps: config.referral_share_bps,
+        penalty_burn_bps: config.penalty_burn_bps,
+        completion_reward: config.completion_reward,
+        completion_reward_half_life: config.completion_reward_half_life,
+        completion_emission_cap: config.completion_emission_cap,
+        max_entity_id_len: MAX_ENTITY_ID_LEN,
+        max_cancel_per_call: MAX_CANCEL_PER_CALL,
+    }
+}
+
+/// Returns how much more DLU can be escrowed before the TVL cap is hit.
+pub fn remaining_tvl(config: &Config, stats: &Stats) -> u64 {
+    config.tvl_cap.saturating_sub(stats.total_value_locked)
+}
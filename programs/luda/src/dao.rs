@@ -0,0 +1,188 @@
+use solana_program::pubkey::Pubkey;
+use solana_program::borsh::{BorshSerialize, BorshDeserialize};
+use crate::errors::DLUError;
+use crate::safe_math;
+use crate::timelock::{GovernanceAction, GOVERNANCE_ACTION_SIZE};
+
+/// Account size of the Dao.
+pub const DAO_SIZE: usize = 32 + 8 + 8 + 8 + 8;
+
+/// Account size of a VoteLock.
+pub const VOTE_LOCK_SIZE: usize = 32 + 8 + 8;
+
+/// Account size of a Proposal.
+pub const PROPOSAL_SIZE: usize = 8 + 32 + GOVERNANCE_ACTION_SIZE + 8 + 8 + 8 + 1 + 9;
+
+/// Account size of a VoteRecord.
+pub const VOTE_RECORD_SIZE: usize = 8 + 1;
+
+/// How holders of the vote mint propose and vote on config updates, kept at the PDA
+/// `["dao"]`. Governance sets it up once; proposals that pass go through the timelock
+/// queue like governance's own updates, so governance can still cancel them there.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct Dao {
+    pub vote_mint: Pubkey,         // Mint whose locked tokens carry votes, normally DLU.
+    pub quorum: u64,               // Votes that must be cast on a proposal for it to pass.
+    pub proposal_threshold: u64,   // Tokens a holder must have locked to create a proposal.
+    pub voting_period: i64,        // Seconds a proposal is open for votes.
+    pub next_proposal_id: u64,
+}
+
+impl Dao {
+    /// Creates the Dao, which needs a quorum and a voting period.
+    pub fn new(vote_mint: Pubkey, quorum: u64, proposal_threshold: u64, voting_period: i64) -> Result<Self, DLUError> {
+        if quorum == 0 || voting_period <= 0 {
+            return Err(DLUError::InvalidOperation);
+        }
+        Ok(Dao { vote_mint, quorum, proposal_threshold, voting_period, next_proposal_id: 0 })
+    }
+
+    /// Opens a proposal by a holder with `locked` tokens at `now`, taking the next ID.
+    pub fn propose(&mut self, proposer: &Pubkey, locked: u64, action: GovernanceAction, now: i64) -> Result<Proposal, DLUError> {
+        if locked == 0 || locked < self.proposal_threshold {
+            return Err(DLUError::InsufficientVotingPower);
+        }
+        let proposal = Proposal {
+            id: self.next_proposal_id,
+            proposer: *proposer,
+            action,
+            votes_for: 0,
+            votes_against: 0,
+            voting_ends_at: now.saturating_add(self.voting_period),
+            status: ProposalStatus::Voting,
+            queued_action: None,
+        };
+        self.next_proposal_id += 1;
+        Ok(proposal)
+    }
+
+    /// Serializes the Dao into a vector of bytes.
+    pub fn serialize(&self) -> Result<Vec<u8>, DLUError> {
+        self.try_to_vec().map_err(|_| DLUError::SerializationFailed)
+    }
+
+    /// Deserializes a Dao from a slice of bytes.
+    pub fn deserialize(input: &mut &[u8]) -> Result<Self, DLUError> {
+        <Self as BorshDeserialize>::deserialize(input).map_err(|_| DLUError::DeserializationFailed)
+    }
+}
+
+/// Tokens a holder locked in the vault of the vote mint to vote with, kept at the PDA
+/// `["vote_lock", owner_pubkey]`. Voting keeps them locked until the vote closes, so the same
+/// tokens can't be unlocked and locked again by someone else to vote twice.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct VoteLock {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub unlock_at: i64,  // Unlocking is allowed from this timestamp on.
+}
+
+impl VoteLock {
+    /// Creates an empty lock for the owner.
+    pub fn new(owner: Pubkey) -> Self {
+        VoteLock { owner, amount: 0, unlock_at: 0 }
+    }
+
+    /// Adds to the locked tokens.
+    pub fn lock(&mut self, amount: u64) -> Result<(), DLUError> {
+        if amount == 0 {
+            return Err(DLUError::InvalidVoteAmount);
+        }
+        self.amount = safe_math::add(self.amount, amount)?;
+        Ok(())
+    }
+
+    /// Takes part of the locked tokens back once no vote they were cast in is still open.
+    pub fn unlock(&mut self, amount: u64, now: i64) -> Result<(), DLUError> {
+        if now < self.unlock_at {
+            return Err(DLUError::VotesLocked);
+        }
+        if amount == 0 || amount > self.amount {
+            return Err(DLUError::InvalidVoteAmount);
+        }
+        self.amount -= amount;
+        Ok(())
+    }
+
+    /// Serializes the VoteLock into a vector of bytes.
+    pub fn serialize(&self) -> Result<Vec<u8>, DLUError> {
+        self.try_to_vec().map_err(|_| DLUError::SerializationFailed)
+    }
+
+    /// Deserializes a VoteLock from a slice of bytes.
+    pub fn deserialize(input: &mut &[u8]) -> Result<Self, DLUError> {
+        <Self as BorshDeserialize>::deserialize(input).map_err(|_| DLUError::DeserializationFailed)
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum ProposalStatus {
+    Voting,
+    Passed,    // Queued in the timelock as `queued_action`.
+    Rejected,
+}
+
+/// A config update holders vote on, kept at the PDA `["proposal", id]`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct Proposal {
+    pub id: u64,
+    pub proposer: Pubkey,
+    pub action: GovernanceAction,
+    pub votes_for: u64,
+    pub votes_against: u64,
+    pub voting_ends_at: i64,
+    pub status: ProposalStatus,
+    pub queued_action: Option<u64>,  // ID of the action in the TimelockQueue once passed.
+}
+
+impl Proposal {
+    /// Casts the votes of `lock` at `now`, keeping its tokens locked until voting ends.
+    pub fn vote(&mut self, lock: &mut VoteLock, support: bool, now: i64) -> Result<VoteRecord, DLUError> {
+        if self.status != ProposalStatus::Voting || now >= self.voting_ends_at {
+            return Err(DLUError::VotingClosed);
+        }
+        if lock.amount == 0 {
+            return Err(DLUError::InsufficientVotingPower);
+        }
+        if support {
+            self.votes_for = safe_math::add(self.votes_for, lock.amount)?;
+        } else {
+            self.votes_against = safe_math::add(self.votes_against, lock.amount)?;
+        }
+        lock.unlock_at = lock.unlock_at.max(self.voting_ends_at);
+        Ok(VoteRecord { weight: lock.amount, support })
+    }
+
+    /// Closes voting at `now`, returning true if the proposal passed: a majority for it, with
+    /// at least `quorum` votes cast.
+    pub fn finalize(&mut self, quorum: u64, now: i64) -> Result<bool, DLUError> {
+        if self.status != ProposalStatus::Voting {
+            return Err(DLUError::VotingClosed);
+        }
+        if now < self.voting_ends_at {
+            return Err(DLUError::VotingNotEnded);
+        }
+        let cast = safe_math::add(self.votes_for, self.votes_against)?;
+        let passed = cast >= quorum && self.votes_for > self.votes_against;
+        self.status = if passed { ProposalStatus::Passed } else { ProposalStatus::Rejected };
+        Ok(passed)
+    }
+
+    /// Serializes the Proposal into a vector of bytes.
+    pub fn serialize(&self) -> Result<Vec<u8>, DLUError> {
+        self.try_to_vec().map_err(|_| DLUError::SerializationFailed)
+    }
+
+    /// Deserializes a Proposal from a slice of bytes.
+    pub fn deserialize(input: &mut &[u8]) -> Result<Self, DLUError> {
+        <Self as BorshDeserialize>::deserialize(input).map_err(|_| DLUError::DeserializationFailed)
+    }
+}
+
+/// How a holder voted on a proposal, kept at the PDA `["vote", proposal_pubkey, voter_pubkey]`
+/// so nobody votes twice.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct VoteRecord {
+    pub weight: u64,
+    pub support: bool,
+}
@@ -0,0 +1,1523 @@
+use std::marker::PhantomData;
+use solana_program::{account_info::AccountInfo, hash::hash, pubkey::Pubkey};
+use solana_program::borsh::{BorshSerialize, BorshDeserialize};
+use crate::user::{Role, User, UserStatus};
+use crate::config::{Config, CAP_BURN_PENALTIES};
+use crate::onetimekeys::{KeyHash, OneTimeKey};
+use crate::arbiter::DisputeKind;
+use crate::archive::ARCHIVE_DELAY;
+use crate::audit::{AuditEntry, AuditLog};
+use crate::chargeback::{Chargeback, ChargebackStatus};
+use crate::dlu_token::DLUToken;
+use crate::escrow::Escrow;
+use crate::errors::DLUError;
+use crate::holdback::{Holdback, HoldbackStatus, HoldbackTerms};
+use crate::migrations;
+pub use crate::location::Location;
+use crate::safe_math;
+use crate::time;
+
+/// Share of a matched deal's payment the matcher earns, in basis points.
+pub const FINDER_FEE_BPS: u16 = 10;
+
+/// Represents the current status of a deal.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, PartialEq, Debug)]
+pub enum DealStatus {
+    Listed,
+    Accepted,
+    Completed,
+    Failed,
+    Expired,
+    Canceled,
+    Matched,  // A request closed by matching it with an offer, which carries the deal on.
+    Settled,  // Closed by splitting the escrow between the parties.
+}
+
+/// Decides which side of a deal lists it and which side accepts it.
+pub trait DealRole: BorshSerialize + BorshDeserialize {
+    /// True when the seller lists the deal (offers), false when the buyer does (requests).
+    const LISTED_BY_SELLER: bool;
+}
+
+/// Role of deals listed by a seller and accepted by a buyer.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct SellerListed;
+
+impl DealRole for SellerListed {
+    const LISTED_BY_SELLER: bool = true;
+}
+
+/// Role of deals listed by a buyer and accepted by a seller.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct BuyerListed;
+
+impl DealRole for BuyerListed {
+    const LISTED_BY_SELLER: bool = false;
+}
+
+/// A deal between a seller and a buyer meeting in person.
+///
+/// Listed → Accepted → Completed/Failed/Expired/Settled, or Listed → Canceled. The lister locks
+/// their deposit when listing, the acceptor when accepting. The buyer always deposits the
+/// payment plus insurance, the seller only the insurance. The insurance equals the payment,
+/// except in barters, which have no payment and set the insurance both sides post, and in
+/// inventory offers, whose insurance covers the whole stock at its tier price while the
+/// payment only covers the quantity accepted.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct Deal<R: DealRole> {
+    id: u64,
+    status: DealStatus,
+    lister: User,
+    acceptor: Option<User>,
+    meeting_point: Location,
+    meeting_point_hash: Option<[u8; 32]>,  // Set while only the public part of the meeting point is known.
+    meeting_datetime: i64,
+    payment: u64,
+    insurance: u64,
+    payment_mint: Pubkey,  // Mint of every token account the deal's funds move through.
+    holdback: Option<Holdback>,  // Warranty share of the payment kept in escrow after completion.
+    chargeback: Option<Chargeback>,  // Seller's insurance kept in escrow while the buyer can charge back.
+    goodsorservice_name: String,
+    goodsorservice_description: String,
+    seller_key: OneTimeKey,
+    buyer_key: OneTimeKey,
+    escrow_id: u64,
+    escrow_vault: Option<Pubkey>,  // The vault holding the escrow; unset until a deal listed before vaults migrates.
+    escrow_flagged: bool,  // Set once `verify_escrow` found its vault short, blocking payouts.
+    nonce: u64,  // Advanced by every status transition, so terminal instructions cannot be replayed.
+    generation: u32,  // Incremented every time the deal is relisted.
+    activate_at: i64,  // The listing cannot be accepted before this time.
+    delist_at: Option<i64>,  // From this time on the listing is expired if no one accepted it.
+    audit_log: AuditLog,
+    #[borsh_skip]
+    role: PhantomData<R>,
+}
+
+impl<R: DealRole> Deal<R> {
+    /// Returns the amount the buyer deposits: the payment plus their insurance.
+    fn buyer_deposit(payment: u64, insurance: u64) -> Result<u64, DLUError> {
+        safe_math::add(payment, insurance)
+    }
+
+    /// Returns the amounts deposited by the lister and by the acceptor.
+    fn deposits(payment: u64, insurance: u64) -> Result<(u64, u64), DLUError> {
+        // The seller only deposits their insurance.
+        let buyer_deposit = Self::buyer_deposit(payment, insurance)?;
+        if R::LISTED_BY_SELLER {
+            Ok((insurance, buyer_deposit))
+        } else {
+            Ok((buyer_deposit, insurance))
+        }
+    }
+
+    /// Returns the role of the user accepting the deal.
+    fn acceptor_role() -> Role {
+        if R::LISTED_BY_SELLER { Role::Buyer } else { Role::Seller }
+    }
+
+    /// Checks that the token accounts hold the mint the deal is paid in.
+    fn check_payment_accounts(&self, token_accounts: &[&AccountInfo]) -> Result<(), DLUError> {
+        DLUToken::check_payment_mint(&self.payment_mint, token_accounts)
+    }
+
+    /// Checks that the deal's escrow sits in the vault passed in. Deals listed before the
+    /// vaults existed have to migrate their escrow there first.
+    fn check_vault(&self, vault_account: &AccountInfo) -> Result<(), DLUError> {
+        if self.escrow_vault != Some(*vault_account.key) {
+            return Err(DLUError::EscrowNotInVault);
+        }
+        Ok(())
+    }
+
+    /// Checks the vault like `check_vault` before funds leave it, refusing once a
+    /// reconciliation flagged the deal. Only governance's emergency unwind releases those.
+    fn check_payout_vault(&self, vault_account: &AccountInfo) -> Result<(), DLUError> {
+        self.check_vault(vault_account)?;
+        if self.escrow_flagged {
+            return Err(DLUError::EscrowFlagged);
+        }
+        Ok(())
+    }
+
+    /// Checks that the seller and buyer are the deal's parties and that the token accounts
+    /// paid out to are their own.
+    fn check_parties(
+        &self,
+        seller: &Pubkey,
+        buyer: &Pubkey,
+        seller_account: &AccountInfo,
+        buyer_account: &AccountInfo,
+    ) -> Result<(), DLUError> {
+        if self.parties() != Some((*seller, *buyer)) {
+            return Err(DLUError::NotDealParty);
+        }
+        let owned_by = |account: &AccountInfo, key: &Pubkey| DLUToken::check_authority(account, key).map_err(|_| DLUError::KeyMismatch);
+        if !owned_by(seller_account, seller)? || !owned_by(buyer_account, buyer)? {
+            return Err(DLUError::KeyMismatch);
+        }
+        Ok(())
+    }
+
+    /// List a new deal, locking the lister's deposit from their token account into the vault
+    /// of its mint. The deal is paid in the mint of that account, whether DLU, USDC or
+    /// wrapped SOL.
+    ///
+    /// With a `meeting_point_hash`, the meeting point only holds what the lister is willing
+    /// to publish, such as the country and town, until they reveal the exact point once the
+    /// deal is accepted. With `holdback` terms, part of the payment stays in escrow as a
+    /// warranty after completion.
+    pub fn list(
+        id: u64,
+        lister: &User,
+        goodsorservice_name: String,
+        goodsorservice_description: String,
+        payment: u64,
+        insurance: u64,
+        holdback: Option<HoldbackTerms>,
+        meeting_point: Location,
+        meeting_point_hash: Option<[u8; 32]>,
+        meeting_datetime: i64,
+        lister_account: &AccountInfo,
+        lister_authority_info: &AccountInfo,
+        vault_account: &AccountInfo,
+    ) -> Result<Self, DLUError> {
+        meeting_point.validate()?;
+        let holdback = holdback.map(|terms| Holdback::new(terms, payment)).transpose()?;
+        let payment_mint = Self::lock_listing_deposit(payment, insurance, lister_account, lister_authority_info, vault_account)?;
+
+        Ok(Self::listed(
+            id,
+            lister,
+            goodsorservice_name,
+            goodsorservice_description,
+            payment,
+            insurance,
+            payment_mint,
+            *vault_account.key,
+            holdback,
+            meeting_point,
+            meeting_point_hash,
+            meeting_datetime,
+        ))
+    }
+
+    /// Locks the deposit of a deal about to be listed from the lister's token account,
+    /// returning the mint the deal is paid in.
+    pub fn lock_listing_deposit(
+        payment: u64,
+        insurance: u64,
+        lister_account: &AccountInfo,
+        lister_authority_info: &AccountInfo,
+        vault_account: &AccountInfo,
+    ) -> Result<Pubkey, DLUError> {
+        let payment_mint = DLUToken::get_mint(lister_account).map_err(|_| DLUError::DeserializationFailed)?;
+        DLUToken::check_payment_mint(&payment_mint, &[vault_account])?;
+
+        // Check lister's balance for sufficient funds for their deposit.
+        let (deposit, _) = Self::deposits(payment, insurance)?;
+        let lister_balance = DLUToken::get_balance(lister_account).map_err(|_| DLUError::BalanceUnavailable)?;
+        if lister_balance < deposit {
+            return Err(DLUError::InsufficientFunds);
+        }
+
+        // Lock the deposit in escrow.
+        Escrow::lock_funds(lister_account, vault_account, lister_authority_info, deposit)?;
+        Ok(payment_mint)
+    }
+
+    /// Builds a listed deal whose lister's deposit is already in the vault `escrow_vault`.
+    pub(crate) fn listed(
+        id: u64,
+        lister: &User,
+        goodsorservice_name: String,
+        goodsorservice_description: String,
+        payment: u64,
+        insurance: u64,
+        payment_mint: Pubkey,
+        escrow_vault: Pubkey,
+        holdback: Option<Holdback>,
+        meeting_point: Location,
+        meeting_point_hash: Option<[u8; 32]>,
+        meeting_datetime: i64,
+    ) -> Self {
+        Deal {
+            id,
+            status: DealStatus::Listed,
+            lister: lister.clone(),
+            acceptor: None,
+            meeting_point,
+            meeting_point_hash,
+            meeting_datetime,
+            payment,
+            insurance,
+            payment_mint,
+            holdback,
+            chargeback: None,
+            goodsorservice_name,
+            goodsorservice_description,
+            seller_key: OneTimeKey::NONE,
+            buyer_key: OneTimeKey::NONE,
+            escrow_id: id,  // The escrow is tracked per deal.
+            escrow_vault: Some(escrow_vault),
+            escrow_flagged: false,
+            nonce: 0,
+            generation: 0,
+            activate_at: 0,
+            delist_at: None,
+            audit_log: AuditLog::default(),
+            role: PhantomData,
+        }
+    }
+
+    /// Accepts a listed deal.
+    pub fn accept(
+        &mut self,
+        acceptor: &mut User,
+        acceptor_account: &AccountInfo,
+        vault_account: &AccountInfo,
+        authority_info: &AccountInfo,
+    ) -> Result<(), DLUError> {
+        // Ensure the deal is in the 'Listed' state.
+        if self.status != DealStatus::Listed {
+            return Err(DLUError::NotListed);
+        }
+        self.check_vault(vault_account)?;
+        self.check_payment_accounts(&[acceptor_account, vault_account])?;
+
+        // Users known for failing in the role they would accept in are turned away.
+        if acceptor.role_status(Self::acceptor_role()) == UserStatus::Fraud {
+            return Err(DLUError::ReputationTooLow);
+        }
+
+        // Check acceptor's balance for sufficient funds for their deposit.
+        let (_, deposit) = Self::deposits(self.payment, self.insurance)?;
+        let acceptor_balance = DLUToken::get_balance(acceptor_account).map_err(|_| DLUError::BalanceUnavailable)?;
+        if acceptor_balance < deposit {
+            return Err(DLUError::InsufficientFunds);
+        }
+
+        // Both parties commit their one-time keys with `CommitKey` once the deal is accepted.
+        self.clear_keys();
+
+        // Lock the acceptor's deposit in escrow.
+        Escrow::lock_funds(acceptor_account, vault_account, authority_info, deposit)?;
+
+        // Update the acceptor field and the status of the deal to 'Accepted'.
+        self.acceptor = Some(acceptor.clone());
+        self.status = DealStatus::Accepted;
+
+        Ok(())
+    }
+
+    /// Accepts a listed deal, locking the acceptor's deposit from their wallet token account,
+    /// which signs for itself with `wallet_seeds`.
+    pub fn accept_from_wallet(
+        &mut self,
+        acceptor: &mut User,
+        wallet_account: &AccountInfo,
+        vault_account: &AccountInfo,
+        wallet_seeds: &[&[u8]],
+    ) -> Result<(), DLUError> {
+        if self.status != DealStatus::Listed {
+            return Err(DLUError::NotListed);
+        }
+        self.check_vault(vault_account)?;
+        self.check_payment_accounts(&[wallet_account, vault_account])?;
+
+        let (_, deposit) = Self::deposits(self.payment, self.insurance)?;
+        let wallet_balance = DLUToken::get_balance(wallet_account).map_err(|_| DLUError::BalanceUnavailable)?;
+        if wallet_balance < deposit {
+            return Err(DLUError::InsufficientFunds);
+        }
+        DLUToken::transfer_signed(wallet_account, vault_account, wallet_account, deposit, &[wallet_seeds])
+            .map_err(|_| DLUError::TransferFailed)?;
+        self.accept_escrowed(acceptor)
+    }
+
+    /// Accepts a listed deal for an acceptor whose deposit is already in escrow.
+    fn accept_escrowed(&mut self, acceptor: &mut User) -> Result<(), DLUError> {
+        if self.status != DealStatus::Listed {
+            return Err(DLUError::NotListed);
+        }
+        if acceptor.role_status(Self::acceptor_role()) == UserStatus::Fraud {
+            return Err(DLUError::ReputationTooLow);
+        }
+
+        // Both parties commit their one-time keys with `CommitKey` once the deal is accepted.
+        self.clear_keys();
+
+        self.acceptor = Some(acceptor.clone());
+        self.status = DealStatus::Accepted;
+
+        Ok(())
+    }
+
+    /// Accepts a listed deal at a quoted payment, whose acceptor locked their insurance for
+    /// it when quoting. The lister's deposit is topped up or partially released to match.
+    pub fn accept_quoted(
+        &mut self,
+        acceptor: &mut User,
+        payment: u64,
+        lister_account: &AccountInfo,
+        lister_authority_info: &AccountInfo,
+        vault_account: &AccountInfo,
+        vault_seeds: &[&[u8]],
+    ) -> Result<(), DLUError> {
+        // Ensure the deal is in the 'Listed' state.
+        if self.status != DealStatus::Listed {
+            return Err(DLUError::NotListed);
+        }
+        self.check_vault(vault_account)?;
+        self.check_payment_accounts(&[lister_account, vault_account])?;
+
+        self.reprice(payment, lister_account, lister_authority_info, vault_account, vault_seeds)?;
+        self.accept_escrowed(acceptor)
+    }
+
+    /// Completes an accepted deal once both parties presented their keys, returning the
+    /// protocol fee taken from the seller's payment. A holdback keeps its share of the rest
+    /// of the payment in escrow from `now` until its window has passed, and a nonzero
+    /// `chargeback_window` keeps the seller's insurance there until it has passed.
+    pub fn complete(
+        &mut self,
+        entered_buyer_key: String,
+        entered_seller_key: String,
+        seller_account: &AccountInfo,
+        buyer_account: &AccountInfo,
+        vault_account: &AccountInfo,
+        vault_seeds: &[&[u8]],
+        treasury_account: &AccountInfo,
+        config: &Config,
+        seller: &mut User,
+        buyer: &mut User,
+        chargeback_window: i64,
+        now: i64,
+    ) -> Result<u64, DLUError> {
+        // Ensure the deal is in the 'Accepted' state.
+        if self.status != DealStatus::Accepted {
+            return Err(DLUError::NotAccepted);
+        }
+        self.check_payout_vault(vault_account)?;
+        self.check_payment_accounts(&[seller_account, buyer_account, vault_account, treasury_account])?;
+        self.check_parties(&seller.pubkey, &buyer.pubkey, seller_account, buyer_account)?;
+
+        // Validate both keys before any funds move.
+        self.buyer_key.check(&entered_buyer_key, now, DLUError::InvalidBuyerKey)?;
+        self.seller_key.check(&entered_seller_key, now, DLUError::InvalidSellerKey)?;
+
+        // Check escrow balance. Double insurance for both seller and buyer.
+        let escrow_balance = DLUToken::get_balance(vault_account).map_err(|_| DLUError::BalanceUnavailable)?;
+        let escrow_total = safe_math::escrow_total(self.payment, self.insurance, 2)?;
+        if escrow_balance < escrow_total {
+            return Err(DLUError::InsufficientEscrowFunds);
+        }
+
+        // Send the protocol fee from the payment to the treasury.
+        let fee = config.fee_for(self.payment, seller, Role::Seller)?;
+        if fee > 0 {
+            Escrow::release_funds(vault_account, treasury_account, vault_seeds, fee)?;
+        }
+
+        // Keep the holdback, if any, out of the seller's proceeds.
+        let held = match self.holdback.as_mut() {
+            Some(holdback) => holdback.hold(safe_math::sub(self.payment, fee)?, now)?,
+            None => 0,
+        };
+
+        // Keep the seller's insurance back while the buyer can still charge the deal back.
+        let seller_insurance = if chargeback_window > 0 && self.insurance > 0 {
+            self.chargeback = Some(Chargeback::open(self.insurance, now, chargeback_window));
+            0
+        } else {
+            self.insurance
+        };
+
+        // Release the rest of the payment, if any, and the seller's insurance to the seller.
+        let seller_total = safe_math::sub(safe_math::add(self.payment, seller_insurance)?, safe_math::add(fee, held)?)?;
+        if seller_total > 0 {
+            Escrow::release_funds(vault_account, seller_account, vault_seeds, seller_total)?;
+        }
+
+        // Release the buyer's insurance back to the buyer.
+        Escrow::release_funds(vault_account, buyer_account, vault_seeds, self.insurance)?;
+
+        // Invalidate the keys and update the status of the deal to 'Completed'.
+        self.clear_keys();
+        self.status = DealStatus::Completed;
+
+        // Mark the deal as successful for both the seller and buyer.
+        seller.mark_deal(Role::Seller, true);
+        buyer.mark_deal(Role::Buyer, true);
+        seller.record_volume(Role::Seller, self.payment);
+        buyer.record_volume(Role::Buyer, self.payment);
+
+        Ok(fee)
+    }
+
+    /// Fails an accepted deal on the seller's claim that the buyer did not honour it.
+    ///
+    /// Both parties recover their principal — the seller their insurance, the buyer their
+    /// payment. Only the buyer's insurance is at stake: the seller receives the config's
+    /// share of it and the rest goes to the penalty account, or is burned when the config
+    /// enables `CAP_BURN_PENALTIES` (the payment mint is then passed as `penalty_account`).
+    /// Returns the penalty.
+    pub fn fail(
+        &mut self,
+        entered_seller_key: String,
+        buyer: &mut User,
+        seller_account: &AccountInfo,
+        buyer_account: &AccountInfo,
+        vault_account: &AccountInfo,
+        penalty_account: &AccountInfo,
+        vault_seeds: &[&[u8]],
+        config: &Config,
+        now: i64,
+    ) -> Result<u64, DLUError> {
+        // Ensure the deal is in the 'Accepted' state.
+        if self.status != DealStatus::Accepted {
+            return Err(DLUError::NotAccepted);
+        }
+
+        self.check_payout_vault(vault_account)?;
+
+        // Validate the seller's key.
+        self.seller_key.check(&entered_seller_key, now, DLUError::InvalidSellerKey)?;
+
+        // A burned penalty passes the mint itself, which the token program checks on burning.
+        let burns_penalty = config.has_capability(CAP_BURN_PENALTIES);
+        let mut token_accounts = vec![seller_account, buyer_account, vault_account];
+        if !burns_penalty {
+            token_accounts.push(penalty_account);
+        }
+        self.check_payment_accounts(&token_accounts)?;
+
+        // Only the acceptor can forfeit the buyer's insurance, and both refunds go to the
+        // parties' own accounts.
+        let (seller_key, _) = self.parties().ok_or(DLUError::CounterpartyNotFound)?;
+        self.check_parties(&seller_key, &buyer.pubkey, seller_account, buyer_account)?;
+
+        let (share, penalty) = config.split_penalty(self.insurance)?;
+
+        // Return the seller's insurance along with their share of the buyer's.
+        let seller_total = safe_math::add(self.insurance, share)?;
+        Escrow::release_funds(vault_account, seller_account, vault_seeds, seller_total)?;
+
+        // Return the buyer's payment; barters have none.
+        if self.payment > 0 {
+            Escrow::release_funds(vault_account, buyer_account, vault_seeds, self.payment)?;
+        }
+
+        // The rest of the buyer's insurance is the penalty.
+        if penalty > 0 {
+            if burns_penalty {
+                Escrow::burn_penalty(vault_account, penalty_account, vault_seeds, penalty)?;
+            } else {
+                Escrow::transfer_to_penalty(vault_account, penalty_account, vault_seeds, penalty)?;
+            }
+        }
+
+        // Invalidate the keys and update the status of the deal to 'Failed'.
+        self.clear_keys();
+        self.status = DealStatus::Failed;
+
+        // Mark the deal as failed for the buyer.
+        buyer.mark_deal(Role::Buyer, false);
+
+        Ok(penalty)
+    }
+
+    /// Settles an accepted deal for an outcome in between completion and failure, such as
+    /// goods delivered but damaged, returning the amounts paid to the seller and the buyer.
+    ///
+    /// Everything in escrow — the payment and both insurances — is shared out by basis
+    /// points, which must add up to the whole; rounding dust goes to the buyer. Neither
+    /// party's reputation is marked.
+    pub fn settle(
+        &mut self,
+        seller_bps: u16,
+        buyer_bps: u16,
+        seller_account: &AccountInfo,
+        buyer_account: &AccountInfo,
+        vault_account: &AccountInfo,
+        vault_seeds: &[&[u8]],
+    ) -> Result<(u64, u64), DLUError> {
+        // Ensure the deal is in the 'Accepted' state.
+        if self.status != DealStatus::Accepted {
+            return Err(DLUError::NotAccepted);
+        }
+        if seller_bps as u64 + buyer_bps as u64 != safe_math::BPS_DENOMINATOR {
+            return Err(DLUError::InvalidSettlementSplit);
+        }
+        self.check_payout_vault(vault_account)?;
+        self.check_payment_accounts(&[seller_account, buyer_account, vault_account])?;
+
+        // The refunds must go to the parties' own accounts.
+        let (seller_key, buyer_key) = self.parties().ok_or(DLUError::CounterpartyNotFound)?;
+        let owned_by = |account: &AccountInfo, key: &Pubkey| DLUToken::check_authority(account, key).map_err(|_| DLUError::KeyMismatch);
+        if !owned_by(seller_account, &seller_key)? || !owned_by(buyer_account, &buyer_key)? {
+            return Err(DLUError::KeyMismatch);
+        }
+
+        let escrow_total = safe_math::escrow_total(self.payment, self.insurance, 2)?;
+        let seller_total = safe_math::bps(escrow_total, seller_bps)?;
+        let buyer_total = safe_math::sub(escrow_total, seller_total)?;
+        for (destination, amount) in [(seller_account, seller_total), (buyer_account, buyer_total)] {
+            if amount > 0 {
+                Escrow::release_funds(vault_account, destination, vault_seeds, amount)?;
+            }
+        }
+
+        // Invalidate the keys and update the status of the deal to 'Settled'.
+        self.clear_keys();
+        self.status = DealStatus::Settled;
+
+        Ok((seller_total, buyer_total))
+    }
+
+    /// Expires an accepted deal once the grace period after the meeting has passed, or
+    /// delists a listing no one accepted before its delist time. Anyone may expire a deal,
+    /// so the refunds must go to the parties' own accounts.
+    pub fn expire(
+        &mut self,
+        vault_account: &AccountInfo,
+        seller_account: &AccountInfo,
+        buyer_account: &AccountInfo,
+        vault_seeds: &[&[u8]],
+        now: i64,
+    ) -> Result<(), DLUError> {
+        if self.is_past_delist_time(now) {
+            // Only the lister deposited, so they get their whole deposit back.
+            let lister_account = self.lister_account(seller_account, buyer_account);
+            self.check_payout_vault(vault_account)?;
+            self.check_payment_accounts(&[vault_account, lister_account])?;
+            if !DLUToken::check_authority(lister_account, &self.lister.pubkey).map_err(|_| DLUError::KeyMismatch)? {
+                return Err(DLUError::KeyMismatch);
+            }
+            let (deposit, _) = Self::deposits(self.payment, self.insurance)?;
+            Escrow::release_funds(vault_account, lister_account, vault_seeds, deposit)?;
+            self.status = DealStatus::Expired;
+            return Ok(());
+        }
+        self.check_expired(now)?;
+        self.check_payout_vault(vault_account)?;
+        self.check_payment_accounts(&[vault_account, seller_account, buyer_account])?;
+        let (seller_key, buyer_key) = self.parties().ok_or(DLUError::CounterpartyNotFound)?;
+        self.check_parties(&seller_key, &buyer_key, seller_account, buyer_account)?;
+
+        // Release the payment and buyer's insurance back to the buyer's account.
+        let buyer_total = Self::buyer_deposit(self.payment, self.insurance)?;
+        Escrow::release_funds(vault_account, buyer_account, vault_seeds, buyer_total)?;
+
+        // Release the seller's insurance back to the seller's account.
+        Escrow::release_funds(vault_account, seller_account, vault_seeds, self.insurance)?;
+
+        // Update the status of the deal to 'Expired'.
+        self.status = DealStatus::Expired;
+
+        Ok(())
+    }
+
+    /// Expires an overdue deal on behalf of any keeper, returning the bounty paid to them.
+    ///
+    /// Only deals whose escrow sits in the vault can be cranked, since the program signs
+    /// the releases itself. The bounty is capped at the insurance and taken half from each
+    /// side's refund, or from the lister's alone when delisting a listing no one accepted.
+    pub fn crank_expire(
+        &mut self,
+        vault_account: &AccountInfo,
+        seller_account: &AccountInfo,
+        buyer_account: &AccountInfo,
+        keeper_account: &AccountInfo,
+        vault_seeds: &[&[u8]],
+        bounty: u64,
+        now: i64,
+    ) -> Result<u64, DLUError> {
+        if self.is_past_delist_time(now) {
+            return self.crank_delist(vault_account, seller_account, buyer_account, keeper_account, vault_seeds, bounty);
+        }
+        self.check_expired(now)?;
+        self.check_payout_vault(vault_account)?;
+        self.check_payment_accounts(&[vault_account, seller_account, buyer_account, keeper_account])?;
+
+        // Anyone may crank, so the refunds must go to the parties' own accounts.
+        let (seller_key, buyer_key) = self.parties().ok_or(DLUError::CounterpartyNotFound)?;
+        let owned_by = |account: &AccountInfo, key: &Pubkey| DLUToken::check_authority(account, key).map_err(|_| DLUError::KeyMismatch);
+        if !owned_by(seller_account, &seller_key)? || !owned_by(buyer_account, &buyer_key)? {
+            return Err(DLUError::KeyMismatch);
+        }
+
+        let bounty = bounty.min(self.insurance);
+        let buyer_share = bounty / 2;
+        let buyer_total = safe_math::sub(Self::buyer_deposit(self.payment, self.insurance)?, buyer_share)?;
+        let seller_total = safe_math::sub(self.insurance, bounty - buyer_share)?;
+
+        for (destination, amount) in [(buyer_account, buyer_total), (seller_account, seller_total), (keeper_account, bounty)] {
+            if amount > 0 {
+                DLUToken::transfer_signed(vault_account, destination, vault_account, amount, &[vault_seeds])
+                    .map_err(|_| DLUError::TransferFailed)?;
+            }
+        }
+
+        self.status = DealStatus::Expired;
+
+        Ok(bounty)
+    }
+
+    /// Delists a listing past its delist time on behalf of any keeper, returning the lister's
+    /// deposit less the bounty, which is capped at the insurance.
+    fn crank_delist(
+        &mut self,
+        vault_account: &AccountInfo,
+        seller_account: &AccountInfo,
+        buyer_account: &AccountInfo,
+        keeper_account: &AccountInfo,
+        vault_seeds: &[&[u8]],
+        bounty: u64,
+    ) -> Result<u64, DLUError> {
+        self.check_payout_vault(vault_account)?;
+        let lister_account = self.lister_account(seller_account, buyer_account);
+        self.check_payment_accounts(&[vault_account, lister_account, keeper_account])?;
+        if !DLUToken::check_authority(lister_account, &self.lister.pubkey).map_err(|_| DLUError::KeyMismatch)? {
+            return Err(DLUError::KeyMismatch);
+        }
+
+        let bounty = bounty.min(self.insurance);
+        let (deposit, _) = Self::deposits(self.payment, self.insurance)?;
+        for (destination, amount) in [(lister_account, safe_math::sub(deposit, bounty)?), (keeper_account, bounty)] {
+            if amount > 0 {
+                DLUToken::transfer_signed(vault_account, destination, vault_account, amount, &[vault_seeds])
+                    .map_err(|_| DLUError::TransferFailed)?;
+            }
+        }
+
+        self.status = DealStatus::Expired;
+
+        Ok(bounty)
+    }
+
+    /// Expires a listing no one accepted within `MAX_LISTING_LIFETIME` past its meeting, on
+    /// behalf of anyone, returning the deposit refunded to the lister out of the vault.
+    pub fn reclaim_stale_listing(
+        &mut self,
+        vault_account: &AccountInfo,
+        lister_account: &AccountInfo,
+        vault_seeds: &[&[u8]],
+        now: i64,
+    ) -> Result<u64, DLUError> {
+        if self.status != DealStatus::Listed {
+            return Err(DLUError::NotListed);
+        }
+        if !self.is_stale(now) {
+            return Err(DLUError::ListingNotStale);
+        }
+        self.check_payout_vault(vault_account)?;
+        self.check_payment_accounts(&[vault_account, lister_account])?;
+
+        // Anyone may reclaim, so the refund must go to the lister's own account.
+        if !DLUToken::check_authority(lister_account, &self.lister.pubkey).map_err(|_| DLUError::KeyMismatch)? {
+            return Err(DLUError::KeyMismatch);
+        }
+
+        let (deposit, _) = Self::deposits(self.payment, self.insurance)?;
+        DLUToken::transfer_signed(vault_account, lister_account, vault_account, deposit, &[vault_seeds])
+            .map_err(|_| DLUError::TransferFailed)?;
+
+        self.status = DealStatus::Expired;
+
+        Ok(deposit)
+    }
+
+    /// Returns true if the deal is still listed `MAX_LISTING_LIFETIME` past its meeting.
+    pub fn is_stale(&self, now: i64) -> bool {
+        self.status == DealStatus::Listed && now >= self.meeting_datetime.saturating_add(time::MAX_LISTING_LIFETIME)
+    }
+
+    /// Returns the lister's token account out of the seller's and the buyer's.
+    fn lister_account<'a, 'b>(&self, seller_account: &'a AccountInfo<'b>, buyer_account: &'a AccountInfo<'b>) -> &'a AccountInfo<'b> {
+        if R::LISTED_BY_SELLER { seller_account } else { buyer_account }
+    }
+
+    /// Returns true if no one accepted the listing before its delist time.
+    fn is_past_delist_time(&self, now: i64) -> bool {
+        self.status == DealStatus::Listed && self.delist_at.is_some_and(|delist_at| now >= delist_at)
+    }
+
+    /// Schedules a listing to open for acceptances at `activate_at` and, with a `delist_at`,
+    /// to be expired from then on unless someone accepted it.
+    pub fn schedule(&mut self, lister: &Pubkey, activate_at: i64, delist_at: Option<i64>) -> Result<(), DLUError> {
+        if self.status != DealStatus::Listed {
+            return Err(DLUError::NotListed);
+        }
+        if *lister != self.lister.pubkey {
+            return Err(DLUError::NotAuthorized);
+        }
+        if delist_at.is_some_and(|delist_at| delist_at <= activate_at) {
+            return Err(DLUError::InvalidSchedule);
+        }
+        self.activate_at = activate_at;
+        self.delist_at = delist_at;
+        Ok(())
+    }
+
+    /// Ensures the listing has been activated and not reached its delist time.
+    pub fn check_active(&self, now: i64) -> Result<(), DLUError> {
+        if now < self.activate_at || self.delist_at.is_some_and(|delist_at| now >= delist_at) {
+            return Err(DLUError::ListingNotActive);
+        }
+        Ok(())
+    }
+
+    /// Returns when the listing opens for acceptances and when it is delisted, if ever.
+    pub fn listing_window(&self) -> (i64, Option<i64>) {
+        (self.activate_at, self.delist_at)
+    }
+
+    /// Ensures the deal is accepted and the grace period after its meeting has passed.
+    fn check_expired(&self, now: i64) -> Result<(), DLUError> {
+        if !time::is_expired(self.meeting_datetime, now) {
+            return Err(DLUError::DealNotExpired);
+        }
+        if self.status != DealStatus::Accepted {
+            return Err(DLUError::NotAccepted);
+        }
+        Ok(())
+    }
+
+    /// Edits a deal that has not been accepted yet. A new payment tops up or partially
+    /// releases the lister's deposit so the escrow always matches the deal's terms.
+    pub fn update(
+        &mut self,
+        lister: &User,
+        goodsorservice_description: Option<String>,
+        meeting_point: Option<Location>,
+        meeting_datetime: Option<i64>,
+        payment: Option<u64>,
+        lister_account: &AccountInfo,
+        lister_authority_info: &AccountInfo,
+        vault_account: &AccountInfo,
+        vault_seeds: &[&[u8]],
+    ) -> Result<(), DLUError> {
+        // Ensure the deal is in the 'Listed' state.
+        if self.status != DealStatus::Listed {
+            return Err(DLUError::NotListed);
+        }
+        if lister.pubkey != self.lister.pubkey {
+            return Err(DLUError::NotAuthorized);
+        }
+        self.check_payout_vault(vault_account)?;
+        self.check_payment_accounts(&[lister_account, vault_account])?;
+        if let Some(meeting_point) = &meeting_point {
+            meeting_point.validate()?;
+        }
+
+        if let Some(payment) = payment {
+            self.reprice(payment, lister_account, lister_authority_info, vault_account, vault_seeds)?;
+        }
+
+        if let Some(goodsorservice_description) = goodsorservice_description {
+            self.goodsorservice_description = goodsorservice_description;
+        }
+        // A new meeting point is published in full.
+        if let Some(meeting_point) = meeting_point {
+            self.meeting_point = meeting_point;
+            self.meeting_point_hash = None;
+        }
+        if let Some(meeting_datetime) = meeting_datetime {
+            self.meeting_datetime = meeting_datetime;
+        }
+
+        Ok(())
+    }
+
+    /// Sets a new payment, insured for the same amount, topping up or partially releasing
+    /// the lister's deposit to match.
+    fn reprice(
+        &mut self,
+        payment: u64,
+        lister_account: &AccountInfo,
+        lister_authority_info: &AccountInfo,
+        vault_account: &AccountInfo,
+        vault_seeds: &[&[u8]],
+    ) -> Result<(), DLUError> {
+        let (old_deposit, _) = Self::deposits(self.payment, self.insurance)?;
+        let (new_deposit, _) = Self::deposits(payment, payment)?;
+
+        if new_deposit > old_deposit {
+            let top_up = safe_math::sub(new_deposit, old_deposit)?;
+            let lister_balance = DLUToken::get_balance(lister_account).map_err(|_| DLUError::BalanceUnavailable)?;
+            if lister_balance < top_up {
+                return Err(DLUError::InsufficientFunds);
+            }
+            Escrow::lock_funds(lister_account, vault_account, lister_authority_info, top_up)?;
+        } else if new_deposit < old_deposit {
+            let refund = safe_math::sub(old_deposit, new_deposit)?;
+            Escrow::release_funds(vault_account, lister_account, vault_seeds, refund)?;
+        }
+
+        self.payment = payment;
+        self.insurance = payment;
+        Ok(())
+    }
+
+    /// Cancels a deal that has not been accepted yet, refunding the lister's deposit.
+    pub fn cancel(
+        &mut self,
+        lister_account: &AccountInfo,
+        vault_account: &AccountInfo,
+        vault_seeds: &[&[u8]],
+    ) -> Result<(), DLUError> {
+        // Ensure the deal is in the 'Listed' state.
+        if self.status != DealStatus::Listed {
+            return Err(DLUError::NotListed);
+        }
+        self.check_payout_vault(vault_account)?;
+        self.check_payment_accounts(&[lister_account, vault_account])?;
+
+        // Release the lister's deposit back to their account.
+        let (deposit, _) = Self::deposits(self.payment, self.insurance)?;
+        Escrow::release_funds(vault_account, lister_account, vault_seeds, deposit)?;
+
+        // Invalidate the keys and update the status of the deal to 'Canceled'.
+        self.clear_keys();
+        self.status = DealStatus::Canceled;
+
+        Ok(())
+    }
+
+    /// Refunds part of the payment of a completed deal from the seller to the buyer.
+    pub fn goodwill_refund(
+        &self,
+        seller: &mut User,
+        buyer: &mut User,
+        seller_account: &AccountInfo,
+        buyer_account: &AccountInfo,
+        seller_authority_info: &AccountInfo,
+        amount: u64,
+    ) -> Result<(), DLUError> {
+        // Ensure the deal is in the 'Completed' state.
+        if self.status != DealStatus::Completed {
+            return Err(DLUError::NotCompleted);
+        }
+
+        // Only the seller of this deal can refund its buyer.
+        if self.parties() != Some((seller.pubkey, buyer.pubkey)) {
+            return Err(DLUError::NotDealParty);
+        }
+
+        // A refund can never exceed what the buyer paid, in the mint they paid in.
+        if amount == 0 || amount > self.payment {
+            return Err(DLUError::InvalidRefundAmount);
+        }
+        self.check_payment_accounts(&[seller_account, buyer_account])?;
+
+        DLUToken::transfer(seller_account, buyer_account, seller_authority_info, amount)
+            .map_err(|_| DLUError::TransferFailed)?;
+
+        seller.record_goodwill_refund_issued();
+        buyer.record_goodwill_refund_received();
+
+        Ok(())
+    }
+
+    /// Lists an expired or canceled deal again under the same account and terms.
+    pub fn relist(
+        &mut self,
+        lister: &mut User,
+        lister_account: &AccountInfo,
+        lister_authority_info: &AccountInfo,
+        vault_account: &AccountInfo,
+    ) -> Result<(), DLUError> {
+        if self.status != DealStatus::Expired && self.status != DealStatus::Canceled {
+            return Err(DLUError::NotRelistable);
+        }
+        if lister.pubkey != self.lister.pubkey {
+            return Err(DLUError::NotAuthorized);
+        }
+        self.check_payment_accounts(&[lister_account, vault_account])?;
+
+        // Lock a fresh deposit from the lister into the vault of the deal's mint.
+        let (deposit, _) = Self::deposits(self.payment, self.insurance)?;
+        let lister_balance = DLUToken::get_balance(lister_account).map_err(|_| DLUError::BalanceUnavailable)?;
+        if lister_balance < deposit {
+            return Err(DLUError::InsufficientFunds);
+        }
+        Escrow::lock_funds(lister_account, vault_account, lister_authority_info, deposit)?;
+
+        // Start over as a new listing.
+        self.lister = lister.clone();
+        self.acceptor = None;
+        self.clear_keys();
+        self.escrow_vault = Some(*vault_account.key);
+        self.generation = self.generation.checked_add(1).ok_or(DLUError::ArithmeticOverflow)?;
+        self.activate_at = 0;
+        self.delist_at = None;
+        self.status = DealStatus::Listed;
+
+        Ok(())
+    }
+
+    /// Points the deal's escrow at the vault of its payment mint, returning the amount to
+    /// move there.
+    pub fn migrate_escrow(&mut self, legacy_escrow_account: &AccountInfo, vault_account: &AccountInfo) -> Result<u64, DLUError> {
+        // Only in-flight deals still hold funds in a legacy escrow account.
+        if self.status != DealStatus::Listed && self.status != DealStatus::Accepted {
+            return Err(DLUError::EscrowEmpty);
+        }
+        if self.escrow_vault.is_some() {
+            return Err(DLUError::EscrowAlreadyMigrated);
+        }
+        self.check_payment_accounts(&[legacy_escrow_account, vault_account])?;
+
+        self.escrow_vault = Some(*vault_account.key);
+        Ok(self.escrowed_amount())
+    }
+
+    /// Returns the escrow of an in-flight deal to the parties who deposited it and cancels
+    /// the deal, for governance unwinding a deal a bug left stuck. Returns the seller's and
+    /// the buyer's refunds out of the vault.
+    pub fn emergency_unwind(
+        &mut self,
+        vault_account: &AccountInfo,
+        seller_account: &AccountInfo,
+        buyer_account: &AccountInfo,
+        vault_seeds: &[&[u8]],
+    ) -> Result<(u64, u64), DLUError> {
+        self.check_vault(vault_account)?;
+        self.check_payment_accounts(&[vault_account, seller_account, buyer_account])?;
+
+        let owned_by = |account: &AccountInfo, key: &Pubkey| DLUToken::check_authority(account, key).map_err(|_| DLUError::KeyMismatch);
+        let (seller_refund, buyer_refund) = match self.status {
+            DealStatus::Listed => {
+                // Only the lister deposited anything yet.
+                let lister_account = self.lister_account(seller_account, buyer_account);
+                if !owned_by(lister_account, &self.lister.pubkey)? {
+                    return Err(DLUError::KeyMismatch);
+                }
+                let (deposit, _) = Self::deposits(self.payment, self.insurance)?;
+                if R::LISTED_BY_SELLER { (deposit, 0) } else { (0, deposit) }
+            }
+            DealStatus::Accepted => {
+                let (seller_key, buyer_key) = self.parties().ok_or(DLUError::CounterpartyNotFound)?;
+                if !owned_by(seller_account, &seller_key)? || !owned_by(buyer_account, &buyer_key)? {
+                    return Err(DLUError::KeyMismatch);
+                }
+                (self.insurance, Self::buyer_deposit(self.payment, self.insurance)?)
+            }
+            _ => return Err(DLUError::EscrowEmpty),
+        };
+
+        for (destination, amount) in [(seller_account, seller_refund), (buyer_account, buyer_refund)] {
+            if amount > 0 {
+                DLUToken::transfer_signed(vault_account, destination, vault_account, amount, &[vault_seeds])
+                    .map_err(|_| DLUError::TransferFailed)?;
+            }
+        }
+
+        self.clear_keys();
+        self.status = DealStatus::Canceled;
+
+        Ok((seller_refund, buyer_refund))
+    }
+
+    /// Reconciles the vault the deal's escrow sits in, which pools every deal of its mint, so
+    /// its balance is compared with `vault_locked`, the total the stats record escrowed there.
+    /// Returns both; a vault holding less flags the deal for good.
+    pub fn verify_escrow(&mut self, vault_account: &AccountInfo, vault_locked: u64) -> Result<(u64, u64), DLUError> {
+        self.check_vault(vault_account)?;
+        self.check_payment_accounts(&[vault_account])?;
+
+        let actual = DLUToken::get_balance(vault_account).map_err(|_| DLUError::BalanceUnavailable)?;
+        if actual < vault_locked {
+            self.escrow_flagged = true;
+        }
+        Ok((vault_locked, actual))
+    }
+
+    /// Returns true once a reconciliation found the deal's escrow short, which blocks its payouts.
+    pub fn is_escrow_flagged(&self) -> bool {
+        self.escrow_flagged
+    }
+
+    /// Returns the amount currently held in escrow for this deal. Both deposits hold the
+    /// insurance as locked at listing, which for inventory offers stays above the payment of
+    /// the quantity accepted.
+    pub fn escrowed_amount(&self) -> u64 {
+        match self.status {
+            DealStatus::Listed if R::LISTED_BY_SELLER => self.insurance,
+            DealStatus::Listed => self.payment.saturating_add(self.insurance),
+            DealStatus::Accepted => self.payment.saturating_add(self.insurance.saturating_mul(2)),
+            DealStatus::Completed => {
+                let held = self.holdback.as_ref().map_or(0, Holdback::escrowed_amount);
+                held.saturating_add(self.chargeback.as_ref().map_or(0, Chargeback::escrowed_amount))
+            }
+            _ => 0,
+        }
+    }
+
+    /// Returns true once the deal can be archived: it ended with nothing left in escrow,
+    /// and `ARCHIVE_DELAY` passed since its meeting time.
+    pub fn is_archivable(&self, now: i64) -> bool {
+        let ended = !matches!(self.status, DealStatus::Listed | DealStatus::Accepted);
+        ended && self.escrowed_amount() == 0 && now >= self.meeting_datetime.saturating_add(ARCHIVE_DELAY)
+    }
+
+    /// Returns the hash of the goods' name, ignoring case and surrounding whitespace, which
+    /// matching requests with offers compares.
+    pub fn goods_name_hash(&self) -> [u8; 32] {
+        hash(self.goodsorservice_name.trim().to_lowercase().as_bytes()).to_bytes()
+    }
+
+    /// Returns the hashes of the seller's and the buyer's one-time keys, `NO_KEY` until
+    /// they committed them.
+    pub fn key_hashes(&self) -> (KeyHash, KeyHash) {
+        (self.seller_key.hash, self.buyer_key.hash)
+    }
+
+    /// Returns the seller's and the buyer's one-time keys as committed.
+    pub fn keys(&self) -> (OneTimeKey, OneTimeKey) {
+        (self.seller_key, self.buyer_key)
+    }
+
+    /// Commits the hash of the one-time key a party of an accepted deal will hand over at
+    /// the meeting, accepted for `KEY_LIFETIME` from `now`. Each key is committed once per
+    /// acceptance, then only replaced by `regenerate_keys`.
+    pub fn commit_key(&mut self, party: &Pubkey, key_hash: KeyHash, now: i64) -> Result<(), DLUError> {
+        if self.status != DealStatus::Accepted {
+            return Err(DLUError::NotAccepted);
+        }
+        let (seller, buyer) = self.parties().ok_or(DLUError::CounterpartyNotFound)?;
+        let key = if *party == seller {
+            &mut self.seller_key
+        } else if *party == buyer {
+            &mut self.buyer_key
+        } else {
+            return Err(DLUError::NotDealParty);
+        };
+        if key.is_committed() {
+            return Err(DLUError::KeyAlreadyCommitted);
+        }
+        *key = OneTimeKey::commit(key_hash, now)?;
+        Ok(())
+    }
+
+    /// Replaces both one-time keys of an accepted deal at the request of both parties, for
+    /// keys that were lost or expired. The new keys are accepted for `KEY_LIFETIME`.
+    pub fn regenerate_keys(
+        &mut self,
+        seller: &Pubkey,
+        buyer: &Pubkey,
+        seller_key_hash: KeyHash,
+        buyer_key_hash: KeyHash,
+        now: i64,
+    ) -> Result<(), DLUError> {
+        if self.status != DealStatus::Accepted {
+            return Err(DLUError::NotAccepted);
+        }
+        if self.parties() != Some((*seller, *buyer)) {
+            return Err(DLUError::NotDealParty);
+        }
+        self.seller_key = OneTimeKey::commit(seller_key_hash, now)?;
+        self.buyer_key = OneTimeKey::commit(buyer_key_hash, now)?;
+        Ok(())
+    }
+
+    /// Reschedules the meeting of an accepted deal to a later `meeting_datetime`, which moves
+    /// its expiry window along. Both parties consent, and only before the deal can be expired.
+    pub fn extend_deadline(
+        &mut self,
+        seller: &Pubkey,
+        buyer: &Pubkey,
+        meeting_datetime: i64,
+        now: i64,
+    ) -> Result<(), DLUError> {
+        if self.status != DealStatus::Accepted {
+            return Err(DLUError::NotAccepted);
+        }
+        if self.parties() != Some((*seller, *buyer)) {
+            return Err(DLUError::NotDealParty);
+        }
+        if meeting_datetime <= self.meeting_datetime || time::is_expired(self.meeting_datetime, now) {
+            return Err(DLUError::InvalidDeadline);
+        }
+        self.meeting_datetime = meeting_datetime;
+        Ok(())
+    }
+
+    /// Replaces the public part of a hidden meeting point with the exact point once the deal
+    /// is accepted, checking it against the hash the lister committed to when listing.
+    pub fn reveal_meeting_point(
+        &mut self,
+        lister: &Pubkey,
+        meeting_point: Location,
+        salt: &[u8; 32],
+    ) -> Result<(), DLUError> {
+        if self.status != DealStatus::Accepted {
+            return Err(DLUError::NotAccepted);
+        }
+        if *lister != self.lister.pubkey {
+            return Err(DLUError::NotAuthorized);
+        }
+        let committed = self.meeting_point_hash.ok_or(DLUError::MeetingPointNotHidden)?;
+        if meeting_point.commitment(salt)? != committed {
+            return Err(DLUError::MeetingPointMismatch);
+        }
+
+        self.meeting_point = meeting_point;
+        self.meeting_point_hash = None;
+        Ok(())
+    }
+
+    /// Returns the meeting point, which is only its public part while it is hidden.
+    pub fn meeting_point(&self) -> &Location {
+        &self.meeting_point
+    }
+
+    /// Returns true while the exact meeting point has not been revealed.
+    pub fn is_meeting_point_hidden(&self) -> bool {
+        self.meeting_point_hash.is_some()
+    }
+
+    /// Returns the id of the deal.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Returns the public keys of the seller and the buyer once the deal has been accepted.
+    pub fn parties(&self) -> Option<(Pubkey, Pubkey)> {
+        let acceptor = self.acceptor.as_ref()?.pubkey;
+        if R::LISTED_BY_SELLER {
+            Some((self.lister.pubkey, acceptor))
+        } else {
+            Some((acceptor, self.lister.pubkey))
+        }
+    }
+
+    /// Returns the holdback of the deal, if it was listed with one.
+    pub fn holdback(&self) -> Option<&Holdback> {
+        self.holdback.as_ref()
+    }
+
+    /// Releases the holdback of a completed deal to the seller once its window has passed
+    /// undisputed, returning the amount.
+    pub fn release_holdback(
+        &mut self,
+        seller: &Pubkey,
+        seller_account: &AccountInfo,
+        vault_account: &AccountInfo,
+        vault_seeds: &[&[u8]],
+        now: i64,
+    ) -> Result<u64, DLUError> {
+        let (seller_key, _) = self.completed_parties()?;
+        if *seller != seller_key {
+            return Err(DLUError::NotAuthorized);
+        }
+        self.check_payout_vault(vault_account)?;
+        self.check_payment_accounts(&[seller_account, vault_account])?;
+
+        let holdback = self.holdback.as_mut().ok_or(DLUError::HoldbackNotHeld)?;
+        let amount = holdback.release(now)?;
+        if amount > 0 {
+            Escrow::release_funds(vault_account, seller_account, vault_seeds, amount)?;
+        }
+        Ok(amount)
+    }
+
+    /// Freezes the holdback of a completed deal on the buyer's claim that the goods are
+    /// defective, while its window is open.
+    pub fn dispute_holdback(&mut self, buyer: &Pubkey, now: i64) -> Result<(), DLUError> {
+        let (_, buyer_key) = self.completed_parties()?;
+        if *buyer != buyer_key {
+            return Err(DLUError::NotAuthorized);
+        }
+        self.holdback.as_mut().ok_or(DLUError::HoldbackNotHeld)?.dispute(now)
+    }
+
+    /// Pays out a disputed holdback: to the buyer when the goods were found defective, to
+    /// the seller otherwise. Returns who was paid and how much.
+    pub fn resolve_holdback_dispute(
+        &mut self,
+        defective: bool,
+        recipient_account: &AccountInfo,
+        vault_account: &AccountInfo,
+        vault_seeds: &[&[u8]],
+    ) -> Result<(Pubkey, u64), DLUError> {
+        let (seller, buyer) = self.completed_parties()?;
+        let recipient = if defective { buyer } else { seller };
+        self.check_payout_vault(vault_account)?;
+        self.check_payment_accounts(&[recipient_account, vault_account])?;
+        if !DLUToken::check_authority(recipient_account, &recipient).map_err(|_| DLUError::KeyMismatch)? {
+            return Err(DLUError::KeyMismatch);
+        }
+
+        let holdback = self.holdback.as_mut().ok_or(DLUError::HoldbackNotHeld)?;
+        let amount = holdback.resolve(defective)?;
+        if amount > 0 {
+            Escrow::release_funds(vault_account, recipient_account, vault_seeds, amount)?;
+        }
+        Ok((recipient, amount))
+    }
+
+    /// Returns the chargeback window of the deal, if its seller's insurance was kept back.
+    pub fn chargeback(&self) -> Option<&Chargeback> {
+        self.chargeback.as_ref()
+    }
+
+    /// Opens the buyer's chargeback claim on a completed deal while its window is open,
+    /// freezing the seller's insurance until arbitration rules on it.
+    pub fn open_chargeback(&mut self, buyer: &Pubkey, now: i64) -> Result<(), DLUError> {
+        let (_, buyer_key) = self.completed_parties()?;
+        if *buyer != buyer_key {
+            return Err(DLUError::NotAuthorized);
+        }
+        self.chargeback.as_mut().ok_or(DLUError::ChargebackNotOpen)?.claim(now)
+    }
+
+    /// Releases the seller's insurance of a completed deal once its chargeback window has
+    /// closed without a claim, returning the amount.
+    pub fn release_seller_insurance(
+        &mut self,
+        seller: &Pubkey,
+        seller_account: &AccountInfo,
+        vault_account: &AccountInfo,
+        vault_seeds: &[&[u8]],
+        now: i64,
+    ) -> Result<u64, DLUError> {
+        let (seller_key, _) = self.completed_parties()?;
+        if *seller != seller_key {
+            return Err(DLUError::NotAuthorized);
+        }
+        self.check_payout_vault(vault_account)?;
+        self.check_payment_accounts(&[seller_account, vault_account])?;
+
+        let amount = self.chargeback.as_mut().ok_or(DLUError::ChargebackNotOpen)?.release(now)?;
+        Escrow::release_funds(vault_account, seller_account, vault_seeds, amount)?;
+        Ok(amount)
+    }
+
+    /// Settles a chargeback claim: the seller's insurance goes to the buyer when the claim
+    /// is upheld and back to the seller otherwise. Returns who was paid and how much.
+    pub fn resolve_chargeback(
+        &mut self,
+        upheld: bool,
+        recipient_account: &AccountInfo,
+        vault_account: &AccountInfo,
+        vault_seeds: &[&[u8]],
+    ) -> Result<(Pubkey, u64), DLUError> {
+        let (seller, buyer) = self.completed_parties()?;
+        let recipient = if upheld { buyer } else { seller };
+        self.check_payout_vault(vault_account)?;
+        self.check_payment_accounts(&[recipient_account, vault_account])?;
+        if !DLUToken::check_authority(recipient_account, &recipient).map_err(|_| DLUError::KeyMismatch)? {
+            return Err(DLUError::KeyMismatch);
+        }
+
+        let amount = self.chargeback.as_mut().ok_or(DLUError::ChargebackNotClaimed)?.resolve(upheld)?;
+        Escrow::release_funds(vault_account, recipient_account, vault_seeds, amount)?;
+        Ok((recipient, amount))
+    }
+
+    /// Returns the seller and buyer of a completed deal while a dispute of the given kind
+    /// awaits a ruling.
+    pub fn disputing_parties(&self, kind: DisputeKind) -> Result<(Pubkey, Pubkey), DLUError> {
+        let parties = self.completed_parties()?;
+        let disputed = match kind {
+            DisputeKind::Holdback => self.holdback.as_ref().map(|h| h.status) == Some(HoldbackStatus::Disputed),
+            DisputeKind::Chargeback => self.chargeback.as_ref().map(|c| c.status) == Some(ChargebackStatus::Claimed),
+        };
+        if !disputed {
+            return Err(DLUError::IncorrectState);
+        }
+        Ok(parties)
+    }
+
+    /// Returns the seller and buyer of a completed deal.
+    fn completed_parties(&self) -> Result<(Pubkey, Pubkey), DLUError> {
+        if self.status != DealStatus::Completed {
+            return Err(DLUError::NotCompleted);
+        }
+        self.parties().ok_or(DLUError::CounterpartyNotFound)
+    }
+
+    /// Returns the payment the buyer locks on acceptance.
+    pub fn payment(&self) -> u64 {
+        self.payment
+    }
+
+    /// Returns when the parties meet, or the service starts.
+    pub fn meeting_datetime(&self) -> i64 {
+        self.meeting_datetime
+    }
+
+    /// Returns the mint the deal is paid in.
+    pub fn payment_mint(&self) -> Pubkey {
+        self.payment_mint
+    }
+
+    /// Returns the public key of the user who listed the deal.
+    pub fn lister_pubkey(&self) -> Pubkey {
+        self.lister.pubkey
+    }
+
+    /// Returns how many times the deal has been relisted.
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+
+    /// Returns the current status of the deal.
+    pub fn status(&self) -> DealStatus {
+        self.status
+    }
+
+    /// Returns the status transitions the deal recorded.
+    pub fn audit_log(&self) -> &AuditLog {
+        &self.audit_log
+    }
+
+    /// Returns the nonce terminal instructions must name to act on the deal as it is now.
+    pub fn nonce(&self) -> u64 {
+        self.nonce
+    }
+
+    /// Checks that an instruction was built against the deal's current state.
+    pub fn check_nonce(&self, nonce: u64) -> Result<(), DLUError> {
+        if nonce != self.nonce {
+            return Err(DLUError::StaleNonce);
+        }
+        Ok(())
+    }
+
+    /// Records that `actor` moved the deal into its current status at `slot`, along with
+    /// what it holds in escrow from then on, and advances its nonce.
+    pub fn record_transition(&mut self, actor: Pubkey, slot: u64) {
+        let entry = AuditEntry { status: self.status, slot, actor, amount: self.escrowed_amount() };
+        self.audit_log.record(entry);
+        self.nonce = self.nonce.wrapping_add(1);
+    }
+
+    /// Updates the status of the deal.
+    pub fn update_status(&mut self, new_status: DealStatus) {
+        self.status = new_status;
+    }
+
+    fn clear_keys(&mut self) {
+        self.buyer_key = OneTimeKey::NONE;
+        self.seller_key = OneTimeKey::NONE;
+    }
+
+    /// Serializes the deal into a vector of bytes, behind its layout version.
+    pub fn serialize(&self) -> Result<Vec<u8>, DLUError> {
+        migrations::pack(self)
+    }
+
+    /// Deserializes a deal from a slice of bytes.
+    pub fn deserialize(input: &mut &[u8]) -> Result<Self, DLUError> {
+        migrations::unpack(input)
+    }
+}
+
+impl Deal<SellerListed> {
+    /// Accepts a listed offer for the buyer of a compatible listed request, which is closed
+    /// as matched. Its deposit covers the buyer's deposit on the offer and the finder's fee
+    /// paid to the matcher, and the rest goes back to the buyer. Returns the fee.
+    pub fn accept_matched(
+        &mut self,
+        request: &mut Deal<BuyerListed>,
+        buyer: &mut User,
+        buyer_account: &AccountInfo,
+        vault_account: &AccountInfo,
+        vault_seeds: &[&[u8]],
+        matcher_account: &AccountInfo,
+    ) -> Result<u64, DLUError> {
+        if request.status != DealStatus::Listed {
+            return Err(DLUError::NotListed);
+        }
+        if buyer.pubkey != request.lister.pubkey || buyer.pubkey == self.lister.pubkey {
+            return Err(DLUError::NotAuthorized);
+        }
+        if request.payment_mint != self.payment_mint
+            || request.goods_name_hash() != self.goods_name_hash()
+            || !request.meeting_point.same_region(&self.meeting_point)
+        {
+            return Err(DLUError::DealsNotCompatible);
+        }
+        self.check_vault(vault_account)?;
+        request.check_vault(vault_account)?;
+        self.check_payment_accounts(&[buyer_account, vault_account, matcher_account])?;
+
+        // The buyer never pays more than they asked to, finder's fee included.
+        let fee = safe_math::bps(self.payment, FINDER_FEE_BPS)?;
+        if safe_math::add(self.payment, fee)? > request.payment {
+            return Err(DLUError::DealsNotCompatible);
+        }
+
+        self.accept_escrowed(buyer)?;
+        let (request_deposit, _) = Deal::<BuyerListed>::deposits(request.payment, request.insurance)?;
+        let (_, buyer_deposit) = Self::deposits(self.payment, self.insurance)?;
+        let refund = safe_math::sub(safe_math::sub(request_deposit, buyer_deposit)?, fee)?;
+        if fee > 0 {
+            Escrow::release_funds(vault_account, matcher_account, vault_seeds, fee)?;
+        }
+        if refund > 0 {
+            Escrow::release_funds(vault_account, buyer_account, vault_seeds, refund)?;
+        }
+
+        request.clear_keys();
+        request.status = DealStatus::Matched;
+
+        Ok(fee)
+    }
+
+    /// Sets the payment of a listed offer priced by the quantity a buyer is about to accept.
+    /// The insurance stays as locked at listing.
+    pub fn price(&mut self, payment: u64) -> Result<(), DLUError> {
+        if self.status != DealStatus::Listed {
+            return Err(DLUError::NotListed);
+        }
+        self.payment = payment;
+        Ok(())
+    }
+
+    /// Lowers the payment of a listed offer by `percent` before a buyer accepts it with a
+    /// promo code, returning the discount.
+    pub fn discount(&mut self, percent: u8) -> Result<u64, DLUError> {
+        if self.status != DealStatus::Listed {
+            return Err(DLUError::NotListed);
+        }
+        let discount = safe_math::bps(self.payment, u16::from(percent) * 100)?;
+        self.payment = safe_math::sub(self.payment, discount)?;
+        Ok(discount)
+    }
+
+    /// Pays `amount` of an accepted offer's payment out to the seller ahead of completion,
+    /// sending the protocol `fee` out of it to the treasury. Only what is left of the
+    /// payment is paid out on completion, refunded or settled.
+    pub fn pay_out_early(
+        &mut self,
+        seller: &Pubkey,
+        amount: u64,
+        fee: u64,
+        seller_account: &AccountInfo,
+        vault_account: &AccountInfo,
+        treasury_account: &AccountInfo,
+        vault_seeds: &[&[u8]],
+    ) -> Result<(), DLUError> {
+        if self.status != DealStatus::Accepted {
+            return Err(DLUError::NotAccepted);
+        }
+        if *seller != self.lister.pubkey {
+            return Err(DLUError::NotAuthorized);
+        }
+        self.check_payout_vault(vault_account)?;
+        self.check_payment_accounts(&[seller_account, vault_account, treasury_account])?;
+
+        self.payment = safe_math::sub(self.payment, amount)?;
+        if fee > 0 {
+            Escrow::release_funds(vault_account, treasury_account, vault_seeds, fee)?;
+        }
+        let proceeds = safe_math::sub(amount, fee)?;
+        if proceeds > 0 {
+            Escrow::release_funds(vault_account, seller_account, vault_seeds, proceeds)?;
+        }
+        Ok(())
+    }
+}
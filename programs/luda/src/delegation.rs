@@ -0,0 +1,76 @@
+use solana_program::pubkey::Pubkey;
+use solana_program::borsh::{BorshSerialize, BorshDeserialize};
+use crate::errors::DLUError;
+
+/// Longest a session key can stay valid, in seconds.
+pub const MAX_SESSION_DURATION: i64 = 86_400;
+/// Most deals or shipments a single session key can act on.
+pub const MAX_SESSION_ENTITIES: usize = 4;
+
+/// Short-lived session key a user authorizes to act for them on specific deals.
+///
+/// Mobile users can hand a throwaway key to their phone for a meetup instead of signing
+/// with their main wallet. The session key can only stand in for its owner on the entities
+/// it was scoped to, until it expires or the owner revokes it. Completions are proven by
+/// the one-time keys rather than a party's signature, so a session key can relay them
+/// already; it is the carrier's `ConfirmPickup` signature it takes the place of.
+///
+/// Lives at the PDA `["delegation", owner_pubkey, session_pubkey]`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct Delegation {
+    pub owner: Pubkey,
+    pub session: Pubkey,
+    pub expires_at: i64,
+    pub entities: Vec<Pubkey>,  // Deals and shipments the session key may act on.
+}
+
+impl Delegation {
+    /// Authorizes `session` to act for `owner` on `entities` from `now` until `expires_at`.
+    pub fn authorize(
+        owner: Pubkey,
+        session: Pubkey,
+        entities: Vec<Pubkey>,
+        expires_at: i64,
+        now: i64,
+    ) -> Result<Self, DLUError> {
+        let valid = session != owner
+            && !entities.is_empty()
+            && entities.len() <= MAX_SESSION_ENTITIES
+            && expires_at > now
+            && expires_at <= now.saturating_add(MAX_SESSION_DURATION);
+        if !valid {
+            return Err(DLUError::InvalidSessionTerms);
+        }
+        Ok(Delegation { owner, session, expires_at, entities })
+    }
+
+    /// Checks that the session key may act for its owner on `entity` at `now`.
+    pub fn check(&self, session: &Pubkey, entity: &Pubkey, now: i64) -> Result<(), DLUError> {
+        if *session != self.session {
+            return Err(DLUError::NotAuthorized);
+        }
+        if now >= self.expires_at {
+            return Err(DLUError::SessionExpired);
+        }
+        if !self.entities.contains(entity) {
+            return Err(DLUError::OutOfSessionScope);
+        }
+        Ok(())
+    }
+
+    /// Ends the session early.
+    pub fn revoke(&mut self) {
+        self.expires_at = 0;
+    }
+
+    /// Serializes the delegation into a vector of bytes.
+    pub fn serialize(&self) -> Result<Vec<u8>, DLUError> {
+        self.try_to_vec().map_err(|_| DLUError::SerializationFailed)
+    }
+
+    /// Deserializes a delegation from a slice of bytes.
+    pub fn deserialize(input: &mut &[u8]) -> Result<Self, DLUError> {
+        // Accounts are sized for a full scope, so the tail may be zero padding.
+        <Self as BorshDeserialize>::deserialize(input).map_err(|_| DLUError::DeserializationFailed)
+    }
+}
@@ -0,0 +1,248 @@
+use crate::addressing::IndexAccount;
+use crate::appeal::Appeal;
+use crate::arbiter::{Arbiter, ArbiterRegistry, Arbitration};
+use crate::archive::History;
+use crate::attestation::{Attestation, AttestorRegistry};
+use crate::bond::FailBond;
+use crate::config::Config;
+use crate::dao::{Dao, Proposal, VoteLock, VoteRecord};
+use crate::delegation::Delegation;
+use crate::emergency::EmergencyResolution;
+use crate::emission::RewardEmission;
+use crate::leaderboard::Leaderboard;
+use crate::messaging::MessageLog;
+use crate::multisig::Multisig;
+use crate::offer::Offer;
+use crate::onetimekeys::KeyManager;
+use crate::organization::Organization;
+use crate::penalty_pool::{PenaltyPool, PenaltyRewards};
+use crate::promo::PromoCodes;
+use crate::quote::Quote;
+use crate::referral::ReferralRewards;
+use crate::region::RegionIndex;
+use crate::request::Request;
+use crate::review::Review;
+use crate::route::{CarrierRoute, RouteIndex};
+use crate::shipment::Shipment;
+use crate::stake::CarrierStake;
+use crate::stats::Stats;
+use crate::timelock::TimelockQueue;
+use crate::user::User;
+use crate::vault_registry::VaultRegistry;
+use crate::watchlist::Watchlist;
+
+/// Kind of data a program account holds, written as the first byte of its data. Loaders
+/// check it, so an account written as one kind is never read as another. Accounts nobody
+/// wrote yet are zeroed and so read `Uninitialized`. Kinds are part of the account layout:
+/// new ones go at the end.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccountKind {
+    Uninitialized = 0,
+    Config,
+    Stats,
+    User,
+    Index,
+    Offer,
+    Request,
+    Quote,
+    Shipment,
+    FailBond,
+    CarrierStake,
+    Review,
+    RegionIndex,
+    MessageLog,
+    CarrierRoute,
+    RouteIndex,
+    Arbiter,
+    ArbiterRegistry,
+    Arbitration,
+    Appeal,
+    Delegation,
+    Organization,
+    Multisig,
+    AttestorRegistry,
+    Attestation,
+    ReferralRewards,
+    PromoCodes,
+    Watchlist,
+    Leaderboard,
+    History,
+    KeyManager,
+    EmergencyResolution,
+    TimelockQueue,
+    Dao,
+    Proposal,
+    VoteLock,
+    VoteRecord,
+    PenaltyPool,
+    PenaltyRewards,
+    VaultRegistry,
+    RewardEmission,
+}
+
+/// An account type the program stores behind its `AccountKind`.
+pub trait Discriminated {
+    /// Kind written in front of accounts of this type.
+    const KIND: AccountKind;
+}
+
+impl Discriminated for Config {
+    const KIND: AccountKind = AccountKind::Config;
+}
+
+impl Discriminated for Stats {
+    const KIND: AccountKind = AccountKind::Stats;
+}
+
+impl Discriminated for User {
+    const KIND: AccountKind = AccountKind::User;
+}
+
+impl Discriminated for IndexAccount {
+    const KIND: AccountKind = AccountKind::Index;
+}
+
+impl Discriminated for Offer {
+    const KIND: AccountKind = AccountKind::Offer;
+}
+
+impl Discriminated for Request {
+    const KIND: AccountKind = AccountKind::Request;
+}
+
+impl Discriminated for Quote {
+    const KIND: AccountKind = AccountKind::Quote;
+}
+
+impl Discriminated for Shipment {
+    const KIND: AccountKind = AccountKind::Shipment;
+}
+
+impl Discriminated for FailBond {
+    const KIND: AccountKind = AccountKind::FailBond;
+}
+
+impl Discriminated for CarrierStake {
+    const KIND: AccountKind = AccountKind::CarrierStake;
+}
+
+impl Discriminated for Review {
+    const KIND: AccountKind = AccountKind::Review;
+}
+
+impl Discriminated for RegionIndex {
+    const KIND: AccountKind = AccountKind::RegionIndex;
+}
+
+impl Discriminated for MessageLog {
+    const KIND: AccountKind = AccountKind::MessageLog;
+}
+
+impl Discriminated for CarrierRoute {
+    const KIND: AccountKind = AccountKind::CarrierRoute;
+}
+
+impl Discriminated for RouteIndex {
+    const KIND: AccountKind = AccountKind::RouteIndex;
+}
+
+impl Discriminated for Arbiter {
+    const KIND: AccountKind = AccountKind::Arbiter;
+}
+
+impl Discriminated for ArbiterRegistry {
+    const KIND: AccountKind = AccountKind::ArbiterRegistry;
+}
+
+impl Discriminated for Arbitration {
+    const KIND: AccountKind = AccountKind::Arbitration;
+}
+
+impl Discriminated for Appeal {
+    const KIND: AccountKind = AccountKind::Appeal;
+}
+
+impl Discriminated for Delegation {
+    const KIND: AccountKind = AccountKind::Delegation;
+}
+
+impl Discriminated for Organization {
+    const KIND: AccountKind = AccountKind::Organization;
+}
+
+impl Discriminated for Multisig {
+    const KIND: AccountKind = AccountKind::Multisig;
+}
+
+impl Discriminated for AttestorRegistry {
+    const KIND: AccountKind = AccountKind::AttestorRegistry;
+}
+
+impl Discriminated for Attestation {
+    const KIND: AccountKind = AccountKind::Attestation;
+}
+
+impl Discriminated for ReferralRewards {
+    const KIND: AccountKind = AccountKind::ReferralRewards;
+}
+
+impl Discriminated for PromoCodes {
+    const KIND: AccountKind = AccountKind::PromoCodes;
+}
+
+impl Discriminated for Watchlist {
+    const KIND: AccountKind = AccountKind::Watchlist;
+}
+
+impl Discriminated for Leaderboard {
+    const KIND: AccountKind = AccountKind::Leaderboard;
+}
+
+impl Discriminated for History {
+    const KIND: AccountKind = AccountKind::History;
+}
+
+impl Discriminated for KeyManager {
+    const KIND: AccountKind = AccountKind::KeyManager;
+}
+
+impl Discriminated for EmergencyResolution {
+    const KIND: AccountKind = AccountKind::EmergencyResolution;
+}
+
+impl Discriminated for TimelockQueue {
+    const KIND: AccountKind = AccountKind::TimelockQueue;
+}
+
+impl Discriminated for Dao {
+    const KIND: AccountKind = AccountKind::Dao;
+}
+
+impl Discriminated for Proposal {
+    const KIND: AccountKind = AccountKind::Proposal;
+}
+
+impl Discriminated for VoteLock {
+    const KIND: AccountKind = AccountKind::VoteLock;
+}
+
+impl Discriminated for VoteRecord {
+    const KIND: AccountKind = AccountKind::VoteRecord;
+}
+
+impl Discriminated for PenaltyPool {
+    const KIND: AccountKind = AccountKind::PenaltyPool;
+}
+
+impl Discriminated for PenaltyRewards {
+    const KIND: AccountKind = AccountKind::PenaltyRewards;
+}
+
+impl Discriminated for VaultRegistry {
+    const KIND: AccountKind = AccountKind::VaultRegistry;
+}
+
+impl Discriminated for RewardEmission {
+    const KIND: AccountKind = AccountKind::RewardEmission;
+}
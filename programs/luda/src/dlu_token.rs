@@ -1,20 +1,99 @@
 use solana_program::{
     account_info::AccountInfo,
+    clock::Clock,
     program_error::ProgramError,
     pubkey::Pubkey,
-    program::{invoke},
+    program::{invoke, invoke_signed},
+    program_option::COption,
+    rent::Rent,
+    system_instruction,
+    sysvar::Sysvar,
 };
-use spl_token::{self, state::Account as TokenAccount};
+use spl_token_2022::{
+    self,
+    extension::{transfer_fee::TransferFeeConfig, BaseStateWithExtensions, ExtensionType, StateWithExtensions},
+    state::{Account as TokenAccount, Mint},
+};
+
+use crate::errors::DLUError;
 
 pub struct DLUToken;
 
 impl DLUToken {
+    // Returns the token program owning an account, either SPL Token or Token-2022.
+    pub fn token_program_id(account_info: &AccountInfo) -> Result<Pubkey, ProgramError> {
+        if *account_info.owner == spl_token::id() || *account_info.owner == spl_token_2022::id() {
+            Ok(*account_info.owner)
+        } else {
+            Err(ProgramError::IncorrectProgramId)
+        }
+    }
+
+    // Reads the base state of a token account of either program, skipping any extensions.
+    fn unpack_account(account_info: &AccountInfo) -> Result<TokenAccount, ProgramError> {
+        Self::token_program_id(account_info)?;
+        let data = account_info.data.borrow();
+        Ok(StateWithExtensions::<TokenAccount>::unpack(&data)?.base)
+    }
+
     // Check the DLU balance of a specific account.
     pub fn get_balance(account_info: &AccountInfo) -> Result<u64, ProgramError> {
-        let token_account_data = TokenAccount::unpack(&account_info.data.borrow())?;
+        let token_account_data = Self::unpack_account(account_info)?;
         Ok(token_account_data.amount)
     }
 
+    // Returns the mint of the tokens a token account holds.
+    pub fn get_mint(account_info: &AccountInfo) -> Result<Pubkey, ProgramError> {
+        Ok(Self::unpack_account(account_info)?.mint)
+    }
+
+    // Checks whether a mint is an NFT: a single token that cannot be divided.
+    pub fn is_nft(mint_info: &AccountInfo) -> Result<bool, ProgramError> {
+        Self::token_program_id(mint_info)?;
+        let data = mint_info.data.borrow();
+        let mint = StateWithExtensions::<Mint>::unpack(&data)?.base;
+        Ok(mint.decimals == 0 && mint.supply == 1)
+    }
+
+    // Checks that token accounts all hold the same mint, which must be `payment_mint` unless
+    // that is the default, as on entities listed before payment mints were recorded.
+    pub fn check_payment_mint(payment_mint: &Pubkey, token_accounts: &[&AccountInfo]) -> Result<(), DLUError> {
+        let mut expected = (*payment_mint != Pubkey::default()).then_some(*payment_mint);
+        for account_info in token_accounts {
+            let mint = Self::get_mint(account_info).map_err(|_| DLUError::DeserializationFailed)?;
+            if *expected.get_or_insert(mint) != mint {
+                return Err(DLUError::MintMismatch);
+            }
+        }
+        Ok(())
+    }
+
+    // Guards a transfer before it is invoked: both accounts belong to the same token program
+    // and hold the same mint, which must be `mint` when the transfer names one, and `authority`
+    // may move `amount` out of the source, as its owner or a delegate approved for that much.
+    pub fn token_guards(
+        src_account_info: &AccountInfo,
+        dest_account_info: &AccountInfo,
+        authority: &Pubkey,
+        amount: u64,
+        mint: Option<&Pubkey>,
+    ) -> Result<(), DLUError> {
+        let token_program_id = Self::token_program_id(src_account_info).map_err(|_| DLUError::InvalidAccountOwner)?;
+        if *dest_account_info.owner != token_program_id {
+            return Err(DLUError::InvalidAccountOwner);
+        }
+        let src = Self::unpack_account(src_account_info).map_err(|_| DLUError::DeserializationFailed)?;
+        let dest = Self::unpack_account(dest_account_info).map_err(|_| DLUError::DeserializationFailed)?;
+        if src.mint != dest.mint || matches!(mint, Some(mint) if *mint != src.mint) {
+            return Err(DLUError::MintMismatch);
+        }
+        let delegated = src.delegate == COption::Some(*authority) && src.delegated_amount >= amount;
+        if src.owner != *authority && !delegated {
+            return Err(DLUError::TokenAuthorityMismatch);
+        }
+        Ok(())
+    }
+
     // Transfers DLU tokens from one account to another.
     pub fn transfer(
         src_account_info: &AccountInfo,
@@ -22,18 +101,219 @@ impl DLUToken {
         authority_info: &AccountInfo,
         amount: u64,
     ) -> Result<(), ProgramError> {
-        let transfer_instruction = spl_token::instruction::transfer(
-            &spl_token::id(),
+        Self::transfer_signed(src_account_info, dest_account_info, authority_info, amount, &[])
+    }
+
+    // Creates the associated DLU token account of a wallet, with the payer funding its rent.
+    pub fn create_associated_account<'a>(
+        payer_info: &AccountInfo<'a>,
+        associated_account_info: &AccountInfo<'a>,
+        wallet_info: &AccountInfo<'a>,
+        mint_info: &AccountInfo<'a>,
+        system_program_info: &AccountInfo<'a>,
+        token_program_info: &AccountInfo<'a>,
+    ) -> Result<(), ProgramError> {
+        let create_instruction = spl_associated_token_account::instruction::create_associated_token_account(
+            payer_info.key,
+            wallet_info.key,
+            mint_info.key,
+            &Self::token_program_id(mint_info)?,
+        );
+
+        invoke(
+            &create_instruction,
+            &[
+                payer_info.clone(),
+                associated_account_info.clone(),
+                wallet_info.clone(),
+                mint_info.clone(),
+                system_program_info.clone(),
+                token_program_info.clone(),
+            ],
+        )
+    }
+
+    // Creates a token account at a program-derived address that is its own authority, with
+    // room for the extensions the mint requires of its accounts. The payer funds its rent.
+    pub fn create_self_owned_account<'a>(
+        payer_info: &AccountInfo<'a>,
+        account_info: &AccountInfo<'a>,
+        mint_info: &AccountInfo<'a>,
+        signer_seeds: &[&[u8]],
+    ) -> Result<(), ProgramError> {
+        let token_program_id = Self::token_program_id(mint_info)?;
+        let space = {
+            let data = mint_info.data.borrow();
+            let mint_extensions = StateWithExtensions::<Mint>::unpack(&data)?.get_extension_types()?;
+            let account_extensions = ExtensionType::get_required_init_account_extensions(&mint_extensions);
+            ExtensionType::try_calculate_account_len::<TokenAccount>(&account_extensions)?
+        };
+
+        let create_instruction = system_instruction::create_account(
+            payer_info.key,
+            account_info.key,
+            Rent::get()?.minimum_balance(space),
+            space as u64,
+            &token_program_id,
+        );
+        invoke_signed(&create_instruction, &[payer_info.clone(), account_info.clone()], &[signer_seeds])?;
+
+        let initialize_instruction = spl_token_2022::instruction::initialize_account3(
+            &token_program_id,
+            account_info.key,
+            mint_info.key,
+            account_info.key,
+        )?;
+        invoke(&initialize_instruction, &[account_info.clone(), mint_info.clone()])
+    }
+
+    // Transfers DLU tokens out of an account whose authority is a program-derived address.
+    pub fn transfer_signed(
+        src_account_info: &AccountInfo,
+        dest_account_info: &AccountInfo,
+        authority_info: &AccountInfo,
+        amount: u64,
+        signer_seeds: &[&[&[u8]]],
+    ) -> Result<(), ProgramError> {
+        Self::token_guards(src_account_info, dest_account_info, authority_info.key, amount, None)?;
+        // Token-2022 accepts the plain transfer so long as the mint charges no transfer fee.
+        let transfer_instruction = spl_token_2022::instruction::transfer(
+            &Self::token_program_id(src_account_info)?,
             &src_account_info.key,
             &dest_account_info.key,
             &authority_info.key,
             &[],
             amount,
         )?;
-        
-        invoke(
-            &transfer_instruction, 
-            &[src_account_info.clone(), dest_account_info.clone(), authority_info.clone()]
+
+        invoke_signed(
+            &transfer_instruction,
+            &[src_account_info.clone(), dest_account_info.clone(), authority_info.clone()],
+            signer_seeds,
+        )
+    }
+
+    // Transfers DLU tokens with the mint and its decimals checked, as Token-2022 requires
+    // when the mint charges a transfer fee. The fee is withheld from what arrives.
+    pub fn transfer_checked(
+        src_account_info: &AccountInfo,
+        mint_info: &AccountInfo,
+        dest_account_info: &AccountInfo,
+        authority_info: &AccountInfo,
+        amount: u64,
+        signer_seeds: &[&[&[u8]]],
+    ) -> Result<(), ProgramError> {
+        let decimals = {
+            let data = mint_info.data.borrow();
+            StateWithExtensions::<Mint>::unpack(&data)?.base.decimals
+        };
+        Self::token_guards(src_account_info, dest_account_info, authority_info.key, amount, Some(mint_info.key))?;
+        let transfer_instruction = spl_token_2022::instruction::transfer_checked(
+            &Self::token_program_id(src_account_info)?,
+            &src_account_info.key,
+            &mint_info.key,
+            &dest_account_info.key,
+            &authority_info.key,
+            &[],
+            amount,
+            decimals,
+        )?;
+
+        invoke_signed(
+            &transfer_instruction,
+            &[src_account_info.clone(), mint_info.clone(), dest_account_info.clone(), authority_info.clone()],
+            signer_seeds,
+        )
+    }
+
+    // Reads the transfer fee configured on a Token-2022 mint, if it has one.
+    fn with_transfer_fee<T>(
+        mint_info: &AccountInfo,
+        no_fee: T,
+        apply: impl FnOnce(&TransferFeeConfig, u64) -> Option<T>,
+    ) -> Result<T, ProgramError> {
+        if *mint_info.owner != spl_token_2022::id() {
+            Self::token_program_id(mint_info)?;
+            return Ok(no_fee);
+        }
+        let data = mint_info.data.borrow();
+        let mint = StateWithExtensions::<Mint>::unpack(&data)?;
+        match mint.get_extension::<TransferFeeConfig>() {
+            Ok(config) => apply(config, Clock::get()?.epoch).ok_or_else(|| DLUError::ArithmeticOverflow.into()),
+            Err(_) => Ok(no_fee),
+        }
+    }
+
+    // Returns the fee the mint withholds this epoch from a transfer of `amount`.
+    pub fn transfer_fee(mint_info: &AccountInfo, amount: u64) -> Result<u64, ProgramError> {
+        Self::with_transfer_fee(mint_info, 0, |config, epoch| config.calculate_epoch_fee(epoch, amount))
+    }
+
+    // Returns how much must be sent for exactly `net` to arrive once the mint's transfer
+    // fee is withheld, so escrow always holds the full amount it accounts for.
+    pub fn gross_amount(mint_info: &AccountInfo, net: u64) -> Result<u64, ProgramError> {
+        Self::with_transfer_fee(mint_info, net, |config, epoch| {
+            let fee = config.get_epoch_fee(epoch).calculate_inverse_fee(net)?;
+            net.checked_add(fee)
+        })
+    }
+
+    // Mints new DLU tokens into an account, signing as the mint authority, which is a
+    // program-derived address.
+    pub fn mint_to_signed(
+        mint_info: &AccountInfo,
+        dest_account_info: &AccountInfo,
+        authority_info: &AccountInfo,
+        amount: u64,
+        signer_seeds: &[&[&[u8]]],
+    ) -> Result<(), ProgramError> {
+        let mint_instruction = spl_token_2022::instruction::mint_to(
+            &Self::token_program_id(mint_info)?,
+            &mint_info.key,
+            &dest_account_info.key,
+            &authority_info.key,
+            &[],
+            amount,
+        )?;
+
+        invoke_signed(
+            &mint_instruction,
+            &[mint_info.clone(), dest_account_info.clone(), authority_info.clone()],
+            signer_seeds,
+        )
+    }
+
+    // Burns DLU tokens held in an account, shrinking the supply.
+    pub fn burn(
+        account_info: &AccountInfo,
+        mint_info: &AccountInfo,
+        authority_info: &AccountInfo,
+        amount: u64,
+    ) -> Result<(), ProgramError> {
+        Self::burn_signed(account_info, mint_info, authority_info, amount, &[])
+    }
+
+    // Burns DLU tokens held in an account whose authority is a program-derived address.
+    pub fn burn_signed(
+        account_info: &AccountInfo,
+        mint_info: &AccountInfo,
+        authority_info: &AccountInfo,
+        amount: u64,
+        signer_seeds: &[&[&[u8]]],
+    ) -> Result<(), ProgramError> {
+        let burn_instruction = spl_token_2022::instruction::burn(
+            &Self::token_program_id(mint_info)?,
+            &account_info.key,
+            &mint_info.key,
+            &authority_info.key,
+            &[],
+            amount,
+        )?;
+
+        invoke_signed(
+            &burn_instruction,
+            &[account_info.clone(), mint_info.clone(), authority_info.clone()],
+            signer_seeds,
         )
     }
 
@@ -42,7 +322,7 @@ impl DLUToken {
         token_account_info: &AccountInfo,
         authority_pubkey: &Pubkey,
     ) -> Result<bool, ProgramError> {
-        let token_account_data = TokenAccount::unpack(&token_account_info.data.borrow())?;
+        let token_account_data = Self::unpack_account(token_account_info)?;
         Ok(token_account_data.owner == *authority_pubkey)
     }
     
@@ -1,10 +1,17 @@
-use solana_program::pubkey::Pubkey;
+use solana_program::{account_info::AccountInfo, pubkey::Pubkey};
 use crate::dlu_token::DLUToken;
-use crate::escrow::Escrow;
+use crate::errors::DLUError;
+use crate::safe_math;
 
+/// DLU a user deposited with the program.
+///
+/// The tokens sit in the user's wallet token account, which is the only source of truth;
+/// `balance` caches it for readers of the user account and is resynced by every
+/// instruction that moves wallet funds. Offers, requests and shipments move DLU between
+/// token accounts directly, except for `DepositAndAccept` paying a buyer's deposit out of it.
 pub struct Wallet {
     pub owner: Pubkey,  // Owner of the DLU wallet.
-    pub balance: u64,   // The current DLU balance.
+    pub balance: u64,   // Cached balance of the wallet token account.
 }
 
 impl Wallet {
@@ -16,45 +23,19 @@ impl Wallet {
         }
     }
 
-    /// Fetches the latest balance from the DLU token ledger.
-    pub fn refresh_balance(&mut self) {
-        // Fetch the balance from DLUtoken.rs (This is a mock, in real-world it would query the ledger.)
-        self.balance = DLUToken::get_balance(&self.owner).unwrap_or(0);
+    /// Resyncs the cached balance with the wallet token account, returning the balance it
+    /// held before.
+    pub fn sync(&mut self, wallet_account: &AccountInfo) -> Result<u64, DLUError> {
+        let actual = DLUToken::get_balance(wallet_account).map_err(|_| DLUError::BalanceUnavailable)?;
+        Ok(std::mem::replace(&mut self.balance, actual))
     }
 
     /// Deducts a specified amount from the wallet.
-    pub fn deduct(&mut self, amount: u64) -> Result<(), &'static str> {
+    pub fn deduct(&mut self, amount: u64) -> Result<(), DLUError> {
         if self.balance < amount {
-            return Err("Insufficient funds in wallet.");
+            return Err(DLUError::InsufficientFunds);
         }
-        self.balance -= amount;  // Deduct the specified amount from the wallet's balance.
-        Ok(())
-    }
-
-    /// Locks a specified amount in escrow.
-    pub fn lock_for_escrow(&mut self, amount: u64) -> Result<u64, &'static str> {
-        // Lock the specified amount in escrow and get the escrow ID.
-        let escrow_id = Escrow::lock_funds(&self.owner, amount)?;
-        Ok(escrow_id)
-    }
-
-    /// Releases a previously locked amount from escrow back to the wallet.
-    pub fn release_from_escrow(&mut self, amount: u64, escrow_id: u64) -> Result<(), &'static str> {
-        // Call to DLUtoken.rs to release the funds from the escrow back to the wallet using the escrow ID.
-        DLUToken::transfer_from_escrow(escrow_id, &self.owner, amount)?;
-        self.refresh_balance(); // Refresh balance after the operation.
-        Ok(())
-    }
-
-    /// Transfers DLU from this wallet to another.
-    pub fn transfer(&mut self, recipient: &mut Wallet, amount: u64) -> Result<(), &'static str> {
-        if self.balance < amount {
-            return Err("Insufficient funds");
-        }
-        // Call to DLUtoken.rs to perform the transfer.
-        DLUToken::transfer(&self.owner, &recipient.owner, amount)?;
-        self.refresh_balance(); // Refresh balance after the operation.
-        recipient.refresh_balance();
+        self.balance = safe_math::sub(self.balance, amount)?;  // Deduct the specified amount from the wallet's balance.
         Ok(())
     }
 }
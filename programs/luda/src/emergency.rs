@@ -0,0 +1,68 @@
+use solana_program::pubkey::Pubkey;
+use solana_program::borsh::{BorshSerialize, BorshDeserialize};
+use crate::errors::DLUError;
+use crate::time::SECONDS_PER_DAY;
+
+/// Time between governance proposing to unwind an entity and being able to, so its
+/// parties see the unwind coming and can object before any funds move.
+pub const EMERGENCY_TIMELOCK: i64 = 3 * SECONDS_PER_DAY;
+
+/// Account size of an EmergencyResolution.
+pub const EMERGENCY_RESOLUTION_SIZE: usize = 32 + 8;
+
+/// Governance's pending proposal to return the escrow of a deal or shipment a bug left
+/// unreachable to its depositors. Lives at the PDA `["emergency", entity_pubkey]`, created by
+/// the entity's first proposal, and an all-zero account holds no proposal.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct EmergencyResolution {
+    pub entity: Pubkey,
+    pub executable_at: i64,  // Zero while nothing is proposed.
+}
+
+impl EmergencyResolution {
+    /// Proposes to unwind `entity` at `now`, returning when the unwind can be executed.
+    pub fn propose(&mut self, entity: &Pubkey, now: i64) -> Result<i64, DLUError> {
+        if self.is_pending() {
+            return Err(DLUError::EmergencyAlreadyProposed);
+        }
+        self.entity = *entity;
+        self.executable_at = now.saturating_add(EMERGENCY_TIMELOCK);
+        Ok(self.executable_at)
+    }
+
+    /// Consumes the proposal to unwind `entity` once its timelock has passed at `now`.
+    pub fn execute(&mut self, entity: &Pubkey, now: i64) -> Result<(), DLUError> {
+        if !self.is_pending() || self.entity != *entity {
+            return Err(DLUError::EmergencyNotProposed);
+        }
+        if now < self.executable_at {
+            return Err(DLUError::EmergencyTimelocked);
+        }
+        *self = EmergencyResolution::default();
+        Ok(())
+    }
+
+    /// Withdraws the proposal to unwind `entity` before it is executed.
+    pub fn cancel(&mut self, entity: &Pubkey) -> Result<(), DLUError> {
+        if !self.is_pending() || self.entity != *entity {
+            return Err(DLUError::EmergencyNotProposed);
+        }
+        *self = EmergencyResolution::default();
+        Ok(())
+    }
+
+    /// Returns true while an unwind is proposed.
+    pub fn is_pending(&self) -> bool {
+        self.executable_at != 0
+    }
+
+    /// Serializes the EmergencyResolution into a vector of bytes.
+    pub fn serialize(&self) -> Result<Vec<u8>, DLUError> {
+        self.try_to_vec().map_err(|_| DLUError::SerializationFailed)
+    }
+
+    /// Deserializes an EmergencyResolution from a slice of bytes.
+    pub fn deserialize(input: &mut &[u8]) -> Result<Self, DLUError> {
+        <Self as BorshDeserialize>::deserialize(input).map_err(|_| DLUError::DeserializationFailed)
+    }
+}
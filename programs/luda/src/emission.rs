@@ -0,0 +1,57 @@
+use solana_program::pubkey::Pubkey;
+use solana_program::borsh::{BorshSerialize, BorshDeserialize};
+use crate::config::Config;
+use crate::errors::DLUError;
+use crate::safe_math;
+
+/// Account size of a RewardEmission.
+pub const REWARD_EMISSION_SIZE: usize = 32 + 8 + 8 + 8;
+
+/// The completion rewards paid in one mint, kept at the PDA `["reward_emission", mint]`. The
+/// tokens come out of the rewards vault of the mint, which governance funds.
+///
+/// Both parties of every completed deal and shipment get the config's completion reward,
+/// halving over every half-life since the first reward was paid, until the emission cap is
+/// reached or the vault runs dry.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct RewardEmission {
+    pub mint: Pubkey,
+    pub started_at: i64,  // When the first reward was paid, from which the rewards decay.
+    pub emitted: u64,     // Paid out over the emission's lifetime, counted against the cap.
+    pub rewarded_completions: u64,
+}
+
+impl RewardEmission {
+    /// Creates the emission of the mint, starting its schedule at `now`.
+    pub fn new(mint: Pubkey, now: i64) -> Self {
+        RewardEmission { mint, started_at: now, emitted: 0, rewarded_completions: 0 }
+    }
+
+    /// Returns the reward each party of a completion gets at `now`, before the cap.
+    pub fn scheduled_reward(&self, config: &Config, now: i64) -> u64 {
+        let elapsed = now.saturating_sub(self.started_at).max(0) as u64;
+        safe_math::decay(config.completion_reward, elapsed, config.completion_reward_half_life)
+    }
+
+    /// Records the rewards of a completion with `parties` parties at `now`, the vault holding
+    /// `available`. Returns the amount to pay each party, which may be zero.
+    pub fn emit(&mut self, config: &Config, parties: u64, available: u64, now: i64) -> Result<u64, DLUError> {
+        let remaining = config.completion_emission_cap.saturating_sub(self.emitted).min(available);
+        let reward = self.scheduled_reward(config, now).min(remaining / parties.max(1));
+        if reward > 0 {
+            self.emitted = safe_math::add(self.emitted, safe_math::mul(reward, parties)?)?;
+            self.rewarded_completions = safe_math::add(self.rewarded_completions, 1)?;
+        }
+        Ok(reward)
+    }
+
+    /// Serializes the emission into a vector of bytes.
+    pub fn serialize(&self) -> Result<Vec<u8>, DLUError> {
+        self.try_to_vec().map_err(|_| DLUError::SerializationFailed)
+    }
+
+    /// Deserializes an emission from a slice of bytes.
+    pub fn deserialize(input: &mut &[u8]) -> Result<Self, DLUError> {
+        <Self as BorshDeserialize>::deserialize(input).map_err(|_| DLUError::DeserializationFailed)
+    }
+}
@@ -1,63 +1,592 @@
+use anchor_lang::error::ERROR_CODE_OFFSET;
 use solana_program::program_error::ProgramError;
 use thiserror::Error;
 
-#[derive(Error, Debug, Clone)]
+/// Errors returned by the program.
+///
+/// Each variant has a fixed discriminant that is reported to clients as the custom
+/// error code `ERROR_CODE_OFFSET + discriminant`, the same range Anchor uses for program
+/// errors. Discriminants must never be reused or reordered; new variants take the next
+/// free number.
+#[derive(Error, Debug, Clone, Copy, PartialEq)]
 pub enum DLUError {
     #[error("Invalid Instruction")]
-    InvalidInstruction,
+    InvalidInstruction = 0,
 
     #[error("Not Authorized")]
-    NotAuthorized,
+    NotAuthorized = 1,
 
     #[error("Insufficient Funds")]
-    InsufficientFunds,
+    InsufficientFunds = 2,
 
     #[error("Offer Not Found")]
-    OfferNotFound,
+    OfferNotFound = 3,
 
     #[error("Request Not Found")]
-    RequestNotFound,
+    RequestNotFound = 4,
 
     #[error("Shipment Not Found")]
-    ShipmentNotFound,
+    ShipmentNotFound = 5,
 
     #[error("Key Mismatch")]
-    KeyMismatch,
+    KeyMismatch = 6,
 
     #[error("Operation Not Allowed")]
-    OperationNotAllowed,
+    OperationNotAllowed = 7,
 
     #[error("Incorrect State")]
-    IncorrectState,
+    IncorrectState = 8,
 
     #[error("User Not Found")]
-    UserNotFound,
+    UserNotFound = 9,
 
     #[error("Invalid Operation")]
-    InvalidOperation,
+    InvalidOperation = 10,
 
     #[error("Account Not Found")]
-    AccountNotFound,
+    AccountNotFound = 11,
 
     #[error("Deserialization Failed")]
-    DeserializationFailed,
+    DeserializationFailed = 12,
 
     #[error("Serialization Failed")]
-    SerializationFailed,
+    SerializationFailed = 13,
 
     #[error("Address Derivation Failed")]
-    AddressDerivationFailed,
+    AddressDerivationFailed = 14,
 
     #[error("Account Creation Failed")]
-    AccountCreationFailed,
+    AccountCreationFailed = 15,
 
     #[error("Shipment Hasn't Expired Yet")]
-    ShipmentNotExpired,
+    ShipmentNotExpired = 16,
 
+    #[error("Total Value Locked Cap Exceeded")]
+    TvlCapExceeded = 17,
+
+    #[error("Account Already Initialized")]
+    AlreadyInitialized = 18,
+
+    #[error("Arithmetic Overflow")]
+    ArithmeticOverflow = 19,
+
+    #[error("Feature Disabled")]
+    FeatureDisabled = 20,
+
+    #[error("Terms Hash Mismatch")]
+    TermsMismatch = 21,
+
+    #[error("Current Terms Not Accepted")]
+    TermsNotAccepted = 22,
+
+    #[error("Invalid Account Owner")]
+    InvalidAccountOwner = 23,
+
+    #[error("Insufficient Funds For Payment")]
+    InsufficientFundsForPayment = 24,
+
+    #[error("Insufficient Funds For Insurance")]
+    InsufficientFundsForInsurance = 25,
+
+    #[error("Insufficient Funds In Escrow")]
+    InsufficientEscrowFunds = 26,
+
+    #[error("Failed To List Offer")]
+    FailedToListOffer = 27,
+
+    #[error("Failed To List Request")]
+    FailedToListRequest = 28,
+
+    #[error("Failed To List Shipment")]
+    FailedToListShipment = 29,
+
+    #[error("Offer Account Not Found")]
+    OfferAccountNotFound = 30,
+
+    #[error("Request Account Not Found")]
+    RequestAccountNotFound = 31,
+
+    #[error("Shipment Account Not Found")]
+    ShipmentAccountNotFound = 32,
+
+    #[error("Unhandled Instruction")]
+    UnhandledInstruction = 33,
+
+    #[error("Not In Listed State")]
+    NotListed = 34,
+
+    #[error("Not In Accepted State")]
+    NotAccepted = 35,
+
+    #[error("Deal Hasn't Expired Yet")]
+    DealNotExpired = 36,
+
+    #[error("Invalid Seller Key")]
+    InvalidSellerKey = 37,
+
+    #[error("Invalid Buyer Key")]
+    InvalidBuyerKey = 38,
+
+    #[error("Invalid Sender Key")]
+    InvalidSenderKey = 39,
+
+    #[error("Invalid Carrier Key")]
+    InvalidCarrierKey = 40,
+
+    #[error("Invalid Recipient Key")]
+    InvalidRecipientKey = 41,
+
+    #[error("Carrier Key Not Entered")]
+    CarrierKeyNotEntered = 42,
+
+    #[error("Counterparty Not Found")]
+    CounterpartyNotFound = 43,
+
+    #[error("Escrow Already Migrated")]
+    EscrowAlreadyMigrated = 44,
+
+    #[error("Escrow Is Empty")]
+    EscrowEmpty = 45,
+
+    #[error("Token Balance Unavailable")]
+    BalanceUnavailable = 46,
+
+    #[error("Invalid Entity Type")]
+    InvalidEntityType = 47,
+
+    #[error("Entity ID Too Long")]
+    EntityIdTooLong = 48,
+
+    #[error("Entity Already Indexed")]
+    AlreadyIndexed = 49,
+
+    #[error("Deal Not Completed")]
+    NotCompleted = 50,
+
+    #[error("Not A Party Of The Deal")]
+    NotDealParty = 51,
+
+    #[error("Invalid Refund Amount")]
+    InvalidRefundAmount = 52,
+
+    #[error("Token Transfer Failed")]
+    TransferFailed = 53,
+
+    #[error("Dispute Window Closed")]
+    DisputeWindowClosed = 54,
+
+    #[error("Dispute Window Still Open")]
+    DisputeWindowOpen = 55,
+
+    #[error("Bond Not Disputed")]
+    BondNotDisputed = 56,
+
+    #[error("Bond Already Settled")]
+    BondSettled = 57,
+
+    #[error("Only Expired Or Canceled Entities Can Be Relisted")]
+    NotRelistable = 58,
+
+    #[error("Fee Rate Too High")]
+    InvalidFeeRate = 59,
+
+    #[error("Penalty Share Too High")]
+    InvalidPenaltyShare = 60,
+
+    #[error("Token Burn Failed")]
+    BurnFailed = 61,
+
+    #[error("Invalid Stake Amount")]
+    InvalidStakeAmount = 62,
+
+    #[error("Stake Still Cooling Down")]
+    StakeCoolingDown = 63,
+
+    #[error("Insufficient Stake")]
+    InsufficientStake = 64,
+
+    #[error("Stake Already Slashed")]
+    AlreadySlashed = 65,
+
+    #[error("Invalid Reputation Half-Life")]
+    InvalidHalfLife = 66,
+
+    #[error("Reputation Too Low For This Role")]
+    ReputationTooLow = 67,
+
+    #[error("Rating Must Be Between 1 And 5")]
+    InvalidRating = 68,
+
+    #[error("Deal Already Reviewed")]
+    AlreadyReviewed = 69,
+
+    #[error("Profile Field Too Long")]
+    ProfileFieldTooLong = 70,
+
+    #[error("Unsupported Account Version")]
+    UnsupportedVersion = 71,
+
+    #[error("Escrow Not Held In The Vault")]
+    EscrowNotInVault = 72,
+
+    #[error("Invalid Wallet Amount")]
+    InvalidWalletAmount = 73,
+
+    #[error("Token Account Holds A Different Mint")]
+    MintMismatch = 74,
+
+    #[error("Mint Is Not An NFT")]
+    NotAnNft = 75,
+
+    #[error("NFT Offers Must Be Canceled One At A Time")]
+    NftOfferNotBatchable = 76,
+
+    #[error("Barter Offers Take Insurance But No Payment")]
+    InvalidBarterTerms = 77,
+
+    #[error("Quote Price Must Be Positive")]
+    InvalidQuotePrice = 78,
+
+    #[error("Quote Note Too Long")]
+    QuoteNoteTooLong = 79,
+
+    #[error("Quote Is No Longer Open")]
+    QuoteNotOpen = 80,
+
+    #[error("Quote Was Submitted For An Earlier Listing")]
+    QuoteStale = 81,
+
+    #[error("Seller Already Has An Open Quote")]
+    QuoteAlreadyOpen = 82,
+
+    #[error("Offer And Request Do Not Match")]
+    DealsNotCompatible = 83,
+
+    #[error("Country Is Not An ISO 3166 Alpha-2 Code")]
+    InvalidCountryCode = 84,
+
+    #[error("Location Field Too Long")]
+    LocationFieldTooLong = 85,
+
+    #[error("Invalid Geohash")]
+    InvalidGeohash = 86,
+
+    #[error("Meeting Point Is Not Hidden")]
+    MeetingPointNotHidden = 87,
+
+    #[error("Revealed Meeting Point Does Not Match Its Hash")]
+    MeetingPointMismatch = 88,
+
+    #[error("Invalid Message Length")]
+    InvalidMessageLength = 89,
+
+    #[error("Shipment Not In Transit")]
+    NotInTransit = 90,
+
+    #[error("Shipment Already Returned")]
+    AlreadyReturned = 91,
+
+    #[error("Invalid Holdback Terms")]
+    InvalidHoldbackTerms = 92,
+
+    #[error("Holdback Not Held")]
+    HoldbackNotHeld = 93,
+
+    #[error("Holdback Not Disputed")]
+    HoldbackNotDisputed = 94,
+
+    #[error("Invalid Chargeback Window")]
+    InvalidChargebackWindow = 95,
+
+    #[error("Chargeback Window Not Open")]
+    ChargebackNotOpen = 96,
+
+    #[error("Chargeback Not Claimed")]
+    ChargebackNotClaimed = 97,
+
+    #[error("Arbiter Already Registered")]
+    ArbiterAlreadyRegistered = 98,
+
+    #[error("Arbiter Registry Full")]
+    ArbiterRegistryFull = 99,
+
+    #[error("Arbiter Not Registered")]
+    ArbiterNotRegistered = 100,
+
+    #[error("No Arbiter Available")]
+    NoArbiterAvailable = 101,
+
+    #[error("Not The Assigned Arbiter")]
+    NotAssignedArbiter = 102,
+
+    #[error("Arbiter Already Assigned")]
+    ArbiterAlreadyAssigned = 103,
+
+    #[error("Dispute Already Ruled")]
+    AlreadyRuled = 104,
+
+    #[error("Dispute Not Ruled")]
+    NotRuled = 105,
+
+    #[error("Not On The Appeal Panel")]
+    NotOnPanel = 106,
+
+    #[error("Appeal Vote Already Cast")]
+    AlreadyVoted = 107,
+
+    #[error("Appeal Still Pending")]
+    AppealPending = 108,
+
+    #[error("Appeal Already Settled")]
+    AppealSettled = 109,
+
+    #[error("Invalid Settlement Split")]
+    InvalidSettlementSplit = 110,
+
+    #[error("Invalid Session Terms")]
+    InvalidSessionTerms = 111,
+
+    #[error("Session Expired")]
+    SessionExpired = 112,
+
+    #[error("Entity Out Of Session Scope")]
+    OutOfSessionScope = 113,
+
+    #[error("Not An Organization Member")]
+    NotOrganizationMember = 114,
+
+    #[error("Organization Full")]
+    OrganizationFull = 115,
+
+    #[error("Invalid Member Roles")]
+    InvalidMemberRoles = 116,
+
+    #[error("Invalid Multisig")]
+    InvalidMultisig = 117,
+
+    #[error("Not Enough Multisig Cosigners")]
+    NotEnoughCosigners = 118,
+
+    #[error("User Banned")]
+    UserBanned = 119,
+
+    #[error("Attestor Registry Full")]
+    AttestorRegistryFull = 120,
+
+    #[error("Attestor Already Registered")]
+    AttestorAlreadyRegistered = 121,
+
+    #[error("Attestor Not Registered")]
+    AttestorNotRegistered = 122,
+
+    #[error("Valid Attestation Required")]
+    AttestationRequired = 123,
+
+    #[error("Invalid Referrer")]
+    InvalidReferrer = 124,
+
+    #[error("Invalid Referral Share")]
+    InvalidReferralShare = 125,
+
+    #[error("No Referral Rewards To Claim")]
+    NoReferralRewards = 126,
+
+    #[error("Invalid Promo Code")]
+    InvalidPromoCode = 127,
+
+    #[error("Promo Code Used Up")]
+    PromoCodeExhausted = 128,
+
+    #[error("Too Many Promo Codes")]
+    PromoCodesFull = 129,
+
+    #[error("Invalid Discount")]
+    InvalidDiscount = 130,
+
+    #[error("Invalid Price Tiers")]
+    InvalidPriceTiers = 131,
+
+    #[error("Invalid Quantity")]
+    InvalidQuantity = 132,
+
+    #[error("Invalid Listing Schedule")]
+    InvalidSchedule = 133,
+
+    #[error("Listing Not Active")]
+    ListingNotActive = 134,
+
+    #[error("Invalid Stream Terms")]
+    InvalidStreamTerms = 135,
+
+    #[error("Offer Not Streaming")]
+    NotStreaming = 136,
+
+    #[error("Stream Stopped")]
+    StreamStopped = 137,
+
+    #[error("Nothing Vested")]
+    NothingVested = 138,
+
+    #[error("Watchlist Full")]
+    WatchlistFull = 139,
+
+    #[error("Account Already In Use")]
+    AccountInUse = 140,
+
+    #[error("Entity Not Archivable")]
+    NotArchivable = 141,
+
+    #[error("Invalid Archive Batch")]
+    InvalidArchiveBatch = 142,
+
+    #[error("Invalid Archive Proof")]
+    InvalidArchiveProof = 143,
+
+    #[error("Account Growth Not Funded")]
+    GrowthNotFunded = 144,
+
+    #[error("Empty Field")]
+    EmptyField = 145,
+
+    #[error("Field Too Long")]
+    FieldTooLong = 146,
+
+    #[error("Invalid Characters")]
+    InvalidCharacters = 147,
+
+    #[error("One-Time Key Already Committed")]
+    KeyAlreadyCommitted = 148,
+
+    #[error("One-Time Key Expired")]
+    KeyExpired = 149,
+
+    #[error("One-Time Key Already Used")]
+    KeyAlreadyUsed = 150,
+
+    #[error("Key Manager Full")]
+    KeyManagerFull = 151,
+
+    #[error("Invalid Key Code")]
+    InvalidKeyCode = 152,
+
+    #[error("Listing Not Stale")]
+    ListingNotStale = 153,
+
+    #[error("Deadline Can Only Be Moved Later Before It Passes")]
+    InvalidDeadline = 154,
+
+    #[error("Emergency Resolution Already Proposed")]
+    EmergencyAlreadyProposed = 155,
+
+    #[error("Emergency Resolution Not Proposed")]
+    EmergencyNotProposed = 156,
+
+    #[error("Emergency Resolution Still Timelocked")]
+    EmergencyTimelocked = 157,
+
+    #[error("Config Updates Must Go Through The Timelock")]
+    GovernanceTimelocked = 158,
+
+    #[error("Governance Action Not Queued")]
+    ActionNotQueued = 159,
+
+    #[error("Governance Action Still Timelocked")]
+    ActionTimelocked = 160,
+
+    #[error("Timelock Queue Full")]
+    TimelockQueueFull = 161,
+
+    #[error("Votes Locked Until Voting Ends")]
+    VotesLocked = 162,
+
+    #[error("Insufficient Voting Power")]
+    InsufficientVotingPower = 163,
+
+    #[error("Voting Closed")]
+    VotingClosed = 164,
+
+    #[error("Voting Not Ended")]
+    VotingNotEnded = 165,
+
+    #[error("Already Voted")]
+    AlreadyVoted = 166,
+
+    #[error("Invalid Vote Amount")]
+    InvalidVoteAmount = 167,
+
+    #[error("Penalty Distribution Too Soon")]
+    DistributionTooSoon = 168,
+
+    #[error("Faucet Cooling Down")]
+    FaucetCoolingDown = 169,
+
+    #[error("Invalid Reward Schedule")]
+    InvalidRewardSchedule = 170,
+
+    #[error("Invalid Expiry Batch")]
+    InvalidExpiryBatch = 171,
+
+    #[error("Vault Already Registered")]
+    VaultAlreadyRegistered = 172,
+
+    #[error("Vault Registry Full")]
+    VaultRegistryFull = 173,
+
+    #[error("Vault Not Registered")]
+    VaultNotRegistered = 174,
+
+    #[error("Token Authority Mismatch")]
+    TokenAuthorityMismatch = 175,
+
+    #[error("Stale Nonce")]
+    StaleNonce = 176,
+
+    #[error("Invalid Route")]
+    InvalidRoute = 177,
+
+    #[error("Route Mismatch")]
+    RouteMismatch = 178,
+
+    #[error("Route Index Full")]
+    RouteIndexFull = 179,
+
+    #[error("Route Capacity Exceeded")]
+    RouteCapacityExceeded = 180,
+
+    #[error("Account Kind Mismatch")]
+    AccountKindMismatch = 181,
+
+    #[error("Escrow Flagged")]
+    EscrowFlagged = 182,
+}
+
+impl DLUError {
+    /// Custom error code reported to clients.
+    pub fn code(self) -> u32 {
+        ERROR_CODE_OFFSET + self as u32
+    }
 }
 
 impl From<DLUError> for ProgramError {
     fn from(e: DLUError) -> Self {
-        ProgramError::Custom(e as u32)
+        ProgramError::Custom(e.code())
+    }
+}
+
+/// Maps the messages returned by addressing and user helpers onto their error variants.
+impl From<&'static str> for DLUError {
+    fn from(e: &'static str) -> Self {
+        match e {
+            "Invalid entity type" => DLUError::InvalidEntityType,
+            "Entity ID too long" => DLUError::EntityIdTooLong,
+            "Failed to derive address" => DLUError::AddressDerivationFailed,
+            "Request already exists in the list" => DLUError::AlreadyIndexed,
+            "Profile field too long" => DLUError::ProfileFieldTooLong,
+            "Invalid referrer" => DLUError::InvalidReferrer,
+            "Faucet cooling down" => DLUError::FaucetCoolingDown,
+            _ if e.starts_with("Failed to serialize") => DLUError::SerializationFailed,
+            _ if e.starts_with("Failed to deserialize") => DLUError::DeserializationFailed,
+            _ => DLUError::InvalidOperation,
+        }
     }
 }
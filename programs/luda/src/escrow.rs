@@ -0,0 +1,87 @@
+use solana_program::account_info::AccountInfo;
+use crate::dlu_token::DLUToken;
+use crate::errors::DLUError;
+use crate::safe_math;
+
+/// Moves DLU into and out of the escrow vault of the payment mint, the registered token
+/// account PDA `["vault", mint]` that is its own authority, so the program signs every
+/// release with `vault_seeds`.
+///
+/// Every movement runs the `DLUToken::token_guards` first, so a transfer naming the wrong
+/// mint, token program or authority fails with its own error rather than inside the token
+/// program, and checks the source covers the amount before anything is invoked.
+pub struct Escrow;
+
+impl Escrow {
+    /// Locks `amount` from a party's token account into the vault, with `authority_info` the
+    /// party signing for it.
+    pub fn lock_funds(
+        src_account_info: &AccountInfo,
+        vault_account_info: &AccountInfo,
+        authority_info: &AccountInfo,
+        amount: u64,
+    ) -> Result<(), DLUError> {
+        Self::move_funds(src_account_info, vault_account_info, authority_info, &[], amount, DLUError::InsufficientFunds)
+    }
+
+    /// Releases `amount` from the vault to a party's token account.
+    pub fn release_funds(
+        vault_account_info: &AccountInfo,
+        dest_account_info: &AccountInfo,
+        vault_seeds: &[&[u8]],
+        amount: u64,
+    ) -> Result<(), DLUError> {
+        Self::move_funds(
+            vault_account_info,
+            dest_account_info,
+            vault_account_info,
+            &[vault_seeds],
+            amount,
+            DLUError::InsufficientEscrowFunds,
+        )
+    }
+
+    /// Moves a forfeited deposit from the vault to the penalty account.
+    pub fn transfer_to_penalty(
+        vault_account_info: &AccountInfo,
+        penalty_account_info: &AccountInfo,
+        vault_seeds: &[&[u8]],
+        amount: u64,
+    ) -> Result<(), DLUError> {
+        Self::release_funds(vault_account_info, penalty_account_info, vault_seeds, amount)
+    }
+
+    /// Burns a forfeited deposit out of the vault, with `mint_info` the payment mint.
+    pub fn burn_penalty(
+        vault_account_info: &AccountInfo,
+        mint_info: &AccountInfo,
+        vault_seeds: &[&[u8]],
+        amount: u64,
+    ) -> Result<(), DLUError> {
+        if amount == 0 {
+            return Ok(());
+        }
+        DLUToken::burn_signed(vault_account_info, mint_info, vault_account_info, amount, &[vault_seeds])
+            .map_err(|_| DLUError::BurnFailed)
+    }
+
+    // Guards and invokes a transfer, failing with `shortfall` when the source holds too little.
+    // Nothing moves for a zero amount.
+    fn move_funds(
+        src_account_info: &AccountInfo,
+        dest_account_info: &AccountInfo,
+        authority_info: &AccountInfo,
+        signer_seeds: &[&[&[u8]]],
+        amount: u64,
+        shortfall: DLUError,
+    ) -> Result<(), DLUError> {
+        if amount == 0 {
+            return Ok(());
+        }
+        DLUToken::token_guards(src_account_info, dest_account_info, authority_info.key, amount, None)?;
+        let balance = DLUToken::get_balance(src_account_info).map_err(|_| DLUError::BalanceUnavailable)?;
+        safe_math::sub(balance, amount).map_err(|_| shortfall)?;
+        DLUToken::transfer_signed(src_account_info, dest_account_info, authority_info, amount, signer_seeds)
+            .map_err(|_| DLUError::TransferFailed)
+    }
+}
@@ -0,0 +1,467 @@
+use solana_program::{log::sol_log_data, pubkey::Pubkey};
+use solana_program::borsh::{BorshSerialize, BorshDeserialize};
+use crate::addressing::EntityType;
+use crate::appeal::{AppealStatus, APPEAL_PANEL_SIZE};
+use crate::arbiter::DisputeKind;
+use crate::compression::CompressedOffer;
+use crate::deal::DealStatus;
+use crate::region::REGION_GEOHASH_LEN;
+use crate::timelock::GovernanceAction;
+
+/// Events emitted by the program for indexers and auditors.
+///
+/// Each event is logged with `sol_log_data` as a single Borsh-encoded `DLUEvent`.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub enum DLUEvent {
+    /// Funds of an in-flight deal were moved from a legacy escrow account into the vault.
+    EscrowMigrated {
+        entity_type: EntityType,
+        entity: Pubkey,
+        legacy_escrow: Pubkey,
+        vault: Pubkey,
+        amount: u64,
+        slot: u64,
+    },
+
+    /// A fail bond was paid out, to the claimant unless `forfeited`.
+    FailBondSettled {
+        entity: Pubkey,
+        recipient: Pubkey,
+        amount: u64,
+        forfeited: bool,
+        slot: u64,
+    },
+
+    /// A seller refunded part of a completed deal's payment to its buyer.
+    GoodwillRefundIssued {
+        entity_type: EntityType,
+        deal_id: u64,
+        seller: Pubkey,
+        buyer: Pubkey,
+        amount: u64,
+        slot: u64,
+    },
+
+    /// A protocol fee was taken from the payment of a completed deal or shipment.
+    FeeCollected {
+        entity_type: EntityType,
+        entity: Pubkey,
+        payee: Pubkey,
+        amount: u64,
+        slot: u64,
+    },
+
+    /// A keeper expired an overdue deal or shipment and was paid a bounty from its escrow.
+    ExpiryCranked {
+        entity_type: EntityType,
+        entity: Pubkey,
+        keeper_account: Pubkey,
+        bounty: u64,
+        slot: u64,
+    },
+
+    /// A matcher linked a request with an offer and was paid a finder's fee from its deposit.
+    DealsMatched {
+        offer: Pubkey,
+        request: Pubkey,
+        matcher_account: Pubkey,
+        fee: u64,
+        slot: u64,
+    },
+
+    /// A user deposited DLU into their wallet, leaving it at `balance`.
+    WalletDeposited {
+        user: Pubkey,
+        amount: u64,
+        balance: u64,
+        slot: u64,
+    },
+
+    /// A user withdrew DLU from their wallet, leaving it at `balance`.
+    WalletWithdrawn {
+        user: Pubkey,
+        amount: u64,
+        balance: u64,
+        slot: u64,
+    },
+
+    /// A user's cached wallet balance had drifted from `recorded` and was reset to `actual`.
+    WalletReconciled {
+        user: Pubkey,
+        recorded: u64,
+        actual: u64,
+        slot: u64,
+    },
+
+    /// The lister of an accepted deal revealed its exact meeting point.
+    MeetingPointRevealed {
+        entity_type: EntityType,
+        deal_id: u64,
+        slot: u64,
+    },
+
+    /// A party of an offer, request or shipment sent the others a message.
+    MessageSent {
+        entity: Pubkey,
+        sender: Pubkey,
+        slot: u64,
+    },
+
+    /// The carrier of a shipment confirmed taking custody of its items.
+    ShipmentPickedUp {
+        shipment_id: u64,
+        carrier: Pubkey,
+        picked_up_at: i64,
+    },
+
+    /// A delivery proof was recorded on a shipment.
+    DeliveryProofSubmitted {
+        shipment_id: u64,
+        proof_hash: [u8; 32],
+        slot: u64,
+    },
+
+    /// The recipient of a completed shipment listed its return to the sender.
+    ReturnShipmentCreated {
+        shipment_id: u64,
+        original_shipment_id: u64,
+        slot: u64,
+    },
+
+    /// The holdback of a completed offer was paid out, to the seller on release or to the
+    /// buyer when a dispute found the goods defective.
+    HoldbackSettled {
+        offer_id: u64,
+        recipient: Pubkey,
+        amount: u64,
+        slot: u64,
+    },
+
+    /// The buyer of a completed offer charged it back, freezing the seller's insurance.
+    ChargebackOpened {
+        offer_id: u64,
+        buyer: Pubkey,
+        slot: u64,
+    },
+
+    /// A chargeback claim was ruled on and the seller's insurance paid out, to the buyer if
+    /// the claim was upheld.
+    ChargebackResolved {
+        offer_id: u64,
+        recipient: Pubkey,
+        amount: u64,
+        slot: u64,
+    },
+
+    /// An arbiter was drawn for a dispute over a completed offer.
+    ArbiterAssigned {
+        entity: Pubkey,
+        kind: DisputeKind,
+        arbiter: Pubkey,
+        slot: u64,
+    },
+
+    /// The assigned arbiter ruled on a dispute and was paid the arbitration fee.
+    DisputeRuled {
+        entity: Pubkey,
+        kind: DisputeKind,
+        arbiter: Pubkey,
+        for_buyer: bool,
+        fee: u64,
+        slot: u64,
+    },
+
+    /// The losing party appealed a ruling and a panel was drawn to vote on it.
+    RulingAppealed {
+        entity: Pubkey,
+        kind: DisputeKind,
+        appellant: Pubkey,
+        bond: u64,
+        panel: [Pubkey; APPEAL_PANEL_SIZE],
+        slot: u64,
+    },
+
+    /// A ruled dispute was paid out, as the arbiter ruled unless an appeal overturned it.
+    DisputeSettled {
+        entity: Pubkey,
+        kind: DisputeKind,
+        for_buyer: bool,
+        appeal: Option<AppealStatus>,
+        slot: u64,
+    },
+
+    /// An accepted offer was settled by splitting its escrow, by an arbiter or both parties.
+    DealSettled {
+        entity: Pubkey,
+        seller_amount: u64,
+        buyer_amount: u64,
+        arbiter: Option<Pubkey>,
+        slot: u64,
+    },
+
+    /// Governance banned or unbanned a user.
+    UserBanned {
+        user: Pubkey,
+        banned: bool,
+        slot: u64,
+    },
+
+    /// An attestor attested a user's identity checks until `expires_at`.
+    UserAttested {
+        user: Pubkey,
+        attestor: Pubkey,
+        expires_at: i64,
+        slot: u64,
+    },
+
+    /// A buyer accepted an offer with a promo code, paying `discount` less.
+    PromoCodeRedeemed {
+        offer: Pubkey,
+        buyer: Pubkey,
+        discount: u64,
+        slot: u64,
+    },
+
+    /// The seller of a streaming offer withdrew `amount` of its vested payment, `fee`
+    /// included.
+    StreamWithdrawn {
+        offer: Pubkey,
+        amount: u64,
+        fee: u64,
+        slot: u64,
+    },
+
+    /// A party stopped the stream of an offer, leaving its unvested remainder to a
+    /// settlement.
+    StreamStopped {
+        offer: Pubkey,
+        stopped_by: Pubkey,
+        slot: u64,
+    },
+
+    /// A wallet followed or unfollowed a user.
+    UserFollowed {
+        follower: Pubkey,
+        user: Pubkey,
+        following: bool,
+        slot: u64,
+    },
+
+    /// A wallet started or stopped watching an offer.
+    OfferWatched {
+        watcher: Pubkey,
+        offer: Pubkey,
+        watching: bool,
+        slot: u64,
+    },
+
+    /// An offer was listed or moved to a new status, which watchers of it are notified of.
+    OfferStatusChanged {
+        offer: Pubkey,
+        seller: Pubkey,
+        status: DealStatus,
+        slot: u64,
+    },
+
+    /// An offer was listed as the `leaf` of a listing tree.
+    CompressedOfferListed {
+        tree: Pubkey,
+        listing: CompressedOffer,
+        leaf: [u8; 32],
+    },
+
+    /// A compressed offer's leaf was emptied, when it was accepted into the `offer` account
+    /// or canceled.
+    CompressedOfferClosed {
+        tree: Pubkey,
+        leaf: [u8; 32],
+        offer: Option<Pubkey>,
+        slot: u64,
+    },
+
+    /// A lister archived the `entities`, in the order of the batch's leaves. Their last
+    /// account data is what the leaves commit to.
+    BatchArchived {
+        lister: Pubkey,
+        batch: u32,
+        entity_type: EntityType,
+        root: [u8; 32],
+        entities: Vec<Pubkey>,
+        slot: u64,
+    },
+
+    /// Someone expired a listing no one accepted in time, refunding the lister's deposit.
+    StaleListingReclaimed {
+        entity_type: EntityType,
+        entity: Pubkey,
+        lister_account: Pubkey,
+        refund: u64,
+        slot: u64,
+    },
+
+    /// Both parties moved the meeting or drop-off time of an entity to a later `deadline`.
+    DeadlineExtended {
+        entity_type: EntityType,
+        entity: Pubkey,
+        deadline: i64,
+        slot: u64,
+    },
+
+    /// The vault held less than the Stats say is escrowed in it, so the entity was flagged.
+    EscrowDiverged {
+        entity_type: EntityType,
+        entity: Pubkey,
+        vault: Pubkey,
+        expected: u64,
+        actual: u64,
+        slot: u64,
+    },
+
+    /// Governance proposed to unwind an entity, which it can do from `executable_at` on.
+    EmergencyResolveProposed {
+        entity_type: EntityType,
+        entity: Pubkey,
+        executable_at: i64,
+        slot: u64,
+    },
+
+    /// Governance unwound an entity, returning its escrow to the parties who deposited it.
+    EmergencyResolved {
+        entity_type: EntityType,
+        entity: Pubkey,
+        seller_or_sender_refund: u64,
+        buyer_or_carrier_refund: u64,
+        slot: u64,
+    },
+
+    /// Governance withdrew its proposal to unwind an entity.
+    EmergencyResolveCanceled {
+        entity: Pubkey,
+        slot: u64,
+    },
+
+    /// Governance queued a config update, which anyone can execute from `executable_at` on.
+    GovernanceActionQueued {
+        id: u64,
+        action: GovernanceAction,
+        executable_at: i64,
+        slot: u64,
+    },
+
+    /// Governance removed a queued config update.
+    GovernanceActionCanceled {
+        id: u64,
+        action: GovernanceAction,
+        slot: u64,
+    },
+
+    /// A queued config update was applied.
+    GovernanceActionExecuted {
+        id: u64,
+        action: GovernanceAction,
+        slot: u64,
+    },
+
+    /// A holder opened a vote on a config update.
+    ProposalCreated {
+        id: u64,
+        proposer: Pubkey,
+        action: GovernanceAction,
+        voting_ends_at: i64,
+        slot: u64,
+    },
+
+    /// A holder voted on a proposal with `weight` locked tokens.
+    VoteCast {
+        proposal_id: u64,
+        voter: Pubkey,
+        support: bool,
+        weight: u64,
+        slot: u64,
+    },
+
+    /// Voting on a proposal closed. A passed proposal was queued in the timelock.
+    ProposalFinalized {
+        id: u64,
+        passed: bool,
+        votes_for: u64,
+        votes_against: u64,
+        slot: u64,
+    },
+
+    /// Penalties that reached the penalty vault of a mint were burned or credited to stakers.
+    PenaltiesDistributed {
+        mint: Pubkey,
+        burned: u64,
+        credited: u64,
+        slot: u64,
+    },
+
+    /// A carrier claimed their share of the penalty pool of a mint.
+    PenaltyRewardsClaimed {
+        carrier: Pubkey,
+        mint: Pubkey,
+        amount: u64,
+        slot: u64,
+    },
+
+    /// Both parties of a completed entity were paid a completion reward of `reward` each.
+    CompletionRewarded {
+        entity_type: EntityType,
+        entity: Pubkey,
+        mint: Pubkey,
+        reward: u64,
+        slot: u64,
+    },
+
+    /// Governance created the canonical escrow vault of a payment mint.
+    VaultRegistered {
+        mint: Pubkey,
+        vault: Pubkey,
+        slot: u64,
+    },
+
+    /// The sender of a listed shipment recorded its measurements, which at the config's
+    /// rates suggest `suggested_payment` against the `payment` offered.
+    ShipmentMeasured {
+        shipment_id: u64,
+        payment: u64,
+        suggested_payment: u64,
+        slot: u64,
+    },
+
+    /// A carrier published a weekly route, or updated the days and capacity of one.
+    RoutePublished {
+        route: Pubkey,
+        carrier: Pubkey,
+        origin: [u8; REGION_GEOHASH_LEN],
+        destination: [u8; REGION_GEOHASH_LEN],
+        days: u8,
+        capacity_grams: u64,
+        slot: u64,
+    },
+
+    /// A carrier closed a route they no longer travel.
+    RouteClosed {
+        route: Pubkey,
+        carrier: Pubkey,
+        slot: u64,
+    },
+
+    /// The sender of a listed shipment listed it along a carrier's route.
+    ShipmentRouted {
+        shipment_id: u64,
+        route: Pubkey,
+        carrier: Pubkey,
+        slot: u64,
+    },
+}
+
+impl DLUEvent {
+    /// Logs the event to the transaction log.
+    pub fn emit(&self) {
+        if let Ok(data) = self.try_to_vec() {
+            sol_log_data(&[&data]);
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use solana_program::borsh::{BorshSerialize, BorshDeserialize};
+use crate::errors::DLUError;
+use crate::safe_math;
+
+/// Largest share of an offer's payment a seller can hold back, in basis points.
+pub const MAX_HOLDBACK_BPS: u16 = 5_000;
+/// Longest a holdback can stay in escrow after completion, in seconds.
+pub const MAX_HOLDBACK_WINDOW: i64 = 90 * 86_400;
+
+/// Warranty terms a seller attaches to an offer when listing it.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, PartialEq, Debug)]
+pub struct HoldbackTerms {
+    pub bps: u16,     // Share of the seller's proceeds kept in escrow.
+    pub window: i64,  // Seconds after completion the buyer can dispute the goods.
+}
+
+/// Represents the current status of a holdback.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, PartialEq, Debug)]
+pub enum HoldbackStatus {
+    Agreed,    // The deal has not completed yet, so nothing is held.
+    Held,
+    Disputed,
+    Released,  // Paid out to the seller.
+    Refunded,  // Paid out to the buyer, the goods being defective.
+}
+
+/// Part of a completed offer's payment kept in escrow as a warranty.
+///
+/// On completion the `HoldbackTerms` share of the seller's proceeds, after the protocol
+/// fee, stays in escrow until the window has passed. The seller then claims it with
+/// `ReleaseHoldback`. Within the window the buyer can dispute the goods, freezing the
+/// holdback until governance rules on whether they were defective.
+#[derive(BorshSerialize, BorshDeserialize, Clone, PartialEq, Debug)]
+pub struct Holdback {
+    pub terms: HoldbackTerms,
+    pub amount: u64,
+    pub release_at: i64,
+    pub status: HoldbackStatus,
+}
+
+impl Holdback {
+    /// Agrees on a holdback of an offer paying `payment`.
+    pub fn new(terms: HoldbackTerms, payment: u64) -> Result<Self, DLUError> {
+        let valid = payment > 0
+            && (1..=MAX_HOLDBACK_BPS).contains(&terms.bps)
+            && (1..=MAX_HOLDBACK_WINDOW).contains(&terms.window);
+        if !valid {
+            return Err(DLUError::InvalidHoldbackTerms);
+        }
+        Ok(Holdback { terms, amount: 0, release_at: 0, status: HoldbackStatus::Agreed })
+    }
+
+    /// Holds back the agreed share of the seller's `proceeds` at completion, returning it.
+    pub fn hold(&mut self, proceeds: u64, now: i64) -> Result<u64, DLUError> {
+        if self.status != HoldbackStatus::Agreed {
+            return Err(DLUError::IncorrectState);
+        }
+        self.amount = safe_math::bps(proceeds, self.terms.bps)?;
+        self.release_at = now.saturating_add(self.terms.window);
+        self.status = HoldbackStatus::Held;
+        Ok(self.amount)
+    }
+
+    /// Freezes the holdback on the buyer's claim that the goods are defective.
+    pub fn dispute(&mut self, now: i64) -> Result<(), DLUError> {
+        if self.status != HoldbackStatus::Held {
+            return Err(DLUError::HoldbackNotHeld);
+        }
+        if now >= self.release_at {
+            return Err(DLUError::DisputeWindowClosed);
+        }
+        self.status = HoldbackStatus::Disputed;
+        Ok(())
+    }
+
+    /// Releases an undisputed holdback to the seller once its window has passed, returning
+    /// the amount.
+    pub fn release(&mut self, now: i64) -> Result<u64, DLUError> {
+        if self.status != HoldbackStatus::Held {
+            return Err(DLUError::HoldbackNotHeld);
+        }
+        if now < self.release_at {
+            return Err(DLUError::DisputeWindowOpen);
+        }
+        self.status = HoldbackStatus::Released;
+        Ok(self.amount)
+    }
+
+    /// Settles a disputed holdback, refunding the buyer when the goods were found
+    /// defective and releasing it to the seller otherwise. Returns the amount.
+    pub fn resolve(&mut self, defective: bool) -> Result<u64, DLUError> {
+        if self.status != HoldbackStatus::Disputed {
+            return Err(DLUError::HoldbackNotDisputed);
+        }
+        self.status = if defective { HoldbackStatus::Refunded } else { HoldbackStatus::Released };
+        Ok(self.amount)
+    }
+
+    /// Returns the amount still in escrow.
+    pub fn escrowed_amount(&self) -> u64 {
+        match self.status {
+            HoldbackStatus::Held | HoldbackStatus::Disputed => self.amount,
+            _ => 0,
+        }
+    }
+}
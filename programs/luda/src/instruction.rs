@@ -0,0 +1,1993 @@
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use solana_program::borsh::{BorshSerialize, BorshDeserialize};
+use crate::addressing::EntityType;
+use crate::arbiter::DisputeKind;
+use crate::badge::BadgeTier;
+use crate::compression::CompressedOffer;
+use crate::errors::DLUError;
+use crate::holdback::HoldbackTerms;
+use crate::location::Location;
+use crate::offer::OfferKind;
+use crate::region::REGION_GEOHASH_LEN;
+use crate::timelock::GovernanceAction;
+
+/// Maximum number of listings closed by a single `CancelAllListed`.
+pub const MAX_CANCEL_PER_CALL: u8 = 10;
+
+/// Leading byte of the current instruction layout: the version byte, then the variant's
+/// one-byte Borsh tag and its fields. Versions are numbered from 0xF0 up, clear of every
+/// variant tag, so input without a version byte is never mistaken for a versioned layout.
+pub const INSTRUCTION_VERSION: u8 = 0xF0;
+
+/// Instructions supported by the LUDA program.
+///
+/// Datetimes are passed as unix timestamps. Accounts are consumed positionally in the
+/// order listed on each variant; `[w]` marks writable accounts and `[s]` signers.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub enum DLUInstruction {
+    // CONFIG
+    /// Creates the Config, Stats, ArbiterRegistry and TimelockQueue accounts. Only the
+    /// program's upgrade authority may initialize them, paying their rent.
+    ///
+    /// 0. `[w]` Config PDA
+    /// 1. `[w]` Stats PDA
+    /// 2. `[w]` ArbiterRegistry PDA
+    /// 3. `[w]` TimelockQueue PDA
+    /// 4. `[ws]` Upgrade authority of the program
+    /// 5. `[]` ProgramData account of the program
+    /// 6. `[]` System program
+    InitializeConfig {
+        governance: Pubkey,
+        tvl_cap: u64,
+        terms_hash: [u8; 32],
+    },
+
+    /// Raises the TVL cap.
+    ///
+    /// 0. `[w]` Config PDA
+    /// 1. `[s]` Governance key
+    RaiseTvlCap {
+        new_cap: u64,
+    },
+
+    /// Replaces the capability bitmap.
+    ///
+    /// 0. `[w]` Config PDA
+    /// 1. `[s]` Governance key
+    SetCapabilities {
+        capabilities: u64,
+    },
+
+    /// Moves the funds of an in-flight deal from its legacy escrow account into the vault
+    /// of its payment mint, which must be registered. Fails once the config enables
+    /// `CAP_TIMELOCKED_GOVERNANCE`, after which governance queues a `MigrateEscrow` action.
+    ///
+    /// 0. `[]` Config PDA
+    /// 1. `[s]` Governance key
+    /// 2. `[w]` Offer, request, or shipment account
+    /// 3. `[w]` Legacy escrow token account
+    /// 4. `[s]` Legacy escrow authority
+    /// 5. `[w]` Vault token account of the payment mint
+    /// 6. `[]` VaultRegistry PDA
+    MigrateEscrow {
+        entity_type: EntityType,
+    },
+
+    /// Publishes a new terms-of-use document.
+    ///
+    /// 0. `[w]` Config PDA
+    /// 1. `[s]` Governance key
+    PublishTerms {
+        terms_hash: [u8; 32],
+    },
+
+    /// Sets the slots after which an operation's weight in users' reputation halves.
+    ///
+    /// 0. `[w]` Config PDA
+    /// 1. `[s]` Governance key
+    SetReputationHalfLife {
+        half_life: u64,
+    },
+
+    // USERS
+    /// Creates a user who accepts the terms identified by `terms_hash`, along with the owner's
+    /// ProfileSummary and IndexAccount if they have none yet. Passing the trailing accounts also creates the
+    /// owner's DLU associated token account if it doesn't exist yet, so they can receive DLU
+    /// right away. Rent is paid by the rent payer, such as a relayer sponsoring an owner without
+    /// SOL, or else by the owner, who must then be writable. Naming a `referrer` credits them a share of the protocol fees of the user's
+    /// completed deals and shipments from then on. The username is up to `MAX_USERNAME_LEN`
+    /// ASCII letters, digits, `_`, `-` and `.`.
+    ///
+    /// 0. `[w]` User account, allocated and still empty
+    /// 1. `[s]` Owner
+    /// 2. `[]` Config PDA
+    /// 3. `[w]` Owner's ProfileSummary PDA
+    /// 4. `[w]` Stats PDA
+    /// 5. `[w]` Owner's IndexAccount PDA
+    /// 6. `[]` System program
+    /// 7. `[ws]` Rent payer, only when someone other than the owner pays
+    /// 8. `[w]` Owner's DLU associated token account, only when creating it
+    /// 9. `[]` DLU mint, only when creating the token account
+    /// 10. `[]` Token program owning the mint, SPL Token or Token-2022, only when creating the token account
+    /// 11. `[]` Associated token account program, only when creating the token account
+    CreateUser {
+        username: String,
+        terms_hash: [u8; 32],
+        referrer: Option<Pubkey>,
+    },
+
+    /// Replaces the given profile fields, growing the user account if they no longer fit.
+    ///
+    /// 0. `[w]` User account
+    /// 1. `[s]` Owner, writable when paying the rent of any added space
+    /// 2. `[]` System program
+    /// 3. `[ws]` Rent payer of any added space, only when someone other than the owner pays it
+    UpdateUserProfile {
+        display_name: Option<String>,
+        metadata_uri: Option<String>,
+        contact_hints: Option<String>,
+    },
+
+    /// Accepts the currently published terms. Required before listing once new terms are published.
+    ///
+    /// 0. `[w]` User account
+    /// 1. `[s]` Owner
+    /// 2. `[]` Config PDA
+    ReacceptTerms {
+        terms_hash: [u8; 32],
+    },
+
+    // OFFERS
+    /// Lists an offer paid in the mint of the seller's token account, such as DLU, USDC or
+    /// wrapped SOL. Every token account the offer's funds later move through must hold it.
+    /// The name and description hold at most `MAX_ITEM_NAME_LEN` and `MAX_DESCRIPTION_LEN`
+    /// bytes, without control characters beyond the description's line breaks and tabs.
+    ///
+    /// An NFT offer also moves its NFT into the offer's NFT escrow, which is created on
+    /// first listing with the seller, or the rent payer following the NFT accounts, paying
+    /// its rent. A barter offer has a zero `payment`:
+    /// both sides deposit the insurance its kind sets and swap goods at the meeting.
+    /// A meeting point with a geohash announces the offer in its region's index, which
+    /// follows the NFT accounts when there are any and the seller's index otherwise. A
+    /// `meeting_point_hash` keeps the exact meeting point hidden until `RevealMeetingPoint`.
+    /// `holdback` terms keep a share of the seller's proceeds in escrow as a warranty for a
+    /// window after completion.
+    ///
+    /// 0. `[w]` Offer account, allocated and still empty
+    /// 1. `[]` Seller's user account
+    /// 2. `[s]` Seller, authority of the seller's token account, writable when paying the NFT escrow's or a new RegionIndex's rent
+    /// 3. `[w]` Seller's token account
+    /// 4. `[w]` Vault token account of the payment mint
+    /// 5. `[]` VaultRegistry PDA
+    /// 6. `[]` Config PDA
+    /// 7. `[w]` Stats PDA
+    /// 8. `[w]` Seller's IndexAccount PDA
+    /// 9. `[w]` Seller's NFT token account, only when listing an NFT
+    /// 10. `[w]` NFT escrow PDA of the offer, only when listing an NFT
+    /// 11. `[]` NFT mint, only when listing an NFT
+    /// 12. `[]` System program, only when listing an NFT
+    /// 13. `[]` Token program owning the NFT mint, only when listing an NFT
+    /// 14. `[ws]` Rent payer of the NFT escrow, only when listing an NFT and someone other than the seller pays it
+    /// 15. `[w]` RegionIndex PDA of the meeting point, created by its first listing, only when it has a geohash
+    /// 16. `[]` System program, only when the meeting point has a geohash
+    /// 17. `[ws]` Rent payer of a new RegionIndex, only when the meeting point has a geohash and someone other than the seller pays it
+    /// 18. `[]` Seller's Organization PDA, only when a member signs for the seller
+    /// 19. `[]` Seller's Multisig PDA, only when the seller is governed by a multisig
+    /// 20.. `[s]` Cosigners of the seller's multisig, only when the payment reaches its high value
+    ListOffer {
+        id: u64,
+        goodsorservice_name: String,
+        goodsorservice_description: String,
+        payment: u64,
+        meeting_point: Location,
+        meeting_datetime: i64,
+        kind: OfferKind,
+        meeting_point_hash: Option<[u8; 32]>,
+        holdback: Option<HoldbackTerms>,
+    },
+
+    /// 0. `[w]` Offer account
+    /// 1. `[w]` Buyer's user account
+    /// 2. `[s]` Buyer, or a member of the buyer's organization
+    /// 3. `[w]` Buyer's token account
+    /// 4. `[w]` Vault token account of the payment mint
+    /// 5. `[]` VaultRegistry PDA
+    /// 6. `[s]` Authority of the buyer's token account
+    /// 7. `[]` Config PDA
+    /// 8. `[w]` Stats PDA
+    /// 9. `[w]` Seller's IndexAccount PDA
+    /// 10. `[]` Buyer's Organization PDA, only when a member signs for the buyer
+    /// 11. `[]` AttestorRegistry PDA, only when the payment is above the KYC threshold
+    /// 12. `[]` Seller's Attestation PDA, likewise
+    /// 13. `[]` Buyer's Attestation PDA, likewise
+    /// 14. `[w]` Offer's PromoCodes PDA, only with a `promo_code`
+    ///
+    /// The buyer accepts `quantity` units, which must be 1 unless the offer sells inventory,
+    /// priced by the offer's tiers. A `promo_code` the seller attached to the offer lowers
+    /// the payment the buyer locks by its discount and uses up one of its uses.
+    AcceptOffer {
+        quantity: u32,
+        promo_code: Option<String>,
+    },
+
+    /// 0. `[w]` Offer account
+    /// 1. `[w]` Seller's user account
+    /// 2. `[w]` Buyer's user account
+    /// 3. `[w]` Seller's token account
+    /// 4. `[w]` Buyer's token account
+    /// 5. `[w]` Vault token account of the payment mint
+    /// 6. `[]` VaultRegistry PDA
+    /// 7. `[w]` Stats PDA
+    /// 8. `[w]` Seller's ProfileSummary PDA
+    /// 9. `[w]` Buyer's ProfileSummary PDA
+    /// 10. `[]` Config PDA
+    /// 11. `[w]` Treasury token account of the payment mint
+    /// 12. `[w]` NFT escrow PDA of the offer, only when the offer sells an NFT
+    /// 13. `[w]` Buyer's NFT token account, only when the offer sells an NFT
+    /// 14. `[w]` ReferralRewards PDA of the seller's referrer and the payment mint, only when the seller was referred and pays a fee
+    /// 15. `[]` System program, likewise
+    /// 16. `[ws]` Rent payer of the ReferralRewards PDA on its first credit, likewise
+    /// 17. `[w]` RewardEmission PDA of the payment mint, only when the config schedules completion rewards
+    /// 18. `[w]` Rewards vault token account of the payment mint, likewise
+    /// 19. `[]` System program, likewise
+    /// 20. `[ws]` Rent payer of the RewardEmission PDA on its first reward, likewise
+    /// 21. `[w]` Seller volume Leaderboard PDA, only when ranking the seller
+    /// 22. `[w]` Seller streak Leaderboard PDA, likewise
+    /// 23. `[]` System program, likewise
+    /// 24. `[ws]` Rent payer of the Leaderboard PDAs on their first ranking, likewise
+    ///
+    /// Complete, fail and settle instructions name the `nonce` of the deal or shipment they
+    /// were built against. Every status transition advances it, so each can only execute
+    /// once, against the state its signers saw.
+    CompleteOffer {
+        buyer_key: String,
+        seller_key: String,
+        nonce: u64,
+    },
+
+    /// 0. `[w]` Offer account
+    /// 1. `[w]` Buyer's user account
+    /// 2. `[w]` Vault token account of the payment mint
+    /// 3. `[]` VaultRegistry PDA
+    /// 4. `[w]` Penalty token account, normally the penalty vault of the mint, or the payment mint when penalties are burned
+    /// 5. `[w]` Stats PDA
+    /// 6. `[w]` Buyer's ProfileSummary PDA
+    /// 7. `[]` Config PDA
+    /// 8. `[s]` Seller, or a member of the seller's organization, posting the fail bond, and writable when also paying the rent of its FailBond
+    /// 9. `[w]` Seller's token account
+    /// 10. `[w]` FailBond PDA of the offer
+    /// 11. `[]` System program
+    /// 12. `[ws]` Rent payer of the FailBond, only when someone other than the signer pays it
+    /// 13. `[w]` Buyer's token account
+    /// 14. `[w]` NFT escrow PDA of the offer, only when the offer sells an NFT
+    /// 15. `[w]` Seller's NFT token account, only when the offer sells an NFT
+    /// 16. `[]` Seller's Organization PDA, only when a member signs for the seller
+    FailOffer {
+        seller_key: String,
+        nonce: u64,  // The offer's current nonce.
+    },
+
+    /// 0. `[w]` Offer account
+    /// 1. `[w]` Vault token account of the payment mint
+    /// 2. `[]` VaultRegistry PDA
+    /// 3. `[w]` Seller's token account
+    /// 4. `[w]` Buyer's token account
+    /// 5. `[w]` Stats PDA
+    /// 6. `[w]` NFT escrow PDA of the offer, only when the offer sells an NFT
+    /// 7. `[w]` Seller's NFT token account, only when the offer sells an NFT
+    ExpireOffer,
+
+    /// Edits a listed offer; fields left as `None` are unchanged. A new payment tops up
+    /// or partially releases the seller's escrowed insurance. Barters take no payment.
+    ///
+    /// 0. `[w]` Offer account
+    /// 1. `[]` Seller's user account
+    /// 2. `[s]` Seller, authority of the seller's token account
+    /// 3. `[w]` Seller's token account
+    /// 4. `[w]` Vault token account of the payment mint
+    /// 5. `[]` VaultRegistry PDA
+    /// 6. `[]` Config PDA
+    /// 7. `[w]` Stats PDA
+    UpdateOffer {
+        goodsorservice_description: Option<String>,
+        meeting_point: Option<Location>,
+        meeting_datetime: Option<i64>,
+        payment: Option<u64>,
+    },
+
+    /// 0. `[w]` Offer account
+    /// 1. `[s]` Seller
+    /// 2. `[w]` Seller's token account
+    /// 3. `[w]` Vault token account of the payment mint
+    /// 4. `[]` VaultRegistry PDA
+    /// 5. `[w]` Stats PDA
+    /// 6. `[w]` Seller's IndexAccount PDA
+    /// 7. `[w]` NFT escrow PDA of the offer, only when the offer sells an NFT
+    /// 8. `[w]` Seller's NFT token account, only when the offer sells an NFT
+    CancelOffer,
+
+    /// Cancels up to `max_count` of the seller's listed offers, oldest first, refunding
+    /// their insurance. Capped at `MAX_CANCEL_PER_CALL`; call again until none are left.
+    /// NFT offers must be canceled with `CancelOffer`, which returns their NFT.
+    ///
+    /// 0. `[s]` Seller
+    /// 1. `[w]` Seller's IndexAccount PDA
+    /// 2. `[w]` Seller's token account
+    /// 3. `[w]` Vault token account of the payment mint
+    /// 4. `[]` VaultRegistry PDA
+    /// 5. `[w]` Stats PDA
+    /// 6.. `[w]` Offer accounts, in the order of the index's active offers
+    CancelAllListed {
+        max_count: u8,
+    },
+
+    /// Lists an expired or canceled offer again in its existing account, locking a
+    /// fresh deposit and bumping its generation. An NFT offer takes its NFT back into escrow.
+    ///
+    /// 0. `[w]` Offer account
+    /// 1. `[w]` Seller's user account
+    /// 2. `[s]` Seller, authority of the seller's token account, writable when paying the NFT escrow's rent
+    /// 3. `[w]` Seller's token account
+    /// 4. `[w]` Vault token account of the payment mint
+    /// 5. `[]` VaultRegistry PDA
+    /// 6. `[]` Config PDA
+    /// 7. `[w]` Stats PDA
+    /// 8. `[w]` Seller's IndexAccount PDA
+    /// 9. `[w]` Seller's NFT token account, only when relisting an NFT
+    /// 10. `[w]` NFT escrow PDA of the offer, only when relisting an NFT
+    /// 11. `[]` NFT mint, only when relisting an NFT
+    /// 12. `[]` System program, only when relisting an NFT
+    /// 13. `[]` Token program owning the NFT mint, only when relisting an NFT
+    /// 14. `[ws]` Rent payer of the NFT escrow, only when relisting an NFT and someone other than the seller pays it
+    RelistOffer,
+
+    // REQUESTS
+    /// Lists a request paid in the mint of the buyer's token account, such as DLU, USDC or
+    /// wrapped SOL. Every token account the request's funds later move through must hold it.
+    /// A `meeting_point_hash` keeps the exact meeting point hidden until `RevealMeetingPoint`.
+    ///
+    /// 0. `[w]` Request account, allocated and still empty
+    /// 1. `[]` Buyer's user account
+    /// 2. `[s]` Buyer, authority of the buyer's token account, writable when paying a new RegionIndex's rent
+    /// 3. `[w]` Buyer's token account
+    /// 4. `[w]` Vault token account of the payment mint
+    /// 5. `[]` VaultRegistry PDA
+    /// 6. `[]` Config PDA
+    /// 7. `[w]` Stats PDA
+    /// 8. `[w]` RegionIndex PDA of the meeting point, created by its first listing, only when it has a geohash
+    /// 9. `[]` System program, only when the meeting point has a geohash
+    /// 10. `[ws]` Rent payer of a new RegionIndex, only when the meeting point has a geohash and someone other than the buyer pays it
+    /// 11. `[]` Buyer's Multisig PDA, only when the buyer is governed by a multisig
+    /// 12.. `[s]` Cosigners of the buyer's multisig, only when the payment reaches its high value
+    ListRequest {
+        id: u64,
+        goodsorservice_name: String,
+        goodsorservice_description: String,
+        payment: u64,
+        meeting_point: Location,
+        meeting_datetime: i64,
+        meeting_point_hash: Option<[u8; 32]>,
+    },
+
+    /// 0. `[w]` Request account
+    /// 1. `[w]` Seller's user account
+    /// 2. `[s]` Seller
+    /// 3. `[w]` Seller's token account
+    /// 4. `[w]` Vault token account of the payment mint
+    /// 5. `[]` VaultRegistry PDA
+    /// 6. `[s]` Authority of the seller's token account
+    /// 7. `[]` Config PDA
+    /// 8. `[w]` Stats PDA
+    /// 9. `[]` AttestorRegistry PDA, only when the payment is above the KYC threshold
+    /// 10. `[]` Buyer's Attestation PDA, likewise
+    /// 11. `[]` Seller's Attestation PDA, likewise
+    AcceptRequest,
+
+    /// 0. `[w]` Request account
+    /// 1. `[w]` Seller's user account
+    /// 2. `[w]` Buyer's user account
+    /// 3. `[w]` Seller's token account
+    /// 4. `[w]` Buyer's token account
+    /// 5. `[w]` Vault token account of the payment mint
+    /// 6. `[]` VaultRegistry PDA
+    /// 7. `[w]` Stats PDA
+    /// 8. `[w]` Seller's ProfileSummary PDA
+    /// 9. `[w]` Buyer's ProfileSummary PDA
+    /// 10. `[]` Config PDA
+    /// 11. `[w]` Treasury token account of the payment mint
+    /// 12. `[w]` ReferralRewards PDA of the seller's referrer and the payment mint, only when the seller was referred and pays a fee
+    /// 13. `[]` System program, likewise
+    /// 14. `[ws]` Rent payer of the ReferralRewards PDA on its first credit, likewise
+    /// 15. `[w]` RewardEmission PDA of the payment mint, only when the config schedules completion rewards
+    /// 16. `[w]` Rewards vault token account of the payment mint, likewise
+    /// 17. `[]` System program, likewise
+    /// 18. `[ws]` Rent payer of the RewardEmission PDA on its first reward, likewise
+    /// 19. `[w]` Seller volume Leaderboard PDA, only when ranking the seller
+    /// 20. `[w]` Seller streak Leaderboard PDA, likewise
+    /// 21. `[]` System program, likewise
+    /// 22. `[ws]` Rent payer of the Leaderboard PDAs on their first ranking, likewise
+    CompleteRequest {
+        buyer_key: String,
+        seller_key: String,
+        nonce: u64,  // The request's current nonce.
+    },
+
+    /// 0. `[w]` Request account
+    /// 1. `[w]` Buyer's user account
+    /// 2. `[w]` Vault token account of the payment mint
+    /// 3. `[]` VaultRegistry PDA
+    /// 4. `[w]` Penalty token account, normally the penalty vault of the mint, or the payment mint when penalties are burned
+    /// 5. `[w]` Stats PDA
+    /// 6. `[w]` Buyer's ProfileSummary PDA
+    /// 7. `[]` Config PDA
+    /// 8. `[s]` Seller, posting the fail bond, and writable when also paying the rent of its FailBond
+    /// 9. `[w]` Seller's token account
+    /// 10. `[w]` FailBond PDA of the request
+    /// 11. `[]` System program
+    /// 12. `[ws]` Rent payer of the FailBond, only when someone other than the seller pays it
+    /// 13. `[w]` Buyer's token account
+    FailRequest {
+        seller_key: String,
+        nonce: u64,  // The request's current nonce.
+    },
+
+    /// 0. `[w]` Request account
+    /// 1. `[w]` Vault token account of the payment mint
+    /// 2. `[]` VaultRegistry PDA
+    /// 3. `[w]` Seller's token account
+    /// 4. `[w]` Buyer's token account
+    /// 5. `[w]` Stats PDA
+    ExpireRequest,
+
+    /// 0. `[w]` Request account
+    /// 1. `[s]` Buyer
+    /// 2. `[w]` Buyer's token account
+    /// 3. `[w]` Vault token account of the payment mint
+    /// 4. `[]` VaultRegistry PDA
+    /// 5. `[w]` Stats PDA
+    CancelRequest,
+
+    /// Lists an expired or canceled request again in its existing account, locking a
+    /// fresh deposit and bumping its generation.
+    ///
+    /// 0. `[w]` Request account
+    /// 1. `[w]` Buyer's user account
+    /// 2. `[s]` Buyer, authority of the buyer's token account
+    /// 3. `[w]` Buyer's token account
+    /// 4. `[w]` Vault token account of the payment mint
+    /// 5. `[]` VaultRegistry PDA
+    /// 6. `[]` Config PDA
+    /// 7. `[w]` Stats PDA
+    RelistRequest,
+
+    // SHIPMENTS
+    /// Lists a shipment paid in the mint of the sender's token account, such as DLU, USDC or
+    /// wrapped SOL. Every token account the shipment's funds later move through must hold it.
+    ///
+    /// 0. `[w]` Shipment account, allocated and still empty
+    /// 1. `[]` Sender's user account
+    /// 2. `[s]` Sender, authority of the sender's token account, writable when paying a new RegionIndex's rent
+    /// 3. `[w]` Sender's token account
+    /// 4. `[w]` Vault token account of the payment mint
+    /// 5. `[]` VaultRegistry PDA
+    /// 6. `[]` Recipient's user account
+    /// 7. `[]` Config PDA
+    /// 8. `[w]` Stats PDA
+    /// 9. `[w]` RegionIndex PDA of the pickup point, created by its first listing, only when it has a geohash
+    /// 10. `[]` System program, only when the pickup point has a geohash
+    /// 11. `[ws]` Rent payer of a new RegionIndex, only when the pickup point has a geohash and someone other than the sender pays it
+    /// 12. `[]` Sender's Organization PDA, only when a member signs for the sender
+    /// 13. `[]` Sender's Multisig PDA, only when the sender is governed by a multisig
+    /// 14.. `[s]` Cosigners of the sender's multisig, only when the payment reaches its high value
+    ListShipment {
+        id: u64,
+        items_name: String,
+        quantity: u32,
+        payment: u64,
+        insurance: u64,
+        pickup_point: Location,
+        pickup_datetime: i64,
+        drop_off_point: Location,
+        drop_off_datetime: i64,
+    },
+
+    /// 0. `[w]` Shipment account
+    /// 1. `[w]` Carrier's user account
+    /// 2. `[s]` Carrier, or a member of the carrier's organization
+    /// 3. `[w]` Carrier's token account
+    /// 4. `[w]` Vault token account of the payment mint
+    /// 5. `[]` VaultRegistry PDA
+    /// 6. `[s]` Authority of the carrier's token account
+    /// 7. `[]` Config PDA
+    /// 8. `[w]` Stats PDA
+    /// 9. `[w]` Carrier's CarrierStake PDA, only when the payment is above the stake threshold
+    /// 10. `[]` Carrier's Organization PDA, only when a member signs for the carrier
+    /// 11. `[]` AttestorRegistry PDA, only when the payment is above the KYC threshold
+    /// 12. `[]` Sender's Attestation PDA, likewise
+    /// 13. `[]` Carrier's Attestation PDA, likewise
+    AcceptShipment,
+
+    /// 0. `[w]` Shipment account
+    /// 1. `[w]` Sender's user account
+    /// 2. `[w]` Carrier's user account
+    /// 3. `[w]` Sender's token account
+    /// 4. `[w]` Carrier's token account
+    /// 5. `[w]` Vault token account of the payment mint
+    /// 6. `[]` VaultRegistry PDA
+    /// 7. `[w]` Stats PDA
+    /// 8. `[w]` Sender's ProfileSummary PDA
+    /// 9. `[w]` Carrier's ProfileSummary PDA
+    /// 10. `[]` Config PDA
+    /// 11. `[w]` Treasury token account of the payment mint
+    /// 12. `[w]` ReferralRewards PDA of the carrier's referrer and the payment mint, only when the carrier was referred and pays a fee
+    /// 13. `[]` System program, likewise
+    /// 14. `[ws]` Rent payer of the ReferralRewards PDA on its first credit, likewise
+    /// 15. `[w]` RewardEmission PDA of the payment mint, only when the config schedules completion rewards
+    /// 16. `[w]` Rewards vault token account of the payment mint, likewise
+    /// 17. `[]` System program, likewise
+    /// 18. `[ws]` Rent payer of the RewardEmission PDA on its first reward, likewise
+    /// 19. `[w]` Carrier volume Leaderboard PDA, only when ranking the carrier
+    /// 20. `[w]` Carrier streak Leaderboard PDA, likewise
+    /// 21. `[]` System program, likewise
+    /// 22. `[ws]` Rent payer of the Leaderboard PDAs on their first ranking, likewise
+    CompleteShipment {
+        carrier_key: String,
+        recipient_key: String,
+        proof_hash: Option<[u8; 32]>,  // Digest of the delivery photo or signature, kept for disputes.
+        nonce: u64,  // The shipment's current nonce.
+    },
+
+    /// 0. `[w]` Shipment account
+    /// 1. `[w]` Carrier's user account
+    /// 2. `[w]` Vault token account of the payment mint
+    /// 3. `[]` VaultRegistry PDA
+    /// 4. `[w]` Penalty token account, normally the penalty vault of the mint, or the payment mint when penalties are burned
+    /// 5. `[w]` Stats PDA
+    /// 6. `[w]` Carrier's ProfileSummary PDA
+    /// 7. `[]` Config PDA
+    /// 8. `[s]` Sender, or a member of the sender's organization, posting the fail bond, and writable when also paying the rent of its FailBond
+    /// 9. `[w]` Sender's token account
+    /// 10. `[w]` FailBond PDA of the shipment
+    /// 11. `[]` System program
+    /// 12. `[ws]` Rent payer of the FailBond, only when someone other than the signer pays it
+    /// 13. `[]` Sender's Organization PDA, only when a member signs for the sender
+    /// 14. `[w]` Carrier volume Leaderboard PDA, only when ranking the carrier
+    /// 15. `[w]` Carrier streak Leaderboard PDA, likewise
+    /// 16. `[]` System program, likewise
+    /// 17. `[ws]` Rent payer of the Leaderboard PDAs on their first ranking, likewise
+    FailShipment {
+        sender_key: String,
+        nonce: u64,  // The shipment's current nonce.
+    },
+
+    /// 0. `[w]` Shipment account
+    /// 1. `[w]` Vault token account of the payment mint
+    /// 2. `[]` VaultRegistry PDA
+    /// 3. `[w]` Sender's token account
+    /// 4. `[w]` Carrier's token account
+    /// 5. `[w]` Stats PDA
+    ExpireShipment,
+
+    /// 0. `[w]` Shipment account
+    /// 1. `[s]` Sender
+    /// 2. `[w]` Sender's token account
+    /// 3. `[w]` Vault token account of the payment mint
+    /// 4. `[]` VaultRegistry PDA
+    /// 5. `[w]` Stats PDA
+    CancelShipment,
+
+    /// Lists an expired or canceled shipment again in its existing account, locking a
+    /// fresh deposit and bumping its generation.
+    ///
+    /// 0. `[w]` Shipment account
+    /// 1. `[w]` Sender's user account
+    /// 2. `[s]` Sender, authority of the sender's token account
+    /// 3. `[w]` Sender's token account
+    /// 4. `[w]` Vault token account of the payment mint
+    /// 5. `[]` VaultRegistry PDA
+    /// 6. `[]` Config PDA
+    /// 7. `[w]` Stats PDA
+    RelistShipment,
+
+    // REFUNDS
+    /// Refunds `amount` of a completed offer's or request's payment from its seller to its buyer.
+    ///
+    /// 0. `[]` Offer or request account
+    /// 1. `[w]` Seller's user account
+    /// 2. `[s]` Seller, authority of the seller's token account
+    /// 3. `[w]` Buyer's user account
+    /// 4. `[w]` Seller's token account
+    /// 5. `[w]` Buyer's token account
+    /// 6. `[w]` Seller's ProfileSummary PDA
+    /// 7. `[]` Config PDA
+    IssueGoodwillRefund {
+        entity_type: EntityType,
+        amount: u64,
+    },
+
+    // BONDS
+    /// Sets the bond claimants post when failing a deal or shipment.
+    ///
+    /// 0. `[w]` Config PDA
+    /// 1. `[s]` Governance key
+    SetFailBond {
+        amount: u64,
+    },
+
+    /// Disputes a fail within the dispute window.
+    ///
+    /// 0. `[]` Failed offer, request, or shipment account
+    /// 1. `[w]` FailBond PDA of the entity
+    /// 2. `[s]` Accused user
+    DisputeFail,
+
+    /// Settles a disputed fail: the bond returns to the claimant if the fail was justified
+    /// and is forfeited to the accused otherwise.
+    ///
+    /// 0. `[]` Config PDA
+    /// 1. `[s]` Governance key
+    /// 2. `[]` Failed offer, request, or shipment account
+    /// 3. `[w]` FailBond PDA of the entity
+    /// 4. `[w]` Vault token account of the payment mint, holding the bond
+    /// 5. `[]` VaultRegistry PDA
+    /// 6. `[w]` Token account of the user the bond is paid out to
+    /// 7. `[w]` Stats PDA
+    ResolveFailDispute {
+        fail_justified: bool,
+    },
+
+    /// Returns an undisputed bond to the claimant once the dispute window has passed.
+    ///
+    /// 0. `[]` Failed offer, request, or shipment account
+    /// 1. `[w]` FailBond PDA of the entity
+    /// 2. `[s]` Claimant
+    /// 3. `[w]` Claimant's token account
+    /// 4. `[w]` Vault token account of the payment mint, holding the bond
+    /// 5. `[]` VaultRegistry PDA
+    /// 6. `[w]` Stats PDA
+    ReclaimFailBond,
+
+    // FEES
+    /// Sets the protocol fee taken from completed deals and shipments.
+    ///
+    /// 0. `[w]` Config PDA
+    /// 1. `[s]` Governance key
+    SetFee {
+        fee_bps: u16,
+        exempt_credible: bool,
+    },
+
+    /// Sets the share of the offender's insurance paid to the wronged party on a fail.
+    ///
+    /// 0. `[w]` Config PDA
+    /// 1. `[s]` Governance key
+    SetPenaltyShare {
+        share_bps: u16,
+    },
+
+    /// Moves collected fees out of the treasury of one mint. Fails once the config enables
+    /// `CAP_TIMELOCKED_GOVERNANCE`, after which governance queues a `WithdrawTreasury` action.
+    ///
+    /// 0. `[]` Config PDA
+    /// 1. `[s]` Governance key
+    /// 2. `[w]` Treasury token account of the mint
+    /// 3. `[w]` Destination token account
+    WithdrawTreasury {
+        amount: u64,
+    },
+
+    // STAKING
+    /// Sets the shipment payment above which carriers must be staked, and the stake required.
+    ///
+    /// 0. `[w]` Config PDA
+    /// 1. `[s]` Governance key
+    SetStakeRequirements {
+        stake_threshold: u64,
+        min_stake: u64,
+    },
+
+    /// Locks DLU into the carrier's stake, creating it on first use in the mint of the
+    /// carrier's token account. Later stakes must be in that mint.
+    ///
+    /// 0. `[w]` Carrier's CarrierStake PDA
+    /// 1. `[s]` Carrier
+    /// 2. `[w]` Carrier's token account
+    /// 3. `[w]` Vault token account of the stake mint
+    /// 4. `[]` VaultRegistry PDA
+    /// 5. `[]` Config PDA
+    /// 6. `[w]` Stats PDA
+    /// 7. `[]` System program
+    /// 8. `[ws]` Rent payer of a new CarrierStake PDA, only when someone other than the carrier pays it
+    Stake {
+        amount: u64,
+    },
+
+    /// Withdraws part of the carrier's stake once its cooldown has ended.
+    ///
+    /// 0. `[w]` Carrier's CarrierStake PDA
+    /// 1. `[s]` Carrier
+    /// 2. `[w]` Carrier's token account
+    /// 3. `[w]` Vault token account of the stake mint
+    /// 4. `[]` VaultRegistry PDA
+    /// 5. `[w]` Stats PDA
+    Unstake {
+        amount: u64,
+    },
+
+    /// Slashes the carrier's stake once the fail of their shipment stands.
+    ///
+    /// 0. `[w]` Failed shipment account
+    /// 1. `[]` FailBond PDA of the shipment
+    /// 2. `[w]` Carrier's CarrierStake PDA
+    /// 3. `[w]` Vault token account of the stake mint
+    /// 4. `[]` VaultRegistry PDA
+    /// 5. `[w]` Penalty token account, normally the penalty vault of the mint, or the stake mint when penalties are burned
+    /// 6. `[]` Config PDA
+    /// 7. `[w]` Stats PDA
+    Slash,
+
+    // REVIEWS
+    /// Rates the counterparty of a completed offer, request, or shipment, once per reviewer.
+    ///
+    /// 0. `[]` Offer, request, or shipment account
+    /// 1. `[s]` Reviewer, seller or buyer of the deal, sender or carrier of the shipment
+    /// 2. `[w]` Review PDA of the deal and reviewer, created by the review
+    /// 3. `[w]` Reviewee's user account
+    /// 4. `[w]` Reviewee's ProfileSummary PDA
+    /// 5. `[]` System program
+    /// 6. `[ws]` Rent payer of the Review PDA, only when someone other than the reviewer pays it
+    SubmitReview {
+        entity_type: EntityType,
+        rating: u8,
+        review_hash: [u8; 32],
+    },
+
+    // BADGES
+    /// Mints or burns a user's badge for a tier so it matches their current status.
+    /// Anyone can call it.
+    ///
+    /// 0. `[]` User account
+    /// 1. `[w]` Badge mint PDA of the tier, created with its metadata by the tier's first badge
+    /// 2. `[w]` User's badge token account PDA for the tier, created by the user's first badge
+    /// 3. `[w]` Metaplex metadata account of the badge mint
+    /// 4. `[]` SPL Token program
+    /// 5. `[]` Metaplex Token Metadata program
+    /// 6. `[]` System program
+    /// 7. `[ws]` Rent payer of a new badge mint, metadata or badge account
+    SyncBadge {
+        tier: BadgeTier,
+    },
+
+    // KEEPERS
+    /// Sets the bounty paid to keepers for cranking the expiry of an overdue entity.
+    ///
+    /// 0. `[w]` Config PDA
+    /// 1. `[s]` Governance key
+    SetCrankBounty {
+        bounty: u64,
+    },
+
+    /// Expires any overdue offer, request, or shipment whose escrow sits in the vault, and
+    /// pays the caller the crank bounty out of it. Anyone can call it. Listings no one
+    /// accepted before their delist time are delisted, refunding the lister's deposit less
+    /// the bounty.
+    ///
+    /// 0. `[w]` Offer, request, or shipment account
+    /// 1. `[w]` Vault token account of the payment mint
+    /// 2. `[]` VaultRegistry PDA
+    /// 3. `[w]` Seller's or sender's token account
+    /// 4. `[w]` Buyer's or carrier's token account
+    /// 5. `[w]` Token account receiving the bounty
+    /// 6. `[]` Config PDA
+    /// 7. `[w]` Stats PDA
+    /// 8. `[w]` NFT escrow PDA of the offer, only when expiring an offer that sells an NFT
+    /// 9. `[w]` Seller's NFT token account, only when expiring an offer that sells an NFT
+    CrankExpire {
+        entity_type: EntityType,
+    },
+
+    // WALLETS
+    /// Deposits DLU from the owner's token account into the user's wallet.
+    ///
+    /// 0. `[w]` User account
+    /// 1. `[s]` Owner, authority of the owner's token account
+    /// 2. `[w]` Owner's token account
+    /// 3. `[w]` User's wallet token account PDA
+    /// 4. `[]` DLU mint, only when it charges a Token-2022 transfer fee
+    DepositDLU {
+        amount: u64,
+    },
+
+    /// Withdraws DLU from the user's wallet to a token account of the owner.
+    ///
+    /// 0. `[w]` User account
+    /// 1. `[s]` Owner
+    /// 2. `[w]` Owner's token account
+    /// 3. `[w]` User's wallet token account PDA
+    /// 4. `[]` User's Multisig PDA, only when the user is governed by a multisig
+    /// 5.. `[s]` Cosigners of the user's multisig, only when the amount reaches its high value
+    /// 6. `[]` DLU mint, only when it charges a Token-2022 transfer fee
+    WithdrawDLU {
+        amount: u64,
+    },
+
+    /// Resyncs the wallet balance cached on a user account with its wallet token account,
+    /// repairing any drift. Anyone may call it.
+    ///
+    /// 0. `[w]` User account
+    /// 1. `[]` User's wallet token account PDA
+    ReconcileWallet,
+
+    // QUOTES
+    /// Quotes a price for a listed request, locking the seller's insurance at that price
+    /// until the buyer selects the quote or the seller withdraws it.
+    ///
+    /// 0. `[]` Request account
+    /// 1. `[w]` Quote PDA of the request and the seller
+    /// 2. `[]` Seller's user account
+    /// 3. `[s]` Seller, authority of the seller's token account
+    /// 4. `[w]` Seller's token account
+    /// 5. `[w]` Vault token account of the payment mint
+    /// 6. `[]` VaultRegistry PDA
+    /// 7. `[]` Config PDA
+    /// 8. `[w]` Stats PDA
+    SubmitQuote {
+        price: u64,
+        note: String,
+    },
+
+    /// Accepts a request for the seller of one of its quotes at the quoted price, topping
+    /// up or partially refunding the buyer's deposit, and generates the one-time keys.
+    ///
+    /// 0. `[w]` Request account
+    /// 1. `[s]` Buyer, authority of the buyer's token account
+    /// 2. `[w]` Buyer's token account
+    /// 3. `[w]` Vault token account of the payment mint
+    /// 4. `[]` VaultRegistry PDA
+    /// 5. `[w]` Quote PDA of the request and the seller
+    /// 6. `[w]` Seller's user account
+    /// 7. `[]` Config PDA
+    /// 8. `[w]` Stats PDA
+    /// 9. `[]` AttestorRegistry PDA, only when the quoted price is above the KYC threshold
+    /// 10. `[]` Buyer's Attestation PDA, likewise
+    /// 11. `[]` Seller's Attestation PDA, likewise
+    SelectQuote,
+
+    /// Withdraws a quote that was not selected, returning the seller's insurance.
+    ///
+    /// 0. `[w]` Quote PDA
+    /// 1. `[s]` Seller
+    /// 2. `[w]` Seller's token account
+    /// 3. `[w]` Vault token account of the payment mint
+    /// 4. `[]` VaultRegistry PDA
+    /// 5. `[w]` Stats PDA
+    WithdrawQuote,
+
+    // MATCHING
+    /// Accepts a listed offer for the buyer of a listed request for the same goods in the
+    /// same town, when the offer's payment plus the finder's fee fits within the request's.
+    /// The request's deposit covers the buyer's deposit and the fee, the rest goes back to
+    /// the buyer, and the request is closed as matched. Anyone can call it.
+    ///
+    /// 0. `[w]` Offer account
+    /// 1. `[w]` Request account
+    /// 2. `[w]` Buyer's user account
+    /// 3. `[w]` Buyer's token account
+    /// 4. `[w]` Vault token account of the payment mint
+    /// 5. `[]` VaultRegistry PDA
+    /// 6. `[w]` Token account receiving the finder's fee
+    /// 7. `[]` Config PDA
+    /// 8. `[w]` Stats PDA
+    /// 9. `[w]` Seller's IndexAccount PDA
+    /// 10. `[]` AttestorRegistry PDA, only when the offer's payment is above the KYC threshold
+    /// 11. `[]` Seller's Attestation PDA, likewise
+    /// 12. `[]` Buyer's Attestation PDA, likewise
+    MatchRequestToOffer,
+
+    // MEETING POINTS
+    /// Publishes the exact meeting point of an accepted offer or request that was listed
+    /// with a `meeting_point_hash`, which must equal `Location::commitment` of the point
+    /// under `salt`. Only the lister can reveal it.
+    ///
+    /// 0. `[w]` Offer or request account
+    /// 1. `[s]` Lister
+    RevealMeetingPoint {
+        entity_type: EntityType,
+        meeting_point: Location,
+        salt: [u8; 32],
+    },
+
+    // MESSAGING
+    /// Appends an encrypted message to the message log of an accepted offer, request or
+    /// shipment. Only the deal's seller and buyer, or the shipment's sender, carrier and
+    /// recipient, can send. Clients encrypt `payload` to the counterparty's key; it holds
+    /// at most `MAX_MESSAGE_LEN` bytes.
+    ///
+    /// 0. `[]` Offer, request, or shipment account
+    /// 1. `[w]` MessageLog PDA of the entity, created by its first message
+    /// 2. `[s]` Sender, writable when paying a new MessageLog's rent
+    /// 3. `[]` System program
+    /// 4. `[ws]` Rent payer of a new MessageLog, only when someone other than the sender pays it
+    SendMessage {
+        entity_type: EntityType,
+        payload: Vec<u8>,
+    },
+
+    // PICKUPS
+    /// Records that the carrier of an accepted shipment took custody of the items, proven
+    /// by the `sender_key` the sender hands over at pickup. The shipment goes in transit,
+    /// and the delivery time `ExpireShipment` enforces starts counting from now. Shipments
+    /// can only be completed or failed once in transit.
+    ///
+    /// 0. `[w]` Shipment account
+    /// 1. `[s]` Carrier, a session key the carrier delegated the shipment to, or a member of the carrier's organization
+    /// 2. `[]` Delegation PDA of the session key, or the carrier's Organization PDA, only when someone else signs
+    ConfirmPickup {
+        sender_key: String,
+    },
+
+    // DELIVERY PROOFS
+    /// Records the digest of a photo or signature the carrier of an in-transit shipment took
+    /// at delivery, so arbiters of a later dispute have durable evidence of the items' state.
+    /// It can be submitted before `CompleteShipment`, which can also carry one.
+    ///
+    /// 0. `[w]` Shipment account
+    /// 1. `[s]` Carrier
+    SubmitProof {
+        proof_hash: [u8; 32],
+    },
+
+    // RETURNS
+    /// Lists the return of a completed shipment: the same items go from its recipient back
+    /// to its sender, from the original drop-off point to the original pickup point, under
+    /// the original insurance. The return is linked to the original by id, paid in the mint
+    /// of the recipient's token account, and counts towards the original recipient's
+    /// shipments as its sender once settled. A shipment can be returned once.
+    ///
+    /// 0. `[w]` Return shipment account, allocated and still empty
+    /// 1. `[w]` Original shipment account
+    /// 2. `[]` Recipient's user account
+    /// 3. `[s]` Recipient, authority of the recipient's token account, writable when paying a new RegionIndex's rent
+    /// 4. `[w]` Recipient's token account
+    /// 5. `[w]` Vault token account of the payment mint
+    /// 6. `[]` VaultRegistry PDA
+    /// 7. `[]` Sender's user account
+    /// 8. `[]` Config PDA
+    /// 9. `[w]` Stats PDA
+    /// 10. `[w]` RegionIndex PDA of the original drop-off point, created by its first listing, only when it has a geohash
+    /// 11. `[]` System program, only when the original drop-off point has a geohash
+    /// 12. `[ws]` Rent payer of a new RegionIndex, only when the original drop-off point has a geohash and someone other than the recipient pays it
+    CreateReturnShipment {
+        id: u64,
+        payment: u64,
+        pickup_datetime: i64,
+        drop_off_datetime: i64,
+    },
+
+    // HOLDBACKS
+    /// Releases the holdback of a completed offer to the seller once its window has passed
+    /// without a dispute.
+    ///
+    /// 0. `[w]` Offer account
+    /// 1. `[s]` Seller
+    /// 2. `[w]` Seller's token account
+    /// 3. `[w]` Vault token account of the payment mint
+    /// 4. `[]` VaultRegistry PDA
+    /// 5. `[w]` Stats PDA
+    ReleaseHoldback,
+
+    /// Disputes the goods of a completed offer within its holdback window, freezing the
+    /// holdback until `ResolveHoldbackDispute`.
+    ///
+    /// 0. `[w]` Offer account
+    /// 1. `[s]` Buyer
+    DisputeHoldback,
+
+    /// Settles a disputed holdback: it is refunded to the buyer if the goods were defective
+    /// and released to the seller otherwise.
+    ///
+    /// 0. `[]` Config PDA
+    /// 1. `[s]` Governance key
+    /// 2. `[w]` Offer account
+    /// 3. `[w]` Vault token account of the payment mint
+    /// 4. `[]` VaultRegistry PDA
+    /// 5. `[w]` Token account of the party the holdback is paid out to
+    /// 6. `[w]` Stats PDA
+    ResolveHoldbackDispute {
+        defective: bool,
+    },
+
+    // CHARGEBACKS
+    /// Sets how long after completion the buyer of an offer can charge it back, during
+    /// which the seller's insurance stays in escrow. Zero makes completions final.
+    ///
+    /// 0. `[w]` Config PDA
+    /// 1. `[s]` Governance key
+    SetChargebackWindow {
+        window: i64,
+    },
+
+    /// Opens a chargeback claim on a completed offer within its window, freezing the
+    /// seller's insurance until `ResolveChargeback`.
+    ///
+    /// 0. `[w]` Offer account
+    /// 1. `[s]` Buyer
+    OpenChargeback,
+
+    /// Releases the seller's insurance of a completed offer once its chargeback window has
+    /// closed without a claim.
+    ///
+    /// 0. `[w]` Offer account
+    /// 1. `[s]` Seller
+    /// 2. `[w]` Seller's token account
+    /// 3. `[w]` Vault token account of the payment mint
+    /// 4. `[]` VaultRegistry PDA
+    /// 5. `[w]` Stats PDA
+    ReleaseSellerInsurance,
+
+    /// Settles a chargeback claim: the seller's insurance goes to the buyer if the claim is
+    /// upheld and back to the seller otherwise.
+    ///
+    /// 0. `[]` Config PDA
+    /// 1. `[s]` Governance key
+    /// 2. `[w]` Offer account
+    /// 3. `[w]` Vault token account of the payment mint
+    /// 4. `[]` VaultRegistry PDA
+    /// 5. `[w]` Token account of the party the insurance is paid out to
+    /// 6. `[w]` Stats PDA
+    ResolveChargeback {
+        upheld: bool,
+    },
+
+    // ARBITRATION
+    /// Sets the stake arbiters register with and the fee they earn from the treasury per
+    /// ruling.
+    ///
+    /// 0. `[w]` Config PDA
+    /// 1. `[s]` Governance key
+    SetArbitrationTerms {
+        min_stake: u64,
+        fee: u64,
+    },
+
+    /// Locks DLU into the user's arbiter stake, creating it on first use in the mint of the
+    /// owner's token account, and adds them to the registry disputes are drawn from once it
+    /// covers the minimum stake. Later stakes must be in that mint.
+    ///
+    /// 0. `[w]` Arbiter PDA of the user
+    /// 1. `[w]` ArbiterRegistry PDA
+    /// 2. `[]` User account
+    /// 3. `[s]` Owner of the user account
+    /// 4. `[w]` Owner's token account
+    /// 5. `[w]` Vault token account of the stake mint
+    /// 6. `[]` VaultRegistry PDA
+    /// 7. `[]` Config PDA
+    /// 8. `[w]` Stats PDA
+    /// 9. `[]` System program
+    /// 10. `[ws]` Rent payer of a new Arbiter PDA, only when someone other than the owner pays it
+    RegisterArbiter {
+        amount: u64,
+    },
+
+    /// Removes an arbiter from the registry, starting the cooldown before they can withdraw.
+    ///
+    /// 0. `[w]` Arbiter PDA of the owner
+    /// 1. `[w]` ArbiterRegistry PDA
+    /// 2. `[s]` Owner
+    DeregisterArbiter,
+
+    /// Returns the whole stake of a deregistered arbiter once the cooldown has ended.
+    ///
+    /// 0. `[w]` Arbiter PDA of the owner
+    /// 1. `[s]` Owner
+    /// 2. `[w]` Owner's token account
+    /// 3. `[w]` Vault token account of the stake mint
+    /// 4. `[]` VaultRegistry PDA
+    /// 5. `[w]` Stats PDA
+    WithdrawArbiterStake,
+
+    /// Draws an arbiter for a dispute over a completed offer from the most recent slot
+    /// hash, never one of the parties. Anyone can call it once the dispute is open, and
+    /// again to redraw if the arbiter left the registry or did not rule in time.
+    ///
+    /// 0. `[]` Offer account
+    /// 1. `[w]` Arbitration PDA of the dispute
+    /// 2. `[]` ArbiterRegistry PDA
+    /// 3. `[]` SlotHashes sysvar
+    /// 4. `[]` System program
+    /// 5. `[ws]` Rent payer of the Arbitration PDA on the first draw
+    AssignArbiter {
+        kind: DisputeKind,
+    },
+
+    /// Rules on a dispute as its assigned arbiter, paying them the arbitration fee from the
+    /// treasury as far as it covers it. The ruling opens the appeal window; `SettleDispute`
+    /// pays the dispute out once it has passed.
+    ///
+    /// 0. `[]` Config PDA
+    /// 1. `[s]` Arbiter
+    /// 2. `[w]` Arbiter PDA of the arbiter
+    /// 3. `[]` ArbiterRegistry PDA
+    /// 4. `[]` Offer account
+    /// 5. `[w]` Arbitration PDA of the dispute
+    /// 6. `[w]` Arbiter's token account
+    /// 7. `[w]` Treasury token account of the payment mint
+    RuleDispute {
+        kind: DisputeKind,
+        for_buyer: bool,
+    },
+
+    // APPEALS
+    /// Sets the bond posted to appeal a ruling.
+    ///
+    /// 0. `[w]` Config PDA
+    /// 1. `[s]` Governance key
+    SetAppealBond {
+        bond: u64,
+    },
+
+    /// Appeals a ruling as the party who lost it, within the appeal window. The appellant
+    /// posts the appeal bond in the offer's mint and a panel of other arbiters is drawn
+    /// from the most recent slot hash to vote on the dispute again.
+    ///
+    /// 0. `[]` Offer account
+    /// 1. `[w]` Arbitration PDA of the dispute
+    /// 2. `[w]` Appeal PDA of the arbitration, created by the appeal
+    /// 3. `[]` ArbiterRegistry PDA
+    /// 4. `[]` SlotHashes sysvar
+    /// 5. `[s]` Appellant, writable when paying the Appeal's rent
+    /// 6. `[w]` Appellant's token account
+    /// 7. `[w]` Vault token account of the payment mint
+    /// 8. `[]` VaultRegistry PDA
+    /// 9. `[]` Config PDA
+    /// 10. `[w]` Stats PDA
+    /// 11. `[]` System program
+    /// 12. `[ws]` Rent payer of the Appeal PDA, only when someone other than the appellant pays it
+    AppealRuling {
+        kind: DisputeKind,
+    },
+
+    /// Casts a panelist's vote on an appealed dispute.
+    ///
+    /// 0. `[]` Offer account
+    /// 1. `[]` Arbitration PDA of the dispute
+    /// 2. `[w]` Appeal PDA of the arbitration
+    /// 3. `[s]` Panelist
+    VoteAppeal {
+        kind: DisputeKind,
+        for_buyer: bool,
+    },
+
+    /// Pays out a ruled dispute once it can no longer change: after the appeal window
+    /// without an appeal, or once the appeal panel reached a majority or ran out of time.
+    /// A decided appeal's bond goes half to the winning side and half to the panelists who
+    /// voted with the majority; a lapsed appeal's bond goes back to the appellant. Anyone
+    /// can call it.
+    ///
+    /// 0. `[w]` Offer account
+    /// 1. `[]` Arbitration PDA of the dispute
+    /// 2. `[w]` Vault token account of the payment mint
+    /// 3. `[]` VaultRegistry PDA
+    /// 4. `[w]` Token account of the party the settlement pays out to
+    /// 5. `[w]` Stats PDA
+    /// 6. `[w]` Appeal PDA of the arbitration, only when the ruling was appealed
+    /// 7. `[w]` Appellant's token account, only when the ruling was appealed
+    /// 8.. `[w]` Token accounts of the panelists who voted with the majority, in panel order
+    SettleDispute {
+        kind: DisputeKind,
+    },
+
+    /// Slashes up to the appeal bond from the stake of an arbiter whose ruling was
+    /// overturned on appeal, once the appeal is settled.
+    ///
+    /// 0. `[]` Offer account
+    /// 1. `[]` Arbitration PDA of the dispute
+    /// 2. `[w]` Appeal PDA of the arbitration
+    /// 3. `[w]` Arbiter PDA of the overturned arbiter
+    /// 4. `[w]` Vault token account of the stake mint
+    /// 5. `[]` VaultRegistry PDA
+    /// 6. `[w]` Penalty token account, normally the penalty vault of the mint, or the stake mint when penalties are burned
+    /// 7. `[]` Config PDA
+    /// 8. `[w]` Stats PDA
+    SlashArbiter {
+        kind: DisputeKind,
+    },
+
+    // SETTLEMENTS
+
+    /// Settles an accepted offer by splitting everything in escrow between the parties by
+    /// basis points, for outcomes such as goods delivered but damaged. Either both parties
+    /// sign, or a registered arbiter who is neither of them does. An NFT goes to the buyer
+    /// unless the seller receives nothing.
+    ///
+    /// 0. `[w]` Offer account
+    /// 1. `[w]` Vault token account of the payment mint
+    /// 2. `[]` VaultRegistry PDA
+    /// 3. `[w]` Seller's token account
+    /// 4. `[w]` Buyer's token account
+    /// 5. `[w]` Stats PDA
+    /// 6. `[s]` Seller, or the arbiter
+    /// 7. `[s]` Buyer, or the ArbiterRegistry PDA when an arbiter settles
+    /// 8. `[w]` NFT escrow PDA of the offer, only when it sells an NFT
+    /// 9. `[w]` NFT token account of the recipient, only when the offer sells an NFT
+    SettleDeal {
+        seller_bps: u16,
+        buyer_bps: u16,
+        nonce: u64,  // The offer's current nonce.
+    },
+
+    // SESSION KEYS
+
+    /// Authorizes a short-lived `session` key to act for the owner on up to
+    /// `MAX_SESSION_ENTITIES` deals or shipments until `expires_at`, at most
+    /// `MAX_SESSION_DURATION` from now. Creating it again replaces the scope and expiry.
+    ///
+    /// 0. `[s]` Owner, writable when paying a new Delegation's rent
+    /// 1. `[w]` Delegation PDA of the owner and session key, created by the first authorization
+    /// 2. `[]` System program
+    /// 3. `[ws]` Rent payer of a new Delegation, only when someone other than the owner pays it
+    CreateSession {
+        session: Pubkey,
+        entities: Vec<Pubkey>,
+        expires_at: i64,
+    },
+
+    /// Ends a session key's delegation before it expires.
+    ///
+    /// 0. `[s]` Owner
+    /// 1. `[w]` Delegation PDA of the owner and session key
+    RevokeSession {
+        session: Pubkey,
+    },
+
+    // ORGANIZATIONS
+
+    /// Turns the owner's user into an organization that member wallets can act for.
+    ///
+    /// 0. `[s]` Owner, writable when paying the Organization's rent
+    /// 1. `[w]` Organization PDA of the owner, created by the instruction
+    /// 2. `[]` System program
+    /// 3. `[ws]` Rent payer of the Organization PDA, only when someone other than the owner pays it
+    CreateOrganization,
+
+    /// Gives a member wallet the `roles`, a combination of the `MEMBER_*` flags, adding it
+    /// to the organization if needed. No roles removes the member.
+    ///
+    /// 0. `[s]` Owner
+    /// 1. `[w]` Organization PDA of the owner
+    SetMember {
+        member: Pubkey,
+        roles: u8,
+    },
+
+    // MULTISIGS
+
+    /// Puts the owner's user under an m-of-n multisig: from then on, listings paying and
+    /// withdrawals of at least `high_value` need `threshold` of the `signers`.
+    ///
+    /// 0. `[w]` User account
+    /// 1. `[s]` Owner, writable when paying the Multisig's rent
+    /// 2. `[w]` Multisig PDA of the owner, created by the instruction
+    /// 3. `[]` System program
+    /// 4. `[ws]` Rent payer of the Multisig PDA, only when someone other than the owner pays it
+    CreateMultisig {
+        threshold: u8,
+        signers: Vec<Pubkey>,
+        high_value: u64,
+    },
+
+    /// Replaces the threshold, signers and high value of a user's multisig, signed by
+    /// enough of its current signers.
+    ///
+    /// 0. `[]` User account
+    /// 1. `[w]` Multisig PDA of the user
+    /// 2.. `[s]` Signers of the multisig, reaching its current threshold
+    UpdateMultisig {
+        threshold: u8,
+        signers: Vec<Pubkey>,
+        high_value: u64,
+    },
+
+    // MODERATION
+
+    /// Bans a user from listing and accepting, or lifts the ban. Banned users can still
+    /// finish the deals they are in and withdraw their wallet.
+    ///
+    /// 0. `[]` Config PDA
+    /// 1. `[s]` Governance key
+    /// 2. `[w]` User account
+    SetUserBanned {
+        banned: bool,
+    },
+
+    // KYC
+    /// Sets the deal payment above which both parties need a valid attestation to accept.
+    ///
+    /// 0. `[w]` Config PDA
+    /// 1. `[s]` Governance key
+    SetKycThreshold {
+        threshold: u64,
+    },
+
+    /// Approves a key to attest users, or withdraws its approval. Withdrawing it also
+    /// voids the attestations it wrote.
+    ///
+    /// 0. `[]` Config PDA
+    /// 1. `[ws]` Governance key, paying the AttestorRegistry's rent when the first approval creates it
+    /// 2. `[w]` AttestorRegistry PDA, created by the first approval
+    /// 3. `[]` System program
+    SetAttestor {
+        attestor: Pubkey,
+        approved: bool,
+    },
+
+    /// Attests that a user passed identity checks until `expires_at`, creating the
+    /// attestation on first use. An expiry that has passed revokes it.
+    ///
+    /// 0. `[]` AttestorRegistry PDA
+    /// 1. `[s]` Attestor, writable when paying a new Attestation's rent
+    /// 2. `[]` User account
+    /// 3. `[w]` Attestation PDA of the user
+    /// 4. `[]` System program
+    /// 5. `[ws]` Rent payer of a new Attestation, only when someone other than the attestor pays it
+    Attest {
+        expires_at: i64,
+    },
+
+    // REFERRALS
+    /// Sets the share of referred payees' protocol fees credited to their referrers.
+    ///
+    /// 0. `[w]` Config PDA
+    /// 1. `[s]` Governance key
+    SetReferralShare {
+        share_bps: u16,
+    },
+
+    /// Pays a referrer everything credited to them in one mint since their last claim.
+    ///
+    /// 0. `[w]` ReferralRewards PDA of the referrer and the mint
+    /// 1. `[s]` Referrer
+    /// 2. `[w]` Treasury token account of the mint
+    /// 3. `[w]` Referrer's token account
+    ClaimReferralRewards,
+
+    // PROMO CODES
+    /// Attaches a promo code to an offer, or replaces the one with the same hash, granting
+    /// `discount_percent` off the payment for up to `uses` acceptances. `code_hash` is
+    /// `promo_code_hash` of the offer and the code; zero `uses` withdraws the code.
+    ///
+    /// 0. `[]` Offer account
+    /// 1. `[s]` Seller, writable when paying the rent of a new PromoCodes account
+    /// 2. `[w]` Offer's PromoCodes PDA, created by the offer's first code
+    /// 3. `[]` System program
+    /// 4. `[ws]` Rent payer of a new PromoCodes account, only when someone other than the seller pays it
+    SetPromoCode {
+        code_hash: [u8; 32],
+        discount_percent: u8,
+        uses: u32,
+    },
+
+    // SCHEDULED LISTINGS
+    /// Schedules a listed offer or request to open for acceptances at `activate_at` and,
+    /// with a `delist_at`, to be expired from then on, refunding the lister, unless someone
+    /// accepted it. Relisting clears the schedule.
+    ///
+    /// 0. `[w]` Offer or request account
+    /// 1. `[s]` Lister
+    ScheduleListing {
+        entity_type: EntityType,
+        activate_at: i64,
+        delist_at: Option<i64>,
+    },
+
+    // STREAMING PAYMENTS
+    /// Pays the seller of an accepted streaming offer the part of its payment vested since
+    /// their last withdrawal, less the protocol fee.
+    ///
+    /// 0. `[w]` Offer account
+    /// 1. `[]` Seller's user account
+    /// 2. `[s]` Seller
+    /// 3. `[w]` Seller's token account
+    /// 4. `[w]` Vault token account of the payment mint
+    /// 5. `[]` VaultRegistry PDA
+    /// 6. `[]` Config PDA
+    /// 7. `[w]` Treasury token account of the payment mint
+    /// 8. `[w]` Stats PDA
+    /// 9. `[w]` ReferralRewards PDA of the seller's referrer and the payment mint, only when the seller was referred and pays a fee
+    /// 10. `[]` System program, likewise
+    /// 11. `[ws]` Rent payer of the ReferralRewards PDA on its first credit, likewise
+    WithdrawStream,
+
+    /// Stops the stream of an accepted streaming offer, freezing vesting. The seller can
+    /// still withdraw what vested until then; the unvested remainder and both insurances
+    /// stay in escrow until the deal is completed or settled with `SettleDeal`.
+    ///
+    /// 0. `[w]` Offer account
+    /// 1. `[s]` Seller or buyer
+    StopStream,
+
+    // WATCHLISTS
+    /// Follows a user on the signer's watchlist, or unfollows them, so clients can notify
+    /// the signer of the user's listings.
+    ///
+    /// 0. `[s]` Follower, writable when paying a new Watchlist's rent
+    /// 1. `[w]` Watchlist PDA of the follower, created by their first follow or watch
+    /// 2. `[]` System program
+    /// 3. `[ws]` Rent payer of a new Watchlist, only when someone other than the follower pays it
+    FollowUser {
+        user: Pubkey,
+        follow: bool,
+    },
+
+    /// Watches an offer on the signer's watchlist, or stops watching it, so clients can
+    /// notify the signer of its `OfferStatusChanged` events.
+    ///
+    /// 0. `[s]` Watcher, writable when paying a new Watchlist's rent
+    /// 1. `[w]` Watchlist PDA of the watcher, created by their first follow or watch
+    /// 2. `[]` Offer account
+    /// 3. `[]` System program
+    /// 4. `[ws]` Rent payer of a new Watchlist, only when someone other than the watcher pays it
+    WatchOffer {
+        watch: bool,
+    },
+
+    // COMPRESSED LISTINGS
+    /// Initializes a concurrent Merkle tree that compressed offers are listed in, with the
+    /// tree's authority PDA as its authority. The client allocates the tree for the depth
+    /// and buffer size, owned by the account compression program.
+    ///
+    /// 0. `[w]` Listing tree
+    /// 1. `[]` Listing tree authority PDA
+    /// 2. `[]` Account compression program
+    /// 3. `[]` Noop program
+    CreateListingTree {
+        max_depth: u32,
+        max_buffer_size: u32,
+    },
+
+    /// Lists a described offer as a leaf of a listing tree instead of in its own account,
+    /// locking the seller's insurance as `ListOffer` does. The listing is logged in full
+    /// with a `CompressedOfferListed` event, which clients need to accept or cancel it.
+    ///
+    /// 0. `[w]` Listing tree
+    /// 1. `[]` Listing tree authority PDA
+    /// 2. `[]` Seller's user account
+    /// 3. `[s]` Seller, authority of the seller's token account
+    /// 4. `[w]` Seller's token account
+    /// 5. `[w]` Vault token account of the payment mint
+    /// 6. `[]` VaultRegistry PDA
+    /// 7. `[]` Config PDA
+    /// 8. `[w]` Stats PDA
+    /// 9. `[]` Account compression program
+    /// 10. `[]` Noop program
+    ListCompressedOffer {
+        id: u64,
+        goodsorservice_name: String,
+        goodsorservice_description: String,
+        payment: u64,
+        meeting_point: Location,
+        meeting_datetime: i64,
+    },
+
+    /// Accepts a compressed offer: proves its leaf at `index` under `root`, empties the
+    /// leaf, and materializes the offer into the offer account, accepted by the buyer.
+    ///
+    /// 0. `[w]` Listing tree
+    /// 1. `[]` Listing tree authority PDA
+    /// 2. `[w]` Offer account, allocated and still empty
+    /// 3. `[]` Seller's user account
+    /// 4. `[w]` Buyer's user account
+    /// 5. `[s]` Buyer
+    /// 6. `[w]` Buyer's token account
+    /// 7. `[w]` Vault token account of the payment mint
+    /// 8. `[]` VaultRegistry PDA
+    /// 9. `[s]` Authority of the buyer's token account
+    /// 10. `[]` Config PDA
+    /// 11. `[w]` Stats PDA
+    /// 12. `[w]` Seller's IndexAccount PDA
+    /// 13. `[]` Account compression program
+    /// 14. `[]` Noop program
+    /// 15.. `[]` Proof nodes of the leaf, from the leaf up
+    AcceptCompressedOffer {
+        listing: CompressedOffer,
+        root: [u8; 32],
+        index: u32,
+    },
+
+    /// Cancels a compressed offer, emptying its leaf and releasing the seller's insurance.
+    ///
+    /// 0. `[w]` Listing tree
+    /// 1. `[]` Listing tree authority PDA
+    /// 2. `[]` Seller's user account
+    /// 3. `[s]` Seller
+    /// 4. `[w]` Seller's token account
+    /// 5. `[w]` Vault token account of the payment mint
+    /// 6. `[]` VaultRegistry PDA
+    /// 7. `[w]` Stats PDA
+    /// 8. `[]` Account compression program
+    /// 9. `[]` Noop program
+    /// 10.. `[]` Proof nodes of the leaf, from the leaf up
+    CancelCompressedOffer {
+        listing: CompressedOffer,
+        root: [u8; 32],
+        index: u32,
+    },
+
+    // ARCHIVES
+    /// Archives up to `MAX_ARCHIVE_BATCH` of the lister's offers, requests, or shipments
+    /// once they ended: appends the Merkle root of their account data to the lister's
+    /// History and closes them, returning their rent to the lister. Each entity must have
+    /// nothing left in escrow and be `ARCHIVE_DELAY` past its meeting or drop-off time.
+    ///
+    /// 0. `[ws]` Lister, receiving the rent and paying for the history's creation and growth
+    /// 1. `[w]` History PDA of the lister, created by their first archive
+    /// 2. `[]` System program
+    /// 3. `[ws]` Rent payer of the history's creation and growth, only when someone other than the lister pays it
+    /// 4.. `[w]` Entity accounts, in the order of the batch's leaves
+    ArchiveBatch {
+        entity_type: EntityType,
+    },
+
+    /// Fails unless `proof`, from the leaf up, shows the entity with the hash of its last
+    /// account data at `index` of the numbered batch of the lister's history.
+    ///
+    /// 0. `[]` History PDA of the lister
+    VerifyArchivedEntity {
+        lister: Pubkey,
+        batch: u32,
+        entity: Pubkey,
+        data_hash: [u8; 32],
+        index: u32,
+        proof: Vec<[u8; 32]>,
+    },
+
+    // ONE-TIME KEYS
+    /// Commits the hash of the signer's one-time key for an accepted offer, request or
+    /// shipment, computed with `onetimekeys::hash_key`. The key itself stays with the party
+    /// until they hand it over: at the meeting for deals, at pickup or delivery for
+    /// shipments. Completing, failing or picking up needs every key it checks committed.
+    /// Short key codes, see `onetimekeys::encode_key_code`, are committed in their
+    /// canonical form and accepted however they are typed. The KeyManager records the key with the `nonce` the client derived it from, see
+    /// `onetimekeys::key_seed`, and refuses nonces and hashes the party committed before.
+    ///
+    /// 0. `[w]` Offer, request, or shipment account
+    /// 1. `[s]` Party, the seller or buyer of a deal, or the sender, carrier or recipient of a shipment, writable when paying a new KeyManager's rent
+    /// 2. `[w]` KeyManager PDA of the offer, request, or shipment, created by its first commit
+    /// 3. `[]` System program
+    /// 4. `[ws]` Rent payer of a new KeyManager, only when someone other than the party pays it
+    CommitKey {
+        entity_type: EntityType,
+        key_hash: [u8; 32],
+        nonce: u64,
+    },
+
+    /// Replaces both one-time keys of an accepted offer or request with new key hashes, for
+    /// keys that were lost or expired. Committed keys are accepted for `KEY_LIFETIME` from
+    /// their commit, so both parties sign off on the fresh ones together.
+    ///
+    /// Both new keys are recorded with `nonce`, and the keys they replace are marked used.
+    ///
+    /// 0. `[w]` Offer or request account
+    /// 1. `[s]` Seller, writable when paying a new KeyManager's rent
+    /// 2. `[s]` Buyer
+    /// 3. `[w]` KeyManager PDA of the offer or request, created here if no key was committed yet
+    /// 4. `[]` System program
+    /// 5. `[ws]` Rent payer of a new KeyManager, only when someone other than the seller pays it
+    RegenerateKeys {
+        entity_type: EntityType,
+        seller_key_hash: [u8; 32],
+        buyer_key_hash: [u8; 32],
+        nonce: u64,
+    },
+
+    // STALE LISTINGS
+    /// Expires an offer, request or shipment no one accepted within `MAX_LISTING_LIFETIME`
+    /// past its meeting or drop-off time, refunding the lister's whole deposit out of the
+    /// vault. Anyone can call it, without a bounty, so escrow is never stranded by a lister
+    /// who walked away.
+    ///
+    /// 0. `[w]` Offer, request, or shipment account
+    /// 1. `[w]` Vault token account of the payment mint
+    /// 2. `[]` VaultRegistry PDA
+    /// 3. `[w]` Lister's token account
+    /// 4. `[w]` Stats PDA
+    /// 5. `[w]` NFT escrow PDA of the offer, only when reclaiming an offer that sells an NFT
+    /// 6. `[w]` Seller's NFT token account, only when reclaiming an offer that sells an NFT
+    ReclaimStaleListing {
+        entity_type: EntityType,
+    },
+
+    // RESCHEDULING
+    /// Moves the meeting time of an accepted offer or request, or the drop-off time of an
+    /// accepted shipment, to a later `deadline`, so a rescheduled meetup doesn't expire the
+    /// deal and force a relist. Both active parties sign, and the current deadline must not
+    /// have passed its grace period yet.
+    ///
+    /// 0. `[w]` Offer, request, or shipment account
+    /// 1. `[s]` Seller or sender
+    /// 2. `[s]` Buyer or carrier
+    ExtendDeadline {
+        entity_type: EntityType,
+        deadline: i64,
+    },
+
+    // RECONCILIATION
+    /// Reconciles the vault an offer, request, or shipment's escrow sits in, comparing its
+    /// balance with the total the Stats record escrowed there across every entity of the
+    /// mint. A vault holding less emits `EscrowDiverged` and flags the entity, whose payouts
+    /// then fail until governance unwinds it, as a safety net for accounting bugs. Anyone can
+    /// call it; it never moves funds.
+    ///
+    /// 0. `[w]` Offer, request, or shipment account
+    /// 1. `[]` Vault token account of the payment mint
+    /// 2. `[]` VaultRegistry PDA
+    /// 3. `[]` Stats PDA
+    VerifyEscrow {
+        entity_type: EntityType,
+    },
+
+    // EMERGENCY RESOLUTIONS
+    /// Proposes to unwind an offer, request, or shipment a bug left stuck, starting the
+    /// `EMERGENCY_TIMELOCK` before `EmergencyResolve` can return its escrow.
+    ///
+    /// 0. `[]` Config PDA
+    /// 1. `[ws]` Governance key, paying the EmergencyResolution's rent when the entity's first proposal creates it
+    /// 2. `[]` Offer, request, or shipment account
+    /// 3. `[w]` EmergencyResolution PDA of the entity, created by its first proposal
+    /// 4. `[]` System program
+    ProposeEmergencyResolve {
+        entity_type: EntityType,
+    },
+
+    /// Returns the escrow of an in-flight offer, request, or shipment whose escrow sits in
+    /// the vault to the parties who deposited it, and cancels it, once the timelock of its
+    /// proposal has passed: the lister's deposit for listings, both deposits once accepted.
+    ///
+    /// 0. `[]` Config PDA
+    /// 1. `[s]` Governance key
+    /// 2. `[w]` Offer, request, or shipment account
+    /// 3. `[w]` EmergencyResolution PDA of the entity
+    /// 4. `[w]` Vault token account of the payment mint
+    /// 5. `[]` VaultRegistry PDA
+    /// 6. `[w]` Seller's or sender's token account
+    /// 7. `[w]` Buyer's or carrier's token account
+    /// 8. `[w]` Stats PDA
+    /// 9. `[w]` NFT escrow PDA of the offer, only when unwinding an offer that sells an NFT
+    /// 10. `[w]` Seller's NFT token account, only when unwinding an offer that sells an NFT
+    EmergencyResolve {
+        entity_type: EntityType,
+    },
+
+    /// Withdraws governance's proposal to unwind an offer, request, or shipment.
+    ///
+    /// 0. `[]` Config PDA
+    /// 1. `[s]` Governance key
+    /// 2. `[]` Offer, request, or shipment account
+    /// 3. `[w]` EmergencyResolution PDA of the entity
+    CancelEmergencyResolve,
+
+    // TIMELOCKED GOVERNANCE
+    /// Queues a governance action, executable by anyone with `ExecuteGovernanceAction` once
+    /// `GOVERNANCE_TIMELOCK` has passed. After governance enables `CAP_TIMELOCKED_GOVERNANCE`,
+    /// the instructions updating the config or moving governed funds directly fail and the
+    /// queue is the only way.
+    ///
+    /// 0. `[]` Config PDA
+    /// 1. `[s]` Governance key
+    /// 2. `[w]` TimelockQueue PDA
+    QueueGovernanceAction {
+        action: GovernanceAction,
+    },
+
+    /// Removes a queued config update before it is executed.
+    ///
+    /// 0. `[]` Config PDA
+    /// 1. `[s]` Governance key
+    /// 2. `[w]` TimelockQueue PDA
+    CancelGovernanceAction {
+        id: u64,
+    },
+
+    /// Applies a queued action whose timelock has passed. Anyone can call it.
+    ///
+    /// 0. `[w]` Config PDA
+    /// 1. `[w]` TimelockQueue PDA
+    ///
+    /// The actions beyond the config then take the accounts their own instruction takes after
+    /// the governance key, which must match the queued keys:
+    ///
+    /// - `WithdrawTreasury`: 2. `[w]` Treasury token account of the mint, 3. `[w]` Destination
+    ///   token account
+    /// - `RegisterVault`: 2. `[ws]` Rent payer of the mint's token accounts and of a new
+    ///   VaultRegistry, then accounts 2-9 of `RegisterVault`
+    /// - `MigrateEscrow`: accounts 2-6 of `MigrateEscrow`
+    ExecuteGovernanceAction {
+        id: u64,
+    },
+
+    // DAO VOTING
+    /// Sets up voting on config updates by holders of `vote_mint`. Can only be done once.
+    ///
+    /// 0. `[]` Config PDA
+    /// 1. `[ws]` Governance key, paying the Dao's rent
+    /// 2. `[w]` Dao PDA, created here
+    /// 3. `[]` System program
+    InitializeDao {
+        vote_mint: Pubkey,
+        quorum: u64,
+        proposal_threshold: u64,
+        voting_period: i64,
+    },
+
+    /// Locks tokens of the vote mint into the vault to vote with, creating the owner's
+    /// VoteLock on first use.
+    ///
+    /// 0. `[]` Dao PDA
+    /// 1. `[w]` Owner's VoteLock PDA, created by their first lock
+    /// 2. `[s]` Owner, writable when paying a new VoteLock's rent
+    /// 3. `[w]` Owner's token account
+    /// 4. `[w]` Vault token account of the vote mint
+    /// 5. `[]` VaultRegistry PDA
+    /// 6. `[]` System program
+    /// 7. `[ws]` Rent payer of a new VoteLock, only when someone other than the owner pays it
+    LockVotes {
+        amount: u64,
+    },
+
+    /// Takes locked tokens back once every vote they were cast in has closed.
+    ///
+    /// 0. `[]` Dao PDA
+    /// 1. `[w]` Owner's VoteLock PDA
+    /// 2. `[s]` Owner
+    /// 3. `[w]` Owner's token account
+    /// 4. `[w]` Vault token account of the vote mint
+    /// 5. `[]` VaultRegistry PDA
+    UnlockVotes {
+        amount: u64,
+    },
+
+    /// Opens a vote on a config update, for a proposer with at least the proposal threshold
+    /// locked.
+    ///
+    /// 0. `[w]` Dao PDA
+    /// 1. `[w]` Proposal PDA of the DAO's next proposal ID, created here
+    /// 2. `[]` Proposer's VoteLock PDA
+    /// 3. `[s]` Proposer, writable when paying the Proposal's rent
+    /// 4. `[]` System program
+    /// 5. `[ws]` Rent payer of the Proposal, only when someone other than the proposer pays it
+    CreateProposal {
+        action: GovernanceAction,
+    },
+
+    /// Votes for or against an open proposal with all the voter's locked tokens, which stay
+    /// locked until voting ends.
+    ///
+    /// 0. `[w]` Proposal PDA
+    /// 1. `[w]` Voter's VoteLock PDA
+    /// 2. `[s]` Voter, writable when paying the VoteRecord's rent
+    /// 3. `[w]` VoteRecord PDA of the proposal and voter, created here
+    /// 4. `[]` System program
+    /// 5. `[ws]` Rent payer of the VoteRecord, only when someone other than the voter pays it
+    CastVote {
+        support: bool,
+    },
+
+    /// Closes voting on a proposal once its period has ended, queuing its update in the
+    /// timelock if it reached quorum with a majority for it. Anyone can call it.
+    ///
+    /// 0. `[]` Dao PDA
+    /// 1. `[w]` Proposal PDA
+    /// 2. `[w]` TimelockQueue PDA
+    FinalizeProposal,
+
+    // PENALTY POOL
+    /// Sets the share of each penalty pool distribution burned instead of paid to stakers.
+    ///
+    /// 0. `[w]` Config PDA
+    /// 1. `[s]` Governance key
+    SetPenaltyBurnShare {
+        share_bps: u16,
+    },
+
+    /// Distributes the penalties that reached the penalty vault of a mint since the last
+    /// distribution: the config's burn share is burned and the rest credited to the enrolled
+    /// carrier stakes. Anyone can call it, at most once per `PENALTY_DISTRIBUTION_INTERVAL`.
+    ///
+    /// 0. `[]` Config PDA
+    /// 1. `[w]` PenaltyPool PDA of the mint, created by the first distribution
+    /// 2. `[w]` Penalty vault token account of the mint
+    /// 3. `[w]` Mint
+    /// 4. `[]` System program
+    /// 5. `[ws]` Rent payer of the PenaltyPool on the first distribution
+    DistributePenalties,
+
+    /// Pays a carrier the penalties credited to their enrolled stake since their last claim,
+    /// then enrolls their current stake. The first claim only enrolls it.
+    ///
+    /// 0. `[w]` PenaltyPool PDA of the mint
+    /// 1. `[w]` Carrier's PenaltyRewards PDA in the pool, created by their first claim
+    /// 2. `[]` Carrier's CarrierStake PDA
+    /// 3. `[s]` Carrier, writable when paying a new PenaltyRewards' rent
+    /// 4. `[w]` Penalty vault token account of the mint
+    /// 5. `[w]` Carrier's token account
+    /// 6. `[]` System program
+    /// 7. `[ws]` Rent payer of a new PenaltyRewards, only when someone other than the carrier pays it
+    ClaimPenaltyRewards,
+
+    // FAUCET
+    /// Mints `FAUCET_AMOUNT` DLU to the owner's associated token account, at most once per
+    /// `FAUCET_COOLDOWN`. Only open when the config enables `CAP_TEST_CLUSTER`, on clusters
+    /// whose DLU mint has the faucet PDA as its mint authority.
+    ///
+    /// 0. `[w]` User account
+    /// 1. `[s]` Owner
+    /// 2. `[]` Config PDA
+    /// 3. `[w]` Owner's DLU associated token account
+    /// 4. `[w]` DLU mint
+    /// 5. `[]` Faucet PDA
+    RequestFaucet,
+
+    // COMPLETION REWARDS
+    /// Schedules the DLU paid to both parties of every completed deal and shipment out of
+    /// the rewards vault of the payment mint: `reward` each at first, halving over every
+    /// `half_life` seconds, until `emission_cap` has been paid in total. A zero `reward`
+    /// stops the rewards.
+    ///
+    /// 0. `[w]` Config PDA
+    /// 1. `[s]` Governance key
+    SetCompletionRewards {
+        reward: u64,
+        half_life: u64,
+        emission_cap: u64,
+    },
+
+    // FUNDED ACCEPTANCE
+    /// Deposits `amount` DLU from the owner's token account into the buyer's wallet, then
+    /// accepts an offer paying the buyer's deposit out of that wallet, so funding and
+    /// accepting succeed or fail together. A zero `amount` pays with what the wallet already
+    /// holds. Quantities and promo codes work as with `AcceptOffer`.
+    ///
+    /// 0. `[w]` Offer account
+    /// 1. `[w]` Buyer's user account
+    /// 2. `[s]` Buyer, authority of the buyer's token account
+    /// 3. `[w]` Buyer's token account
+    /// 4. `[w]` Buyer's wallet token account PDA
+    /// 5. `[w]` Vault token account of the payment mint
+    /// 6. `[]` VaultRegistry PDA
+    /// 7. `[]` Config PDA
+    /// 8. `[w]` Stats PDA
+    /// 9. `[w]` Seller's IndexAccount PDA
+    /// 10. `[]` AttestorRegistry PDA, only when the payment is above the KYC threshold
+    /// 11. `[]` Seller's Attestation PDA, likewise
+    /// 12. `[]` Buyer's Attestation PDA, likewise
+    /// 13. `[w]` Offer's PromoCodes PDA, only with a `promo_code`
+    DepositAndAccept {
+        amount: u64,
+        quantity: u32,
+        promo_code: Option<String>,
+    },
+
+    // BATCH EXPIRY
+    /// Expires up to `MAX_EXPIRY_BATCH` overdue offers, requests, or shipments of one type
+    /// like `CrankExpire`, paying the caller the crank bounty of each. Entities not overdue
+    /// yet, or already settled, are skipped, so keepers can batch whatever they saw due.
+    /// `ids` are the IDs of the entities, in the order their accounts follow. NFT offers
+    /// return their NFT as well and are expired with `CrankExpire` alone.
+    ///
+    /// 0. `[w]` Token account receiving the bounties, in the mint of the batch
+    /// 1. `[w]` Vault token account of that mint
+    /// 2. `[]` VaultRegistry PDA
+    /// 3. `[]` Config PDA
+    /// 4. `[w]` Stats PDA
+    /// 5.. `[w]` For every ID: the offer, request, or shipment account, then the seller's or sender's and the buyer's or carrier's token accounts
+    BatchExpire {
+        entity_type: EntityType,
+        ids: Vec<u64>,
+    },
+
+    // VAULT REGISTRY
+    /// Creates the canonical escrow vault of a payment mint, the token account at the PDA
+    /// `["vault", mint]` that is its own authority, and records it in the VaultRegistry. The
+    /// mint's treasury, penalty vault and rewards vault are created alongside it the same way.
+    /// Escrow only ever moves into registered vaults. The first registration creates the
+    /// VaultRegistry. Fails once the config enables `CAP_TIMELOCKED_GOVERNANCE`, after which
+    /// governance queues a `RegisterVault` action.
+    ///
+    /// 0. `[]` Config PDA
+    /// 1. `[ws]` Governance key, paying the rent of the mint's token accounts and of a new VaultRegistry
+    /// 2. `[w]` VaultRegistry PDA
+    /// 3. `[w]` Vault token account PDA of the mint
+    /// 4. `[w]` Treasury token account PDA of the mint
+    /// 5. `[w]` Penalty vault token account PDA of the mint
+    /// 6. `[w]` Rewards vault token account PDA of the mint
+    /// 7. `[]` Mint
+    /// 8. `[]` System program
+    /// 9. `[]` Token program owning the mint
+    RegisterVault,
+
+    // SHIPMENT PRICING
+    /// Sets the rates shipment payments are suggested from: `base_rate` per shipment plus
+    /// `rate_per_km` of distance and `rate_per_kg` of chargeable weight, in base units of the
+    /// payment mint.
+    ///
+    /// 0. `[w]` Config PDA
+    /// 1. `[s]` Governance key
+    SetShippingRates {
+        base_rate: u64,
+        rate_per_km: u64,
+        rate_per_kg: u64,
+    },
+
+    /// Records the weight, volume and distance of a listed shipment with the config's current
+    /// rates, and logs the payment they suggest. Measurements left out are not charged for.
+    ///
+    /// 0. `[w]` Shipment account
+    /// 1. `[s]` Sender
+    /// 2. `[]` Config PDA
+    MeasureShipment {
+        weight_grams: Option<u32>,
+        volume_cm3: Option<u32>,
+        distance_km: Option<u32>,
+    },
+
+    // CARRIER ROUTES
+    /// Publishes a trip the signing carrier makes every week from the `origin` geohash region
+    /// to `destination` on the `days` bits of `ROUTE_DAYS_MASK`, taking up to
+    /// `capacity_grams` of chargeable weight, and lists it in the origin's route index.
+    /// Publishing a route again updates its days and capacity. The carrier pays the rent of
+    /// the route and, for the first route from the origin, of its index.
+    ///
+    /// 0. `[ws]` Carrier
+    /// 1. `[]` Carrier's user account
+    /// 2. `[w]` CarrierRoute PDA of the carrier and `route_id`
+    /// 3. `[w]` RouteIndex PDA of the origin region
+    /// 4. `[]` System program
+    PublishRoute {
+        route_id: u32,
+        origin: [u8; REGION_GEOHASH_LEN],
+        destination: [u8; REGION_GEOHASH_LEN],
+        days: u8,
+        capacity_grams: u64,
+    },
+
+    /// Closes a route the signing carrier no longer travels, removing it from its origin's
+    /// route index and returning its rent to the carrier.
+    ///
+    /// 0. `[ws]` Carrier
+    /// 1. `[w]` CarrierRoute PDA of the carrier and `route_id`
+    /// 2. `[w]` RouteIndex PDA of the route's origin region
+    CloseRoute {
+        route_id: u32,
+    },
+
+    /// Lists a shipment along a carrier route running from the region of its pickup point to
+    /// that of its drop-off point, with capacity for its measured weight, so the route's
+    /// carrier can pick it up.
+    ///
+    /// 0. `[w]` Shipment account
+    /// 1. `[s]` Sender
+    /// 2. `[]` CarrierRoute account
+    ReferenceRoute,
+}
+
+impl DLUInstruction {
+    /// Decodes an instruction in the current layout.
+    ///
+    /// Layouts are frozen once deployed: reordering variants or changing their fields needs a
+    /// new `INSTRUCTION_VERSION`, with the previous layout frozen and still decoded here.
+    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+        match input.split_first() {
+            Some((&INSTRUCTION_VERSION, encoded)) => {
+                Self::try_from_slice(encoded).map_err(|_| DLUError::InvalidInstruction.into())
+            }
+            _ => Err(DLUError::InvalidInstruction.into()),
+        }
+    }
+
+    /// Encodes the instruction in the current layout.
+    pub fn pack(&self) -> Result<Vec<u8>, DLUError> {
+        let mut data = vec![INSTRUCTION_VERSION];
+        self.serialize(&mut data).map_err(|_| DLUError::SerializationFailed)?;
+        Ok(data)
+    }
+}
@@ -0,0 +1,208 @@
+use solana_program::{
+    hash::hash,
+    instruction::{AccountMeta, Instruction},
+    pubkey,
+    pubkey::Pubkey,
+    system_program,
+};
+use solana_program::borsh::{BorshSerialize, BorshDeserialize};
+use crate::addressing::{
+    derive_config_address, derive_nft_escrow_address, derive_stats_address, derive_vault_address,
+    derive_vault_registry_address, EntityType,
+};
+use crate::errors::DLUError;
+use crate::instruction::DLUInstruction;
+use crate::time;
+
+/// Id of the Clockwork thread program that runs scheduled cranks.
+pub const THREAD_PROGRAM_ID: Pubkey = pubkey!("CLoCKyJ6DXBJqqu2VWx9RLbgnwwR6BMHHuyasVmfMzBh");
+pub const THREAD_SEED: &[u8] = b"thread";  // Thread PDAs are derived by the thread program.
+
+/// Lamports a new expiry thread is funded with to pay for its single execution.
+pub const THREAD_FUNDING: u64 = 10_000_000;
+
+/// Most entities one `BatchExpire` expires, so the batch and its refund accounts fit in a
+/// transaction.
+pub const MAX_EXPIRY_BATCH: usize = 8;
+
+/// Accounts of an overdue entity that `CrankExpire` needs.
+pub struct CrankAccounts {
+    pub entity_type: EntityType,
+    pub entity: Pubkey,
+    pub payment_mint: Pubkey,  // Picks the vault the entity's escrow sits in.
+    pub seller_or_sender_account: Pubkey,
+    pub buyer_or_carrier_account: Pubkey,
+    pub keeper_account: Pubkey,  // Token account receiving the bounty.
+    pub seller_nft_account: Option<Pubkey>,  // Where an NFT offer returns its NFT to.
+}
+
+/// An overdue entity in a `BatchExpire`, with the token accounts of its parties.
+pub struct ExpiryBatchEntry {
+    pub id: u64,
+    pub entity: Pubkey,
+    pub seller_or_sender_account: Pubkey,
+    pub buyer_or_carrier_account: Pubkey,
+}
+
+/// Returns the first unix timestamp at which an entity with the given deadline (its
+/// meeting or drop-off time) can be expired.
+pub fn crank_at(deadline: i64) -> i64 {
+    time::expiry_time(deadline).saturating_add(1)
+}
+
+/// Builds the `CrankExpire` instruction for an overdue entity. It needs no signer, so a
+/// keeper bot or a Clockwork thread can submit it as is.
+pub fn crank_expire_instruction(program_id: &Pubkey, accounts: &CrankAccounts) -> Result<Instruction, DLUError> {
+    let (vault, _) = derive_vault_address(program_id, &accounts.payment_mint);
+    let (registry, _) = derive_vault_registry_address(program_id);
+    let (config, _) = derive_config_address(program_id);
+    let (stats, _) = derive_stats_address(program_id);
+    let data = DLUInstruction::CrankExpire { entity_type: accounts.entity_type }.pack()?;
+
+    let mut metas = vec![
+        AccountMeta::new(accounts.entity, false),
+        AccountMeta::new(vault, false),
+        AccountMeta::new_readonly(registry, false),
+        AccountMeta::new(accounts.seller_or_sender_account, false),
+        AccountMeta::new(accounts.buyer_or_carrier_account, false),
+        AccountMeta::new(accounts.keeper_account, false),
+        AccountMeta::new_readonly(config, false),
+        AccountMeta::new(stats, false),
+    ];
+    if let Some(seller_nft_account) = accounts.seller_nft_account {
+        let (nft_escrow, _) = derive_nft_escrow_address(program_id, &accounts.entity);
+        metas.extend([AccountMeta::new(nft_escrow, false), AccountMeta::new(seller_nft_account, false)]);
+    }
+
+    Ok(Instruction { program_id: *program_id, accounts: metas, data })
+}
+
+/// Builds the `BatchExpire` instruction for overdue entities of one type whose escrow sits
+/// in the vault of `payment_mint`, paying every bounty to `keeper_account`, a token account
+/// of that mint. Like `CrankExpire` it needs no signer.
+pub fn batch_expire_instruction(
+    program_id: &Pubkey,
+    entity_type: EntityType,
+    payment_mint: &Pubkey,
+    keeper_account: &Pubkey,
+    entries: &[ExpiryBatchEntry],
+) -> Result<Instruction, DLUError> {
+    let (vault, _) = derive_vault_address(program_id, payment_mint);
+    let (registry, _) = derive_vault_registry_address(program_id);
+    let (config, _) = derive_config_address(program_id);
+    let (stats, _) = derive_stats_address(program_id);
+    let ids = entries.iter().map(|entry| entry.id).collect();
+    let data = DLUInstruction::BatchExpire { entity_type, ids }.pack()?;
+
+    let mut metas = vec![
+        AccountMeta::new(*keeper_account, false),
+        AccountMeta::new(vault, false),
+        AccountMeta::new_readonly(registry, false),
+        AccountMeta::new_readonly(config, false),
+        AccountMeta::new(stats, false),
+    ];
+    for entry in entries {
+        metas.extend([
+            AccountMeta::new(entry.entity, false),
+            AccountMeta::new(entry.seller_or_sender_account, false),
+            AccountMeta::new(entry.buyer_or_carrier_account, false),
+        ]);
+    }
+
+    Ok(Instruction { program_id: *program_id, accounts: metas, data })
+}
+
+/// Derives the thread an authority schedules the expiry of an entity on.
+pub fn derive_expiry_thread_address(authority: &Pubkey, entity: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[THREAD_SEED, authority.as_ref(), entity.as_ref()], &THREAD_PROGRAM_ID)
+}
+
+/// Builds the Clockwork `thread_create` instruction that submits `CrankExpire` for an
+/// entity as soon as its grace period after `deadline` has passed.
+///
+/// The thread is keyed by the entity, so every entity gets at most one expiry thread per
+/// authority. A thread firing for an entity that was completed or failed in the meantime
+/// simply fails its crank and stops.
+pub fn create_expiry_thread_instruction(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+    accounts: &CrankAccounts,
+    deadline: i64,
+) -> Result<Instruction, DLUError> {
+    let crank = crank_expire_instruction(program_id, accounts)?;
+    let (thread, _) = derive_expiry_thread_address(authority, &accounts.entity);
+
+    let args = ThreadCreateArgs {
+        amount: THREAD_FUNDING,
+        id: accounts.entity.to_bytes().to_vec(),
+        instructions: vec![crank.into()],
+        trigger: Trigger::Timestamp { unix_ts: crank_at(deadline) },
+    };
+    let mut data = hash(b"global:thread_create").to_bytes()[..8].to_vec();
+    args.serialize(&mut data).map_err(|_| DLUError::SerializationFailed)?;
+
+    Ok(Instruction {
+        program_id: THREAD_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(thread, false),
+        ],
+        data,
+    })
+}
+
+// Borsh mirrors of the Clockwork thread program's instruction arguments.
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct ThreadCreateArgs {
+    amount: u64,
+    id: Vec<u8>,
+    instructions: Vec<SerializableInstruction>,
+    trigger: Trigger,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct SerializableInstruction {
+    program_id: Pubkey,
+    accounts: Vec<SerializableAccount>,
+    data: Vec<u8>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct SerializableAccount {
+    pubkey: Pubkey,
+    is_signer: bool,
+    is_writable: bool,
+}
+
+#[allow(dead_code)]
+#[derive(BorshSerialize, BorshDeserialize)]
+enum Trigger {
+    Account { address: Pubkey, offset: u64, size: u64 },
+    Cron { schedule: String, skippable: bool },
+    Now,
+    Slot { slot: u64 },
+    Epoch { epoch: u64 },
+    Timestamp { unix_ts: i64 },
+}
+
+impl From<Instruction> for SerializableInstruction {
+    fn from(instruction: Instruction) -> Self {
+        SerializableInstruction {
+            program_id: instruction.program_id,
+            accounts: instruction
+                .accounts
+                .into_iter()
+                .map(|meta| SerializableAccount {
+                    pubkey: meta.pubkey,
+                    is_signer: meta.is_signer,
+                    is_writable: meta.is_writable,
+                })
+                .collect(),
+            data: instruction.data,
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use solana_program::pubkey::Pubkey;
+use solana_program::borsh::{BorshSerialize, BorshDeserialize};
+use crate::errors::DLUError;
+use crate::user::{Role, User};
+
+/// Users ranked on each leaderboard.
+pub const LEADERBOARD_ENTRIES: usize = 100;
+
+/// Ranking a leaderboard keeps.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, PartialEq, Debug)]
+pub enum LeaderboardKind {
+    SellerVolume,   // Sellers by the payments of their completed deals.
+    SellerStreak,   // Sellers by deals completed in a row.
+    CarrierVolume,  // Carriers by the payments of their completed shipments.
+    CarrierStreak,  // Carriers by shipments completed in a row.
+}
+
+/// Leaderboards ranking sellers, updated when their deals complete.
+pub const SELLER_LEADERBOARDS: [LeaderboardKind; 2] = [LeaderboardKind::SellerVolume, LeaderboardKind::SellerStreak];
+/// Leaderboards ranking carriers, updated when their shipments complete or fail.
+pub const CARRIER_LEADERBOARDS: [LeaderboardKind; 2] = [LeaderboardKind::CarrierVolume, LeaderboardKind::CarrierStreak];
+
+impl LeaderboardKind {
+    /// Returns the score a user is ranked by on this leaderboard.
+    pub fn score(&self, user: &User) -> u64 {
+        match self {
+            LeaderboardKind::SellerVolume => user.completed_volume(Role::Seller),
+            LeaderboardKind::SellerStreak => user.success_streak(Role::Seller) as u64,
+            LeaderboardKind::CarrierVolume => user.completed_volume(Role::Carrier),
+            LeaderboardKind::CarrierStreak => user.success_streak(Role::Carrier) as u64,
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, PartialEq, Debug)]
+pub struct LeaderboardEntry {
+    pub user: Pubkey,
+    pub score: u64,
+}
+
+/// Top `LEADERBOARD_ENTRIES` users of a ranking, best first.
+///
+/// Leaderboards are updated opportunistically: completions rank their seller or carrier
+/// when the client passes the leaderboards along, so a user's entry holds the score of
+/// their last ranked completion. Users tied on score keep the order they reached it in.
+///
+/// Lives at the PDA `["leaderboard", [kind]]`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default)]
+pub struct Leaderboard {
+    pub entries: Vec<LeaderboardEntry>,
+}
+
+impl Leaderboard {
+    /// Records `user`'s new `score`, returning their rank from 0, or None once they no
+    /// longer make the board. A zero score takes the user off the board.
+    pub fn rank(&mut self, user: Pubkey, score: u64) -> Option<usize> {
+        let current = self.entries.iter().position(|entry| entry.user == user);
+        if let Some(rank) = current {
+            if self.entries[rank].score == score {
+                return Some(rank);
+            }
+            self.entries.remove(rank);
+        }
+        if score == 0 {
+            return None;
+        }
+
+        let rank = self.entries.iter().position(|entry| entry.score < score).unwrap_or(self.entries.len());
+        if rank >= LEADERBOARD_ENTRIES {
+            return None;
+        }
+        self.entries.insert(rank, LeaderboardEntry { user, score });
+        self.entries.truncate(LEADERBOARD_ENTRIES);
+        Some(rank)
+    }
+
+    /// Serializes the leaderboard into a vector of bytes.
+    pub fn serialize(&self) -> Result<Vec<u8>, DLUError> {
+        self.try_to_vec().map_err(|_| DLUError::SerializationFailed)
+    }
+
+    /// Deserializes a leaderboard from a slice of bytes.
+    pub fn deserialize(input: &mut &[u8]) -> Result<Self, DLUError> {
+        // Accounts are sized for a full board, so the tail may be zero padding.
+        <Self as BorshDeserialize>::deserialize(input).map_err(|_| DLUError::DeserializationFailed)
+    }
+}
@@ -10,6 +10,8 @@ use crate::processor::Processor;
 
 declare_id!("xxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxx");
 
+// The real instruction set is dispatched by `processor`; clients are generated from the
+// hand-maintained IDL at idl/luda.json, which mirrors `instruction::DLUInstruction`.
 #[program]
 pub mod luda {
     use super::*;
@@ -22,16 +24,63 @@ pub mod luda {
 pub struct Initialize {}
 
 pub mod user;         // User profiles, status, etc.
+pub mod profile_summary; // Fixed-layout reputation summaries for wallets
+pub mod deal;         // Deal state machine shared by offers and requests
 pub mod offer;        // Offers posted by sellers
 pub mod request;      // Requests posted by buyers
+pub mod holdback;     // Warranty holdbacks kept in escrow after completion
+pub mod chargeback;   // Chargeback windows over sellers' insurance after completion
+pub mod quote;        // Sellers' quotes on requests
+pub mod location;     // Validated meeting, pickup and drop-off points
+pub mod validation;   // Length and character checks of instruction strings
+pub mod region;       // Geohash region indices of recent listings
+pub mod messaging;    // Encrypted message logs between deal parties
 pub mod shipment;     // Shipment details and tracking
+pub mod pricing;      // Suggested shipment payments by distance and weight
+pub mod route;        // Recurring carrier routes and the indices senders find them in
 pub mod dlu_token;    // DLU token related operations
 pub mod dlu_wallet;   // DLU wallet operations
 pub mod escrow;       // Escrow operations
-pub mod onetimekeys;  // Generation and management of one-time keys
+pub mod bond;         // Bonds posted on fail claims
+pub mod stake;        // Carrier stakes gating high-value shipments
+pub mod arbiter;      // Staked arbiters drawn to rule on disputes
+pub mod appeal;       // Panels re-ruling appealed arbitrations
+pub mod delegation;   // Session keys acting for users on specific deals
+pub mod organization; // Business identities with member wallets
+pub mod multisig;     // m-of-n signer sets governing shared users
+pub mod moderation;   // Governance bans of misbehaving users
+pub mod attestation;  // KYC attestations gating high-value deals
+pub mod referral;     // Fee shares earned by referrers
+pub mod promo;        // Hashed discount codes on offers
+pub mod stream;       // Payments vesting over long-running services
+pub mod watchlist;    // Followed users and watched offers for notification feeds
+pub mod leaderboard;  // Top sellers and carriers by volume and streak
+pub mod audit;        // Status transitions deals record for auditors
+pub mod compression;  // Offers listed as leaves of concurrent Merkle trees
+pub mod archive;      // Merkle roots of finished entities closed for rent
+pub mod review;       // Ratings and reviews of completed deals
+pub mod badge;        // Soulbound status badges
+pub mod onetimekeys;  // Hashed one-time keys and their verification
+pub mod emergency;    // Timelocked governance unwinds of stuck escrow
+pub mod timelock;     // Queue of timelocked governance config updates
+pub mod dao;          // Token-weighted voting on config updates
+pub mod penalty_pool; // Penalties streamed to carrier stakers
+pub mod emission;     // Capped, decaying DLU rewards for completed deals
+pub mod vault_registry; // Canonical escrow vaults of the payment mints
 pub mod addressing;   // Entities addressing
+pub mod time;         // Clock access and expiry math
+pub mod keeper;       // Scheduled expiry cranks for keeper bots and Clockwork threads
+pub mod constants;    // Seeds, sizes, and limits shared with clients
+pub mod safe_math;    // Checked arithmetic for escrow amounts
+pub mod config;       // Program-wide settings controlled by governance
+pub mod stats;        // Aggregate marketplace counters
+pub mod migrations;   // Account layout versions and upgrades
+pub mod discriminator; // Kinds of data program accounts hold
+pub mod instruction;  // Instruction definitions and decoding
+pub mod resolver;     // Positional account parsing and validation
 pub mod processor;    // Core processing logic
-pub mod error;        // Error handling
+pub mod events;       // Events logged for indexers and auditors
+pub mod errors;       // Error handling
 
 entrypoint!(process_instruction);
 
@@ -0,0 +1,122 @@
+use solana_program::hash::hashv;
+use solana_program::borsh::{BorshSerialize, BorshDeserialize};
+use crate::errors::DLUError;
+use crate::region::REGION_GEOHASH_LEN;
+
+/// Longest town name a location can hold, in bytes.
+pub const MAX_TOWN_LEN: usize = 64;
+/// Longest street address a location can hold, in bytes.
+pub const MAX_ADDRESS_LEN: usize = 128;
+/// Longest geohash a location can hold. Twelve characters pin a point to a few centimeters.
+pub const MAX_GEOHASH_LEN: usize = 12;
+
+/// Officially assigned ISO 3166-1 alpha-2 codes, in alphabetical order.
+const ISO_COUNTRY_CODES: &[u8] = b"\
+    ADAEAFAGAIALAMAOAQARASATAUAWAXAZBABBBDBEBFBGBHBIBJBLBMBNBOBQBRBSBTBVBWBYBZ\
+    CACCCDCFCGCHCICKCLCMCNCOCRCUCVCWCXCYCZDEDJDKDMDODZECEEEGEHERESETFIFJFKFMFOFR\
+    GAGBGDGEGFGGGHGIGLGMGNGPGQGRGSGTGUGWGYHKHMHNHRHTHUIDIEILIMINIOIQIRISITJEJMJOJP\
+    KEKGKHKIKMKNKPKRKWKYKZLALBLCLILKLRLSLTLULVLYMAMCMDMEMFMGMHMKMLMMMNMOMPMQMRMSMT\
+    MUMVMWMXMYMZNANCNENFNGNINLNONPNRNUNZOMPAPEPFPGPHPKPLPMPNPRPSPTPWPYQARERORSRURW\
+    SASBSCSDSESGSHSISJSKSLSMSNSOSRSSSTSVSXSYSZTCTDTFTGTHTJTKTLTMTNTOTRTTTVTWTZUAUG\
+    UMUSUYUZVAVCVEVGVIVNVUWFWSYEYTZAZMZW";
+
+/// Characters of the geohash base32 alphabet.
+const GEOHASH_ALPHABET: &[u8] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+/// Where a deal's parties meet or a shipment is picked up and dropped off.
+///
+/// The country is a two-letter ISO 3166-1 code so clients can filter listings by region
+/// without guessing at spellings. Locations arrive in instruction data, so the program
+/// calls `validate` on every location it stores.
+#[derive(BorshSerialize, BorshDeserialize, Clone, PartialEq, Debug)]
+pub struct Location {
+    country: [u8; 2],  // ISO 3166-1 alpha-2 code, upper case.
+    town: String,
+    address: String,
+    geohash: Option<String>,  // Lower-case base32 geohash of the point, at any precision.
+}
+
+impl Location {
+    /// Creates a location from its parts. The country code may be given in either case.
+    pub fn new(country: &str, town: String, address: String) -> Result<Self, DLUError> {
+        let country = country.to_ascii_uppercase();
+        let country = country.as_bytes().try_into().map_err(|_| DLUError::InvalidCountryCode)?;
+        let location = Location { country, town, address, geohash: None };
+        location.validate()?;
+        Ok(location)
+    }
+
+    /// Pins the location to a geohash, normalized to lower case.
+    pub fn with_geohash(mut self, geohash: &str) -> Result<Self, DLUError> {
+        self.geohash = Some(geohash.to_ascii_lowercase());
+        self.validate()?;
+        Ok(self)
+    }
+
+    /// Checks the country code and the bounds of the other parts.
+    pub fn validate(&self) -> Result<(), DLUError> {
+        if !is_country_code(&self.country) {
+            return Err(DLUError::InvalidCountryCode);
+        }
+        if self.town.len() > MAX_TOWN_LEN || self.address.len() > MAX_ADDRESS_LEN {
+            return Err(DLUError::LocationFieldTooLong);
+        }
+        if let Some(geohash) = &self.geohash {
+            let valid = (1..=MAX_GEOHASH_LEN).contains(&geohash.len())
+                && geohash.bytes().all(|c| GEOHASH_ALPHABET.contains(&c));
+            if !valid {
+                return Err(DLUError::InvalidGeohash);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the hash a lister commits to when hiding the location. The salt keeps anyone
+    /// from recovering a short address by hashing candidate addresses in the same town.
+    pub fn commitment(&self, salt: &[u8; 32]) -> Result<[u8; 32], DLUError> {
+        let encoded = self.try_to_vec().map_err(|_| DLUError::SerializationFailed)?;
+        Ok(hashv(&[&encoded, salt]).to_bytes())
+    }
+
+    /// Returns the ISO 3166-1 alpha-2 country code.
+    pub fn country(&self) -> &str {
+        std::str::from_utf8(&self.country).unwrap_or_default()
+    }
+
+    /// Returns the town.
+    pub fn town(&self) -> &str {
+        &self.town
+    }
+
+    /// Returns the street address.
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// Returns the geohash of the point, if the location was pinned to one.
+    pub fn geohash(&self) -> Option<&str> {
+        self.geohash.as_deref()
+    }
+
+    /// Returns the geohash prefix of the region the location is indexed in, if it has a
+    /// geohash at least that precise.
+    pub fn region(&self) -> Option<[u8; REGION_GEOHASH_LEN]> {
+        self.geohash.as_ref()?.as_bytes().get(..REGION_GEOHASH_LEN)?.try_into().ok()
+    }
+
+    /// Returns true when both locations are in the same town of the same country.
+    pub fn same_region(&self, other: &Location) -> bool {
+        self.country == other.country && self.town.eq_ignore_ascii_case(&other.town)
+    }
+}
+
+/// Returns true for officially assigned ISO 3166-1 alpha-2 codes.
+pub fn is_country_code(code: &[u8; 2]) -> bool {
+    ISO_COUNTRY_CODES.chunks_exact(2).any(|assigned| assigned == code)
+}
+
+/// Returns true for a lower-case geohash prefix naming a region, as `Location::region`
+/// returns them.
+pub fn is_region_prefix(prefix: &[u8; REGION_GEOHASH_LEN]) -> bool {
+    prefix.iter().all(|c| GEOHASH_ALPHABET.contains(c))
+}
@@ -0,0 +1,71 @@
+use solana_program::pubkey::Pubkey;
+use solana_program::borsh::{BorshSerialize, BorshDeserialize};
+use crate::errors::DLUError;
+
+/// Longest encrypted payload a message can carry, in bytes.
+pub const MAX_MESSAGE_LEN: usize = 256;
+/// Messages a log keeps before the newest start replacing the oldest.
+pub const MAX_MESSAGES: usize = 16;
+
+/// A message one party of a deal or shipment sent the others.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct Message {
+    pub sender: Pubkey,
+    pub sent_at: i64,
+    pub payload: Vec<u8>,  // Encrypted by the sender's client to the recipients' keys.
+}
+
+/// Messages the parties of an accepted deal or shipment exchange to coordinate the
+/// meeting or handover, so they don't need to swap contacts off the platform.
+///
+/// The program never sees plaintext: clients encrypt each payload to the counterparty's
+/// key before sending. The log is a ring of the `MAX_MESSAGES` most recent messages.
+///
+/// Lives at the PDA `["messages", entity_pubkey]`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default)]
+pub struct MessageLog {
+    pub entity: Pubkey,
+    pub next: u16,  // Slot the next message overwrites once the ring is full.
+    pub messages: Vec<Message>,
+}
+
+impl MessageLog {
+    /// Appends a message from one of the `parties` of the entity, replacing the oldest one
+    /// once the log is full.
+    pub fn send(
+        &mut self,
+        entity: Pubkey,
+        parties: &[Pubkey],
+        sender: Pubkey,
+        payload: Vec<u8>,
+        now: i64,
+    ) -> Result<(), DLUError> {
+        if !parties.contains(&sender) {
+            return Err(DLUError::NotDealParty);
+        }
+        if payload.is_empty() || payload.len() > MAX_MESSAGE_LEN {
+            return Err(DLUError::InvalidMessageLength);
+        }
+
+        self.entity = entity;
+        let message = Message { sender, sent_at: now, payload };
+        if self.messages.len() < MAX_MESSAGES {
+            self.messages.push(message);
+        } else {
+            self.messages[self.next as usize] = message;
+            self.next = ((self.next as usize + 1) % MAX_MESSAGES) as u16;
+        }
+        Ok(())
+    }
+
+    /// Serializes the log into a vector of bytes.
+    pub fn serialize(&self) -> Result<Vec<u8>, DLUError> {
+        self.try_to_vec().map_err(|_| DLUError::SerializationFailed)
+    }
+
+    /// Deserializes a log from a slice of bytes.
+    pub fn deserialize(input: &mut &[u8]) -> Result<Self, DLUError> {
+        // Accounts are sized for a full ring, so the tail may be zero padding.
+        <Self as BorshDeserialize>::deserialize(input).map_err(|_| DLUError::DeserializationFailed)
+    }
+}
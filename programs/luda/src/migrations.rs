@@ -0,0 +1,167 @@
+use solana_program::borsh::{BorshDeserialize, BorshSerialize};
+use crate::deal::{Deal, DealRole, DealStatus};
+use crate::discriminator::AccountKind;
+use crate::errors::DLUError;
+use crate::shipment::{Shipment, ShipmentStatus};
+use crate::user::User;
+
+/// Layout version written in front of User accounts.
+pub const USER_VERSION: u8 = 1;
+/// Layout version written in front of Offer and Request accounts, including their escrow state.
+pub const DEAL_VERSION: u8 = 1;
+/// Layout version written in front of Shipment accounts, including their escrow state.
+pub const SHIPMENT_VERSION: u8 = 1;
+
+/// An account type whose payload starts with a one-byte layout version.
+///
+/// Accounts are always written with the current `VERSION`. Data written by an older
+/// program is decoded by `migrate` and rewritten in the current layout the next time the
+/// account is saved, so accounts upgrade on first touch after a program upgrade without
+/// a separate migration pass. When a layout changes, bump its version, keep the previous
+/// layout here as a private `...V<n>` struct, and add an arm to `migrate` converting it.
+pub trait Versioned: BorshSerialize + BorshDeserialize {
+    /// Layout version written by this program.
+    const VERSION: u8;
+
+    /// Decodes data written in the layout of an older `version`.
+    fn migrate(version: u8, input: &mut &[u8]) -> Result<Self, DLUError>;
+}
+
+impl Versioned for User {
+    const VERSION: u8 = USER_VERSION;
+
+    fn migrate(_version: u8, _input: &mut &[u8]) -> Result<Self, DLUError> {
+        Err(DLUError::UnsupportedVersion)
+    }
+}
+
+impl<R: DealRole> Versioned for Deal<R> {
+    const VERSION: u8 = DEAL_VERSION;
+
+    fn migrate(_version: u8, _input: &mut &[u8]) -> Result<Self, DLUError> {
+        Err(DLUError::UnsupportedVersion)
+    }
+}
+
+impl Versioned for Shipment {
+    const VERSION: u8 = SHIPMENT_VERSION;
+
+    fn migrate(_version: u8, _input: &mut &[u8]) -> Result<Self, DLUError> {
+        Err(DLUError::UnsupportedVersion)
+    }
+}
+
+/// Serializes a value behind its current layout version.
+pub fn pack<T: Versioned>(value: &T) -> Result<Vec<u8>, DLUError> {
+    let mut data = vec![T::VERSION];
+    value.serialize(&mut data).map_err(|_| DLUError::SerializationFailed)?;
+    Ok(data)
+}
+
+/// Deserializes a value, upgrading it from an older layout if needed.
+///
+/// `input` is left past the value; `decode_account` fails when any of an account's
+/// payload is left over.
+pub fn unpack<T: Versioned>(input: &mut &[u8]) -> Result<T, DLUError> {
+    let (&version, mut rest) = input.split_first().ok_or(DLUError::DeserializationFailed)?;
+    let value = if version == T::VERSION {
+        T::deserialize(&mut rest).map_err(|_| DLUError::DeserializationFailed)?
+    } else if version < T::VERSION {
+        T::migrate(version, &mut rest)?
+    } else {
+        return Err(DLUError::UnsupportedVersion);
+    };
+    *input = rest;
+    Ok(value)
+}
+
+/// Length in bytes of the `AccountKind` every program account's data starts with.
+pub const DISCRIMINATOR_LEN: usize = 1;
+
+/// Length in bytes of the little-endian `u32` behind the account kind. It counts the bytes
+/// of the payload behind it, so a value ending in zero bytes is never mistaken for the
+/// zeroed space after it.
+pub const LENGTH_PREFIX_LEN: usize = 4;
+
+/// Length in bytes of the account kind and length prefix in front of every payload.
+pub const ACCOUNT_HEADER_LEN: usize = DISCRIMINATOR_LEN + LENGTH_PREFIX_LEN;
+
+/// Returns the space an account needs to hold a `payload_len`-byte payload behind its
+/// kind and length prefix.
+pub const fn account_space(payload_len: usize) -> usize {
+    ACCOUNT_HEADER_LEN + payload_len
+}
+
+/// Frames a payload behind its kind and length prefix, as accounts store it.
+pub fn frame(kind: AccountKind, payload: &[u8]) -> Result<Vec<u8>, DLUError> {
+    let len = u32::try_from(payload.len()).map_err(|_| DLUError::SerializationFailed)?;
+    let mut data = vec![kind as u8];
+    data.extend_from_slice(&len.to_le_bytes());
+    data.extend_from_slice(payload);
+    Ok(data)
+}
+
+/// Returns true if the program never wrote the account's data, or wiped it when closing
+/// the account.
+pub fn is_unwritten(data: &[u8]) -> bool {
+    data.first() == Some(&(AccountKind::Uninitialized as u8))
+}
+
+/// Returns exactly the bytes an account's length prefix counts, or `None` when the data is
+/// too short for the header or for what it counts. An account never written has a zero
+/// prefix, and so an empty payload.
+pub fn payload_of(data: &[u8]) -> Option<&[u8]> {
+    let prefix = data.get(DISCRIMINATOR_LEN..ACCOUNT_HEADER_LEN)?;
+    let len = u32::from_le_bytes(prefix.try_into().ok()?) as usize;
+    data.get(ACCOUNT_HEADER_LEN..ACCOUNT_HEADER_LEN.checked_add(len)?)
+}
+
+/// Decodes the payload of an account of `kind` with `decode`, failing when the account
+/// holds another kind or when any of the payload is left over. An account never written
+/// is decoded from its zeroed space instead, which the layouts sized for full lists read as
+/// empty.
+pub fn decode_account<T, E: From<DLUError>>(
+    data: &[u8],
+    kind: AccountKind,
+    decode: impl FnOnce(&mut &[u8]) -> Result<T, E>,
+) -> Result<T, E> {
+    let mut payload = payload_of(data).ok_or(DLUError::DeserializationFailed)?;
+    if is_unwritten(data) && payload.is_empty() {
+        return decode(&mut &data[ACCOUNT_HEADER_LEN..]);
+    }
+    if data[0] != kind as u8 {
+        return Err(DLUError::AccountKindMismatch.into());
+    }
+    let value = decode(&mut payload)?;
+    if !payload.is_empty() {
+        return Err(DLUError::DeserializationFailed.into());
+    }
+    Ok(value)
+}
+
+/// Returns the layout version a payload was written with, if it has any.
+pub fn version_of(payload: &[u8]) -> Option<u8> {
+    payload.first().copied()
+}
+
+/// Returns the status of the deal an account holds without decoding the rest of it. Every
+/// deal layout starts with the id and the status.
+pub fn deal_status_of(data: &[u8]) -> Option<DealStatus> {
+    let data = payload_of(data)?;
+    if version_of(data)? == 0 {
+        return None;
+    }
+    let status = data.get(1 + 8..1 + 8 + 1)?;
+    DealStatus::try_from_slice(status).ok()
+}
+
+/// Returns the status of the shipment an account holds, which every shipment layout also
+/// keeps right behind the id.
+pub fn shipment_status_of(data: &[u8]) -> Option<ShipmentStatus> {
+    let data = payload_of(data)?;
+    if version_of(data)? == 0 {
+        return None;
+    }
+    let status = data.get(1 + 8..1 + 8 + 1)?;
+    ShipmentStatus::try_from_slice(status).ok()
+}
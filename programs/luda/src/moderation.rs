@@ -0,0 +1,23 @@
+use solana_program::pubkey::Pubkey;
+use crate::config::Config;
+use crate::errors::DLUError;
+use crate::user::{User, USER_BANNED};
+
+/// Bans or unbans a user on behalf of governance.
+///
+/// A ban only bars the user from new listings and acceptances. Deals they are already in
+/// can still be completed, failed or expired, and their wallet can still be withdrawn, so
+/// a ban never traps counterparties' or the user's own funds.
+pub fn set_banned(config: &Config, authority: &Pubkey, user: &mut User, banned: bool) -> Result<(), DLUError> {
+    config.check_governance(authority)?;
+    user.set_flag(USER_BANNED, banned);
+    Ok(())
+}
+
+/// Rejects banned users from listing and accepting.
+pub fn check_not_banned(user: &User) -> Result<(), DLUError> {
+    if user.has_flag(USER_BANNED) {
+        return Err(DLUError::UserBanned);
+    }
+    Ok(())
+}
@@ -0,0 +1,75 @@
+use solana_program::pubkey::Pubkey;
+use solana_program::borsh::{BorshSerialize, BorshDeserialize};
+use crate::errors::DLUError;
+
+/// Most signers a multisig can have.
+pub const MAX_MULTISIG_SIGNERS: usize = 8;
+
+/// m-of-n signer set governing a shared user's high-value actions.
+///
+/// Once a user has a multisig, listings paying at least `high_value` and wallet
+/// withdrawals of at least `high_value` need `threshold` distinct signatures from
+/// `signers`, the acting signer included. Smaller actions only need the usual signature,
+/// so day-to-day trading stays cheap. Changing the multisig always needs the threshold.
+///
+/// Lives at the PDA `["multisig", owner_pubkey]`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct Multisig {
+    pub owner: Pubkey,  // Key of the governed user account.
+    pub threshold: u8,
+    pub signers: Vec<Pubkey>,
+    pub high_value: u64,  // Amount from which actions need the threshold.
+}
+
+impl Multisig {
+    /// Creates a multisig, failing unless `threshold` distinct `signers` can meet it.
+    pub fn new(owner: Pubkey, threshold: u8, signers: Vec<Pubkey>, high_value: u64) -> Result<Self, DLUError> {
+        let mut multisig = Multisig { owner, threshold: 0, signers: Vec::new(), high_value: 0 };
+        multisig.set(threshold, signers, high_value)?;
+        Ok(multisig)
+    }
+
+    /// Replaces the threshold, signer set and high-value amount.
+    pub fn set(&mut self, threshold: u8, signers: Vec<Pubkey>, high_value: u64) -> Result<(), DLUError> {
+        let distinct = signers.iter().enumerate().all(|(i, signer)| !signers[..i].contains(signer));
+        let valid = distinct
+            && signers.len() <= MAX_MULTISIG_SIGNERS
+            && (1..=signers.len()).contains(&(threshold as usize));
+        if !valid {
+            return Err(DLUError::InvalidMultisig);
+        }
+        self.threshold = threshold;
+        self.signers = signers;
+        self.high_value = high_value;
+        Ok(())
+    }
+
+    /// Checks that the distinct `signatures` from the signer set reach the threshold.
+    pub fn check_threshold(&self, signatures: &[Pubkey]) -> Result<(), DLUError> {
+        let signed = self.signers.iter().filter(|signer| signatures.contains(signer)).count();
+        if signed < self.threshold as usize {
+            return Err(DLUError::NotEnoughCosigners);
+        }
+        Ok(())
+    }
+
+    /// Checks the `signatures` on an action moving `amount`, which only need the threshold
+    /// from `high_value` on.
+    pub fn check(&self, signatures: &[Pubkey], amount: u64) -> Result<(), DLUError> {
+        if amount < self.high_value {
+            return Ok(());
+        }
+        self.check_threshold(signatures)
+    }
+
+    /// Serializes the multisig into a vector of bytes.
+    pub fn serialize(&self) -> Result<Vec<u8>, DLUError> {
+        self.try_to_vec().map_err(|_| DLUError::SerializationFailed)
+    }
+
+    /// Deserializes a multisig from a slice of bytes.
+    pub fn deserialize(input: &mut &[u8]) -> Result<Self, DLUError> {
+        // Accounts are sized for a full signer set, so the tail may be zero padding.
+        <Self as BorshDeserialize>::deserialize(input).map_err(|_| DLUError::DeserializationFailed)
+    }
+}
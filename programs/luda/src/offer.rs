@@ -1,297 +1,522 @@
-use crate::user::User; 
-use crate::onetimekeys::Onetimekeys;
-use crate::dlu_wallet::Wallet;
-use crate::escrow::Escrow; 
-use chrono::{DateTime, Utc}; 
+use std::ops::{Deref, DerefMut};
+use solana_program::{account_info::AccountInfo, pubkey::Pubkey};
 use solana_program::borsh::{BorshSerialize, BorshDeserialize};
+use crate::compression::CompressedOffer;
+use crate::deal::{Deal, SellerListed};
+use crate::config::Config;
+use crate::dlu_token::DLUToken;
+use crate::errors::DLUError;
+use crate::holdback::HoldbackTerms;
+use crate::request::Request;
+use crate::safe_math;
+use crate::stream::PaymentStream;
+use crate::time;
+use crate::user::{Role, User};
+
+pub use crate::deal::{DealStatus as OfferStatus, Location};
+
+/// Most price tiers an inventory offer can have.
+pub const MAX_PRICE_TIERS: usize = 4;
+
+/// Unit price of an inventory offer from `min_quantity` units on. Unused tiers are zero.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub struct PriceTier {
+    pub min_quantity: u32,
+    pub unit_price: u64,
+}
 
+/// Pads `tiers` into the fixed tier table of an inventory offer.
+pub fn price_tiers(tiers: &[PriceTier]) -> Result<[PriceTier; MAX_PRICE_TIERS], DLUError> {
+    let mut table = [PriceTier::default(); MAX_PRICE_TIERS];
+    table.get_mut(..tiers.len()).ok_or(DLUError::InvalidPriceTiers)?.copy_from_slice(tiers);
+    Ok(table)
+}
 
-/// Represents an in-game location.
-pub struct Location {
-    country: String,
-    town: String,
-    address: String,
+/// What an offer sells.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub enum OfferKind {
+    /// Goods or a service described by the offer and handed over at the meeting.
+    #[default]
+    Described,
+    /// An SPL NFT of `mint`, held in the offer's NFT escrow from listing until the deal
+    /// completes and it goes to the buyer. Any other outcome returns it to the seller.
+    Nft { mint: Pubkey },
+    /// Goods swapped for goods at the meeting. There is no payment; both sides deposit the
+    /// `insurance`, which they get back once the swap completes.
+    Barter { insurance: u64 },
+    /// Up to `max_quantity` units of stock, priced per unit by the tier of the quantity the
+    /// buyer accepts, such as 1–9 units at one price and 10 or more at a lower one. It lists
+    /// without a payment; the seller's insurance covers the whole stock at its tier price,
+    /// and the accepted `quantity`, zero while listed, sets the payment.
+    Inventory { max_quantity: u32, tiers: [PriceTier; MAX_PRICE_TIERS], quantity: u32 },
+    /// A long-running service whose payment vests to the seller over the service period
+    /// starting at the meeting time, instead of all at completion.
+    Stream(PaymentStream),
 }
 
-/// Represents the current status of an offer.
-pub enum OfferStatus {
-    Listed,
-    Accepted,
-    Completed,
-    Failed,
-    Expired,
-    Canceled,
+impl OfferKind {
+    /// Returns the insurance both sides deposit for an offer of this kind paying `payment`.
+    fn insurance(self, payment: u64) -> Result<u64, DLUError> {
+        match self {
+            OfferKind::Barter { insurance } if payment == 0 && insurance > 0 => Ok(insurance),
+            OfferKind::Barter { .. } => Err(DLUError::InvalidBarterTerms),
+            OfferKind::Inventory { max_quantity, tiers, .. } if payment == 0 && Self::valid_tiers(&tiers, max_quantity) => {
+                Self::tier_total(&tiers, max_quantity)
+            }
+            OfferKind::Inventory { .. } => Err(DLUError::InvalidPriceTiers),
+            OfferKind::Stream(stream) if payment > 0 && stream.is_valid() => Ok(payment),
+            OfferKind::Stream(_) => Err(DLUError::InvalidStreamTerms),
+            OfferKind::Described | OfferKind::Nft { .. } => Ok(payment),
+        }
+    }
+
+    /// Returns true if the tiers start at one unit, rise in quantity up to `max_quantity`
+    /// with a price each, and leave only unused tiers after the last one.
+    fn valid_tiers(tiers: &[PriceTier; MAX_PRICE_TIERS], max_quantity: u32) -> bool {
+        let used = tiers.iter().take_while(|tier| tier.min_quantity > 0).count();
+        tiers[0].min_quantity == 1
+            && tiers[..used].iter().all(|tier| tier.unit_price > 0 && tier.min_quantity <= max_quantity)
+            && tiers[..used].windows(2).all(|pair| pair[0].min_quantity < pair[1].min_quantity)
+            && tiers[used..].iter().all(|tier| *tier == PriceTier::default())
+    }
+
+    /// Returns the price of `quantity` units at the unit price of their tier.
+    fn tier_total(tiers: &[PriceTier; MAX_PRICE_TIERS], quantity: u32) -> Result<u64, DLUError> {
+        let tier = tiers.iter()
+            .take_while(|tier| tier.min_quantity > 0)
+            .filter(|tier| tier.min_quantity <= quantity)
+            .last()
+            .ok_or(DLUError::InvalidQuantity)?;
+        safe_math::mul(tier.unit_price, u64::from(quantity))
+    }
 }
 
 /// Represents a single offer posted by a seller.
-pub struct Offer {
-    id: u64,
-    status: OfferStatus,
-    seller: User,
-    buyer: Option<User>,
-    meeting_point: Location,
-    meeting_datetime: DateTime<Utc>,
-    payment: u64,
-    insurance: u64,
-    goodsorservice_name: String,
-    goodsorservice_description: String,
-    seller_key: String,
-    buyer_key: String,
-    escrow_id: u64,
-}
+///
+/// The seller locks their insurance when listing; the buyer locks the payment plus their
+/// insurance when accepting. All state transitions live in `Deal`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct Offer(Deal<SellerListed>, OfferKind);
 
 impl Offer {
-    /// List a new offer.
+    /// List a new offer, optionally holding back part of the payment as a warranty.
     pub fn list_offer(
         id: u64,
-        seller: &mut User,
+        kind: OfferKind,
+        seller: &User,
         goodsorservice_name: String,
         goodsorservice_description: String,
         payment: u64,
+        holdback: Option<HoldbackTerms>,
         meeting_point: Location,
-        meeting_datetime: DateTime<Utc>,
-    ) -> Result<Self, &'static str> {
-        // Insurance is always equal to payment.
-        let insurance = payment;
-
-        // Check seller's balance for sufficient funds for insurance.
-        if seller.wallet.balance < insurance {
-            return Err("Insufficient funds for insurance.");
-        }
-
-        // Deduct insurance amount from seller's wallet.
-        seller.wallet.balance -= insurance;
-
-        // Lock insurance amount in escrow.
-        let escrow_id = Escrow::lock_funds(&seller.wallet, insurance)?;
-
-        Ok(Offer {
+        meeting_point_hash: Option<[u8; 32]>,
+        meeting_datetime: i64,
+        seller_account: &AccountInfo,
+        seller_authority_info: &AccountInfo,
+        vault_account: &AccountInfo,
+    ) -> Result<Self, DLUError> {
+        Deal::list(
             id,
-            status: OfferStatus::Listed,
-            seller: seller.clone(),
-            buyer: None,
+            seller,
+            goodsorservice_name,
+            goodsorservice_description,
+            payment,
+            kind.insurance(payment)?,
+            holdback,
             meeting_point,
+            meeting_point_hash,
             meeting_datetime,
+            seller_account,
+            seller_authority_info,
+            vault_account,
+        )
+        .map(|deal| Offer(deal, kind))
+    }
+
+    /// Locks the seller's insurance for a compressed listing of a described offer, returning
+    /// the mint the offer is paid in and the insurance.
+    pub fn lock_compressed_listing(
+        payment: u64,
+        seller_account: &AccountInfo,
+        seller_authority_info: &AccountInfo,
+        vault_account: &AccountInfo,
+    ) -> Result<(Pubkey, u64), DLUError> {
+        let insurance = OfferKind::Described.insurance(payment)?;
+        let payment_mint = Deal::<SellerListed>::lock_listing_deposit(
             payment,
             insurance,
-            goodsorservice_name,
-            goodsorservice_description,
-            seller_key: String::new(),
-            buyer_key: String::new(),
-            escrow_id,
-        })
+            seller_account,
+            seller_authority_info,
+            vault_account,
+        )?;
+        Ok((payment_mint, insurance))
     }
 
-    pub fn accept_offer(
-        &mut self, 
-        buyer: &mut User,
-        buyer_account: &AccountInfo, 
-        escrow_account: &AccountInfo, 
-        authority_info: &AccountInfo
-    ) -> Result<(), &'static str> {
-        // Ensure the offer is in the 'Listed' state.
-        if self.status != OfferStatus::Listed {
-            return Err("Offer is not in the 'Listed' state.");
+    /// Materializes the compressed listing of `seller`, whose insurance sits in `vault`, into
+    /// a listed offer, whose audit log starts with the listing.
+    pub fn materialize(listing: CompressedOffer, seller: &User, vault: &Pubkey) -> Result<Self, DLUError> {
+        if listing.seller != seller.pubkey {
+            return Err(DLUError::KeyMismatch);
         }
-        
-        // Generate the one-time keys for both seller and buyer.
-        self.seller_key = onetimekeys::generate_key(); 
-        self.buyer_key = onetimekeys::generate_key();
-
-        // Update the buyer field.
-        self.buyer = Some(buyer.clone());
-
-        // Check buyer's balance.
-        let buyer_balance = DLUToken::get_balance(buyer_account)?;
-        let total_deduction = self.payment + self.insurance;
-        if buyer_balance < total_deduction {
-            return Err("Insufficient funds in buyer's account.");
+        let insurance = OfferKind::Described.insurance(listing.payment)?;
+        let mut deal = Deal::listed(
+            listing.id,
+            seller,
+            listing.goodsorservice_name,
+            listing.goodsorservice_description,
+            listing.payment,
+            insurance,
+            listing.payment_mint,
+            *vault,
+            None,
+            listing.meeting_point,
+            None,
+            listing.meeting_datetime,
+        );
+        deal.record_transition(listing.seller, listing.listed_slot);
+        Ok(Offer(deal, OfferKind::Described))
+    }
+
+    /// Moves the NFT an NFT offer sells from the seller into the offer's NFT escrow.
+    pub fn deposit_nft(
+        &self,
+        seller_nft_account: &AccountInfo,
+        nft_escrow_account: &AccountInfo,
+        nft_mint_info: &AccountInfo,
+        seller_authority_info: &AccountInfo,
+    ) -> Result<(), DLUError> {
+        let OfferKind::Nft { mint } = self.1 else {
+            return Err(DLUError::InvalidOperation);
+        };
+        if *nft_mint_info.key != mint {
+            return Err(DLUError::MintMismatch);
         }
+        if !DLUToken::is_nft(nft_mint_info).map_err(|_| DLUError::DeserializationFailed)? {
+            return Err(DLUError::NotAnNft);
+        }
+        DLUToken::check_payment_mint(&mint, &[seller_nft_account, nft_escrow_account])?;
+        DLUToken::transfer(seller_nft_account, nft_escrow_account, seller_authority_info, 1)
+            .map_err(|_| DLUError::TransferFailed)
+    }
 
-        // Deduct the payment and insurance amounts from the buyer's wallet.
-        buyer.wallet.balance -= total_deduction;
+    /// Releases the NFT held in the offer's NFT escrow to a token account of `recipient`.
+    pub fn release_nft(
+        &self,
+        nft_escrow_account: &AccountInfo,
+        recipient_nft_account: &AccountInfo,
+        recipient: &Pubkey,
+        nft_escrow_seeds: &[&[u8]],
+    ) -> Result<(), DLUError> {
+        let OfferKind::Nft { mint } = self.1 else {
+            return Err(DLUError::InvalidOperation);
+        };
+        DLUToken::check_payment_mint(&mint, &[nft_escrow_account, recipient_nft_account])?;
+        if !DLUToken::check_authority(recipient_nft_account, recipient).map_err(|_| DLUError::KeyMismatch)? {
+            return Err(DLUError::KeyMismatch);
+        }
+        DLUToken::transfer_signed(nft_escrow_account, recipient_nft_account, nft_escrow_account, 1, &[nft_escrow_seeds])
+            .map_err(|_| DLUError::TransferFailed)
+    }
 
-        // Lock the payment and insurance amounts in escrow.
-        Escrow::lock_funds(buyer_account, escrow_account, authority_info, total_deduction)?;
+    /// Prices the `quantity` of units a buyer is about to accept. Inventory offers charge it
+    /// at the unit price of its tier, while other offers sell a single unit at their payment.
+    pub fn price_quantity(&mut self, quantity: u32) -> Result<(), DLUError> {
+        match &mut self.1 {
+            OfferKind::Inventory { max_quantity, tiers, quantity: accepted } => {
+                if quantity == 0 || quantity > *max_quantity {
+                    return Err(DLUError::InvalidQuantity);
+                }
+                let payment = OfferKind::tier_total(tiers, quantity)?;
+                *accepted = quantity;
+                self.0.price(payment)
+            }
+            _ if quantity == 1 => Ok(()),
+            _ => Err(DLUError::InvalidQuantity),
+        }
+    }
 
-        // Update the status of the offer to 'Accepted'.
-        self.status = OfferStatus::Accepted;
+    /// Accepts the offer by a buyer.
+    pub fn accept_offer(
+        &mut self,
+        buyer: &mut User,
+        buyer_account: &AccountInfo,
+        vault_account: &AccountInfo,
+        authority_info: &AccountInfo,
+    ) -> Result<(), DLUError> {
+        self.0.accept(buyer, buyer_account, vault_account, authority_info)
+    }
 
-        Ok(())
+    /// Accepts the offer by a buyer paying out of their wallet.
+    pub fn accept_offer_from_wallet(
+        &mut self,
+        buyer: &mut User,
+        wallet_account: &AccountInfo,
+        vault_account: &AccountInfo,
+        wallet_seeds: &[&[u8]],
+    ) -> Result<(), DLUError> {
+        self.0.accept_from_wallet(buyer, wallet_account, vault_account, wallet_seeds)
+    }
+
+    /// Accepts the offer for the buyer of a matching request, paying the matcher a finder's
+    /// fee out of the request's deposit. Barters have no payment to match on, and inventory
+    /// offers only have one once a quantity is accepted.
+    pub fn match_request(
+        &mut self,
+        request: &mut Request,
+        buyer: &mut User,
+        buyer_account: &AccountInfo,
+        vault_account: &AccountInfo,
+        vault_seeds: &[&[u8]],
+        matcher_account: &AccountInfo,
+    ) -> Result<u64, DLUError> {
+        if let OfferKind::Barter { .. } | OfferKind::Inventory { .. } = self.1 {
+            return Err(DLUError::DealsNotCompatible);
+        }
+        self.0.accept_matched(request, buyer, buyer_account, vault_account, vault_seeds, matcher_account)
     }
 
     pub fn complete_offer(
-        &mut self, 
-        entered_buyer_key: String, 
+        &mut self,
+        entered_buyer_key: String,
         entered_seller_key: String,
         seller_account: &AccountInfo,
         buyer_account: &AccountInfo,
-        escrow_account: &AccountInfo,
-        escrow_authority_info: &AccountInfo,
+        vault_account: &AccountInfo,
+        vault_seeds: &[&[u8]],
+        treasury_account: &AccountInfo,
+        config: &Config,
         seller: &mut User,
         buyer: &mut User,
-    ) -> Result<(), &'static str> {
-        // Ensure the offer is in the 'Accepted' state.
-        if self.status != OfferStatus::Accepted {
-            return Err("Offer is not in the 'Accepted' state.");
-        }
+        now: i64,
+    ) -> Result<u64, DLUError> {
+        self.0.complete(
+            entered_buyer_key,
+            entered_seller_key,
+            seller_account,
+            buyer_account,
+            vault_account,
+            vault_seeds,
+            treasury_account,
+            config,
+            seller,
+            buyer,
+            config.chargeback_window,
+            now,
+        )
+    }
 
-        // Validate the buyer's key.
-        if entered_buyer_key != self.buyer_key {
-            return Err("Invalid buyer key provided.");
-        }
+    pub fn fail_offer(
+        &mut self,
+        entered_seller_key: String,
+        buyer: &mut User,
+        seller_account: &AccountInfo,
+        buyer_account: &AccountInfo,
+        vault_account: &AccountInfo,
+        penalty_account: &AccountInfo,
+        vault_seeds: &[&[u8]],
+        config: &Config,
+        now: i64,
+    ) -> Result<u64, DLUError> {
+        self.0.fail(
+            entered_seller_key,
+            buyer,
+            seller_account,
+            buyer_account,
+            vault_account,
+            penalty_account,
+            vault_seeds,
+            config,
+            now,
+        )
+    }
 
-        // Check escrow balance.
-        let escrow_balance = DLUToken::get_balance(escrow_account)?;
-        if escrow_balance < (self.payment + 2 * self.insurance) { // Double insurance for both seller and buyer.
-            return Err("Insufficient funds in escrow.");
-        }
+    pub fn expire_offer(
+        &mut self,
+        vault_account: &AccountInfo,
+        seller_account: &AccountInfo,
+        buyer_account: &AccountInfo,
+        vault_seeds: &[&[u8]],
+        now: i64,
+    ) -> Result<(), DLUError> {
+        self.check_stream_expired(now)?;
+        self.0.expire(vault_account, seller_account, buyer_account, vault_seeds, now)
+    }
 
-        // Release the payment amount to the seller's account and update seller's balance.
-        Escrow::release_funds(escrow_account, seller_account, escrow_authority_info, self.payment)?;
-        seller.wallet.balance += self.payment;
+    /// Expires an overdue offer on behalf of a keeper, returning the bounty paid to them.
+    pub fn crank_expire_offer(
+        &mut self,
+        vault_account: &AccountInfo,
+        seller_account: &AccountInfo,
+        buyer_account: &AccountInfo,
+        keeper_account: &AccountInfo,
+        vault_seeds: &[&[u8]],
+        bounty: u64,
+        now: i64,
+    ) -> Result<u64, DLUError> {
+        self.check_stream_expired(now)?;
+        self.0.crank_expire(vault_account, seller_account, buyer_account, keeper_account, vault_seeds, bounty, now)
+    }
 
-        // Validate the seller's key.
-        if entered_seller_key != self.seller_key {
-            return Err("Invalid seller key provided.");
+    /// Ensures an accepted streaming offer is past the grace period after its service
+    /// period rather than its meeting, and was not stopped, since a stopped stream's
+    /// remainder awaits a settlement.
+    fn check_stream_expired(&self, now: i64) -> Result<(), DLUError> {
+        match self.1 {
+            OfferKind::Stream(stream) if self.0.status() == OfferStatus::Accepted => {
+                if stream.stopped_at.is_some() {
+                    return Err(DLUError::StreamStopped);
+                }
+                if !time::is_expired(stream.end(self.0.meeting_datetime()), now) {
+                    return Err(DLUError::DealNotExpired);
+                }
+                Ok(())
+            }
+            _ => Ok(()),
         }
+    }
 
-        // Release the insurance amounts back to the seller and buyer, then update their balances.
-        Escrow::release_funds(escrow_account, seller_account, escrow_authority_info, self.insurance)?;
-        seller.wallet.balance += self.insurance;
-        
-        Escrow::release_funds(escrow_account, buyer_account, escrow_authority_info, self.insurance)?;
-        buyer.wallet.balance += self.insurance;
-
-        // Invalidate the keys.
-        self.buyer_key.clear();
-        self.seller_key.clear();
-
-        // Update the status of the offer to 'Completed'.
-        self.status = OfferStatus::Completed;
-
-        // Mark the deal as successful for both the seller and buyer.
-        seller.mark_deal(true);
-        buyer.mark_deal(true);
-
-        Ok(())
+    /// Pays the seller of an accepted streaming offer everything vested since their last
+    /// withdrawal, less the protocol fee. Returns the amount vested and the fee.
+    pub fn withdraw_stream(
+        &mut self,
+        seller: &User,
+        seller_account: &AccountInfo,
+        vault_account: &AccountInfo,
+        treasury_account: &AccountInfo,
+        vault_seeds: &[&[u8]],
+        config: &Config,
+        now: i64,
+    ) -> Result<(u64, u64), DLUError> {
+        let OfferKind::Stream(stream) = &mut self.1 else {
+            return Err(DLUError::NotStreaming);
+        };
+        let amount = stream.withdraw(self.0.payment(), self.0.meeting_datetime(), now)?;
+        let fee = config.fee_for(amount, seller, Role::Seller)?;
+        self.0.pay_out_early(
+            &seller.pubkey,
+            amount,
+            fee,
+            seller_account,
+            vault_account,
+            treasury_account,
+            vault_seeds,
+        )?;
+        Ok((amount, fee))
     }
 
-    pub fn fail_offer(
-        &mut self, 
-        entered_seller_key: String,
-        buyer: &mut User,
-        escrow_account: &AccountInfo,
-        penalty_account: &AccountInfo,
-        escrow_authority_info: &AccountInfo,
-    ) -> Result<(), &'static str> {
-        // Ensure the offer is in the 'Accepted' state.
-        if self.status != OfferStatus::Accepted {
-            return Err("Offer is not in the 'Accepted' state.");
+    /// Stops the stream of an accepted streaming offer on behalf of its seller or buyer.
+    pub fn stop_stream(&mut self, party: &Pubkey, now: i64) -> Result<(), DLUError> {
+        let OfferKind::Stream(stream) = &mut self.1 else {
+            return Err(DLUError::NotStreaming);
+        };
+        if self.0.status() != OfferStatus::Accepted {
+            return Err(DLUError::NotAccepted);
         }
-
-        // Validate the seller's key.
-        if entered_seller_key != self.seller_key {
-            return Err("Invalid seller key provided.");
+        let (seller, buyer) = self.0.parties().ok_or(DLUError::CounterpartyNotFound)?;
+        if *party != seller && *party != buyer {
+            return Err(DLUError::NotAuthorized);
         }
+        stream.stop(now)
+    }
 
-        // Calculate the total amount to be transferred to the penalty account.
-        let total_amount = self.payment + 2 * self.insurance; 
+    /// Edits a listed offer, adjusting the seller's escrowed insurance to a new payment.
+    pub fn update_offer(
+        &mut self,
+        seller: &User,
+        goodsorservice_description: Option<String>,
+        meeting_point: Option<Location>,
+        meeting_datetime: Option<i64>,
+        payment: Option<u64>,
+        seller_account: &AccountInfo,
+        seller_authority_info: &AccountInfo,
+        vault_account: &AccountInfo,
+        vault_seeds: &[&[u8]],
+    ) -> Result<(), DLUError> {
+        // A barter's insurance stands in for the payment it does not have, and an inventory
+        // offer's tiers set its payment.
+        match (self.1, payment) {
+            (OfferKind::Barter { .. }, Some(_)) => return Err(DLUError::InvalidBarterTerms),
+            (OfferKind::Inventory { .. }, Some(_)) => return Err(DLUError::InvalidPriceTiers),
+            _ => {}
+        }
+        self.0.update(
+            seller,
+            goodsorservice_description,
+            meeting_point,
+            meeting_datetime,
+            payment,
+            seller_account,
+            seller_authority_info,
+            vault_account,
+            vault_seeds,
+        )
+    }
 
-        // Transfer the total_amount from the escrow to the penalty account.
-        Escrow::transfer_to_penalty(escrow_account, escrow_authority_info, total_amount)?;
+    /// Lists an expired or canceled offer again, locking fresh insurance from the seller.
+    pub fn relist_offer(
+        &mut self,
+        seller: &mut User,
+        seller_account: &AccountInfo,
+        seller_authority_info: &AccountInfo,
+        vault_account: &AccountInfo,
+    ) -> Result<(), DLUError> {
+        self.0.relist(seller, seller_account, seller_authority_info, vault_account)?;
+        match &mut self.1 {
+            OfferKind::Inventory { quantity, .. } => *quantity = 0,
+            // The payment streamed out early is owed again by the next buyer.
+            OfferKind::Stream(stream) => {
+                self.0.price(safe_math::add(self.0.payment(), stream.withdrawn)?)?;
+                *stream = PaymentStream::new(stream.duration);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
 
-        // Invalidate the keys.
-        self.buyer_key.clear();
-        self.seller_key.clear();
+    pub fn cancel_offer(
+        &mut self,
+        seller_account: &AccountInfo,
+        vault_account: &AccountInfo,
+        vault_seeds: &[&[u8]],
+    ) -> Result<(), DLUError> {
+        self.0.cancel(seller_account, vault_account, vault_seeds)
+    }
 
-        // Update the status of the offer to 'Failed'.
-        self.status = OfferStatus::Failed;
+    /// Returns what the offer sells.
+    pub fn kind(&self) -> OfferKind {
+        self.1
+    }
 
-        // Mark the deal as failed for the buyer.
-        buyer.mark_deal(false);
+    /// Returns the public key of the seller who listed the offer.
+    pub fn seller_pubkey(&self) -> Pubkey {
+        self.0.lister_pubkey()
+    }
 
-        Ok(())
+    /// Serializes the offer into a vector of bytes: the versioned deal, then the kind.
+    pub fn serialize(&self) -> Result<Vec<u8>, DLUError> {
+        let mut data = self.0.serialize()?;
+        self.1.serialize(&mut data).map_err(|_| DLUError::SerializationFailed)?;
+        Ok(data)
     }
 
-	pub fn expire_offer(
-		&mut self,
-		escrow_account: &AccountInfo,
-		seller_account: &AccountInfo,
-		buyer_account: &AccountInfo,
-		escrow_authority_info: &AccountInfo,
-	) -> Result<(), &'static str> {
-		// Ensure the current date-time is past the meeting_datetime + 24 hours.
-		let current_datetime = Utc::now();
-		if current_datetime <= self.meeting_datetime + Duration::hours(24) {
-			return Err("Offer hasn't expired yet.");
-		}
-
-		// Ensure the offer is still in the 'Accepted' state.
-		if self.status != OfferStatus::Accepted {
-			return Err("Offer is not in the 'Accepted' state.");
-		}
-
-		// Release the payment and buyer's insurance back to the buyer's account.
-		let buyer_total = self.payment + self.insurance;
-		Escrow::release_funds(escrow_account, buyer_account, escrow_authority_info, buyer_total)?;
-
-		// Add the payment and insurance amounts back to the buyer's wallet.
-		if let Some(buyer) = &mut self.buyer {
-			buyer.wallet.balance += buyer_total;
-		} else {
-			return Err("Buyer not found in the offer.");
-		}
-
-		// Release the seller's insurance back to the seller's account.
-		Escrow::release_funds(escrow_account, seller_account, escrow_authority_info, self.insurance)?;
-
-		// Add the insurance amount back to the seller's wallet.
-		self.seller.wallet.balance += self.insurance;
-
-		// Update the status of the offer to 'Expired'.
-		self.status = OfferStatus::Expired;
-
-		Ok(())
-	}
-		
-	pub fn cancel_offer(
-		&mut self,
-		seller_account: &AccountInfo,
-		escrow_account: &AccountInfo,
-		escrow_authority_info: &AccountInfo,
-	) -> Result<(), &'static str> {
-		// Ensure the offer is in the 'Listed' state.
-		if self.status != OfferStatus::Listed {
-			return Err("Offer is not in the 'Listed' state or has already been accepted.");
-		}
-
-		// Release the locked insurance back to the seller's account.
-		// The locked amount in escrow is equal to the insurance amount, which is the same as the payment amount.
-		Escrow::release_funds(escrow_account, seller_account, escrow_authority_info, self.insurance)?;
-
-		// Invalidate the seller's key.
-		self.seller_key.clear();
-
-		// Update the status of the offer to 'Canceled'.
-		self.status = OfferStatus::Canceled;
-
-		Ok(())
-	}
-
-    /// Updates the status of the offer.
-    pub fn update_status(&mut self, new_status: OfferStatus) {
-        self.status = new_status;
+    /// Deserializes an offer from a slice of bytes.
+    pub fn deserialize(input: &mut &[u8]) -> Result<Self, DLUError> {
+        let deal = Deal::deserialize(input)?;
+        let kind = OfferKind::deserialize(input).map_err(|_| DLUError::DeserializationFailed)?;
+        Ok(Offer(deal, kind))
     }
-	
-	/// Serializes the offer into a vector of bytes.
-    pub fn serialize(&self) -> Result<Vec<u8>, &'static str> {
-        self.try_to_vec().map_err(|_| "Failed to serialize Offer")
+}
+
+impl Deref for Offer {
+    type Target = Deal<SellerListed>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
     }
+}
 
-    /// Deserializes an offer from a slice of bytes.
-    pub fn deserialize(input: &mut &[u8]) -> Result<Self, &'static str> {
-        Self::try_from_slice(input).map_err(|_| "Failed to deserialize Offer")
+impl DerefMut for Offer {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
     }
-    
 }
@@ -0,0 +1,215 @@
+use std::hint::black_box;
+use solana_program::hash::hashv;
+use solana_program::keccak;
+use solana_program::pubkey::Pubkey;
+use solana_program::borsh::{BorshSerialize, BorshDeserialize};
+use crate::errors::DLUError;
+use crate::time::SECONDS_PER_DAY;
+
+/// Time a committed one-time key is accepted for. Keys that outlive it, like lost ones,
+/// are replaced with `RegenerateKeys`.
+pub const KEY_LIFETIME: i64 = 14 * SECONDS_PER_DAY;
+
+/// Hash a one-time key is stored as, so the key itself never appears on chain.
+pub type KeyHash = [u8; 32];
+
+/// Stored in place of a key no party committed yet, or cleared once its deal or shipment
+/// resolved. No entered key ever matches it, so an emptied key can't complete anything.
+pub const NO_KEY: KeyHash = [0; 32];
+
+/// Expiry of keys committed before keys expired, which stay accepted until their deal or
+/// shipment resolves.
+pub const NEVER_EXPIRES: i64 = i64::MAX;
+
+/// Domain of key hashes, keeping them apart from every other hash the program commits to.
+const KEY_DOMAIN: &[u8] = b"luda-one-time-key";
+
+/// Domain of key seeds.
+const SEED_DOMAIN: &[u8] = b"luda-key-seed";
+
+/// Crockford's base32 digits, which leave out I, L, O and U so codes read back unambiguously.
+const CODE_ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// Random bytes behind a key code, 40 bits spelled as eight base32 digits.
+pub const KEY_CODE_BYTES: usize = 5;
+
+/// Digits of a key code: eight for the key and two for its checksum, which catches typos
+/// before they count as a wrong key.
+pub const KEY_CODE_LEN: usize = 10;
+
+/// Largest prime that fits the two checksum digits. The checksum is the key's value modulo
+/// it, which changes whenever a single digit is mistyped or two neighbours are swapped.
+const CODE_CHECKSUM_PRIME: u64 = 1021;
+
+/// Most keys a KeyManager records for its deal or shipment, one bit each in its used-key
+/// bitmap.
+pub const MAX_MANAGED_KEYS: usize = 32;
+
+/// Account size of a KeyManager holding `MAX_MANAGED_KEYS` keys.
+pub const KEY_MANAGER_SIZE: usize = 4 + MAX_MANAGED_KEYS * (32 + 8 + 32) + 4;
+
+/// Returns the seed of a party's one-time key for a deal or shipment: the keccak hash of
+/// its id, the party, and the nonce the client picked. The seed is public, so clients
+/// derive the key itself from it together with a secret only the party holds, and can
+/// derive it again from the nonce the KeyManager recorded.
+pub fn key_seed(entity_id: u64, party: &Pubkey, nonce: u64) -> [u8; 32] {
+    keccak::hashv(&[SEED_DOMAIN, &entity_id.to_le_bytes(), party.as_ref(), &nonce.to_le_bytes()]).to_bytes()
+}
+
+/// Returns the hash a party commits to for their one-time key, or `NO_KEY` for an empty key.
+pub fn hash_key(key: &str) -> KeyHash {
+    if key.is_empty() {
+        return NO_KEY;
+    }
+    hashv(&[KEY_DOMAIN, key.as_bytes()]).to_bytes()
+}
+
+/// Spells `payload` as a short key code, in the canonical form parties commit the hash of.
+pub fn encode_key_code(payload: &[u8; KEY_CODE_BYTES]) -> String {
+    let bits = payload.iter().fold(0u64, |bits, byte| bits << 8 | u64::from(*byte));
+    let check = bits % CODE_CHECKSUM_PRIME;
+    let digits = (0..8).rev().map(|i| (bits >> (5 * i)) & 31).chain([check >> 5, check & 31]);
+    digits.map(|digit| CODE_ALPHABET[digit as usize] as char).collect()
+}
+
+/// Reads a key code as typed or scanned, ignoring case, dashes and spaces, and reading O
+/// as 0 and I or L as 1. Fails with `InvalidKeyCode` unless its checksum matches.
+pub fn decode_key_code(code: &str) -> Result<[u8; KEY_CODE_BYTES], DLUError> {
+    let mut digits = Vec::with_capacity(KEY_CODE_LEN);
+    for c in code.chars().filter(|c| *c != '-' && *c != ' ') {
+        let c = match c.to_ascii_uppercase() {
+            'O' => '0',
+            'I' | 'L' => '1',
+            c => c,
+        };
+        let digit = CODE_ALPHABET.iter().position(|d| *d as char == c).ok_or(DLUError::InvalidKeyCode)?;
+        if digits.len() == KEY_CODE_LEN {
+            return Err(DLUError::InvalidKeyCode);
+        }
+        digits.push(digit as u64);
+    }
+    if digits.len() != KEY_CODE_LEN {
+        return Err(DLUError::InvalidKeyCode);
+    }
+    let bits = digits[..8].iter().fold(0u64, |bits, digit| bits << 5 | digit);
+    if (digits[8] << 5 | digits[9]) != bits % CODE_CHECKSUM_PRIME {
+        return Err(DLUError::InvalidKeyCode);
+    }
+    let mut payload = [0; KEY_CODE_BYTES];
+    for (i, byte) in payload.iter_mut().enumerate() {
+        *byte = (bits >> (8 * (KEY_CODE_BYTES - 1 - i))) as u8;
+    }
+    Ok(payload)
+}
+
+/// Returns true if the entered key hashes to the stored one. Empty and cleared keys never
+/// match, and the hashes are compared in constant time, so how long a check takes tells
+/// nothing about how close a guess came.
+pub fn verify(entered: &str, stored: &KeyHash) -> bool {
+    if *stored == NO_KEY {
+        return false;
+    }
+    let entered = hash_key(entered);
+    let difference = entered.iter().zip(stored).fold(0, |difference, (a, b)| difference | (a ^ b));
+    black_box(difference) == 0
+}
+
+/// A party's one-time key as committed: its hash, and when it stops being accepted.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct OneTimeKey {
+    pub hash: KeyHash,
+    pub expires_at: i64,
+}
+
+impl OneTimeKey {
+    /// No key, which is never accepted.
+    pub const NONE: OneTimeKey = OneTimeKey { hash: NO_KEY, expires_at: 0 };
+
+    /// Commits to the key with the given hash at `now`, accepted for `KEY_LIFETIME`.
+    pub fn commit(hash: KeyHash, now: i64) -> Result<Self, DLUError> {
+        if hash == NO_KEY {
+            return Err(DLUError::InvalidOperation);
+        }
+        Ok(OneTimeKey { hash, expires_at: now.saturating_add(KEY_LIFETIME) })
+    }
+
+    /// Returns true once a key was committed.
+    pub fn is_committed(&self) -> bool {
+        self.hash != NO_KEY
+    }
+
+    /// Checks an entered key at `now`, failing with `invalid` unless it matches and with
+    /// `KeyExpired` if it matches but expired. A key code is checked in its canonical form,
+    /// however it was typed.
+    pub fn check(&self, entered: &str, now: i64, invalid: DLUError) -> Result<(), DLUError> {
+        let canonical = decode_key_code(entered).ok().map(|payload| encode_key_code(&payload));
+        if !verify(canonical.as_deref().unwrap_or(entered), &self.hash) {
+            return Err(invalid);
+        }
+        if now >= self.expires_at {
+            return Err(DLUError::KeyExpired);
+        }
+        Ok(())
+    }
+}
+
+/// A key recorded by a KeyManager: whose it is, the nonce it was derived with, and its hash.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct ManagedKey {
+    pub party: Pubkey,
+    pub nonce: u64,
+    pub hash: KeyHash,
+}
+
+/// Every one-time key committed for a deal or shipment, kept at the PDA derived from its
+/// address. A key is used once a newer key of its party replaced it, and used keys stay
+/// recorded, so neither their nonce nor their hash is ever accepted again.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct KeyManager {
+    pub keys: Vec<ManagedKey>,
+    pub used: u32,
+}
+
+impl KeyManager {
+    /// Serializes the KeyManager into a vector of bytes.
+    pub fn serialize(&self) -> Result<Vec<u8>, DLUError> {
+        self.try_to_vec().map_err(|_| DLUError::SerializationFailed)
+    }
+
+    /// Deserializes a KeyManager from a slice of bytes.
+    pub fn deserialize(input: &mut &[u8]) -> Result<Self, DLUError> {
+        // A fresh KeyManager account is all zeros, which reads as no keys.
+        <Self as BorshDeserialize>::deserialize(input).map_err(|_| DLUError::DeserializationFailed)
+    }
+
+    /// Records the key a party committed with `nonce`, marking the party's previous key
+    /// used.
+    pub fn record(&mut self, party: &Pubkey, nonce: u64, hash: KeyHash) -> Result<(), DLUError> {
+        let reused = self.keys.iter().any(|key| key.hash == hash || (key.party == *party && key.nonce == nonce));
+        if reused {
+            return Err(DLUError::KeyAlreadyUsed);
+        }
+        if self.keys.len() >= MAX_MANAGED_KEYS {
+            return Err(DLUError::KeyManagerFull);
+        }
+        if let Some(index) = self.active_index(party) {
+            self.used |= 1 << index;
+        }
+        self.keys.push(ManagedKey { party: *party, nonce, hash });
+        Ok(())
+    }
+
+    /// Returns the party's key that is not used yet, if they committed one.
+    pub fn active_key(&self, party: &Pubkey) -> Option<&ManagedKey> {
+        self.active_index(party).map(|index| &self.keys[index])
+    }
+
+    /// Returns true if the key at `index` was replaced.
+    pub fn is_used(&self, index: usize) -> bool {
+        index < MAX_MANAGED_KEYS && self.used & (1 << index) != 0
+    }
+
+    fn active_index(&self, party: &Pubkey) -> Option<usize> {
+        (0..self.keys.len()).rev().find(|&index| self.keys[index].party == *party && !self.is_used(index))
+    }
+}
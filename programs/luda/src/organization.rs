@@ -0,0 +1,77 @@
+use solana_program::pubkey::Pubkey;
+use solana_program::borsh::{BorshSerialize, BorshDeserialize};
+use crate::errors::DLUError;
+
+/// Most member wallets an organization can have.
+pub const MAX_MEMBERS: usize = 16;
+
+// Roles a member can hold, combined as bit flags.
+pub const MEMBER_LISTER: u8 = 1 << 0;    // Lists offers and shipments.
+pub const MEMBER_ACCEPTOR: u8 = 1 << 1;  // Accepts offers and shipments.
+pub const MEMBER_FINISHER: u8 = 1 << 2;  // Confirms pickups and files fail claims.
+pub const ALL_MEMBER_ROLES: u8 = MEMBER_LISTER | MEMBER_ACCEPTOR | MEMBER_FINISHER;
+
+/// A wallet allowed to act for an organization, and the roles it holds.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, PartialEq, Debug)]
+pub struct Member {
+    pub key: Pubkey,
+    pub roles: u8,
+}
+
+/// Business identity, such as a courier company, that several employee wallets act for.
+///
+/// The organization is an ordinary user whose owner adds members with roles. Offer and
+/// shipment handlers accept a member's signature wherever the user's signature would be
+/// needed for their roles, while the deal is made in, and the reputation earned by, the
+/// organization's user account.
+///
+/// Lives at the PDA `["organization", owner_pubkey]`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct Organization {
+    pub owner: Pubkey,  // Key of the organization's user account.
+    pub members: Vec<Member>,
+}
+
+impl Organization {
+    /// Creates an organization without members.
+    pub fn new(owner: Pubkey) -> Self {
+        Organization { owner, members: Vec::new() }
+    }
+
+    /// Gives `member` the `roles`, adding them if needed; no roles removes them.
+    pub fn set_member(&mut self, member: Pubkey, roles: u8) -> Result<(), DLUError> {
+        if member == self.owner || roles & !ALL_MEMBER_ROLES != 0 {
+            return Err(DLUError::InvalidMemberRoles);
+        }
+        let position = self.members.iter().position(|m| m.key == member);
+        match (position, roles) {
+            (Some(position), 0) => {
+                self.members.swap_remove(position);
+            }
+            (Some(position), _) => self.members[position].roles = roles,
+            (None, 0) => return Err(DLUError::NotOrganizationMember),
+            (None, _) if self.members.len() >= MAX_MEMBERS => return Err(DLUError::OrganizationFull),
+            (None, _) => self.members.push(Member { key: member, roles }),
+        }
+        Ok(())
+    }
+
+    /// Checks that `member` holds `role` in the organization.
+    pub fn check_member(&self, member: &Pubkey, role: u8) -> Result<(), DLUError> {
+        match self.members.iter().find(|m| m.key == *member) {
+            Some(m) if m.roles & role == role => Ok(()),
+            _ => Err(DLUError::NotOrganizationMember),
+        }
+    }
+
+    /// Serializes the organization into a vector of bytes.
+    pub fn serialize(&self) -> Result<Vec<u8>, DLUError> {
+        self.try_to_vec().map_err(|_| DLUError::SerializationFailed)
+    }
+
+    /// Deserializes an organization from a slice of bytes.
+    pub fn deserialize(input: &mut &[u8]) -> Result<Self, DLUError> {
+        // Accounts are sized for a full member list, so the tail may be zero padding.
+        <Self as BorshDeserialize>::deserialize(input).map_err(|_| DLUError::DeserializationFailed)
+    }
+}
@@ -0,0 +1,137 @@
+use solana_program::pubkey::Pubkey;
+use solana_program::borsh::{BorshSerialize, BorshDeserialize};
+use crate::errors::DLUError;
+use crate::safe_math;
+use crate::time::SECONDS_PER_DAY;
+
+/// Shortest time between two distributions of a penalty pool, so penalties reach stakers as a
+/// steady stream instead of whoever cranks right after a slash.
+pub const PENALTY_DISTRIBUTION_INTERVAL: i64 = SECONDS_PER_DAY;
+
+/// Fixed-point scale of `PenaltyPool::reward_per_stake`.
+pub const REWARD_PER_STAKE_SCALE: u128 = 1_000_000_000_000;
+
+/// Account size of a PenaltyPool.
+pub const PENALTY_POOL_SIZE: usize = 32 + 8 + 16 + 8 + 8 + 8 + 8;
+
+/// Account size of a PenaltyRewards.
+pub const PENALTY_REWARDS_SIZE: usize = 32 + 8 + 16 + 8;
+
+/// How the penalties of one mint are shared among carrier stakers, kept at the PDA
+/// `["penalty_pool", mint]`. The tokens themselves sit in the penalty vault of the mint until
+/// stakers claim them.
+///
+/// Every distribution burns the config's burn share of what arrived in the vault since the
+/// last one and credits the rest to the stakes enrolled in the pool, pro rata.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct PenaltyPool {
+    pub mint: Pubkey,
+    pub total_staked: u64,        // Stake enrolled through the stakers' PenaltyRewards.
+    pub reward_per_stake: u128,   // Credited per staked token over the pool's lifetime, scaled.
+    pub pending: u64,             // Held back for the next distribution, such as rounding dust.
+    pub accounted: u64,           // Vault balance as of the last distribution or claim.
+    pub last_distributed_at: i64,
+    pub total_burned: u64,
+}
+
+impl PenaltyPool {
+    /// Creates an empty pool for the mint.
+    pub fn new(mint: Pubkey) -> Self {
+        PenaltyPool {
+            mint,
+            total_staked: 0,
+            reward_per_stake: 0,
+            pending: 0,
+            accounted: 0,
+            last_distributed_at: 0,
+            total_burned: 0,
+        }
+    }
+
+    /// Distributes what arrived in the vault, whose balance is now `balance`, at `now`.
+    /// Returns the amount to burn and the amount credited to stakers.
+    pub fn distribute(&mut self, balance: u64, burn_bps: u16, now: i64) -> Result<(u64, u64), DLUError> {
+        if now < self.last_distributed_at.saturating_add(PENALTY_DISTRIBUTION_INTERVAL) {
+            return Err(DLUError::DistributionTooSoon);
+        }
+        let arrived = safe_math::sub(balance, self.accounted)?;
+        let burned = safe_math::bps(arrived, burn_bps)?;
+        let available = safe_math::add(arrived - burned, self.pending)?;
+
+        // Without stakers the penalties wait for the first one to enroll.
+        let credited = if self.total_staked == 0 {
+            0
+        } else {
+            let increment = available as u128 * REWARD_PER_STAKE_SCALE / self.total_staked as u128;
+            self.reward_per_stake = self.reward_per_stake.checked_add(increment).ok_or(DLUError::ArithmeticOverflow)?;
+            (increment * self.total_staked as u128 / REWARD_PER_STAKE_SCALE) as u64
+        };
+
+        self.pending = available - credited;
+        self.accounted = balance - burned;
+        self.last_distributed_at = now;
+        self.total_burned = safe_math::add(self.total_burned, burned)?;
+        Ok((burned, credited))
+    }
+
+    /// Pays out the rewards of a staker whose stake is now `stake`, then enrolls that stake for
+    /// future distributions. Returns the amount to pay.
+    ///
+    /// Only the part of the enrolled stake the staker still holds earns rewards; what the rest
+    /// would have earned goes back to the pool for the next distribution.
+    pub fn claim(&mut self, rewards: &mut PenaltyRewards, stake: u64) -> Result<u64, DLUError> {
+        let earned = |staked: u64| -> Result<u64, DLUError> {
+            let delta = self.reward_per_stake - rewards.reward_per_stake_paid;
+            let scaled = delta.checked_mul(staked as u128).ok_or(DLUError::ArithmeticOverflow)?;
+            u64::try_from(scaled / REWARD_PER_STAKE_SCALE).map_err(|_| DLUError::ArithmeticOverflow)
+        };
+        let payout = earned(rewards.staked.min(stake))?;
+        let forfeited = earned(rewards.staked)? - payout;
+
+        self.pending = safe_math::add(self.pending, forfeited)?;
+        self.accounted = safe_math::sub(self.accounted, payout)?;
+        self.total_staked = safe_math::add(safe_math::sub(self.total_staked, rewards.staked)?, stake)?;
+        rewards.staked = stake;
+        rewards.reward_per_stake_paid = self.reward_per_stake;
+        rewards.total_claimed = safe_math::add(rewards.total_claimed, payout)?;
+        Ok(payout)
+    }
+
+    /// Serializes the pool into a vector of bytes.
+    pub fn serialize(&self) -> Result<Vec<u8>, DLUError> {
+        self.try_to_vec().map_err(|_| DLUError::SerializationFailed)
+    }
+
+    /// Deserializes a pool from a slice of bytes.
+    pub fn deserialize(input: &mut &[u8]) -> Result<Self, DLUError> {
+        <Self as BorshDeserialize>::deserialize(input).map_err(|_| DLUError::DeserializationFailed)
+    }
+}
+
+/// A carrier's share of the penalty pool of a mint, kept at the PDA
+/// `["penalty_rewards", mint, carrier_pubkey]`. The carrier's first claim enrolls their stake;
+/// every claim after pays what it earned since and enrolls the stake as it is then.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct PenaltyRewards {
+    pub carrier: Pubkey,
+    pub staked: u64,                   // Stake enrolled in the pool at the last claim.
+    pub reward_per_stake_paid: u128,   // The pool's `reward_per_stake` at the last claim.
+    pub total_claimed: u64,
+}
+
+impl PenaltyRewards {
+    /// Creates a carrier's rewards with nothing enrolled yet.
+    pub fn new(carrier: Pubkey) -> Self {
+        PenaltyRewards { carrier, staked: 0, reward_per_stake_paid: 0, total_claimed: 0 }
+    }
+
+    /// Serializes the rewards into a vector of bytes.
+    pub fn serialize(&self) -> Result<Vec<u8>, DLUError> {
+        self.try_to_vec().map_err(|_| DLUError::SerializationFailed)
+    }
+
+    /// Deserializes rewards from a slice of bytes.
+    pub fn deserialize(input: &mut &[u8]) -> Result<Self, DLUError> {
+        <Self as BorshDeserialize>::deserialize(input).map_err(|_| DLUError::DeserializationFailed)
+    }
+}
@@ -0,0 +1,37 @@
+use solana_program::borsh::{BorshSerialize, BorshDeserialize};
+use crate::errors::DLUError;
+use crate::safe_math;
+
+/// Cubic centimeters of parcel volume that count as one gram of chargeable weight, the
+/// usual volumetric divisor of 5,000 cm³ per kilogram.
+pub const CM3_PER_VOLUMETRIC_GRAM: u64 = 5;
+
+/// Rates governance sets for suggesting shipment payments, in base units of the payment
+/// mint. Zero rates suggest nothing, leaving the payment entirely to the sender.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct ShippingRates {
+    pub base_rate: u64,    // Charged once per shipment.
+    pub rate_per_km: u64,
+    pub rate_per_kg: u64,  // Charged on the heavier of the actual and the volumetric weight.
+}
+
+/// Returns the weight a parcel is charged on: its actual weight or the weight its volume
+/// counts as, whichever is heavier.
+pub fn chargeable_grams(weight_grams: Option<u32>, volume_cm3: Option<u32>) -> u64 {
+    let volumetric = volume_cm3.map_or(0, |volume| (volume as u64 + CM3_PER_VOLUMETRIC_GRAM - 1) / CM3_PER_VOLUMETRIC_GRAM);
+    volumetric.max(weight_grams.map_or(0, u64::from))
+}
+
+/// Returns the payment the rates suggest for a shipment of the measurements given, rounded
+/// up to whole base units. Measurements left out add nothing.
+pub fn suggested_payment(
+    rates: &ShippingRates,
+    weight_grams: Option<u32>,
+    volume_cm3: Option<u32>,
+    distance_km: Option<u32>,
+) -> Result<u64, DLUError> {
+    let distance = safe_math::mul(rates.rate_per_km, distance_km.map_or(0, u64::from))?;
+    let grams = chargeable_grams(weight_grams, volume_cm3) as u128;
+    let weight = u64::try_from((rates.rate_per_kg as u128 * grams + 999) / 1_000).map_err(|_| DLUError::ArithmeticOverflow)?;
+    safe_math::add(safe_math::add(rates.base_rate, distance)?, weight)
+}
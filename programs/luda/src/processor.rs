@@ -0,0 +1,5713 @@
+use solana_program::{
+    account_info::AccountInfo,
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+    entrypoint::ProgramResult,
+    msg,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    program_utils::limited_deserialize,
+    pubkey::Pubkey,
+    system_instruction,
+    system_program,
+    sysvar::{rent::Rent, Sysvar},
+};
+use solana_program::borsh::BorshSerialize;
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use crate::addressing::{
+    derive_badge_address, derive_badge_mint_address, derive_delegation_address, derive_nft_escrow_address, derive_quote_address,
+    derive_arbiter_address, derive_config_address, derive_referral_address, derive_reward_emission_address, derive_stats_address, derive_listing_tree_authority_address, derive_stake_address, derive_treasury_address, derive_vault_address,
+    derive_wallet_address, derive_proposal_address, derive_vote_lock_address, derive_penalty_pool_address, derive_penalty_rewards_address,
+    derive_penalty_vault_address, derive_faucet_address, derive_rewards_vault_address, derive_carrier_route_address, derive_route_index_address, derive_vote_record_address, derive_dao_address, derive_timelock_address, derive_emergency_address, derive_key_manager_address, derive_history_address, derive_leaderboard_address, derive_watchlist_address, derive_promo_address, derive_attestation_address, derive_attestor_registry_address, derive_multisig_address, derive_organization_address, derive_appeal_address, derive_message_log_address, derive_region_address, derive_review_address, derive_arbitration_address, derive_arbiter_registry_address, derive_bond_address, derive_index_address, derive_profile_summary_address, derive_vault_registry_address, EntityType, IndexAccount, BADGE_MINT_SEED, BADGE_SEED, FAUCET_SEED,
+    CARRIER_ROUTE_SEED, CONFIG_SEED, REFERRAL_SEED, REWARD_EMISSION_SEED, ROUTE_INDEX_SEED, STATS_SEED, LISTING_TREE_SEED, NFT_ESCROW_SEED, PENALTY_VAULT_SEED, REWARDS_VAULT_SEED, TREASURY_SEED, VAULT_REGISTRY_SEED, PROFILE_SUMMARY_SEED, INDEX_SEED, BOND_SEED, ARBITER_REGISTRY_SEED, ARBITER_SEED, ARBITRATION_SEED, STAKE_SEED, REVIEW_SEED, REGION_SEED, MESSAGES_SEED, APPEAL_SEED, DELEGATION_SEED, ORGANIZATION_SEED, MULTISIG_SEED, ATTESTOR_REGISTRY_SEED, ATTESTATION_SEED, PROMO_SEED, WATCHLIST_SEED, LEADERBOARD_SEED, HISTORY_SEED, KEY_MANAGER_SEED, EMERGENCY_SEED, TIMELOCK_SEED, DAO_SEED, PROPOSAL_SEED, VOTE_LOCK_SEED, VOTE_SEED, PENALTY_POOL_SEED, PENALTY_REWARDS_SEED, VAULT_SEED, WALLET_SEED,
+};
+use crate::appeal::{Appeal, AppealStatus, APPEAL_PANEL_SIZE};
+use crate::archive::{self, ArchivedBatch, History, MAX_ARCHIVE_BATCH};
+use crate::keeper::MAX_EXPIRY_BATCH;
+use crate::arbiter::{self, Arbiter, ArbiterRegistry, Arbitration, DisputeKind};
+use crate::attestation::{Attestation, AttestorRegistry};
+use crate::badge::{self, BadgeTier, TOKEN_METADATA_PROGRAM_ID};
+use crate::bond::FailBond;
+use crate::compression::{self, CompressedOffer, TreeAccounts, ACCOUNT_COMPRESSION_PROGRAM_ID, NOOP_PROGRAM_ID};
+use crate::config::{Config, CAP_BURN_PENALTIES, CAP_ESCROW_MIGRATION, CAP_TEST_CLUSTER};
+use crate::constants::{CARRIER_ROUTE_SIZE, CONFIG_SIZE, REFERRAL_REWARDS_SIZE, REWARD_EMISSION_SIZE, ROUTE_INDEX_SIZE, STATS_SIZE, VAULT_REGISTRY_SIZE, PROFILE_SUMMARY_SIZE, INDEX_SIZE, FAIL_BOND_SIZE, ARBITER_REGISTRY_SIZE, ARBITER_SIZE, ARBITRATION_SIZE, CARRIER_STAKE_SIZE, REVIEW_SIZE, REGION_INDEX_SIZE, MESSAGE_LOG_SIZE, APPEAL_SIZE, DELEGATION_SIZE, ORGANIZATION_SIZE, MULTISIG_SIZE, ATTESTOR_REGISTRY_SIZE, ATTESTATION_SIZE, PROMO_CODES_SIZE, WATCHLIST_SIZE, LEADERBOARD_SIZE, KEY_MANAGER_SIZE, EMERGENCY_RESOLUTION_SIZE, TIMELOCK_QUEUE_SIZE, DAO_SIZE, PROPOSAL_SIZE, VOTE_LOCK_SIZE, VOTE_RECORD_SIZE, PENALTY_POOL_SIZE, PENALTY_REWARDS_SIZE};
+use crate::delegation::Delegation;
+use crate::discriminator::{AccountKind, Discriminated};
+use crate::dlu_token::DLUToken;
+use crate::dao::{Dao, Proposal, VoteLock};
+use crate::dlu_wallet::Wallet;
+use crate::emergency::EmergencyResolution;
+use crate::emission::RewardEmission;
+use crate::errors::DLUError;
+use crate::escrow::Escrow;
+use crate::events::DLUEvent;
+use crate::holdback::HoldbackTerms;
+use crate::instruction::{DLUInstruction, MAX_CANCEL_PER_CALL};
+use crate::leaderboard::{Leaderboard, LeaderboardKind, CARRIER_LEADERBOARDS, SELLER_LEADERBOARDS};
+use crate::location::Location;
+use crate::onetimekeys::{KeyHash, KeyManager};
+use crate::validation;
+use crate::messaging::MessageLog;
+use crate::migrations;
+use crate::offer::{Offer, OfferKind, OfferStatus};
+use crate::penalty_pool::{PenaltyPool, PenaltyRewards};
+use crate::organization::{Organization, MEMBER_ACCEPTOR, MEMBER_FINISHER, MEMBER_LISTER};
+use crate::multisig::Multisig;
+use crate::moderation::{self, check_not_banned};
+use crate::profile_summary::ProfileSummary;
+use crate::quote::Quote;
+use crate::referral::ReferralRewards;
+use crate::promo::PromoCodes;
+use crate::region::{RegionIndex, RegionListing, REGION_GEOHASH_LEN};
+use crate::request::{Request, RequestStatus};
+use crate::review::Review;
+use crate::route::{CarrierRoute, RouteIndex};
+use crate::resolver::AccountsResolver;
+use crate::safe_math;
+use crate::shipment::Shipment;
+use crate::pricing::ShippingRates;
+use crate::stake::CarrierStake;
+use crate::stats::Stats;
+use crate::time;
+use crate::timelock::{GovernanceAction, TimelockQueue};
+use crate::user::{User, FAUCET_AMOUNT, USER_MULTISIG};
+use crate::vault_registry::VaultRegistry;
+use crate::watchlist::Watchlist;
+
+pub struct Processor;
+
+impl Processor {
+    /// Decodes the instruction and dispatches it to its handler.
+    pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], input: &[u8]) -> ProgramResult {
+        let instruction = DLUInstruction::unpack(input)?;
+        let mut resolver = AccountsResolver::new(program_id, accounts);
+
+        match instruction {
+            DLUInstruction::InitializeConfig { governance, tvl_cap, terms_hash } => {
+                msg!("Instruction: InitializeConfig");
+                Self::process_initialize_config(&mut resolver, governance, tvl_cap, terms_hash)
+            }
+            DLUInstruction::RaiseTvlCap { new_cap } => {
+                msg!("Instruction: RaiseTvlCap");
+                Self::process_raise_tvl_cap(&mut resolver, new_cap)
+            }
+            DLUInstruction::SetCapabilities { capabilities } => {
+                msg!("Instruction: SetCapabilities");
+                Self::process_set_capabilities(&mut resolver, capabilities)
+            }
+            DLUInstruction::MigrateEscrow { entity_type } => {
+                msg!("Instruction: MigrateEscrow");
+                Self::process_migrate_escrow(&mut resolver, entity_type)
+            }
+            DLUInstruction::PublishTerms { terms_hash } => {
+                msg!("Instruction: PublishTerms");
+                Self::process_publish_terms(&mut resolver, terms_hash)
+            }
+            DLUInstruction::SetReputationHalfLife { half_life } => {
+                msg!("Instruction: SetReputationHalfLife");
+                Self::process_set_reputation_half_life(&mut resolver, half_life)
+            }
+            DLUInstruction::CreateUser { username, terms_hash, referrer } => {
+                msg!("Instruction: CreateUser");
+                Self::process_create_user(&mut resolver, username, terms_hash, referrer)
+            }
+            DLUInstruction::UpdateUserProfile { display_name, metadata_uri, contact_hints } => {
+                msg!("Instruction: UpdateUserProfile");
+                Self::process_update_user_profile(&mut resolver, display_name, metadata_uri, contact_hints)
+            }
+            DLUInstruction::ReacceptTerms { terms_hash } => {
+                msg!("Instruction: ReacceptTerms");
+                Self::process_reaccept_terms(&mut resolver, terms_hash)
+            }
+            DLUInstruction::ListOffer {
+                id, goodsorservice_name, goodsorservice_description, payment, meeting_point, meeting_datetime, kind,
+                meeting_point_hash, holdback,
+            } => {
+                msg!("Instruction: ListOffer");
+                Self::process_list_offer(
+                    &mut resolver, id, goodsorservice_name, goodsorservice_description,
+                    payment, meeting_point, meeting_datetime, kind, meeting_point_hash, holdback,
+                )
+            }
+            DLUInstruction::AcceptOffer { quantity, promo_code } => {
+                msg!("Instruction: AcceptOffer");
+                Self::process_accept_offer(&mut resolver, quantity, promo_code)
+            }
+            DLUInstruction::CompleteOffer { buyer_key, seller_key, nonce } => {
+                msg!("Instruction: CompleteOffer");
+                Self::process_complete_offer(&mut resolver, buyer_key, seller_key, nonce)
+            }
+            DLUInstruction::FailOffer { seller_key, nonce } => {
+                msg!("Instruction: FailOffer");
+                Self::process_fail_offer(&mut resolver, seller_key, nonce)
+            }
+            DLUInstruction::ExpireOffer => {
+                msg!("Instruction: ExpireOffer");
+                Self::process_expire_offer(&mut resolver)
+            }
+            DLUInstruction::UpdateOffer { goodsorservice_description, meeting_point, meeting_datetime, payment } => {
+                msg!("Instruction: UpdateOffer");
+                Self::process_update_offer(
+                    &mut resolver, goodsorservice_description, meeting_point, meeting_datetime, payment,
+                )
+            }
+            DLUInstruction::CancelOffer => {
+                msg!("Instruction: CancelOffer");
+                Self::process_cancel_offer(&mut resolver)
+            }
+            DLUInstruction::RelistOffer => {
+                msg!("Instruction: RelistOffer");
+                Self::process_relist_offer(&mut resolver)
+            }
+            DLUInstruction::CancelAllListed { max_count } => {
+                msg!("Instruction: CancelAllListed");
+                Self::process_cancel_all_listed(&mut resolver, max_count)
+            }
+            DLUInstruction::ListRequest {
+                id, goodsorservice_name, goodsorservice_description, payment, meeting_point, meeting_datetime,
+                meeting_point_hash,
+            } => {
+                msg!("Instruction: ListRequest");
+                Self::process_list_request(
+                    &mut resolver, id, goodsorservice_name, goodsorservice_description,
+                    payment, meeting_point, meeting_datetime, meeting_point_hash,
+                )
+            }
+            DLUInstruction::AcceptRequest => {
+                msg!("Instruction: AcceptRequest");
+                Self::process_accept_request(&mut resolver)
+            }
+            DLUInstruction::CompleteRequest { buyer_key, seller_key, nonce } => {
+                msg!("Instruction: CompleteRequest");
+                Self::process_complete_request(&mut resolver, buyer_key, seller_key, nonce)
+            }
+            DLUInstruction::FailRequest { seller_key, nonce } => {
+                msg!("Instruction: FailRequest");
+                Self::process_fail_request(&mut resolver, seller_key, nonce)
+            }
+            DLUInstruction::ExpireRequest => {
+                msg!("Instruction: ExpireRequest");
+                Self::process_expire_request(&mut resolver)
+            }
+            DLUInstruction::CancelRequest => {
+                msg!("Instruction: CancelRequest");
+                Self::process_cancel_request(&mut resolver)
+            }
+            DLUInstruction::RelistRequest => {
+                msg!("Instruction: RelistRequest");
+                Self::process_relist_request(&mut resolver)
+            }
+            DLUInstruction::ListShipment {
+                id, items_name, quantity, payment, insurance, pickup_point, pickup_datetime,
+                drop_off_point, drop_off_datetime,
+            } => {
+                msg!("Instruction: ListShipment");
+                Self::process_list_shipment(
+                    &mut resolver, id, items_name, quantity, payment, insurance,
+                    pickup_point, pickup_datetime, drop_off_point, drop_off_datetime,
+                )
+            }
+            DLUInstruction::AcceptShipment => {
+                msg!("Instruction: AcceptShipment");
+                Self::process_accept_shipment(&mut resolver)
+            }
+            DLUInstruction::CompleteShipment { carrier_key, recipient_key, proof_hash, nonce } => {
+                msg!("Instruction: CompleteShipment");
+                Self::process_complete_shipment(&mut resolver, carrier_key, recipient_key, proof_hash, nonce)
+            }
+            DLUInstruction::FailShipment { sender_key, nonce } => {
+                msg!("Instruction: FailShipment");
+                Self::process_fail_shipment(&mut resolver, sender_key, nonce)
+            }
+            DLUInstruction::ExpireShipment => {
+                msg!("Instruction: ExpireShipment");
+                Self::process_expire_shipment(&mut resolver)
+            }
+            DLUInstruction::CancelShipment => {
+                msg!("Instruction: CancelShipment");
+                Self::process_cancel_shipment(&mut resolver)
+            }
+            DLUInstruction::RelistShipment => {
+                msg!("Instruction: RelistShipment");
+                Self::process_relist_shipment(&mut resolver)
+            }
+            DLUInstruction::IssueGoodwillRefund { entity_type, amount } => {
+                msg!("Instruction: IssueGoodwillRefund");
+                Self::process_issue_goodwill_refund(&mut resolver, entity_type, amount)
+            }
+            DLUInstruction::SetFailBond { amount } => {
+                msg!("Instruction: SetFailBond");
+                Self::process_set_fail_bond(&mut resolver, amount)
+            }
+            DLUInstruction::DisputeFail => {
+                msg!("Instruction: DisputeFail");
+                Self::process_dispute_fail(&mut resolver)
+            }
+            DLUInstruction::ResolveFailDispute { fail_justified } => {
+                msg!("Instruction: ResolveFailDispute");
+                Self::process_resolve_fail_dispute(&mut resolver, fail_justified)
+            }
+            DLUInstruction::ReclaimFailBond => {
+                msg!("Instruction: ReclaimFailBond");
+                Self::process_reclaim_fail_bond(&mut resolver)
+            }
+            DLUInstruction::SetFee { fee_bps, exempt_credible } => {
+                msg!("Instruction: SetFee");
+                Self::process_set_fee(&mut resolver, fee_bps, exempt_credible)
+            }
+            DLUInstruction::SetPenaltyShare { share_bps } => {
+                msg!("Instruction: SetPenaltyShare");
+                Self::process_set_penalty_share(&mut resolver, share_bps)
+            }
+            DLUInstruction::WithdrawTreasury { amount } => {
+                msg!("Instruction: WithdrawTreasury");
+                Self::process_withdraw_treasury(&mut resolver, amount)
+            }
+            DLUInstruction::SetStakeRequirements { stake_threshold, min_stake } => {
+                msg!("Instruction: SetStakeRequirements");
+                Self::process_set_stake_requirements(&mut resolver, stake_threshold, min_stake)
+            }
+            DLUInstruction::Stake { amount } => {
+                msg!("Instruction: Stake");
+                Self::process_stake(&mut resolver, amount)
+            }
+            DLUInstruction::Unstake { amount } => {
+                msg!("Instruction: Unstake");
+                Self::process_unstake(&mut resolver, amount)
+            }
+            DLUInstruction::Slash => {
+                msg!("Instruction: Slash");
+                Self::process_slash(&mut resolver)
+            }
+            DLUInstruction::SubmitReview { entity_type, rating, review_hash } => {
+                msg!("Instruction: SubmitReview");
+                Self::process_submit_review(&mut resolver, entity_type, rating, review_hash)
+            }
+            DLUInstruction::SyncBadge { tier } => {
+                msg!("Instruction: SyncBadge");
+                Self::process_sync_badge(&mut resolver, tier)
+            }
+            DLUInstruction::SetCrankBounty { bounty } => {
+                msg!("Instruction: SetCrankBounty");
+                Self::process_set_crank_bounty(&mut resolver, bounty)
+            }
+            DLUInstruction::CrankExpire { entity_type } => {
+                msg!("Instruction: CrankExpire");
+                Self::process_crank_expire(&mut resolver, entity_type)
+            }
+
+            // WALLETS
+            DLUInstruction::DepositDLU { amount } => {
+                msg!("Instruction: DepositDLU");
+                Self::process_deposit_dlu(&mut resolver, amount)
+            }
+            DLUInstruction::WithdrawDLU { amount } => {
+                msg!("Instruction: WithdrawDLU");
+                Self::process_withdraw_dlu(&mut resolver, amount)
+            }
+            DLUInstruction::ReconcileWallet => {
+                msg!("Instruction: ReconcileWallet");
+                Self::process_reconcile_wallet(&mut resolver)
+            }
+
+            // QUOTES
+            DLUInstruction::SubmitQuote { price, note } => {
+                msg!("Instruction: SubmitQuote");
+                Self::process_submit_quote(&mut resolver, price, note)
+            }
+            DLUInstruction::SelectQuote => {
+                msg!("Instruction: SelectQuote");
+                Self::process_select_quote(&mut resolver)
+            }
+            DLUInstruction::WithdrawQuote => {
+                msg!("Instruction: WithdrawQuote");
+                Self::process_withdraw_quote(&mut resolver)
+            }
+
+            // MATCHING
+            DLUInstruction::MatchRequestToOffer => {
+                msg!("Instruction: MatchRequestToOffer");
+                Self::process_match_request_to_offer(&mut resolver)
+            }
+
+            // MEETING POINTS
+            DLUInstruction::RevealMeetingPoint { entity_type, meeting_point, salt } => {
+                msg!("Instruction: RevealMeetingPoint");
+                Self::process_reveal_meeting_point(&mut resolver, entity_type, meeting_point, salt)
+            }
+
+            // MESSAGING
+            DLUInstruction::SendMessage { entity_type, payload } => {
+                msg!("Instruction: SendMessage");
+                Self::process_send_message(&mut resolver, entity_type, payload)
+            }
+
+            // PICKUPS
+            DLUInstruction::ConfirmPickup { sender_key } => {
+                msg!("Instruction: ConfirmPickup");
+                Self::process_confirm_pickup(&mut resolver, sender_key)
+            }
+
+            // DELIVERY PROOFS
+            DLUInstruction::SubmitProof { proof_hash } => {
+                msg!("Instruction: SubmitProof");
+                Self::process_submit_proof(&mut resolver, proof_hash)
+            }
+
+            // RETURNS
+            DLUInstruction::CreateReturnShipment { id, payment, pickup_datetime, drop_off_datetime } => {
+                msg!("Instruction: CreateReturnShipment");
+                Self::process_create_return_shipment(&mut resolver, id, payment, pickup_datetime, drop_off_datetime)
+            }
+
+            // HOLDBACKS
+            DLUInstruction::ReleaseHoldback => {
+                msg!("Instruction: ReleaseHoldback");
+                Self::process_release_holdback(&mut resolver)
+            }
+            DLUInstruction::DisputeHoldback => {
+                msg!("Instruction: DisputeHoldback");
+                Self::process_dispute_holdback(&mut resolver)
+            }
+            DLUInstruction::ResolveHoldbackDispute { defective } => {
+                msg!("Instruction: ResolveHoldbackDispute");
+                Self::process_resolve_holdback_dispute(&mut resolver, defective)
+            }
+
+            // CHARGEBACKS
+            DLUInstruction::SetChargebackWindow { window } => {
+                msg!("Instruction: SetChargebackWindow");
+                Self::process_set_chargeback_window(&mut resolver, window)
+            }
+            DLUInstruction::OpenChargeback => {
+                msg!("Instruction: OpenChargeback");
+                Self::process_open_chargeback(&mut resolver)
+            }
+            DLUInstruction::ReleaseSellerInsurance => {
+                msg!("Instruction: ReleaseSellerInsurance");
+                Self::process_release_seller_insurance(&mut resolver)
+            }
+            DLUInstruction::ResolveChargeback { upheld } => {
+                msg!("Instruction: ResolveChargeback");
+                Self::process_resolve_chargeback(&mut resolver, upheld)
+            }
+
+            // ARBITRATION
+            DLUInstruction::SetArbitrationTerms { min_stake, fee } => {
+                msg!("Instruction: SetArbitrationTerms");
+                Self::process_set_arbitration_terms(&mut resolver, min_stake, fee)
+            }
+            DLUInstruction::RegisterArbiter { amount } => {
+                msg!("Instruction: RegisterArbiter");
+                Self::process_register_arbiter(&mut resolver, amount)
+            }
+            DLUInstruction::DeregisterArbiter => {
+                msg!("Instruction: DeregisterArbiter");
+                Self::process_deregister_arbiter(&mut resolver)
+            }
+            DLUInstruction::WithdrawArbiterStake => {
+                msg!("Instruction: WithdrawArbiterStake");
+                Self::process_withdraw_arbiter_stake(&mut resolver)
+            }
+            DLUInstruction::AssignArbiter { kind } => {
+                msg!("Instruction: AssignArbiter");
+                Self::process_assign_arbiter(&mut resolver, kind)
+            }
+            DLUInstruction::RuleDispute { kind, for_buyer } => {
+                msg!("Instruction: RuleDispute");
+                Self::process_rule_dispute(&mut resolver, kind, for_buyer)
+            }
+
+            // APPEALS
+            DLUInstruction::SetAppealBond { bond } => {
+                msg!("Instruction: SetAppealBond");
+                Self::process_set_appeal_bond(&mut resolver, bond)
+            }
+            DLUInstruction::AppealRuling { kind } => {
+                msg!("Instruction: AppealRuling");
+                Self::process_appeal_ruling(&mut resolver, kind)
+            }
+            DLUInstruction::VoteAppeal { kind, for_buyer } => {
+                msg!("Instruction: VoteAppeal");
+                Self::process_vote_appeal(&mut resolver, kind, for_buyer)
+            }
+            DLUInstruction::SettleDispute { kind } => {
+                msg!("Instruction: SettleDispute");
+                Self::process_settle_dispute(&mut resolver, kind)
+            }
+            DLUInstruction::SlashArbiter { kind } => {
+                msg!("Instruction: SlashArbiter");
+                Self::process_slash_arbiter(&mut resolver, kind)
+            }
+
+            // SETTLEMENTS
+
+            DLUInstruction::SettleDeal { seller_bps, buyer_bps, nonce } => {
+                msg!("Instruction: SettleDeal");
+                Self::process_settle_deal(&mut resolver, seller_bps, buyer_bps, nonce)
+            }
+
+            // SESSION KEYS
+
+            DLUInstruction::CreateSession { session, entities, expires_at } => {
+                msg!("Instruction: CreateSession");
+                Self::process_create_session(&mut resolver, session, entities, expires_at)
+            }
+            DLUInstruction::RevokeSession { session } => {
+                msg!("Instruction: RevokeSession");
+                Self::process_revoke_session(&mut resolver, session)
+            }
+
+            // ORGANIZATIONS
+
+            DLUInstruction::CreateOrganization => {
+                msg!("Instruction: CreateOrganization");
+                Self::process_create_organization(&mut resolver)
+            }
+            DLUInstruction::SetMember { member, roles } => {
+                msg!("Instruction: SetMember");
+                Self::process_set_member(&mut resolver, member, roles)
+            }
+
+            // MULTISIGS
+
+            DLUInstruction::CreateMultisig { threshold, signers, high_value } => {
+                msg!("Instruction: CreateMultisig");
+                Self::process_create_multisig(&mut resolver, threshold, signers, high_value)
+            }
+            DLUInstruction::UpdateMultisig { threshold, signers, high_value } => {
+                msg!("Instruction: UpdateMultisig");
+                Self::process_update_multisig(&mut resolver, threshold, signers, high_value)
+            }
+
+            // MODERATION
+
+            DLUInstruction::SetUserBanned { banned } => {
+                msg!("Instruction: SetUserBanned");
+                Self::process_set_user_banned(&mut resolver, banned)
+            }
+
+            // KYC
+
+            DLUInstruction::SetKycThreshold { threshold } => {
+                msg!("Instruction: SetKycThreshold");
+                Self::process_set_kyc_threshold(&mut resolver, threshold)
+            }
+            DLUInstruction::SetAttestor { attestor, approved } => {
+                msg!("Instruction: SetAttestor");
+                Self::process_set_attestor(&mut resolver, attestor, approved)
+            }
+            DLUInstruction::Attest { expires_at } => {
+                msg!("Instruction: Attest");
+                Self::process_attest(&mut resolver, expires_at)
+            }
+
+            // REFERRALS
+
+            DLUInstruction::SetReferralShare { share_bps } => {
+                msg!("Instruction: SetReferralShare");
+                Self::process_set_referral_share(&mut resolver, share_bps)
+            }
+            DLUInstruction::ClaimReferralRewards => {
+                msg!("Instruction: ClaimReferralRewards");
+                Self::process_claim_referral_rewards(&mut resolver)
+            }
+            DLUInstruction::SetPromoCode { code_hash, discount_percent, uses } => {
+                msg!("Instruction: SetPromoCode");
+                Self::process_set_promo_code(&mut resolver, code_hash, discount_percent, uses)
+            }
+            DLUInstruction::ScheduleListing { entity_type, activate_at, delist_at } => {
+                msg!("Instruction: ScheduleListing");
+                Self::process_schedule_listing(&mut resolver, entity_type, activate_at, delist_at)
+            }
+            DLUInstruction::WithdrawStream => {
+                msg!("Instruction: WithdrawStream");
+                Self::process_withdraw_stream(&mut resolver)
+            }
+            DLUInstruction::StopStream => {
+                msg!("Instruction: StopStream");
+                Self::process_stop_stream(&mut resolver)
+            }
+            DLUInstruction::FollowUser { user, follow } => {
+                msg!("Instruction: FollowUser");
+                Self::process_follow_user(&mut resolver, user, follow)
+            }
+            DLUInstruction::WatchOffer { watch } => {
+                msg!("Instruction: WatchOffer");
+                Self::process_watch_offer(&mut resolver, watch)
+            }
+            DLUInstruction::CreateListingTree { max_depth, max_buffer_size } => {
+                msg!("Instruction: CreateListingTree");
+                Self::process_create_listing_tree(&mut resolver, max_depth, max_buffer_size)
+            }
+            DLUInstruction::ListCompressedOffer {
+                id,
+                goodsorservice_name,
+                goodsorservice_description,
+                payment,
+                meeting_point,
+                meeting_datetime,
+            } => {
+                msg!("Instruction: ListCompressedOffer");
+                Self::process_list_compressed_offer(
+                    &mut resolver,
+                    id,
+                    goodsorservice_name,
+                    goodsorservice_description,
+                    payment,
+                    meeting_point,
+                    meeting_datetime,
+                )
+            }
+            DLUInstruction::AcceptCompressedOffer { listing, root, index } => {
+                msg!("Instruction: AcceptCompressedOffer");
+                Self::process_accept_compressed_offer(&mut resolver, listing, root, index)
+            }
+            DLUInstruction::CancelCompressedOffer { listing, root, index } => {
+                msg!("Instruction: CancelCompressedOffer");
+                Self::process_cancel_compressed_offer(&mut resolver, listing, root, index)
+            }
+            DLUInstruction::ArchiveBatch { entity_type } => {
+                msg!("Instruction: ArchiveBatch");
+                Self::process_archive_batch(&mut resolver, entity_type)
+            }
+            DLUInstruction::VerifyArchivedEntity { lister, batch, entity, data_hash, index, proof } => {
+                msg!("Instruction: VerifyArchivedEntity");
+                Self::process_verify_archived_entity(&mut resolver, lister, batch, entity, data_hash, index, proof)
+            }
+            DLUInstruction::CommitKey { entity_type, key_hash, nonce } => {
+                msg!("Instruction: CommitKey");
+                Self::process_commit_key(&mut resolver, entity_type, key_hash, nonce)
+            }
+            DLUInstruction::RegenerateKeys { entity_type, seller_key_hash, buyer_key_hash, nonce } => {
+                msg!("Instruction: RegenerateKeys");
+                Self::process_regenerate_keys(&mut resolver, entity_type, seller_key_hash, buyer_key_hash, nonce)
+            }
+            DLUInstruction::ReclaimStaleListing { entity_type } => {
+                msg!("Instruction: ReclaimStaleListing");
+                Self::process_reclaim_stale_listing(&mut resolver, entity_type)
+            }
+            DLUInstruction::ExtendDeadline { entity_type, deadline } => {
+                msg!("Instruction: ExtendDeadline");
+                Self::process_extend_deadline(&mut resolver, entity_type, deadline)
+            }
+            DLUInstruction::VerifyEscrow { entity_type } => {
+                msg!("Instruction: VerifyEscrow");
+                Self::process_verify_escrow(&mut resolver, entity_type)
+            }
+            DLUInstruction::ProposeEmergencyResolve { entity_type } => {
+                msg!("Instruction: ProposeEmergencyResolve");
+                Self::process_propose_emergency_resolve(&mut resolver, entity_type)
+            }
+            DLUInstruction::EmergencyResolve { entity_type } => {
+                msg!("Instruction: EmergencyResolve");
+                Self::process_emergency_resolve(&mut resolver, entity_type)
+            }
+            DLUInstruction::CancelEmergencyResolve => {
+                msg!("Instruction: CancelEmergencyResolve");
+                Self::process_cancel_emergency_resolve(&mut resolver)
+            }
+            DLUInstruction::QueueGovernanceAction { action } => {
+                msg!("Instruction: QueueGovernanceAction");
+                Self::process_queue_governance_action(&mut resolver, action)
+            }
+            DLUInstruction::CancelGovernanceAction { id } => {
+                msg!("Instruction: CancelGovernanceAction");
+                Self::process_cancel_governance_action(&mut resolver, id)
+            }
+            DLUInstruction::ExecuteGovernanceAction { id } => {
+                msg!("Instruction: ExecuteGovernanceAction");
+                Self::process_execute_governance_action(&mut resolver, id)
+            }
+            DLUInstruction::InitializeDao { vote_mint, quorum, proposal_threshold, voting_period } => {
+                msg!("Instruction: InitializeDao");
+                Self::process_initialize_dao(&mut resolver, vote_mint, quorum, proposal_threshold, voting_period)
+            }
+            DLUInstruction::LockVotes { amount } => {
+                msg!("Instruction: LockVotes");
+                Self::process_lock_votes(&mut resolver, amount)
+            }
+            DLUInstruction::UnlockVotes { amount } => {
+                msg!("Instruction: UnlockVotes");
+                Self::process_unlock_votes(&mut resolver, amount)
+            }
+            DLUInstruction::CreateProposal { action } => {
+                msg!("Instruction: CreateProposal");
+                Self::process_create_proposal(&mut resolver, action)
+            }
+            DLUInstruction::CastVote { support } => {
+                msg!("Instruction: CastVote");
+                Self::process_cast_vote(&mut resolver, support)
+            }
+            DLUInstruction::FinalizeProposal => {
+                msg!("Instruction: FinalizeProposal");
+                Self::process_finalize_proposal(&mut resolver)
+            }
+            DLUInstruction::SetPenaltyBurnShare { share_bps } => {
+                msg!("Instruction: SetPenaltyBurnShare");
+                Self::process_set_penalty_burn_share(&mut resolver, share_bps)
+            }
+            DLUInstruction::DistributePenalties => {
+                msg!("Instruction: DistributePenalties");
+                Self::process_distribute_penalties(&mut resolver)
+            }
+            DLUInstruction::ClaimPenaltyRewards => {
+                msg!("Instruction: ClaimPenaltyRewards");
+                Self::process_claim_penalty_rewards(&mut resolver)
+            }
+            DLUInstruction::RequestFaucet => {
+                msg!("Instruction: RequestFaucet");
+                Self::process_request_faucet(&mut resolver)
+            }
+            DLUInstruction::SetCompletionRewards { reward, half_life, emission_cap } => {
+                msg!("Instruction: SetCompletionRewards");
+                Self::process_set_completion_rewards(&mut resolver, reward, half_life, emission_cap)
+            }
+            DLUInstruction::DepositAndAccept { amount, quantity, promo_code } => {
+                msg!("Instruction: DepositAndAccept");
+                Self::process_deposit_and_accept(&mut resolver, amount, quantity, promo_code)
+            }
+            DLUInstruction::BatchExpire { entity_type, ids } => {
+                msg!("Instruction: BatchExpire");
+                Self::process_batch_expire(&mut resolver, entity_type, ids)
+            }
+            DLUInstruction::RegisterVault => {
+                msg!("Instruction: RegisterVault");
+                Self::process_register_vault(&mut resolver)
+            }
+            DLUInstruction::SetShippingRates { base_rate, rate_per_km, rate_per_kg } => {
+                msg!("Instruction: SetShippingRates");
+                Self::process_set_shipping_rates(&mut resolver, ShippingRates { base_rate, rate_per_km, rate_per_kg })
+            }
+            DLUInstruction::MeasureShipment { weight_grams, volume_cm3, distance_km } => {
+                msg!("Instruction: MeasureShipment");
+                Self::process_measure_shipment(&mut resolver, weight_grams, volume_cm3, distance_km)
+            }
+            DLUInstruction::PublishRoute { route_id, origin, destination, days, capacity_grams } => {
+                msg!("Instruction: PublishRoute");
+                Self::process_publish_route(&mut resolver, route_id, origin, destination, days, capacity_grams)
+            }
+            DLUInstruction::CloseRoute { route_id } => {
+                msg!("Instruction: CloseRoute");
+                Self::process_close_route(&mut resolver, route_id)
+            }
+            DLUInstruction::ReferenceRoute => {
+                msg!("Instruction: ReferenceRoute");
+                Self::process_reference_route(&mut resolver)
+            }
+        }
+    }
+
+    // CONFIG
+
+    fn process_initialize_config(
+        resolver: &mut AccountsResolver,
+        governance: Pubkey,
+        tvl_cap: u64,
+        terms_hash: [u8; 32],
+    ) -> ProgramResult {
+        let program_id = resolver.program_id();
+        let (config_key, config_bump) = derive_config_address(program_id);
+        let (stats_key, stats_bump) = derive_stats_address(program_id);
+        let (registry_key, registry_bump) = derive_arbiter_registry_address(program_id);
+        let (timelock_key, timelock_bump) = derive_timelock_address(program_id);
+        let config_account = resolver.next_with_key(&config_key)?;
+        let stats_account = resolver.next_with_key(&stats_key)?;
+        let registry_account = resolver.next_with_key(&registry_key)?;
+        let timelock_account = resolver.next_with_key(&timelock_key)?;
+        let authority_info = resolver.next_signer()?;
+        let program_data_account = resolver.next_with_key(&bpf_loader_upgradeable::get_program_data_address(program_id))?;
+        let system_program_info = resolver.next_with_key(&system_program::id())?;
+
+        // The config can only be initialized once, and only by whoever may upgrade the
+        // program, so no one can front-run the deployment and pick governance.
+        check_upgrade_authority(program_data_account, authority_info.key)?;
+        if config_account.owner == program_id {
+            return Err(DLUError::AlreadyInitialized.into());
+        }
+
+        create_pda_account(program_id, config_account, authority_info, system_program_info, CONFIG_SIZE, &[CONFIG_SEED, &[config_bump]])?;
+        create_pda_account(program_id, stats_account, authority_info, system_program_info, STATS_SIZE, &[STATS_SEED, &[stats_bump]])?;
+        let registry_seeds: &[&[u8]] = &[ARBITER_REGISTRY_SEED, &[registry_bump]];
+        create_pda_account(program_id, registry_account, authority_info, system_program_info, ARBITER_REGISTRY_SIZE, registry_seeds)?;
+        let timelock_seeds: &[&[u8]] = &[TIMELOCK_SEED, &[timelock_bump]];
+        create_pda_account(program_id, timelock_account, authority_info, system_program_info, TIMELOCK_QUEUE_SIZE, timelock_seeds)?;
+        save_account_data(config_account, &Config::new(governance, tvl_cap, terms_hash))?;
+        save_account_data(stats_account, &Stats::new())?;
+        save_account_data(registry_account, &ArbiterRegistry::default())?;
+        save_account_data(timelock_account, &TimelockQueue::default())
+    }
+
+    fn process_raise_tvl_cap(resolver: &mut AccountsResolver, new_cap: u64) -> ProgramResult {
+        let config_account = resolver.next_config()?;
+        let governance_account = resolver.next_signer()?;
+
+        let mut config = load_config(config_account)?;
+        config.check_direct_update()?;
+        config.raise_tvl_cap(governance_account.key, new_cap)?;
+        msg!("TVL cap raised to {} (epoch {})", config.tvl_cap, config.tvl_epoch);
+
+        save_account_data(config_account, &config)
+    }
+
+    fn process_set_capabilities(resolver: &mut AccountsResolver, capabilities: u64) -> ProgramResult {
+        let config_account = resolver.next_config()?;
+        let governance_account = resolver.next_signer()?;
+
+        let mut config = load_config(config_account)?;
+        config.check_direct_update()?;
+        config.set_capabilities(governance_account.key, capabilities)?;
+        msg!("Capabilities set to {:#x}", config.capabilities);
+
+        save_account_data(config_account, &config)
+    }
+
+    fn process_migrate_escrow(resolver: &mut AccountsResolver, entity_type: EntityType) -> ProgramResult {
+        let config = load_config(resolver.next_config()?)?;
+
+        // Only governance may move funds of live deals.
+        let governance_account = resolver.next_signer()?;
+        config.check_governance(governance_account.key)?;
+        config.check_direct_update()?;
+
+        migrate_escrow(resolver, &config, entity_type)?;
+        Ok(())
+    }
+
+    // USERS
+
+    fn process_publish_terms(resolver: &mut AccountsResolver, terms_hash: [u8; 32]) -> ProgramResult {
+        let config_account = resolver.next_config()?;
+        let governance_account = resolver.next_signer()?;
+
+        let mut config = load_config(config_account)?;
+        config.check_direct_update()?;
+        config.publish_terms(governance_account.key, terms_hash)?;
+        msg!("Terms version {} published", config.terms_version);
+
+        save_account_data(config_account, &config)
+    }
+
+    fn process_set_reputation_half_life(resolver: &mut AccountsResolver, half_life: u64) -> ProgramResult {
+        let config_account = resolver.next_config()?;
+        let governance_account = resolver.next_signer()?;
+
+        let mut config = load_config(config_account)?;
+        config.check_direct_update()?;
+        config.set_reputation_half_life(governance_account.key, half_life)?;
+        msg!("Reputation half-life set to {} slots", config.reputation_half_life);
+
+        save_account_data(config_account, &config)
+    }
+
+    fn process_create_user(
+        resolver: &mut AccountsResolver,
+        username: String,
+        terms_hash: [u8; 32],
+        referrer: Option<Pubkey>,
+    ) -> ProgramResult {
+        validation::username(&username)?;
+        let user_account = resolver.next_fresh_account()?;
+        let owner_account = resolver.next_signer()?;
+
+        // New users must accept the terms currently published in the config.
+        let terms_version = load_config(resolver.next_config()?)?.check_terms_hash(&terms_hash)?;
+        let summary_account = resolver.next_profile_summary(owner_account.key)?;
+        let stats_account = resolver.next_stats()?;
+        let index_account = resolver.next_index(owner_account.key)?;
+        let system_program_info = resolver.next_with_key(&system_program::id())?;
+        let payer_info = resolver.next_payer(owner_account);
+        let mut stats = load_stats(stats_account)?;
+
+        let mut user = User::new(username, *owner_account.key, Wallet::new(*owner_account.key), terms_version);
+        if let Some(referrer) = referrer {
+            user.set_referrer(referrer).map_err(DLUError::from)?;
+        }
+        save_user(user_account, &user)?;
+        stats.record_user();
+        save_account_data(stats_account, &stats)?;
+
+        if resolver.has_next() {
+            let token_account = resolver.next()?;
+            let mint_info = resolver.next()?;
+            let token_program_id = DLUToken::token_program_id(mint_info)?;
+            let token_program_info = resolver.next_with_key(&token_program_id)?;
+            resolver.next_with_key(&spl_associated_token_account::id())?;
+            let associated_address =
+                get_associated_token_address_with_program_id(owner_account.key, mint_info.key, &token_program_id);
+            if token_account.key != &associated_address {
+                return Err(DLUError::KeyMismatch.into());
+            }
+            // Registering with an existing token account is fine; only a missing one is created.
+            if token_account.data_is_empty() {
+                DLUToken::create_associated_account(
+                    payer_info, token_account, owner_account, mint_info, system_program_info, token_program_info,
+                )?;
+                msg!("Associated token account created");
+            }
+        }
+
+        // An owner registering another user account keeps the summary and index they already have.
+        let program_id = resolver.program_id();
+        let (_, summary_bump) = derive_profile_summary_address(program_id, owner_account.key);
+        let summary_seeds: &[&[u8]] = &[PROFILE_SUMMARY_SEED, owner_account.key.as_ref(), &[summary_bump]];
+        create_raw_pda_account(program_id, summary_account, payer_info, system_program_info, PROFILE_SUMMARY_SIZE, summary_seeds)?;
+        let (_, index_bump) = derive_index_address(program_id, owner_account.key);
+        let index_seeds: &[&[u8]] = &[INDEX_SEED, owner_account.key.as_ref(), &[index_bump]];
+        create_pda_account(program_id, index_account, payer_info, system_program_info, INDEX_SIZE, index_seeds)?;
+        refresh_profile_summary(summary_account, &user)
+    }
+
+    fn process_update_user_profile(
+        resolver: &mut AccountsResolver,
+        display_name: Option<String>,
+        metadata_uri: Option<String>,
+        contact_hints: Option<String>,
+    ) -> ProgramResult {
+        let user_account = resolver.next_program_account()?;
+        let mut user = load_user(user_account)?;
+        let owner_info = resolver.next_signer_for(&user.pubkey)?;
+        let system_program_info = resolver.next_with_key(&system_program::id())?;
+        let payer_info = resolver.next_payer(owner_info);
+
+        user.update_profile(display_name, metadata_uri, contact_hints).map_err(DLUError::from)?;
+
+        let data = user.serialize().map_err(|_| DLUError::SerializationFailed)?;
+        let space = migrations::account_space(data.len());
+        if space > user_account.data_len() {
+            grow_account(user_account, payer_info, system_program_info, space)?;
+        }
+        write_account_data(user_account, AccountKind::User, &data)
+    }
+
+    fn process_reaccept_terms(resolver: &mut AccountsResolver, terms_hash: [u8; 32]) -> ProgramResult {
+        let user_account = resolver.next_program_account()?;
+        let mut user = load_user(user_account)?;
+        resolver.next_signer_for(&user.pubkey)?;
+
+        user.accepted_terms_version = load_config(resolver.next_config()?)?.check_terms_hash(&terms_hash)?;
+        save_user(user_account, &user)
+    }
+
+    // OFFERS
+
+    fn process_list_offer(
+        resolver: &mut AccountsResolver,
+        id: u64,
+        goodsorservice_name: String,
+        goodsorservice_description: String,
+        payment: u64,
+        meeting_point: Location,
+        meeting_datetime: i64,
+        kind: OfferKind,
+        meeting_point_hash: Option<[u8; 32]>,
+        holdback: Option<HoldbackTerms>,
+    ) -> ProgramResult {
+        validation::item_name(&goodsorservice_name)?;
+        validation::description(&goodsorservice_description)?;
+        validation::location(&meeting_point)?;
+        let region = meeting_point.region();
+        let offer_account = resolver.next_fresh_account()?;
+        let seller = load_user(resolver.next_program_account()?)?;
+        let seller_authority_info = resolver.next_signer()?;
+        let seller_account = resolver.next_token_account()?;
+        let (vault_account, _) = next_registered_vault(resolver, &DLUToken::get_mint(seller_account)?)?;
+        let config = load_config(resolver.next_config()?)?;
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+        let index_account = resolver.next_index(&seller.pubkey)?;
+        let mut index = load_index(index_account)?;
+
+        config.check_terms_accepted(seller.accepted_terms_version)?;
+        check_not_banned(&seller)?;
+
+        let mut offer = Offer::list_offer(
+            id,
+            kind,
+            &seller,
+            goodsorservice_name,
+            goodsorservice_description,
+            payment,
+            holdback,
+            meeting_point,
+            meeting_point_hash,
+            meeting_datetime,
+            seller_account,
+            seller_authority_info,
+            vault_account,
+        )?;
+
+        deposit_nft(resolver, offer_account, &offer, seller_authority_info)?;
+        add_to_region(resolver, region, *offer_account.key, EntityType::Offer, seller_authority_info)?;
+        check_member(resolver, seller_authority_info.key, &seller.pubkey, MEMBER_LISTER)?;
+        check_multisig(resolver, &seller, seller_authority_info.key, payment)?;
+
+        // Exceeding the cap fails the whole transaction, reverting the escrow transfer above.
+        stats.lock_value(&config, vault_account.key, offer.escrowed_amount())?;
+        stats.record_listing();
+        index.add_offer(*offer_account.key);
+
+        save_offer(offer_account, &mut offer, seller_authority_info.key)?;
+        save_index(index_account, &index)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    fn process_accept_offer(resolver: &mut AccountsResolver, quantity: u32, promo_code: Option<String>) -> ProgramResult {
+        let offer_account = resolver.next_program_account()?;
+        let buyer_user_account = resolver.next_program_account()?;
+        let mut offer = load_offer(offer_account)?;
+        let mut buyer = load_user(buyer_user_account)?;
+        let buyer_info = resolver.next_signer()?;
+        let buyer_account = resolver.next_token_account()?;
+        let (vault_account, _) = next_registered_vault(resolver, &offer.payment_mint())?;
+        let authority_info = resolver.next_signer()?;
+        let config = load_config(resolver.next_config()?)?;
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+        let index_account = resolver.next_index(&offer.seller_pubkey())?;
+        let mut index = load_index(index_account)?;
+        check_member(resolver, buyer_info.key, &buyer.pubkey, MEMBER_ACCEPTOR)?;
+        check_not_banned(&buyer)?;
+        offer.check_active(time::now()?)?;
+        offer.price_quantity(quantity)?;
+        check_attestations(resolver, &config, offer.payment(), &[offer.seller_pubkey(), buyer.pubkey])?;
+        if let Some(code) = promo_code {
+            redeem_promo_code(resolver, offer_account.key, &mut offer, &buyer, &code)?;
+        }
+
+        let locked_before = offer.escrowed_amount();
+        offer.accept_offer(&mut buyer, buyer_account, vault_account, authority_info)?;
+
+        // Exceeding the cap fails the whole transaction, reverting the escrow transfer above.
+        stats.lock_value(&config, vault_account.key, safe_math::sub(offer.escrowed_amount(), locked_before)?)?;
+        index.accept_offer(offer_account.key, &offer.seller_pubkey(), &buyer.pubkey);
+
+        save_offer(offer_account, &mut offer, buyer_info.key)?;
+        save_user(buyer_user_account, &buyer)?;
+        save_index(index_account, &index)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    fn process_complete_offer(
+        resolver: &mut AccountsResolver,
+        buyer_key: String,
+        seller_key: String,
+        nonce: u64,
+    ) -> ProgramResult {
+        let offer_account = resolver.next_program_account()?;
+        let mut offer = load_offer(offer_account)?;
+        offer.check_nonce(nonce)?;
+        let seller_user_account = resolver.next_program_account()?;
+        let buyer_user_account = resolver.next_program_account()?;
+        let seller_account = resolver.next_token_account()?;
+        let buyer_account = resolver.next_token_account()?;
+        let mint = offer.payment_mint();
+        let (vault_account, bump) = next_registered_vault(resolver, &mint)?;
+        let vault_seeds: &[&[u8]] = &[VAULT_SEED, mint.as_ref(), &[bump]];
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+
+        let mut seller = load_user(seller_user_account)?;
+        let mut buyer = load_user(buyer_user_account)?;
+        let seller_summary_account = resolver.next_profile_summary(&seller.pubkey)?;
+        let buyer_summary_account = resolver.next_profile_summary(&buyer.pubkey)?;
+        let config = load_config(resolver.next_config()?)?;
+        let treasury_account = resolver.next_treasury()?;
+        decay_reputation(&mut seller, &config)?;
+        decay_reputation(&mut buyer, &config)?;
+
+        let locked_before = offer.escrowed_amount();
+        let fee = offer.complete_offer(
+            buyer_key,
+            seller_key,
+            seller_account,
+            buyer_account,
+            vault_account,
+            vault_seeds,
+            treasury_account,
+            &config,
+            &mut seller,
+            &mut buyer,
+            time::now()?,
+        )?;
+        release_nft(resolver, offer_account, &offer, &buyer.pubkey)?;
+        stats.release_value(vault_account.key, safe_math::sub(locked_before, offer.escrowed_amount())?);
+        stats.record_completion();
+        if fee > 0 {
+            DLUEvent::FeeCollected {
+                entity_type: EntityType::Offer,
+                entity: *offer_account.key,
+                payee: seller.pubkey,
+                amount: fee,
+                slot: time::slot()?,
+            }.emit();
+            credit_referrer(resolver, &config, &seller, treasury_account, fee)?;
+        }
+        let entity = (EntityType::Offer, *offer_account.key);
+        let parties = [(seller_account, &seller.pubkey), (buyer_account, &buyer.pubkey)];
+        pay_completion_rewards(resolver, &config, entity, treasury_account, &parties)?;
+        update_leaderboards(resolver, &seller, &SELLER_LEADERBOARDS)?;
+
+        save_offer(offer_account, &mut offer, vault_account.key)?;
+        save_user(seller_user_account, &seller)?;
+        save_user(buyer_user_account, &buyer)?;
+        refresh_profile_summary(seller_summary_account, &seller)?;
+        refresh_profile_summary(buyer_summary_account, &buyer)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    fn process_fail_offer(resolver: &mut AccountsResolver, seller_key: String, nonce: u64) -> ProgramResult {
+        let offer_account = resolver.next_program_account()?;
+        let mut offer = load_offer(offer_account)?;
+        offer.check_nonce(nonce)?;
+        let buyer_user_account = resolver.next_program_account()?;
+        let mint = offer.payment_mint();
+        let (vault_account, bump) = next_registered_vault(resolver, &mint)?;
+        let vault_seeds: &[&[u8]] = &[VAULT_SEED, mint.as_ref(), &[bump]];
+        let penalty_account = resolver.next_token_account()?;
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+
+        let mut buyer = load_user(buyer_user_account)?;
+        let buyer_summary_account = resolver.next_profile_summary(&buyer.pubkey)?;
+
+        let config = load_config(resolver.next_config()?)?;
+        let seller_info = resolver.next_signer()?;
+        let seller_account = resolver.next_token_account()?;
+        let bond_account = resolver.next_bond(offer_account.key)?;
+        let system_program_info = resolver.next_with_key(&system_program::id())?;
+        let payer_info = resolver.next_payer(seller_info);
+        decay_reputation(&mut buyer, &config)?;
+        let buyer_account = resolver.next_token_account()?;
+
+        let locked_before = offer.escrowed_amount();
+        let penalty = offer.fail_offer(
+            seller_key,
+            &mut buyer,
+            seller_account,
+            buyer_account,
+            vault_account,
+            penalty_account,
+            vault_seeds,
+            &config,
+            time::now()?,
+        )?;
+        release_nft(resolver, offer_account, &offer, &offer.seller_pubkey())?;
+        check_member(resolver, seller_info.key, &offer.seller_pubkey(), MEMBER_FINISHER)?;
+        stats.release_value(vault_account.key, safe_math::sub(locked_before, offer.escrowed_amount())?);
+        stats.record_failure(penalty);
+        post_fail_bond(
+            resolver.program_id(), &config, bond_account, EntityType::Offer, offer_account.key, seller_info,
+            seller_account, &buyer.pubkey, vault_account, payer_info, system_program_info, &mut stats,
+        )?;
+
+        save_offer(offer_account, &mut offer, seller_info.key)?;
+        save_user(buyer_user_account, &buyer)?;
+        refresh_profile_summary(buyer_summary_account, &buyer)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    fn process_expire_offer(resolver: &mut AccountsResolver) -> ProgramResult {
+        let offer_account = resolver.next_program_account()?;
+        let mut offer = load_offer(offer_account)?;
+        let mint = offer.payment_mint();
+        let (vault_account, bump) = next_registered_vault(resolver, &mint)?;
+        let vault_seeds: &[&[u8]] = &[VAULT_SEED, mint.as_ref(), &[bump]];
+        let seller_account = resolver.next_token_account()?;
+        let buyer_account = resolver.next_token_account()?;
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+
+        let locked_before = offer.escrowed_amount();
+        offer.expire_offer(vault_account, seller_account, buyer_account, vault_seeds, time::now()?)?;
+        release_nft(resolver, offer_account, &offer, &offer.seller_pubkey())?;
+        stats.release_value(vault_account.key, safe_math::sub(locked_before, offer.escrowed_amount())?);
+
+        save_offer(offer_account, &mut offer, vault_account.key)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    fn process_update_offer(
+        resolver: &mut AccountsResolver,
+        goodsorservice_description: Option<String>,
+        meeting_point: Option<Location>,
+        meeting_datetime: Option<i64>,
+        payment: Option<u64>,
+    ) -> ProgramResult {
+        if let Some(description) = &goodsorservice_description {
+            validation::description(description)?;
+        }
+        if let Some(meeting_point) = &meeting_point {
+            validation::location(meeting_point)?;
+        }
+        let offer_account = resolver.next_program_account()?;
+        let mut offer = load_offer(offer_account)?;
+        let seller = load_user(resolver.next_program_account()?)?;
+        if seller.pubkey != offer.seller_pubkey() {
+            return Err(DLUError::KeyMismatch.into());
+        }
+        let seller_authority_info = resolver.next_signer_for(&seller.pubkey)?;
+        let seller_account = resolver.next_token_account()?;
+        let mint = offer.payment_mint();
+        let (vault_account, bump) = next_registered_vault(resolver, &mint)?;
+        let vault_seeds: &[&[u8]] = &[VAULT_SEED, mint.as_ref(), &[bump]];
+        let config = load_config(resolver.next_config()?)?;
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+
+        let locked_before = offer.escrowed_amount();
+        offer.update_offer(
+            &seller,
+            goodsorservice_description,
+            meeting_point,
+            meeting_datetime,
+            payment,
+            seller_account,
+            seller_authority_info,
+            vault_account,
+            vault_seeds,
+        )?;
+
+        // A top-up counts against the TVL cap like any other deposit.
+        let locked_after = offer.escrowed_amount();
+        if locked_after > locked_before {
+            stats.lock_value(&config, vault_account.key, safe_math::sub(locked_after, locked_before)?)?;
+        } else {
+            stats.release_value(vault_account.key, safe_math::sub(locked_before, locked_after)?);
+        }
+
+        save_offer(offer_account, &mut offer, seller_authority_info.key)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    fn process_cancel_offer(resolver: &mut AccountsResolver) -> ProgramResult {
+        let offer_account = resolver.next_program_account()?;
+        let mut offer = load_offer(offer_account)?;
+        let seller_info = resolver.next_signer_for(&offer.seller_pubkey())?;
+        let seller_account = resolver.next_token_account()?;
+        let mint = offer.payment_mint();
+        let (vault_account, bump) = next_registered_vault(resolver, &mint)?;
+        let vault_seeds: &[&[u8]] = &[VAULT_SEED, mint.as_ref(), &[bump]];
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+        let index_account = resolver.next_index(&offer.seller_pubkey())?;
+        let mut index = load_index(index_account)?;
+
+        let locked_before = offer.escrowed_amount();
+        offer.cancel_offer(seller_account, vault_account, vault_seeds)?;
+        release_nft(resolver, offer_account, &offer, &offer.seller_pubkey())?;
+        stats.release_value(vault_account.key, safe_math::sub(locked_before, offer.escrowed_amount())?);
+        index.cancel_offer(offer_account.key);
+
+        save_offer(offer_account, &mut offer, seller_info.key)?;
+        save_index(index_account, &index)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    fn process_relist_offer(resolver: &mut AccountsResolver) -> ProgramResult {
+        let offer_account = resolver.next_program_account()?;
+        let seller_user_account = resolver.next_program_account()?;
+        let mut offer = load_offer(offer_account)?;
+        let mut seller = load_user(seller_user_account)?;
+        let seller_authority_info = resolver.next_signer_for(&seller.pubkey)?;
+        let seller_account = resolver.next_token_account()?;
+        let (vault_account, _) = next_registered_vault(resolver, &offer.payment_mint())?;
+        let config = load_config(resolver.next_config()?)?;
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+        let index_account = resolver.next_index(&seller.pubkey)?;
+        let mut index = load_index(index_account)?;
+
+        config.check_terms_accepted(seller.accepted_terms_version)?;
+        check_not_banned(&seller)?;
+
+        let locked_before = offer.escrowed_amount();
+        offer.relist_offer(&mut seller, seller_account, seller_authority_info, vault_account)?;
+        deposit_nft(resolver, offer_account, &offer, seller_authority_info)?;
+        stats.lock_value(&config, vault_account.key, safe_math::sub(offer.escrowed_amount(), locked_before)?)?;
+        // Expired offers are still in the active list; drop them first so they aren't listed twice.
+        index.remove_offer(offer_account.key);
+        index.add_offer(*offer_account.key);
+        msg!("Offer relisted as generation {}", offer.generation());
+
+        save_offer(offer_account, &mut offer, seller_authority_info.key)?;
+        save_user(seller_user_account, &seller)?;
+        save_index(index_account, &index)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    fn process_cancel_all_listed(resolver: &mut AccountsResolver, max_count: u8) -> ProgramResult {
+        let seller_info = resolver.next_signer()?;
+        let index_account = resolver.next_index(seller_info.key)?;
+        let mut index = load_index(index_account)?;
+        let seller_account = resolver.next_token_account()?;
+        let mint = DLUToken::get_mint(seller_account)?;
+        let (vault_account, bump) = next_registered_vault(resolver, &mint)?;
+        let vault_seeds: &[&[u8]] = &[VAULT_SEED, mint.as_ref(), &[bump]];
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+
+        let count = max_count.min(MAX_CANCEL_PER_CALL) as usize;
+        let page: Vec<Pubkey> = index.active_offers.iter().take(count).copied().collect();
+        for offer_key in page.iter() {
+            let offer_account = resolver.next_with_key(offer_key)?;
+            let mut offer = load_offer(offer_account)?;
+            if offer.seller_pubkey() != *seller_info.key {
+                return Err(DLUError::NotAuthorized.into());
+            }
+            // Each NFT offer needs its own escrow accounts to return the NFT.
+            if matches!(offer.kind(), OfferKind::Nft { .. }) {
+                return Err(DLUError::NftOfferNotBatchable.into());
+            }
+
+            let locked_before = offer.escrowed_amount();
+            offer.cancel_offer(seller_account, vault_account, vault_seeds)?;
+            stats.release_value(vault_account.key, safe_math::sub(locked_before, offer.escrowed_amount())?);
+            index.cancel_offer(offer_key);
+
+            save_offer(offer_account, &mut offer, seller_info.key)?;
+        }
+        msg!("Canceled {} offers, {} still listed", page.len(), index.active_offers.len());
+
+        save_index(index_account, &index)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    // REQUESTS
+
+    fn process_list_request(
+        resolver: &mut AccountsResolver,
+        id: u64,
+        goodsorservice_name: String,
+        goodsorservice_description: String,
+        payment: u64,
+        meeting_point: Location,
+        meeting_datetime: i64,
+        meeting_point_hash: Option<[u8; 32]>,
+    ) -> ProgramResult {
+        validation::item_name(&goodsorservice_name)?;
+        validation::description(&goodsorservice_description)?;
+        validation::location(&meeting_point)?;
+        let region = meeting_point.region();
+        let request_account = resolver.next_fresh_account()?;
+        let buyer = load_user(resolver.next_program_account()?)?;
+        let buyer_authority_info = resolver.next_signer_for(&buyer.pubkey)?;
+        let buyer_account = resolver.next_token_account()?;
+        let (vault_account, _) = next_registered_vault(resolver, &DLUToken::get_mint(buyer_account)?)?;
+        let config = load_config(resolver.next_config()?)?;
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+
+        config.check_terms_accepted(buyer.accepted_terms_version)?;
+        check_not_banned(&buyer)?;
+
+        let mut request = Request::list_request(
+            id,
+            &buyer,
+            goodsorservice_name,
+            goodsorservice_description,
+            payment,
+            meeting_point,
+            meeting_point_hash,
+            meeting_datetime,
+            buyer_account,
+            buyer_authority_info,
+            vault_account,
+        )?;
+        add_to_region(resolver, region, *request_account.key, EntityType::Request, buyer_authority_info)?;
+        check_multisig(resolver, &buyer, buyer_authority_info.key, payment)?;
+
+        // Exceeding the cap fails the whole transaction, reverting the escrow transfer above.
+        stats.lock_value(&config, vault_account.key, request.escrowed_amount())?;
+        stats.record_listing();
+
+        save_request(request_account, &mut request, buyer_authority_info.key)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    fn process_accept_request(resolver: &mut AccountsResolver) -> ProgramResult {
+        let request_account = resolver.next_program_account()?;
+        let seller_user_account = resolver.next_program_account()?;
+        let mut request = load_request(request_account)?;
+        let mut seller = load_user(seller_user_account)?;
+        resolver.next_signer_for(&seller.pubkey)?;
+        let seller_account = resolver.next_token_account()?;
+        let (vault_account, _) = next_registered_vault(resolver, &request.payment_mint())?;
+        let authority_info = resolver.next_signer()?;
+        let config = load_config(resolver.next_config()?)?;
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+
+        check_not_banned(&seller)?;
+        request.check_active(time::now()?)?;
+        check_attestations(resolver, &config, request.payment(), &[request.buyer_pubkey(), seller.pubkey])?;
+        let locked_before = request.escrowed_amount();
+        request.accept_request(&mut seller, seller_account, vault_account, authority_info)?;
+
+        // Exceeding the cap fails the whole transaction, reverting the escrow transfer above.
+        stats.lock_value(&config, vault_account.key, safe_math::sub(request.escrowed_amount(), locked_before)?)?;
+
+        save_request(request_account, &mut request, &seller.pubkey)?;
+        save_user(seller_user_account, &seller)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    fn process_complete_request(
+        resolver: &mut AccountsResolver,
+        buyer_key: String,
+        seller_key: String,
+        nonce: u64,
+    ) -> ProgramResult {
+        let request_account = resolver.next_program_account()?;
+        let mut request = load_request(request_account)?;
+        request.check_nonce(nonce)?;
+        let seller_user_account = resolver.next_program_account()?;
+        let buyer_user_account = resolver.next_program_account()?;
+        let seller_account = resolver.next_token_account()?;
+        let buyer_account = resolver.next_token_account()?;
+        let mint = request.payment_mint();
+        let (vault_account, bump) = next_registered_vault(resolver, &mint)?;
+        let vault_seeds: &[&[u8]] = &[VAULT_SEED, mint.as_ref(), &[bump]];
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+        let mut seller = load_user(seller_user_account)?;
+        let mut buyer = load_user(buyer_user_account)?;
+        let seller_summary_account = resolver.next_profile_summary(&seller.pubkey)?;
+        let buyer_summary_account = resolver.next_profile_summary(&buyer.pubkey)?;
+        let config = load_config(resolver.next_config()?)?;
+        let treasury_account = resolver.next_treasury()?;
+        decay_reputation(&mut seller, &config)?;
+        decay_reputation(&mut buyer, &config)?;
+
+        let locked_before = request.escrowed_amount();
+        let fee = request.complete_request(
+            buyer_key,
+            seller_key,
+            seller_account,
+            buyer_account,
+            vault_account,
+            vault_seeds,
+            treasury_account,
+            &config,
+            &mut seller,
+            &mut buyer,
+            time::now()?,
+        )?;
+        stats.release_value(vault_account.key, safe_math::sub(locked_before, request.escrowed_amount())?);
+        stats.record_completion();
+        if fee > 0 {
+            DLUEvent::FeeCollected {
+                entity_type: EntityType::Request,
+                entity: *request_account.key,
+                payee: seller.pubkey,
+                amount: fee,
+                slot: time::slot()?,
+            }.emit();
+            credit_referrer(resolver, &config, &seller, treasury_account, fee)?;
+        }
+        let entity = (EntityType::Request, *request_account.key);
+        let parties = [(seller_account, &seller.pubkey), (buyer_account, &buyer.pubkey)];
+        pay_completion_rewards(resolver, &config, entity, treasury_account, &parties)?;
+        update_leaderboards(resolver, &seller, &SELLER_LEADERBOARDS)?;
+
+        save_request(request_account, &mut request, vault_account.key)?;
+        save_user(seller_user_account, &seller)?;
+        save_user(buyer_user_account, &buyer)?;
+        refresh_profile_summary(seller_summary_account, &seller)?;
+        refresh_profile_summary(buyer_summary_account, &buyer)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    fn process_fail_request(resolver: &mut AccountsResolver, seller_key: String, nonce: u64) -> ProgramResult {
+        let request_account = resolver.next_program_account()?;
+        let mut request = load_request(request_account)?;
+        request.check_nonce(nonce)?;
+        let buyer_user_account = resolver.next_program_account()?;
+        let mint = request.payment_mint();
+        let (vault_account, bump) = next_registered_vault(resolver, &mint)?;
+        let vault_seeds: &[&[u8]] = &[VAULT_SEED, mint.as_ref(), &[bump]];
+        let penalty_account = resolver.next_token_account()?;
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+
+        let mut buyer = load_user(buyer_user_account)?;
+        let buyer_summary_account = resolver.next_profile_summary(&buyer.pubkey)?;
+
+        let (seller_pubkey, _) = request.parties().ok_or(DLUError::CounterpartyNotFound)?;
+        let config = load_config(resolver.next_config()?)?;
+        let seller_info = resolver.next_signer_for(&seller_pubkey)?;
+        let seller_account = resolver.next_token_account()?;
+        let bond_account = resolver.next_bond(request_account.key)?;
+        let system_program_info = resolver.next_with_key(&system_program::id())?;
+        let payer_info = resolver.next_payer(seller_info);
+        decay_reputation(&mut buyer, &config)?;
+        let buyer_account = resolver.next_token_account()?;
+
+        let locked_before = request.escrowed_amount();
+        let penalty = request.fail_request(
+            seller_key,
+            &mut buyer,
+            seller_account,
+            buyer_account,
+            vault_account,
+            penalty_account,
+            vault_seeds,
+            &config,
+            time::now()?,
+        )?;
+        stats.release_value(vault_account.key, safe_math::sub(locked_before, request.escrowed_amount())?);
+        stats.record_failure(penalty);
+        post_fail_bond(
+            resolver.program_id(), &config, bond_account, EntityType::Request, request_account.key, seller_info,
+            seller_account, &buyer.pubkey, vault_account, payer_info, system_program_info, &mut stats,
+        )?;
+
+        save_request(request_account, &mut request, seller_info.key)?;
+        save_user(buyer_user_account, &buyer)?;
+        refresh_profile_summary(buyer_summary_account, &buyer)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    fn process_expire_request(resolver: &mut AccountsResolver) -> ProgramResult {
+        let request_account = resolver.next_program_account()?;
+        let mut request = load_request(request_account)?;
+        let mint = request.payment_mint();
+        let (vault_account, bump) = next_registered_vault(resolver, &mint)?;
+        let vault_seeds: &[&[u8]] = &[VAULT_SEED, mint.as_ref(), &[bump]];
+        let seller_account = resolver.next_token_account()?;
+        let buyer_account = resolver.next_token_account()?;
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+
+        let locked_before = request.escrowed_amount();
+        request.expire_request(vault_account, seller_account, buyer_account, vault_seeds, time::now()?)?;
+        stats.release_value(vault_account.key, safe_math::sub(locked_before, request.escrowed_amount())?);
+
+        save_request(request_account, &mut request, vault_account.key)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    fn process_cancel_request(resolver: &mut AccountsResolver) -> ProgramResult {
+        let request_account = resolver.next_program_account()?;
+        let mut request = load_request(request_account)?;
+        let buyer_info = resolver.next_signer_for(&request.buyer_pubkey())?;
+        let buyer_account = resolver.next_token_account()?;
+        let mint = request.payment_mint();
+        let (vault_account, bump) = next_registered_vault(resolver, &mint)?;
+        let vault_seeds: &[&[u8]] = &[VAULT_SEED, mint.as_ref(), &[bump]];
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+
+        let locked_before = request.escrowed_amount();
+        request.cancel_request(buyer_account, vault_account, vault_seeds)?;
+        stats.release_value(vault_account.key, safe_math::sub(locked_before, request.escrowed_amount())?);
+
+        save_request(request_account, &mut request, buyer_info.key)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    fn process_relist_request(resolver: &mut AccountsResolver) -> ProgramResult {
+        let request_account = resolver.next_program_account()?;
+        let buyer_user_account = resolver.next_program_account()?;
+        let mut request = load_request(request_account)?;
+        let mut buyer = load_user(buyer_user_account)?;
+        let buyer_authority_info = resolver.next_signer_for(&buyer.pubkey)?;
+        let buyer_account = resolver.next_token_account()?;
+        let (vault_account, _) = next_registered_vault(resolver, &request.payment_mint())?;
+        let config = load_config(resolver.next_config()?)?;
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+
+        config.check_terms_accepted(buyer.accepted_terms_version)?;
+        check_not_banned(&buyer)?;
+
+        let locked_before = request.escrowed_amount();
+        request.relist_request(&mut buyer, buyer_account, buyer_authority_info, vault_account)?;
+        stats.lock_value(&config, vault_account.key, safe_math::sub(request.escrowed_amount(), locked_before)?)?;
+        msg!("Request relisted as generation {}", request.generation());
+
+        save_request(request_account, &mut request, buyer_authority_info.key)?;
+        save_user(buyer_user_account, &buyer)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    // SHIPMENTS
+
+    fn process_list_shipment(
+        resolver: &mut AccountsResolver,
+        id: u64,
+        items_name: String,
+        quantity: u32,
+        payment: u64,
+        insurance: u64,
+        pickup_point: Location,
+        pickup_datetime: i64,
+        drop_off_point: Location,
+        drop_off_datetime: i64,
+    ) -> ProgramResult {
+        validation::item_name(&items_name)?;
+        validation::location(&pickup_point)?;
+        validation::location(&drop_off_point)?;
+        let region = pickup_point.region();
+        let shipment_account = resolver.next_fresh_account()?;
+        let sender = load_user(resolver.next_program_account()?)?;
+        let sender_authority_info = resolver.next_signer()?;
+        let sender_account = resolver.next_token_account()?;
+        let (vault_account, _) = next_registered_vault(resolver, &DLUToken::get_mint(sender_account)?)?;
+        let recipient = load_user(resolver.next_program_account()?)?;
+        let config = load_config(resolver.next_config()?)?;
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+
+        config.check_terms_accepted(sender.accepted_terms_version)?;
+        check_not_banned(&sender)?;
+
+        let mut shipment = Shipment::list_shipment(
+            id,
+            &sender,
+            recipient,
+            items_name,
+            quantity,
+            payment,
+            insurance,
+            pickup_point,
+            pickup_datetime,
+            drop_off_point,
+            drop_off_datetime,
+            sender_account,
+            sender_authority_info,
+            vault_account,
+        )?;
+        add_to_region(resolver, region, *shipment_account.key, EntityType::Shipment, sender_authority_info)?;
+        check_member(resolver, sender_authority_info.key, &sender.pubkey, MEMBER_LISTER)?;
+        check_multisig(resolver, &sender, sender_authority_info.key, payment)?;
+
+        // Exceeding the cap fails the whole transaction, reverting the escrow transfer above.
+        stats.lock_value(&config, vault_account.key, shipment.escrowed_amount())?;
+        stats.record_listing();
+
+        save_shipment(shipment_account, &mut shipment)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    fn process_accept_shipment(resolver: &mut AccountsResolver) -> ProgramResult {
+        let shipment_account = resolver.next_program_account()?;
+        let carrier_user_account = resolver.next_program_account()?;
+        let mut shipment = load_shipment(shipment_account)?;
+        let mut carrier = load_user(carrier_user_account)?;
+        let carrier_info = resolver.next_signer()?;
+        let carrier_account = resolver.next_token_account()?;
+        let (vault_account, _) = next_registered_vault(resolver, &shipment.payment_mint())?;
+        let authority_info = resolver.next_signer()?;
+        let config = load_config(resolver.next_config()?)?;
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+
+        // High-value shipments need a staked carrier; accepting one locks the stake for another cooldown.
+        if config.requires_stake(shipment.payment()) {
+            let stake_account = resolver.next_stake(&carrier.pubkey)?;
+            let mut stake = load_stake(stake_account)?;
+            stake.commit(config.min_stake, time::now()?)?;
+            save_stake(stake_account, &stake)?;
+        }
+        check_member(resolver, carrier_info.key, &carrier.pubkey, MEMBER_ACCEPTOR)?;
+        check_not_banned(&carrier)?;
+        check_attestations(resolver, &config, shipment.payment(), &[shipment.sender_pubkey(), carrier.pubkey])?;
+
+        let locked_before = shipment.escrowed_amount();
+        shipment.accept_shipment(&mut carrier, carrier_account, vault_account, authority_info)?;
+
+        // Exceeding the cap fails the whole transaction, reverting the escrow transfer above.
+        stats.lock_value(&config, vault_account.key, safe_math::sub(shipment.escrowed_amount(), locked_before)?)?;
+
+        save_shipment(shipment_account, &mut shipment)?;
+        save_user(carrier_user_account, &carrier)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    fn process_complete_shipment(
+        resolver: &mut AccountsResolver,
+        carrier_key: String,
+        recipient_key: String,
+        proof_hash: Option<[u8; 32]>,
+        nonce: u64,
+    ) -> ProgramResult {
+        let shipment_account = resolver.next_program_account()?;
+        let mut shipment = load_shipment(shipment_account)?;
+        shipment.check_nonce(nonce)?;
+        let sender_user_account = resolver.next_program_account()?;
+        let carrier_user_account = resolver.next_program_account()?;
+        let sender_account = resolver.next_token_account()?;
+        let carrier_account = resolver.next_token_account()?;
+        let mint = shipment.payment_mint();
+        let (vault_account, bump) = next_registered_vault(resolver, &mint)?;
+        let vault_seeds: &[&[u8]] = &[VAULT_SEED, mint.as_ref(), &[bump]];
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+        let mut sender = load_user(sender_user_account)?;
+        let mut carrier = load_user(carrier_user_account)?;
+        let sender_summary_account = resolver.next_profile_summary(&sender.pubkey)?;
+        let carrier_summary_account = resolver.next_profile_summary(&carrier.pubkey)?;
+        let config = load_config(resolver.next_config()?)?;
+        let treasury_account = resolver.next_treasury()?;
+        decay_reputation(&mut sender, &config)?;
+        decay_reputation(&mut carrier, &config)?;
+
+        let locked_before = shipment.escrowed_amount();
+        let fee = shipment.complete_shipment(
+            carrier_key,
+            recipient_key,
+            proof_hash,
+            sender_account,
+            carrier_account,
+            vault_account,
+            vault_seeds,
+            treasury_account,
+            &config,
+            &mut sender,
+            &mut carrier,
+            time::now()?,
+        )?;
+        stats.release_value(vault_account.key, safe_math::sub(locked_before, shipment.escrowed_amount())?);
+        stats.record_completion();
+        if fee > 0 {
+            DLUEvent::FeeCollected {
+                entity_type: EntityType::Shipment,
+                entity: *shipment_account.key,
+                payee: carrier.pubkey,
+                amount: fee,
+                slot: time::slot()?,
+            }.emit();
+            credit_referrer(resolver, &config, &carrier, treasury_account, fee)?;
+        }
+        if let Some(proof_hash) = proof_hash {
+            DLUEvent::DeliveryProofSubmitted { shipment_id: shipment.id(), proof_hash, slot: time::slot()? }.emit();
+        }
+        let entity = (EntityType::Shipment, *shipment_account.key);
+        let parties = [(sender_account, &sender.pubkey), (carrier_account, &carrier.pubkey)];
+        pay_completion_rewards(resolver, &config, entity, treasury_account, &parties)?;
+        update_leaderboards(resolver, &carrier, &CARRIER_LEADERBOARDS)?;
+
+        save_shipment(shipment_account, &mut shipment)?;
+        save_user(sender_user_account, &sender)?;
+        save_user(carrier_user_account, &carrier)?;
+        refresh_profile_summary(sender_summary_account, &sender)?;
+        refresh_profile_summary(carrier_summary_account, &carrier)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    fn process_fail_shipment(resolver: &mut AccountsResolver, sender_key: String, nonce: u64) -> ProgramResult {
+        let shipment_account = resolver.next_program_account()?;
+        let mut shipment = load_shipment(shipment_account)?;
+        shipment.check_nonce(nonce)?;
+        let carrier_user_account = resolver.next_program_account()?;
+        let mint = shipment.payment_mint();
+        let (vault_account, bump) = next_registered_vault(resolver, &mint)?;
+        let vault_seeds: &[&[u8]] = &[VAULT_SEED, mint.as_ref(), &[bump]];
+        let penalty_account = resolver.next_token_account()?;
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+
+        let mut carrier = load_user(carrier_user_account)?;
+        let carrier_summary_account = resolver.next_profile_summary(&carrier.pubkey)?;
+
+        let config = load_config(resolver.next_config()?)?;
+        let sender_info = resolver.next_signer()?;
+        let sender_account = resolver.next_token_account()?;
+        let bond_account = resolver.next_bond(shipment_account.key)?;
+        let system_program_info = resolver.next_with_key(&system_program::id())?;
+        let payer_info = resolver.next_payer(sender_info);
+        check_member(resolver, sender_info.key, &shipment.sender_pubkey(), MEMBER_FINISHER)?;
+        decay_reputation(&mut carrier, &config)?;
+
+        let locked_before = shipment.escrowed_amount();
+        let penalty = shipment.fail_shipment(
+            sender_key,
+            &mut carrier,
+            sender_account,
+            vault_account,
+            penalty_account,
+            vault_seeds,
+            &config,
+            time::now()?,
+        )?;
+        stats.release_value(vault_account.key, safe_math::sub(locked_before, shipment.escrowed_amount())?);
+        stats.record_failure(penalty);
+        post_fail_bond(
+            resolver.program_id(), &config, bond_account, EntityType::Shipment, shipment_account.key, sender_info,
+            sender_account, &carrier.pubkey, vault_account, payer_info, system_program_info, &mut stats,
+        )?;
+        // The failure ends the carrier's streak.
+        update_leaderboards(resolver, &carrier, &CARRIER_LEADERBOARDS)?;
+
+        save_shipment(shipment_account, &mut shipment)?;
+        save_user(carrier_user_account, &carrier)?;
+        refresh_profile_summary(carrier_summary_account, &carrier)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    fn process_expire_shipment(resolver: &mut AccountsResolver) -> ProgramResult {
+        let shipment_account = resolver.next_program_account()?;
+        let mut shipment = load_shipment(shipment_account)?;
+        let mint = shipment.payment_mint();
+        let (vault_account, bump) = next_registered_vault(resolver, &mint)?;
+        let vault_seeds: &[&[u8]] = &[VAULT_SEED, mint.as_ref(), &[bump]];
+        let sender_account = resolver.next_token_account()?;
+        let carrier_account = resolver.next_token_account()?;
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+
+        let locked_before = shipment.escrowed_amount();
+        shipment.expire_shipment(vault_account, sender_account, carrier_account, vault_seeds, time::now()?)?;
+        stats.release_value(vault_account.key, safe_math::sub(locked_before, shipment.escrowed_amount())?);
+
+        save_shipment(shipment_account, &mut shipment)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    fn process_cancel_shipment(resolver: &mut AccountsResolver) -> ProgramResult {
+        let shipment_account = resolver.next_program_account()?;
+        let mut shipment = load_shipment(shipment_account)?;
+        resolver.next_signer_for(&shipment.sender_pubkey())?;
+        let sender_account = resolver.next_token_account()?;
+        let mint = shipment.payment_mint();
+        let (vault_account, bump) = next_registered_vault(resolver, &mint)?;
+        let vault_seeds: &[&[u8]] = &[VAULT_SEED, mint.as_ref(), &[bump]];
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+
+        let locked_before = shipment.escrowed_amount();
+        shipment.cancel_shipment(sender_account, vault_account, vault_seeds)?;
+        stats.release_value(vault_account.key, safe_math::sub(locked_before, shipment.escrowed_amount())?);
+
+        save_shipment(shipment_account, &mut shipment)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    fn process_relist_shipment(resolver: &mut AccountsResolver) -> ProgramResult {
+        let shipment_account = resolver.next_program_account()?;
+        let sender_user_account = resolver.next_program_account()?;
+        let mut shipment = load_shipment(shipment_account)?;
+        let mut sender = load_user(sender_user_account)?;
+        let sender_authority_info = resolver.next_signer_for(&sender.pubkey)?;
+        let sender_account = resolver.next_token_account()?;
+        let (vault_account, _) = next_registered_vault(resolver, &shipment.payment_mint())?;
+        let config = load_config(resolver.next_config()?)?;
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+
+        config.check_terms_accepted(sender.accepted_terms_version)?;
+        check_not_banned(&sender)?;
+
+        let locked_before = shipment.escrowed_amount();
+        shipment.relist_shipment(&mut sender, sender_account, sender_authority_info, vault_account)?;
+        stats.lock_value(&config, vault_account.key, safe_math::sub(shipment.escrowed_amount(), locked_before)?)?;
+        msg!("Shipment relisted as generation {}", shipment.generation());
+
+        save_shipment(shipment_account, &mut shipment)?;
+        save_user(sender_user_account, &sender)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    // REFUNDS
+
+    fn process_issue_goodwill_refund(
+        resolver: &mut AccountsResolver,
+        entity_type: EntityType,
+        amount: u64,
+    ) -> ProgramResult {
+        let deal_account = resolver.next_program_account()?;
+        let seller_user_account = resolver.next_program_account()?;
+        let mut seller = load_user(seller_user_account)?;
+        let seller_authority_info = resolver.next_signer_for(&seller.pubkey)?;
+        let buyer_user_account = resolver.next_program_account()?;
+        let mut buyer = load_user(buyer_user_account)?;
+        let seller_account = resolver.next_token_account()?;
+        let buyer_account = resolver.next_token_account()?;
+        let seller_summary_account = resolver.next_profile_summary(&seller.pubkey)?;
+        let config = load_config(resolver.next_config()?)?;
+        decay_reputation(&mut seller, &config)?;
+
+        let deal_id = match entity_type {
+            EntityType::Offer => {
+                let offer = load_offer(deal_account)?;
+                offer.goodwill_refund(&mut seller, &mut buyer, seller_account, buyer_account, seller_authority_info, amount)
+                    ?;
+                offer.id()
+            }
+            EntityType::Request => {
+                let request = load_request(deal_account)?;
+                request.goodwill_refund(&mut seller, &mut buyer, seller_account, buyer_account, seller_authority_info, amount)
+                    ?;
+                request.id()
+            }
+            EntityType::Shipment => return Err(DLUError::InvalidEntityType.into()),
+        };
+
+        save_user(seller_user_account, &seller)?;
+        save_user(buyer_user_account, &buyer)?;
+        refresh_profile_summary(seller_summary_account, &seller)?;
+
+        DLUEvent::GoodwillRefundIssued {
+            entity_type,
+            deal_id,
+            seller: seller.pubkey,
+            buyer: buyer.pubkey,
+            amount,
+            slot: time::slot()?,
+        }.emit();
+
+        Ok(())
+    }
+
+    // BONDS
+
+    fn process_set_fail_bond(resolver: &mut AccountsResolver, amount: u64) -> ProgramResult {
+        let config_account = resolver.next_config()?;
+        let governance_account = resolver.next_signer()?;
+
+        let mut config = load_config(config_account)?;
+        config.check_direct_update()?;
+        config.set_fail_bond(governance_account.key, amount)?;
+        msg!("Fail bond set to {}", config.fail_bond);
+
+        save_account_data(config_account, &config)
+    }
+
+    fn process_dispute_fail(resolver: &mut AccountsResolver) -> ProgramResult {
+        let entity_account = resolver.next_program_account()?;
+        let bond_account = resolver.next_bond(entity_account.key)?;
+        let accused_info = resolver.next_signer()?;
+
+        let mut bond = load_bond(bond_account)?;
+        bond.dispute(accused_info.key, time::now()?)?;
+
+        save_account_data(bond_account, &bond)
+    }
+
+    fn process_resolve_fail_dispute(resolver: &mut AccountsResolver, fail_justified: bool) -> ProgramResult {
+        let config = load_config(resolver.next_config()?)?;
+        let governance_account = resolver.next_signer()?;
+        config.check_governance(governance_account.key)?;
+
+        let entity_account = resolver.next_program_account()?;
+        let bond_account = resolver.next_bond(entity_account.key)?;
+        let mut bond = load_bond(bond_account)?;
+        let mint = load_payment_mint(entity_account, bond.entity_type)?;
+        let (vault_account, bump) = next_registered_vault(resolver, &mint)?;
+        let vault_seeds: &[&[u8]] = &[VAULT_SEED, mint.as_ref(), &[bump]];
+        let recipient_account = resolver.next_token_account()?;
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+
+        let recipient = bond.resolve(fail_justified)?;
+        settle_fail_bond(&bond, &recipient, vault_account, vault_seeds, recipient_account, &mut stats)?;
+
+        save_account_data(bond_account, &bond)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    fn process_reclaim_fail_bond(resolver: &mut AccountsResolver) -> ProgramResult {
+        let entity_account = resolver.next_program_account()?;
+        let bond_account = resolver.next_bond(entity_account.key)?;
+        let mut bond = load_bond(bond_account)?;
+        let claimant_info = resolver.next_signer()?;
+        let claimant_account = resolver.next_token_account()?;
+        let mint = load_payment_mint(entity_account, bond.entity_type)?;
+        let (vault_account, bump) = next_registered_vault(resolver, &mint)?;
+        let vault_seeds: &[&[u8]] = &[VAULT_SEED, mint.as_ref(), &[bump]];
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+
+        bond.reclaim(claimant_info.key, time::now()?)?;
+        settle_fail_bond(&bond, claimant_info.key, vault_account, vault_seeds, claimant_account, &mut stats)?;
+
+        save_account_data(bond_account, &bond)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    // FEES
+
+    fn process_set_fee(resolver: &mut AccountsResolver, fee_bps: u16, exempt_credible: bool) -> ProgramResult {
+        let config_account = resolver.next_config()?;
+        let governance_account = resolver.next_signer()?;
+
+        let mut config = load_config(config_account)?;
+        config.check_direct_update()?;
+        config.set_fee(governance_account.key, fee_bps, exempt_credible)?;
+        msg!("Fee set to {} bps", config.fee_bps);
+
+        save_account_data(config_account, &config)
+    }
+
+    fn process_set_penalty_share(resolver: &mut AccountsResolver, share_bps: u16) -> ProgramResult {
+        let config_account = resolver.next_config()?;
+        let governance_account = resolver.next_signer()?;
+
+        let mut config = load_config(config_account)?;
+        config.check_direct_update()?;
+        config.set_penalty_share(governance_account.key, share_bps)?;
+        msg!("Penalty share set to {} bps", config.penalty_share_bps);
+
+        save_account_data(config_account, &config)
+    }
+
+    fn process_withdraw_treasury(resolver: &mut AccountsResolver, amount: u64) -> ProgramResult {
+        let config = load_config(resolver.next_config()?)?;
+        let governance_account = resolver.next_signer()?;
+        config.check_governance(governance_account.key)?;
+        config.check_direct_update()?;
+
+        withdraw_treasury(resolver, amount)?;
+        Ok(())
+    }
+
+    // STAKING
+
+    fn process_set_stake_requirements(
+        resolver: &mut AccountsResolver,
+        stake_threshold: u64,
+        min_stake: u64,
+    ) -> ProgramResult {
+        let config_account = resolver.next_config()?;
+        let governance_account = resolver.next_signer()?;
+
+        let mut config = load_config(config_account)?;
+        config.check_direct_update()?;
+        config.set_stake_requirements(governance_account.key, stake_threshold, min_stake)?;
+        msg!("Stake of {} required above {}", config.min_stake, config.stake_threshold);
+
+        save_account_data(config_account, &config)
+    }
+
+    fn process_stake(resolver: &mut AccountsResolver, amount: u64) -> ProgramResult {
+        let stake_account = resolver.next()?;
+        let carrier_info = resolver.next_signer()?;
+        let (stake_key, stake_bump) = derive_stake_address(resolver.program_id(), carrier_info.key);
+        if *stake_account.key != stake_key {
+            return Err(DLUError::KeyMismatch.into());
+        }
+        let carrier_account = resolver.next_token_account()?;
+
+        // The first stake creates the account, held in the mint of the carrier's token account.
+        let mut stake = if stake_account.owner != resolver.program_id() {
+            CarrierStake::new(*carrier_info.key, DLUToken::get_mint(carrier_account)?)
+        } else {
+            load_stake(stake_account)?
+        };
+        let (vault_account, _) = next_registered_vault(resolver, &stake.mint)?;
+        let config = load_config(resolver.next_config()?)?;
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+        let system_program_info = resolver.next_with_key(&system_program::id())?;
+        let payer_info = resolver.next_payer(carrier_info);
+
+        let stake_seeds: &[&[u8]] = &[STAKE_SEED, carrier_info.key.as_ref(), &[stake_bump]];
+        create_pda_account(resolver.program_id(), stake_account, payer_info, system_program_info, CARRIER_STAKE_SIZE, stake_seeds)?;
+
+        stake.stake(amount, time::now()?)?;
+        DLUToken::transfer(carrier_account, vault_account, carrier_info, amount)?;
+        stats.lock_value(&config, vault_account.key, amount)?;
+        msg!("Carrier stake is now {}", stake.amount);
+
+        save_stake(stake_account, &stake)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    fn process_unstake(resolver: &mut AccountsResolver, amount: u64) -> ProgramResult {
+        let stake_account = resolver.next_program_account()?;
+        let mut stake = load_stake(stake_account)?;
+        resolver.next_signer_for(&stake.carrier)?;
+        let carrier_account = resolver.next_token_account()?;
+        let mint = stake.mint;
+        let (vault_account, bump) = next_registered_vault(resolver, &mint)?;
+        let vault_seeds: &[&[u8]] = &[VAULT_SEED, mint.as_ref(), &[bump]];
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+
+        if !DLUToken::check_authority(carrier_account, &stake.carrier)? {
+            return Err(DLUError::KeyMismatch.into());
+        }
+
+        stake.unstake(amount, time::now()?)?;
+        DLUToken::transfer_signed(vault_account, carrier_account, vault_account, amount, &[vault_seeds])?;
+        stats.release_value(vault_account.key, amount);
+        msg!("Carrier stake is now {}", stake.amount);
+
+        save_stake(stake_account, &stake)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    fn process_slash(resolver: &mut AccountsResolver) -> ProgramResult {
+        let shipment_account = resolver.next_program_account()?;
+        let bond_account = resolver.next_bond(shipment_account.key)?;
+        let mut shipment = load_shipment(shipment_account)?;
+        let carrier = shipment.carrier_pubkey().ok_or(DLUError::CounterpartyNotFound)?;
+        let stake_account = resolver.next_stake(&carrier)?;
+        let mut stake = load_stake(stake_account)?;
+        let mint = stake.mint;
+        let (vault_account, bump) = next_registered_vault(resolver, &mint)?;
+        let vault_seeds: &[&[u8]] = &[VAULT_SEED, mint.as_ref(), &[bump]];
+        let penalty_account = resolver.next_token_account()?;
+        let config = load_config(resolver.next_config()?)?;
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+
+        // Only a fail that can no longer be overturned costs the carrier their stake.
+        let bond = load_bond(bond_account)?;
+        if !bond.fail_upheld(time::now()?) {
+            return Err(DLUError::DisputeWindowOpen.into());
+        }
+
+        shipment.mark_stake_slashed()?;
+        let slashed = stake.slash(config.min_stake)?;
+        if slashed > 0 {
+            if config.has_capability(CAP_BURN_PENALTIES) {
+                Escrow::burn_penalty(vault_account, penalty_account, vault_seeds, slashed)?;
+            } else {
+                Escrow::transfer_to_penalty(vault_account, penalty_account, vault_seeds, slashed)?;
+            }
+            stats.release_value(vault_account.key, slashed);
+            stats.record_penalty(slashed);
+        }
+        msg!("Slashed {} from the carrier's stake", slashed);
+
+        save_shipment(shipment_account, &mut shipment)?;
+        save_stake(stake_account, &stake)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    // REVIEWS
+
+    fn process_submit_review(
+        resolver: &mut AccountsResolver,
+        entity_type: EntityType,
+        rating: u8,
+        review_hash: [u8; 32],
+    ) -> ProgramResult {
+        let entity_account = resolver.next_program_account()?;
+        let reviewer_info = resolver.next_signer()?;
+
+        let (deal_id, parties) = match entity_type {
+            EntityType::Offer => {
+                let offer = load_offer(entity_account)?;
+                if offer.status() != OfferStatus::Completed {
+                    return Err(DLUError::NotCompleted.into());
+                }
+                (offer.id(), offer.parties())
+            }
+            EntityType::Request => {
+                let request = load_request(entity_account)?;
+                if request.status() != RequestStatus::Completed {
+                    return Err(DLUError::NotCompleted.into());
+                }
+                (request.id(), request.parties())
+            }
+            EntityType::Shipment => {
+                let shipment = load_shipment(entity_account)?;
+                if !shipment.is_completed() {
+                    return Err(DLUError::NotCompleted.into());
+                }
+                (shipment.id(), shipment.carrier_pubkey().map(|carrier| (shipment.sender_pubkey(), carrier)))
+            }
+        };
+
+        // Each party reviews the other.
+        let (first, second) = parties.ok_or(DLUError::CounterpartyNotFound)?;
+        let reviewee = if *reviewer_info.key == first {
+            second
+        } else if *reviewer_info.key == second {
+            first
+        } else {
+            return Err(DLUError::NotDealParty.into());
+        };
+
+        let review_account = resolver.next_review(deal_id, reviewer_info.key)?;
+        if review_account.owner == resolver.program_id() {
+            return Err(DLUError::AlreadyReviewed.into());
+        }
+        let reviewee_user_account = resolver.next_program_account()?;
+        let mut reviewee_user = load_user(reviewee_user_account)?;
+        if reviewee_user.pubkey != reviewee {
+            return Err(DLUError::KeyMismatch.into());
+        }
+        let reviewee_summary_account = resolver.next_profile_summary(&reviewee)?;
+        let system_program_info = resolver.next_with_key(&system_program::id())?;
+        let payer_info = resolver.next_payer(reviewer_info);
+
+        let review = Review::submit(entity_type, deal_id, *reviewer_info.key, reviewee, rating, review_hash, time::now()?)?;
+        let (_, review_bump) = derive_review_address(resolver.program_id(), deal_id, reviewer_info.key);
+        let review_seeds: &[&[u8]] = &[REVIEW_SEED, &deal_id.to_le_bytes(), reviewer_info.key.as_ref(), &[review_bump]];
+        create_pda_account(resolver.program_id(), review_account, payer_info, system_program_info, REVIEW_SIZE, review_seeds)?;
+        reviewee_user.record_rating(rating);
+        msg!("Rated {} stars", rating);
+
+        save_account_data(review_account, &review)?;
+        save_user(reviewee_user_account, &reviewee_user)?;
+        refresh_profile_summary(reviewee_summary_account, &reviewee_user)
+    }
+
+    // BADGES
+
+    fn process_sync_badge(resolver: &mut AccountsResolver, tier: BadgeTier) -> ProgramResult {
+        let user_account = resolver.next_program_account()?;
+        let user = load_user(user_account)?;
+        let (mint_key, mint_bump) = derive_badge_mint_address(resolver.program_id(), tier);
+        let mint_info = resolver.next_with_key(&mint_key)?;
+        let (badge_key, badge_bump) = derive_badge_address(resolver.program_id(), &user.pubkey, tier);
+        let badge_account = resolver.next_with_key(&badge_key)?;
+        let metadata_account = resolver.next_with_key(&badge::metadata_address(&mint_key))?;
+        resolver.next_with_key(&spl_token::id())?;
+        let metadata_program_info = resolver.next_with_key(&TOKEN_METADATA_PROGRAM_ID)?;
+        let system_program_info = resolver.next_with_key(&system_program::id())?;
+        let payer_info = resolver.next_signer()?;
+
+        let mint_seeds: &[&[u8]] = &[BADGE_MINT_SEED, &[tier as u8], &[mint_bump]];
+        let badge_seeds: &[&[u8]] = &[BADGE_SEED, user.pubkey.as_ref(), &[tier as u8], &[badge_bump]];
+        let held = badge_account.owner == &spl_token::id() && DLUToken::get_balance(badge_account)? > 0;
+        let earned = tier.is_earned_by(user.status);
+
+        if earned && !held {
+            // The tier's first badge creates its mint and metadata, and a user's first badge
+            // of the tier creates their badge account.
+            if mint_info.owner != &spl_token::id() {
+                badge::create_badge_mint(payer_info, mint_info, mint_seeds)?;
+                badge::create_badge_metadata(
+                    tier,
+                    metadata_account,
+                    mint_info,
+                    payer_info,
+                    system_program_info,
+                    metadata_program_info,
+                    mint_seeds,
+                )?;
+            }
+            if badge_account.owner != &spl_token::id() {
+                DLUToken::create_self_owned_account(payer_info, badge_account, mint_info, badge_seeds)?;
+            }
+            badge::mint_badge(mint_info, badge_account, mint_seeds)?;
+            msg!("{:?} badge minted", tier);
+        } else if !earned && held {
+            badge::burn_badge(badge_account, mint_info, badge_seeds)?;
+            msg!("{:?} badge burned", tier);
+        }
+
+        Ok(())
+    }
+
+    // KEEPERS
+
+    fn process_set_crank_bounty(resolver: &mut AccountsResolver, bounty: u64) -> ProgramResult {
+        let config_account = resolver.next_config()?;
+        let governance_account = resolver.next_signer()?;
+
+        let mut config = load_config(config_account)?;
+        config.check_direct_update()?;
+        config.set_crank_bounty(governance_account.key, bounty)?;
+        msg!("Crank bounty set to {}", config.crank_bounty);
+
+        save_account_data(config_account, &config)
+    }
+
+    fn process_crank_expire(resolver: &mut AccountsResolver, entity_type: EntityType) -> ProgramResult {
+        let entity_account = resolver.next_program_account()?;
+        let mint = load_payment_mint(entity_account, entity_type)?;
+        let (vault_account, bump) = next_registered_vault(resolver, &mint)?;
+        let seller_or_sender_account = resolver.next_token_account()?;
+        let buyer_or_carrier_account = resolver.next_token_account()?;
+        let keeper_account = resolver.next_token_account()?;
+        let config = load_config(resolver.next_config()?)?;
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+
+        // The vault is its own authority, so the program signs the releases.
+        let vault_seeds: &[&[u8]] = &[VAULT_SEED, mint.as_ref(), &[bump]];
+        let now = time::now()?;
+
+        let (locked_before, locked_after, bounty) = match entity_type {
+            EntityType::Offer => {
+                let mut offer = load_offer(entity_account)?;
+                let locked_before = offer.escrowed_amount();
+                let bounty = offer.crank_expire_offer(
+                    vault_account, seller_or_sender_account, buyer_or_carrier_account, keeper_account,
+                    vault_seeds, config.crank_bounty, now,
+                )?;
+                release_nft(resolver, entity_account, &offer, &offer.seller_pubkey())?;
+                save_offer(entity_account, &mut offer, keeper_account.key)?;
+                (locked_before, offer.escrowed_amount(), bounty)
+            }
+            EntityType::Request => {
+                let mut request = load_request(entity_account)?;
+                let locked_before = request.escrowed_amount();
+                let bounty = request.crank_expire_request(
+                    vault_account, seller_or_sender_account, buyer_or_carrier_account, keeper_account,
+                    vault_seeds, config.crank_bounty, now,
+                )?;
+                save_request(entity_account, &mut request, keeper_account.key)?;
+                (locked_before, request.escrowed_amount(), bounty)
+            }
+            EntityType::Shipment => {
+                let mut shipment = load_shipment(entity_account)?;
+                let locked_before = shipment.escrowed_amount();
+                let bounty = shipment.crank_expire_shipment(
+                    vault_account, seller_or_sender_account, buyer_or_carrier_account, keeper_account,
+                    vault_seeds, config.crank_bounty, now,
+                )?;
+                save_shipment(entity_account, &mut shipment)?;
+                (locked_before, shipment.escrowed_amount(), bounty)
+            }
+        };
+        stats.release_value(vault_account.key, safe_math::sub(locked_before, locked_after)?);
+
+        DLUEvent::ExpiryCranked {
+            entity_type,
+            entity: *entity_account.key,
+            keeper_account: *keeper_account.key,
+            bounty,
+            slot: time::slot()?,
+        }
+        .emit();
+
+        save_account_data(stats_account, &stats)
+    }
+
+    // WALLETS
+
+    fn process_deposit_dlu(resolver: &mut AccountsResolver, amount: u64) -> ProgramResult {
+        let user_account = resolver.next_program_account()?;
+        let mut user = load_user(user_account)?;
+        let owner_info = resolver.next_signer_for(&user.pubkey)?;
+        let owner_token_account = resolver.next_token_account()?;
+        let wallet_account = resolver.next_wallet(&user.pubkey)?;
+
+        if amount == 0 {
+            return Err(DLUError::InvalidWalletAmount.into());
+        }
+
+        // With a transfer fee the owner covers it, so the wallet is credited the full amount.
+        if resolver.has_next() {
+            let mint_info = resolver.next()?;
+            let gross = DLUToken::gross_amount(mint_info, amount)?;
+            DLUToken::transfer_checked(owner_token_account, mint_info, wallet_account, owner_info, gross, &[])?;
+        } else {
+            DLUToken::transfer(owner_token_account, wallet_account, owner_info, amount)?;
+        }
+        user.wallet.sync(wallet_account)?;
+        DLUEvent::WalletDeposited {
+            user: user.pubkey,
+            amount,
+            balance: user.wallet.balance,
+            slot: time::slot()?,
+        }
+        .emit();
+
+        save_user(user_account, &user)
+    }
+
+    fn process_withdraw_dlu(resolver: &mut AccountsResolver, amount: u64) -> ProgramResult {
+        let user_account = resolver.next_program_account()?;
+        let mut user = load_user(user_account)?;
+        resolver.next_signer_for(&user.pubkey)?;
+        let owner_token_account = resolver.next_token_account()?;
+        let wallet_account = resolver.next_wallet(&user.pubkey)?;
+
+        if amount == 0 {
+            return Err(DLUError::InvalidWalletAmount.into());
+        }
+        if !DLUToken::check_authority(owner_token_account, &user.pubkey)? {
+            return Err(DLUError::KeyMismatch.into());
+        }
+        // The token account, not the cached balance, decides what can be withdrawn.
+        if DLUToken::get_balance(wallet_account)? < amount {
+            return Err(DLUError::InsufficientFunds.into());
+        }
+
+        check_multisig(resolver, &user, &user.pubkey, amount)?;
+
+        let (_, bump) = derive_wallet_address(resolver.program_id(), &user.pubkey);
+        let wallet_seeds: &[&[u8]] = &[WALLET_SEED, user.pubkey.as_ref(), &[bump]];
+        // A transfer fee is withheld from what reaches the owner's token account.
+        if resolver.has_next() {
+            let mint_info = resolver.next()?;
+            DLUToken::transfer_checked(
+                wallet_account, mint_info, owner_token_account, wallet_account, amount, &[wallet_seeds],
+            )?;
+        } else {
+            DLUToken::transfer_signed(wallet_account, owner_token_account, wallet_account, amount, &[wallet_seeds])?;
+        }
+        user.wallet.sync(wallet_account)?;
+        DLUEvent::WalletWithdrawn {
+            user: user.pubkey,
+            amount,
+            balance: user.wallet.balance,
+            slot: time::slot()?,
+        }
+        .emit();
+
+        save_user(user_account, &user)
+    }
+
+    fn process_reconcile_wallet(resolver: &mut AccountsResolver) -> ProgramResult {
+        let user_account = resolver.next_program_account()?;
+        let mut user = load_user(user_account)?;
+        let wallet_account = resolver.next_wallet(&user.pubkey)?;
+
+        let recorded = user.wallet.sync(wallet_account)?;
+        if recorded == user.wallet.balance {
+            msg!("Wallet balance already matches its token account");
+            return Ok(());
+        }
+        DLUEvent::WalletReconciled {
+            user: user.pubkey,
+            recorded,
+            actual: user.wallet.balance,
+            slot: time::slot()?,
+        }
+        .emit();
+
+        save_user(user_account, &user)
+    }
+
+    // QUOTES
+
+    fn process_submit_quote(resolver: &mut AccountsResolver, price: u64, note: String) -> ProgramResult {
+        let request_account = resolver.next_program_account()?;
+        let request = load_request(request_account)?;
+        let quote_account = resolver.next_program_account()?;
+        let seller = load_user(resolver.next_program_account()?)?;
+        let seller_info = resolver.next_signer_for(&seller.pubkey)?;
+        let (quote_key, _) = derive_quote_address(resolver.program_id(), request_account.key, &seller.pubkey);
+        if *quote_account.key != quote_key {
+            return Err(DLUError::KeyMismatch.into());
+        }
+        let seller_account = resolver.next_token_account()?;
+        let (vault_account, _) = next_registered_vault(resolver, &request.payment_mint())?;
+        let config = load_config(resolver.next_config()?)?;
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+
+        config.check_terms_accepted(seller.accepted_terms_version)?;
+
+        // A withdrawn or spent quote may be replaced by a new one.
+        if quote_account.data.borrow().iter().any(|b| *b != 0) && load_quote(quote_account)?.is_open() {
+            return Err(DLUError::QuoteAlreadyOpen.into());
+        }
+        let quote = Quote::submit(*request_account.key, &request, seller.pubkey, price, note, time::now()?)?;
+        DLUToken::check_payment_mint(&quote.mint, &[seller_account, vault_account])?;
+
+        // The seller's insurance equals the price they quote.
+        DLUToken::transfer(seller_account, vault_account, seller_info, price)?;
+        stats.lock_value(&config, vault_account.key, price)?;
+
+        save_quote(quote_account, &quote)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    fn process_select_quote(resolver: &mut AccountsResolver) -> ProgramResult {
+        let request_account = resolver.next_program_account()?;
+        let mut request = load_request(request_account)?;
+        let buyer_authority_info = resolver.next_signer_for(&request.buyer_pubkey())?;
+        let buyer_account = resolver.next_token_account()?;
+        let mint = request.payment_mint();
+        let (vault_account, bump) = next_registered_vault(resolver, &mint)?;
+        let vault_seeds: &[&[u8]] = &[VAULT_SEED, mint.as_ref(), &[bump]];
+        let quote_account = resolver.next_program_account()?;
+        let mut quote = load_quote(quote_account)?;
+        let (quote_key, _) = derive_quote_address(resolver.program_id(), request_account.key, &quote.seller);
+        if *quote_account.key != quote_key {
+            return Err(DLUError::KeyMismatch.into());
+        }
+        let seller_user_account = resolver.next_program_account()?;
+        let mut seller = load_user(seller_user_account)?;
+        if seller.pubkey != quote.seller {
+            return Err(DLUError::KeyMismatch.into());
+        }
+        let config = load_config(resolver.next_config()?)?;
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+
+        quote.select(request_account.key, &request)?;
+        request.check_active(time::now()?)?;
+        check_attestations(resolver, &config, quote.price, &[request.buyer_pubkey(), seller.pubkey])?;
+
+        let locked_before = request.escrowed_amount();
+        request.accept_quote(
+            &mut seller,
+            quote.price,
+            buyer_account,
+            buyer_authority_info,
+            vault_account,
+            vault_seeds,
+        )?;
+        // The quote's insurance was counted when it was submitted and now belongs to the request.
+        let counted = safe_math::add(locked_before, quote.price)?;
+        let locked_after = request.escrowed_amount();
+        if locked_after > counted {
+            stats.lock_value(&config, vault_account.key, safe_math::sub(locked_after, counted)?)?;
+        } else {
+            stats.release_value(vault_account.key, safe_math::sub(counted, locked_after)?);
+        }
+        msg!("Request accepted at a quoted {}", quote.price);
+
+        save_request(request_account, &mut request, buyer_authority_info.key)?;
+        save_quote(quote_account, &quote)?;
+        save_user(seller_user_account, &seller)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    fn process_withdraw_quote(resolver: &mut AccountsResolver) -> ProgramResult {
+        let quote_account = resolver.next_program_account()?;
+        let mut quote = load_quote(quote_account)?;
+        let seller_info = resolver.next_signer_for(&quote.seller)?;
+        let seller_account = resolver.next_token_account()?;
+        let mint = quote.mint;
+        let (vault_account, bump) = next_registered_vault(resolver, &mint)?;
+        let vault_seeds: &[&[u8]] = &[VAULT_SEED, mint.as_ref(), &[bump]];
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+
+        if !DLUToken::check_authority(seller_account, &quote.seller)? {
+            return Err(DLUError::KeyMismatch.into());
+        }
+        DLUToken::check_payment_mint(&quote.mint, &[seller_account, vault_account])?;
+
+        let amount = quote.withdraw(seller_info.key)?;
+        DLUToken::transfer_signed(vault_account, seller_account, vault_account, amount, &[vault_seeds])?;
+        stats.release_value(vault_account.key, amount);
+
+        save_quote(quote_account, &quote)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    // MATCHING
+
+    fn process_match_request_to_offer(resolver: &mut AccountsResolver) -> ProgramResult {
+        let offer_account = resolver.next_program_account()?;
+        let request_account = resolver.next_program_account()?;
+        let mut offer = load_offer(offer_account)?;
+        let mut request = load_request(request_account)?;
+        let buyer_user_account = resolver.next_program_account()?;
+        let mut buyer = load_user(buyer_user_account)?;
+        let buyer_account = resolver.next_token_account()?;
+        if !DLUToken::check_authority(buyer_account, &buyer.pubkey)? {
+            return Err(DLUError::KeyMismatch.into());
+        }
+        let mint = offer.payment_mint();
+        let (vault_account, bump) = next_registered_vault(resolver, &mint)?;
+        let vault_seeds: &[&[u8]] = &[VAULT_SEED, mint.as_ref(), &[bump]];
+        let matcher_account = resolver.next_token_account()?;
+        let config = load_config(resolver.next_config()?)?;
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+        let index_account = resolver.next_index(&offer.seller_pubkey())?;
+        let mut index = load_index(index_account)?;
+        let now = time::now()?;
+        offer.check_active(now)?;
+        request.check_active(now)?;
+        check_attestations(resolver, &config, offer.payment(), &[offer.seller_pubkey(), buyer.pubkey])?;
+
+        let locked_before = safe_math::add(offer.escrowed_amount(), request.escrowed_amount())?;
+        let fee = offer.match_request(
+            &mut request,
+            &mut buyer,
+            buyer_account,
+            vault_account,
+            vault_seeds,
+            matcher_account,
+        )?;
+        stats.release_value(vault_account.key, safe_math::sub(locked_before, offer.escrowed_amount())?);
+        index.accept_offer(offer_account.key, &offer.seller_pubkey(), &buyer.pubkey);
+
+        DLUEvent::DealsMatched {
+            offer: *offer_account.key,
+            request: *request_account.key,
+            matcher_account: *matcher_account.key,
+            fee,
+            slot: time::slot()?,
+        }
+        .emit();
+
+        save_offer(offer_account, &mut offer, vault_account.key)?;
+        save_request(request_account, &mut request, vault_account.key)?;
+        save_user(buyer_user_account, &buyer)?;
+        save_index(index_account, &index)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    // MEETING POINTS
+
+    fn process_reveal_meeting_point(
+        resolver: &mut AccountsResolver,
+        entity_type: EntityType,
+        meeting_point: Location,
+        salt: [u8; 32],
+    ) -> ProgramResult {
+        let deal_account = resolver.next_program_account()?;
+        let lister_info = resolver.next_signer()?;
+
+        let deal_id = match entity_type {
+            EntityType::Offer => {
+                let mut offer = load_offer(deal_account)?;
+                offer.reveal_meeting_point(lister_info.key, meeting_point, &salt)?;
+                save_offer(deal_account, &mut offer, lister_info.key)?;
+                offer.id()
+            }
+            EntityType::Request => {
+                let mut request = load_request(deal_account)?;
+                request.reveal_meeting_point(lister_info.key, meeting_point, &salt)?;
+                save_request(deal_account, &mut request, lister_info.key)?;
+                request.id()
+            }
+            EntityType::Shipment => return Err(DLUError::InvalidEntityType.into()),
+        };
+
+        DLUEvent::MeetingPointRevealed { entity_type, deal_id, slot: time::slot()? }.emit();
+        Ok(())
+    }
+
+    // MESSAGING
+
+    fn process_send_message(
+        resolver: &mut AccountsResolver,
+        entity_type: EntityType,
+        payload: Vec<u8>,
+    ) -> ProgramResult {
+        let entity_account = resolver.next_program_account()?;
+        let log_account = resolver.next_message_log(entity_account.key)?;
+        let sender_info = resolver.next_signer()?;
+
+        let parties = match entity_type {
+            EntityType::Offer => {
+                let (seller, buyer) = load_offer(entity_account)?.parties().ok_or(DLUError::NotAccepted)?;
+                vec![seller, buyer]
+            }
+            EntityType::Request => {
+                let (seller, buyer) = load_request(entity_account)?.parties().ok_or(DLUError::NotAccepted)?;
+                vec![seller, buyer]
+            }
+            EntityType::Shipment => {
+                let shipment = load_shipment(entity_account)?;
+                let carrier = shipment.carrier_pubkey().ok_or(DLUError::NotAccepted)?;
+                vec![shipment.sender_pubkey(), carrier, shipment.recipient_pubkey()]
+            }
+        };
+
+        let system_program_info = resolver.next_with_key(&system_program::id())?;
+        let payer_info = resolver.next_payer(sender_info);
+
+        // The deal's first message creates its log.
+        let mut log = if log_account.owner != resolver.program_id() {
+            let (_, bump) = derive_message_log_address(resolver.program_id(), entity_account.key);
+            let log_seeds: &[&[u8]] = &[MESSAGES_SEED, entity_account.key.as_ref(), &[bump]];
+            create_pda_account(resolver.program_id(), log_account, payer_info, system_program_info, MESSAGE_LOG_SIZE, log_seeds)?;
+            MessageLog::default()
+        } else {
+            load_account_data(log_account, MessageLog::deserialize)?
+        };
+        log.send(*entity_account.key, &parties, *sender_info.key, payload, time::now()?)?;
+        save_account_data(log_account, &log)?;
+
+        DLUEvent::MessageSent { entity: *entity_account.key, sender: *sender_info.key, slot: time::slot()? }.emit();
+        Ok(())
+    }
+
+    // PICKUPS
+
+    fn process_confirm_pickup(resolver: &mut AccountsResolver, sender_key: String) -> ProgramResult {
+        let shipment_account = resolver.next_program_account()?;
+        let mut shipment = load_shipment(shipment_account)?;
+        let carrier = shipment.carrier_pubkey().ok_or(DLUError::NotAccepted)?;
+        next_signer_or_delegate(resolver, &carrier, shipment_account.key)?;
+
+        let now = time::now()?;
+        shipment.confirm_pickup(&carrier, sender_key, now)?;
+        save_shipment(shipment_account, &mut shipment)?;
+
+        DLUEvent::ShipmentPickedUp { shipment_id: shipment.id(), carrier, picked_up_at: now }.emit();
+        Ok(())
+    }
+
+    // DELIVERY PROOFS
+
+    fn process_submit_proof(resolver: &mut AccountsResolver, proof_hash: [u8; 32]) -> ProgramResult {
+        let shipment_account = resolver.next_program_account()?;
+        let carrier_info = resolver.next_signer()?;
+        let mut shipment = load_shipment(shipment_account)?;
+
+        shipment.submit_proof(carrier_info.key, proof_hash)?;
+        save_shipment(shipment_account, &mut shipment)?;
+
+        DLUEvent::DeliveryProofSubmitted { shipment_id: shipment.id(), proof_hash, slot: time::slot()? }.emit();
+        Ok(())
+    }
+
+    // RETURNS
+
+    fn process_create_return_shipment(
+        resolver: &mut AccountsResolver,
+        id: u64,
+        payment: u64,
+        pickup_datetime: i64,
+        drop_off_datetime: i64,
+    ) -> ProgramResult {
+        let return_account = resolver.next_fresh_account()?;
+        let original_account = resolver.next_program_account()?;
+        if return_account.key == original_account.key {
+            return Err(DLUError::InvalidOperation.into());
+        }
+        let recipient = load_user(resolver.next_program_account()?)?;
+        let recipient_authority_info = resolver.next_signer_for(&recipient.pubkey)?;
+        let recipient_account = resolver.next_token_account()?;
+        let mut original = load_shipment(original_account)?;
+        let (vault_account, _) = next_registered_vault(resolver, &original.payment_mint())?;
+        let sender = load_user(resolver.next_program_account()?)?;
+        let config = load_config(resolver.next_config()?)?;
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+
+        config.check_terms_accepted(recipient.accepted_terms_version)?;
+
+        let mut reverse = original.create_return(
+            id,
+            &recipient,
+            sender,
+            payment,
+            pickup_datetime,
+            drop_off_datetime,
+            recipient_account,
+            recipient_authority_info,
+            vault_account,
+        )?;
+        add_to_region(resolver, reverse.pickup_point().region(), *return_account.key, EntityType::Shipment, recipient_authority_info)?;
+
+        // Exceeding the cap fails the whole transaction, reverting the escrow transfer above.
+        stats.lock_value(&config, vault_account.key, reverse.escrowed_amount())?;
+        stats.record_listing();
+
+        DLUEvent::ReturnShipmentCreated {
+            shipment_id: reverse.id(),
+            original_shipment_id: original.id(),
+            slot: time::slot()?,
+        }.emit();
+
+        save_shipment(return_account, &mut reverse)?;
+        save_shipment(original_account, &mut original)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    // HOLDBACKS
+
+    fn process_release_holdback(resolver: &mut AccountsResolver) -> ProgramResult {
+        let offer_account = resolver.next_program_account()?;
+        let seller_info = resolver.next_signer()?;
+        let seller_account = resolver.next_token_account()?;
+        let mut offer = load_offer(offer_account)?;
+        let mint = offer.payment_mint();
+        let (vault_account, bump) = next_registered_vault(resolver, &mint)?;
+        let vault_seeds: &[&[u8]] = &[VAULT_SEED, mint.as_ref(), &[bump]];
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+
+        let amount = offer.release_holdback(
+            seller_info.key,
+            seller_account,
+            vault_account,
+            vault_seeds,
+            time::now()?,
+        )?;
+        stats.release_value(vault_account.key, amount);
+
+        DLUEvent::HoldbackSettled {
+            offer_id: offer.id(),
+            recipient: *seller_info.key,
+            amount,
+            slot: time::slot()?,
+        }.emit();
+
+        save_offer(offer_account, &mut offer, seller_info.key)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    fn process_dispute_holdback(resolver: &mut AccountsResolver) -> ProgramResult {
+        let offer_account = resolver.next_program_account()?;
+        let buyer_info = resolver.next_signer()?;
+
+        let mut offer = load_offer(offer_account)?;
+        offer.dispute_holdback(buyer_info.key, time::now()?)?;
+
+        save_offer(offer_account, &mut offer, buyer_info.key)
+    }
+
+    fn process_resolve_holdback_dispute(resolver: &mut AccountsResolver, defective: bool) -> ProgramResult {
+        let config = load_config(resolver.next_config()?)?;
+        let governance_account = resolver.next_signer()?;
+        config.check_governance(governance_account.key)?;
+
+        let offer_account = resolver.next_program_account()?;
+        let mut offer = load_offer(offer_account)?;
+        let mint = offer.payment_mint();
+        let (vault_account, bump) = next_registered_vault(resolver, &mint)?;
+        let vault_seeds: &[&[u8]] = &[VAULT_SEED, mint.as_ref(), &[bump]];
+        let recipient_account = resolver.next_token_account()?;
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+
+        let (recipient, amount) = offer.resolve_holdback_dispute(
+            defective,
+            recipient_account,
+            vault_account,
+            vault_seeds,
+        )?;
+        stats.release_value(vault_account.key, amount);
+
+        DLUEvent::HoldbackSettled { offer_id: offer.id(), recipient, amount, slot: time::slot()? }.emit();
+
+        save_offer(offer_account, &mut offer, governance_account.key)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    // CHARGEBACKS
+
+    fn process_set_chargeback_window(resolver: &mut AccountsResolver, window: i64) -> ProgramResult {
+        let config_account = resolver.next_config()?;
+        let governance_account = resolver.next_signer()?;
+
+        let mut config = load_config(config_account)?;
+        config.check_direct_update()?;
+        config.set_chargeback_window(governance_account.key, window)?;
+        msg!("Chargeback window set to {}", config.chargeback_window);
+
+        save_account_data(config_account, &config)
+    }
+
+    fn process_open_chargeback(resolver: &mut AccountsResolver) -> ProgramResult {
+        let offer_account = resolver.next_program_account()?;
+        let buyer_info = resolver.next_signer()?;
+
+        let mut offer = load_offer(offer_account)?;
+        offer.open_chargeback(buyer_info.key, time::now()?)?;
+
+        DLUEvent::ChargebackOpened { offer_id: offer.id(), buyer: *buyer_info.key, slot: time::slot()? }.emit();
+
+        save_offer(offer_account, &mut offer, buyer_info.key)
+    }
+
+    fn process_release_seller_insurance(resolver: &mut AccountsResolver) -> ProgramResult {
+        let offer_account = resolver.next_program_account()?;
+        let seller_info = resolver.next_signer()?;
+        let seller_account = resolver.next_token_account()?;
+        let mut offer = load_offer(offer_account)?;
+        let mint = offer.payment_mint();
+        let (vault_account, bump) = next_registered_vault(resolver, &mint)?;
+        let vault_seeds: &[&[u8]] = &[VAULT_SEED, mint.as_ref(), &[bump]];
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+
+        let amount = offer.release_seller_insurance(
+            seller_info.key,
+            seller_account,
+            vault_account,
+            vault_seeds,
+            time::now()?,
+        )?;
+        stats.release_value(vault_account.key, amount);
+
+        save_offer(offer_account, &mut offer, seller_info.key)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    fn process_resolve_chargeback(resolver: &mut AccountsResolver, upheld: bool) -> ProgramResult {
+        let config = load_config(resolver.next_config()?)?;
+        let governance_account = resolver.next_signer()?;
+        config.check_governance(governance_account.key)?;
+
+        let offer_account = resolver.next_program_account()?;
+        let mut offer = load_offer(offer_account)?;
+        let mint = offer.payment_mint();
+        let (vault_account, bump) = next_registered_vault(resolver, &mint)?;
+        let vault_seeds: &[&[u8]] = &[VAULT_SEED, mint.as_ref(), &[bump]];
+        let recipient_account = resolver.next_token_account()?;
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+
+        let (recipient, amount) = offer.resolve_chargeback(upheld, recipient_account, vault_account, vault_seeds)?;
+        stats.release_value(vault_account.key, amount);
+
+        DLUEvent::ChargebackResolved { offer_id: offer.id(), recipient, amount, slot: time::slot()? }.emit();
+
+        save_offer(offer_account, &mut offer, governance_account.key)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    // ARBITRATION
+
+    fn process_set_arbitration_terms(resolver: &mut AccountsResolver, min_stake: u64, fee: u64) -> ProgramResult {
+        let config_account = resolver.next_config()?;
+        let governance_account = resolver.next_signer()?;
+
+        let mut config = load_config(config_account)?;
+        config.check_direct_update()?;
+        config.set_arbitration_terms(governance_account.key, min_stake, fee)?;
+        msg!("Arbiters stake {} and earn {} per ruling", config.arbiter_min_stake, config.arbitration_fee);
+
+        save_account_data(config_account, &config)
+    }
+
+    fn process_register_arbiter(resolver: &mut AccountsResolver, amount: u64) -> ProgramResult {
+        let arbiter_account = resolver.next()?;
+        let registry_account = resolver.next_arbiter_registry()?;
+        let user = load_user(resolver.next_program_account()?)?;
+        let owner_info = resolver.next_signer_for(&user.pubkey)?;
+        let (arbiter_key, arbiter_bump) = derive_arbiter_address(resolver.program_id(), owner_info.key);
+        if *arbiter_account.key != arbiter_key {
+            return Err(DLUError::KeyMismatch.into());
+        }
+        let owner_account = resolver.next_token_account()?;
+
+        // The first registration creates the account, held in the mint of the owner's token account.
+        let mut arbiter = if arbiter_account.owner != resolver.program_id() {
+            Arbiter::new(*owner_info.key, DLUToken::get_mint(owner_account)?)
+        } else {
+            load_arbiter(arbiter_account)?
+        };
+        let (vault_account, _) = next_registered_vault(resolver, &arbiter.mint)?;
+        let config = load_config(resolver.next_config()?)?;
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+        let mut registry = load_arbiter_registry(registry_account)?;
+        let system_program_info = resolver.next_with_key(&system_program::id())?;
+        let payer_info = resolver.next_payer(owner_info);
+
+        let arbiter_seeds: &[&[u8]] = &[ARBITER_SEED, owner_info.key.as_ref(), &[arbiter_bump]];
+        create_pda_account(resolver.program_id(), arbiter_account, payer_info, system_program_info, ARBITER_SIZE, arbiter_seeds)?;
+
+        let joining = !arbiter.registered;
+        arbiter.register(amount, config.arbiter_min_stake)?;
+        if joining {
+            registry.add(arbiter.owner)?;
+        }
+        DLUToken::transfer(owner_account, vault_account, owner_info, amount)?;
+        stats.lock_value(&config, vault_account.key, amount)?;
+        msg!("Arbiter stake is now {}", arbiter.stake);
+
+        save_account_data(arbiter_account, &arbiter)?;
+        save_account_data(registry_account, &registry)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    fn process_deregister_arbiter(resolver: &mut AccountsResolver) -> ProgramResult {
+        let arbiter_account = resolver.next_program_account()?;
+        let registry_account = resolver.next_arbiter_registry()?;
+        let mut arbiter = load_arbiter(arbiter_account)?;
+        resolver.next_signer_for(&arbiter.owner)?;
+
+        let mut registry = load_arbiter_registry(registry_account)?;
+        arbiter.deregister(time::now()?)?;
+        registry.remove(&arbiter.owner)?;
+
+        save_account_data(arbiter_account, &arbiter)?;
+        save_account_data(registry_account, &registry)
+    }
+
+    fn process_withdraw_arbiter_stake(resolver: &mut AccountsResolver) -> ProgramResult {
+        let arbiter_account = resolver.next_program_account()?;
+        let mut arbiter = load_arbiter(arbiter_account)?;
+        resolver.next_signer_for(&arbiter.owner)?;
+        let owner_account = resolver.next_token_account()?;
+        let mint = arbiter.mint;
+        let (vault_account, bump) = next_registered_vault(resolver, &mint)?;
+        let vault_seeds: &[&[u8]] = &[VAULT_SEED, mint.as_ref(), &[bump]];
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+
+        if !DLUToken::check_authority(owner_account, &arbiter.owner)? {
+            return Err(DLUError::KeyMismatch.into());
+        }
+
+        let amount = arbiter.withdraw(time::now()?)?;
+        DLUToken::transfer_signed(vault_account, owner_account, vault_account, amount, &[vault_seeds])?;
+        stats.release_value(vault_account.key, amount);
+
+        save_account_data(arbiter_account, &arbiter)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    fn process_assign_arbiter(resolver: &mut AccountsResolver, kind: DisputeKind) -> ProgramResult {
+        let offer_account = resolver.next_program_account()?;
+        let arbitration_account = resolver.next_arbitration(offer_account.key, kind)?;
+        let registry = load_arbiter_registry(resolver.next_arbiter_registry()?)?;
+        let slot_hash = arbiter::recent_slot_hash(resolver.next_slot_hashes()?)?;
+        let system_program_info = resolver.next_with_key(&system_program::id())?;
+        let payer_info = resolver.next_signer()?;
+        let now = time::now()?;
+
+        let (seller, buyer) = load_offer(offer_account)?.disputing_parties(kind)?;
+        if arbitration_account.data.borrow().iter().any(|b| *b != 0)
+            && !load_arbitration(arbitration_account)?.can_reassign(&registry, now)
+        {
+            return Err(DLUError::ArbiterAlreadyAssigned.into());
+        }
+
+        let drawn = registry.draw(&slot_hash, offer_account.key, &[seller, buyer])?;
+        let arbitration = Arbitration::assign(*offer_account.key, kind, drawn, now);
+        let program_id = resolver.program_id();
+        let (_, bump) = derive_arbitration_address(program_id, offer_account.key, kind);
+        let seeds: &[&[u8]] = &[ARBITRATION_SEED, offer_account.key.as_ref(), &[kind as u8], &[bump]];
+        create_pda_account(program_id, arbitration_account, payer_info, system_program_info, ARBITRATION_SIZE, seeds)?;
+
+        DLUEvent::ArbiterAssigned { entity: *offer_account.key, kind, arbiter: drawn, slot: time::slot()? }.emit();
+
+        save_account_data(arbitration_account, &arbitration)
+    }
+
+    fn process_rule_dispute(resolver: &mut AccountsResolver, kind: DisputeKind, for_buyer: bool) -> ProgramResult {
+        let config = load_config(resolver.next_config()?)?;
+        let arbiter_info = resolver.next_signer()?;
+        let arbiter_account = resolver.next_arbiter(arbiter_info.key)?;
+        let registry = load_arbiter_registry(resolver.next_arbiter_registry()?)?;
+        let offer_account = resolver.next_program_account()?;
+        let arbitration_account = resolver.next_arbitration(offer_account.key, kind)?;
+        let arbiter_token_account = resolver.next_token_account()?;
+        let treasury_account = resolver.next_treasury()?;
+
+        // Only an arbiter still registered, and so still slashable, can rule.
+        if !registry.contains(arbiter_info.key) {
+            return Err(DLUError::ArbiterNotRegistered.into());
+        }
+        // Governance may have settled the dispute since the arbiter was drawn.
+        let offer = load_offer(offer_account)?;
+        offer.disputing_parties(kind)?;
+        let mut arbitration = load_arbitration(arbitration_account)?;
+        arbitration.rule(arbiter_info.key, for_buyer, time::now()?)?;
+
+        // The treasury pays the fee in the offer's mint, as far as its balance covers it.
+        DLUToken::check_payment_mint(&offer.payment_mint(), &[treasury_account, arbiter_token_account])?;
+        if !DLUToken::check_authority(arbiter_token_account, arbiter_info.key)? {
+            return Err(DLUError::KeyMismatch.into());
+        }
+        let fee = config.arbitration_fee.min(DLUToken::get_balance(treasury_account)?);
+        if fee > 0 {
+            let mint = DLUToken::get_mint(treasury_account)?;
+            let (_, bump) = derive_treasury_address(resolver.program_id(), &mint);
+            DLUToken::transfer_signed(
+                treasury_account,
+                arbiter_token_account,
+                treasury_account,
+                fee,
+                &[&[TREASURY_SEED, mint.as_ref(), &[bump]]],
+            )?;
+        }
+        let mut arbiter = load_arbiter(arbiter_account)?;
+        arbiter.record_ruling(fee)?;
+
+        DLUEvent::DisputeRuled {
+            entity: *offer_account.key,
+            kind,
+            arbiter: *arbiter_info.key,
+            for_buyer,
+            fee,
+            slot: time::slot()?,
+        }.emit();
+
+        save_account_data(arbitration_account, &arbitration)?;
+        save_account_data(arbiter_account, &arbiter)
+    }
+
+    // APPEALS
+
+    fn process_set_appeal_bond(resolver: &mut AccountsResolver, bond: u64) -> ProgramResult {
+        let config_account = resolver.next_config()?;
+        let governance_account = resolver.next_signer()?;
+
+        let mut config = load_config(config_account)?;
+        config.check_direct_update()?;
+        config.set_appeal_bond(governance_account.key, bond)?;
+        msg!("Appeals post a bond of {}", config.appeal_bond);
+
+        save_account_data(config_account, &config)
+    }
+
+    fn process_appeal_ruling(resolver: &mut AccountsResolver, kind: DisputeKind) -> ProgramResult {
+        let offer_account = resolver.next_program_account()?;
+        let arbitration_account = resolver.next_arbitration(offer_account.key, kind)?;
+        let appeal_account = resolver.next_appeal(arbitration_account.key)?;
+        let registry = load_arbiter_registry(resolver.next_arbiter_registry()?)?;
+        let slot_hash = arbiter::recent_slot_hash(resolver.next_slot_hashes()?)?;
+        let appellant_info = resolver.next_signer()?;
+        let appellant_account = resolver.next_token_account()?;
+        let offer = load_offer(offer_account)?;
+        let (vault_account, _) = next_registered_vault(resolver, &offer.payment_mint())?;
+        let config = load_config(resolver.next_config()?)?;
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+        let system_program_info = resolver.next_with_key(&system_program::id())?;
+        let payer_info = resolver.next_payer(appellant_info);
+        let now = time::now()?;
+
+        let (seller, buyer) = offer.disputing_parties(kind)?;
+        let mut arbitration = load_arbitration(arbitration_account)?;
+        let appealed_ruling = arbitration.appeal(appellant_info.key, &seller, &buyer, now)?;
+        let (_, appeal_bump) = derive_appeal_address(resolver.program_id(), arbitration_account.key);
+        let appeal_seeds: &[&[u8]] = &[APPEAL_SEED, arbitration_account.key.as_ref(), &[appeal_bump]];
+        create_pda_account(resolver.program_id(), appeal_account, payer_info, system_program_info, APPEAL_SIZE, appeal_seeds)?;
+
+        // The panel never includes the parties or the arbiter whose ruling is appealed.
+        let excluded = [seller, buyer, arbitration.arbiter];
+        let drawn = registry.draw_panel(&slot_hash, arbitration_account.key, &excluded, APPEAL_PANEL_SIZE)?;
+        let mut panel = [Pubkey::default(); APPEAL_PANEL_SIZE];
+        panel.copy_from_slice(&drawn);
+
+        let bond = config.appeal_bond;
+        if bond > 0 {
+            DLUToken::check_payment_mint(&offer.payment_mint(), &[appellant_account, vault_account])?;
+            DLUToken::transfer(appellant_account, vault_account, appellant_info, bond)?;
+            stats.lock_value(&config, vault_account.key, bond)?;
+        }
+        let appeal = Appeal::file(*arbitration_account.key, *appellant_info.key, bond, appealed_ruling, panel, now);
+
+        DLUEvent::RulingAppealed {
+            entity: *offer_account.key,
+            kind,
+            appellant: *appellant_info.key,
+            bond,
+            panel,
+            slot: time::slot()?,
+        }.emit();
+
+        save_account_data(arbitration_account, &arbitration)?;
+        save_account_data(appeal_account, &appeal)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    fn process_vote_appeal(resolver: &mut AccountsResolver, kind: DisputeKind, for_buyer: bool) -> ProgramResult {
+        let offer_account = resolver.next_program_account()?;
+        let arbitration_account = resolver.next_arbitration(offer_account.key, kind)?;
+        let appeal_account = resolver.next_appeal(arbitration_account.key)?;
+        let panelist_info = resolver.next_signer()?;
+
+        let mut appeal = load_appeal(appeal_account)?;
+        appeal.vote(panelist_info.key, for_buyer, time::now()?)?;
+
+        save_account_data(appeal_account, &appeal)
+    }
+
+    fn process_settle_dispute(resolver: &mut AccountsResolver, kind: DisputeKind) -> ProgramResult {
+        let offer_account = resolver.next_program_account()?;
+        let arbitration_account = resolver.next_arbitration(offer_account.key, kind)?;
+        let mut offer = load_offer(offer_account)?;
+        let mint = offer.payment_mint();
+        let (vault_account, bump) = next_registered_vault(resolver, &mint)?;
+        let vault_seeds: &[&[u8]] = &[VAULT_SEED, mint.as_ref(), &[bump]];
+        let recipient_account = resolver.next_token_account()?;
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+        let now = time::now()?;
+
+        let arbitration = load_arbitration(arbitration_account)?;
+        let ruling = arbitration.ruling()?;
+        let mut appeal = None;
+        let for_buyer = if arbitration.appealed {
+            let appeal_account = resolver.next_appeal(arbitration_account.key)?;
+            let mut filed = load_appeal(appeal_account)?;
+            let decision = filed.settle(now)?;
+            appeal = Some((appeal_account, filed));
+            decision
+        } else if arbitration.in_appeal_window(now) {
+            return Err(DLUError::DisputeWindowOpen.into());
+        } else {
+            ruling
+        };
+
+        let (recipient, amount) = match kind {
+            DisputeKind::Holdback => offer.resolve_holdback_dispute(for_buyer, recipient_account, vault_account, vault_seeds)?,
+            DisputeKind::Chargeback => offer.resolve_chargeback(for_buyer, recipient_account, vault_account, vault_seeds)?,
+        };
+        stats.release_value(vault_account.key, amount);
+
+        if let Some((appeal_account, appeal)) = &appeal {
+            let appellant_account = resolver.next_token_account()?;
+            if appeal.status == AppealStatus::Lapsed {
+                pay_bond_share(vault_account, appellant_account, vault_seeds, &appeal.appellant, &mint, appeal.bond)?;
+            } else {
+                let (winner_share, panelist_share) = appeal.bond_shares();
+                pay_bond_share(vault_account, recipient_account, vault_seeds, &recipient, &mint, winner_share)?;
+                for panelist in appeal.majority_panelists() {
+                    let panelist_account = resolver.next_token_account()?;
+                    pay_bond_share(vault_account, panelist_account, vault_seeds, &panelist, &mint, panelist_share)?;
+                }
+            }
+            stats.release_value(vault_account.key, appeal.bond);
+            save_account_data(appeal_account, &appeal)?;
+        }
+
+        let settled = match kind {
+            DisputeKind::Holdback => DLUEvent::HoldbackSettled { offer_id: offer.id(), recipient, amount, slot: time::slot()? },
+            DisputeKind::Chargeback => DLUEvent::ChargebackResolved { offer_id: offer.id(), recipient, amount, slot: time::slot()? },
+        };
+        settled.emit();
+        DLUEvent::DisputeSettled {
+            entity: *offer_account.key,
+            kind,
+            for_buyer,
+            appeal: appeal.as_ref().map(|(_, appeal)| appeal.status),
+            slot: time::slot()?,
+        }.emit();
+
+        save_offer(offer_account, &mut offer, vault_account.key)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    fn process_slash_arbiter(resolver: &mut AccountsResolver, kind: DisputeKind) -> ProgramResult {
+        let offer_account = resolver.next_program_account()?;
+        let arbitration_account = resolver.next_arbitration(offer_account.key, kind)?;
+        let appeal_account = resolver.next_appeal(arbitration_account.key)?;
+        let arbitration = load_arbitration(arbitration_account)?;
+        let arbiter_account = resolver.next_arbiter(&arbitration.arbiter)?;
+        let mut arbiter = load_arbiter(arbiter_account)?;
+        let mint = arbiter.mint;
+        let (vault_account, bump) = next_registered_vault(resolver, &mint)?;
+        let vault_seeds: &[&[u8]] = &[VAULT_SEED, mint.as_ref(), &[bump]];
+        let penalty_account = resolver.next_token_account()?;
+        let config = load_config(resolver.next_config()?)?;
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+
+        let mut appeal = load_appeal(appeal_account)?;
+        appeal.mark_arbiter_slashed()?;
+        let slashed = arbiter.slash_overturned(appeal.bond)?;
+        if slashed > 0 {
+            if config.has_capability(CAP_BURN_PENALTIES) {
+                Escrow::burn_penalty(vault_account, penalty_account, vault_seeds, slashed)?;
+            } else {
+                Escrow::transfer_to_penalty(vault_account, penalty_account, vault_seeds, slashed)?;
+            }
+            stats.release_value(vault_account.key, slashed);
+            stats.record_penalty(slashed);
+        }
+        msg!("Slashed {} from the arbiter's stake", slashed);
+
+        save_account_data(appeal_account, &appeal)?;
+        save_account_data(arbiter_account, &arbiter)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    // SETTLEMENTS
+
+    fn process_settle_deal(resolver: &mut AccountsResolver, seller_bps: u16, buyer_bps: u16, nonce: u64) -> ProgramResult {
+        let offer_account = resolver.next_program_account()?;
+        let mut offer = load_offer(offer_account)?;
+        offer.check_nonce(nonce)?;
+        let mint = offer.payment_mint();
+        let (vault_account, bump) = next_registered_vault(resolver, &mint)?;
+        let vault_seeds: &[&[u8]] = &[VAULT_SEED, mint.as_ref(), &[bump]];
+        let seller_account = resolver.next_token_account()?;
+        let buyer_account = resolver.next_token_account()?;
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+
+        let (seller, buyer) = offer.parties().ok_or(DLUError::CounterpartyNotFound)?;
+
+        // Both parties agree on the split, or an arbiter who is neither of them imposes it.
+        let settler_info = resolver.next_signer()?;
+        let arbiter = if *settler_info.key == seller {
+            resolver.next_signer_for(&buyer)?;
+            None
+        } else if *settler_info.key == buyer {
+            return Err(DLUError::NotAuthorized.into());
+        } else {
+            let registry = load_arbiter_registry(resolver.next_arbiter_registry()?)?;
+            if !registry.contains(settler_info.key) {
+                return Err(DLUError::ArbiterNotRegistered.into());
+            }
+            Some(*settler_info.key)
+        };
+
+        let locked_before = offer.escrowed_amount();
+        let (seller_amount, buyer_amount) = offer.settle(
+            seller_bps,
+            buyer_bps,
+            seller_account,
+            buyer_account,
+            vault_account,
+            vault_seeds,
+        )?;
+        let nft_recipient = if seller_amount > 0 { buyer } else { seller };
+        release_nft(resolver, offer_account, &offer, &nft_recipient)?;
+        stats.release_value(vault_account.key, safe_math::sub(locked_before, offer.escrowed_amount())?);
+
+        DLUEvent::DealSettled {
+            entity: *offer_account.key,
+            seller_amount,
+            buyer_amount,
+            arbiter,
+            slot: time::slot()?,
+        }.emit();
+
+        save_offer(offer_account, &mut offer, settler_info.key)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    // SESSION KEYS
+
+    fn process_create_session(
+        resolver: &mut AccountsResolver,
+        session: Pubkey,
+        entities: Vec<Pubkey>,
+        expires_at: i64,
+    ) -> ProgramResult {
+        let owner_info = resolver.next_signer()?;
+        let delegation_account = resolver.next_delegation(owner_info.key, &session)?;
+        let system_program_info = resolver.next_with_key(&system_program::id())?;
+        let payer_info = resolver.next_payer(owner_info);
+
+        let delegation = Delegation::authorize(*owner_info.key, session, entities, expires_at, time::now()?)?;
+        msg!("Session key valid until {}", delegation.expires_at);
+
+        let (_, bump) = derive_delegation_address(resolver.program_id(), owner_info.key, &session);
+        let delegation_seeds: &[&[u8]] = &[DELEGATION_SEED, owner_info.key.as_ref(), session.as_ref(), &[bump]];
+        create_pda_account(resolver.program_id(), delegation_account, payer_info, system_program_info, DELEGATION_SIZE, delegation_seeds)?;
+
+        save_account_data(delegation_account, &delegation)
+    }
+
+    fn process_revoke_session(resolver: &mut AccountsResolver, session: Pubkey) -> ProgramResult {
+        let owner_info = resolver.next_signer()?;
+        let delegation_account = resolver.next_delegation(owner_info.key, &session)?;
+
+        let mut delegation = load_delegation(delegation_account)?;
+        delegation.revoke();
+
+        save_account_data(delegation_account, &delegation)
+    }
+
+    // ORGANIZATIONS
+
+    fn process_create_organization(resolver: &mut AccountsResolver) -> ProgramResult {
+        let owner_info = resolver.next_signer()?;
+        let organization_account = resolver.next_organization(owner_info.key)?;
+        let system_program_info = resolver.next_with_key(&system_program::id())?;
+        let payer_info = resolver.next_payer(owner_info);
+
+        // Recreating the organization would silently drop its members.
+        if organization_account.owner == resolver.program_id() {
+            return Err(DLUError::AlreadyInitialized.into());
+        }
+
+        let (_, bump) = derive_organization_address(resolver.program_id(), owner_info.key);
+        let organization_seeds: &[&[u8]] = &[ORGANIZATION_SEED, owner_info.key.as_ref(), &[bump]];
+        create_pda_account(resolver.program_id(), organization_account, payer_info, system_program_info, ORGANIZATION_SIZE, organization_seeds)?;
+        save_account_data(organization_account, &Organization::new(*owner_info.key))
+    }
+
+    fn process_set_member(resolver: &mut AccountsResolver, member: Pubkey, roles: u8) -> ProgramResult {
+        let owner_info = resolver.next_signer()?;
+        let organization_account = resolver.next_organization(owner_info.key)?;
+
+        let mut organization = load_organization(organization_account)?;
+        organization.set_member(member, roles)?;
+        msg!("Organization has {} members", organization.members.len());
+
+        save_account_data(organization_account, &organization)
+    }
+
+    // MULTISIGS
+
+    fn process_create_multisig(
+        resolver: &mut AccountsResolver,
+        threshold: u8,
+        signers: Vec<Pubkey>,
+        high_value: u64,
+    ) -> ProgramResult {
+        let user_account = resolver.next_program_account()?;
+        let mut user = load_user(user_account)?;
+        let owner_info = resolver.next_signer_for(&user.pubkey)?;
+        let multisig_account = resolver.next_multisig(&user.pubkey)?;
+        let system_program_info = resolver.next_with_key(&system_program::id())?;
+        let payer_info = resolver.next_payer(owner_info);
+
+        // Only the multisig itself may change how the user is governed from now on.
+        if user.has_flag(USER_MULTISIG) {
+            return Err(DLUError::AlreadyInitialized.into());
+        }
+        let multisig = Multisig::new(user.pubkey, threshold, signers, high_value)?;
+        user.set_flag(USER_MULTISIG, true);
+
+        let (_, bump) = derive_multisig_address(resolver.program_id(), &user.pubkey);
+        let multisig_seeds: &[&[u8]] = &[MULTISIG_SEED, user.pubkey.as_ref(), &[bump]];
+        create_pda_account(resolver.program_id(), multisig_account, payer_info, system_program_info, MULTISIG_SIZE, multisig_seeds)?;
+
+        save_account_data(multisig_account, &multisig)?;
+        save_user(user_account, &user)
+    }
+
+    fn process_update_multisig(
+        resolver: &mut AccountsResolver,
+        threshold: u8,
+        signers: Vec<Pubkey>,
+        high_value: u64,
+    ) -> ProgramResult {
+        let user = load_user(resolver.next_program_account()?)?;
+        let multisig_account = resolver.next_multisig(&user.pubkey)?;
+
+        let mut multisig = load_multisig(multisig_account)?;
+        multisig.check_threshold(&resolver.next_cosigners())?;
+        multisig.set(threshold, signers, high_value)?;
+        msg!("Multisig needs {} of {} signers", multisig.threshold, multisig.signers.len());
+
+        save_account_data(multisig_account, &multisig)
+    }
+
+    // MODERATION
+
+    fn process_set_user_banned(resolver: &mut AccountsResolver, banned: bool) -> ProgramResult {
+        let config = load_config(resolver.next_config()?)?;
+        let governance_account = resolver.next_signer()?;
+        let user_account = resolver.next_program_account()?;
+
+        let mut user = load_user(user_account)?;
+        moderation::set_banned(&config, governance_account.key, &mut user, banned)?;
+        DLUEvent::UserBanned { user: user.pubkey, banned, slot: time::slot()? }.emit();
+
+        save_user(user_account, &user)
+    }
+
+    // KYC
+
+    fn process_set_kyc_threshold(resolver: &mut AccountsResolver, threshold: u64) -> ProgramResult {
+        let config_account = resolver.next_config()?;
+        let governance_account = resolver.next_signer()?;
+
+        let mut config = load_config(config_account)?;
+        config.check_direct_update()?;
+        config.set_kyc_threshold(governance_account.key, threshold)?;
+        msg!("Deals above {} need attested parties", config.kyc_threshold);
+
+        save_account_data(config_account, &config)
+    }
+
+    fn process_set_attestor(resolver: &mut AccountsResolver, attestor: Pubkey, approved: bool) -> ProgramResult {
+        let config = load_config(resolver.next_config()?)?;
+        let governance_account = resolver.next_signer()?;
+        config.check_governance(governance_account.key)?;
+        let registry_account = resolver.next_attestor_registry()?;
+        let system_program_info = resolver.next_with_key(&system_program::id())?;
+
+        // The first approval creates the registry, with governance paying its rent.
+        let (_, registry_bump) = derive_attestor_registry_address(resolver.program_id());
+        let registry_seeds: &[&[u8]] = &[ATTESTOR_REGISTRY_SEED, &[registry_bump]];
+        create_pda_account(resolver.program_id(), registry_account, governance_account, system_program_info, ATTESTOR_REGISTRY_SIZE, registry_seeds)?;
+        let mut registry = load_attestor_registry(registry_account)?;
+        if approved {
+            registry.add(attestor)?;
+        } else {
+            registry.remove(&attestor)?;
+        }
+
+        save_account_data(registry_account, &registry)
+    }
+
+    fn process_attest(resolver: &mut AccountsResolver, expires_at: i64) -> ProgramResult {
+        let registry = load_attestor_registry(resolver.next_attestor_registry()?)?;
+        let attestor_info = resolver.next_signer()?;
+        let user = load_user(resolver.next_program_account()?)?;
+        let attestation_account = resolver.next_attestation(&user.pubkey)?;
+        let system_program_info = resolver.next_with_key(&system_program::id())?;
+        let payer_info = resolver.next_payer(attestor_info);
+
+        let attestation = Attestation::attest(&registry, *attestor_info.key, user.pubkey, expires_at)?;
+        let (_, bump) = derive_attestation_address(resolver.program_id(), &user.pubkey);
+        let attestation_seeds: &[&[u8]] = &[ATTESTATION_SEED, user.pubkey.as_ref(), &[bump]];
+        create_pda_account(resolver.program_id(), attestation_account, payer_info, system_program_info, ATTESTATION_SIZE, attestation_seeds)?;
+        DLUEvent::UserAttested { user: user.pubkey, attestor: attestation.attestor, expires_at, slot: time::slot()? }.emit();
+
+        save_account_data(attestation_account, &attestation)
+    }
+
+    // REFERRALS
+
+    fn process_set_referral_share(resolver: &mut AccountsResolver, share_bps: u16) -> ProgramResult {
+        let config_account = resolver.next_config()?;
+        let governance_account = resolver.next_signer()?;
+
+        let mut config = load_config(config_account)?;
+        config.check_direct_update()?;
+        config.set_referral_share(governance_account.key, share_bps)?;
+        msg!("Referrers earn {} bps of referred fees", config.referral_share_bps);
+
+        save_account_data(config_account, &config)
+    }
+
+    fn process_claim_referral_rewards(resolver: &mut AccountsResolver) -> ProgramResult {
+        let rewards_account = resolver.next_program_account()?;
+        let mut rewards = load_referral_rewards(rewards_account)?;
+        resolver.next_signer_for(&rewards.referrer)?;
+        let treasury_account = resolver.next_treasury()?;
+        let referrer_account = resolver.next_token_account()?;
+        if DLUToken::get_mint(treasury_account)? != rewards.mint {
+            return Err(DLUError::MintMismatch.into());
+        }
+
+        // The rewards were left in the treasury, which is its own authority.
+        let amount = rewards.claim()?;
+        let (_, bump) = derive_treasury_address(resolver.program_id(), &rewards.mint);
+        DLUToken::transfer_signed(
+            treasury_account,
+            referrer_account,
+            treasury_account,
+            amount,
+            &[&[TREASURY_SEED, rewards.mint.as_ref(), &[bump]]],
+        )?;
+        msg!("Claimed {} in referral rewards", amount);
+
+        save_account_data(rewards_account, &rewards)
+    }
+
+    // PROMO CODES
+
+    fn process_set_promo_code(
+        resolver: &mut AccountsResolver,
+        code_hash: [u8; 32],
+        discount_percent: u8,
+        uses: u32,
+    ) -> ProgramResult {
+        let offer_account = resolver.next_program_account()?;
+        let offer = load_offer(offer_account)?;
+        let seller_info = resolver.next_signer_for(&offer.seller_pubkey())?;
+        let promo_account = resolver.next_promo_codes(offer_account.key)?;
+        let system_program_info = resolver.next_with_key(&system_program::id())?;
+        let payer_info = resolver.next_payer(seller_info);
+
+        // The offer's first code creates its PromoCodes account.
+        let (_, bump) = derive_promo_address(resolver.program_id(), offer_account.key);
+        let promo_seeds: &[&[u8]] = &[PROMO_SEED, offer_account.key.as_ref(), &[bump]];
+        create_pda_account(resolver.program_id(), promo_account, payer_info, system_program_info, PROMO_CODES_SIZE, promo_seeds)?;
+        let mut promo_codes = load_promo_codes(promo_account)?;
+        promo_codes.set(code_hash, discount_percent, uses)?;
+
+        save_account_data(promo_account, &promo_codes)
+    }
+
+    // SCHEDULED LISTINGS
+
+    fn process_schedule_listing(
+        resolver: &mut AccountsResolver,
+        entity_type: EntityType,
+        activate_at: i64,
+        delist_at: Option<i64>,
+    ) -> ProgramResult {
+        let entity_account = resolver.next_program_account()?;
+        let lister_info = resolver.next_signer()?;
+
+        match entity_type {
+            EntityType::Offer => {
+                let mut offer = load_offer(entity_account)?;
+                offer.schedule(lister_info.key, activate_at, delist_at)?;
+                save_offer(entity_account, &mut offer, lister_info.key)
+            }
+            EntityType::Request => {
+                let mut request = load_request(entity_account)?;
+                request.schedule(lister_info.key, activate_at, delist_at)?;
+                save_request(entity_account, &mut request, lister_info.key)
+            }
+            EntityType::Shipment => Err(DLUError::InvalidEntityType.into()),
+        }
+    }
+
+    // STREAMING PAYMENTS
+
+    fn process_withdraw_stream(resolver: &mut AccountsResolver) -> ProgramResult {
+        let offer_account = resolver.next_program_account()?;
+        let seller = load_user(resolver.next_program_account()?)?;
+        resolver.next_signer_for(&seller.pubkey)?;
+        let seller_account = resolver.next_token_account()?;
+        let mut offer = load_offer(offer_account)?;
+        let mint = offer.payment_mint();
+        let (vault_account, bump) = next_registered_vault(resolver, &mint)?;
+        let vault_seeds: &[&[u8]] = &[VAULT_SEED, mint.as_ref(), &[bump]];
+        let config = load_config(resolver.next_config()?)?;
+        let treasury_account = resolver.next_treasury()?;
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+
+        let (amount, fee) = offer.withdraw_stream(
+            &seller,
+            seller_account,
+            vault_account,
+            treasury_account,
+            vault_seeds,
+            &config,
+            time::now()?,
+        )?;
+        stats.release_value(vault_account.key, amount);
+        if fee > 0 {
+            DLUEvent::FeeCollected {
+                entity_type: EntityType::Offer,
+                entity: *offer_account.key,
+                payee: seller.pubkey,
+                amount: fee,
+                slot: time::slot()?,
+            }.emit();
+            credit_referrer(resolver, &config, &seller, treasury_account, fee)?;
+        }
+        DLUEvent::StreamWithdrawn { offer: *offer_account.key, amount, fee, slot: time::slot()? }.emit();
+
+        save_offer(offer_account, &mut offer, &seller.pubkey)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    fn process_stop_stream(resolver: &mut AccountsResolver) -> ProgramResult {
+        let offer_account = resolver.next_program_account()?;
+        let party_info = resolver.next_signer()?;
+
+        let mut offer = load_offer(offer_account)?;
+        offer.stop_stream(party_info.key, time::now()?)?;
+        DLUEvent::StreamStopped { offer: *offer_account.key, stopped_by: *party_info.key, slot: time::slot()? }.emit();
+
+        save_offer(offer_account, &mut offer, party_info.key)
+    }
+
+    // WATCHLISTS
+
+    fn process_follow_user(resolver: &mut AccountsResolver, user: Pubkey, follow: bool) -> ProgramResult {
+        let follower_info = resolver.next_signer()?;
+        let watchlist_account = resolver.next_watchlist(follower_info.key)?;
+
+        let mut watchlist = load_or_create_watchlist(resolver, watchlist_account, follower_info)?;
+        watchlist.follow(follower_info.key, user, follow)?;
+        DLUEvent::UserFollowed { follower: *follower_info.key, user, following: follow, slot: time::slot()? }.emit();
+
+        save_account_data(watchlist_account, &watchlist)
+    }
+
+    fn process_watch_offer(resolver: &mut AccountsResolver, watch: bool) -> ProgramResult {
+        let watcher_info = resolver.next_signer()?;
+        let watchlist_account = resolver.next_watchlist(watcher_info.key)?;
+        let offer_account = resolver.next_program_account()?;
+
+        // Only offers can be watched, though one can be unwatched however it ended up.
+        if watch {
+            load_offer(offer_account)?;
+        }
+        let mut watchlist = load_or_create_watchlist(resolver, watchlist_account, watcher_info)?;
+        watchlist.watch(*offer_account.key, watch)?;
+        DLUEvent::OfferWatched { watcher: *watcher_info.key, offer: *offer_account.key, watching: watch, slot: time::slot()? }.emit();
+
+        save_account_data(watchlist_account, &watchlist)
+    }
+
+    // COMPRESSED LISTINGS
+
+    fn process_create_listing_tree(resolver: &mut AccountsResolver, max_depth: u32, max_buffer_size: u32) -> ProgramResult {
+        let tree_info = resolver.next_listing_tree()?;
+        let tree_authority_info = resolver.next_listing_tree_authority(tree_info.key)?;
+        let tree = next_tree_accounts(resolver, tree_info, tree_authority_info)?;
+
+        let (_, bump) = derive_listing_tree_authority_address(resolver.program_id(), tree_info.key);
+        let authority_seeds: &[&[u8]] = &[LISTING_TREE_SEED, tree_info.key.as_ref(), &[bump]];
+        compression::init_tree(&tree, authority_seeds, max_depth, max_buffer_size)
+    }
+
+    fn process_list_compressed_offer(
+        resolver: &mut AccountsResolver,
+        id: u64,
+        goodsorservice_name: String,
+        goodsorservice_description: String,
+        payment: u64,
+        meeting_point: Location,
+        meeting_datetime: i64,
+    ) -> ProgramResult {
+        validation::item_name(&goodsorservice_name)?;
+        validation::description(&goodsorservice_description)?;
+        validation::location(&meeting_point)?;
+        let tree_info = resolver.next_listing_tree()?;
+        let tree_authority_info = resolver.next_listing_tree_authority(tree_info.key)?;
+        let seller = load_user(resolver.next_program_account()?)?;
+        let seller_authority_info = resolver.next_signer_for(&seller.pubkey)?;
+        let seller_account = resolver.next_token_account()?;
+        let (vault_account, _) = next_registered_vault(resolver, &DLUToken::get_mint(seller_account)?)?;
+        let config = load_config(resolver.next_config()?)?;
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+        let tree = next_tree_accounts(resolver, tree_info, tree_authority_info)?;
+
+        config.check_terms_accepted(seller.accepted_terms_version)?;
+        check_not_banned(&seller)?;
+
+        let (payment_mint, insurance) =
+            Offer::lock_compressed_listing(payment, seller_account, seller_authority_info, vault_account)?;
+        let listing = CompressedOffer {
+            seller: seller.pubkey,
+            id,
+            goodsorservice_name,
+            goodsorservice_description,
+            payment,
+            payment_mint,
+            meeting_point,
+            meeting_datetime,
+            listed_slot: time::slot()?,
+        };
+        let leaf = listing.leaf()?;
+        let (_, bump) = derive_listing_tree_authority_address(resolver.program_id(), tree_info.key);
+        let authority_seeds: &[&[u8]] = &[LISTING_TREE_SEED, tree_info.key.as_ref(), &[bump]];
+        compression::append_leaf(&tree, authority_seeds, leaf)?;
+
+        // Exceeding the cap fails the whole transaction, reverting the escrow transfer above.
+        stats.lock_value(&config, vault_account.key, insurance)?;
+        stats.record_listing();
+        DLUEvent::CompressedOfferListed { tree: *tree_info.key, listing, leaf }.emit();
+
+        save_account_data(stats_account, &stats)
+    }
+
+    fn process_accept_compressed_offer(
+        resolver: &mut AccountsResolver,
+        listing: CompressedOffer,
+        root: [u8; 32],
+        index: u32,
+    ) -> ProgramResult {
+        let tree_info = resolver.next_listing_tree()?;
+        let tree_authority_info = resolver.next_listing_tree_authority(tree_info.key)?;
+        let offer_account = resolver.next_fresh_account()?;
+        let seller = load_user(resolver.next_program_account()?)?;
+        let buyer_user_account = resolver.next_program_account()?;
+        let mut buyer = load_user(buyer_user_account)?;
+        let buyer_info = resolver.next_signer_for(&buyer.pubkey)?;
+        let buyer_account = resolver.next_token_account()?;
+        let (vault_account, _) = next_registered_vault(resolver, &listing.payment_mint)?;
+        let authority_info = resolver.next_signer()?;
+        let config = load_config(resolver.next_config()?)?;
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+        let index_account = resolver.next_index(&seller.pubkey)?;
+        let mut seller_index = load_index(index_account)?;
+        let tree = next_tree_accounts(resolver, tree_info, tree_authority_info)?;
+        let proof = resolver.next_remaining();
+        check_not_banned(&buyer)?;
+
+        // Emptying the leaf proves the listing and keeps it from being accepted twice.
+        let leaf = listing.leaf()?;
+        let (_, bump) = derive_listing_tree_authority_address(resolver.program_id(), tree_info.key);
+        let authority_seeds: &[&[u8]] = &[LISTING_TREE_SEED, tree_info.key.as_ref(), &[bump]];
+        compression::remove_leaf(&tree, proof, authority_seeds, root, leaf, index)?;
+
+        let mut offer = Offer::materialize(listing, &seller, vault_account.key)?;
+        let locked_before = offer.escrowed_amount();
+        offer.accept_offer(&mut buyer, buyer_account, vault_account, authority_info)?;
+
+        // Exceeding the cap fails the whole transaction, reverting the escrow transfer above.
+        stats.lock_value(&config, vault_account.key, safe_math::sub(offer.escrowed_amount(), locked_before)?)?;
+        seller_index.accept_offer(offer_account.key, &seller.pubkey, &buyer.pubkey);
+        DLUEvent::CompressedOfferClosed { tree: *tree_info.key, leaf, offer: Some(*offer_account.key), slot: time::slot()? }.emit();
+
+        save_offer(offer_account, &mut offer, buyer_info.key)?;
+        save_user(buyer_user_account, &buyer)?;
+        save_index(index_account, &seller_index)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    fn process_cancel_compressed_offer(
+        resolver: &mut AccountsResolver,
+        listing: CompressedOffer,
+        root: [u8; 32],
+        index: u32,
+    ) -> ProgramResult {
+        let tree_info = resolver.next_listing_tree()?;
+        let tree_authority_info = resolver.next_listing_tree_authority(tree_info.key)?;
+        let seller = load_user(resolver.next_program_account()?)?;
+        resolver.next_signer_for(&seller.pubkey)?;
+        let seller_account = resolver.next_token_account()?;
+        let mint = listing.payment_mint;
+        let (vault_account, bump) = next_registered_vault(resolver, &mint)?;
+        let vault_seeds: &[&[u8]] = &[VAULT_SEED, mint.as_ref(), &[bump]];
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+        let tree = next_tree_accounts(resolver, tree_info, tree_authority_info)?;
+        let proof = resolver.next_remaining();
+
+        let leaf = listing.leaf()?;
+        let (_, bump) = derive_listing_tree_authority_address(resolver.program_id(), tree_info.key);
+        let authority_seeds: &[&[u8]] = &[LISTING_TREE_SEED, tree_info.key.as_ref(), &[bump]];
+        compression::remove_leaf(&tree, proof, authority_seeds, root, leaf, index)?;
+
+        // The offer is only materialized in memory, to cancel it the way listed offers are.
+        let mut offer = Offer::materialize(listing, &seller, vault_account.key)?;
+        let locked_before = offer.escrowed_amount();
+        offer.cancel_offer(seller_account, vault_account, vault_seeds)?;
+        stats.release_value(vault_account.key, safe_math::sub(locked_before, offer.escrowed_amount())?);
+        DLUEvent::CompressedOfferClosed { tree: *tree_info.key, leaf, offer: None, slot: time::slot()? }.emit();
+
+        save_account_data(stats_account, &stats)
+    }
+
+    // ARCHIVES
+
+    fn process_archive_batch(resolver: &mut AccountsResolver, entity_type: EntityType) -> ProgramResult {
+        let lister_info = resolver.next_signer()?;
+        let history_account = resolver.next_history(lister_info.key)?;
+        let system_program_info = resolver.next_with_key(&system_program::id())?;
+        let payer_info = resolver.next_payer(lister_info);
+        let entity_accounts = resolver.next_remaining();
+
+        let repeated = entity_accounts.iter().enumerate().any(|(i, account)| entity_accounts[..i].iter().any(|other| other.key == account.key));
+        if entity_accounts.is_empty() || entity_accounts.len() > MAX_ARCHIVE_BATCH || repeated {
+            return Err(DLUError::InvalidArchiveBatch.into());
+        }
+
+        let now = time::now()?;
+        let mut leaves = Vec::with_capacity(entity_accounts.len());
+        for entity_account in entity_accounts {
+            if entity_account.owner != resolver.program_id() {
+                return Err(DLUError::InvalidAccountOwner.into());
+            }
+            let (lister, archivable) = match entity_type {
+                EntityType::Offer => {
+                    let offer = load_offer(entity_account)?;
+                    (offer.lister_pubkey(), offer.is_archivable(now))
+                }
+                EntityType::Request => {
+                    let request = load_request(entity_account)?;
+                    (request.lister_pubkey(), request.is_archivable(now))
+                }
+                EntityType::Shipment => {
+                    let shipment = load_shipment(entity_account)?;
+                    (shipment.sender_pubkey(), shipment.is_archivable(now))
+                }
+            };
+            if lister != *lister_info.key {
+                return Err(DLUError::NotAuthorized.into());
+            }
+            if !archivable {
+                return Err(DLUError::NotArchivable.into());
+            }
+            leaves.push(archive::leaf(entity_account.key, &archive::data_hash(&entity_account.data.borrow())));
+        }
+
+        let root = archive::merkle_root(&leaves);
+        let slot = time::slot()?;
+        let created = history_account.owner != resolver.program_id();
+        let mut history = if created { History::default() } else { load_history(history_account)? };
+        let batch = history.archive(ArchivedBatch { entity_type, root, count: leaves.len() as u8, slot });
+        for entity_account in entity_accounts {
+            close_account(entity_account, lister_info)?;
+        }
+        let entities = entity_accounts.iter().map(|account| *account.key).collect();
+        DLUEvent::BatchArchived { lister: *lister_info.key, batch, entity_type, root, entities, slot }.emit();
+
+        let data = history.serialize()?;
+        if created {
+            let (_, bump) = derive_history_address(resolver.program_id(), lister_info.key);
+            let history_seeds: &[&[u8]] = &[HISTORY_SEED, lister_info.key.as_ref(), &[bump]];
+            create_pda_account(resolver.program_id(), history_account, payer_info, system_program_info, data.len(), history_seeds)?;
+        }
+        let space = migrations::account_space(data.len());
+        if space > history_account.data_len() {
+            grow_account(history_account, payer_info, system_program_info, space)?;
+        }
+        write_account_data(history_account, AccountKind::History, &data)
+    }
+
+    fn process_verify_archived_entity(
+        resolver: &mut AccountsResolver,
+        lister: Pubkey,
+        batch: u32,
+        entity: Pubkey,
+        data_hash: [u8; 32],
+        index: u32,
+        proof: Vec<[u8; 32]>,
+    ) -> ProgramResult {
+        let history = load_history(resolver.next_history(&lister)?)?;
+        history.verify(batch, &entity, &data_hash, index, &proof)?;
+        msg!("Entity {} is leaf {} of batch {}", entity, index, batch);
+        Ok(())
+    }
+
+    // ONE-TIME KEYS
+
+    fn process_commit_key(
+        resolver: &mut AccountsResolver,
+        entity_type: EntityType,
+        key_hash: KeyHash,
+        nonce: u64,
+    ) -> ProgramResult {
+        let entity_account = resolver.next_program_account()?;
+        let party_info = resolver.next_signer()?;
+        let key_manager_account = resolver.next_key_manager(entity_account.key)?;
+        let mut key_manager = load_or_create_key_manager(resolver, key_manager_account, entity_account.key, party_info)?;
+        let now = time::now()?;
+
+        match entity_type {
+            EntityType::Offer => {
+                let mut offer = load_offer(entity_account)?;
+                offer.commit_key(party_info.key, key_hash, now)?;
+                save_offer(entity_account, &mut offer, party_info.key)?;
+            }
+            EntityType::Request => {
+                let mut request = load_request(entity_account)?;
+                request.commit_key(party_info.key, key_hash, now)?;
+                save_request(entity_account, &mut request, party_info.key)?;
+            }
+            EntityType::Shipment => {
+                let mut shipment = load_shipment(entity_account)?;
+                shipment.commit_key(party_info.key, key_hash, now)?;
+                save_shipment(entity_account, &mut shipment)?;
+            }
+        }
+
+        key_manager.record(party_info.key, nonce, key_hash)?;
+        save_account_data(key_manager_account, &key_manager)
+    }
+
+    fn process_regenerate_keys(
+        resolver: &mut AccountsResolver,
+        entity_type: EntityType,
+        seller_key_hash: KeyHash,
+        buyer_key_hash: KeyHash,
+        nonce: u64,
+    ) -> ProgramResult {
+        let deal_account = resolver.next_program_account()?;
+        let seller_info = resolver.next_signer()?;
+        let buyer_info = resolver.next_signer()?;
+        let key_manager_account = resolver.next_key_manager(deal_account.key)?;
+        let mut key_manager = load_or_create_key_manager(resolver, key_manager_account, deal_account.key, seller_info)?;
+        let now = time::now()?;
+
+        match entity_type {
+            EntityType::Offer => {
+                let mut offer = load_offer(deal_account)?;
+                offer.regenerate_keys(seller_info.key, buyer_info.key, seller_key_hash, buyer_key_hash, now)?;
+                save_offer(deal_account, &mut offer, seller_info.key)?;
+            }
+            EntityType::Request => {
+                let mut request = load_request(deal_account)?;
+                request.regenerate_keys(seller_info.key, buyer_info.key, seller_key_hash, buyer_key_hash, now)?;
+                save_request(deal_account, &mut request, seller_info.key)?;
+            }
+            EntityType::Shipment => return Err(DLUError::InvalidEntityType.into()),
+        }
+
+        key_manager.record(seller_info.key, nonce, seller_key_hash)?;
+        key_manager.record(buyer_info.key, nonce, buyer_key_hash)?;
+        save_account_data(key_manager_account, &key_manager)
+    }
+
+    // STALE LISTINGS
+
+    fn process_reclaim_stale_listing(resolver: &mut AccountsResolver, entity_type: EntityType) -> ProgramResult {
+        let entity_account = resolver.next_program_account()?;
+        let mint = load_payment_mint(entity_account, entity_type)?;
+        let (vault_account, bump) = next_registered_vault(resolver, &mint)?;
+        let lister_account = resolver.next_token_account()?;
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+
+        // The vault is its own authority, so the program signs the refund.
+        let vault_seeds: &[&[u8]] = &[VAULT_SEED, mint.as_ref(), &[bump]];
+        let now = time::now()?;
+
+        let refund = match entity_type {
+            EntityType::Offer => {
+                let mut offer = load_offer(entity_account)?;
+                let refund = offer.reclaim_stale_listing(vault_account, lister_account, vault_seeds, now)?;
+                release_nft(resolver, entity_account, &offer, &offer.seller_pubkey())?;
+                save_offer(entity_account, &mut offer, lister_account.key)?;
+                refund
+            }
+            EntityType::Request => {
+                let mut request = load_request(entity_account)?;
+                let refund = request.reclaim_stale_listing(vault_account, lister_account, vault_seeds, now)?;
+                save_request(entity_account, &mut request, lister_account.key)?;
+                refund
+            }
+            EntityType::Shipment => {
+                let mut shipment = load_shipment(entity_account)?;
+                let refund = shipment.reclaim_stale_listing(vault_account, lister_account, vault_seeds, now)?;
+                save_shipment(entity_account, &mut shipment)?;
+                refund
+            }
+        };
+        stats.release_value(vault_account.key, refund);
+
+        DLUEvent::StaleListingReclaimed {
+            entity_type,
+            entity: *entity_account.key,
+            lister_account: *lister_account.key,
+            refund,
+            slot: time::slot()?,
+        }
+        .emit();
+
+        save_account_data(stats_account, &stats)
+    }
+
+    // RESCHEDULING
+
+    fn process_extend_deadline(resolver: &mut AccountsResolver, entity_type: EntityType, deadline: i64) -> ProgramResult {
+        let entity_account = resolver.next_program_account()?;
+        let seller_or_sender_info = resolver.next_signer()?;
+        let buyer_or_carrier_info = resolver.next_signer()?;
+        let now = time::now()?;
+
+        match entity_type {
+            EntityType::Offer => {
+                let mut offer = load_offer(entity_account)?;
+                offer.extend_deadline(seller_or_sender_info.key, buyer_or_carrier_info.key, deadline, now)?;
+                save_offer(entity_account, &mut offer, seller_or_sender_info.key)?;
+            }
+            EntityType::Request => {
+                let mut request = load_request(entity_account)?;
+                request.extend_deadline(seller_or_sender_info.key, buyer_or_carrier_info.key, deadline, now)?;
+                save_request(entity_account, &mut request, seller_or_sender_info.key)?;
+            }
+            EntityType::Shipment => {
+                let mut shipment = load_shipment(entity_account)?;
+                shipment.extend_deadline(seller_or_sender_info.key, buyer_or_carrier_info.key, deadline, now)?;
+                save_shipment(entity_account, &mut shipment)?;
+            }
+        }
+
+        DLUEvent::DeadlineExtended {
+            entity_type,
+            entity: *entity_account.key,
+            deadline,
+            slot: time::slot()?,
+        }
+        .emit();
+        Ok(())
+    }
+
+    // RECONCILIATION
+
+    fn process_verify_escrow(resolver: &mut AccountsResolver, entity_type: EntityType) -> ProgramResult {
+        let entity_account = resolver.next_program_account()?;
+        let (vault_account, _) = next_registered_vault(resolver, &load_payment_mint(entity_account, entity_type)?)?;
+        let vault_locked = load_stats(resolver.next_stats()?)?.locked_in(vault_account.key);
+
+        let (expected, actual) = match entity_type {
+            EntityType::Offer => {
+                let mut offer = load_offer(entity_account)?;
+                let amounts = offer.verify_escrow(vault_account, vault_locked)?;
+                save_offer(entity_account, &mut offer, vault_account.key)?;
+                amounts
+            }
+            EntityType::Request => {
+                let mut request = load_request(entity_account)?;
+                let amounts = request.verify_escrow(vault_account, vault_locked)?;
+                save_request(entity_account, &mut request, vault_account.key)?;
+                amounts
+            }
+            EntityType::Shipment => {
+                let mut shipment = load_shipment(entity_account)?;
+                let amounts = shipment.verify_escrow(vault_account, vault_locked)?;
+                save_shipment(entity_account, &mut shipment)?;
+                amounts
+            }
+        };
+
+        if actual < expected {
+            DLUEvent::EscrowDiverged {
+                entity_type,
+                entity: *entity_account.key,
+                vault: *vault_account.key,
+                expected,
+                actual,
+                slot: time::slot()?,
+            }
+            .emit();
+        } else {
+            msg!("Vault holds {} covering the {} escrowed for {}", actual, expected, entity_account.key);
+        }
+        Ok(())
+    }
+
+    // EMERGENCY RESOLUTIONS
+
+    fn process_propose_emergency_resolve(resolver: &mut AccountsResolver, entity_type: EntityType) -> ProgramResult {
+        let config = load_config(resolver.next_config()?)?;
+        let governance_account = resolver.next_signer()?;
+        config.check_governance(governance_account.key)?;
+        let entity_account = resolver.next_program_account()?;
+        let emergency_account = resolver.next_emergency_resolution(entity_account.key)?;
+        let system_program_info = resolver.next_with_key(&system_program::id())?;
+
+        // Only entities still holding escrow have anything to unwind.
+        let escrowed = match entity_type {
+            EntityType::Offer => load_offer(entity_account)?.escrowed_amount(),
+            EntityType::Request => load_request(entity_account)?.escrowed_amount(),
+            EntityType::Shipment => load_shipment(entity_account)?.escrowed_amount(),
+        };
+        if escrowed == 0 {
+            return Err(DLUError::EscrowEmpty.into());
+        }
+
+        // The entity's first proposal creates its resolution, with governance paying its rent.
+        let (_, bump) = derive_emergency_address(resolver.program_id(), entity_account.key);
+        let emergency_seeds: &[&[u8]] = &[EMERGENCY_SEED, entity_account.key.as_ref(), &[bump]];
+        create_pda_account(resolver.program_id(), emergency_account, governance_account, system_program_info, EMERGENCY_RESOLUTION_SIZE, emergency_seeds)?;
+        let mut emergency = load_emergency_resolution(emergency_account)?;
+        let executable_at = emergency.propose(entity_account.key, time::now()?)?;
+
+        DLUEvent::EmergencyResolveProposed {
+            entity_type,
+            entity: *entity_account.key,
+            executable_at,
+            slot: time::slot()?,
+        }
+        .emit();
+
+        save_account_data(emergency_account, &emergency)
+    }
+
+    fn process_emergency_resolve(resolver: &mut AccountsResolver, entity_type: EntityType) -> ProgramResult {
+        let config = load_config(resolver.next_config()?)?;
+        let governance_account = resolver.next_signer()?;
+        config.check_governance(governance_account.key)?;
+        let entity_account = resolver.next_program_account()?;
+        let emergency_account = resolver.next_emergency_resolution(entity_account.key)?;
+        let mint = load_payment_mint(entity_account, entity_type)?;
+        let (vault_account, bump) = next_registered_vault(resolver, &mint)?;
+        let seller_or_sender_account = resolver.next_token_account()?;
+        let buyer_or_carrier_account = resolver.next_token_account()?;
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+
+        let mut emergency = load_proposed_emergency(resolver.program_id(), emergency_account)?;
+        emergency.execute(entity_account.key, time::now()?)?;
+
+        // The vault is its own authority, so the program signs the refunds.
+        let vault_seeds: &[&[u8]] = &[VAULT_SEED, mint.as_ref(), &[bump]];
+
+        let (seller_or_sender_refund, buyer_or_carrier_refund) = match entity_type {
+            EntityType::Offer => {
+                let mut offer = load_offer(entity_account)?;
+                let refunds = offer.emergency_unwind(vault_account, seller_or_sender_account, buyer_or_carrier_account, vault_seeds)?;
+                release_nft(resolver, entity_account, &offer, &offer.seller_pubkey())?;
+                save_offer(entity_account, &mut offer, governance_account.key)?;
+                refunds
+            }
+            EntityType::Request => {
+                let mut request = load_request(entity_account)?;
+                let refunds = request.emergency_unwind(vault_account, seller_or_sender_account, buyer_or_carrier_account, vault_seeds)?;
+                save_request(entity_account, &mut request, governance_account.key)?;
+                refunds
+            }
+            EntityType::Shipment => {
+                let mut shipment = load_shipment(entity_account)?;
+                let refunds = shipment.emergency_unwind(vault_account, seller_or_sender_account, buyer_or_carrier_account, vault_seeds)?;
+                save_shipment(entity_account, &mut shipment)?;
+                refunds
+            }
+        };
+        stats.release_value(vault_account.key, safe_math::add(seller_or_sender_refund, buyer_or_carrier_refund)?);
+
+        DLUEvent::EmergencyResolved {
+            entity_type,
+            entity: *entity_account.key,
+            seller_or_sender_refund,
+            buyer_or_carrier_refund,
+            slot: time::slot()?,
+        }
+        .emit();
+
+        save_account_data(emergency_account, &emergency)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    fn process_cancel_emergency_resolve(resolver: &mut AccountsResolver) -> ProgramResult {
+        let config = load_config(resolver.next_config()?)?;
+        let governance_account = resolver.next_signer()?;
+        config.check_governance(governance_account.key)?;
+        let entity_account = resolver.next_program_account()?;
+        let emergency_account = resolver.next_emergency_resolution(entity_account.key)?;
+
+        let mut emergency = load_proposed_emergency(resolver.program_id(), emergency_account)?;
+        emergency.cancel(entity_account.key)?;
+
+        DLUEvent::EmergencyResolveCanceled { entity: *entity_account.key, slot: time::slot()? }.emit();
+
+        save_account_data(emergency_account, &emergency)
+    }
+
+    // TIMELOCKED GOVERNANCE
+
+    fn process_queue_governance_action(resolver: &mut AccountsResolver, action: GovernanceAction) -> ProgramResult {
+        let config = load_config(resolver.next_config()?)?;
+        let governance_account = resolver.next_signer()?;
+        config.check_governance(governance_account.key)?;
+        let timelock_account = resolver.next_timelock_queue()?;
+
+        let mut timelock = load_timelock_queue(timelock_account)?;
+        let (id, executable_at) = timelock.queue(action.clone(), time::now()?)?;
+
+        DLUEvent::GovernanceActionQueued { id, action, executable_at, slot: time::slot()? }.emit();
+
+        save_account_data(timelock_account, &timelock)
+    }
+
+    fn process_cancel_governance_action(resolver: &mut AccountsResolver, id: u64) -> ProgramResult {
+        let config = load_config(resolver.next_config()?)?;
+        let governance_account = resolver.next_signer()?;
+        config.check_governance(governance_account.key)?;
+        let timelock_account = resolver.next_timelock_queue()?;
+
+        let mut timelock = load_timelock_queue(timelock_account)?;
+        let action = timelock.cancel(id)?;
+
+        DLUEvent::GovernanceActionCanceled { id, action, slot: time::slot()? }.emit();
+
+        save_account_data(timelock_account, &timelock)
+    }
+
+    fn process_execute_governance_action(resolver: &mut AccountsResolver, id: u64) -> ProgramResult {
+        let config_account = resolver.next_config()?;
+        let timelock_account = resolver.next_timelock_queue()?;
+
+        let mut config = load_config(config_account)?;
+        let mut timelock = load_timelock_queue(timelock_account)?;
+        let action = timelock.take_executable(id, time::now()?)?;
+
+        // Actions beyond the config take their accounts after the queue. Each one returns the
+        // keys it acted on, and a mismatch with the queued ones fails the whole execution.
+        match action {
+            GovernanceAction::WithdrawTreasury { mint, destination, amount } => {
+                if withdraw_treasury(resolver, amount)? != (mint, destination) {
+                    return Err(DLUError::KeyMismatch.into());
+                }
+            }
+            GovernanceAction::RegisterVault { mint } => {
+                let payer_info = resolver.next_signer()?;
+                if register_vault(resolver, payer_info)? != mint {
+                    return Err(DLUError::KeyMismatch.into());
+                }
+            }
+            GovernanceAction::MigrateEscrow { entity_type, entity, legacy_escrow } => {
+                if migrate_escrow(resolver, &config, entity_type)? != (entity, legacy_escrow) {
+                    return Err(DLUError::KeyMismatch.into());
+                }
+            }
+            _ => action.apply(&mut config)?,
+        }
+
+        DLUEvent::GovernanceActionExecuted { id, action, slot: time::slot()? }.emit();
+
+        save_account_data(config_account, &config)?;
+        save_account_data(timelock_account, &timelock)
+    }
+
+    // DAO VOTING
+
+    fn process_initialize_dao(
+        resolver: &mut AccountsResolver,
+        vote_mint: Pubkey,
+        quorum: u64,
+        proposal_threshold: u64,
+        voting_period: i64,
+    ) -> ProgramResult {
+        let config = load_config(resolver.next_config()?)?;
+        let governance_account = resolver.next_signer()?;
+        config.check_governance(governance_account.key)?;
+        let (dao_key, dao_bump) = derive_dao_address(resolver.program_id());
+        let dao_account = resolver.next_with_key(&dao_key)?;
+        let system_program_info = resolver.next_with_key(&system_program::id())?;
+
+        // The DAO can only be initialized once, with governance paying its rent.
+        if dao_account.owner == resolver.program_id() {
+            return Err(DLUError::AlreadyInitialized.into());
+        }
+        let dao = Dao::new(vote_mint, quorum, proposal_threshold, voting_period)?;
+        create_pda_account(resolver.program_id(), dao_account, governance_account, system_program_info, DAO_SIZE, &[DAO_SEED, &[dao_bump]])?;
+
+        save_account_data(dao_account, &dao)
+    }
+
+    fn process_lock_votes(resolver: &mut AccountsResolver, amount: u64) -> ProgramResult {
+        let dao = load_dao(resolver.next_dao()?)?;
+        let vote_lock_account = resolver.next()?;
+        let owner_info = resolver.next_signer()?;
+        let (vote_lock_key, vote_lock_bump) = derive_vote_lock_address(resolver.program_id(), owner_info.key);
+        if *vote_lock_account.key != vote_lock_key {
+            return Err(DLUError::KeyMismatch.into());
+        }
+        let owner_account = resolver.next_token_account()?;
+        let (vault_account, _) = next_registered_vault(resolver, &dao.vote_mint)?;
+        let system_program_info = resolver.next_with_key(&system_program::id())?;
+        let payer_info = resolver.next_payer(owner_info);
+        DLUToken::check_payment_mint(&dao.vote_mint, &[owner_account])?;
+
+        // The first lock creates the account.
+        let mut vote_lock = if vote_lock_account.owner != resolver.program_id() {
+            let vote_lock_seeds: &[&[u8]] = &[VOTE_LOCK_SEED, owner_info.key.as_ref(), &[vote_lock_bump]];
+            create_pda_account(resolver.program_id(), vote_lock_account, payer_info, system_program_info, VOTE_LOCK_SIZE, vote_lock_seeds)?;
+            VoteLock::new(*owner_info.key)
+        } else {
+            load_vote_lock(vote_lock_account)?
+        };
+
+        vote_lock.lock(amount)?;
+        DLUToken::transfer(owner_account, vault_account, owner_info, amount)?;
+        msg!("Votes locked are now {}", vote_lock.amount);
+
+        save_account_data(vote_lock_account, &vote_lock)
+    }
+
+    fn process_unlock_votes(resolver: &mut AccountsResolver, amount: u64) -> ProgramResult {
+        let dao = load_dao(resolver.next_dao()?)?;
+        let vote_lock_account = resolver.next_program_account()?;
+        let mut vote_lock = load_vote_lock(vote_lock_account)?;
+        resolver.next_signer_for(&vote_lock.owner)?;
+        let owner_account = resolver.next_token_account()?;
+        let (vault_account, bump) = next_registered_vault(resolver, &dao.vote_mint)?;
+        DLUToken::check_payment_mint(&dao.vote_mint, &[owner_account])?;
+
+        if !DLUToken::check_authority(owner_account, &vote_lock.owner)? {
+            return Err(DLUError::KeyMismatch.into());
+        }
+
+        // The vault is its own authority, so the program signs the withdrawal.
+        let vault_seeds: &[&[u8]] = &[VAULT_SEED, dao.vote_mint.as_ref(), &[bump]];
+
+        vote_lock.unlock(amount, time::now()?)?;
+        DLUToken::transfer_signed(vault_account, owner_account, vault_account, amount, &[vault_seeds])?;
+        msg!("Votes locked are now {}", vote_lock.amount);
+
+        save_account_data(vote_lock_account, &vote_lock)
+    }
+
+    fn process_create_proposal(resolver: &mut AccountsResolver, action: GovernanceAction) -> ProgramResult {
+        let dao_account = resolver.next_dao()?;
+        let mut dao = load_dao(dao_account)?;
+        let proposal_account = resolver.next_proposal(dao.next_proposal_id)?;
+        let vote_lock_account = resolver.next_program_account()?;
+        let proposer_info = resolver.next_signer()?;
+        let (vote_lock_key, _) = derive_vote_lock_address(resolver.program_id(), proposer_info.key);
+        if *vote_lock_account.key != vote_lock_key {
+            return Err(DLUError::KeyMismatch.into());
+        }
+        let vote_lock = load_vote_lock(vote_lock_account)?;
+        let system_program_info = resolver.next_with_key(&system_program::id())?;
+        let payer_info = resolver.next_payer(proposer_info);
+
+        let proposal = dao.propose(proposer_info.key, vote_lock.amount, action, time::now()?)?;
+        let (_, proposal_bump) = derive_proposal_address(resolver.program_id(), proposal.id);
+        let proposal_seeds: &[&[u8]] = &[PROPOSAL_SEED, &proposal.id.to_le_bytes(), &[proposal_bump]];
+        create_pda_account(resolver.program_id(), proposal_account, payer_info, system_program_info, PROPOSAL_SIZE, proposal_seeds)?;
+
+        DLUEvent::ProposalCreated {
+            id: proposal.id,
+            proposer: proposal.proposer,
+            action: proposal.action.clone(),
+            voting_ends_at: proposal.voting_ends_at,
+            slot: time::slot()?,
+        }
+        .emit();
+
+        save_account_data(proposal_account, &proposal)?;
+        save_account_data(dao_account, &dao)
+    }
+
+    fn process_cast_vote(resolver: &mut AccountsResolver, support: bool) -> ProgramResult {
+        let proposal_account = resolver.next_program_account()?;
+        let mut proposal = load_proposal(proposal_account)?;
+        let (proposal_key, _) = derive_proposal_address(resolver.program_id(), proposal.id);
+        if *proposal_account.key != proposal_key {
+            return Err(DLUError::KeyMismatch.into());
+        }
+        let vote_lock_account = resolver.next_program_account()?;
+        let mut vote_lock = load_vote_lock(vote_lock_account)?;
+        let voter_info = resolver.next_signer_for(&vote_lock.owner)?;
+        let (vote_lock_key, _) = derive_vote_lock_address(resolver.program_id(), voter_info.key);
+        if *vote_lock_account.key != vote_lock_key {
+            return Err(DLUError::KeyMismatch.into());
+        }
+        let vote_record_account = resolver.next_vote_record(proposal_account.key, voter_info.key)?;
+        let system_program_info = resolver.next_with_key(&system_program::id())?;
+        let payer_info = resolver.next_payer(voter_info);
+        if vote_record_account.owner == resolver.program_id() {
+            return Err(DLUError::AlreadyVoted.into());
+        }
+
+        let vote_record = proposal.vote(&mut vote_lock, support, time::now()?)?;
+        let (_, vote_record_bump) = derive_vote_record_address(resolver.program_id(), proposal_account.key, voter_info.key);
+        let vote_record_seeds: &[&[u8]] = &[VOTE_SEED, proposal_account.key.as_ref(), voter_info.key.as_ref(), &[vote_record_bump]];
+        create_pda_account(resolver.program_id(), vote_record_account, payer_info, system_program_info, VOTE_RECORD_SIZE, vote_record_seeds)?;
+
+        DLUEvent::VoteCast {
+            proposal_id: proposal.id,
+            voter: *voter_info.key,
+            support,
+            weight: vote_record.weight,
+            slot: time::slot()?,
+        }
+        .emit();
+
+        save_account_data(vote_record_account, &vote_record)?;
+        save_account_data(vote_lock_account, &vote_lock)?;
+        save_account_data(proposal_account, &proposal)
+    }
+
+    fn process_finalize_proposal(resolver: &mut AccountsResolver) -> ProgramResult {
+        let dao = load_dao(resolver.next_dao()?)?;
+        let proposal_account = resolver.next_program_account()?;
+        let mut proposal = load_proposal(proposal_account)?;
+        let (proposal_key, _) = derive_proposal_address(resolver.program_id(), proposal.id);
+        if *proposal_account.key != proposal_key {
+            return Err(DLUError::KeyMismatch.into());
+        }
+        let timelock_account = resolver.next_timelock_queue()?;
+        let mut timelock = load_timelock_queue(timelock_account)?;
+
+        // The PDA of an ID the DAO never handed out holds no proposal yet.
+        if proposal.id >= dao.next_proposal_id {
+            return Err(DLUError::InvalidOperation.into());
+        }
+
+        let passed = proposal.finalize(dao.quorum, time::now()?)?;
+        if passed {
+            // Passing stands in for governance's signature; the timelock still applies.
+            let (id, executable_at) = timelock.queue(proposal.action.clone(), time::now()?)?;
+            proposal.queued_action = Some(id);
+            DLUEvent::GovernanceActionQueued { id, action: proposal.action.clone(), executable_at, slot: time::slot()? }.emit();
+        }
+
+        DLUEvent::ProposalFinalized {
+            id: proposal.id,
+            passed,
+            votes_for: proposal.votes_for,
+            votes_against: proposal.votes_against,
+            slot: time::slot()?,
+        }
+        .emit();
+
+        save_account_data(timelock_account, &timelock)?;
+        save_account_data(proposal_account, &proposal)
+    }
+
+    // PENALTY POOL
+
+    fn process_set_penalty_burn_share(resolver: &mut AccountsResolver, share_bps: u16) -> ProgramResult {
+        let config_account = resolver.next_config()?;
+        let governance_account = resolver.next_signer()?;
+
+        let mut config = load_config(config_account)?;
+        config.check_direct_update()?;
+        config.set_penalty_burn_share(governance_account.key, share_bps)?;
+        msg!("{} bps of distributed penalties are burned", config.penalty_burn_bps);
+
+        save_account_data(config_account, &config)
+    }
+
+    fn process_distribute_penalties(resolver: &mut AccountsResolver) -> ProgramResult {
+        let config = load_config(resolver.next_config()?)?;
+        let pool_account = resolver.next()?;
+        let penalty_vault_account = resolver.next_penalty_vault()?;
+        let mint = DLUToken::get_mint(penalty_vault_account)?;
+        let (pool_key, pool_bump) = derive_penalty_pool_address(resolver.program_id(), &mint);
+        if *pool_account.key != pool_key {
+            return Err(DLUError::KeyMismatch.into());
+        }
+        let mint_info = resolver.next_with_key(&mint)?;
+        let system_program_info = resolver.next_with_key(&system_program::id())?;
+        let payer_info = resolver.next_signer()?;
+
+        // The first distribution creates the pool.
+        let mut pool = if pool_account.owner != resolver.program_id() {
+            let pool_seeds: &[&[u8]] = &[PENALTY_POOL_SEED, mint.as_ref(), &[pool_bump]];
+            create_pda_account(resolver.program_id(), pool_account, payer_info, system_program_info, PENALTY_POOL_SIZE, pool_seeds)?;
+            PenaltyPool::new(mint)
+        } else {
+            load_penalty_pool(pool_account)?
+        };
+
+        let balance = DLUToken::get_balance(penalty_vault_account)?;
+        let (burned, credited) = pool.distribute(balance, config.penalty_burn_bps, time::now()?)?;
+        if burned > 0 {
+            // The penalty vault is its own authority.
+            let (_, bump) = derive_penalty_vault_address(resolver.program_id(), &mint);
+            DLUToken::burn_signed(
+                penalty_vault_account,
+                mint_info,
+                penalty_vault_account,
+                burned,
+                &[&[PENALTY_VAULT_SEED, mint.as_ref(), &[bump]]],
+            )
+            .map_err(|_| DLUError::BurnFailed)?;
+        }
+        DLUEvent::PenaltiesDistributed { mint, burned, credited, slot: time::slot()? }.emit();
+
+        save_account_data(pool_account, &pool)
+    }
+
+    fn process_claim_penalty_rewards(resolver: &mut AccountsResolver) -> ProgramResult {
+        let pool_account = resolver.next_program_account()?;
+        let mut pool = load_penalty_pool(pool_account)?;
+        let (pool_key, _) = derive_penalty_pool_address(resolver.program_id(), &pool.mint);
+        if *pool_account.key != pool_key {
+            return Err(DLUError::KeyMismatch.into());
+        }
+        let rewards_account = resolver.next()?;
+        let stake_account = resolver.next_program_account()?;
+        let stake = load_stake(stake_account)?;
+        let carrier_info = resolver.next_signer_for(&stake.carrier)?;
+        let (stake_key, _) = derive_stake_address(resolver.program_id(), &stake.carrier);
+        let (rewards_key, rewards_bump) = derive_penalty_rewards_address(resolver.program_id(), &pool.mint, &stake.carrier);
+        if *stake_account.key != stake_key || *rewards_account.key != rewards_key {
+            return Err(DLUError::KeyMismatch.into());
+        }
+        let penalty_vault_account = resolver.next_penalty_vault()?;
+        let carrier_account = resolver.next_token_account()?;
+        let system_program_info = resolver.next_with_key(&system_program::id())?;
+        let payer_info = resolver.next_payer(carrier_info);
+        if DLUToken::get_mint(penalty_vault_account)? != pool.mint {
+            return Err(DLUError::MintMismatch.into());
+        }
+
+        // The first claim creates the rewards account.
+        let mut rewards = if rewards_account.owner != resolver.program_id() {
+            let rewards_seeds: &[&[u8]] = &[PENALTY_REWARDS_SEED, pool.mint.as_ref(), stake.carrier.as_ref(), &[rewards_bump]];
+            create_pda_account(resolver.program_id(), rewards_account, payer_info, system_program_info, PENALTY_REWARDS_SIZE, rewards_seeds)?;
+            PenaltyRewards::new(stake.carrier)
+        } else {
+            load_penalty_rewards(rewards_account)?
+        };
+
+        let amount = pool.claim(&mut rewards, stake.amount)?;
+        if amount > 0 {
+            let (_, bump) = derive_penalty_vault_address(resolver.program_id(), &pool.mint);
+            DLUToken::transfer_signed(
+                penalty_vault_account,
+                carrier_account,
+                penalty_vault_account,
+                amount,
+                &[&[PENALTY_VAULT_SEED, pool.mint.as_ref(), &[bump]]],
+            )?;
+        }
+        msg!("Claimed {} in penalty rewards", amount);
+        DLUEvent::PenaltyRewardsClaimed { carrier: stake.carrier, mint: pool.mint, amount, slot: time::slot()? }.emit();
+
+        save_account_data(rewards_account, &rewards)?;
+        save_account_data(pool_account, &pool)
+    }
+
+    // FAUCET
+
+    fn process_request_faucet(resolver: &mut AccountsResolver) -> ProgramResult {
+        let user_account = resolver.next_program_account()?;
+        let mut user = load_user(user_account)?;
+        resolver.next_signer_for(&user.pubkey)?;
+        let config = load_config(resolver.next_config()?)?;
+        let token_account = resolver.next_token_account()?;
+        let mint_info = resolver.next()?;
+        let (faucet_key, bump) = derive_faucet_address(resolver.program_id());
+        let faucet_info = resolver.next_with_key(&faucet_key)?;
+
+        // Mainnet DLU has a fixed supply; only test clusters hand it out.
+        config.require_capability(CAP_TEST_CLUSTER)?;
+        let associated_address =
+            get_associated_token_address_with_program_id(&user.pubkey, mint_info.key, &DLUToken::token_program_id(mint_info)?);
+        if *token_account.key != associated_address {
+            return Err(DLUError::KeyMismatch.into());
+        }
+
+        user.draw_faucet(time::now()?).map_err(DLUError::from)?;
+        DLUToken::mint_to_signed(mint_info, token_account, faucet_info, FAUCET_AMOUNT, &[&[FAUCET_SEED, &[bump]]])?;
+        msg!("Faucet minted {} DLU", FAUCET_AMOUNT);
+
+        save_user(user_account, &user)
+    }
+
+    // COMPLETION REWARDS
+
+    fn process_set_completion_rewards(
+        resolver: &mut AccountsResolver,
+        reward: u64,
+        half_life: u64,
+        emission_cap: u64,
+    ) -> ProgramResult {
+        let config_account = resolver.next_config()?;
+        let governance_account = resolver.next_signer()?;
+
+        let mut config = load_config(config_account)?;
+        config.check_direct_update()?;
+        config.set_completion_rewards(governance_account.key, reward, half_life, emission_cap)?;
+        msg!("Completions pay {} to each party, halving every {} seconds, up to {}", reward, half_life, emission_cap);
+
+        save_account_data(config_account, &config)
+    }
+
+    // FUNDED ACCEPTANCE
+
+    fn process_deposit_and_accept(
+        resolver: &mut AccountsResolver,
+        amount: u64,
+        quantity: u32,
+        promo_code: Option<String>,
+    ) -> ProgramResult {
+        let offer_account = resolver.next_program_account()?;
+        let buyer_user_account = resolver.next_program_account()?;
+        let mut offer = load_offer(offer_account)?;
+        let mut buyer = load_user(buyer_user_account)?;
+        let owner_info = resolver.next_signer_for(&buyer.pubkey)?;
+        let owner_token_account = resolver.next_token_account()?;
+        let wallet_account = resolver.next_wallet(&buyer.pubkey)?;
+        let (vault_account, _) = next_registered_vault(resolver, &offer.payment_mint())?;
+        let config = load_config(resolver.next_config()?)?;
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+        let index_account = resolver.next_index(&offer.seller_pubkey())?;
+        let mut index = load_index(index_account)?;
+        check_not_banned(&buyer)?;
+        offer.check_active(time::now()?)?;
+        offer.price_quantity(quantity)?;
+        check_attestations(resolver, &config, offer.payment(), &[offer.seller_pubkey(), buyer.pubkey])?;
+        if let Some(code) = promo_code {
+            redeem_promo_code(resolver, offer_account.key, &mut offer, &buyer, &code)?;
+        }
+
+        if amount > 0 {
+            DLUToken::transfer(owner_token_account, wallet_account, owner_info, amount)?;
+            buyer.wallet.sync(wallet_account)?;
+            DLUEvent::WalletDeposited {
+                user: buyer.pubkey,
+                amount,
+                balance: buyer.wallet.balance,
+                slot: time::slot()?,
+            }
+            .emit();
+        }
+
+        let buyer_key = buyer.pubkey;
+        let (_, bump) = derive_wallet_address(resolver.program_id(), &buyer_key);
+        let wallet_seeds: &[&[u8]] = &[WALLET_SEED, buyer_key.as_ref(), &[bump]];
+        let locked_before = offer.escrowed_amount();
+        offer.accept_offer_from_wallet(&mut buyer, wallet_account, vault_account, wallet_seeds)?;
+        buyer.wallet.sync(wallet_account)?;
+
+        // Exceeding the cap fails the whole transaction, reverting the deposit and the escrow transfer.
+        stats.lock_value(&config, vault_account.key, safe_math::sub(offer.escrowed_amount(), locked_before)?)?;
+        index.accept_offer(offer_account.key, &offer.seller_pubkey(), &buyer.pubkey);
+
+        save_offer(offer_account, &mut offer, owner_info.key)?;
+        save_user(buyer_user_account, &buyer)?;
+        save_index(index_account, &index)?;
+        save_account_data(stats_account, &stats)
+    }
+
+    // BATCH EXPIRY
+
+    fn process_batch_expire(resolver: &mut AccountsResolver, entity_type: EntityType, ids: Vec<u64>) -> ProgramResult {
+        // The keeper is paid in the mint of the batch, so its account names the vault.
+        let keeper_account = resolver.next_token_account()?;
+        let mint = DLUToken::get_mint(keeper_account)?;
+        let (vault_account, bump) = next_registered_vault(resolver, &mint)?;
+        let config = load_config(resolver.next_config()?)?;
+        let stats_account = resolver.next_stats()?;
+        let mut stats = load_stats(stats_account)?;
+        let entity_accounts = resolver.next_remaining();
+        if ids.is_empty() || ids.len() > MAX_EXPIRY_BATCH || entity_accounts.len() != 3 * ids.len() {
+            return Err(DLUError::InvalidExpiryBatch.into());
+        }
+
+        // The vault is its own authority, so the program signs the releases.
+        let vault_seeds: &[&[u8]] = &[VAULT_SEED, mint.as_ref(), &[bump]];
+        let now = time::now()?;
+        let bounty = config.crank_bounty;
+
+        for (id, accounts) in ids.into_iter().zip(entity_accounts.chunks(3)) {
+            let [entity_account, seller_or_sender_account, buyer_or_carrier_account] = accounts else {
+                return Err(DLUError::InvalidExpiryBatch.into());
+            };
+            if entity_account.owner != resolver.program_id() {
+                return Err(DLUError::InvalidAccountOwner.into());
+            }
+            let refund_accounts = (vault_account, seller_or_sender_account, buyer_or_carrier_account, keeper_account);
+            let cranked = match entity_type {
+                EntityType::Offer => {
+                    let mut offer = load_offer(entity_account)?;
+                    if offer.id() != id || matches!(offer.kind(), OfferKind::Nft { .. }) {
+                        return Err(DLUError::InvalidExpiryBatch.into());
+                    }
+                    let locked_before = offer.escrowed_amount();
+                    let (vault, seller, buyer, keeper) = refund_accounts;
+                    let cranked = offer.crank_expire_offer(vault, seller, buyer, keeper, vault_seeds, bounty, now);
+                    if cranked.is_ok() {
+                        save_offer(entity_account, &mut offer, keeper_account.key)?;
+                    }
+                    cranked.map(|paid| (locked_before, offer.escrowed_amount(), paid))
+                }
+                EntityType::Request => {
+                    let mut request = load_request(entity_account)?;
+                    if request.id() != id {
+                        return Err(DLUError::InvalidExpiryBatch.into());
+                    }
+                    let locked_before = request.escrowed_amount();
+                    let (vault, seller, buyer, keeper) = refund_accounts;
+                    let cranked = request.crank_expire_request(vault, seller, buyer, keeper, vault_seeds, bounty, now);
+                    if cranked.is_ok() {
+                        save_request(entity_account, &mut request, keeper_account.key)?;
+                    }
+                    cranked.map(|paid| (locked_before, request.escrowed_amount(), paid))
+                }
+                EntityType::Shipment => {
+                    let mut shipment = load_shipment(entity_account)?;
+                    if shipment.id() != id {
+                        return Err(DLUError::InvalidExpiryBatch.into());
+                    }
+                    let locked_before = shipment.escrowed_amount();
+                    let (vault, sender, carrier, keeper) = refund_accounts;
+                    let cranked = shipment.crank_expire_shipment(vault, sender, carrier, keeper, vault_seeds, bounty, now);
+                    if cranked.is_ok() {
+                        save_shipment(entity_account, &mut shipment)?;
+                    }
+                    cranked.map(|paid| (locked_before, shipment.escrowed_amount(), paid))
+                }
+            };
+
+            // These checks fail before anything moves, so skipping the entity leaves it untouched.
+            let (locked_before, locked_after, paid) = match cranked {
+                Ok(cranked) => cranked,
+                Err(DLUError::DealNotExpired | DLUError::ShipmentNotExpired | DLUError::NotAccepted | DLUError::StreamStopped) => {
+                    continue
+                }
+                Err(error) => return Err(error.into()),
+            };
+            stats.release_value(vault_account.key, safe_math::sub(locked_before, locked_after)?);
+            DLUEvent::ExpiryCranked {
+                entity_type,
+                entity: *entity_account.key,
+                keeper_account: *keeper_account.key,
+                bounty: paid,
+                slot: time::slot()?,
+            }
+            .emit();
+        }
+
+        save_account_data(stats_account, &stats)
+    }
+
+    // VAULT REGISTRY
+
+    fn process_register_vault(resolver: &mut AccountsResolver) -> ProgramResult {
+        let config = load_config(resolver.next_config()?)?;
+        let governance_account = resolver.next_signer()?;
+        config.check_governance(governance_account.key)?;
+        config.check_direct_update()?;
+
+        register_vault(resolver, governance_account)?;
+        Ok(())
+    }
+
+    // SHIPMENT PRICING
+
+    fn process_set_shipping_rates(resolver: &mut AccountsResolver, rates: ShippingRates) -> ProgramResult {
+        let config_account = resolver.next_config()?;
+        let governance_account = resolver.next_signer()?;
+
+        let mut config = load_config(config_account)?;
+        config.check_direct_update()?;
+        config.set_shipping_rates(governance_account.key, rates)?;
+        msg!(
+            "Shipments are suggested {} plus {} per km and {} per kg",
+            rates.base_rate,
+            rates.rate_per_km,
+            rates.rate_per_kg
+        );
+
+        save_account_data(config_account, &config)
+    }
+
+    fn process_measure_shipment(
+        resolver: &mut AccountsResolver,
+        weight_grams: Option<u32>,
+        volume_cm3: Option<u32>,
+        distance_km: Option<u32>,
+    ) -> ProgramResult {
+        let shipment_account = resolver.next_program_account()?;
+        let sender_info = resolver.next_signer()?;
+        let config = load_config(resolver.next_config()?)?;
+
+        let mut shipment = load_shipment(shipment_account)?;
+        let suggested_payment =
+            shipment.measure(sender_info.key, weight_grams, volume_cm3, distance_km, config.shipping_rates)?;
+        save_shipment(shipment_account, &mut shipment)?;
+
+        DLUEvent::ShipmentMeasured {
+            shipment_id: shipment.id(),
+            payment: shipment.payment(),
+            suggested_payment,
+            slot: time::slot()?,
+        }
+        .emit();
+        Ok(())
+    }
+
+    // CARRIER ROUTES
+
+    fn process_publish_route(
+        resolver: &mut AccountsResolver,
+        route_id: u32,
+        origin: [u8; REGION_GEOHASH_LEN],
+        destination: [u8; REGION_GEOHASH_LEN],
+        days: u8,
+        capacity_grams: u64,
+    ) -> ProgramResult {
+        let carrier_info = resolver.next_signer()?;
+        let carrier = load_user(resolver.next_program_account()?)?;
+        if carrier.pubkey != *carrier_info.key {
+            return Err(DLUError::NotAuthorized.into());
+        }
+        check_not_banned(&carrier)?;
+        let route_account = resolver.next_carrier_route(carrier_info.key, route_id)?;
+        let index_account = resolver.next_route_index(&origin)?;
+        let system_program_info = resolver.next_with_key(&system_program::id())?;
+
+        // The carrier pays the rent of a new route, and of its origin's index when it is the
+        // first route from there.
+        let program_id = resolver.program_id();
+        let (_, route_bump) = derive_carrier_route_address(program_id, carrier_info.key, route_id);
+        let route_id_bytes = route_id.to_le_bytes();
+        let route_seeds: &[&[u8]] = &[CARRIER_ROUTE_SEED, carrier_info.key.as_ref(), &route_id_bytes, &[route_bump]];
+        create_pda_account(program_id, route_account, carrier_info, system_program_info, CARRIER_ROUTE_SIZE, route_seeds)?;
+        let (_, index_bump) = derive_route_index_address(program_id, &origin);
+        let index_seeds: &[&[u8]] = &[ROUTE_INDEX_SEED, &origin, &[index_bump]];
+        create_pda_account(program_id, index_account, carrier_info, system_program_info, ROUTE_INDEX_SIZE, index_seeds)?;
+
+        let mut route = load_carrier_route(route_account)?;
+        route.publish(*carrier_info.key, route_id, origin, destination, days, capacity_grams, time::now()?)?;
+        let mut index = load_route_index(index_account)?;
+        index.upsert(*route_account.key, &route)?;
+
+        DLUEvent::RoutePublished {
+            route: *route_account.key,
+            carrier: *carrier_info.key,
+            origin,
+            destination,
+            days,
+            capacity_grams,
+            slot: time::slot()?,
+        }
+        .emit();
+        save_account_data(route_account, &route)?;
+        save_account_data(index_account, &index)
+    }
+
+    fn process_close_route(resolver: &mut AccountsResolver, route_id: u32) -> ProgramResult {
+        let carrier_info = resolver.next_signer()?;
+        let route_account = resolver.next_carrier_route(carrier_info.key, route_id)?;
+        let route = load_carrier_route(route_account)?;
+        if !route.is_published() {
+            return Err(DLUError::InvalidRoute.into());
+        }
+        let index_account = resolver.next_route_index(&route.origin)?;
+
+        let mut index = load_route_index(index_account)?;
+        index.remove(route_account.key);
+        save_account_data(index_account, &index)?;
+
+        DLUEvent::RouteClosed { route: *route_account.key, carrier: *carrier_info.key, slot: time::slot()? }.emit();
+        close_account(route_account, carrier_info)
+    }
+
+    fn process_reference_route(resolver: &mut AccountsResolver) -> ProgramResult {
+        let shipment_account = resolver.next_program_account()?;
+        let sender_info = resolver.next_signer()?;
+        let route_account = resolver.next_program_account()?;
+
+        // An account never written still reads as an empty route, so it must sit at the
+        // route's own address.
+        let route = load_carrier_route(route_account)?;
+        let (route_key, _) = derive_carrier_route_address(resolver.program_id(), &route.carrier, route.route_id);
+        if route_key != *route_account.key {
+            return Err(DLUError::InvalidRoute.into());
+        }
+
+        let mut shipment = load_shipment(shipment_account)?;
+        shipment.reference_route(sender_info.key, route_key, &route)?;
+        save_shipment(shipment_account, &mut shipment)?;
+
+        DLUEvent::ShipmentRouted {
+            shipment_id: shipment.id(),
+            route: route_key,
+            carrier: route.carrier,
+            slot: time::slot()?,
+        }
+        .emit();
+        Ok(())
+    }
+}
+
+/// Takes the programs a listing tree is called through, after its other accounts.
+fn next_tree_accounts<'a, 'b>(
+    resolver: &mut AccountsResolver<'a, 'b>,
+    tree: &'a AccountInfo<'b>,
+    authority: &'a AccountInfo<'b>,
+) -> Result<TreeAccounts<'a, 'b>, ProgramError> {
+    Ok(TreeAccounts {
+        tree,
+        authority,
+        compression_program: resolver.next_with_key(&ACCOUNT_COMPRESSION_PROGRAM_ID)?,
+        noop_program: resolver.next_with_key(&NOOP_PROGRAM_ID)?,
+    })
+}
+
+/// Takes the signer acting for `owner` on `entity` as a finisher: the owner themselves, a
+/// session key followed by the Delegation the owner granted it, or a member followed by the
+/// owner's Organization.
+fn next_signer_or_delegate(resolver: &mut AccountsResolver, owner: &Pubkey, entity: &Pubkey) -> ProgramResult {
+    let signer_info = resolver.next_signer()?;
+    if signer_info.key == owner {
+        return Ok(());
+    }
+    let (delegation_key, _) = derive_delegation_address(resolver.program_id(), owner, signer_info.key);
+    if resolver.peek_key() == Some(delegation_key) {
+        let delegation_account = resolver.next_delegation(owner, signer_info.key)?;
+        return Ok(load_delegation(delegation_account)?.check(signer_info.key, entity, time::now()?)?);
+    }
+    check_member(resolver, signer_info.key, owner, MEMBER_FINISHER)
+}
+
+/// Checks that the signer acts for the user `party` in `role`: it is the user themselves,
+/// or a member holding the role, whose Organization account the resolver takes next.
+fn check_member(resolver: &mut AccountsResolver, signer: &Pubkey, party: &Pubkey, role: u8) -> ProgramResult {
+    if signer == party {
+        return Ok(());
+    }
+    let organization = load_organization(resolver.next_organization(party)?)?;
+    Ok(organization.check_member(signer, role)?)
+}
+
+/// Checks the signatures `user` needs to act on `amount` when governed by a multisig: the
+/// user's Multisig account comes next, followed by the cosigners who, with `signer`, must
+/// reach its threshold once the amount is high.
+fn check_multisig(resolver: &mut AccountsResolver, user: &User, signer: &Pubkey, amount: u64) -> ProgramResult {
+    if !user.has_flag(USER_MULTISIG) {
+        return Ok(());
+    }
+    let multisig = load_multisig(resolver.next_multisig(&user.pubkey)?)?;
+    let mut signatures = resolver.next_cosigners();
+    signatures.push(*signer);
+    Ok(multisig.check(&signatures, amount)?)
+}
+
+/// Credits the referrer of `payee` with their share of the collected `fee`, which stays in
+/// the treasury until claimed: the referrer's ReferralRewards account for the treasury's
+/// mint comes next when the payee was referred, followed by the system program and the rent
+/// payer the first credit creates the account with.
+fn credit_referrer(
+    resolver: &mut AccountsResolver,
+    config: &Config,
+    payee: &User,
+    treasury_account: &AccountInfo,
+    fee: u64,
+) -> ProgramResult {
+    let referrer = match payee.referrer {
+        Some(referrer) => referrer,
+        None => return Ok(()),
+    };
+    let mint = DLUToken::get_mint(treasury_account)?;
+    let rewards_account = resolver.next_referral_rewards(&referrer, &mint)?;
+    let system_program_info = resolver.next_with_key(&system_program::id())?;
+    let payer_info = resolver.next_signer()?;
+    let (_, bump) = derive_referral_address(resolver.program_id(), &referrer, &mint);
+    let seeds: &[&[u8]] = &[REFERRAL_SEED, referrer.as_ref(), mint.as_ref(), &[bump]];
+    create_pda_account(resolver.program_id(), rewards_account, payer_info, system_program_info, REFERRAL_REWARDS_SIZE, seeds)?;
+    let mut rewards = if rewards_account.data.borrow().iter().all(|b| *b == 0) {
+        ReferralRewards::new(referrer, mint)
+    } else {
+        load_referral_rewards(rewards_account)?
+    };
+    rewards.credit(config.referral_share(fee)?)?;
+    save_account_data(rewards_account, &rewards)
+}
+
+/// Pays the parties of a completed `entity` their completion reward out of the rewards vault
+/// of the payment mint, once the config schedules rewards: the mint's RewardEmission and
+/// rewards vault come next, followed by the system program and the rent payer the first
+/// reward creates the emission with, starting its decay. Each party's token account must
+/// be their own.
+fn pay_completion_rewards(
+    resolver: &mut AccountsResolver,
+    config: &Config,
+    entity: (EntityType, Pubkey),
+    treasury_account: &AccountInfo,
+    parties: &[(&AccountInfo, &Pubkey)],
+) -> ProgramResult {
+    if config.completion_reward == 0 {
+        return Ok(());
+    }
+    for (party_account, party) in parties {
+        if !DLUToken::check_authority(party_account, party)? {
+            return Err(DLUError::KeyMismatch.into());
+        }
+    }
+    let mint = DLUToken::get_mint(treasury_account)?;
+    let emission_account = resolver.next_reward_emission(&mint)?;
+    let rewards_vault_account = resolver.next_rewards_vault(&mint)?;
+    let system_program_info = resolver.next_with_key(&system_program::id())?;
+    let payer_info = resolver.next_signer()?;
+    let (_, emission_bump) = derive_reward_emission_address(resolver.program_id(), &mint);
+    let seeds: &[&[u8]] = &[REWARD_EMISSION_SEED, mint.as_ref(), &[emission_bump]];
+    create_pda_account(resolver.program_id(), emission_account, payer_info, system_program_info, REWARD_EMISSION_SIZE, seeds)?;
+    let now = time::now()?;
+    let mut emission = if emission_account.data.borrow().iter().all(|b| *b == 0) {
+        RewardEmission::new(mint, now)
+    } else {
+        load_reward_emission(emission_account)?
+    };
+
+    // Rewards stop once the cap or the vault runs out, never blocking the completion.
+    let available = DLUToken::get_balance(rewards_vault_account)?;
+    let reward = emission.emit(config, parties.len() as u64, available, now)?;
+    if reward > 0 {
+        // The rewards vault is its own authority.
+        let (_, bump) = derive_rewards_vault_address(resolver.program_id(), &mint);
+        for (party_account, _) in parties {
+            DLUToken::transfer_signed(
+                rewards_vault_account,
+                party_account,
+                rewards_vault_account,
+                reward,
+                &[&[REWARDS_VAULT_SEED, mint.as_ref(), &[bump]]],
+            )?;
+        }
+        let (entity_type, entity) = entity;
+        DLUEvent::CompletionRewarded { entity_type, entity, mint, reward, slot: time::slot()? }.emit();
+    }
+    save_account_data(emission_account, &emission)
+}
+
+/// Applies the discount of the promo `code` the buyer presents to the listed `offer`: the
+/// offer's PromoCodes account comes next.
+fn redeem_promo_code(
+    resolver: &mut AccountsResolver,
+    offer_key: &Pubkey,
+    offer: &mut Offer,
+    buyer: &User,
+    code: &str,
+) -> ProgramResult {
+    let promo_account = resolver.next_promo_codes(offer_key)?;
+    let mut promo_codes = load_promo_codes(promo_account)?;
+    let discount = offer.discount(promo_codes.redeem(offer_key, code)?)?;
+    DLUEvent::PromoCodeRedeemed { offer: *offer_key, buyer: buyer.pubkey, discount, slot: time::slot()? }.emit();
+    save_account_data(promo_account, &promo_codes)
+}
+
+/// Checks that every party to a deal paying `payment` carries a valid attestation once the
+/// payment is above the KYC threshold: the AttestorRegistry comes next, followed by the
+/// parties' Attestation accounts in order.
+fn check_attestations(resolver: &mut AccountsResolver, config: &Config, payment: u64, parties: &[Pubkey]) -> ProgramResult {
+    if !config.requires_attestation(payment) {
+        return Ok(());
+    }
+    let registry = load_attestor_registry(resolver.next_attestor_registry()?)?;
+    let now = time::now()?;
+    for party in parties {
+        let attestation = load_attestation(resolver.next_attestation(party)?)?;
+        attestation.check(&registry, party, now)?;
+    }
+    Ok(())
+}
+
+/// Moves the claimant's fail bond into the vault and records it against the failed entity,
+/// in a FailBond account `payer_info` funds.
+fn post_fail_bond<'a>(
+    program_id: &Pubkey,
+    config: &Config,
+    bond_account: &AccountInfo<'a>,
+    entity_type: EntityType,
+    entity: &Pubkey,
+    claimant_info: &AccountInfo,
+    claimant_account: &AccountInfo,
+    accused: &Pubkey,
+    vault_account: &AccountInfo,
+    payer_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    stats: &mut Stats,
+) -> ProgramResult {
+    DLUToken::transfer(claimant_account, vault_account, claimant_info, config.fail_bond)?;
+    stats.lock_value(config, vault_account.key, config.fail_bond)?;
+
+    let (_, bump) = derive_bond_address(program_id, entity);
+    let seeds: &[&[u8]] = &[BOND_SEED, entity.as_ref(), &[bump]];
+    create_pda_account(program_id, bond_account, payer_info, system_program_info, FAIL_BOND_SIZE, seeds)?;
+    let bond = FailBond::post(entity_type, *entity, *claimant_info.key, *accused, config.fail_bond, time::now()?);
+    save_account_data(bond_account, &bond)
+}
+
+/// Pays a settled fail bond out of the vault to the recipient's token account.
+fn settle_fail_bond(
+    bond: &FailBond,
+    recipient: &Pubkey,
+    vault_account: &AccountInfo,
+    vault_seeds: &[&[u8]],
+    recipient_account: &AccountInfo,
+    stats: &mut Stats,
+) -> ProgramResult {
+    if !DLUToken::check_authority(recipient_account, recipient)? {
+        return Err(DLUError::KeyMismatch.into());
+    }
+
+    DLUToken::transfer_signed(vault_account, recipient_account, vault_account, bond.amount, &[vault_seeds])?;
+    stats.release_value(vault_account.key, bond.amount);
+
+    DLUEvent::FailBondSettled {
+        entity: bond.entity,
+        recipient: *recipient,
+        amount: bond.amount,
+        forfeited: *recipient == bond.accused,
+        slot: time::slot()?,
+    }.emit();
+
+    Ok(())
+}
+
+/// Pays a share of an appeal bond out of the vault to a token account of `owner`.
+fn pay_bond_share(
+    vault_account: &AccountInfo,
+    destination_account: &AccountInfo,
+    vault_seeds: &[&[u8]],
+    owner: &Pubkey,
+    payment_mint: &Pubkey,
+    amount: u64,
+) -> ProgramResult {
+    DLUToken::check_payment_mint(payment_mint, &[destination_account])?;
+    if !DLUToken::check_authority(destination_account, owner)? {
+        return Err(DLUError::KeyMismatch.into());
+    }
+    if amount > 0 {
+        DLUToken::transfer_signed(vault_account, destination_account, vault_account, amount, &[vault_seeds])?;
+    }
+    Ok(())
+}
+
+/// Takes an NFT offer's NFT from the seller into the offer's NFT escrow, creating the
+/// escrow on first listing. Other offers take no NFT accounts.
+fn deposit_nft<'b>(
+    resolver: &mut AccountsResolver<'_, 'b>,
+    offer_account: &AccountInfo,
+    offer: &Offer,
+    seller_info: &AccountInfo<'b>,
+) -> ProgramResult {
+    if !matches!(offer.kind(), OfferKind::Nft { .. }) {
+        return Ok(());
+    }
+    let seller_nft_account = resolver.next_token_account()?;
+    let nft_escrow_account = resolver.next_nft_escrow(offer_account.key)?;
+    let nft_mint_info = resolver.next()?;
+    resolver.next_with_key(&system_program::id())?;
+    resolver.next_with_key(&DLUToken::token_program_id(nft_mint_info)?)?;
+    let payer_info = resolver.next_payer(seller_info);
+
+    // A relisted offer reuses the escrow its NFT was returned from.
+    if nft_escrow_account.data_is_empty() {
+        let (_, bump) = derive_nft_escrow_address(resolver.program_id(), offer_account.key);
+        let nft_escrow_seeds: &[&[u8]] = &[NFT_ESCROW_SEED, offer_account.key.as_ref(), &[bump]];
+        DLUToken::create_self_owned_account(payer_info, nft_escrow_account, nft_mint_info, nft_escrow_seeds)?;
+    }
+    Ok(offer.deposit_nft(seller_nft_account, nft_escrow_account, nft_mint_info, seller_info)?)
+}
+
+/// Announces a new listing in the index of its location's geohash region, creating the
+/// index for the region's first listing with the lister, or the rent payer following the
+/// system program, paying its rent. Listings at a location without a geohash take no
+/// region accounts.
+fn add_to_region<'b>(
+    resolver: &mut AccountsResolver<'_, 'b>,
+    region: Option<[u8; REGION_GEOHASH_LEN]>,
+    address: Pubkey,
+    entity_type: EntityType,
+    lister_info: &AccountInfo<'b>,
+) -> ProgramResult {
+    let Some(prefix) = region else {
+        return Ok(());
+    };
+    let region_account = resolver.next_region(&prefix)?;
+    let system_program_info = resolver.next_with_key(&system_program::id())?;
+    let payer_info = resolver.next_payer(lister_info);
+    let mut index = if region_account.owner != resolver.program_id() {
+        let (_, bump) = derive_region_address(resolver.program_id(), &prefix);
+        let region_seeds: &[&[u8]] = &[REGION_SEED, &prefix, &[bump]];
+        create_pda_account(resolver.program_id(), region_account, payer_info, system_program_info, REGION_INDEX_SIZE, region_seeds)?;
+        RegionIndex::default()
+    } else {
+        load_account_data(region_account, RegionIndex::deserialize)?
+    };
+    index.add(prefix, RegionListing { address, entity_type, listed_at: time::now()? });
+    save_account_data(region_account, &index)
+}
+
+/// Loads a wallet's watchlist, creating it on the wallet's first follow or watch with the
+/// wallet, or the rent payer following the system program, paying its rent.
+fn load_or_create_watchlist<'b>(
+    resolver: &mut AccountsResolver<'_, 'b>,
+    watchlist_account: &AccountInfo<'b>,
+    owner_info: &AccountInfo<'b>,
+) -> Result<Watchlist, ProgramError> {
+    let system_program_info = resolver.next_with_key(&system_program::id())?;
+    let payer_info = resolver.next_payer(owner_info);
+    let (_, bump) = derive_watchlist_address(resolver.program_id(), owner_info.key);
+    let watchlist_seeds: &[&[u8]] = &[WATCHLIST_SEED, owner_info.key.as_ref(), &[bump]];
+    create_pda_account(resolver.program_id(), watchlist_account, payer_info, system_program_info, WATCHLIST_SIZE, watchlist_seeds)?;
+    load_watchlist(watchlist_account)
+}
+
+/// Loads the KeyManager of a deal or shipment, creating it on the entity's first key commit
+/// with the signer, or the rent payer following the system program, paying its rent.
+fn load_or_create_key_manager<'b>(
+    resolver: &mut AccountsResolver<'_, 'b>,
+    key_manager_account: &AccountInfo<'b>,
+    entity: &Pubkey,
+    signer_info: &AccountInfo<'b>,
+) -> Result<KeyManager, ProgramError> {
+    let system_program_info = resolver.next_with_key(&system_program::id())?;
+    let payer_info = resolver.next_payer(signer_info);
+    let (_, bump) = derive_key_manager_address(resolver.program_id(), entity);
+    let key_manager_seeds: &[&[u8]] = &[KEY_MANAGER_SEED, entity.as_ref(), &[bump]];
+    create_pda_account(resolver.program_id(), key_manager_account, payer_info, system_program_info, KEY_MANAGER_SIZE, key_manager_seeds)?;
+    load_key_manager(key_manager_account)
+}
+
+/// Releases an NFT offer's NFT from the offer's NFT escrow, which is its own authority, to a
+/// token account of the recipient. Other offers take no NFT accounts.
+fn release_nft(
+    resolver: &mut AccountsResolver,
+    offer_account: &AccountInfo,
+    offer: &Offer,
+    recipient: &Pubkey,
+) -> ProgramResult {
+    if !matches!(offer.kind(), OfferKind::Nft { .. }) {
+        return Ok(());
+    }
+    let nft_escrow_account = resolver.next_nft_escrow(offer_account.key)?;
+    let recipient_nft_account = resolver.next_token_account()?;
+
+    let (_, bump) = derive_nft_escrow_address(resolver.program_id(), offer_account.key);
+    let nft_escrow_seeds: &[&[u8]] = &[NFT_ESCROW_SEED, offer_account.key.as_ref(), &[bump]];
+    Ok(offer.release_nft(nft_escrow_account, recipient_nft_account, recipient, nft_escrow_seeds)?)
+}
+
+/// Takes the escrow vault of `mint` and the VaultRegistry after it, checking the vault is the
+/// one governance registered, and returns it with the bump of its address. The vault is its
+/// own authority, so the program signs every release out of it.
+fn next_registered_vault<'a, 'b>(
+    resolver: &mut AccountsResolver<'a, 'b>,
+    mint: &Pubkey,
+) -> Result<(&'a AccountInfo<'b>, u8), ProgramError> {
+    let vault_account = resolver.next_vault(mint)?;
+    let registry = load_vault_registry(resolver.next_vault_registry()?)?;
+    registry.check_registered(mint, vault_account.key)?;
+    let (_, bump) = derive_vault_address(resolver.program_id(), mint);
+    Ok((vault_account, bump))
+}
+
+/// Withdraws `amount` from the treasury token account of a mint into the destination token
+/// account after it, returning the mint and the destination.
+fn withdraw_treasury(resolver: &mut AccountsResolver, amount: u64) -> Result<(Pubkey, Pubkey), ProgramError> {
+    let treasury_account = resolver.next_treasury()?;
+    let destination_account = resolver.next_token_account()?;
+
+    // The treasury is its own authority, so the program signs for it.
+    let mint = DLUToken::get_mint(treasury_account)?;
+    let (_, bump) = derive_treasury_address(resolver.program_id(), &mint);
+    DLUToken::transfer_signed(
+        treasury_account,
+        destination_account,
+        treasury_account,
+        amount,
+        &[&[TREASURY_SEED, mint.as_ref(), &[bump]]],
+    )?;
+    msg!("Withdrew {} from the treasury", amount);
+
+    Ok((mint, *destination_account.key))
+}
+
+/// Creates and registers the vault of the mint among the accounts that follow, along with the
+/// mint's treasury, penalty vault and rewards vault, with the payer funding their rent and
+/// that of a new VaultRegistry, and returns the mint.
+fn register_vault<'b>(resolver: &mut AccountsResolver<'_, 'b>, payer_info: &AccountInfo<'b>) -> Result<Pubkey, ProgramError> {
+    let registry_account = resolver.next_vault_registry()?;
+    let vault_info = resolver.next()?;
+    let treasury_info = resolver.next()?;
+    let penalty_vault_info = resolver.next()?;
+    let rewards_vault_info = resolver.next()?;
+    let mint_info = resolver.next()?;
+    let system_program_info = resolver.next_with_key(&system_program::id())?;
+    resolver.next_with_key(&DLUToken::token_program_id(mint_info)?)?;
+
+    let program_id = resolver.program_id();
+    let mint = *mint_info.key;
+    let (vault_key, bump) = derive_vault_address(program_id, &mint);
+    let (_, registry_bump) = derive_vault_registry_address(program_id);
+    let registry_seeds: &[&[u8]] = &[VAULT_REGISTRY_SEED, &[registry_bump]];
+    create_pda_account(program_id, registry_account, payer_info, system_program_info, VAULT_REGISTRY_SIZE, registry_seeds)?;
+    let mut registry = load_vault_registry(registry_account)?;
+    registry.register(mint, vault_key, time::now()?)?;
+
+    // Each is a token account of the mint at a PDA that is its own authority.
+    for (account_info, seed, (key, bump)) in [
+        (vault_info, VAULT_SEED, (vault_key, bump)),
+        (treasury_info, TREASURY_SEED, derive_treasury_address(program_id, &mint)),
+        (penalty_vault_info, PENALTY_VAULT_SEED, derive_penalty_vault_address(program_id, &mint)),
+        (rewards_vault_info, REWARDS_VAULT_SEED, derive_rewards_vault_address(program_id, &mint)),
+    ] {
+        if *account_info.key != key {
+            return Err(DLUError::KeyMismatch.into());
+        }
+        DLUToken::create_self_owned_account(payer_info, account_info, mint_info, &[seed, mint.as_ref(), &[bump]])?;
+    }
+
+    DLUEvent::VaultRegistered { mint: *mint_info.key, vault: vault_key, slot: time::slot()? }.emit();
+
+    save_account_data(registry_account, &registry)?;
+    Ok(*mint_info.key)
+}
+
+/// Moves the funds of the deal or shipment among the accounts that follow from its legacy
+/// escrow into the registered vault of its payment mint, returning the entity and the legacy
+/// escrow. The transition is recorded as governance's.
+fn migrate_escrow(resolver: &mut AccountsResolver, config: &Config, entity_type: EntityType) -> Result<(Pubkey, Pubkey), ProgramError> {
+    config.require_capability(CAP_ESCROW_MIGRATION)?;
+
+    let entity_account = resolver.next_program_account()?;
+    let legacy_escrow_account = resolver.next_token_account()?;
+    let legacy_escrow_authority = resolver.next_signer()?;
+    let mint = load_payment_mint(entity_account, entity_type)?;
+    let (vault_account, _) = next_registered_vault(resolver, &mint)?;
+
+    // Rewrite the entity's escrow terms first; this rejects terminal or already migrated entities.
+    let amount = match entity_type {
+        EntityType::Offer => {
+            let mut offer = load_offer(entity_account)?;
+            let amount = offer.migrate_escrow(legacy_escrow_account, vault_account)?;
+            save_offer(entity_account, &mut offer, &config.governance)?;
+            amount
+        }
+        EntityType::Request => {
+            let mut request = load_request(entity_account)?;
+            let amount = request.migrate_escrow(legacy_escrow_account, vault_account)?;
+            save_request(entity_account, &mut request, &config.governance)?;
+            amount
+        }
+        EntityType::Shipment => {
+            let mut shipment = load_shipment(entity_account)?;
+            let amount = shipment.migrate_escrow(legacy_escrow_account, vault_account)?;
+            save_shipment(entity_account, &mut shipment)?;
+            amount
+        }
+    };
+
+    DLUToken::transfer(legacy_escrow_account, vault_account, legacy_escrow_authority, amount)?;
+
+    DLUEvent::EscrowMigrated {
+        entity_type,
+        entity: *entity_account.key,
+        legacy_escrow: *legacy_escrow_account.key,
+        vault: *vault_account.key,
+        amount,
+        slot: time::slot()?,
+    }.emit();
+
+    Ok((*entity_account.key, *legacy_escrow_account.key))
+}
+
+/// Returns the mint an offer, request or shipment is paid in.
+fn load_payment_mint(entity_account: &AccountInfo, entity_type: EntityType) -> Result<Pubkey, ProgramError> {
+    Ok(match entity_type {
+        EntityType::Offer => load_offer(entity_account)?.payment_mint(),
+        EntityType::Request => load_request(entity_account)?.payment_mint(),
+        EntityType::Shipment => load_shipment(entity_account)?.payment_mint(),
+    })
+}
+
+/// Brings a user's decayed reputation up to the current slot before new operations are recorded.
+fn decay_reputation(user: &mut User, config: &Config) -> ProgramResult {
+    user.decay_reputation(time::slot()?, config.reputation_half_life);
+    Ok(())
+}
+
+fn load_config(account: &AccountInfo) -> Result<Config, ProgramError> {
+    load_account_data(account, Config::deserialize)
+}
+
+fn load_stats(account: &AccountInfo) -> Result<Stats, ProgramError> {
+    load_account_data(account, Stats::deserialize)
+}
+
+/// Rewrites a user's ProfileSummary account after their reputation changed.
+fn refresh_profile_summary(summary_account: &AccountInfo, user: &User) -> ProgramResult {
+    let summary = ProfileSummary::from_user(user, time::slot()?);
+    write_raw_account_data(summary_account, &summary.pack())
+}
+
+fn load_bond(account: &AccountInfo) -> Result<FailBond, ProgramError> {
+    load_account_data(account, FailBond::deserialize)
+}
+
+fn load_arbiter(account: &AccountInfo) -> Result<Arbiter, ProgramError> {
+    load_account_data(account, Arbiter::deserialize)
+}
+
+fn load_arbiter_registry(account: &AccountInfo) -> Result<ArbiterRegistry, ProgramError> {
+    load_account_data(account, ArbiterRegistry::deserialize)
+}
+
+fn load_arbitration(account: &AccountInfo) -> Result<Arbitration, ProgramError> {
+    load_account_data(account, Arbitration::deserialize)
+}
+
+fn load_appeal(account: &AccountInfo) -> Result<Appeal, ProgramError> {
+    load_account_data(account, Appeal::deserialize)
+}
+
+fn load_delegation(account: &AccountInfo) -> Result<Delegation, ProgramError> {
+    load_account_data(account, Delegation::deserialize)
+}
+
+fn load_organization(account: &AccountInfo) -> Result<Organization, ProgramError> {
+    load_account_data(account, Organization::deserialize)
+}
+
+fn load_multisig(account: &AccountInfo) -> Result<Multisig, ProgramError> {
+    load_account_data(account, Multisig::deserialize)
+}
+
+fn load_attestor_registry(account: &AccountInfo) -> Result<AttestorRegistry, ProgramError> {
+    load_account_data(account, AttestorRegistry::deserialize)
+}
+
+fn load_attestation(account: &AccountInfo) -> Result<Attestation, ProgramError> {
+    load_account_data(account, Attestation::deserialize)
+}
+
+fn load_referral_rewards(account: &AccountInfo) -> Result<ReferralRewards, ProgramError> {
+    load_account_data(account, ReferralRewards::deserialize)
+}
+
+fn load_promo_codes(account: &AccountInfo) -> Result<PromoCodes, ProgramError> {
+    load_account_data(account, PromoCodes::deserialize)
+}
+
+fn load_watchlist(account: &AccountInfo) -> Result<Watchlist, ProgramError> {
+    load_account_data(account, Watchlist::deserialize)
+}
+
+fn load_carrier_route(account: &AccountInfo) -> Result<CarrierRoute, ProgramError> {
+    load_account_data(account, CarrierRoute::deserialize)
+}
+
+fn load_route_index(account: &AccountInfo) -> Result<RouteIndex, ProgramError> {
+    load_account_data(account, RouteIndex::deserialize)
+}
+
+fn load_leaderboard(account: &AccountInfo) -> Result<Leaderboard, ProgramError> {
+    load_account_data(account, Leaderboard::deserialize)
+}
+
+fn load_history(account: &AccountInfo) -> Result<History, ProgramError> {
+    load_account_data(account, History::deserialize)
+}
+
+fn load_key_manager(account: &AccountInfo) -> Result<KeyManager, ProgramError> {
+    load_account_data(account, KeyManager::deserialize)
+}
+
+fn load_emergency_resolution(account: &AccountInfo) -> Result<EmergencyResolution, ProgramError> {
+    load_account_data(account, EmergencyResolution::deserialize)
+}
+
+/// Loads an entity's EmergencyResolution, which holds no proposal until governance's first
+/// proposal for the entity creates its account.
+fn load_proposed_emergency(program_id: &Pubkey, account: &AccountInfo) -> Result<EmergencyResolution, ProgramError> {
+    if account.owner != program_id {
+        return Ok(EmergencyResolution::default());
+    }
+    load_emergency_resolution(account)
+}
+
+fn load_timelock_queue(account: &AccountInfo) -> Result<TimelockQueue, ProgramError> {
+    load_account_data(account, TimelockQueue::deserialize)
+}
+
+fn load_dao(account: &AccountInfo) -> Result<Dao, ProgramError> {
+    load_account_data(account, Dao::deserialize)
+}
+
+fn load_vote_lock(account: &AccountInfo) -> Result<VoteLock, ProgramError> {
+    load_account_data(account, VoteLock::deserialize)
+}
+
+fn load_proposal(account: &AccountInfo) -> Result<Proposal, ProgramError> {
+    load_account_data(account, Proposal::deserialize)
+}
+
+fn load_penalty_pool(account: &AccountInfo) -> Result<PenaltyPool, ProgramError> {
+    load_account_data(account, PenaltyPool::deserialize)
+}
+
+fn load_penalty_rewards(account: &AccountInfo) -> Result<PenaltyRewards, ProgramError> {
+    load_account_data(account, PenaltyRewards::deserialize)
+}
+
+fn load_vault_registry(account: &AccountInfo) -> Result<VaultRegistry, ProgramError> {
+    load_account_data(account, VaultRegistry::deserialize)
+}
+
+fn load_reward_emission(account: &AccountInfo) -> Result<RewardEmission, ProgramError> {
+    load_account_data(account, RewardEmission::deserialize)
+}
+
+/// Ranks `user` on the leaderboards of the given kinds, which the client passes last and
+/// may leave out: leaderboards are only updated when a completion carries them. The system
+/// program and the rent payer follow them, creating each leaderboard on its first ranking.
+fn update_leaderboards(resolver: &mut AccountsResolver, user: &User, kinds: &[LeaderboardKind]) -> ProgramResult {
+    if !resolver.has_next() {
+        return Ok(());
+    }
+    let leaderboard_accounts = kinds.iter().map(|kind| resolver.next_leaderboard(*kind)).collect::<Result<Vec<_>, _>>()?;
+    let system_program_info = resolver.next_with_key(&system_program::id())?;
+    let payer_info = resolver.next_signer()?;
+    for (kind, leaderboard_account) in kinds.iter().zip(leaderboard_accounts) {
+        let (_, bump) = derive_leaderboard_address(resolver.program_id(), *kind);
+        let leaderboard_seeds: &[&[u8]] = &[LEADERBOARD_SEED, &[*kind as u8], &[bump]];
+        create_pda_account(resolver.program_id(), leaderboard_account, payer_info, system_program_info, LEADERBOARD_SIZE, leaderboard_seeds)?;
+        let mut leaderboard = load_leaderboard(leaderboard_account)?;
+        leaderboard.rank(user.pubkey, kind.score(user));
+        save_account_data(leaderboard_account, &leaderboard)?;
+    }
+    Ok(())
+}
+
+fn load_stake(account: &AccountInfo) -> Result<CarrierStake, ProgramError> {
+    load_account_data(account, CarrierStake::deserialize)
+}
+
+fn save_stake(account: &AccountInfo, stake: &CarrierStake) -> ProgramResult {
+    save_account_data(account, stake)
+}
+
+fn load_index(account: &AccountInfo) -> Result<IndexAccount, ProgramError> {
+    load_account_data(account, |input| IndexAccount::deserialize(input).map_err(|_| DLUError::DeserializationFailed))
+}
+
+fn save_index(account: &AccountInfo, index: &IndexAccount) -> ProgramResult {
+    let data = index.serialize().map_err(|_| DLUError::SerializationFailed)?;
+    write_growing_account_data(account, AccountKind::Index, &data)
+}
+
+fn load_user(account: &AccountInfo) -> Result<User, ProgramError> {
+    load_account_data(account, |input| User::deserialize(input).map_err(|_| DLUError::DeserializationFailed))
+}
+
+fn save_user(account: &AccountInfo, user: &User) -> ProgramResult {
+    let data = user.serialize().map_err(|_| DLUError::SerializationFailed)?;
+    write_growing_account_data(account, AccountKind::User, &data)
+}
+
+fn load_offer(account: &AccountInfo) -> Result<Offer, ProgramError> {
+    load_account_data(account, Offer::deserialize)
+}
+
+/// Saves an offer. When `actor` listed it or changed its status, the transition goes into
+/// the offer's audit log and its watchers are notified.
+fn save_offer(account: &AccountInfo, offer: &mut Offer, actor: &Pubkey) -> ProgramResult {
+    if migrations::deal_status_of(&account.data.borrow()) != Some(offer.status()) {
+        let slot = time::slot()?;
+        offer.record_transition(*actor, slot);
+        DLUEvent::OfferStatusChanged {
+            offer: *account.key,
+            seller: offer.seller_pubkey(),
+            status: offer.status(),
+            slot,
+        }.emit();
+    }
+    write_growing_account_data(account, AccountKind::Offer, &offer.serialize()?)
+}
+
+fn load_request(account: &AccountInfo) -> Result<Request, ProgramError> {
+    load_account_data(account, Request::deserialize)
+}
+
+/// Saves a request. When `actor` listed it or changed its status, the transition goes into
+/// the request's audit log.
+fn save_request(account: &AccountInfo, request: &mut Request, actor: &Pubkey) -> ProgramResult {
+    if migrations::deal_status_of(&account.data.borrow()) != Some(request.status()) {
+        request.record_transition(*actor, time::slot()?);
+    }
+    write_growing_account_data(account, AccountKind::Request, &request.serialize()?)
+}
+
+fn load_quote(account: &AccountInfo) -> Result<Quote, ProgramError> {
+    load_account_data(account, Quote::deserialize)
+}
+
+fn save_quote(account: &AccountInfo, quote: &Quote) -> ProgramResult {
+    save_account_data(account, quote)
+}
+
+fn load_shipment(account: &AccountInfo) -> Result<Shipment, ProgramError> {
+    load_account_data(account, Shipment::deserialize)
+}
+
+/// Saves a shipment, advancing its nonce when it was listed or changed its status.
+fn save_shipment(account: &AccountInfo, shipment: &mut Shipment) -> ProgramResult {
+    if migrations::shipment_status_of(&account.data.borrow()) != Some(shipment.status()) {
+        shipment.advance_nonce();
+    }
+    write_growing_account_data(account, AccountKind::Shipment, &shipment.serialize()?)
+}
+
+/// Creates a program account with room for a `payload_len`-byte payload behind its length
+/// prefix at a program-derived address, with the payer funding its rent. An account the
+/// program owns already is left as it is, so handlers can call this for accounts created on
+/// first use.
+fn create_pda_account<'a>(
+    program_id: &Pubkey,
+    account: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    payload_len: usize,
+    signer_seeds: &[&[u8]],
+) -> ProgramResult {
+    let space = migrations::account_space(payload_len);
+    create_raw_pda_account(program_id, account, payer, system_program_info, space, signer_seeds)
+}
+
+/// Creates a program account of exactly `space` bytes at a program-derived address, like
+/// `create_pda_account`. Profile summaries, whose public layout has no header, are created
+/// through it directly.
+fn create_raw_pda_account<'a>(
+    program_id: &Pubkey,
+    account: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    space: usize,
+    signer_seeds: &[&[u8]],
+) -> ProgramResult {
+    if account.owner == program_id {
+        return Ok(());
+    }
+    if *account.owner != system_program::id() || !account.data_is_empty() {
+        return Err(DLUError::InvalidAccountOwner.into());
+    }
+    let required = Rent::get()?.minimum_balance(space);
+    if account.lamports() == 0 {
+        return invoke_signed(
+            &system_instruction::create_account(payer.key, account.key, required, space as u64, program_id),
+            &[payer.clone(), account.clone(), system_program_info.clone()],
+            &[signer_seeds],
+        );
+    }
+
+    // Anyone can send lamports to the address beforehand, which `create_account` refuses, so
+    // such an account is topped up, allocated and assigned instead.
+    let shortfall = required.saturating_sub(account.lamports());
+    if shortfall > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, account.key, shortfall),
+            &[payer.clone(), account.clone(), system_program_info.clone()],
+        )?;
+    }
+    invoke_signed(
+        &system_instruction::allocate(account.key, space as u64),
+        &[account.clone(), system_program_info.clone()],
+        &[signer_seeds],
+    )?;
+    invoke_signed(
+        &system_instruction::assign(account.key, program_id),
+        &[account.clone(), system_program_info.clone()],
+        &[signer_seeds],
+    )
+}
+
+/// Checks that `authority` may upgrade the program, as recorded in its ProgramData account.
+fn check_upgrade_authority(program_data_account: &AccountInfo, authority: &Pubkey) -> ProgramResult {
+    if *program_data_account.owner != bpf_loader_upgradeable::id() {
+        return Err(DLUError::InvalidAccountOwner.into());
+    }
+    let data = program_data_account.data.borrow();
+    let metadata = data
+        .get(..UpgradeableLoaderState::size_of_programdata_metadata())
+        .ok_or(DLUError::DeserializationFailed)?;
+    match limited_deserialize(metadata) {
+        Ok(UpgradeableLoaderState::ProgramData { upgrade_authority_address: Some(upgrade_authority), .. })
+            if upgrade_authority == *authority => Ok(()),
+        Ok(_) => Err(DLUError::NotAuthorized.into()),
+        Err(_) => Err(DLUError::DeserializationFailed.into()),
+    }
+}
+
+/// Reallocates an account to `new_len` bytes, with the payer topping up its rent exemption.
+fn grow_account<'a>(
+    account: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    new_len: usize,
+) -> ProgramResult {
+    let required = Rent::get()?.minimum_balance(new_len);
+    let shortfall = required.saturating_sub(account.lamports());
+    if shortfall > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, account.key, shortfall),
+            &[payer.clone(), account.clone(), system_program_info.clone()],
+        )?;
+    }
+    account.realloc(new_len, false)
+}
+
+/// Closes a program account, moving its rent to `recipient` and wiping its data.
+fn close_account(account: &AccountInfo, recipient: &AccountInfo) -> ProgramResult {
+    let lamports = safe_math::add(recipient.lamports(), account.lamports())?;
+    **recipient.try_borrow_mut_lamports()? = lamports;
+    **account.try_borrow_mut_lamports()? = 0;
+    account.data.borrow_mut().fill(0);
+    Ok(())
+}
+
+/// Writes serialized data to an account, growing the account first when the data outgrew
+/// it, as deals and shipments do when parties, keys, or audit entries are added. The
+/// account's lamports must already cover the rent of the larger size: clients top it up
+/// with a transfer ahead of the instruction, so handlers need no payer for it.
+fn write_growing_account_data(account: &AccountInfo, kind: AccountKind, data: &[u8]) -> ProgramResult {
+    let space = migrations::account_space(data.len());
+    if space > account.data_len() {
+        if account.lamports() < Rent::get()?.minimum_balance(space) {
+            return Err(DLUError::GrowthNotFunded.into());
+        }
+        account.realloc(space, false)?;
+    }
+    write_account_data(account, kind, data)
+}
+
+/// Decodes an account of `T`'s kind with `decode`, which gets exactly the payload behind its
+/// length prefix.
+fn load_account_data<T: Discriminated>(
+    account: &AccountInfo,
+    decode: impl FnOnce(&mut &[u8]) -> Result<T, DLUError>,
+) -> Result<T, ProgramError> {
+    Ok(migrations::decode_account(&account.data.borrow(), T::KIND, decode)?)
+}
+
+/// Borsh-serializes a value into an account. Versioned accounts, which deals, shipments
+/// and users are, go through their own `serialize` and `write_account_data` instead.
+fn save_account_data<T: BorshSerialize + Discriminated>(account: &AccountInfo, value: &T) -> ProgramResult {
+    let data = value.try_to_vec().map_err(|_| DLUError::SerializationFailed)?;
+    write_account_data(account, T::KIND, &data)
+}
+
+/// Writes serialized data to an account behind its kind and `u32` length prefix, so loaders
+/// read back exactly these bytes.
+fn write_account_data(account: &AccountInfo, kind: AccountKind, data: &[u8]) -> ProgramResult {
+    write_raw_account_data(account, &migrations::frame(kind, data)?)
+}
+
+/// Writes data to the start of an account's data buffer, failing instead of panicking when
+/// it does not fit. The rest of the buffer is zeroed, so no bytes of older, longer data
+/// linger after it. Profile summaries, whose public layout has no kind or length prefix, are
+/// written through it directly.
+fn write_raw_account_data(account: &AccountInfo, data: &[u8]) -> ProgramResult {
+    let mut account_data = account.data.borrow_mut();
+    if data.len() > account_data.len() {
+        return Err(DLUError::SerializationFailed.into());
+    }
+    let (head, tail) = account_data.split_at_mut(data.len());
+    head.copy_from_slice(data);
+    tail.fill(0);
+    Ok(())
+}
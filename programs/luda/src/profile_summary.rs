@@ -0,0 +1,103 @@
+use solana_program::pubkey::Pubkey;
+use crate::errors::DLUError;
+use crate::user::User;
+
+/// Current version of the summary layout.
+pub const PROFILE_SUMMARY_VERSION: u8 = 1;
+
+/// Size in bytes of a serialized ProfileSummary.
+pub const PROFILE_SUMMARY_SIZE: usize = 80;
+
+/// Small, fixed-size view of a user's reputation for wallets and other light clients.
+///
+/// The account lives at the PDA `["profile", user_pubkey]` and is rewritten whenever the
+/// user's reputation changes. Its layout is public and stable; new fields are only ever
+/// appended into the reserved space and announced by bumping `version`.
+///
+/// | Offset | Size | Field                  | Encoding                                   |
+/// |--------|------|------------------------|--------------------------------------------|
+/// | 0      | 1    | `version`              | u8                                         |
+/// | 1      | 1    | `status`               | u8, `UserStatus` discriminant (New = 0)    |
+/// | 2      | 32   | `user`                 | user pubkey                                |
+/// | 34     | 4    | `total_deals`          | u32 little-endian                          |
+/// | 38     | 4    | `successful_deals`     | u32 little-endian                          |
+/// | 42     | 4    | `total_shipments`      | u32 little-endian                          |
+/// | 46     | 4    | `successful_shipments` | u32 little-endian                          |
+/// | 50     | 2    | `rating`               | u16 little-endian, stars x 100, 0 = none   |
+/// | 52     | 4    | `rating_count`         | u32 little-endian                          |
+/// | 56     | 8    | `last_updated_slot`    | u64 little-endian                          |
+/// | 64     | 16   | reserved               | zeroed                                     |
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfileSummary {
+    pub version: u8,
+    pub status: u8,
+    pub user: Pubkey,
+    pub total_deals: u32,
+    pub successful_deals: u32,
+    pub total_shipments: u32,
+    pub successful_shipments: u32,
+    pub rating: u16,
+    pub rating_count: u32,
+    pub last_updated_slot: u64,
+}
+
+impl ProfileSummary {
+    /// Builds a summary from the current state of a user.
+    pub fn from_user(user: &User, slot: u64) -> Self {
+        ProfileSummary {
+            version: PROFILE_SUMMARY_VERSION,
+            status: user.status as u8,
+            user: user.pubkey,
+            total_deals: user.total_deals,
+            successful_deals: user.successful_deals,
+            total_shipments: user.total_shipments,
+            successful_shipments: user.successful_shipments,
+            rating: user.average_rating(),
+            rating_count: user.rating_count,
+            last_updated_slot: slot,
+        }
+    }
+
+    /// Packs the summary into its fixed on-chain layout.
+    pub fn pack(&self) -> [u8; PROFILE_SUMMARY_SIZE] {
+        let mut data = [0u8; PROFILE_SUMMARY_SIZE];
+        data[0] = self.version;
+        data[1] = self.status;
+        data[2..34].copy_from_slice(self.user.as_ref());
+        data[34..38].copy_from_slice(&self.total_deals.to_le_bytes());
+        data[38..42].copy_from_slice(&self.successful_deals.to_le_bytes());
+        data[42..46].copy_from_slice(&self.total_shipments.to_le_bytes());
+        data[46..50].copy_from_slice(&self.successful_shipments.to_le_bytes());
+        data[50..52].copy_from_slice(&self.rating.to_le_bytes());
+        data[52..56].copy_from_slice(&self.rating_count.to_le_bytes());
+        data[56..64].copy_from_slice(&self.last_updated_slot.to_le_bytes());
+        data
+    }
+
+    /// Unpacks a summary from its fixed on-chain layout.
+    pub fn unpack(data: &[u8]) -> Result<Self, DLUError> {
+        if data.len() < PROFILE_SUMMARY_SIZE {
+            return Err(DLUError::DeserializationFailed);
+        }
+
+        let u32_at = |offset: usize| bytes_at::<4>(data, offset).map(u32::from_le_bytes);
+        Ok(ProfileSummary {
+            version: data[0],
+            status: data[1],
+            user: Pubkey::new_from_array(bytes_at::<32>(data, 2)?),
+            total_deals: u32_at(34)?,
+            successful_deals: u32_at(38)?,
+            total_shipments: u32_at(42)?,
+            successful_shipments: u32_at(46)?,
+            rating: u16::from_le_bytes(bytes_at::<2>(data, 50)?),
+            rating_count: u32_at(52)?,
+            last_updated_slot: u64::from_le_bytes(bytes_at::<8>(data, 56)?),
+        })
+    }
+}
+
+/// Returns the `N` bytes of `data` starting at `offset`.
+fn bytes_at<const N: usize>(data: &[u8], offset: usize) -> Result<[u8; N], DLUError> {
+    let bytes = data.get(offset..offset + N).ok_or(DLUError::DeserializationFailed)?;
+    <[u8; N]>::try_from(bytes).map_err(|_| DLUError::DeserializationFailed)
+}
@@ -0,0 +1,69 @@
+use solana_program::{hash::hashv, pubkey::Pubkey};
+use solana_program::borsh::{BorshSerialize, BorshDeserialize};
+use crate::errors::DLUError;
+
+/// Most promo codes an offer can carry.
+pub const MAX_PROMO_CODES: usize = 8;
+
+/// Returns the hash an offer stores for a promo code. Salting it with the offer keeps
+/// codes from being looked up across offers.
+pub fn promo_code_hash(offer: &Pubkey, code: &str) -> [u8; 32] {
+    hashv(&[offer.as_ref(), code.as_bytes()]).to_bytes()
+}
+
+/// A discount the seller hands out as a code, stored only as its hash.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct PromoCode {
+    pub hash: [u8; 32],
+    pub discount_percent: u8,
+    pub remaining_uses: u32,
+}
+
+/// Promo codes a seller attached to an offer.
+///
+/// A buyer accepting the offer with a code's preimage pays the discounted payment, and the
+/// code loses one use. The discount lowers the offer's payment itself, so an offer relisted
+/// after a discounted acceptance keeps the lower payment until the seller updates it.
+///
+/// Lives at the PDA `["promo", offer_pubkey]`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default)]
+pub struct PromoCodes {
+    pub codes: Vec<PromoCode>,
+}
+
+impl PromoCodes {
+    /// Adds or replaces the code with `hash`. Zero `uses` withdraws it.
+    pub fn set(&mut self, hash: [u8; 32], discount_percent: u8, uses: u32) -> Result<(), DLUError> {
+        self.codes.retain(|code| code.hash != hash);
+        if uses == 0 {
+            return Ok(());
+        }
+        if !(1..=100).contains(&discount_percent) {
+            return Err(DLUError::InvalidDiscount);
+        }
+        if self.codes.len() >= MAX_PROMO_CODES {
+            return Err(DLUError::PromoCodesFull);
+        }
+        self.codes.push(PromoCode { hash, discount_percent, remaining_uses: uses });
+        Ok(())
+    }
+
+    /// Uses up one redemption of `code` on `offer`, returning its discount percentage.
+    pub fn redeem(&mut self, offer: &Pubkey, code: &str) -> Result<u8, DLUError> {
+        let hash = promo_code_hash(offer, code);
+        let promo = self.codes.iter_mut().find(|promo| promo.hash == hash).ok_or(DLUError::InvalidPromoCode)?;
+        promo.remaining_uses = promo.remaining_uses.checked_sub(1).ok_or(DLUError::PromoCodeExhausted)?;
+        Ok(promo.discount_percent)
+    }
+
+    /// Serializes the promo codes into a vector of bytes.
+    pub fn serialize(&self) -> Result<Vec<u8>, DLUError> {
+        self.try_to_vec().map_err(|_| DLUError::SerializationFailed)
+    }
+
+    /// Deserializes promo codes from a slice of bytes.
+    pub fn deserialize(input: &mut &[u8]) -> Result<Self, DLUError> {
+        // Accounts are sized for every code, so the tail may be zero padding.
+        <Self as BorshDeserialize>::deserialize(input).map_err(|_| DLUError::DeserializationFailed)
+    }
+}
@@ -0,0 +1,117 @@
+use solana_program::pubkey::Pubkey;
+use solana_program::borsh::{BorshSerialize, BorshDeserialize};
+use crate::errors::DLUError;
+use crate::request::{Request, RequestStatus};
+
+/// Longest note a seller can attach to a quote, in bytes.
+pub const MAX_QUOTE_NOTE_LEN: usize = 200;
+
+/// Represents the current status of a quote.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, PartialEq, Debug)]
+pub enum QuoteStatus {
+    Open,
+    Selected,
+    Withdrawn,
+}
+
+/// Price a seller asks for a buyer's request, with a note on what they would deliver.
+///
+/// The seller locks their insurance at the quoted price when quoting, so selecting a quote
+/// accepts the request in one step: it is repriced to the quote and accepted by its
+/// seller. Sellers withdraw quotes that were not selected to get their insurance back. A
+/// quote only applies to the listing of the request it was submitted for.
+///
+/// Lives at the PDA `["quote", request_pubkey, seller_pubkey]`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct Quote {
+    pub request: Pubkey,
+    pub seller: Pubkey,
+    pub mint: Pubkey,  // Payment mint of the request, which the insurance is locked in.
+    pub price: u64,
+    pub note: String,
+    pub request_generation: u32,  // Generation of the request when the quote was submitted.
+    pub submitted_at: i64,
+    pub status: QuoteStatus,
+}
+
+impl Quote {
+    /// Records a seller's quote on a listed request.
+    pub fn submit(
+        request_key: Pubkey,
+        request: &Request,
+        seller: Pubkey,
+        price: u64,
+        note: String,
+        now: i64,
+    ) -> Result<Self, DLUError> {
+        if request.status() != RequestStatus::Listed {
+            return Err(DLUError::NotListed);
+        }
+        // Buyers can't bid on their own requests.
+        if seller == request.buyer_pubkey() {
+            return Err(DLUError::NotAuthorized);
+        }
+        if price == 0 {
+            return Err(DLUError::InvalidQuotePrice);
+        }
+        if note.len() > MAX_QUOTE_NOTE_LEN {
+            return Err(DLUError::QuoteNoteTooLong);
+        }
+
+        Ok(Quote {
+            request: request_key,
+            seller,
+            mint: request.payment_mint(),
+            price,
+            note,
+            request_generation: request.generation(),
+            submitted_at: now,
+            status: QuoteStatus::Open,
+        })
+    }
+
+    /// Returns true while the quote holds the seller's insurance.
+    pub fn is_open(&self) -> bool {
+        self.status == QuoteStatus::Open
+    }
+
+    /// Marks the quote as chosen by the buyer of the request.
+    pub fn select(&mut self, request_key: &Pubkey, request: &Request) -> Result<(), DLUError> {
+        if !self.is_open() {
+            return Err(DLUError::QuoteNotOpen);
+        }
+        if self.request != *request_key {
+            return Err(DLUError::KeyMismatch);
+        }
+        if self.request_generation != request.generation() {
+            return Err(DLUError::QuoteStale);
+        }
+
+        self.status = QuoteStatus::Selected;
+        Ok(())
+    }
+
+    /// Withdraws an open quote on behalf of its seller, returning the insurance to release.
+    pub fn withdraw(&mut self, seller: &Pubkey) -> Result<u64, DLUError> {
+        if !self.is_open() {
+            return Err(DLUError::QuoteNotOpen);
+        }
+        if *seller != self.seller {
+            return Err(DLUError::NotAuthorized);
+        }
+
+        self.status = QuoteStatus::Withdrawn;
+        Ok(self.price)
+    }
+
+    /// Serializes the quote into a vector of bytes.
+    pub fn serialize(&self) -> Result<Vec<u8>, DLUError> {
+        self.try_to_vec().map_err(|_| DLUError::SerializationFailed)
+    }
+
+    /// Deserializes a quote from a slice of bytes.
+    pub fn deserialize(input: &mut &[u8]) -> Result<Self, DLUError> {
+        // Accounts are sized for the longest note, so the tail may be zero padding.
+        <Self as BorshDeserialize>::deserialize(input).map_err(|_| DLUError::DeserializationFailed)
+    }
+}
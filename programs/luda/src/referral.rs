@@ -0,0 +1,51 @@
+use solana_program::pubkey::Pubkey;
+use solana_program::borsh::{BorshSerialize, BorshDeserialize};
+use crate::errors::DLUError;
+use crate::safe_math;
+
+/// Protocol fees a referrer earned in one mint from the deals of the users they referred.
+///
+/// Every completion whose payee names a referrer credits the config's referral share of
+/// the collected fee here, while the tokens themselves stay in the treasury of the mint
+/// until the referrer claims them.
+///
+/// Lives at the PDA `["referral", referrer_pubkey, mint]`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct ReferralRewards {
+    pub referrer: Pubkey,
+    pub mint: Pubkey,
+    pub unclaimed: u64,     // Credited and still held by the treasury.
+    pub total_earned: u64,  // Credited over the referrer's lifetime, claimed or not.
+}
+
+impl ReferralRewards {
+    /// Creates an empty rewards account.
+    pub fn new(referrer: Pubkey, mint: Pubkey) -> Self {
+        ReferralRewards { referrer, mint, unclaimed: 0, total_earned: 0 }
+    }
+
+    /// Credits the referrer's share of a fee.
+    pub fn credit(&mut self, amount: u64) -> Result<(), DLUError> {
+        self.unclaimed = safe_math::add(self.unclaimed, amount)?;
+        self.total_earned = safe_math::add(self.total_earned, amount)?;
+        Ok(())
+    }
+
+    /// Takes everything credited since the last claim.
+    pub fn claim(&mut self) -> Result<u64, DLUError> {
+        if self.unclaimed == 0 {
+            return Err(DLUError::NoReferralRewards);
+        }
+        Ok(std::mem::take(&mut self.unclaimed))
+    }
+
+    /// Serializes the rewards into a vector of bytes.
+    pub fn serialize(&self) -> Result<Vec<u8>, DLUError> {
+        self.try_to_vec().map_err(|_| DLUError::SerializationFailed)
+    }
+
+    /// Deserializes rewards from a slice of bytes.
+    pub fn deserialize(input: &mut &[u8]) -> Result<Self, DLUError> {
+        <Self as BorshDeserialize>::deserialize(input).map_err(|_| DLUError::DeserializationFailed)
+    }
+}
@@ -0,0 +1,58 @@
+use solana_program::pubkey::Pubkey;
+use solana_program::borsh::{BorshSerialize, BorshDeserialize};
+use crate::addressing::EntityType;
+use crate::errors::DLUError;
+
+/// Geohash characters naming a region. Four characters make cells of about 39 by 20 km,
+/// roughly a city with its suburbs.
+pub const REGION_GEOHASH_LEN: usize = 4;
+/// Listings a region index keeps before the newest start replacing the oldest.
+pub const MAX_REGION_LISTINGS: usize = 64;
+
+/// A listing announced in a region.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct RegionListing {
+    pub address: Pubkey,
+    pub entity_type: EntityType,
+    pub listed_at: i64,
+}
+
+/// Latest listings whose location lies in one geohash region, so clients can fetch the
+/// listings near them instead of scanning every account of the program.
+///
+/// Offers, requests and shipments are appended when listed at a location with a geohash;
+/// shipments by their pickup point. The index is a ring of the `MAX_REGION_LISTINGS`
+/// most recent entries and is not updated when a listing is accepted or closed, so
+/// clients check each listing's status after fetching it.
+///
+/// Lives at the PDA `["region", geohash_prefix]`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default)]
+pub struct RegionIndex {
+    pub prefix: [u8; REGION_GEOHASH_LEN],
+    pub next: u16,  // Slot the next listing overwrites once the ring is full.
+    pub listings: Vec<RegionListing>,
+}
+
+impl RegionIndex {
+    /// Appends a listing to the region, replacing the oldest one once the index is full.
+    pub fn add(&mut self, prefix: [u8; REGION_GEOHASH_LEN], listing: RegionListing) {
+        self.prefix = prefix;
+        if self.listings.len() < MAX_REGION_LISTINGS {
+            self.listings.push(listing);
+            return;
+        }
+        self.listings[self.next as usize] = listing;
+        self.next = ((self.next as usize + 1) % MAX_REGION_LISTINGS) as u16;
+    }
+
+    /// Serializes the region index into a vector of bytes.
+    pub fn serialize(&self) -> Result<Vec<u8>, DLUError> {
+        self.try_to_vec().map_err(|_| DLUError::SerializationFailed)
+    }
+
+    /// Deserializes a region index from a slice of bytes.
+    pub fn deserialize(input: &mut &[u8]) -> Result<Self, DLUError> {
+        // Accounts are sized for a full ring, so the tail may be zero padding.
+        <Self as BorshDeserialize>::deserialize(input).map_err(|_| DLUError::DeserializationFailed)
+    }
+}
@@ -0,0 +1,468 @@
+use std::slice::Iter;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_program,
+    sysvar::slot_hashes,
+};
+use crate::addressing::{
+    derive_appeal_address, derive_arbiter_address, derive_attestation_address, derive_attestor_registry_address, derive_referral_address, derive_promo_address, derive_watchlist_address, derive_leaderboard_address, derive_listing_tree_authority_address, derive_history_address, derive_key_manager_address, derive_emergency_address, derive_timelock_address, derive_vault_registry_address, derive_carrier_route_address, derive_route_index_address, derive_dao_address, derive_proposal_address, derive_vote_lock_address, derive_vote_record_address, derive_penalty_pool_address, derive_penalty_rewards_address, derive_penalty_vault_address, derive_reward_emission_address, derive_rewards_vault_address, derive_delegation_address, derive_arbiter_registry_address, derive_arbitration_address, derive_bond_address, derive_config_address, derive_index_address, derive_message_log_address,
+    derive_nft_escrow_address, derive_multisig_address, derive_organization_address, derive_profile_summary_address, derive_region_address, derive_stats_address, derive_review_address,
+    derive_stake_address, derive_treasury_address, derive_vault_address, derive_wallet_address,
+};
+use crate::arbiter::DisputeKind;
+use crate::compression::ACCOUNT_COMPRESSION_PROGRAM_ID;
+use crate::dlu_token::DLUToken;
+use crate::errors::DLUError;
+use crate::leaderboard::LeaderboardKind;
+use crate::migrations;
+use crate::region::REGION_GEOHASH_LEN;
+
+/// Consumes an instruction's accounts in the order documented on `DLUInstruction`,
+/// validating each one as it is taken.
+pub struct AccountsResolver<'a, 'b> {
+    program_id: &'a Pubkey,
+    accounts: Iter<'a, AccountInfo<'b>>,
+}
+
+impl<'a, 'b> AccountsResolver<'a, 'b> {
+    /// Creates a resolver positioned at the first account.
+    pub fn new(program_id: &'a Pubkey, accounts: &'a [AccountInfo<'b>]) -> Self {
+        AccountsResolver {
+            program_id,
+            accounts: accounts.iter(),
+        }
+    }
+
+    /// Returns the id of the program the accounts are resolved for.
+    pub fn program_id(&self) -> &'a Pubkey {
+        self.program_id
+    }
+
+    /// Returns whether any accounts are left, for instructions with optional trailing accounts.
+    pub fn has_next(&self) -> bool {
+        self.accounts.len() > 0
+    }
+
+    /// Returns the key of the next account without taking it.
+    pub fn peek_key(&self) -> Option<Pubkey> {
+        self.accounts.as_slice().first().map(|account| *account.key)
+    }
+
+    /// Takes the next account without further checks.
+    pub fn next(&mut self) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        next_account_info(&mut self.accounts)
+    }
+
+    /// Takes the next account, which must have signed the transaction.
+    pub fn next_signer(&mut self) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let account = self.next()?;
+        if !account.is_signer {
+            return Err(DLUError::NotAuthorized.into());
+        }
+        Ok(account)
+    }
+
+    /// Takes the next account, which must be the given key and have signed the transaction.
+    pub fn next_signer_for(&mut self, key: &Pubkey) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let account = self.next_signer()?;
+        if account.key != key {
+            return Err(DLUError::KeyMismatch.into());
+        }
+        Ok(account)
+    }
+
+    /// Takes the next account, which must be the given key.
+    pub fn next_with_key(&mut self, key: &Pubkey) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let account = self.next()?;
+        if account.key != key {
+            return Err(DLUError::KeyMismatch.into());
+        }
+        Ok(account)
+    }
+
+    /// Takes the next account, which must be owned by this program.
+    pub fn next_program_account(&mut self) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let account = self.next()?;
+        if account.owner != self.program_id {
+            return Err(DLUError::InvalidAccountOwner.into());
+        }
+        Ok(account)
+    }
+
+    /// Takes the next account for a user, deal or shipment about to be created: owned by this
+    /// program and never written, as clients allocate it with the system program.
+    pub fn next_fresh_account(&mut self) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let account = self.next_program_account()?;
+        if !migrations::is_unwritten(&account.data.borrow()) {
+            return Err(DLUError::AccountInUse.into());
+        }
+        Ok(account)
+    }
+
+    /// Takes the next account, which must be owned by SPL Token or Token-2022.
+    pub fn next_token_account(&mut self) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let account = self.next()?;
+        if *account.owner != spl_token::id() && *account.owner != spl_token_2022::id() {
+            return Err(DLUError::InvalidAccountOwner.into());
+        }
+        Ok(account)
+    }
+
+    /// Takes the Config account.
+    pub fn next_config(&mut self) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let (config_key, _) = derive_config_address(self.program_id);
+        self.next_program_pda(&config_key)
+    }
+
+    /// Takes the Stats account.
+    pub fn next_stats(&mut self) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let (stats_key, _) = derive_stats_address(self.program_id);
+        self.next_program_pda(&stats_key)
+    }
+
+    /// Takes the ProfileSummary account of the given user, which `CreateUser` creates.
+    pub fn next_profile_summary(&mut self, user: &Pubkey) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let (summary_key, _) = derive_profile_summary_address(self.program_id, user);
+        self.next_creatable_pda(&summary_key)
+    }
+
+    /// Takes the IndexAccount of the given user, which `CreateUser` creates.
+    pub fn next_index(&mut self, user: &Pubkey) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let (index_key, _) = derive_index_address(self.program_id, user);
+        self.next_creatable_pda(&index_key)
+    }
+
+    /// Takes the FailBond account of the given entity, which failing the entity creates.
+    pub fn next_bond(&mut self, entity: &Pubkey) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let (bond_key, _) = derive_bond_address(self.program_id, entity);
+        self.next_creatable_pda(&bond_key)
+    }
+
+    /// Takes the CarrierStake account of the given carrier.
+    pub fn next_stake(&mut self, carrier: &Pubkey) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let (stake_key, _) = derive_stake_address(self.program_id, carrier);
+        self.next_program_pda(&stake_key)
+    }
+
+    /// Takes the Review account the given reviewer left for a deal.
+    pub fn next_review(&mut self, deal_id: u64, reviewer: &Pubkey) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let (review_key, _) = derive_review_address(self.program_id, deal_id, reviewer);
+        self.next_creatable_pda(&review_key)
+    }
+
+    /// Takes the RegionIndex account of the given geohash region.
+    pub fn next_region(&mut self, prefix: &[u8; REGION_GEOHASH_LEN]) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let (region_key, _) = derive_region_address(self.program_id, prefix);
+        self.next_creatable_pda(&region_key)
+    }
+
+    /// Takes the MessageLog account of the given offer, request or shipment.
+    pub fn next_message_log(&mut self, entity: &Pubkey) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let (log_key, _) = derive_message_log_address(self.program_id, entity);
+        self.next_creatable_pda(&log_key)
+    }
+
+    /// Takes the Arbiter account of the given user.
+    pub fn next_arbiter(&mut self, owner: &Pubkey) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let (arbiter_key, _) = derive_arbiter_address(self.program_id, owner);
+        self.next_program_pda(&arbiter_key)
+    }
+
+    /// Takes the ArbiterRegistry account.
+    pub fn next_arbiter_registry(&mut self) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let (registry_key, _) = derive_arbiter_registry_address(self.program_id);
+        self.next_program_pda(&registry_key)
+    }
+
+    /// Takes the Arbitration account of a dispute of the given kind over an entity, which
+    /// drawing its first arbiter creates.
+    pub fn next_arbitration(&mut self, entity: &Pubkey, kind: DisputeKind) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let (arbitration_key, _) = derive_arbitration_address(self.program_id, entity, kind);
+        self.next_creatable_pda(&arbitration_key)
+    }
+
+    /// Takes the Appeal account against the ruling of an Arbitration.
+    pub fn next_appeal(&mut self, arbitration: &Pubkey) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let (appeal_key, _) = derive_appeal_address(self.program_id, arbitration);
+        self.next_creatable_pda(&appeal_key)
+    }
+
+    /// Takes the Delegation account an owner granted to a session key.
+    pub fn next_delegation(&mut self, owner: &Pubkey, session: &Pubkey) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let (delegation_key, _) = derive_delegation_address(self.program_id, owner, session);
+        self.next_creatable_pda(&delegation_key)
+    }
+
+    /// Takes the Organization account of a user.
+    pub fn next_organization(&mut self, owner: &Pubkey) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let (organization_key, _) = derive_organization_address(self.program_id, owner);
+        self.next_creatable_pda(&organization_key)
+    }
+
+    /// Takes the Multisig account governing a user.
+    pub fn next_multisig(&mut self, owner: &Pubkey) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let (multisig_key, _) = derive_multisig_address(self.program_id, owner);
+        self.next_creatable_pda(&multisig_key)
+    }
+
+    /// Takes the AttestorRegistry account.
+    pub fn next_attestor_registry(&mut self) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let (registry_key, _) = derive_attestor_registry_address(self.program_id);
+        self.next_creatable_pda(&registry_key)
+    }
+
+    /// Takes the Attestation account of a user.
+    pub fn next_attestation(&mut self, owner: &Pubkey) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let (attestation_key, _) = derive_attestation_address(self.program_id, owner);
+        self.next_creatable_pda(&attestation_key)
+    }
+
+    /// Takes the ReferralRewards account of a referrer in a mint, which the first credit creates.
+    pub fn next_referral_rewards(&mut self, referrer: &Pubkey, mint: &Pubkey) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let (referral_key, _) = derive_referral_address(self.program_id, referrer, mint);
+        self.next_creatable_pda(&referral_key)
+    }
+
+    /// Takes the PromoCodes account of an offer.
+    pub fn next_promo_codes(&mut self, offer: &Pubkey) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let (promo_key, _) = derive_promo_address(self.program_id, offer);
+        self.next_creatable_pda(&promo_key)
+    }
+
+    /// Takes the Watchlist account of a wallet.
+    pub fn next_watchlist(&mut self, owner: &Pubkey) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let (watchlist_key, _) = derive_watchlist_address(self.program_id, owner);
+        self.next_creatable_pda(&watchlist_key)
+    }
+
+    /// Takes the Leaderboard account of the given kind.
+    pub fn next_leaderboard(&mut self, kind: LeaderboardKind) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let (leaderboard_key, _) = derive_leaderboard_address(self.program_id, kind);
+        self.next_creatable_pda(&leaderboard_key)
+    }
+
+    /// Takes a compressed listing tree, which must be owned by the account compression program.
+    pub fn next_listing_tree(&mut self) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let account = self.next()?;
+        if *account.owner != ACCOUNT_COMPRESSION_PROGRAM_ID {
+            return Err(DLUError::InvalidAccountOwner.into());
+        }
+        Ok(account)
+    }
+
+    /// Takes the authority PDA of a compressed listing tree.
+    pub fn next_listing_tree_authority(&mut self, tree: &Pubkey) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let (authority_key, _) = derive_listing_tree_authority_address(self.program_id, tree);
+        self.next_with_key(&authority_key)
+    }
+
+    /// Takes the History account of a lister.
+    pub fn next_history(&mut self, lister: &Pubkey) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let (history_key, _) = derive_history_address(self.program_id, lister);
+        self.next_creatable_pda(&history_key)
+    }
+
+    /// Takes the KeyManager account of a deal or shipment.
+    pub fn next_key_manager(&mut self, entity: &Pubkey) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let (key_manager_key, _) = derive_key_manager_address(self.program_id, entity);
+        self.next_creatable_pda(&key_manager_key)
+    }
+
+    /// Takes the EmergencyResolution account of a deal or shipment.
+    pub fn next_emergency_resolution(&mut self, entity: &Pubkey) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let (emergency_key, _) = derive_emergency_address(self.program_id, entity);
+        self.next_creatable_pda(&emergency_key)
+    }
+
+    /// Takes the TimelockQueue account.
+    pub fn next_timelock_queue(&mut self) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let (timelock_key, _) = derive_timelock_address(self.program_id);
+        self.next_program_pda(&timelock_key)
+    }
+
+    /// Takes the VaultRegistry account, which the first `RegisterVault` creates.
+    pub fn next_vault_registry(&mut self) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let (registry_key, _) = derive_vault_registry_address(self.program_id);
+        self.next_creatable_pda(&registry_key)
+    }
+
+    /// Takes one of a carrier's CarrierRoute accounts, which publishing the route creates.
+    pub fn next_carrier_route(&mut self, carrier: &Pubkey, route_id: u32) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let (route_key, _) = derive_carrier_route_address(self.program_id, carrier, route_id);
+        self.next_creatable_pda(&route_key)
+    }
+
+    /// Takes the RouteIndex account of the routes leaving the given geohash region, which the
+    /// first route published from there creates.
+    pub fn next_route_index(&mut self, origin: &[u8; REGION_GEOHASH_LEN]) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let (index_key, _) = derive_route_index_address(self.program_id, origin);
+        self.next_creatable_pda(&index_key)
+    }
+
+    /// Takes the Dao account.
+    pub fn next_dao(&mut self) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let (dao_key, _) = derive_dao_address(self.program_id);
+        self.next_program_pda(&dao_key)
+    }
+
+    /// Takes the Proposal account with the given ID.
+    pub fn next_proposal(&mut self, id: u64) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let (proposal_key, _) = derive_proposal_address(self.program_id, id);
+        self.next_creatable_pda(&proposal_key)
+    }
+
+    /// Takes the VoteLock account of a voter.
+    pub fn next_vote_lock(&mut self, owner: &Pubkey) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let (vote_lock_key, _) = derive_vote_lock_address(self.program_id, owner);
+        self.next_program_pda(&vote_lock_key)
+    }
+
+    /// Takes the VoteRecord account of a voter on a proposal.
+    pub fn next_vote_record(&mut self, proposal: &Pubkey, voter: &Pubkey) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let (vote_record_key, _) = derive_vote_record_address(self.program_id, proposal, voter);
+        self.next_creatable_pda(&vote_record_key)
+    }
+
+    /// Takes the PenaltyPool account of a mint.
+    pub fn next_penalty_pool(&mut self, mint: &Pubkey) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let (pool_key, _) = derive_penalty_pool_address(self.program_id, mint);
+        self.next_program_pda(&pool_key)
+    }
+
+    /// Takes the PenaltyRewards account of a carrier in the pool of a mint.
+    pub fn next_penalty_rewards(&mut self, mint: &Pubkey, carrier: &Pubkey) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let (rewards_key, _) = derive_penalty_rewards_address(self.program_id, mint, carrier);
+        self.next_program_pda(&rewards_key)
+    }
+
+    /// Takes the RewardEmission account of a mint, which the first reward creates.
+    pub fn next_reward_emission(&mut self, mint: &Pubkey) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let (emission_key, _) = derive_reward_emission_address(self.program_id, mint);
+        self.next_creatable_pda(&emission_key)
+    }
+
+    /// Takes every account left, such as the proof nodes of a Merkle tree leaf.
+    pub fn next_remaining(&mut self) -> &'a [AccountInfo<'b>] {
+        let remaining = self.accounts.as_slice();
+        self.accounts = [].iter();
+        remaining
+    }
+
+    /// Takes the signers that follow, stopping at the first account that does not sign.
+    pub fn next_cosigners(&mut self) -> Vec<Pubkey> {
+        let mut cosigners = Vec::new();
+        while let Some(account) = self.accounts.as_slice().first().filter(|account| account.is_signer) {
+            cosigners.push(*account.key);
+            self.accounts.next();
+        }
+        cosigners
+    }
+
+    /// Takes the rent payer that follows when it signs and is writable, such as a relayer
+    /// sponsoring a user who holds no SOL, and falls back to `default` otherwise.
+    pub fn next_payer<'c>(&mut self, default: &'c AccountInfo<'b>) -> &'c AccountInfo<'b>
+    where
+        'a: 'c,
+    {
+        match self.accounts.as_slice().first() {
+            Some(account) if account.is_signer && account.is_writable => {
+                self.accounts.next();
+                account
+            }
+            _ => default,
+        }
+    }
+
+    /// Takes the SlotHashes sysvar.
+    pub fn next_slot_hashes(&mut self) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        self.next_with_key(&slot_hashes::id())
+    }
+
+    /// Takes the escrow vault token account of a mint.
+    pub fn next_vault(&mut self, mint: &Pubkey) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let account = self.next_token_account()?;
+        let (vault_key, _) = derive_vault_address(self.program_id, mint);
+        if *account.key != vault_key {
+            return Err(DLUError::KeyMismatch.into());
+        }
+        if DLUToken::get_mint(account)? != *mint {
+            return Err(DLUError::MintMismatch.into());
+        }
+        Ok(account)
+    }
+
+    /// Takes the wallet token account of the given user.
+    pub fn next_wallet(&mut self, user: &Pubkey) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let (wallet_key, _) = derive_wallet_address(self.program_id, user);
+        let account = self.next_token_account()?;
+        if *account.key != wallet_key {
+            return Err(DLUError::KeyMismatch.into());
+        }
+        Ok(account)
+    }
+
+    /// Takes the NFT escrow token account of an offer, which does not exist yet when the
+    /// offer is first listed.
+    pub fn next_nft_escrow(&mut self, offer: &Pubkey) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let (escrow_key, _) = derive_nft_escrow_address(self.program_id, offer);
+        let account = self.next()?;
+        if *account.key != escrow_key {
+            return Err(DLUError::KeyMismatch.into());
+        }
+        Ok(account)
+    }
+
+    /// Takes the treasury token account of the mint it holds.
+    pub fn next_treasury(&mut self) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let account = self.next_token_account()?;
+        let (treasury_key, _) = derive_treasury_address(self.program_id, &DLUToken::get_mint(account)?);
+        if *account.key != treasury_key {
+            return Err(DLUError::KeyMismatch.into());
+        }
+        Ok(account)
+    }
+
+    /// Takes the penalty vault token account of the mint it holds.
+    pub fn next_penalty_vault(&mut self) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let account = self.next_token_account()?;
+        let (penalty_vault_key, _) = derive_penalty_vault_address(self.program_id, &DLUToken::get_mint(account)?);
+        if *account.key != penalty_vault_key {
+            return Err(DLUError::KeyMismatch.into());
+        }
+        Ok(account)
+    }
+
+    /// Takes the rewards vault token account of a mint.
+    pub fn next_rewards_vault(&mut self, mint: &Pubkey) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let account = self.next_token_account()?;
+        let (rewards_vault_key, _) = derive_rewards_vault_address(self.program_id, mint);
+        if *account.key != rewards_vault_key {
+            return Err(DLUError::KeyMismatch.into());
+        }
+        if DLUToken::get_mint(account)? != *mint {
+            return Err(DLUError::MintMismatch.into());
+        }
+        Ok(account)
+    }
+
+    fn next_program_pda(&mut self, key: &Pubkey) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let account = self.next_program_account()?;
+        if account.key != key {
+            return Err(DLUError::KeyMismatch.into());
+        }
+        Ok(account)
+    }
+
+    // Takes the account at a program-derived address the program creates on first use: it
+    // is owned by this program already, or an empty system account yet to be created.
+    fn next_creatable_pda(&mut self, key: &Pubkey) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        let account = self.next_with_key(key)?;
+        let uncreated = *account.owner == system_program::id() && account.data_is_empty();
+        if account.owner != self.program_id && !uncreated {
+            return Err(DLUError::InvalidAccountOwner.into());
+        }
+        Ok(account)
+    }
+}
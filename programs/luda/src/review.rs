@@ -0,0 +1,61 @@
+use solana_program::pubkey::Pubkey;
+use solana_program::borsh::{BorshSerialize, BorshDeserialize};
+use crate::addressing::EntityType;
+use crate::errors::DLUError;
+
+/// Lowest and highest rating a review can give, in stars.
+pub const MIN_RATING: u8 = 1;
+pub const MAX_RATING: u8 = 5;
+
+/// Rating and review one party of a completed deal or shipment left for the other.
+///
+/// The review text lives off-chain; only its hash is stored. Lives at the PDA
+/// `["review", deal_id (u64 little-endian), reviewer_pubkey]`, so each party can review a
+/// deal once.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct Review {
+    pub entity_type: EntityType,
+    pub deal_id: u64,
+    pub reviewer: Pubkey,
+    pub reviewee: Pubkey,
+    pub rating: u8,
+    pub review_hash: [u8; 32],
+    pub submitted_at: i64,
+}
+
+impl Review {
+    /// Records a review, rejecting ratings outside of `MIN_RATING..=MAX_RATING`.
+    pub fn submit(
+        entity_type: EntityType,
+        deal_id: u64,
+        reviewer: Pubkey,
+        reviewee: Pubkey,
+        rating: u8,
+        review_hash: [u8; 32],
+        now: i64,
+    ) -> Result<Self, DLUError> {
+        if !(MIN_RATING..=MAX_RATING).contains(&rating) {
+            return Err(DLUError::InvalidRating);
+        }
+
+        Ok(Review {
+            entity_type,
+            deal_id,
+            reviewer,
+            reviewee,
+            rating,
+            review_hash,
+            submitted_at: now,
+        })
+    }
+
+    /// Serializes the review into a vector of bytes.
+    pub fn serialize(&self) -> Result<Vec<u8>, DLUError> {
+        self.try_to_vec().map_err(|_| DLUError::SerializationFailed)
+    }
+
+    /// Deserializes a review from a slice of bytes.
+    pub fn deserialize(input: &mut &[u8]) -> Result<Self, DLUError> {
+        <Self as BorshDeserialize>::deserialize(input).map_err(|_| DLUError::DeserializationFailed)
+    }
+}
@@ -0,0 +1,151 @@
+use solana_program::pubkey::Pubkey;
+use solana_program::borsh::{BorshSerialize, BorshDeserialize};
+use crate::errors::DLUError;
+use crate::location;
+use crate::region::REGION_GEOHASH_LEN;
+
+/// Bits of `CarrierRoute::days`, one per weekday from Monday at bit 0 to Sunday at bit 6.
+pub const ROUTE_DAYS_MASK: u8 = 0b111_1111;
+
+/// Routes a route index lists at once. Carriers close routes they no longer travel to make
+/// room for others.
+pub const MAX_REGION_ROUTES: usize = 32;
+
+/// Account size of a CarrierRoute.
+pub const CARRIER_ROUTE_SIZE: usize = 32 + 4 + 2 * REGION_GEOHASH_LEN + 1 + 8 + 8;
+
+/// Account size of a RouteIndex.
+pub const ROUTE_INDEX_SIZE: usize = REGION_GEOHASH_LEN + 4 + MAX_REGION_ROUTES * (32 + 32 + REGION_GEOHASH_LEN + 1 + 8);
+
+/// A trip a carrier makes every week between two geohash regions, kept at the PDA
+/// `["carrier_route", carrier_pubkey, route_id]`. Senders reference a route from the
+/// shipments they list along it; a fresh account is all zeros, which reads as no route.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct CarrierRoute {
+    pub carrier: Pubkey,
+    pub route_id: u32,
+    pub origin: [u8; REGION_GEOHASH_LEN],
+    pub destination: [u8; REGION_GEOHASH_LEN],
+    pub days: u8,             // Weekdays the carrier travels, as `ROUTE_DAYS_MASK` bits.
+    pub capacity_grams: u64,  // Most chargeable weight the carrier takes per trip.
+    pub published_at: i64,
+}
+
+impl CarrierRoute {
+    /// Publishes the route, or updates the days and capacity of a route the carrier
+    /// published before, which keeps its regions.
+    pub fn publish(
+        &mut self,
+        carrier: Pubkey,
+        route_id: u32,
+        origin: [u8; REGION_GEOHASH_LEN],
+        destination: [u8; REGION_GEOHASH_LEN],
+        days: u8,
+        capacity_grams: u64,
+        now: i64,
+    ) -> Result<(), DLUError> {
+        if !location::is_region_prefix(&origin) || !location::is_region_prefix(&destination) {
+            return Err(DLUError::InvalidRoute);
+        }
+        if days == 0 || days & !ROUTE_DAYS_MASK != 0 || capacity_grams == 0 {
+            return Err(DLUError::InvalidRoute);
+        }
+        if self.is_published() && (self.origin, self.destination) != (origin, destination) {
+            return Err(DLUError::RouteMismatch);
+        }
+        *self = CarrierRoute { carrier, route_id, origin, destination, days, capacity_grams, published_at: now };
+        Ok(())
+    }
+
+    /// Returns true once the carrier published the route.
+    pub fn is_published(&self) -> bool {
+        self.carrier != Pubkey::default()
+    }
+
+    /// Checks that a shipment from the `pickup` region to the `drop_off` region, charged on
+    /// `chargeable_grams`, travels along the route.
+    pub fn check_carries(
+        &self,
+        pickup: Option<[u8; REGION_GEOHASH_LEN]>,
+        drop_off: Option<[u8; REGION_GEOHASH_LEN]>,
+        chargeable_grams: u64,
+    ) -> Result<(), DLUError> {
+        if !self.is_published() {
+            return Err(DLUError::InvalidRoute);
+        }
+        if pickup != Some(self.origin) || drop_off != Some(self.destination) {
+            return Err(DLUError::RouteMismatch);
+        }
+        if chargeable_grams > self.capacity_grams {
+            return Err(DLUError::RouteCapacityExceeded);
+        }
+        Ok(())
+    }
+
+    /// Serializes the route into a vector of bytes.
+    pub fn serialize(&self) -> Result<Vec<u8>, DLUError> {
+        self.try_to_vec().map_err(|_| DLUError::SerializationFailed)
+    }
+
+    /// Deserializes a route from a slice of bytes.
+    pub fn deserialize(input: &mut &[u8]) -> Result<Self, DLUError> {
+        <Self as BorshDeserialize>::deserialize(input).map_err(|_| DLUError::DeserializationFailed)
+    }
+}
+
+/// A route listed in the index of its origin region.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct RouteEntry {
+    pub route: Pubkey,
+    pub carrier: Pubkey,
+    pub destination: [u8; REGION_GEOHASH_LEN],
+    pub days: u8,
+    pub capacity_grams: u64,
+}
+
+/// The carrier routes leaving one geohash region, so senders can find carriers already
+/// traveling their way before they list a shipment. Unlike the region listings, routes
+/// stay until their carrier closes them.
+///
+/// Lives at the PDA `["route_index", origin_prefix]`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default)]
+pub struct RouteIndex {
+    pub origin: [u8; REGION_GEOHASH_LEN],
+    pub routes: Vec<RouteEntry>,
+}
+
+impl RouteIndex {
+    /// Lists the route at `address`, or refreshes its entry when it is listed already.
+    pub fn upsert(&mut self, address: Pubkey, route: &CarrierRoute) -> Result<(), DLUError> {
+        let entry = RouteEntry {
+            route: address,
+            carrier: route.carrier,
+            destination: route.destination,
+            days: route.days,
+            capacity_grams: route.capacity_grams,
+        };
+        self.origin = route.origin;
+        match self.routes.iter_mut().find(|listed| listed.route == address) {
+            Some(listed) => *listed = entry,
+            None if self.routes.len() >= MAX_REGION_ROUTES => return Err(DLUError::RouteIndexFull),
+            None => self.routes.push(entry),
+        }
+        Ok(())
+    }
+
+    /// Removes the route at `address` from the index.
+    pub fn remove(&mut self, address: &Pubkey) {
+        self.routes.retain(|listed| listed.route != *address);
+    }
+
+    /// Serializes the route index into a vector of bytes.
+    pub fn serialize(&self) -> Result<Vec<u8>, DLUError> {
+        self.try_to_vec().map_err(|_| DLUError::SerializationFailed)
+    }
+
+    /// Deserializes a route index from a slice of bytes.
+    pub fn deserialize(input: &mut &[u8]) -> Result<Self, DLUError> {
+        // Accounts are sized for a full index, so the tail may be zero padding.
+        <Self as BorshDeserialize>::deserialize(input).map_err(|_| DLUError::DeserializationFailed)
+    }
+}
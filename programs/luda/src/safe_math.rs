@@ -0,0 +1,48 @@
+use crate::errors::DLUError;
+
+/// Basis points in a whole amount.
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// Adds two amounts, failing on overflow.
+pub fn add(a: u64, b: u64) -> Result<u64, DLUError> {
+    a.checked_add(b).ok_or(DLUError::ArithmeticOverflow)
+}
+
+/// Subtracts `b` from `a`, failing on underflow.
+pub fn sub(a: u64, b: u64) -> Result<u64, DLUError> {
+    a.checked_sub(b).ok_or(DLUError::ArithmeticOverflow)
+}
+
+/// Multiplies two amounts, failing on overflow.
+pub fn mul(a: u64, b: u64) -> Result<u64, DLUError> {
+    a.checked_mul(b).ok_or(DLUError::ArithmeticOverflow)
+}
+
+/// Returns `bps` basis points of `amount`, rounded down.
+pub fn bps(amount: u64, bps: u16) -> Result<u64, DLUError> {
+    let share = amount as u128 * bps as u128 / BPS_DENOMINATOR as u128;
+    u64::try_from(share).map_err(|_| DLUError::ArithmeticOverflow)
+}
+
+/// Decays `value` by `elapsed` over an exponential half-life, rounded down.
+///
+/// Whole half-lives halve the value exactly; the remainder is interpolated linearly
+/// (`2^-f ≈ 1 - f/2`), which is exact at both ends and within 6% in between.
+pub fn decay(value: u64, elapsed: u64, half_life: u64) -> u64 {
+    if half_life == 0 {
+        return value;
+    }
+    let halvings = elapsed / half_life;
+    if halvings >= u64::BITS as u64 {
+        return 0;
+    }
+    let halved = value >> halvings;
+    let remainder = (elapsed % half_life) as u128;
+    let reduction = halved as u128 * remainder / (2 * half_life as u128);
+    halved - reduction as u64
+}
+
+/// Returns the total escrow for a payment plus the given number of insurance deposits.
+pub fn escrow_total(payment: u64, insurance: u64, insurance_count: u64) -> Result<u64, DLUError> {
+    add(payment, mul(insurance, insurance_count)?)
+}
@@ -1,17 +1,19 @@
-use crate::user::User;
-use crate::onetimekeys::Onetimekeys;
+use solana_program::{account_info::AccountInfo, pubkey::Pubkey};
+use crate::user::{Role, User, UserStatus};
+use crate::onetimekeys::{KeyHash, OneTimeKey};
 use crate::dlu_wallet::Wallet;
+use crate::dlu_token::DLUToken;
 use crate::escrow::Escrow;
-use chrono::{DateTime, Utc};
+use crate::archive::ARCHIVE_DELAY;
+use crate::config::{Config, CAP_BURN_PENALTIES};
+use crate::errors::DLUError;
+use crate::migrations;
+use crate::pricing::{self, ShippingRates};
+use crate::route::CarrierRoute;
+use crate::safe_math;
+use crate::time;
 use solana_program::borsh::{BorshSerialize, BorshDeserialize};
-
-
-/// Represents an in-game location for shipment drop-offs and pickups.
-pub struct Location {
-    country: String,
-    town: String,
-    address: String,
-}
+pub use crate::location::Location;
 
 /// Represents the current status of a shipment.
 pub enum ShipmentStatus {
@@ -21,6 +23,7 @@ pub enum ShipmentStatus {
     Failed,
     Expired,
     Canceled,
+    InTransit,  // Picked up by the carrier, who now has custody of the items.
 }
 
 /// Represents a single shipment request posted by a sender.
@@ -31,45 +34,110 @@ pub struct Shipment {
     carrier: Option<User>,
     recipient: User,
 	pickup_point: Location,
-    pickup_datetime: DateTime<Utc>,
+    pickup_datetime: i64,
     drop_off_point: Location,
-    drop_off_datetime: DateTime<Utc>,
+    drop_off_datetime: i64,
+    picked_up_at: Option<i64>,  // Set when the carrier confirmed the pickup with the sender's key.
+    delivery_proof: Option<[u8; 32]>,  // Digest of the carrier's delivery photo or signature.
+    return_of: Option<u64>,  // Id of the shipment this one sends back, for return shipments.
+    returned: bool,  // Set once a return shipment was created for this one.
     payment: u64,
     insurance: u64,
+    payment_mint: Pubkey,  // Mint of every token account the shipment's funds move through.
     items_name: String,
     quantity: u32,
-    sender_key: String,
-    carrier_key: String,
-    recipient_key: String,
+    sender_key: OneTimeKey,
+    carrier_key: OneTimeKey,
+    recipient_key: OneTimeKey,
     escrow_id: u64,
+    escrow_vault: Option<Pubkey>,  // The vault holding the escrow; unset until a shipment listed before vaults migrates.
+    escrow_flagged: bool,  // Set once `verify_escrow` found its vault short, blocking payouts.
+    nonce: u64,  // Advanced by every status transition, so terminal instructions cannot be replayed.
+    weight_grams: Option<u32>,
+    volume_cm3: Option<u32>,
+    distance_km: Option<u32>,
+    quoted_rates: Option<ShippingRates>,  // Config rates when the sender measured the shipment.
+    route: Option<Pubkey>,  // CarrierRoute the sender listed the shipment along.
+    generation: u32,  // Incremented every time the shipment is relisted.
+    stake_slashed: bool,  // Set once the carrier's stake was slashed for this fail.
 }
 
 impl Shipment {
-	/// List a new shipment request.
+	/// Checks that the token accounts hold the mint the shipment is paid in.
+	fn check_payment_accounts(&self, token_accounts: &[&AccountInfo]) -> Result<(), DLUError> {
+		DLUToken::check_payment_mint(&self.payment_mint, token_accounts)
+	}
+
+	/// Checks that the shipment's escrow sits in the vault passed in. Shipments listed
+	/// before the vaults existed have to migrate their escrow there first.
+	fn check_vault(&self, vault_account: &AccountInfo) -> Result<(), DLUError> {
+		if self.escrow_vault != Some(*vault_account.key) {
+			return Err(DLUError::EscrowNotInVault);
+		}
+		Ok(())
+	}
+
+	/// Checks the vault like `check_vault` before funds leave it, refusing once a
+	/// reconciliation flagged the shipment. Only governance's emergency unwind releases those.
+	fn check_payout_vault(&self, vault_account: &AccountInfo) -> Result<(), DLUError> {
+		self.check_vault(vault_account)?;
+		if self.escrow_flagged {
+			return Err(DLUError::EscrowFlagged);
+		}
+		Ok(())
+	}
+
+	/// Checks that the sender and carrier are the shipment's parties and that the token
+	/// accounts paid out to are their own.
+	fn check_parties(
+		&self,
+		sender: &Pubkey,
+		carrier: &Pubkey,
+		sender_account: &AccountInfo,
+		carrier_account: &AccountInfo,
+	) -> Result<(), DLUError> {
+		if *sender != self.sender.pubkey || self.carrier_pubkey() != Some(*carrier) {
+			return Err(DLUError::NotDealParty);
+		}
+		let owned_by = |account: &AccountInfo, key: &Pubkey| DLUToken::check_authority(account, key).map_err(|_| DLUError::KeyMismatch);
+		if !owned_by(sender_account, sender)? || !owned_by(carrier_account, carrier)? {
+			return Err(DLUError::KeyMismatch);
+		}
+		Ok(())
+	}
+
+	/// List a new shipment request, locking the payment from the sender's token account into
+	/// the vault of its mint. The shipment is paid in the mint of that account, whether DLU,
+	/// USDC or wrapped SOL.
 	pub fn list_shipment(
 		id: u64,
-		sender: &mut User,  // Mutable reference to sender for updating the wallet balance.
+		sender: &User,
 		recipient: User,    // Add recipient as an argument.
 		items_name: String,
 		quantity: u32,
 		payment: u64,
 		insurance: u64,     // Insurance set explicitly by sender.
 		pickup_point: Location,           // New pickup location argument
-		pickup_datetime: DateTime<Utc>,   // New pickup datetime argument
+		pickup_datetime: i64,   // New pickup datetime argument
 		drop_off_point: Location,
-		drop_off_datetime: DateTime<Utc>,
-	) -> Result<Self, &'static str> {
+		drop_off_datetime: i64,
+		sender_account: &AccountInfo,
+		sender_authority_info: &AccountInfo,
+		vault_account: &AccountInfo,
+	) -> Result<Self, DLUError> {
+		pickup_point.validate()?;
+		drop_off_point.validate()?;
+		let payment_mint = DLUToken::get_mint(sender_account).map_err(|_| DLUError::DeserializationFailed)?;
+		DLUToken::check_payment_mint(&payment_mint, &[vault_account])?;
 
 		// Check sender's balance for sufficient funds for payment.
-		if sender.wallet.balance < payment {
-			return Err("Insufficient funds for payment.");
+		let sender_balance = DLUToken::get_balance(sender_account).map_err(|_| DLUError::BalanceUnavailable)?;
+		if sender_balance < payment {
+			return Err(DLUError::InsufficientFundsForPayment);
 		}
 
-		// Deduct payment amount from sender's wallet.
-		sender.wallet.balance -= payment;  // Assuming balance is mutable.
-
 		// Lock payment amount in escrow.
-		let escrow_id = Escrow::lock_funds(&sender.wallet, payment)?;
+		Escrow::lock_funds(sender_account, vault_account, sender_authority_info, payment)?;
 
 		Ok(Shipment {
 			id,
@@ -81,48 +149,121 @@ impl Shipment {
 			pickup_datetime,        // Initialize pickup datetime
 			drop_off_point,
 			drop_off_datetime,
+			picked_up_at: None,
+			delivery_proof: None,
+			return_of: None,
+			returned: false,
 			payment,
 			insurance,
+			payment_mint,
 			items_name,
 			quantity,
-			sender_key: String::new(),
-			carrier_key: String::new(),
-			recipient_key: String::new(),  // Initialize recipient's one-time key.
-			escrow_id,
+			sender_key: OneTimeKey::NONE,
+			carrier_key: OneTimeKey::NONE,
+			recipient_key: OneTimeKey::NONE,
+			escrow_id: id,  // The escrow is tracked per shipment.
+			escrow_vault: Some(*vault_account.key),
+			escrow_flagged: false,
+			nonce: 0,
+			weight_grams: None,
+			volume_cm3: None,
+			distance_km: None,
+			quoted_rates: None,
+			route: None,
+			generation: 0,
+			stake_slashed: false,
 		})
 	}
 
+	/// Creates the return of a completed shipment, sending the same items from its recipient
+	/// back to its sender under the original insurance. The return picks up at the original
+	/// drop-off point and delivers to the original pickup point, paid by the recipient.
+	///
+	/// A shipment can be returned once. The return settles like any shipment, counting for
+	/// its sender, the original recipient, and its carrier; the original sender only receives
+	/// the items, so the return never counts as a second successful shipment for them.
+	pub fn create_return(
+		&mut self,
+		id: u64,
+		recipient: &User,  // Recipient of this shipment, sending the return.
+		sender: User,      // Sender of this shipment, receiving the return.
+		payment: u64,
+		pickup_datetime: i64,
+		drop_off_datetime: i64,
+		recipient_account: &AccountInfo,
+		recipient_authority_info: &AccountInfo,
+		vault_account: &AccountInfo,
+	) -> Result<Shipment, DLUError> {
+		if self.status != ShipmentStatus::Completed {
+			return Err(DLUError::NotCompleted);
+		}
+		if self.returned {
+			return Err(DLUError::AlreadyReturned);
+		}
+		if recipient.pubkey != self.recipient.pubkey {
+			return Err(DLUError::NotAuthorized);
+		}
+		if sender.pubkey != self.sender.pubkey {
+			return Err(DLUError::KeyMismatch);
+		}
+
+		let mut reverse = Shipment::list_shipment(
+			id,
+			recipient,
+			sender,
+			self.items_name.clone(),
+			self.quantity,
+			payment,
+			self.insurance,
+			self.drop_off_point.clone(),
+			pickup_datetime,
+			self.pickup_point.clone(),
+			drop_off_datetime,
+			recipient_account,
+			recipient_authority_info,
+			vault_account,
+		)?;
+		reverse.return_of = Some(self.id);
+		self.returned = true;
+
+		Ok(reverse)
+	}
+
 	pub fn accept_shipment(
 		&mut self, 
 		carrier: &mut User, // Mutable reference to the carrier.
 		carrier_account: &AccountInfo, 
-		escrow_account: &AccountInfo, 
+		vault_account: &AccountInfo, 
 		authority_info: &AccountInfo
-	) -> Result<(), &'static str> {
+	) -> Result<(), DLUError> {
 		// Ensure the shipment is in the 'Listed' state.
 		if self.status != ShipmentStatus::Listed {
-			return Err("Shipment is not in the 'Listed' state.");
+			return Err(DLUError::NotListed);
 		}
+		self.check_vault(vault_account)?;
+		self.check_payment_accounts(&[carrier_account, vault_account])?;
 		
-		// Generate the one-time keys for sender, carrier, and recipient.
-		self.sender_key = onetimekeys::generate_key(); 
-		self.carrier_key = onetimekeys::generate_key();
-		self.recipient_key = onetimekeys::generate_key();
+		// Carriers known for failing shipments are turned away.
+		if carrier.role_status(Role::Carrier) == UserStatus::Fraud {
+			return Err(DLUError::ReputationTooLow);
+		}
+
+		// The sender, carrier and recipient commit their one-time keys with `CommitKey`.
+		self.sender_key = OneTimeKey::NONE;
+		self.carrier_key = OneTimeKey::NONE;
+		self.recipient_key = OneTimeKey::NONE;
 
 		// Update the carrier field.
 		self.carrier = Some(carrier.clone());
 
 		// Check carrier's balance for insurance.
-		let carrier_balance = DLUToken::get_balance(carrier_account)?;
+		let carrier_balance = DLUToken::get_balance(carrier_account).map_err(|_| DLUError::BalanceUnavailable)?;
 		if carrier_balance < self.insurance {
-			return Err("Insufficient funds in carrier's account for insurance.");
+			return Err(DLUError::InsufficientFundsForInsurance);
 		}
 
-		// Deduct the insurance amount from the carrier's wallet.
-		carrier.wallet.balance -= self.insurance; // Assuming balance is mutable.
-
 		// Lock the insurance amount in escrow.
-		Escrow::lock_funds(carrier_account, escrow_account, authority_info, self.insurance)?;
+		Escrow::lock_funds(carrier_account, vault_account, authority_info, self.insurance)?;
 
 		// Update the status of the shipment to 'Accepted'.
 		self.status = ShipmentStatus::Accepted;
@@ -130,159 +271,422 @@ impl Shipment {
 		Ok(())
 	}
 
+	/// Confirms that the carrier took custody of the items. The sender hands the carrier
+	/// their key at pickup, so entering it proves the handover happened; the delivery clock
+	/// starts from then.
+	pub fn confirm_pickup(&mut self, carrier: &Pubkey, entered_sender_key: String, now: i64) -> Result<(), DLUError> {
+		// Ensure the shipment is in the 'Accepted' state.
+		if self.status != ShipmentStatus::Accepted {
+			return Err(DLUError::NotAccepted);
+		}
+		if self.carrier_pubkey() != Some(*carrier) {
+			return Err(DLUError::NotAuthorized);
+		}
+
+		// Validate the sender's key.
+		self.sender_key.check(&entered_sender_key, now, DLUError::InvalidSenderKey)?;
+
+		self.picked_up_at = Some(now);
+		self.status = ShipmentStatus::InTransit;
+
+		Ok(())
+	}
+
+	/// Records the digest of a photo or signature proving the items' state at delivery, kept
+	/// as evidence for disputes. The carrier can submit it any time while in transit; a
+	/// later proof replaces an earlier one.
+	pub fn submit_proof(&mut self, carrier: &Pubkey, proof_hash: [u8; 32]) -> Result<(), DLUError> {
+		if self.status != ShipmentStatus::InTransit {
+			return Err(DLUError::NotInTransit);
+		}
+		if self.carrier_pubkey() != Some(*carrier) {
+			return Err(DLUError::NotAuthorized);
+		}
+		self.delivery_proof = Some(proof_hash);
+		Ok(())
+	}
+
+	/// Completes an accepted shipment, returning the protocol fee taken from the carrier's payment.
+	/// A `proof_hash` given here replaces any proof submitted before.
 	pub fn complete_shipment(
 		&mut self, 
 		entered_carrier_key: String, 
 		entered_recipient_key: String,
+		proof_hash: Option<[u8; 32]>,
 		sender_account: &AccountInfo,
 		carrier_account: &AccountInfo,
-		escrow_account: &AccountInfo,
-		escrow_authority_info: &AccountInfo,
+		vault_account: &AccountInfo,
+		vault_seeds: &[&[u8]],
+		treasury_account: &AccountInfo,
+		config: &Config,
 		sender: &mut User,  // Mutable reference to sender User
 		carrier: &mut User, // Mutable reference to carrier User
-	) -> Result<(), &'static str> {
-		// Ensure the shipment is in the 'Accepted' state.
-		if self.status != ShipmentStatus::Accepted {
-			return Err("Shipment is not in the 'Accepted' state.");
+		now: i64,
+	) -> Result<u64, DLUError> {
+		// Ensure the carrier picked up the items.
+		if self.status != ShipmentStatus::InTransit {
+			return Err(DLUError::NotInTransit);
 		}
+		self.check_payout_vault(vault_account)?;
+		self.check_payment_accounts(&[sender_account, carrier_account, vault_account, treasury_account])?;
+		self.check_parties(&sender.pubkey, &carrier.pubkey, sender_account, carrier_account)?;
 
 		// Validate the carrier's key.
-		if entered_carrier_key != self.carrier_key {
-			return Err("Invalid carrier key provided.");
-		}
+		self.carrier_key.check(&entered_carrier_key, now, DLUError::InvalidCarrierKey)?;
 
 		// Check escrow balance.
-		let escrow_balance = DLUToken::get_balance(escrow_account)?;
-		if escrow_balance < (self.payment + self.insurance) {
-			return Err("Insufficient funds in escrow.");
+		let escrow_balance = DLUToken::get_balance(vault_account).map_err(|_| DLUError::BalanceUnavailable)?;
+		let escrow_total = safe_math::add(self.payment, self.insurance)?;
+		if escrow_balance < escrow_total {
+			return Err(DLUError::InsufficientEscrowFunds);
 		}
 
 		// Validate the recipient's key.
-		if entered_recipient_key != self.recipient_key {
-			return Err("Invalid recipient key provided.");
+		self.recipient_key.check(&entered_recipient_key, now, DLUError::InvalidRecipientKey)?;
+
+		// Send the protocol fee from the payment to the treasury.
+		let fee = config.fee_for(self.payment, carrier, Role::Carrier)?;
+		if fee > 0 {
+			Escrow::release_funds(vault_account, treasury_account, vault_seeds, fee)?;
 		}
 
-		// Release the payment and insurance amounts to the carrier's account and update carrier's balance.
-		let total_release = self.payment + self.insurance;
-		Escrow::release_funds(escrow_account, carrier_account, escrow_authority_info, total_release)?;
-		carrier.wallet.balance += total_release;
+		// Release the rest of the payment and the insurance to the carrier's account.
+		let total_release = safe_math::sub(safe_math::add(self.payment, self.insurance)?, fee)?;
+		Escrow::release_funds(vault_account, carrier_account, vault_seeds, total_release)?;
 
 		// Invalidate the keys.
-		self.sender_key.clear();
-		self.carrier_key.clear();
-		self.recipient_key.clear();
+		self.sender_key = OneTimeKey::NONE;
+		self.carrier_key = OneTimeKey::NONE;
+		self.recipient_key = OneTimeKey::NONE;
+
+		if proof_hash.is_some() {
+			self.delivery_proof = proof_hash;
+		}
 
 		// Update the status of the shipment to 'Completed'.
 		self.status = ShipmentStatus::Completed;
 
 		// Mark the shipment as successful for both the sender and carrier.
-		sender.mark_deal(true);
-		carrier.mark_deal(true);
+		sender.mark_shipment(Role::Sender, true);
+		carrier.mark_shipment(Role::Carrier, true);
+		sender.record_volume(Role::Sender, self.payment);
+		carrier.record_volume(Role::Carrier, self.payment);
 
-		Ok(())
+		Ok(fee)
 	}
 
+	/// Fails a picked-up shipment on the sender's claim. The sender recovers the payment and
+	/// the config's share of the carrier's insurance; the rest goes to the penalty account, or is
+	/// burned when the config enables `CAP_BURN_PENALTIES` (the payment mint is then passed as `penalty_account`).
+	/// Returns the penalty.
 	pub fn fail_shipment(
 		&mut self, 
 		entered_sender_key: String,
 		carrier: &mut User,
-		escrow_account: &AccountInfo,
+		sender_account: &AccountInfo,
+		vault_account: &AccountInfo,
 		penalty_account: &AccountInfo,
-		escrow_authority_info: &AccountInfo,
-	) -> Result<(), &'static str> {
-		// Ensure the shipment is in the 'Accepted' state.
-		if self.status != ShipmentStatus::Accepted {
-			return Err("Shipment is not in the 'Accepted' state.");
+		vault_seeds: &[&[u8]],
+		config: &Config,
+		now: i64,
+	) -> Result<u64, DLUError> {
+		// Ensure the carrier picked up the items.
+		if self.status != ShipmentStatus::InTransit {
+			return Err(DLUError::NotInTransit);
 		}
 
-		// Ensure that the carrier's key has been entered (i.e., the carrier has picked up the goods).
-		if self.carrier_key.is_empty() {
-			return Err("Carrier key has not been entered. Shipment has not been picked up.");
-		}
+		self.check_payout_vault(vault_account)?;
 
 		// Validate the sender's key.
-		if entered_sender_key != self.sender_key {
-			return Err("Invalid sender key provided.");
+		self.sender_key.check(&entered_sender_key, now, DLUError::InvalidSenderKey)?;
+
+		// A burned penalty passes the mint itself, which the token program checks on burning.
+		let burns_penalty = config.has_capability(CAP_BURN_PENALTIES);
+		let mut token_accounts = vec![sender_account, vault_account];
+		if !burns_penalty {
+			token_accounts.push(penalty_account);
 		}
+		self.check_payment_accounts(&token_accounts)?;
+
+		// Only the carrier can forfeit their insurance, and the refund goes to the sender's own account.
+		if self.carrier_pubkey() != Some(carrier.pubkey) {
+			return Err(DLUError::NotDealParty);
+		}
+		if !DLUToken::check_authority(sender_account, &self.sender.pubkey).map_err(|_| DLUError::KeyMismatch)? {
+			return Err(DLUError::KeyMismatch);
+		}
+
+		let (share, penalty) = config.split_penalty(self.insurance)?;
 
-		// Calculate the total amount to be transferred to the penalty account.
-		let total_amount = self.payment + self.insurance; 
+		// Return the payment to the sender along with their share of the carrier's insurance.
+		let sender_total = safe_math::add(self.payment, share)?;
+		Escrow::release_funds(vault_account, sender_account, vault_seeds, sender_total)?;
 
-		// Transfer the total_amount from the escrow to the penalty account.
-		Escrow::transfer_to_penalty(escrow_account, penalty_account, escrow_authority_info, total_amount)?;
+		// The rest of the carrier's insurance is the penalty.
+		if penalty > 0 {
+			if burns_penalty {
+				Escrow::burn_penalty(vault_account, penalty_account, vault_seeds, penalty)?;
+			} else {
+				Escrow::transfer_to_penalty(vault_account, penalty_account, vault_seeds, penalty)?;
+			}
+		}
 
 		// Invalidate the keys.
-		self.sender_key.clear();
-		self.carrier_key.clear();
-		self.recipient_key.clear();
+		self.sender_key = OneTimeKey::NONE;
+		self.carrier_key = OneTimeKey::NONE;
+		self.recipient_key = OneTimeKey::NONE;
 
 		// Update the status of the shipment to 'Failed'.
 		self.status = ShipmentStatus::Failed;
 
 		// Mark the shipment as failed for the carrier.
-		carrier.mark_deal(false);
+		carrier.mark_shipment(Role::Carrier, false);
 
-		Ok(())
+		Ok(penalty)
 	}
 
 	pub fn expire_shipment(
 		&mut self,
-		escrow_account: &AccountInfo,
+		vault_account: &AccountInfo,
+		sender_account: &AccountInfo,
+		carrier_account: &AccountInfo,
+		vault_seeds: &[&[u8]],
+		now: i64,
+	) -> Result<(), DLUError> {
+		self.check_expired(now)?;
+		self.check_payout_vault(vault_account)?;
+		self.check_payment_accounts(&[vault_account, sender_account, carrier_account])?;
+
+		// Anyone may expire a shipment, so the refunds must go to the parties' own accounts.
+		let carrier_key = self.carrier_pubkey().ok_or(DLUError::CounterpartyNotFound)?;
+		self.check_parties(&self.sender.pubkey, &carrier_key, sender_account, carrier_account)?;
+
+		// Release the payment back to the sender's account.
+		Escrow::release_funds(vault_account, sender_account, vault_seeds, self.payment)?;
+
+		// Release the carrier's insurance back to the carrier's account.
+		Escrow::release_funds(vault_account, carrier_account, vault_seeds, self.insurance)?;
+
+		// Update the status of the shipment to 'Expired'.
+		self.status = ShipmentStatus::Expired;
+
+		Ok(())
+	}
+
+	/// Expires an overdue shipment on behalf of any keeper, returning the bounty paid to them.
+	///
+	/// Only shipments whose escrow sits in the vault can be cranked, since the program
+	/// signs the releases itself. The bounty is capped at the insurance and taken half from
+	/// each side's refund.
+	pub fn crank_expire_shipment(
+		&mut self,
+		vault_account: &AccountInfo,
 		sender_account: &AccountInfo,
 		carrier_account: &AccountInfo,
-		escrow_authority_info: &AccountInfo,
-	) -> Result<(), &'static str> {
-		// Ensure the current date-time is past the drop_off_datetime + 24 hours.
-		let current_datetime = Utc::now();
-		if current_datetime <= self.drop_off_datetime + Duration::hours(24) {
-			return Err("Shipment hasn't expired yet.");
+		keeper_account: &AccountInfo,
+		vault_seeds: &[&[u8]],
+		bounty: u64,
+		now: i64,
+	) -> Result<u64, DLUError> {
+		self.check_expired(now)?;
+		self.check_payout_vault(vault_account)?;
+		self.check_payment_accounts(&[vault_account, sender_account, carrier_account, keeper_account])?;
+
+		// Anyone may crank, so the refunds must go to the parties' own accounts.
+		let carrier_key = self.carrier_pubkey().ok_or(DLUError::CounterpartyNotFound)?;
+		let owned_by = |account: &AccountInfo, key: &Pubkey| DLUToken::check_authority(account, key).map_err(|_| DLUError::KeyMismatch);
+		if !owned_by(sender_account, &self.sender.pubkey)? || !owned_by(carrier_account, &carrier_key)? {
+			return Err(DLUError::KeyMismatch);
 		}
 
-		// Ensure the shipment is still in the 'Accepted' state.
-		if self.status != ShipmentStatus::Accepted {
-			return Err("Shipment is not in the 'Accepted' state.");
+		let bounty = bounty.min(self.insurance);
+		let sender_share = bounty / 2;
+		let sender_total = safe_math::sub(self.payment, sender_share)?;
+		let carrier_total = safe_math::sub(self.insurance, bounty - sender_share)?;
+
+		for (destination, amount) in [(sender_account, sender_total), (carrier_account, carrier_total), (keeper_account, bounty)] {
+			if amount > 0 {
+				DLUToken::transfer_signed(vault_account, destination, vault_account, amount, &[vault_seeds])
+					.map_err(|_| DLUError::TransferFailed)?;
+			}
 		}
 
-		// Release the payment back to the sender's account.
-		Escrow::release_funds(escrow_account, sender_account, escrow_authority_info, self.payment)?;
+		self.status = ShipmentStatus::Expired;
 
-		// Add the payment amount back to the sender's wallet.
-		self.sender.wallet.balance += self.payment; // Assuming balance is mutable.
+		Ok(bounty)
+	}
 
-		// Release the carrier's insurance back to the carrier's account.
-		Escrow::release_funds(escrow_account, carrier_account, escrow_authority_info, self.insurance)?;
+	/// Expires a shipment no carrier accepted within `MAX_LISTING_LIFETIME` past its drop-off
+	/// time, on behalf of anyone, returning the payment refunded to the sender out of the vault.
+	pub fn reclaim_stale_listing(
+		&mut self,
+		vault_account: &AccountInfo,
+		sender_account: &AccountInfo,
+		vault_seeds: &[&[u8]],
+		now: i64,
+	) -> Result<u64, DLUError> {
+		if self.status != ShipmentStatus::Listed {
+			return Err(DLUError::NotListed);
+		}
+		if !self.is_stale(now) {
+			return Err(DLUError::ListingNotStale);
+		}
+		self.check_payout_vault(vault_account)?;
+		self.check_payment_accounts(&[vault_account, sender_account])?;
 
-		// Assuming the carrier is an Option<User>, and there is a possibility of it being None.
-		if let Some(carrier) = &mut self.carrier {
-			// Add the insurance amount back to the carrier's wallet.
-			carrier.wallet.balance += self.insurance; // Assuming balance is mutable.
-		} else {
-			return Err("Carrier not found in the shipment.");
+		// Anyone may reclaim, so the refund must go to the sender's own account.
+		if !DLUToken::check_authority(sender_account, &self.sender.pubkey).map_err(|_| DLUError::KeyMismatch)? {
+			return Err(DLUError::KeyMismatch);
 		}
 
-		// Update the status of the shipment to 'Expired'.
+		DLUToken::transfer_signed(vault_account, sender_account, vault_account, self.payment, &[vault_seeds])
+			.map_err(|_| DLUError::TransferFailed)?;
+
+		self.sender_key = OneTimeKey::NONE;
 		self.status = ShipmentStatus::Expired;
 
+		Ok(self.payment)
+	}
+
+	/// Returns true if the shipment is still listed `MAX_LISTING_LIFETIME` past its drop-off.
+	pub fn is_stale(&self, now: i64) -> bool {
+		self.status == ShipmentStatus::Listed && now >= self.drop_off_datetime.saturating_add(time::MAX_LISTING_LIFETIME)
+	}
+
+	/// Ensures the shipment is accepted and the grace period after its delivery deadline has passed.
+	fn check_expired(&self, now: i64) -> Result<(), DLUError> {
+		if !time::is_expired(self.delivery_deadline(), now) {
+			return Err(DLUError::ShipmentNotExpired);
+		}
+		if self.status != ShipmentStatus::Accepted && self.status != ShipmentStatus::InTransit {
+			return Err(DLUError::NotAccepted);
+		}
 		Ok(())
 	}
+
+	/// Returns when the items are due at the drop-off point. Once picked up, the carrier gets
+	/// the scheduled time between pickup and drop-off counted from the actual pickup; until
+	/// then, the scheduled drop-off.
+	pub fn delivery_deadline(&self) -> i64 {
+		match self.picked_up_at {
+			Some(picked_up_at) => {
+				let window = self.drop_off_datetime.saturating_sub(self.pickup_datetime).max(0);
+				picked_up_at.saturating_add(window)
+			}
+			None => self.drop_off_datetime,
+		}
+	}
+
+	/// Returns when the carrier confirmed the pickup, if they did.
+	pub fn picked_up_at(&self) -> Option<i64> {
+		self.picked_up_at
+	}
+
+	/// Returns where the items are picked up.
+	pub fn pickup_point(&self) -> &Location {
+		&self.pickup_point
+	}
+
+	/// Returns where the items are dropped off.
+	pub fn drop_off_point(&self) -> &Location {
+		&self.drop_off_point
+	}
+
+	/// Returns the id of the shipment this one sends back, if it is a return.
+	pub fn return_of(&self) -> Option<u64> {
+		self.return_of
+	}
+
+	/// Returns the digest of the delivery proof, if one was submitted.
+	pub fn delivery_proof(&self) -> Option<[u8; 32]> {
+		self.delivery_proof
+	}
+
+	/// Records the weight, volume and distance the sender measured for a listed shipment,
+	/// along with the config's current `rates`, and returns the payment they suggest. The
+	/// quote inputs stay on the shipment so a dispute can reproduce what the payment was set
+	/// against; measuring again replaces them until a carrier accepts.
+	pub fn measure(
+		&mut self,
+		sender: &Pubkey,
+		weight_grams: Option<u32>,
+		volume_cm3: Option<u32>,
+		distance_km: Option<u32>,
+		rates: ShippingRates,
+	) -> Result<u64, DLUError> {
+		if self.status != ShipmentStatus::Listed {
+			return Err(DLUError::NotListed);
+		}
+		if *sender != self.sender.pubkey {
+			return Err(DLUError::NotAuthorized);
+		}
+		let suggested = pricing::suggested_payment(&rates, weight_grams, volume_cm3, distance_km)?;
+		self.weight_grams = weight_grams;
+		self.volume_cm3 = volume_cm3;
+		self.distance_km = distance_km;
+		self.quoted_rates = Some(rates);
+		Ok(suggested)
+	}
+
+	/// Returns the measured weight in grams, volume in cubic centimeters and distance in
+	/// kilometers, as far as the sender gave them.
+	pub fn measurements(&self) -> (Option<u32>, Option<u32>, Option<u32>) {
+		(self.weight_grams, self.volume_cm3, self.distance_km)
+	}
+
+	/// Returns the rates the shipment was quoted at, if the sender measured it.
+	pub fn quoted_rates(&self) -> Option<ShippingRates> {
+		self.quoted_rates
+	}
+
+	/// Lists a shipment along the carrier route at `route_address`, which must run from the
+	/// region of its pickup point to the region of its drop-off point and take its measured
+	/// weight. Referencing another route replaces it until a carrier accepts; the route's
+	/// carrier is notified but gets no claim on the shipment.
+	pub fn reference_route(&mut self, sender: &Pubkey, route_address: Pubkey, route: &CarrierRoute) -> Result<(), DLUError> {
+		if self.status != ShipmentStatus::Listed {
+			return Err(DLUError::NotListed);
+		}
+		if *sender != self.sender.pubkey {
+			return Err(DLUError::NotAuthorized);
+		}
+		let grams = pricing::chargeable_grams(self.weight_grams, self.volume_cm3);
+		route.check_carries(self.pickup_point.region(), self.drop_off_point.region(), grams)?;
+		self.route = Some(route_address);
+		Ok(())
+	}
+
+	/// Returns the carrier route the shipment was listed along, if any.
+	pub fn route(&self) -> Option<Pubkey> {
+		self.route
+	}
+
+	/// Returns the payment suggested when the sender measured the shipment, if they did.
+	pub fn suggested_payment(&self) -> Result<Option<u64>, DLUError> {
+		self.quoted_rates
+			.map(|rates| pricing::suggested_payment(&rates, self.weight_grams, self.volume_cm3, self.distance_km))
+			.transpose()
+	}
 	
 	pub fn cancel_shipment(
 		&mut self,
 		sender_account: &AccountInfo,
-		escrow_account: &AccountInfo,
-		escrow_authority_info: &AccountInfo,
-	) -> Result<(), &'static str> {
+		vault_account: &AccountInfo,
+		vault_seeds: &[&[u8]],
+	) -> Result<(), DLUError> {
 		// Ensure the shipment is in the 'Listed' state.
 		if self.status != ShipmentStatus::Listed {
-			return Err("Shipment is not in the 'Listed' state or has already been accepted.");
+			return Err(DLUError::NotListed);
 		}
+		self.check_payout_vault(vault_account)?;
+		self.check_payment_accounts(&[sender_account, vault_account])?;
 
 		// Release the locked payment back to the sender's account.
 		// The locked amount in escrow is the payment amount.
-		Escrow::release_funds(escrow_account, sender_account, escrow_authority_info, self.payment)?;
+		Escrow::release_funds(vault_account, sender_account, vault_seeds, self.payment)?;
 
 		// Invalidate the sender's key.
-		self.sender_key.clear();
+		self.sender_key = OneTimeKey::NONE;
 
 		// Update the status of the shipment to 'Canceled'.
 		self.status = ShipmentStatus::Canceled;
@@ -290,18 +694,289 @@ impl Shipment {
 		Ok(())
 	}
 	
+	/// Lists an expired or canceled shipment again, locking a fresh payment from the sender.
+	pub fn relist_shipment(
+		&mut self,
+		sender: &mut User,
+		sender_account: &AccountInfo,
+		sender_authority_info: &AccountInfo,
+		vault_account: &AccountInfo,
+	) -> Result<(), DLUError> {
+		if self.status != ShipmentStatus::Expired && self.status != ShipmentStatus::Canceled {
+			return Err(DLUError::NotRelistable);
+		}
+		if sender.pubkey != self.sender.pubkey {
+			return Err(DLUError::NotAuthorized);
+		}
+		self.check_payment_accounts(&[sender_account, vault_account])?;
+
+		// Lock a fresh payment from the sender into the vault of the shipment's mint.
+		let sender_balance = DLUToken::get_balance(sender_account).map_err(|_| DLUError::BalanceUnavailable)?;
+		if sender_balance < self.payment {
+			return Err(DLUError::InsufficientFundsForPayment);
+		}
+		Escrow::lock_funds(sender_account, vault_account, sender_authority_info, self.payment)?;
+
+		// Start over as a new listing.
+		self.sender = sender.clone();
+		self.carrier = None;
+		self.picked_up_at = None;
+		self.sender_key = OneTimeKey::NONE;
+		self.carrier_key = OneTimeKey::NONE;
+		self.recipient_key = OneTimeKey::NONE;
+		self.escrow_vault = Some(*vault_account.key);
+		self.generation = self.generation.checked_add(1).ok_or(DLUError::ArithmeticOverflow)?;
+		self.status = ShipmentStatus::Listed;
+
+		Ok(())
+	}
+
+	/// Returns how many times the shipment has been relisted.
+	pub fn generation(&self) -> u32 {
+		self.generation
+	}
+
+	/// Returns the nonce terminal instructions must name to act on the shipment as it is now.
+	pub fn nonce(&self) -> u64 {
+		self.nonce
+	}
+
+	/// Checks that an instruction was built against the shipment's current state.
+	pub fn check_nonce(&self, nonce: u64) -> Result<(), DLUError> {
+		if nonce != self.nonce {
+			return Err(DLUError::StaleNonce);
+		}
+		Ok(())
+	}
+
+	/// Advances the nonce once the shipment moved into a new status.
+	pub fn advance_nonce(&mut self) {
+		self.nonce = self.nonce.wrapping_add(1);
+	}
+
+	/// Returns the id of the shipment.
+	pub fn id(&self) -> u64 {
+		self.id
+	}
+
+	/// Returns the current status of the shipment.
+	pub fn status(&self) -> ShipmentStatus {
+		self.status
+	}
+
+	/// Returns true once the shipment was delivered.
+	pub fn is_completed(&self) -> bool {
+		self.status == ShipmentStatus::Completed
+	}
+
+	/// Returns the payment the carrier receives on delivery.
+	pub fn payment(&self) -> u64 {
+		self.payment
+	}
+
+	/// Records that the carrier's stake was slashed for this failed shipment.
+	pub fn mark_stake_slashed(&mut self) -> Result<(), DLUError> {
+		if self.status != ShipmentStatus::Failed {
+			return Err(DLUError::IncorrectState);
+		}
+		if self.stake_slashed {
+			return Err(DLUError::AlreadySlashed);
+		}
+		self.stake_slashed = true;
+		Ok(())
+	}
+
+	/// Returns the mint the shipment is paid in.
+	pub fn payment_mint(&self) -> Pubkey {
+		self.payment_mint
+	}
+
+	/// Returns the public key of the sender who listed the shipment.
+	pub fn sender_pubkey(&self) -> Pubkey {
+		self.sender.pubkey
+	}
+
+	/// Returns the public key of the carrier once the shipment has been accepted.
+	pub fn carrier_pubkey(&self) -> Option<Pubkey> {
+		self.carrier.as_ref().map(|carrier| carrier.pubkey)
+	}
+
+	/// Returns the public key of the recipient.
+	pub fn recipient_pubkey(&self) -> Pubkey {
+		self.recipient.pubkey
+	}
+
+	/// Returns the hashes of the sender's, carrier's and recipient's one-time keys, `NO_KEY`
+	/// until they committed them.
+	pub fn key_hashes(&self) -> (KeyHash, KeyHash, KeyHash) {
+		(self.sender_key.hash, self.carrier_key.hash, self.recipient_key.hash)
+	}
+
+	/// Returns the sender's, carrier's and recipient's one-time keys as committed.
+	pub fn keys(&self) -> (OneTimeKey, OneTimeKey, OneTimeKey) {
+		(self.sender_key, self.carrier_key, self.recipient_key)
+	}
+
+	/// Commits the hash of the one-time key a party of an accepted shipment will hand over:
+	/// the sender's at pickup, the carrier's and recipient's at delivery. A party holding
+	/// several roles commits the same key to each of them. Each key is committed once per
+	/// acceptance and accepted for `KEY_LIFETIME` from `now`.
+	pub fn commit_key(&mut self, party: &Pubkey, key_hash: KeyHash, now: i64) -> Result<(), DLUError> {
+		if self.status != ShipmentStatus::Accepted && self.status != ShipmentStatus::InTransit {
+			return Err(DLUError::NotAccepted);
+		}
+		let committed_key = OneTimeKey::commit(key_hash, now)?;
+		let carrier = self.carrier_pubkey().ok_or(DLUError::CounterpartyNotFound)?;
+		let parties = [self.sender.pubkey, carrier, self.recipient.pubkey];
+		let keys = [&mut self.sender_key, &mut self.carrier_key, &mut self.recipient_key];
+		let mut committed = false;
+		for (holder, key) in parties.iter().zip(keys) {
+			if holder != party {
+				continue;
+			}
+			if key.is_committed() {
+				return Err(DLUError::KeyAlreadyCommitted);
+			}
+			*key = committed_key;
+			committed = true;
+		}
+		if !committed {
+			return Err(DLUError::NotDealParty);
+		}
+		Ok(())
+	}
+
+	/// Reschedules the drop-off of an accepted or picked up shipment to a later
+	/// `drop_off_datetime`, which moves its delivery deadline along. The sender and the
+	/// carrier both consent, and only before the shipment can be expired.
+	pub fn extend_deadline(
+		&mut self,
+		sender: &Pubkey,
+		carrier: &Pubkey,
+		drop_off_datetime: i64,
+		now: i64,
+	) -> Result<(), DLUError> {
+		if self.status != ShipmentStatus::Accepted && self.status != ShipmentStatus::InTransit {
+			return Err(DLUError::NotAccepted);
+		}
+		if *sender != self.sender.pubkey || Some(*carrier) != self.carrier_pubkey() {
+			return Err(DLUError::NotDealParty);
+		}
+		if drop_off_datetime <= self.drop_off_datetime || time::is_expired(self.delivery_deadline(), now) {
+			return Err(DLUError::InvalidDeadline);
+		}
+		self.drop_off_datetime = drop_off_datetime;
+		Ok(())
+	}
+
+	/// Returns the escrow of an in-flight shipment to the parties who deposited it and
+	/// cancels the shipment, for governance unwinding a shipment a bug left stuck. Returns
+	/// the sender's and the carrier's refunds out of the vault.
+	pub fn emergency_unwind(
+		&mut self,
+		vault_account: &AccountInfo,
+		sender_account: &AccountInfo,
+		carrier_account: &AccountInfo,
+		vault_seeds: &[&[u8]],
+	) -> Result<(u64, u64), DLUError> {
+		self.check_vault(vault_account)?;
+		self.check_payment_accounts(&[vault_account, sender_account, carrier_account])?;
+
+		let owned_by = |account: &AccountInfo, key: &Pubkey| DLUToken::check_authority(account, key).map_err(|_| DLUError::KeyMismatch);
+		if !owned_by(sender_account, &self.sender.pubkey)? {
+			return Err(DLUError::KeyMismatch);
+		}
+		let carrier_refund = match self.status {
+			ShipmentStatus::Listed => 0,
+			ShipmentStatus::Accepted | ShipmentStatus::InTransit => {
+				let carrier_key = self.carrier_pubkey().ok_or(DLUError::CounterpartyNotFound)?;
+				if !owned_by(carrier_account, &carrier_key)? {
+					return Err(DLUError::KeyMismatch);
+				}
+				self.insurance
+			}
+			_ => return Err(DLUError::EscrowEmpty),
+		};
+
+		for (destination, amount) in [(sender_account, self.payment), (carrier_account, carrier_refund)] {
+			if amount > 0 {
+				DLUToken::transfer_signed(vault_account, destination, vault_account, amount, &[vault_seeds])
+					.map_err(|_| DLUError::TransferFailed)?;
+			}
+		}
+
+		self.sender_key = OneTimeKey::NONE;
+		self.carrier_key = OneTimeKey::NONE;
+		self.recipient_key = OneTimeKey::NONE;
+		self.status = ShipmentStatus::Canceled;
+
+		Ok((self.payment, carrier_refund))
+	}
+
+	/// Reconciles the vault the shipment's escrow sits in against `vault_locked`, the total
+	/// the stats record escrowed there, like `Deal::verify_escrow`. Returns both; a vault
+	/// holding less flags the shipment for good.
+	pub fn verify_escrow(&mut self, vault_account: &AccountInfo, vault_locked: u64) -> Result<(u64, u64), DLUError> {
+		self.check_vault(vault_account)?;
+		self.check_payment_accounts(&[vault_account])?;
+
+		let actual = DLUToken::get_balance(vault_account).map_err(|_| DLUError::BalanceUnavailable)?;
+		if actual < vault_locked {
+			self.escrow_flagged = true;
+		}
+		Ok((vault_locked, actual))
+	}
+
+	/// Returns true once a reconciliation found the shipment's escrow short, which blocks its payouts.
+	pub fn is_escrow_flagged(&self) -> bool {
+		self.escrow_flagged
+	}
+
+	/// Returns the amount currently held in escrow for this shipment.
+	pub fn escrowed_amount(&self) -> u64 {
+		match self.status {
+			ShipmentStatus::Listed => self.payment,
+			ShipmentStatus::Accepted | ShipmentStatus::InTransit => self.payment.saturating_add(self.insurance),
+			_ => 0,
+		}
+	}
+
+	/// Returns true once the shipment can be archived: it ended with nothing left in
+	/// escrow, and `ARCHIVE_DELAY` passed since its drop-off time.
+	pub fn is_archivable(&self, now: i64) -> bool {
+		let ended = !matches!(self.status, ShipmentStatus::Listed | ShipmentStatus::Accepted | ShipmentStatus::InTransit);
+		ended && self.escrowed_amount() == 0 && now >= self.drop_off_datetime.saturating_add(ARCHIVE_DELAY)
+	}
+
+	/// Points the shipment's escrow at the vault of its payment mint, returning the amount to
+	/// move there.
+	pub fn migrate_escrow(&mut self, legacy_escrow_account: &AccountInfo, vault_account: &AccountInfo) -> Result<u64, DLUError> {
+		// Only in-flight shipments still hold funds in a legacy escrow account.
+		let in_flight = matches!(self.status, ShipmentStatus::Listed | ShipmentStatus::Accepted | ShipmentStatus::InTransit);
+		if !in_flight {
+			return Err(DLUError::EscrowEmpty);
+		}
+		if self.escrow_vault.is_some() {
+			return Err(DLUError::EscrowAlreadyMigrated);
+		}
+		self.check_payment_accounts(&[legacy_escrow_account, vault_account])?;
+
+		self.escrow_vault = Some(*vault_account.key);
+		Ok(self.escrowed_amount())
+	}
+
 	/// Updates the status of the shipment.
     pub fn update_status(&mut self, new_status: ShipmentStatus) {
         self.status = new_status;
     }
 	
 	/// Serializes the shipment into a vector of bytes.
-    pub fn serialize(&self) -> Result<Vec<u8>, &'static str> {
-        self.try_to_vec().map_err(|_| "Failed to serialize Shipment")
+    pub fn serialize(&self) -> Result<Vec<u8>, DLUError> {
+        migrations::pack(self)
     }
 
     /// Deserializes a shipment from a slice of bytes.
-    pub fn deserialize(input: &mut &[u8]) -> Result<Self, &'static str> {
-        Self::try_from_slice(input).map_err(|_| "Failed to deserialize Shipment")
+    pub fn deserialize(input: &mut &[u8]) -> Result<Self, DLUError> {
+        migrations::unpack(input)
     }
 }
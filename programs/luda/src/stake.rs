@@ -0,0 +1,86 @@
+use solana_program::pubkey::Pubkey;
+use solana_program::borsh::{BorshSerialize, BorshDeserialize};
+use crate::errors::DLUError;
+use crate::safe_math;
+use crate::time;
+
+/// DLU a carrier keeps locked to be allowed to carry high-value shipments.
+///
+/// Staking or accepting a stake-gated shipment restarts the `STAKE_COOLDOWN`, and nothing
+/// can be unstaked before it ends, so a carrier can't stake, accept, and withdraw at once.
+///
+/// Lives at the PDA `["stake", carrier_pubkey]`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct CarrierStake {
+    pub carrier: Pubkey,
+    pub mint: Pubkey,  // Payment mint the stake is held in, in the vault of that mint.
+    pub amount: u64,
+    pub cooldown_until: i64,  // Unstaking is allowed from this timestamp on.
+    pub total_slashed: u64,
+}
+
+impl CarrierStake {
+    /// Creates an empty stake for the carrier, held in `mint`.
+    pub fn new(carrier: Pubkey, mint: Pubkey) -> Self {
+        CarrierStake {
+            carrier,
+            mint,
+            amount: 0,
+            cooldown_until: 0,
+            total_slashed: 0,
+        }
+    }
+
+    /// Adds to the stake and restarts the cooldown.
+    pub fn stake(&mut self, amount: u64, now: i64) -> Result<(), DLUError> {
+        if amount == 0 {
+            return Err(DLUError::InvalidStakeAmount);
+        }
+        self.amount = safe_math::add(self.amount, amount)?;
+        self.restart_cooldown(now);
+        Ok(())
+    }
+
+    /// Withdraws part of the stake once the cooldown has ended.
+    pub fn unstake(&mut self, amount: u64, now: i64) -> Result<(), DLUError> {
+        if now < self.cooldown_until {
+            return Err(DLUError::StakeCoolingDown);
+        }
+        if amount == 0 || amount > self.amount {
+            return Err(DLUError::InvalidStakeAmount);
+        }
+        self.amount -= amount;
+        Ok(())
+    }
+
+    /// Ensures the stake covers the given minimum and locks it for another cooldown.
+    pub fn commit(&mut self, min_stake: u64, now: i64) -> Result<(), DLUError> {
+        if self.amount < min_stake {
+            return Err(DLUError::InsufficientStake);
+        }
+        self.restart_cooldown(now);
+        Ok(())
+    }
+
+    /// Takes up to `amount` from the stake, returning how much was slashed.
+    pub fn slash(&mut self, amount: u64) -> Result<u64, DLUError> {
+        let slashed = amount.min(self.amount);
+        self.amount -= slashed;
+        self.total_slashed = safe_math::add(self.total_slashed, slashed)?;
+        Ok(slashed)
+    }
+
+    fn restart_cooldown(&mut self, now: i64) {
+        self.cooldown_until = now.saturating_add(time::STAKE_COOLDOWN);
+    }
+
+    /// Serializes the stake into a vector of bytes.
+    pub fn serialize(&self) -> Result<Vec<u8>, DLUError> {
+        self.try_to_vec().map_err(|_| DLUError::SerializationFailed)
+    }
+
+    /// Deserializes a stake from a slice of bytes.
+    pub fn deserialize(input: &mut &[u8]) -> Result<Self, DLUError> {
+        <Self as BorshDeserialize>::deserialize(input).map_err(|_| DLUError::DeserializationFailed)
+    }
+}
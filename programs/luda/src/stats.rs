@@ -0,0 +1,124 @@
+use solana_program::borsh::{BorshSerialize, BorshDeserialize};
+use solana_program::pubkey::Pubkey;
+use crate::config::Config;
+use crate::errors::DLUError;
+use crate::safe_math;
+use crate::vault_registry::MAX_REGISTERED_VAULTS;
+
+/// The value escrowed in one vault, which that vault's balance must cover.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct VaultLock {
+    pub vault: Pubkey,
+    pub amount: u64,
+}
+
+/// Aggregate marketplace counters updated by the handlers.
+///
+/// Lets clients show marketplace health from a single account instead of scanning every
+/// entity. Listings, completions, and failures count offers, requests, and shipments alike;
+/// the volumes are running totals of everything ever moved into and out of escrow. The TVL is
+/// also kept per vault, which is what `VerifyEscrow` reconciles a vault's balance against.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default)]
+pub struct Stats {
+    pub total_value_locked: u64,  // DLU currently held in escrow across all entities.
+    pub tvl_epoch: u32,           // Config epoch the peak below belongs to.
+    pub epoch_peak_value_locked: u64,  // Highest TVL reached during the current epoch.
+    pub users_created: u64,
+    pub listings: u64,
+    pub completions: u64,
+    pub failures: u64,
+    pub volume_escrowed: u64,
+    pub volume_released: u64,
+    pub penalties: u64,  // Forfeited insurance and slashed stakes, burned or sent to the penalty account.
+    pub vault_locks: Vec<VaultLock>,  // The TVL split by the vault holding it.
+}
+
+impl Stats {
+    /// Creates a new stats account with zeroed counters.
+    pub fn new() -> Self {
+        Stats::default()
+    }
+
+    /// Records value newly escrowed in a vault, rejecting it if the config's TVL cap would be
+    /// exceeded.
+    pub fn lock_value(&mut self, config: &Config, vault: &Pubkey, amount: u64) -> Result<(), DLUError> {
+        // Start a fresh epoch once governance has changed the cap.
+        if self.tvl_epoch != config.tvl_epoch {
+            self.tvl_epoch = config.tvl_epoch;
+            self.epoch_peak_value_locked = self.total_value_locked;
+        }
+
+        let new_total = safe_math::add(self.total_value_locked, amount)?;
+        if new_total > config.tvl_cap {
+            return Err(DLUError::TvlCapExceeded);
+        }
+
+        let index = match self.vault_locks.iter().position(|lock| lock.vault == *vault) {
+            Some(index) => index,
+            None if self.vault_locks.len() < MAX_REGISTERED_VAULTS => {
+                self.vault_locks.push(VaultLock { vault: *vault, amount: 0 });
+                self.vault_locks.len() - 1
+            }
+            None => return Err(DLUError::VaultRegistryFull),
+        };
+        let locked = &mut self.vault_locks[index];
+        locked.amount = safe_math::add(locked.amount, amount)?;
+
+        self.total_value_locked = new_total;
+        self.volume_escrowed = self.volume_escrowed.saturating_add(amount);
+        if new_total > self.epoch_peak_value_locked {
+            self.epoch_peak_value_locked = new_total;
+        }
+        Ok(())
+    }
+
+    /// Records value leaving escrow in a vault.
+    pub fn release_value(&mut self, vault: &Pubkey, amount: u64) {
+        if let Some(locked) = self.vault_locks.iter_mut().find(|lock| lock.vault == *vault) {
+            locked.amount = locked.amount.saturating_sub(amount);
+        }
+        self.total_value_locked = self.total_value_locked.saturating_sub(amount);
+        self.volume_released = self.volume_released.saturating_add(amount);
+    }
+
+    /// Returns the value escrowed in a vault, which its balance should never fall below.
+    pub fn locked_in(&self, vault: &Pubkey) -> u64 {
+        self.vault_locks.iter().find(|lock| lock.vault == *vault).map_or(0, |lock| lock.amount)
+    }
+
+    /// Records a newly created user.
+    pub fn record_user(&mut self) {
+        self.users_created = self.users_created.saturating_add(1);
+    }
+
+    /// Records a new offer, request, or shipment.
+    pub fn record_listing(&mut self) {
+        self.listings = self.listings.saturating_add(1);
+    }
+
+    /// Records a completed offer, request, or shipment.
+    pub fn record_completion(&mut self) {
+        self.completions = self.completions.saturating_add(1);
+    }
+
+    /// Records a failed offer, request, or shipment and the penalty it cost.
+    pub fn record_failure(&mut self, penalty: u64) {
+        self.failures = self.failures.saturating_add(1);
+        self.record_penalty(penalty);
+    }
+
+    /// Records a penalty taken out of escrow.
+    pub fn record_penalty(&mut self, amount: u64) {
+        self.penalties = self.penalties.saturating_add(amount);
+    }
+
+    /// Serializes the stats into a vector of bytes.
+    pub fn serialize(&self) -> Result<Vec<u8>, DLUError> {
+        self.try_to_vec().map_err(|_| DLUError::SerializationFailed)
+    }
+
+    /// Deserializes stats from a slice of bytes.
+    pub fn deserialize(input: &mut &[u8]) -> Result<Self, DLUError> {
+        <Self as BorshDeserialize>::deserialize(input).map_err(|_| DLUError::DeserializationFailed)
+    }
+}
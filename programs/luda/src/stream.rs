@@ -0,0 +1,66 @@
+use solana_program::borsh::{BorshSerialize, BorshDeserialize};
+use crate::errors::DLUError;
+use crate::safe_math;
+use crate::time::SECONDS_PER_DAY;
+
+/// Longest service period a streaming offer can vest its payment over, in seconds.
+pub const MAX_STREAM_DURATION: i64 = 365 * SECONDS_PER_DAY;
+
+/// Payment of a long-running service vesting linearly to the seller.
+///
+/// The service period runs `duration` seconds from the offer's meeting time. Once the
+/// offer is accepted, the seller withdraws whatever has vested with `WithdrawStream`, and
+/// completing the offer pays them what is left. Either party can stop the stream, which
+/// freezes vesting: the seller can still withdraw what vested until then, while the unvested
+/// remainder stays in escrow until the parties or an arbiter settle the deal.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, PartialEq, Debug)]
+pub struct PaymentStream {
+    pub duration: i64,
+    pub withdrawn: u64,  // Paid out to the seller so far.
+    pub stopped_at: Option<i64>,
+}
+
+impl PaymentStream {
+    /// Agrees on a stream vesting over `duration` seconds.
+    pub fn new(duration: i64) -> Self {
+        PaymentStream { duration, withdrawn: 0, stopped_at: None }
+    }
+
+    /// Returns true if the stream can be listed: a fresh stream over a supported period.
+    pub fn is_valid(&self) -> bool {
+        (1..=MAX_STREAM_DURATION).contains(&self.duration) && self.withdrawn == 0 && self.stopped_at.is_none()
+    }
+
+    /// Returns when the service period starting at `start` ends.
+    pub fn end(&self, start: i64) -> i64 {
+        start.saturating_add(self.duration)
+    }
+
+    /// Returns the share of `total` vested at `now` over the period starting at `start`.
+    pub fn vested(&self, total: u64, start: i64, now: i64) -> u64 {
+        let until = self.stopped_at.map_or(now, |stopped_at| stopped_at.min(now));
+        let elapsed = until.saturating_sub(start).clamp(0, self.duration);
+        (total as u128 * elapsed as u128 / self.duration as u128) as u64
+    }
+
+    /// Records the withdrawal of everything vested but not withdrawn yet, given the
+    /// `remaining` payment in escrow, returning the amount.
+    pub fn withdraw(&mut self, remaining: u64, start: i64, now: i64) -> Result<u64, DLUError> {
+        let total = safe_math::add(remaining, self.withdrawn)?;
+        let amount = safe_math::sub(self.vested(total, start, now), self.withdrawn)?;
+        if amount == 0 {
+            return Err(DLUError::NothingVested);
+        }
+        self.withdrawn = safe_math::add(self.withdrawn, amount)?;
+        Ok(amount)
+    }
+
+    /// Stops vesting at `now`.
+    pub fn stop(&mut self, now: i64) -> Result<(), DLUError> {
+        if self.stopped_at.is_some() {
+            return Err(DLUError::StreamStopped);
+        }
+        self.stopped_at = Some(now);
+        Ok(())
+    }
+}
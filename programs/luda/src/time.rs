@@ -0,0 +1,55 @@
+use solana_program::{clock::Clock, program_error::ProgramError, sysvar::Sysvar};
+
+// All datetimes in the program are unix timestamps in seconds.
+pub const SECONDS_PER_HOUR: i64 = 60 * 60;
+pub const SECONDS_PER_DAY: i64 = 24 * SECONDS_PER_HOUR;
+
+/// Approximate number of slots per day, at the target slot time of 400ms.
+pub const SLOTS_PER_DAY: u64 = 216_000;
+
+/// Time after a meeting or drop-off during which the deal can still be completed.
+pub const EXPIRY_GRACE_PERIOD: i64 = SECONDS_PER_DAY;
+
+/// Longest a listing no one accepted stays open past its meeting or drop-off time, after
+/// which anyone can reclaim the lister's deposit for them.
+pub const MAX_LISTING_LIFETIME: i64 = 30 * SECONDS_PER_DAY;
+
+/// Time after a fail during which the accused party can dispute it.
+pub const FAIL_DISPUTE_WINDOW: i64 = 3 * SECONDS_PER_DAY;
+
+/// Time after staking or accepting a stake-gated shipment before a carrier can unstake.
+pub const STAKE_COOLDOWN: i64 = 7 * SECONDS_PER_DAY;
+
+/// Time after deregistering before an arbiter can withdraw their stake, which covers the
+/// appeal window of their last rulings.
+pub const ARBITER_COOLDOWN: i64 = 14 * SECONDS_PER_DAY;
+
+/// Time an assigned arbiter has to rule before the dispute can be drawn again, and an
+/// appeal panel has to reach a majority before the appeal lapses.
+pub const ARBITRATION_TIMEOUT: i64 = 7 * SECONDS_PER_DAY;
+
+/// Time after a ruling during which the losing party can appeal it.
+pub const APPEAL_WINDOW: i64 = 3 * SECONDS_PER_DAY;
+
+/// Time a user waits between two draws from the faucet of a test cluster.
+pub const FAUCET_COOLDOWN: i64 = SECONDS_PER_DAY;
+
+/// Returns the current unix timestamp from the Clock sysvar.
+pub fn now() -> Result<i64, ProgramError> {
+    Ok(Clock::get()?.unix_timestamp)
+}
+
+/// Returns the current slot from the Clock sysvar.
+pub fn slot() -> Result<u64, ProgramError> {
+    Ok(Clock::get()?.slot)
+}
+
+/// Returns the timestamp after which a deal due at `deadline` can be expired.
+pub fn expiry_time(deadline: i64) -> i64 {
+    deadline.saturating_add(EXPIRY_GRACE_PERIOD)
+}
+
+/// Returns true once the grace period after `deadline` has passed.
+pub fn is_expired(deadline: i64, now: i64) -> bool {
+    now > expiry_time(deadline)
+}
@@ -0,0 +1,145 @@
+use solana_program::borsh::{BorshSerialize, BorshDeserialize};
+use solana_program::pubkey::Pubkey;
+use crate::addressing::EntityType;
+use crate::config::Config;
+use crate::errors::DLUError;
+use crate::pricing::ShippingRates;
+use crate::time::SECONDS_PER_DAY;
+
+/// Time between governance queuing a config update and anyone being able to execute it,
+/// so users see the change coming and can leave before it applies.
+pub const GOVERNANCE_TIMELOCK: i64 = 2 * SECONDS_PER_DAY;
+
+/// Maximum number of governance actions waiting in the queue at once.
+pub const MAX_QUEUED_ACTIONS: usize = 8;
+
+/// Largest serialized GovernanceAction: its tag and the mint, destination and amount of
+/// `WithdrawTreasury`.
+pub const GOVERNANCE_ACTION_SIZE: usize = 1 + 32 + 32 + 8;
+
+/// Account size of the TimelockQueue.
+pub const TIMELOCK_QUEUE_SIZE: usize = 8 + 4 + MAX_QUEUED_ACTIONS * (8 + GOVERNANCE_ACTION_SIZE + 8);
+
+/// An update governance can queue, mirroring the instruction that applies it directly.
+/// Changing where fail penalties go is `SetCapabilities` toggling `CAP_BURN_PENALTIES`. The
+/// last three move funds or create accounts rather than update the config, so executing them
+/// takes the accounts of their instruction.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub enum GovernanceAction {
+    RaiseTvlCap { new_cap: u64 },
+    SetCapabilities { capabilities: u64 },
+    PublishTerms { terms_hash: [u8; 32] },
+    SetReputationHalfLife { half_life: u64 },
+    SetFailBond { amount: u64 },
+    SetFee { fee_bps: u16, exempt_credible: bool },
+    SetPenaltyShare { share_bps: u16 },
+    SetStakeRequirements { stake_threshold: u64, min_stake: u64 },
+    SetCrankBounty { bounty: u64 },
+    SetChargebackWindow { window: i64 },
+    SetArbitrationTerms { min_stake: u64, fee: u64 },
+    SetAppealBond { bond: u64 },
+    SetKycThreshold { threshold: u64 },
+    SetReferralShare { share_bps: u16 },
+    SetPenaltyBurnShare { share_bps: u16 },
+    SetCompletionRewards { reward: u64, half_life: u64, emission_cap: u64 },
+    SetShippingRates { base_rate: u64, rate_per_km: u64, rate_per_kg: u64 },
+    WithdrawTreasury { mint: Pubkey, destination: Pubkey, amount: u64 },
+    RegisterVault { mint: Pubkey },
+    MigrateEscrow { entity_type: EntityType, entity: Pubkey, legacy_escrow: Pubkey },
+}
+
+impl GovernanceAction {
+    /// Applies the update to the config on behalf of its governance key. The actions beyond
+    /// the config fail here, as the processor executes them with their accounts.
+    pub fn apply(&self, config: &mut Config) -> Result<(), DLUError> {
+        let governance = config.governance;
+        match *self {
+            GovernanceAction::RaiseTvlCap { new_cap } => config.raise_tvl_cap(&governance, new_cap),
+            GovernanceAction::SetCapabilities { capabilities } => config.set_capabilities(&governance, capabilities),
+            GovernanceAction::PublishTerms { terms_hash } => config.publish_terms(&governance, terms_hash),
+            GovernanceAction::SetReputationHalfLife { half_life } => config.set_reputation_half_life(&governance, half_life),
+            GovernanceAction::SetFailBond { amount } => config.set_fail_bond(&governance, amount),
+            GovernanceAction::SetFee { fee_bps, exempt_credible } => config.set_fee(&governance, fee_bps, exempt_credible),
+            GovernanceAction::SetPenaltyShare { share_bps } => config.set_penalty_share(&governance, share_bps),
+            GovernanceAction::SetStakeRequirements { stake_threshold, min_stake } => {
+                config.set_stake_requirements(&governance, stake_threshold, min_stake)
+            }
+            GovernanceAction::SetCrankBounty { bounty } => config.set_crank_bounty(&governance, bounty),
+            GovernanceAction::SetChargebackWindow { window } => config.set_chargeback_window(&governance, window),
+            GovernanceAction::SetArbitrationTerms { min_stake, fee } => config.set_arbitration_terms(&governance, min_stake, fee),
+            GovernanceAction::SetAppealBond { bond } => config.set_appeal_bond(&governance, bond),
+            GovernanceAction::SetKycThreshold { threshold } => config.set_kyc_threshold(&governance, threshold),
+            GovernanceAction::SetReferralShare { share_bps } => config.set_referral_share(&governance, share_bps),
+            GovernanceAction::SetPenaltyBurnShare { share_bps } => config.set_penalty_burn_share(&governance, share_bps),
+            GovernanceAction::SetCompletionRewards { reward, half_life, emission_cap } => {
+                config.set_completion_rewards(&governance, reward, half_life, emission_cap)
+            }
+            GovernanceAction::SetShippingRates { base_rate, rate_per_km, rate_per_kg } => {
+                config.set_shipping_rates(&governance, ShippingRates { base_rate, rate_per_km, rate_per_kg })
+            }
+            GovernanceAction::WithdrawTreasury { .. }
+            | GovernanceAction::RegisterVault { .. }
+            | GovernanceAction::MigrateEscrow { .. } => Err(DLUError::InvalidOperation),
+        }
+    }
+}
+
+/// A governance action waiting in the queue until `executable_at`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct QueuedAction {
+    pub id: u64,
+    pub action: GovernanceAction,
+    pub executable_at: i64,
+}
+
+/// The actions governance queued, kept at the PDA `["timelock"]`. Once the config enables
+/// `CAP_TIMELOCKED_GOVERNANCE` they are the only way to update it or move governed funds.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct TimelockQueue {
+    pub next_id: u64,
+    pub queued: Vec<QueuedAction>,
+}
+
+impl TimelockQueue {
+    /// Serializes the TimelockQueue into a vector of bytes.
+    pub fn serialize(&self) -> Result<Vec<u8>, DLUError> {
+        self.try_to_vec().map_err(|_| DLUError::SerializationFailed)
+    }
+
+    /// Deserializes a TimelockQueue from a slice of bytes.
+    pub fn deserialize(input: &mut &[u8]) -> Result<Self, DLUError> {
+        // A fresh TimelockQueue account is all zeros, which reads as an empty queue.
+        <Self as BorshDeserialize>::deserialize(input).map_err(|_| DLUError::DeserializationFailed)
+    }
+
+    /// Queues an action at `now`, returning its ID and when it can be executed.
+    pub fn queue(&mut self, action: GovernanceAction, now: i64) -> Result<(u64, i64), DLUError> {
+        if self.queued.len() >= MAX_QUEUED_ACTIONS {
+            return Err(DLUError::TimelockQueueFull);
+        }
+        let id = self.next_id;
+        let executable_at = now.saturating_add(GOVERNANCE_TIMELOCK);
+        self.next_id += 1;
+        self.queued.push(QueuedAction { id, action, executable_at });
+        Ok((id, executable_at))
+    }
+
+    /// Removes a queued action without executing it.
+    pub fn cancel(&mut self, id: u64) -> Result<GovernanceAction, DLUError> {
+        let index = self.index_of(id)?;
+        Ok(self.queued.remove(index).action)
+    }
+
+    /// Removes a queued action whose timelock has passed at `now`, for the caller to apply.
+    pub fn take_executable(&mut self, id: u64, now: i64) -> Result<GovernanceAction, DLUError> {
+        let index = self.index_of(id)?;
+        if now < self.queued[index].executable_at {
+            return Err(DLUError::ActionTimelocked);
+        }
+        Ok(self.queued.remove(index).action)
+    }
+
+    fn index_of(&self, id: u64) -> Result<usize, DLUError> {
+        self.queued.iter().position(|queued| queued.id == id).ok_or(DLUError::ActionNotQueued)
+    }
+}
@@ -1,9 +1,34 @@
 use solana_program::pubkey::Pubkey;
 use crate::DLU_wallet::DLUWallet;
 use solana_program::borsh::{BorshSerialize, BorshDeserialize};
+use crate::migrations;
+use crate::safe_math;
+use crate::time;
+
+/// Weight of a single operation in the decayed reputation counters.
+pub const REPUTATION_SCALE: u64 = 1_000_000;
+
+// Longest profile fields a user can set, in bytes.
+pub const MAX_DISPLAY_NAME_LEN: usize = 32;
+pub const MAX_METADATA_URI_LEN: usize = 128;  // Fits an IPFS URI with a CIDv1.
+pub const MAX_CONTACT_HINTS_LEN: usize = 64;
+
+/// Reviews a user needs before their average rating affects their status.
+pub const MIN_RATINGS_FOR_STATUS: u32 = 3;
+
+/// Average rating, in hundredths of a star, below which a user can't be Reliable or Credible.
+pub const POOR_RATING: u16 = 250;
+
+/// DLU the faucet mints per draw on test clusters.
+pub const FAUCET_AMOUNT: u64 = 100_000_000;
+
+// Flags a user can carry, combined as bit flags.
+pub const USER_MULTISIG: u8 = 1 << 0;  // High-value actions need the cosigners of the user's Multisig PDA.
+pub const USER_BANNED: u8 = 1 << 1;    // Barred by moderation from new listings and acceptances.
 
 
 /// Represents the status of a user based on their performance in deals and shipments.
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum UserStatus {
     New,
     Credible,
@@ -14,11 +39,65 @@ pub enum UserStatus {
     Fraud,
 }
 
+/// Role a user plays in a deal or shipment.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, PartialEq, Debug)]
+pub enum Role {
+    Seller,
+    Buyer,
+    Sender,
+    Carrier,
+}
+
+/// Number of roles, and so of `RoleReputation` entries kept per user.
+pub const ROLE_COUNT: usize = 4;
+
+/// Reputation of a user in a single role, so a perfect seller who once failed as a carrier
+/// still looks reliable to buyers.
+#[derive(Clone, Debug)]
+pub struct RoleReputation {
+    pub total: u32,
+    pub successful: u32,
+    pub decayed_successes: u64,  // Weighted like the user's overall decayed counters.
+    pub decayed_failures: u64,
+    pub status: UserStatus,
+}
+
+impl RoleReputation {
+    /// Creates an empty reputation.
+    pub fn new() -> Self {
+        RoleReputation {
+            total: 0,
+            successful: 0,
+            decayed_successes: 0,
+            decayed_failures: 0,
+            status: UserStatus::New,
+        }
+    }
+
+    fn decay(&mut self, elapsed: u64, half_life: u64) {
+        self.decayed_successes = safe_math::decay(self.decayed_successes, elapsed, half_life);
+        self.decayed_failures = safe_math::decay(self.decayed_failures, elapsed, half_life);
+    }
+
+    fn record(&mut self, successful: bool) {
+        self.total = self.total.saturating_add(1);
+        if successful {
+            self.successful = self.successful.saturating_add(1);
+            self.decayed_successes = self.decayed_successes.saturating_add(REPUTATION_SCALE);
+        } else {
+            self.decayed_failures = self.decayed_failures.saturating_add(REPUTATION_SCALE);
+        }
+        self.status = status_for(self.total, self.decayed_successes, self.decayed_failures);
+    }
+}
+
 /// Represents a user in the system, tracking their details, wallet, and performance metrics.
 pub struct User {
     pub username: String,
     pub pubkey: Pubkey,
     pub wallet: DLUWallet,
+    pub flags: u8,  // `USER_*` flags.
+    pub referrer: Option<Pubkey>,  // User who referred this one and earns a share of their fees.
     pub status: UserStatus,
     pub total_deals: u32,
     pub successful_deals: u32,
@@ -26,15 +105,32 @@ pub struct User {
     pub total_shipments: u32,
     pub successful_shipments: u32,
     pub failed_shipments: u32,
+    pub accepted_terms_version: u32,  // Version of the terms of use the user last accepted.
+    pub goodwill_refunds_issued: u32,    // Refunds given to buyers after completed deals.
+    pub goodwill_refunds_received: u32,  // Refunds received from sellers after completed deals.
+    pub decayed_successes: u64,  // Successful operations, each weighing REPUTATION_SCALE when it happened.
+    pub decayed_failures: u64,   // Failed operations, weighted like `decayed_successes`.
+    pub reputation_slot: u64,    // Slot the decayed counters were last brought up to date.
+    pub roles: [RoleReputation; ROLE_COUNT],  // Reputation per role, indexed by `Role`.
+    pub rating_sum: u64,    // Sum of the stars of all reviews received.
+    pub rating_count: u32,  // Number of reviews received.
+    pub display_name: String,
+    pub metadata_uri: String,   // Off-chain metadata such as avatar and bio, usually an IPFS URI.
+    pub contact_hints: String,  // How counterparties can reach the user, e.g. a chat handle.
+    pub completed_volume: [u64; ROLE_COUNT],  // Payments of successful deals and shipments, per role.
+    pub success_streaks: [u32; ROLE_COUNT],   // Successes in a row per role, reset by a failure.
+    pub last_faucet_at: i64,  // When the user last drew DLU from the faucet of a test cluster.
 }
 
 impl User {
     /// Creates a new user with initial values.
-    pub fn new(username: String, pubkey: Pubkey, wallet: DLUWallet) -> Self {
+    pub fn new(username: String, pubkey: Pubkey, wallet: DLUWallet, accepted_terms_version: u32) -> Self {
         User {
             username,
             pubkey,
             wallet,
+            flags: 0,
+            referrer: None,
             status: UserStatus::New,
             total_deals: 0,
             successful_deals: 0,
@@ -42,63 +138,235 @@ impl User {
             total_shipments: 0,
             successful_shipments: 0,
             failed_shipments: 0,
+            accepted_terms_version,
+            goodwill_refunds_issued: 0,
+            goodwill_refunds_received: 0,
+            decayed_successes: 0,
+            decayed_failures: 0,
+            reputation_slot: 0,
+            roles: [RoleReputation::new(), RoleReputation::new(), RoleReputation::new(), RoleReputation::new()],
+            rating_sum: 0,
+            rating_count: 0,
+            display_name: String::new(),
+            metadata_uri: String::new(),
+            contact_hints: String::new(),
+            completed_volume: [0; ROLE_COUNT],
+            success_streaks: [0; ROLE_COUNT],
+            last_faucet_at: 0,
+        }
+    }
+
+    /// Returns true if the user carries the `USER_*` flag.
+    pub fn has_flag(&self, flag: u8) -> bool {
+        self.flags & flag == flag
+    }
+
+    /// Raises or clears a `USER_*` flag.
+    pub fn set_flag(&mut self, flag: u8, on: bool) {
+        if on {
+            self.flags |= flag;
+        } else {
+            self.flags &= !flag;
         }
     }
 
-    /// Increments the deal counters based on the outcome.
-    pub fn mark_deal(&mut self, successful: bool) {
-        self.total_deals += 1;
+    /// Records who referred the user; nobody can refer themselves.
+    pub fn set_referrer(&mut self, referrer: Pubkey) -> Result<(), &'static str> {
+        if referrer == self.pubkey {
+            return Err("Invalid referrer");
+        }
+        self.referrer = Some(referrer);
+        Ok(())
+    }
+
+    /// Records a draw from the faucet at `now`, once `FAUCET_COOLDOWN` has passed since the last.
+    pub fn draw_faucet(&mut self, now: i64) -> Result<(), &'static str> {
+        if self.last_faucet_at != 0 && now < self.last_faucet_at.saturating_add(time::FAUCET_COOLDOWN) {
+            return Err("Faucet cooling down");
+        }
+        self.last_faucet_at = now;
+        Ok(())
+    }
+
+    /// Fades the decayed counters by the slots elapsed since they were last updated.
+    /// Must be called before recording new operations so they enter at full weight.
+    pub fn decay_reputation(&mut self, slot: u64, half_life: u64) {
+        let elapsed = slot.saturating_sub(self.reputation_slot);
+        self.decayed_successes = safe_math::decay(self.decayed_successes, elapsed, half_life);
+        self.decayed_failures = safe_math::decay(self.decayed_failures, elapsed, half_life);
+        for role in self.roles.iter_mut() {
+            role.decay(elapsed, half_life);
+        }
+        self.reputation_slot = self.reputation_slot.max(slot);
+    }
+
+    /// Increments the deal counters of the user and of their role based on the outcome.
+    pub fn mark_deal(&mut self, role: Role, successful: bool) {
+        self.total_deals = self.total_deals.saturating_add(1);
         if successful {
-            self.successful_deals += 1;
+            self.successful_deals = self.successful_deals.saturating_add(1);
         } else {
-            self.failed_deals += 1;
+            self.failed_deals = self.failed_deals.saturating_add(1);
         }
+        self.record_outcome(role, successful);
+    }
+
+    /// Increments the shipment counters of the user and of their role based on the outcome.
+    pub fn mark_shipment(&mut self, role: Role, successful: bool) {
+        self.total_shipments = self.total_shipments.saturating_add(1);
+        if successful {
+            self.successful_shipments = self.successful_shipments.saturating_add(1);
+        } else {
+            self.failed_shipments = self.failed_shipments.saturating_add(1);
+        }
+        self.record_outcome(role, successful);
+    }
+
+    /// Records a goodwill refund given to a buyer, which counts towards the user's reputation
+    /// as a seller.
+    pub fn record_goodwill_refund_issued(&mut self) {
+        self.goodwill_refunds_issued = self.goodwill_refunds_issued.saturating_add(1);
+        self.record_outcome(Role::Seller, true);
+    }
+
+    /// Records a goodwill refund received from a seller.
+    pub fn record_goodwill_refund_received(&mut self) {
+        self.goodwill_refunds_received = self.goodwill_refunds_received.saturating_add(1);
+    }
+
+    /// Replaces the given profile fields, leaving the others untouched.
+    pub fn update_profile(
+        &mut self,
+        display_name: Option<String>,
+        metadata_uri: Option<String>,
+        contact_hints: Option<String>,
+    ) -> Result<(), &'static str> {
+        // Check every field before changing any of them.
+        let too_long = |field: &Option<String>, max: usize| field.as_ref().map_or(false, |value| value.len() > max);
+        if too_long(&display_name, MAX_DISPLAY_NAME_LEN)
+            || too_long(&metadata_uri, MAX_METADATA_URI_LEN)
+            || too_long(&contact_hints, MAX_CONTACT_HINTS_LEN)
+        {
+            return Err("Profile field too long");
+        }
+
+        if let Some(display_name) = display_name {
+            self.display_name = display_name;
+        }
+        if let Some(metadata_uri) = metadata_uri {
+            self.metadata_uri = metadata_uri;
+        }
+        if let Some(contact_hints) = contact_hints {
+            self.contact_hints = contact_hints;
+        }
+        Ok(())
+    }
+
+    /// Records a rating from a review and updates the status.
+    pub fn record_rating(&mut self, rating: u8) {
+        self.rating_sum = self.rating_sum.saturating_add(rating as u64);
+        self.rating_count = self.rating_count.saturating_add(1);
         self.update_status();
     }
 
-    /// Increments the shipment counters based on the outcome.
-    pub fn mark_shipment(&mut self, successful: bool) {
-        self.total_shipments += 1;
+    /// Returns the average rating received, in hundredths of a star, or 0 without reviews.
+    pub fn average_rating(&self) -> u16 {
+        if self.rating_count == 0 {
+            return 0;
+        }
+        (self.rating_sum.saturating_mul(100) / self.rating_count as u64) as u16
+    }
+
+    /// Returns the user's reputation in the given role.
+    pub fn role(&self, role: Role) -> &RoleReputation {
+        &self.roles[role as usize]
+    }
+
+    /// Returns the user's status in the given role.
+    pub fn role_status(&self, role: Role) -> UserStatus {
+        self.role(role).status
+    }
+
+    /// Adds the payment of a successful deal or shipment to the user's volume in the role.
+    pub fn record_volume(&mut self, role: Role, payment: u64) {
+        let volume = &mut self.completed_volume[role as usize];
+        *volume = volume.saturating_add(payment);
+    }
+
+    /// Returns the payments of the deals or shipments the user completed in the given role.
+    pub fn completed_volume(&self, role: Role) -> u64 {
+        self.completed_volume[role as usize]
+    }
+
+    /// Returns how many operations in a row the user completed in the given role.
+    pub fn success_streak(&self, role: Role) -> u32 {
+        self.success_streaks[role as usize]
+    }
+
+    /// Adds an operation at full weight to the decayed counters and updates the statuses.
+    fn record_outcome(&mut self, role: Role, successful: bool) {
         if successful {
-            self.successful_shipments += 1;
+            self.decayed_successes = self.decayed_successes.saturating_add(REPUTATION_SCALE);
         } else {
-            self.failed_shipments += 1;
+            self.decayed_failures = self.decayed_failures.saturating_add(REPUTATION_SCALE);
         }
+        self.roles[role as usize].record(successful);
+        let streak = &mut self.success_streaks[role as usize];
+        *streak = if successful { streak.saturating_add(1) } else { 0 };
         self.update_status();
     }
 
-    /// Updates the status of a user based on the success rate of their deals and shipments.
+    /// Updates the overall status of a user based on the success rate of all their deals
+    /// and shipments. Each goodwill refund issued counts as an additional successful operation.
+    ///
+    /// Users rated poorly by enough counterparties are capped at Risky however many deals
+    /// they completed.
     pub fn update_status(&mut self) {
-        let total_operations = self.total_deals + self.total_shipments;
-        let successful_operations = self.successful_deals + self.successful_shipments;
+        let total_operations = self.total_deals + self.total_shipments + self.goodwill_refunds_issued;
+        let status = status_for(total_operations, self.decayed_successes, self.decayed_failures);
 
-        if total_operations < 3 {
-            self.status = UserStatus::New;
-        } else if total_operations > 10 && successful_operations == total_operations {
-            self.status = UserStatus::Credible;
-        } else {
-            let success_rate = successful_operations as f32 / total_operations as f32;
-            self.status = if success_rate >= 0.9 {
-                UserStatus::Reliable
-            } else if success_rate >= 0.7 {
-                UserStatus::Risky
-            } else if success_rate >= 0.5 {
-                UserStatus::Unreliable
-            } else if success_rate >= 0.2 {
-                UserStatus::Suspicious
-            } else {
-                UserStatus::Fraud
-            };
-        }
-    }
-
-    /// Serializes the user into a vector of bytes.
+        let poorly_rated = self.rating_count >= MIN_RATINGS_FOR_STATUS && self.average_rating() < POOR_RATING;
+        self.status = match status {
+            UserStatus::Credible | UserStatus::Reliable if poorly_rated => UserStatus::Risky,
+            status => status,
+        };
+    }
+
+    /// Serializes the user into a vector of bytes, behind its layout version.
     pub fn serialize(&self) -> Result<Vec<u8>, &'static str> {
-        self.try_to_vec().map_err(|_| "Failed to serialize User")
+        migrations::pack(self).map_err(|_| "Failed to serialize User")
     }
 
     /// Deserializes a user from a slice of bytes.
     pub fn deserialize(input: &mut &[u8]) -> Result<Self, &'static str> {
-        Self::try_from_slice(input).map_err(|_| "Failed to deserialize User")
+        migrations::unpack(input).map_err(|_| "Failed to deserialize User")
+    }
+}
+
+/// Derives a status from a number of operations and their decayed outcomes.
+///
+/// The success rate is taken from the decayed counters, so old failures fade; the lifetime
+/// total only decides when a user stops being New or can become Credible.
+fn status_for(total_operations: u32, decayed_successes: u64, decayed_failures: u64) -> UserStatus {
+    let decayed_total = decayed_successes.saturating_add(decayed_failures);
+
+    if total_operations < 3 || decayed_total == 0 {
+        UserStatus::New
+    } else if total_operations > 10 && decayed_failures < REPUTATION_SCALE / 2 {
+        // Less than half a failure's weight left: any failure is long in the past.
+        UserStatus::Credible
+    } else {
+        let success_rate = decayed_successes as f64 / decayed_total as f64;
+        if success_rate >= 0.9 {
+            UserStatus::Reliable
+        } else if success_rate >= 0.7 {
+            UserStatus::Risky
+        } else if success_rate >= 0.5 {
+            UserStatus::Unreliable
+        } else if success_rate >= 0.2 {
+            UserStatus::Suspicious
+        } else {
+            UserStatus::Fraud
+        }
     }
 }
@@ -0,0 +1,62 @@
+use crate::errors::DLUError;
+use crate::location::Location;
+
+/// Longest username a user can register with, in bytes.
+pub const MAX_USERNAME_LEN: usize = 32;
+/// Longest name of the goods, service or items a listing can hold, in bytes.
+pub const MAX_ITEM_NAME_LEN: usize = 64;
+/// Longest description a listing can hold, in bytes.
+pub const MAX_DESCRIPTION_LEN: usize = 1_024;
+
+// Handlers check the free-form text of their instruction before storing any of it. Strings
+// arrive already checked to be valid UTF-8 by Borsh, so what is left is their length, which
+// bounds both the account space and the compute spent copying them, and their characters:
+// control characters have no place in a listing and only garble the clients displaying it.
+
+/// Checks a username: ASCII letters, digits, `_`, `-` and `.`, since usernames are shown
+/// and searched for verbatim.
+pub fn username(username: &str) -> Result<(), DLUError> {
+    check_length(username, MAX_USERNAME_LEN, false)?;
+    if !username.bytes().all(|c| c.is_ascii_alphanumeric() || matches!(c, b'_' | b'-' | b'.')) {
+        return Err(DLUError::InvalidCharacters);
+    }
+    Ok(())
+}
+
+/// Checks the name of the goods, service or items listed.
+pub fn item_name(name: &str) -> Result<(), DLUError> {
+    check_length(name, MAX_ITEM_NAME_LEN, false)?;
+    check_characters(name, false)
+}
+
+/// Checks a listing's description, which may be empty and span several lines.
+pub fn description(description: &str) -> Result<(), DLUError> {
+    check_length(description, MAX_DESCRIPTION_LEN, true)?;
+    check_characters(description, true)
+}
+
+/// Checks a meeting, pickup or drop-off point: its country code and bounds, then the
+/// characters of its town and address.
+pub fn location(location: &Location) -> Result<(), DLUError> {
+    location.validate()?;
+    check_characters(location.town(), false)?;
+    check_characters(location.address(), false)
+}
+
+fn check_length(value: &str, max: usize, may_be_empty: bool) -> Result<(), DLUError> {
+    if value.is_empty() && !may_be_empty {
+        return Err(DLUError::EmptyField);
+    }
+    if value.len() > max {
+        return Err(DLUError::FieldTooLong);
+    }
+    Ok(())
+}
+
+fn check_characters(value: &str, multiline: bool) -> Result<(), DLUError> {
+    let allowed = |c: char| !c.is_control() || (multiline && matches!(c, '\n' | '\t'));
+    if !value.chars().all(allowed) {
+        return Err(DLUError::InvalidCharacters);
+    }
+    Ok(())
+}
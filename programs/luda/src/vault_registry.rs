@@ -0,0 +1,63 @@
+use solana_program::pubkey::Pubkey;
+use solana_program::borsh::{BorshSerialize, BorshDeserialize};
+use crate::errors::DLUError;
+
+/// Most payment mints the registry holds a vault for.
+pub const MAX_REGISTERED_VAULTS: usize = 16;
+
+/// Account size of the VaultRegistry.
+pub const VAULT_REGISTRY_SIZE: usize = 4 + MAX_REGISTERED_VAULTS * (32 + 32 + 8);
+
+/// The canonical escrow vault of a payment mint.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct RegisteredVault {
+    pub mint: Pubkey,
+    pub vault: Pubkey,  // The PDA `["vault", mint]`, a token account that is its own authority.
+    pub registered_at: i64,
+}
+
+/// The escrow vaults governance created with `RegisterVault`, kept at the PDA
+/// `["vault_registry"]`. Only the program can create an account at a vault's address, so
+/// every vault in use is one listed here, and escrow can only move into a registered one.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct VaultRegistry {
+    pub vaults: Vec<RegisteredVault>,
+}
+
+impl VaultRegistry {
+    /// Serializes the VaultRegistry into a vector of bytes.
+    pub fn serialize(&self) -> Result<Vec<u8>, DLUError> {
+        self.try_to_vec().map_err(|_| DLUError::SerializationFailed)
+    }
+
+    /// Deserializes a VaultRegistry from a slice of bytes.
+    pub fn deserialize(input: &mut &[u8]) -> Result<Self, DLUError> {
+        // A fresh VaultRegistry account is all zeros, which reads as an empty registry.
+        <Self as BorshDeserialize>::deserialize(input).map_err(|_| DLUError::DeserializationFailed)
+    }
+
+    /// Registers the vault of a mint at `now`.
+    pub fn register(&mut self, mint: Pubkey, vault: Pubkey, now: i64) -> Result<(), DLUError> {
+        if self.vault_of(&mint).is_some() {
+            return Err(DLUError::VaultAlreadyRegistered);
+        }
+        if self.vaults.len() >= MAX_REGISTERED_VAULTS {
+            return Err(DLUError::VaultRegistryFull);
+        }
+        self.vaults.push(RegisteredVault { mint, vault, registered_at: now });
+        Ok(())
+    }
+
+    /// Returns the registered vault of a mint.
+    pub fn vault_of(&self, mint: &Pubkey) -> Option<Pubkey> {
+        self.vaults.iter().find(|registered| registered.mint == *mint).map(|registered| registered.vault)
+    }
+
+    /// Checks that `vault` is the registered vault of `mint`.
+    pub fn check_registered(&self, mint: &Pubkey, vault: &Pubkey) -> Result<(), DLUError> {
+        match self.vault_of(mint) {
+            Some(registered) if registered == *vault => Ok(()),
+            _ => Err(DLUError::VaultNotRegistered),
+        }
+    }
+}
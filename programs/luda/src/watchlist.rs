@@ -0,0 +1,62 @@
+use solana_program::pubkey::Pubkey;
+use solana_program::borsh::{BorshSerialize, BorshDeserialize};
+use crate::errors::DLUError;
+
+/// Most users a watchlist can follow.
+pub const MAX_FOLLOWED_USERS: usize = 32;
+/// Most offers a watchlist can watch.
+pub const MAX_WATCHED_OFFERS: usize = 32;
+
+/// Users a wallet follows and offers it watches.
+///
+/// Clients build notification feeds by filtering the program's events by the watchlist:
+/// listings of followed users, and the `OfferStatusChanged` events every offer emits when
+/// its status changes. Following and watching emit events of their own, so indexers can
+/// count followers and watchers.
+///
+/// Lives at the PDA `["watchlist", owner_pubkey]`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default)]
+pub struct Watchlist {
+    pub followed: Vec<Pubkey>,
+    pub watched: Vec<Pubkey>,
+}
+
+impl Watchlist {
+    /// Follows `user` for `owner`, or unfollows them.
+    pub fn follow(&mut self, owner: &Pubkey, user: Pubkey, follow: bool) -> Result<(), DLUError> {
+        if *owner == user {
+            return Err(DLUError::InvalidOperation);
+        }
+        toggle(&mut self.followed, user, follow, MAX_FOLLOWED_USERS)
+    }
+
+    /// Watches `offer`, or stops watching it.
+    pub fn watch(&mut self, offer: Pubkey, watch: bool) -> Result<(), DLUError> {
+        toggle(&mut self.watched, offer, watch, MAX_WATCHED_OFFERS)
+    }
+
+    /// Serializes the watchlist into a vector of bytes.
+    pub fn serialize(&self) -> Result<Vec<u8>, DLUError> {
+        self.try_to_vec().map_err(|_| DLUError::SerializationFailed)
+    }
+
+    /// Deserializes a watchlist from a slice of bytes.
+    pub fn deserialize(input: &mut &[u8]) -> Result<Self, DLUError> {
+        // Accounts are sized for full lists, so the tail may be zero padding.
+        <Self as BorshDeserialize>::deserialize(input).map_err(|_| DLUError::DeserializationFailed)
+    }
+}
+
+/// Adds `key` to `list` when `add`, up to `max` keys, and removes it otherwise.
+fn toggle(list: &mut Vec<Pubkey>, key: Pubkey, add: bool, max: usize) -> Result<(), DLUError> {
+    let present = list.contains(&key);
+    if !add {
+        list.retain(|listed| *listed != key);
+    } else if !present {
+        if list.len() >= max {
+            return Err(DLUError::WatchlistFull);
+        }
+        list.push(key);
+    }
+    Ok(())
+}
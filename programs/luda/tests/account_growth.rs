@@ -0,0 +1,124 @@
+mod common;
+
+use common::{DealKind, Harness};
+use luda::errors::DLUError;
+use luda::instruction::DLUInstruction;
+use luda::discriminator::AccountKind;
+use luda::migrations::{account_space, decode_account, frame, ACCOUNT_HEADER_LEN, DISCRIMINATOR_LEN};
+use solana_program::instruction::{AccountMeta, InstructionError};
+use solana_program::rent::Rent;
+use solana_sdk::account::Account;
+use solana_sdk::signature::Signer;
+use solana_sdk::transaction::TransactionError;
+
+#[tokio::test]
+async fn accepting_grows_a_tight_offer_account_within_its_rent() {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    let (offer, _) = h.list_deal(DealKind::Offer, &seller, &buyer).await;
+
+    // Shrink the offer account to exactly its listed data behind the header.
+    let listed_len = account_space(h.offer(offer).await.serialize().unwrap().len());
+    let data = h.account(offer).await.data[..listed_len].to_vec();
+    h.set_account(offer, h.program_id, data.clone());
+
+    let instruction = h.accept_deal_instruction(DealKind::Offer, offer, &seller, &buyer);
+    let error = h.try_process_all(&[instruction], &[&buyer.owner]).await.unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(0, InstructionError::Custom(DLUError::GrowthNotFunded.code()))
+    );
+
+    // Topped up with rent for the buyer's side, the account grows to fit it.
+    let lamports = Rent::default().minimum_balance(2 * listed_len);
+    let account = Account { lamports, data, owner: h.program_id, executable: false, rent_epoch: 0 };
+    h.context.set_account(&offer, &account.into());
+    h.accept_deal(DealKind::Offer, offer, &seller, &buyer).await;
+
+    let grown = h.account(offer).await.data.len();
+    assert!(grown > listed_len);
+    assert_eq!(account_space(h.offer(offer).await.serialize().unwrap().len()), grown);
+}
+
+#[tokio::test]
+async fn shorter_data_leaves_no_stale_bytes_behind() {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    let (offer, _) = h.list_described_deal(DealKind::Offer, &seller, &buyer, &"Long description. ".repeat(20)).await;
+
+    let update = DLUInstruction::UpdateOffer {
+        goodsorservice_description: Some("Short".to_string()),
+        meeting_point: None,
+        meeting_datetime: None,
+        payment: None,
+    };
+    let accounts = vec![
+        AccountMeta::new(offer, false),
+        AccountMeta::new_readonly(seller.user, false),
+        AccountMeta::new_readonly(seller.owner.pubkey(), true),
+        AccountMeta::new(seller.token, false),
+        AccountMeta::new(h.escrow, false),
+        AccountMeta::new_readonly(h.vault_registry(), false),
+        AccountMeta::new_readonly(h.config(), false),
+        AccountMeta::new(h.stats(), false),
+    ];
+    h.process(h.instruction(&update, accounts), &[&seller.owner]).await;
+
+    let len = h.offer(offer).await.serialize().unwrap().len();
+    let data = h.account(offer).await.data;
+    assert_eq!(data[0], AccountKind::Offer as u8);
+    assert_eq!(data[DISCRIMINATOR_LEN..ACCOUNT_HEADER_LEN], (len as u32).to_le_bytes());
+    assert!(data[account_space(len)..].iter().all(|byte| *byte == 0));
+}
+
+#[test]
+fn loaders_read_exactly_the_bytes_the_prefix_counts() {
+    // A payload ending in zero bytes is told apart from the zeroed space after it.
+    let mut data = frame(AccountKind::Review, &[7, 0, 0]).unwrap();
+    data.resize(64, 0);
+    let take_all = |input: &mut &[u8]| -> Result<Vec<u8>, DLUError> { Ok(std::mem::take(input).to_vec()) };
+    assert_eq!(decode_account(&data, AccountKind::Review, take_all), Ok(vec![7, 0, 0]));
+
+    // Leaving part of the payload unread fails, as does a prefix counting past the account.
+    let take_one = |input: &mut &[u8]| -> Result<u8, DLUError> {
+        let (&first, rest) = input.split_first().ok_or(DLUError::DeserializationFailed)?;
+        *input = rest;
+        Ok(first)
+    };
+    assert_eq!(decode_account(&data, AccountKind::Review, take_one), Err(DLUError::DeserializationFailed));
+    assert_eq!(
+        decode_account(&data[..ACCOUNT_HEADER_LEN + 1], AccountKind::Review, take_all),
+        Err(DLUError::DeserializationFailed)
+    );
+}
+
+#[test]
+fn loaders_reject_accounts_of_another_kind() {
+    let data = frame(AccountKind::Review, &[7]).unwrap();
+    let take_all = |input: &mut &[u8]| -> Result<Vec<u8>, DLUError> { Ok(std::mem::take(input).to_vec()) };
+    assert_eq!(decode_account(&data, AccountKind::Watchlist, take_all), Err(DLUError::AccountKindMismatch));
+
+    // An account never written reads from its zeroed space, whichever kind it is loaded as.
+    assert_eq!(decode_account(&[0; 8], AccountKind::Watchlist, take_all), Ok(vec![0; 3]));
+}
+
+#[tokio::test]
+async fn listing_into_a_written_account_fails() {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    let (offer, meeting_datetime) = h.list_deal(DealKind::Offer, &seller, &buyer).await;
+
+    // Neither another user's account nor a listed deal can be overwritten by a new listing.
+    for account in [buyer.user, offer] {
+        h.next_blockhash().await;
+        let instruction = h.list_deal_instruction(DealKind::Offer, account, &seller, "Overwrite", None, meeting_datetime);
+        let error = h.try_process_all(&[instruction], &[&seller.owner]).await.unwrap_err();
+        assert_eq!(
+            error,
+            TransactionError::InstructionError(0, InstructionError::Custom(DLUError::AccountInUse.code()))
+        );
+    }
+}
@@ -0,0 +1,177 @@
+mod common;
+
+use common::{Harness, Party, PAYMENT, STARTING_BALANCE};
+use luda::addressing::derive_appeal_address;
+use luda::appeal::{Appeal, AppealStatus};
+use luda::arbiter::{Arbiter, DisputeKind};
+use luda::constants::{ARBITRATION_TIMEOUT, DEFAULT_APPEAL_BOND, DEFAULT_ARBITER_MIN_STAKE};
+use luda::instruction::DLUInstruction;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_program::system_program;
+use solana_program::sysvar;
+use solana_sdk::signature::Signer;
+
+const KIND: DisputeKind = DisputeKind::Chargeback;
+const BOND: u64 = DEFAULT_APPEAL_BOND;
+const STAKE: u64 = DEFAULT_ARBITER_MIN_STAKE;
+
+struct Dispute {
+    seller: Party,
+    buyer: Party,
+    arbiters: Vec<Party>,
+    offer: Pubkey,
+    judge: usize,  // Index of the arbiter who ruled.
+}
+
+impl Dispute {
+    fn arbiter(&self, key: &Pubkey) -> &Party {
+        self.arbiters.iter().find(|party| party.owner.pubkey() == *key).unwrap()
+    }
+}
+
+/// Charges an offer back before four registered arbiters and has the drawn one rule for
+/// the seller.
+async fn ruled_for_the_seller(h: &mut Harness) -> Dispute {
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    let mut arbiters = Vec::new();
+    for name in ["alice", "bob", "carol", "dave"] {
+        let arbiter = h.create_user(name).await;
+        h.register_arbiter(&arbiter).await;
+        arbiters.push(arbiter);
+    }
+    let offer = h.charged_back_offer(&seller, &buyer).await;
+    let drawn = h.assign_arbiter(offer).await.arbiter;
+    let judge = arbiters.iter().position(|party| party.owner.pubkey() == drawn).unwrap();
+    h.rule_dispute(offer, &arbiters[judge], false).await;
+    Dispute { seller, buyer, arbiters, offer, judge }
+}
+
+fn appeal_address(h: &Harness, offer: Pubkey) -> Pubkey {
+    derive_appeal_address(&h.program_id, &h.arbitration(offer)).0
+}
+
+fn appeal_instruction(h: &Harness, offer: Pubkey, appellant: &Party) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(offer, false),
+        AccountMeta::new(h.arbitration(offer), false),
+        AccountMeta::new(appeal_address(h, offer), false),
+        AccountMeta::new_readonly(h.arbiter_registry(), false),
+        AccountMeta::new_readonly(sysvar::slot_hashes::id(), false),
+        AccountMeta::new_readonly(appellant.owner.pubkey(), true),
+        AccountMeta::new(appellant.token, false),
+        AccountMeta::new(h.escrow, false),
+        AccountMeta::new_readonly(h.vault_registry(), false),
+        AccountMeta::new_readonly(h.config(), false),
+        AccountMeta::new(h.stats(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new(h.context.payer.pubkey(), true),
+    ];
+    h.instruction(&DLUInstruction::AppealRuling { kind: KIND }, accounts)
+}
+
+fn vote_instruction(h: &Harness, offer: Pubkey, panelist: &Party, for_buyer: bool) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(offer, false),
+        AccountMeta::new_readonly(h.arbitration(offer), false),
+        AccountMeta::new(appeal_address(h, offer), false),
+        AccountMeta::new_readonly(panelist.owner.pubkey(), true),
+    ];
+    h.instruction(&DLUInstruction::VoteAppeal { kind: KIND, for_buyer }, accounts)
+}
+
+fn settle_instruction(h: &Harness, offer: Pubkey, recipient: &Party, appellant: &Party, panelists: &[&Party]) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(offer, false),
+        AccountMeta::new_readonly(h.arbitration(offer), false),
+        AccountMeta::new(h.escrow, false),
+        AccountMeta::new_readonly(h.vault_registry(), false),
+        AccountMeta::new(recipient.token, false),
+        AccountMeta::new(h.stats(), false),
+        AccountMeta::new(appeal_address(h, offer), false),
+        AccountMeta::new(appellant.token, false),
+    ];
+    accounts.extend(panelists.iter().map(|panelist| AccountMeta::new(panelist.token, false)));
+    h.instruction(&DLUInstruction::SettleDispute { kind: KIND }, accounts)
+}
+
+async fn load_appeal(h: &mut Harness, offer: Pubkey) -> Appeal {
+    h.decode(appeal_address(h, offer), Appeal::deserialize).await
+}
+
+#[tokio::test]
+async fn a_panel_majority_overturns_a_ruling_and_shares_the_bond() {
+    let mut h = Harness::start().await;
+    let d = ruled_for_the_seller(&mut h).await;
+
+    // Only the losing party can appeal.
+    let winner = appeal_instruction(&h, d.offer, &d.seller);
+    assert!(h.try_process_all(&[winner], &[&d.seller.owner]).await.is_err());
+    let appeal = appeal_instruction(&h, d.offer, &d.buyer);
+    h.process(appeal, &[&d.buyer.owner]).await;
+
+    let panel = load_appeal(&mut h, d.offer).await.panel;
+    let judge = d.arbiters[d.judge].owner.pubkey();
+    assert!(!panel.contains(&judge));
+    let (first, second, third) = (d.arbiter(&panel[0]), d.arbiter(&panel[1]), d.arbiter(&panel[2]));
+
+    let outsider = vote_instruction(&h, d.offer, &d.arbiters[d.judge], true);
+    assert!(h.try_process_all(&[outsider], &[&d.arbiters[d.judge].owner]).await.is_err());
+    h.process(vote_instruction(&h, d.offer, first, true), &[&first.owner]).await;
+    h.process(vote_instruction(&h, d.offer, third, false), &[&third.owner]).await;
+    let undecided = settle_instruction(&h, d.offer, &d.buyer, &d.buyer, &[first]);
+    assert!(h.try_process_all(&[undecided], &[]).await.is_err());
+    h.process(vote_instruction(&h, d.offer, second, true), &[&second.owner]).await;
+
+    h.process(settle_instruction(&h, d.offer, &d.buyer, &d.buyer, &[first, second]), &[]).await;
+    assert_eq!(load_appeal(&mut h, d.offer).await.status, AppealStatus::Overturned);
+    // The buyer recovers the insurance and half the bond; the majority shares the rest.
+    assert_eq!(h.balance(d.buyer.token).await, STARTING_BALANCE - BOND / 2);
+    assert_eq!(h.balance(first.token).await, STARTING_BALANCE - STAKE + BOND / 4);
+    assert_eq!(h.balance(second.token).await, STARTING_BALANCE - STAKE + BOND / 4);
+    assert_eq!(h.balance(third.token).await, STARTING_BALANCE - STAKE);
+    assert_eq!(h.escrow_balance().await, 4 * STAKE);
+
+    // The overturned arbiter loses up to the bond from their stake, once.
+    let judge_account = h.arbiter(&d.arbiters[d.judge]);
+    let accounts = vec![
+        AccountMeta::new_readonly(d.offer, false),
+        AccountMeta::new_readonly(h.arbitration(d.offer), false),
+        AccountMeta::new(appeal_address(&h, d.offer), false),
+        AccountMeta::new(judge_account, false),
+        AccountMeta::new(h.escrow, false),
+        AccountMeta::new_readonly(h.vault_registry(), false),
+        AccountMeta::new(h.penalty, false),
+        AccountMeta::new_readonly(h.config(), false),
+        AccountMeta::new(h.stats(), false),
+    ];
+    let slash = h.instruction(&DLUInstruction::SlashArbiter { kind: KIND }, accounts);
+    h.process(slash.clone(), &[]).await;
+    let arbiter = h.decode(judge_account, Arbiter::deserialize).await;
+    assert_eq!((arbiter.stake, arbiter.overturned), (STAKE - BOND, 1));
+    assert_eq!(h.balance(h.penalty).await, BOND);
+    assert!(h.try_process_all(&[slash], &[]).await.is_err());
+}
+
+#[tokio::test]
+async fn a_lapsed_appeal_keeps_the_ruling_and_returns_the_bond() {
+    let mut h = Harness::start().await;
+    let d = ruled_for_the_seller(&mut h).await;
+    let appeal = appeal_instruction(&h, d.offer, &d.buyer);
+    h.process(appeal, &[&d.buyer.owner]).await;
+
+    let filed = load_appeal(&mut h, d.offer).await;
+    let lone = d.arbiter(&filed.panel[0]);
+    h.process(vote_instruction(&h, d.offer, lone, true), &[&lone.owner]).await;
+    h.expire_clock(filed.filed_at + ARBITRATION_TIMEOUT).await;
+    let late = d.arbiter(&filed.panel[1]);
+    let vote = vote_instruction(&h, d.offer, late, true);
+    assert!(h.try_process_all(&[vote], &[&late.owner]).await.is_err());
+
+    h.process(settle_instruction(&h, d.offer, &d.seller, &d.buyer, &[]), &[]).await;
+    assert_eq!(load_appeal(&mut h, d.offer).await.status, AppealStatus::Lapsed);
+    assert_eq!(h.balance(d.seller.token).await, STARTING_BALANCE + PAYMENT);
+    assert_eq!(h.balance(d.buyer.token).await, STARTING_BALANCE - PAYMENT);
+    assert_eq!(h.escrow_balance().await, 4 * STAKE);
+}
@@ -0,0 +1,106 @@
+mod common;
+
+use common::{Harness, Party, STARTING_BALANCE};
+use luda::arbiter::{Arbiter, DisputeKind};
+use luda::constants::{APPEAL_WINDOW, ARBITER_COOLDOWN, DEFAULT_ARBITER_MIN_STAKE, DEFAULT_ARBITRATION_FEE};
+use luda::instruction::DLUInstruction;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+
+const STAKE: u64 = DEFAULT_ARBITER_MIN_STAKE;
+
+fn settle_instruction(h: &Harness, offer: Pubkey, recipient: &Party) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new(offer, false),
+        AccountMeta::new_readonly(h.arbitration(offer), false),
+        AccountMeta::new(h.escrow, false),
+        AccountMeta::new_readonly(h.vault_registry(), false),
+        AccountMeta::new(recipient.token, false),
+        AccountMeta::new(h.stats(), false),
+    ];
+    h.instruction(&DLUInstruction::SettleDispute { kind: DisputeKind::Chargeback }, accounts)
+}
+
+#[tokio::test]
+async fn a_drawn_arbiter_rules_on_a_chargeback_for_a_fee() {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    let judge = h.create_user("judge").await;
+    h.register_arbiter(&judge).await;
+    let offer = h.charged_back_offer(&seller, &buyer).await;
+    assert_eq!(h.assign_arbiter(offer).await.arbiter, judge.owner.pubkey());
+
+    // The treasury pays the fee out of what it has collected.
+    let treasury = h.treasury();
+    h.set_token_account(treasury, treasury, DEFAULT_ARBITRATION_FEE);
+    let impostor = h.rule_dispute_instruction(offer, &buyer, true);
+    assert!(h.try_process_all(&[impostor], &[&buyer.owner]).await.is_err());
+    h.rule_dispute(offer, &judge, true).await;
+
+    assert_eq!(h.balance(judge.token).await, STARTING_BALANCE - STAKE + DEFAULT_ARBITRATION_FEE);
+    let arbiter = h.decode(h.arbiter(&judge), Arbiter::deserialize).await;
+    assert_eq!((arbiter.rulings, arbiter.fees_earned), (1, DEFAULT_ARBITRATION_FEE));
+    let again = h.rule_dispute_instruction(offer, &judge, false);
+    assert!(h.try_process_all(&[again], &[&judge.owner]).await.is_err());
+
+    // The insurance stays in escrow until the ruling can no longer be appealed.
+    let early = settle_instruction(&h, offer, &buyer);
+    assert!(h.try_process_all(&[early], &[]).await.is_err());
+    let ruled_at = h.load_arbitration(offer).await.ruled_at;
+    h.expire_clock(ruled_at + APPEAL_WINDOW).await;
+    h.process(settle_instruction(&h, offer, &buyer), &[]).await;
+
+    assert_eq!(h.balance(buyer.token).await, STARTING_BALANCE);
+    assert_eq!(h.balance(seller.token).await, STARTING_BALANCE);
+    assert_eq!(h.escrow_balance().await, STAKE);
+}
+
+#[tokio::test]
+async fn parties_are_never_drawn_for_their_own_dispute() {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    h.register_arbiter(&seller).await;
+    let offer = h.charged_back_offer(&seller, &buyer).await;
+
+    let assign = h.assign_arbiter_instruction(offer);
+    assert!(h.try_process_all(&[assign], &[]).await.is_err());
+}
+
+#[tokio::test]
+async fn stakes_stay_locked_through_the_cooldown() {
+    let mut h = Harness::start().await;
+    let judge = h.create_user("judge").await;
+    h.register_arbiter(&judge).await;
+
+    let withdraw = |h: &Harness| {
+        let accounts = vec![
+            AccountMeta::new(h.arbiter(&judge), false),
+            AccountMeta::new_readonly(judge.owner.pubkey(), true),
+            AccountMeta::new(judge.token, false),
+            AccountMeta::new(h.escrow, false),
+            AccountMeta::new_readonly(h.vault_registry(), false),
+            AccountMeta::new(h.stats(), false),
+        ];
+        h.instruction(&DLUInstruction::WithdrawArbiterStake, accounts)
+    };
+    let registered = withdraw(&h);
+    assert!(h.try_process_all(&[registered], &[&judge.owner]).await.is_err());
+
+    let accounts = vec![
+        AccountMeta::new(h.arbiter(&judge), false),
+        AccountMeta::new(h.arbiter_registry(), false),
+        AccountMeta::new_readonly(judge.owner.pubkey(), true),
+    ];
+    h.process(h.instruction(&DLUInstruction::DeregisterArbiter, accounts), &[&judge.owner]).await;
+    let cooling = withdraw(&h);
+    assert!(h.try_process_all(&[cooling], &[&judge.owner]).await.is_err());
+
+    let now = h.now().await;
+    h.expire_clock(now + ARBITER_COOLDOWN).await;
+    h.process(withdraw(&h), &[&judge.owner]).await;
+    assert_eq!(h.balance(judge.token).await, STARTING_BALANCE);
+    assert_eq!(h.escrow_balance().await, 0);
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use common::{DealKind, Harness, Party};
+use luda::addressing::{derive_history_address, EntityType};
+use luda::archive::{self, History, ARCHIVE_DELAY};
+use luda::discriminator::AccountKind;
+use luda::errors::DLUError;
+use luda::instruction::DLUInstruction;
+use luda::migrations::decode_account;
+use solana_program::instruction::{AccountMeta, Instruction, InstructionError};
+use solana_program::pubkey::Pubkey;
+use solana_program::rent::Rent;
+use solana_program::system_program;
+use solana_sdk::signature::Signer;
+use solana_sdk::transaction::TransactionError;
+
+const LISTER_LAMPORTS: u64 = 1_000_000_000;
+
+fn archive_instruction(h: &Harness, lister: &Party, entity_type: EntityType, entities: &[Pubkey]) -> Instruction {
+    let history = derive_history_address(&h.program_id, &lister.owner.pubkey()).0;
+    let mut accounts = vec![
+        AccountMeta::new(lister.owner.pubkey(), true),
+        AccountMeta::new(history, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    accounts.extend(entities.iter().map(|entity| AccountMeta::new(*entity, false)));
+    h.instruction(&DLUInstruction::ArchiveBatch { entity_type }, accounts)
+}
+
+fn verify_instruction(h: &Harness, lister: &Party, entity: Pubkey, data_hash: [u8; 32], index: u32, proof: Vec<[u8; 32]>) -> Instruction {
+    let history = derive_history_address(&h.program_id, &lister.owner.pubkey()).0;
+    let instruction = DLUInstruction::VerifyArchivedEntity { lister: lister.owner.pubkey(), batch: 0, entity, data_hash, index, proof };
+    h.instruction(&instruction, vec![AccountMeta::new_readonly(history, false)])
+}
+
+fn custom(code: DLUError) -> TransactionError {
+    TransactionError::InstructionError(0, InstructionError::Custom(code.code()))
+}
+
+#[tokio::test]
+async fn finished_deals_are_archived_for_their_rent_and_stay_provable() {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    h.fund(seller.owner.pubkey(), LISTER_LAMPORTS);
+
+    let (completed, completed_meeting) = h.list_deal(DealKind::Offer, &seller, &buyer).await;
+    h.accept_deal(DealKind::Offer, completed, &seller, &buyer).await;
+    h.complete_deal(DealKind::Offer, completed, &seller, &buyer).await;
+    let (canceled, canceled_meeting) = h.list_deal(DealKind::Offer, &seller, &buyer).await;
+    h.cancel_deal(DealKind::Offer, canceled, &seller, &buyer).await;
+    let (listed, _) = h.list_deal(DealKind::Offer, &seller, &buyer).await;
+
+    // Archiving waits out the review window, and never takes a deal still in escrow.
+    let instruction = archive_instruction(&h, &seller, EntityType::Offer, &[completed, canceled]);
+    let error = h.try_process_all(&[instruction], &[&seller.owner]).await.unwrap_err();
+    assert_eq!(error, custom(DLUError::NotArchivable));
+    h.warp_to(completed_meeting.max(canceled_meeting) + ARCHIVE_DELAY).await;
+    let instruction = archive_instruction(&h, &seller, EntityType::Offer, &[completed, listed]);
+    let error = h.try_process_all(&[instruction], &[&seller.owner]).await.unwrap_err();
+    assert_eq!(error, custom(DLUError::NotArchivable));
+    h.fund(buyer.owner.pubkey(), LISTER_LAMPORTS);
+    let instruction = archive_instruction(&h, &buyer, EntityType::Offer, &[completed]);
+    let error = h.try_process_all(&[instruction], &[&buyer.owner]).await.unwrap_err();
+    assert_eq!(error, custom(DLUError::NotAuthorized));
+
+    let data = [h.account(completed).await.data, h.account(canceled).await.data];
+    let rent = h.account(completed).await.lamports + h.account(canceled).await.lamports;
+    let before = h.account(seller.owner.pubkey()).await.lamports;
+    let instruction = archive_instruction(&h, &seller, EntityType::Offer, &[completed, canceled]);
+    h.process(instruction, &[&seller.owner]).await;
+
+    for entity in [completed, canceled] {
+        assert!(h.context.banks_client.get_account(entity).await.unwrap().is_none());
+    }
+    let history_address = derive_history_address(&h.program_id, &seller.owner.pubkey()).0;
+    let history_account = h.account(history_address).await;
+    assert_eq!(history_account.lamports, Rent::default().minimum_balance(history_account.data.len()));
+    assert_eq!(h.account(seller.owner.pubkey()).await.lamports, before + rent - history_account.lamports);
+
+    let history = decode_account(&history_account.data, AccountKind::History, History::deserialize).unwrap();
+    let leaves = [
+        archive::leaf(&completed, &archive::data_hash(&data[0])),
+        archive::leaf(&canceled, &archive::data_hash(&data[1])),
+    ];
+    assert_eq!(history.batches.len(), 1);
+    assert_eq!((history.batches[0].root, history.batches[0].count), (archive::merkle_root(&leaves), 2));
+
+    let proof = archive::merkle_proof(&leaves, 1);
+    let instruction = verify_instruction(&h, &seller, canceled, archive::data_hash(&data[1]), 1, proof.clone());
+    h.process(instruction, &[]).await;
+    let instruction = verify_instruction(&h, &seller, canceled, archive::data_hash(&data[0]), 1, proof);
+    let error = h.try_process_all(&[instruction], &[]).await.unwrap_err();
+    assert_eq!(error, custom(DLUError::InvalidArchiveProof));
+}
+
+#[test]
+fn proofs_hold_for_every_leaf_of_uneven_batches() {
+    for count in 1..=5u8 {
+        let leaves: Vec<[u8; 32]> = (0..count).map(|i| archive::leaf(&Pubkey::new_unique(), &[i; 32])).collect();
+        let root = archive::merkle_root(&leaves);
+        for (index, leaf) in leaves.iter().enumerate() {
+            let proof = archive::merkle_proof(&leaves, index);
+            assert!(archive::verify(&root, leaf, index as u32, &proof));
+            assert!(!archive::verify(&root, leaf, index as u32 + 1, &proof));
+        }
+    }
+}
@@ -0,0 +1,128 @@
+mod common;
+
+use common::{DealKind, Harness, Party, PAYMENT};
+use luda::addressing::{derive_attestation_address, derive_attestor_registry_address};
+use luda::instruction::DLUInstruction;
+use luda::offer::OfferStatus;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_program::system_program;
+use solana_sdk::signature::{Keypair, Signer};
+
+fn registry(h: &Harness) -> Pubkey {
+    derive_attestor_registry_address(&h.program_id).0
+}
+
+fn attestation(h: &Harness, party: &Party) -> Pubkey {
+    derive_attestation_address(&h.program_id, &party.owner.pubkey()).0
+}
+
+/// Approves `attestor`, creating the attestor registry, and requires attestations on deals
+/// paying more than half the usual payment.
+async fn enable_kyc(h: &mut Harness, attestor: &Keypair) {
+    let governance = h.context.payer.pubkey();
+    let threshold = DLUInstruction::SetKycThreshold { threshold: PAYMENT / 2 };
+    let accounts = vec![
+        AccountMeta::new(h.config(), false),
+        AccountMeta::new_readonly(governance, true),
+    ];
+    h.process(h.instruction(&threshold, accounts), &[]).await;
+    set_attestor(h, attestor, true).await;
+}
+
+async fn set_attestor(h: &mut Harness, attestor: &Keypair, approved: bool) {
+    let accounts = vec![
+        AccountMeta::new_readonly(h.config(), false),
+        AccountMeta::new(h.context.payer.pubkey(), true),
+        AccountMeta::new(registry(h), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    let instruction = DLUInstruction::SetAttestor { attestor: attestor.pubkey(), approved };
+    h.process(h.instruction(&instruction, accounts), &[]).await;
+}
+
+fn attest_instruction(h: &Harness, attestor: &Keypair, party: &Party, expires_at: i64) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(registry(h), false),
+        AccountMeta::new_readonly(attestor.pubkey(), true),
+        AccountMeta::new_readonly(party.user, false),
+        AccountMeta::new(attestation(h, party), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new(h.context.payer.pubkey(), true),
+    ];
+    h.instruction(&DLUInstruction::Attest { expires_at }, accounts)
+}
+
+async fn attest(h: &mut Harness, attestor: &Keypair, party: &Party, expires_at: i64) {
+    h.process(attest_instruction(h, attestor, party, expires_at), &[attestor]).await;
+}
+
+/// Accepts the offer for the buyer, passing both parties' attestations.
+fn attested_accept_instruction(h: &Harness, offer: Pubkey, seller: &Party, buyer: &Party) -> Instruction {
+    let mut instruction = h.accept_deal_instruction(DealKind::Offer, offer, seller, buyer);
+    instruction.accounts.extend([
+        AccountMeta::new_readonly(registry(h), false),
+        AccountMeta::new_readonly(attestation(h, seller), false),
+        AccountMeta::new_readonly(attestation(h, buyer), false),
+    ]);
+    instruction
+}
+
+#[tokio::test]
+async fn high_value_deals_need_both_parties_attested() {
+    let mut h = Harness::start().await;
+    let attestor = Keypair::new();
+    enable_kyc(&mut h, &attestor).await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    let (offer, _) = h.list_deal(DealKind::Offer, &seller, &buyer).await;
+
+    let unattested = h.accept_deal_instruction(DealKind::Offer, offer, &seller, &buyer);
+    assert!(h.try_process_all(&[unattested], &[&buyer.owner]).await.is_err());
+
+    let in_a_year = h.now().await + 365 * 86_400;
+    attest(&mut h, &attestor, &seller, in_a_year).await;
+    let mut seller_only = attested_accept_instruction(&h, offer, &seller, &buyer);
+    seller_only.accounts.pop();
+    assert!(h.try_process_all(&[seller_only], &[&buyer.owner]).await.is_err());
+
+    // Only approved attestors can attest.
+    let impostor = Keypair::new();
+    let forged = attest_instruction(&h, &impostor, &buyer, in_a_year);
+    assert!(h.try_process_all(&[forged], &[&impostor]).await.is_err());
+
+    attest(&mut h, &attestor, &buyer, in_a_year).await;
+    h.process(attested_accept_instruction(&h, offer, &seller, &buyer), &[&buyer.owner]).await;
+    assert_eq!(h.offer(offer).await.status(), OfferStatus::Accepted);
+}
+
+#[tokio::test]
+async fn expired_and_orphaned_attestations_do_not_count() {
+    let mut h = Harness::start().await;
+    let attestor = Keypair::new();
+    enable_kyc(&mut h, &attestor).await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    // Each attempt accepts a fresh offer, so no transaction repeats a failed one.
+    let (expired, _) = h.list_deal(DealKind::Offer, &seller, &buyer).await;
+    let (orphaned, _) = h.list_deal(DealKind::Offer, &seller, &buyer).await;
+    let (offer, _) = h.list_deal(DealKind::Offer, &seller, &buyer).await;
+
+    let now = h.now().await;
+    attest(&mut h, &attestor, &seller, now + 86_400).await;
+    attest(&mut h, &attestor, &buyer, now).await;
+    let accept = attested_accept_instruction(&h, expired, &seller, &buyer);
+    assert!(h.try_process_all(&[accept], &[&buyer.owner]).await.is_err());
+
+    // Withdrawing the attestor's approval voids what it attested.
+    attest(&mut h, &attestor, &buyer, now + 86_400).await;
+    set_attestor(&mut h, &attestor, false).await;
+    let accept = attested_accept_instruction(&h, orphaned, &seller, &buyer);
+    assert!(h.try_process_all(&[accept], &[&buyer.owner]).await.is_err());
+
+    let other = Keypair::new();
+    set_attestor(&mut h, &other, true).await;
+    attest(&mut h, &other, &seller, now + 86_400).await;
+    attest(&mut h, &other, &buyer, now + 86_400).await;
+    h.process(attested_accept_instruction(&h, offer, &seller, &buyer), &[&buyer.owner]).await;
+}
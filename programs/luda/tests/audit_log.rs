@@ -0,0 +1,55 @@
+mod common;
+
+use common::{DealKind, Harness, PAYMENT};
+use luda::audit::{AuditEntry, AuditLog, MAX_AUDIT_ENTRIES};
+use luda::deal::DealStatus;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+
+fn transitions(log: &AuditLog) -> Vec<(DealStatus, Pubkey, u64)> {
+    log.chronological().iter().map(|entry| (entry.status, entry.actor, entry.amount)).collect()
+}
+
+#[tokio::test]
+async fn deals_record_who_moved_them_and_what_stayed_in_escrow() {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    let vault = h.escrow;
+
+    let (offer, _) = h.list_deal(DealKind::Offer, &seller, &buyer).await;
+    h.accept_deal(DealKind::Offer, offer, &seller, &buyer).await;
+    h.complete_deal(DealKind::Offer, offer, &seller, &buyer).await;
+    let log = h.offer(offer).await.audit_log().clone();
+    assert_eq!(transitions(&log), vec![
+        (DealStatus::Listed, seller.owner.pubkey(), PAYMENT),
+        (DealStatus::Accepted, buyer.owner.pubkey(), 3 * PAYMENT),
+        (DealStatus::Completed, vault, 0),
+    ]);
+    assert!(log.chronological().windows(2).all(|pair| pair[0].slot <= pair[1].slot));
+
+    let (request, _) = h.list_deal(DealKind::Request, &seller, &buyer).await;
+    h.accept_deal(DealKind::Request, request, &seller, &buyer).await;
+    h.fail_deal(DealKind::Request, request, &seller, &buyer).await;
+    let log = h.request(request).await.audit_log().clone();
+    assert_eq!(transitions(&log), vec![
+        (DealStatus::Listed, buyer.owner.pubkey(), 2 * PAYMENT),
+        (DealStatus::Accepted, seller.owner.pubkey(), 3 * PAYMENT),
+        (DealStatus::Failed, seller.owner.pubkey(), 0),
+    ]);
+    assert_eq!(log.recorded, 3);
+}
+
+#[test]
+fn full_audit_logs_roll_out_their_oldest_entries() {
+    let mut log = AuditLog::default();
+    let entry = |slot| AuditEntry { status: DealStatus::Listed, slot, actor: Pubkey::default(), amount: 0 };
+    for slot in 0..MAX_AUDIT_ENTRIES as u64 + 3 {
+        log.record(entry(slot));
+    }
+
+    assert_eq!(log.recorded as usize, MAX_AUDIT_ENTRIES + 3);
+    assert_eq!(log.entries.len(), MAX_AUDIT_ENTRIES);
+    let slots: Vec<u64> = log.chronological().iter().map(|entry| entry.slot).collect();
+    assert_eq!(slots, (3..MAX_AUDIT_ENTRIES as u64 + 3).collect::<Vec<_>>());
+}
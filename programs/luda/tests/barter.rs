@@ -0,0 +1,75 @@
+mod common;
+
+use common::{DealKind, Harness, Party, STARTING_BALANCE};
+use luda::deal::Location;
+use luda::instruction::DLUInstruction;
+use luda::offer::OfferKind;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+
+const OFFER_SPACE: usize = 4_096;
+const BARTER_INSURANCE: u64 = 3_000_000;
+
+fn list_barter_instruction(
+    h: &mut Harness,
+    seller: &Party,
+    offer: Pubkey,
+    payment: u64,
+    meeting_datetime: i64,
+) -> Instruction {
+    h.set_program_account(offer, OFFER_SPACE);
+    let instruction = DLUInstruction::ListOffer {
+        id: 1,
+        goodsorservice_name: "Bicycle".to_string(),
+        goodsorservice_description: "Swapped for a guitar".to_string(),
+        payment,
+        meeting_point: Location::new("DE", "Berlin".to_string(), "Alexanderplatz 1".to_string()).unwrap(),
+        meeting_datetime,
+        kind: OfferKind::Barter { insurance: BARTER_INSURANCE },
+        meeting_point_hash: None,
+        holdback: None,
+    };
+    let accounts = vec![
+        AccountMeta::new(offer, false),
+        AccountMeta::new_readonly(seller.user, false),
+        AccountMeta::new(seller.owner.pubkey(), true),
+        AccountMeta::new(seller.token, false),
+        AccountMeta::new(h.escrow, false),
+        AccountMeta::new_readonly(h.vault_registry(), false),
+        AccountMeta::new_readonly(h.config(), false),
+        AccountMeta::new(h.stats(), false),
+        AccountMeta::new(h.index(seller), false),
+    ];
+    h.instruction(&instruction, accounts)
+}
+
+#[tokio::test]
+async fn a_completed_barter_returns_both_insurances() {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+
+    let offer = Pubkey::new_unique();
+    let meeting_datetime = h.now().await + 86_400;
+    let instruction = list_barter_instruction(&mut h, &seller, offer, 0, meeting_datetime);
+    h.process(instruction, &[&seller.owner]).await;
+    h.accept_deal(DealKind::Offer, offer, &seller, &buyer).await;
+    assert_eq!(h.balance(h.escrow).await, 2 * BARTER_INSURANCE);
+    assert_eq!(h.balance(buyer.token).await, STARTING_BALANCE - BARTER_INSURANCE);
+
+    h.complete_deal(DealKind::Offer, offer, &seller, &buyer).await;
+    assert_eq!(h.balance(h.escrow).await, 0);
+    assert_eq!(h.balance(seller.token).await, STARTING_BALANCE);
+    assert_eq!(h.balance(buyer.token).await, STARTING_BALANCE);
+}
+
+#[tokio::test]
+async fn a_barter_cannot_take_a_payment() {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+
+    let meeting_datetime = h.now().await + 86_400;
+    let instruction = list_barter_instruction(&mut h, &seller, Pubkey::new_unique(), 1_000, meeting_datetime);
+    assert!(h.try_process_all(&[instruction], &[&seller.owner]).await.is_err());
+}
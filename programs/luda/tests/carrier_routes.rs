@@ -0,0 +1,131 @@
+mod common;
+
+use common::{Harness, Party, ENTITY_SPACE, INSURANCE, PAYMENT};
+use luda::addressing::{derive_carrier_route_address, derive_region_address, derive_route_index_address};
+use luda::deal::Location;
+use luda::errors::DLUError;
+use luda::instruction::DLUInstruction;
+use luda::route::{CarrierRoute, RouteIndex};
+use solana_program::instruction::{AccountMeta, Instruction, InstructionError};
+use solana_program::pubkey::Pubkey;
+use solana_program::system_program;
+use solana_sdk::signature::Signer;
+use solana_sdk::transaction::TransactionError;
+
+const WEEKDAYS: u8 = 0b001_1111;
+
+fn publish_instruction(h: &Harness, carrier: &Party, route_id: u32, destination: [u8; 4]) -> Instruction {
+    let publish = DLUInstruction::PublishRoute { route_id, origin: *b"u33d", destination, days: WEEKDAYS, capacity_grams: 10_000 };
+    let accounts = vec![
+        AccountMeta::new(carrier.owner.pubkey(), true),
+        AccountMeta::new_readonly(carrier.user, false),
+        AccountMeta::new(derive_carrier_route_address(&h.program_id, &carrier.owner.pubkey(), route_id).0, false),
+        AccountMeta::new(derive_route_index_address(&h.program_id, b"u33d").0, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    h.instruction(&publish, accounts)
+}
+
+fn reference_instruction(h: &Harness, shipment: Pubkey, sender: &Party, route: Pubkey) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new(shipment, false),
+        AccountMeta::new_readonly(sender.owner.pubkey(), true),
+        AccountMeta::new_readonly(route, false),
+    ];
+    h.instruction(&DLUInstruction::ReferenceRoute, accounts)
+}
+
+/// Lists a shipment picked up in Berlin's u33d region and dropped off in Hamburg's u1x0.
+async fn list_regional_shipment(h: &mut Harness, sender: &Party, recipient: &Party) -> Pubkey {
+    let shipment = Pubkey::new_unique();
+    h.set_program_account(shipment, ENTITY_SPACE);
+    let region = derive_region_address(&h.program_id, b"u33d").0;
+    let pickup_datetime = h.now().await + 3_600;
+
+    let instruction = DLUInstruction::ListShipment {
+        id: 1,
+        items_name: "Books".to_string(),
+        quantity: 3,
+        payment: PAYMENT,
+        insurance: INSURANCE,
+        pickup_point: Location::new("DE", "Berlin".to_string(), "Torstrasse 5".to_string()).unwrap().with_geohash("u33dc1").unwrap(),
+        pickup_datetime,
+        drop_off_point: Location::new("DE", "Hamburg".to_string(), "Jungfernstieg 2".to_string()).unwrap().with_geohash("u1x0es").unwrap(),
+        drop_off_datetime: pickup_datetime + 86_400,
+    };
+    let accounts = vec![
+        AccountMeta::new(shipment, false),
+        AccountMeta::new_readonly(sender.user, false),
+        AccountMeta::new_readonly(sender.owner.pubkey(), true),
+        AccountMeta::new(sender.token, false),
+        AccountMeta::new(h.escrow, false),
+        AccountMeta::new_readonly(h.vault_registry(), false),
+        AccountMeta::new_readonly(recipient.user, false),
+        AccountMeta::new_readonly(h.config(), false),
+        AccountMeta::new(h.stats(), false),
+        AccountMeta::new(region, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new(h.context.payer.pubkey(), true),
+    ];
+    h.process(h.instruction(&instruction, accounts), &[&sender.owner]).await;
+    shipment
+}
+
+#[tokio::test]
+async fn senders_find_carriers_traveling_their_way_and_list_along_their_routes() {
+    let mut h = Harness::start().await;
+    let sender = h.create_user("sender").await;
+    let recipient = h.create_user("recipient").await;
+    let carrier = h.create_user("carrier").await;
+    let (to_hamburg, _) = derive_carrier_route_address(&h.program_id, &carrier.owner.pubkey(), 1);
+    let (to_munich, _) = derive_carrier_route_address(&h.program_id, &carrier.owner.pubkey(), 2);
+    let index = derive_route_index_address(&h.program_id, b"u33d").0;
+    h.fund(carrier.owner.pubkey(), 1_000_000_000);
+
+    let publish = [publish_instruction(&h, &carrier, 1, *b"u1x0"), publish_instruction(&h, &carrier, 2, *b"u281")];
+    h.process_all(&publish, &[&carrier.owner]).await;
+    let routes = h.decode(index, RouteIndex::deserialize).await;
+    assert_eq!(&routes.origin, b"u33d");
+    let listed: Vec<_> = routes.routes.iter().map(|entry| (entry.route, entry.destination)).collect();
+    assert_eq!(listed, [(to_hamburg, *b"u1x0"), (to_munich, *b"u281")]);
+
+    // Only the route running where the shipment goes takes it.
+    let shipment = list_regional_shipment(&mut h, &sender, &recipient).await;
+    let error = h.try_process_all(&[reference_instruction(&h, shipment, &sender, to_munich)], &[&sender.owner]).await;
+    let code = DLUError::RouteMismatch.code();
+    assert_eq!(error.unwrap_err(), TransactionError::InstructionError(0, InstructionError::Custom(code)));
+    h.process(reference_instruction(&h, shipment, &sender, to_hamburg), &[&sender.owner]).await;
+    assert_eq!(h.shipment(shipment).await.route(), Some(to_hamburg));
+
+    // A closed route leaves the index and its rent goes back to the carrier.
+    let close = DLUInstruction::CloseRoute { route_id: 2 };
+    let accounts = vec![
+        AccountMeta::new(carrier.owner.pubkey(), true),
+        AccountMeta::new(to_munich, false),
+        AccountMeta::new(index, false),
+    ];
+    h.process(h.instruction(&close, accounts), &[&carrier.owner]).await;
+    let routes = h.decode(index, RouteIndex::deserialize).await;
+    let listed: Vec<_> = routes.routes.iter().map(|entry| entry.route).collect();
+    assert_eq!(listed, [to_hamburg]);
+}
+
+#[test]
+fn routes_run_between_regions_on_some_weekday_with_room_to_spare() {
+    let carrier = Pubkey::new_unique();
+    let mut route = CarrierRoute::default();
+    assert_eq!(route.publish(carrier, 1, *b"u33d", *b"u1x0", 0, 10_000, 0), Err(DLUError::InvalidRoute));
+    assert_eq!(route.publish(carrier, 1, *b"u33d", *b"u1x0", 0b1000_0000, 10_000, 0), Err(DLUError::InvalidRoute));
+    assert_eq!(route.publish(carrier, 1, *b"u33a", *b"u1x0", WEEKDAYS, 10_000, 0), Err(DLUError::InvalidRoute));
+    route.publish(carrier, 1, *b"u33d", *b"u1x0", WEEKDAYS, 10_000, 0).unwrap();
+
+    // Republishing updates the days and capacity, but a route keeps the regions it runs between.
+    assert_eq!(route.publish(carrier, 1, *b"u33d", *b"u281", WEEKDAYS, 10_000, 0), Err(DLUError::RouteMismatch));
+    route.publish(carrier, 1, *b"u33d", *b"u1x0", 0b110_0000, 20_000, 0).unwrap();
+    assert_eq!(route.capacity_grams, 20_000);
+
+    assert_eq!(route.check_carries(Some(*b"u33d"), Some(*b"u1x0"), 20_000), Ok(()));
+    assert_eq!(route.check_carries(Some(*b"u33d"), Some(*b"u1x0"), 20_001), Err(DLUError::RouteCapacityExceeded));
+    assert_eq!(route.check_carries(Some(*b"u33d"), None, 0), Err(DLUError::RouteMismatch));
+    assert_eq!(CarrierRoute::default().check_carries(Some(*b"u33d"), Some(*b"u1x0"), 0), Err(DLUError::InvalidRoute));
+}
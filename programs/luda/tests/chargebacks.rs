@@ -0,0 +1,110 @@
+mod common;
+
+use common::{DealKind, Harness, Party, PAYMENT, STARTING_BALANCE};
+use luda::chargeback::ChargebackStatus;
+use luda::instruction::DLUInstruction;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+
+const WINDOW: i64 = 7 * 86_400;
+
+async fn set_chargeback_window(h: &mut Harness, window: i64) {
+    let accounts = vec![
+        AccountMeta::new(h.config(), false),
+        AccountMeta::new_readonly(h.context.payer.pubkey(), true),
+    ];
+    h.process(h.instruction(&DLUInstruction::SetChargebackWindow { window }, accounts), &[]).await;
+}
+
+async fn complete_offer(h: &mut Harness, seller: &Party, buyer: &Party) -> Pubkey {
+    let (offer, _) = h.list_deal(DealKind::Offer, seller, buyer).await;
+    h.accept_deal(DealKind::Offer, offer, seller, buyer).await;
+    h.complete_deal(DealKind::Offer, offer, seller, buyer).await;
+    offer
+}
+
+fn open_instruction(h: &Harness, offer: Pubkey, buyer: &Party) -> Instruction {
+    let accounts = vec![AccountMeta::new(offer, false), AccountMeta::new_readonly(buyer.owner.pubkey(), true)];
+    h.instruction(&DLUInstruction::OpenChargeback, accounts)
+}
+
+fn release_instruction(h: &Harness, offer: Pubkey, seller: &Party) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new(offer, false),
+        AccountMeta::new_readonly(seller.owner.pubkey(), true),
+        AccountMeta::new(seller.token, false),
+        AccountMeta::new(h.escrow, false),
+        AccountMeta::new_readonly(h.vault_registry(), false),
+        AccountMeta::new(h.stats(), false),
+    ];
+    h.instruction(&DLUInstruction::ReleaseSellerInsurance, accounts)
+}
+
+#[tokio::test]
+async fn completions_are_final_without_a_window() {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    let offer = complete_offer(&mut h, &seller, &buyer).await;
+
+    assert!(h.offer(offer).await.chargeback().is_none());
+    assert_eq!(h.balance(seller.token).await, STARTING_BALANCE + PAYMENT);
+    let claim = open_instruction(&h, offer, &buyer);
+    assert!(h.try_process_all(&[claim], &[&buyer.owner]).await.is_err());
+}
+
+#[tokio::test]
+async fn the_seller_recovers_unclaimed_insurance_after_the_window() {
+    let mut h = Harness::start().await;
+    set_chargeback_window(&mut h, WINDOW).await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    let offer = complete_offer(&mut h, &seller, &buyer).await;
+
+    // The seller is paid at once, but their insurance waits out the window.
+    assert_eq!(h.balance(seller.token).await, STARTING_BALANCE);
+    assert_eq!(h.offer(offer).await.escrowed_amount(), PAYMENT);
+
+    let early = release_instruction(&h, offer, &seller);
+    assert!(h.try_process_all(&[early], &[&seller.owner]).await.is_err());
+
+    let closes_at = h.offer(offer).await.chargeback().unwrap().closes_at;
+    h.expire_clock(closes_at).await;
+    let late = open_instruction(&h, offer, &buyer);
+    assert!(h.try_process_all(&[late], &[&buyer.owner]).await.is_err());
+
+    h.process(release_instruction(&h, offer, &seller), &[&seller.owner]).await;
+    assert_eq!(h.balance(seller.token).await, STARTING_BALANCE + PAYMENT);
+    assert_eq!(h.escrow_balance().await, 0);
+}
+
+#[tokio::test]
+async fn an_upheld_chargeback_pays_the_sellers_insurance_to_the_buyer() {
+    let mut h = Harness::start().await;
+    set_chargeback_window(&mut h, WINDOW).await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    let offer = complete_offer(&mut h, &seller, &buyer).await;
+
+    let impostor = open_instruction(&h, offer, &seller);
+    assert!(h.try_process_all(&[impostor], &[&seller.owner]).await.is_err());
+    h.process(open_instruction(&h, offer, &buyer), &[&buyer.owner]).await;
+
+    let accounts = vec![
+        AccountMeta::new_readonly(h.config(), false),
+        AccountMeta::new_readonly(h.context.payer.pubkey(), true),
+        AccountMeta::new(offer, false),
+        AccountMeta::new(h.escrow, false),
+        AccountMeta::new_readonly(h.vault_registry(), false),
+        AccountMeta::new(buyer.token, false),
+        AccountMeta::new(h.stats(), false),
+    ];
+    let ruling = h.instruction(&DLUInstruction::ResolveChargeback { upheld: true }, accounts);
+    h.process(ruling, &[]).await;
+
+    assert_eq!(h.offer(offer).await.chargeback().unwrap().status, ChargebackStatus::Upheld);
+    assert_eq!(h.balance(buyer.token).await, STARTING_BALANCE);
+    assert_eq!(h.balance(seller.token).await, STARTING_BALANCE);
+    assert_eq!(h.escrow_balance().await, 0);
+}
@@ -0,0 +1,1011 @@
+// Program-test harness shared by the integration suites that drive whole lifecycles.
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use luda::addressing::{
+    derive_arbiter_address, derive_arbiter_registry_address, derive_arbitration_address, derive_bond_address,
+    derive_config_address, derive_index_address, derive_key_manager_address, derive_profile_summary_address, derive_stake_address,
+    derive_penalty_vault_address, derive_rewards_vault_address, derive_stats_address, derive_timelock_address, derive_treasury_address, derive_vault_address, derive_vault_registry_address, derive_wallet_address, EntityType,
+};
+use luda::arbiter::{Arbitration, DisputeKind};
+use luda::constants::DEFAULT_ARBITER_MIN_STAKE;
+use luda::deal::{DealStatus, Location};
+use luda::discriminator::{AccountKind, Discriminated};
+use luda::errors::DLUError;
+use luda::instruction::DLUInstruction;
+use luda::keeper;
+use luda::migrations::{account_space, decode_account, frame};
+use luda::offer::{Offer, OfferKind};
+use luda::onetimekeys::{self, KeyHash, KeyManager, NO_KEY};
+use luda::request::Request;
+use luda::shipment::{Shipment, ShipmentStatus};
+use luda::user::User;
+use solana_program::bpf_loader_upgradeable::{self, get_program_data_address};
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::program_option::COption;
+use solana_program::program_pack::Pack;
+use solana_program::pubkey::Pubkey;
+use solana_program::rent::Rent;
+use solana_program::system_instruction;
+use solana_program::system_program;
+use solana_program::sysvar::{self, clock::Clock};
+use solana_program_test::{processor, ProgramTest, ProgramTestContext};
+use solana_sdk::account::Account;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::{Transaction, TransactionError};
+use spl_associated_token_account::get_associated_token_address;
+use spl_token::state::{Account as TokenAccount, AccountState, Mint};
+
+pub const STARTING_BALANCE: u64 = 1_000_000_000;
+pub const PAYMENT: u64 = 4_000_000;
+pub const INSURANCE: u64 = 2_000_000;  // Shipments only; deals always insure the payment.
+const USER_SPACE: usize = 1_024;
+pub const ENTITY_SPACE: usize = 4_096;
+
+#[derive(Clone, Copy, Debug)]
+pub enum DealKind {
+    Offer,
+    Request,
+}
+
+pub struct Party {
+    pub owner: Keypair,
+    pub user: Pubkey,
+    pub token: Pubkey,
+}
+
+/// A program-test bank with an initialized config, a DLU mint, and the token accounts
+/// every lifecycle shares: the registered vault escrow sits in, the penalty account and
+/// the treasury.
+pub struct Harness {
+    pub context: ProgramTestContext,
+    pub program_id: Pubkey,
+    pub mint: Pubkey,
+    pub escrow: Pubkey,  // The vault of the mint, which is its own authority.
+    pub penalty: Pubkey,
+    pub terms_hash: [u8; 32],
+    pub last_units: u64,  // Compute units consumed by the last transaction.
+    owners: HashMap<Pubkey, Keypair>,  // Of the users registered here, to commit their one-time keys.
+}
+
+impl Harness {
+    /// Starts a bank running the program natively, which is fast but not metered.
+    pub async fn start() -> Self {
+        let program_id = Pubkey::new_unique();
+        Self::start_with(ProgramTest::new("luda", program_id, processor!(luda::process_instruction)), program_id).await
+    }
+
+    /// Starts a bank running the SBF build from `cargo build-sbf`, so compute units are
+    /// metered as on-chain.
+    pub async fn start_metered() -> Self {
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new("luda", program_id, None);
+        program_test.prefer_bpf(true);
+        Self::start_with(program_test, program_id).await
+    }
+
+    async fn start_with(program_test: ProgramTest, program_id: Pubkey) -> Self {
+        let mint = Pubkey::new_unique();
+        let mut harness = Harness {
+            context: program_test.start_with_context().await,
+            program_id,
+            mint,
+            escrow: derive_vault_address(&program_id, &mint).0,
+            penalty: Pubkey::new_unique(),
+            terms_hash: [7; 32],
+            last_units: 0,
+            owners: HashMap::new(),
+        };
+
+        harness.set_program_data(program_id, harness.context.payer.pubkey());
+        harness.set_mint();
+        harness.set_token_account(harness.penalty, Pubkey::new_unique(), 0);
+
+        let instruction = harness.initialize_config_instruction(harness.context.payer.pubkey());
+        harness.process(instruction, &[]).await;
+        harness.register_vault().await;
+        harness
+    }
+
+    /// Builds the config initialization signed by `authority`, with the payer as governance.
+    pub fn initialize_config_instruction(&self, authority: Pubkey) -> Instruction {
+        let instruction = DLUInstruction::InitializeConfig {
+            governance: self.context.payer.pubkey(),
+            tvl_cap: u64::MAX,
+            terms_hash: self.terms_hash,
+        };
+        let accounts = vec![
+            AccountMeta::new(self.config(), false),
+            AccountMeta::new(self.stats(), false),
+            AccountMeta::new(self.arbiter_registry(), false),
+            AccountMeta::new(derive_timelock_address(&self.program_id).0, false),
+            AccountMeta::new(authority, true),
+            AccountMeta::new_readonly(get_program_data_address(&self.program_id), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ];
+        self.instruction(&instruction, accounts)
+    }
+
+    // ACCOUNTS
+
+    pub fn set_account(&mut self, address: Pubkey, owner: Pubkey, data: Vec<u8>) {
+        let account = Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner,
+            executable: false,
+            rent_epoch: 0,
+        };
+        self.context.set_account(&address, &account.into());
+    }
+
+    pub fn fund(&mut self, address: Pubkey, lamports: u64) {
+        let account = Account { lamports, owner: system_program::id(), ..Account::default() };
+        self.context.set_account(&address, &account.into());
+    }
+
+    /// Plants a zeroed program account with room for a `space`-byte payload behind its kind
+    /// and length prefix.
+    /// Builds the payer's allocation of a program-owned account of `space` payload bytes at a
+    /// fresh keypair, as clients send it ahead of the instruction that fills the account.
+    pub fn allocate_instruction(&self, account: &Keypair, space: usize) -> Instruction {
+        let space = account_space(space);
+        let lamports = Rent::default().minimum_balance(space);
+        system_instruction::create_account(&self.context.payer.pubkey(), &account.pubkey(), lamports, space as u64, &self.program_id)
+    }
+
+    pub fn set_program_account(&mut self, address: Pubkey, space: usize) {
+        self.set_account(address, self.program_id, vec![0; account_space(space)]);
+    }
+
+    /// Plants a program account holding `payload` behind `kind` and its length prefix,
+    /// followed by `spare` zeroed bytes.
+    pub fn set_account_data(&mut self, address: Pubkey, kind: AccountKind, payload: &[u8], spare: usize) {
+        let mut data = frame(kind, payload).unwrap();
+        data.resize(data.len() + spare, 0);
+        self.set_account(address, self.program_id, data);
+    }
+
+    /// Records `upgrade_authority` as the authority of the program, as the upgradeable loader
+    /// does on deployment: the bincode of `UpgradeableLoaderState::ProgramData`.
+    pub fn set_program_data(&mut self, program_id: Pubkey, upgrade_authority: Pubkey) {
+        let mut data = 3u32.to_le_bytes().to_vec();
+        data.extend_from_slice(&0u64.to_le_bytes());  // Deployment slot.
+        data.push(1);
+        data.extend_from_slice(upgrade_authority.as_ref());
+        self.set_account(get_program_data_address(&program_id), bpf_loader_upgradeable::id(), data);
+    }
+
+    pub fn set_mint(&mut self) {
+        let mut data = vec![0; Mint::LEN];
+        let mint = Mint {
+            mint_authority: COption::None,
+            supply: 0,
+            decimals: 6,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        };
+        Mint::pack(mint, &mut data).unwrap();
+        self.set_account(self.mint, spl_token::id(), data);
+    }
+
+    pub fn set_token_account(&mut self, address: Pubkey, owner: Pubkey, amount: u64) {
+        self.set_token_account_of(address, self.mint, owner, amount);
+    }
+
+    pub fn set_token_account_of(&mut self, address: Pubkey, mint: Pubkey, owner: Pubkey, amount: u64) {
+        let mut data = vec![0; TokenAccount::LEN];
+        let account = TokenAccount {
+            mint,
+            owner,
+            amount,
+            state: AccountState::Initialized,
+            ..TokenAccount::default()
+        };
+        TokenAccount::pack(account, &mut data).unwrap();
+        self.set_account(address, spl_token::id(), data);
+    }
+
+    pub async fn account(&mut self, address: Pubkey) -> Account {
+        self.context.banks_client.get_account(address).await.unwrap().unwrap()
+    }
+
+    /// Decodes a program account of `T`'s kind with `decode`, which gets exactly the payload
+    /// behind its length prefix.
+    pub async fn decode<T: Discriminated, E: From<DLUError> + Debug>(
+        &mut self,
+        address: Pubkey,
+        decode: impl FnOnce(&mut &[u8]) -> Result<T, E>,
+    ) -> T {
+        decode_account(&self.account(address).await.data, T::KIND, decode).unwrap()
+    }
+
+    pub async fn balance(&mut self, token: Pubkey) -> u64 {
+        TokenAccount::unpack(&self.account(token).await.data).unwrap().amount
+    }
+
+    pub async fn escrow_balance(&mut self) -> u64 {
+        self.balance(self.escrow).await
+    }
+
+    pub async fn offer(&mut self, address: Pubkey) -> Offer {
+        self.decode(address, Offer::deserialize).await
+    }
+
+    pub async fn request(&mut self, address: Pubkey) -> Request {
+        self.decode(address, Request::deserialize).await
+    }
+
+    pub async fn shipment(&mut self, address: Pubkey) -> Shipment {
+        self.decode(address, Shipment::deserialize).await
+    }
+
+    /// Returns the nonce a terminal instruction must name to act on the deal now.
+    pub async fn deal_nonce(&mut self, kind: DealKind, address: Pubkey) -> u64 {
+        match kind {
+            DealKind::Offer => self.offer(address).await.nonce(),
+            DealKind::Request => self.request(address).await.nonce(),
+        }
+    }
+
+    pub async fn user(&mut self, party: &Party) -> User {
+        self.decode(party.user, |input| User::deserialize(input).map_err(|_| DLUError::DeserializationFailed)).await
+    }
+
+    /// Returns the amount the program believes is escrowed for a deal.
+    pub async fn locked(&mut self, kind: DealKind, address: Pubkey) -> u64 {
+        match kind {
+            DealKind::Offer => self.offer(address).await.escrowed_amount(),
+            DealKind::Request => self.request(address).await.escrowed_amount(),
+        }
+    }
+
+    /// Returns the seller's and buyer's one-time keys, committing them first if the deal is
+    /// accepted and they did not yet.
+    pub async fn keys(&mut self, kind: DealKind, address: Pubkey) -> (String, String) {
+        let (entity_type, status, parties, (seller_hash, buyer_hash)) = match kind {
+            DealKind::Offer => {
+                let offer = self.offer(address).await;
+                (EntityType::Offer, offer.status(), offer.parties(), offer.key_hashes())
+            }
+            DealKind::Request => {
+                let request = self.request(address).await;
+                (EntityType::Request, request.status(), request.parties(), request.key_hashes())
+            }
+        };
+        let Some((seller, buyer)) = parties else {
+            return (String::new(), String::new());
+        };
+        let accepted = status == DealStatus::Accepted;
+        let keys = self.commit_keys(entity_type, address, accepted, &[(seller, seller_hash), (buyer, buyer_hash)]).await;
+        (keys[0].clone(), keys[1].clone())
+    }
+
+    /// Returns the sender's, carrier's and recipient's one-time keys, committing them first
+    /// if the shipment is accepted or in transit and they did not yet.
+    pub async fn shipment_keys(&mut self, address: Pubkey) -> (String, String, String) {
+        let shipment = self.shipment(address).await;
+        let Some(carrier) = shipment.carrier_pubkey() else {
+            return (String::new(), String::new(), String::new());
+        };
+        let accepted = matches!(shipment.status(), ShipmentStatus::Accepted | ShipmentStatus::InTransit);
+        let (sender_hash, carrier_hash, recipient_hash) = shipment.key_hashes();
+        let parties =
+            [(shipment.sender_pubkey(), sender_hash), (carrier, carrier_hash), (shipment.recipient_pubkey(), recipient_hash)];
+        let keys = self.commit_keys(EntityType::Shipment, address, accepted, &parties).await;
+        (keys[0].clone(), keys[1].clone(), keys[2].clone())
+    }
+
+    /// Returns the key of each of the parties, committing those the `accepted` entity has
+    /// no hash of yet. Parties keep their keys off-chain; the test derives them from the
+    /// entity and the party, so a party holding several roles uses one key for all.
+    async fn commit_keys(
+        &mut self,
+        entity_type: EntityType,
+        entity: Pubkey,
+        accepted: bool,
+        parties: &[(Pubkey, KeyHash)],
+    ) -> Vec<String> {
+        let mut committed = Vec::new();
+        let mut keys = Vec::new();
+        for (party, key_hash) in parties {
+            if accepted && *key_hash == NO_KEY && !committed.contains(party) {
+                // Every key a party commits gets the next nonce, as a retired key can't come back.
+                let nonce = self.key_manager(entity).await.keys.iter().filter(|key| key.party == *party).count() as u64;
+                let key = Self::one_time_key(entity, party, nonce);
+                let owner = self.owners[party].insecure_clone();
+                let instruction = DLUInstruction::CommitKey { entity_type, key_hash: onetimekeys::hash_key(&key), nonce };
+                let accounts = vec![
+                    AccountMeta::new(entity, false),
+                    AccountMeta::new_readonly(*party, true),
+                    AccountMeta::new(derive_key_manager_address(&self.program_id, &entity).0, false),
+                    AccountMeta::new_readonly(system_program::id(), false),
+                    AccountMeta::new(self.context.payer.pubkey(), true),
+                ];
+                self.process(self.instruction(&instruction, accounts), &[&owner]).await;
+                committed.push(*party);
+            }
+            let nonce = self.key_manager(entity).await.active_key(party).map_or(0, |key| key.nonce);
+            keys.push(Self::one_time_key(entity, party, nonce));
+        }
+        keys
+    }
+
+    /// Returns the one-time key a party of the harness derives with `nonce`.
+    pub fn one_time_key(entity: Pubkey, party: &Pubkey, nonce: u64) -> String {
+        format!("{}-{}-{}", entity, party, nonce)
+    }
+
+    /// Returns the KeyManager of a deal or shipment, empty until its first key commit.
+    pub async fn key_manager(&mut self, entity: Pubkey) -> KeyManager {
+        let address = derive_key_manager_address(&self.program_id, &entity).0;
+        let Some(account) = self.context.banks_client.get_account(address).await.unwrap() else {
+            return KeyManager::default();
+        };
+        decode_account(&account.data, KeyManager::KIND, KeyManager::deserialize).unwrap()
+    }
+
+    /// Moves the clock past the grace period after `deadline`; it never moves backwards.
+    pub async fn expire_clock(&mut self, deadline: i64) {
+        self.warp_to(keeper::crank_at(deadline)).await;
+    }
+
+    /// Moves the clock to `timestamp`; it never moves backwards.
+    pub async fn warp_to(&mut self, timestamp: i64) {
+        let mut clock: Clock = self.context.banks_client.get_sysvar().await.unwrap();
+        clock.unix_timestamp = clock.unix_timestamp.max(timestamp);
+        self.context.set_sysvar(&clock);
+    }
+
+    pub async fn now(&mut self) -> i64 {
+        let clock: Clock = self.context.banks_client.get_sysvar().await.unwrap();
+        clock.unix_timestamp
+    }
+
+    // TRANSACTIONS
+
+    pub fn instruction(&self, instruction: &DLUInstruction, accounts: Vec<AccountMeta>) -> Instruction {
+        Instruction::new_with_bytes(self.program_id, &instruction.pack().unwrap(), accounts)
+    }
+
+    pub async fn process(&mut self, instruction: Instruction, signers: &[&Keypair]) {
+        self.process_all(&[instruction], signers).await
+    }
+
+    pub async fn process_all(&mut self, instructions: &[Instruction], signers: &[&Keypair]) {
+        self.try_process_all(instructions, signers).await.unwrap();
+    }
+
+    /// Waits for a new blockhash, so a transaction identical to an earlier one is not
+    /// rejected as already processed.
+    pub async fn next_blockhash(&mut self) {
+        self.context.get_new_latest_blockhash().await.unwrap();
+    }
+
+    /// Processes the instructions in one transaction, returning how it failed, if it did.
+    pub async fn try_process_all(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<(), TransactionError> {
+        let blockhash = self.context.banks_client.get_latest_blockhash().await.unwrap();
+        let mut all_signers = vec![&self.context.payer];
+        all_signers.extend_from_slice(signers);
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.context.payer.pubkey()),
+            &all_signers,
+            blockhash,
+        );
+        let outcome = self.context.banks_client.process_transaction_with_metadata(transaction).await.unwrap();
+        self.last_units = outcome.metadata.map_or(0, |metadata| metadata.compute_units_consumed);
+        outcome.result
+    }
+
+    pub fn summary(&self, party: &Party) -> Pubkey {
+        derive_profile_summary_address(&self.program_id, &party.owner.pubkey()).0
+    }
+
+    pub fn index(&self, party: &Party) -> Pubkey {
+        derive_index_address(&self.program_id, &party.owner.pubkey()).0
+    }
+
+    pub fn config(&self) -> Pubkey {
+        derive_config_address(&self.program_id).0
+    }
+
+    pub fn stats(&self) -> Pubkey {
+        derive_stats_address(&self.program_id).0
+    }
+
+    pub fn treasury(&self) -> Pubkey {
+        derive_treasury_address(&self.program_id, &self.mint).0
+    }
+
+    pub fn arbiter_registry(&self) -> Pubkey {
+        derive_arbiter_registry_address(&self.program_id).0
+    }
+
+    pub fn arbiter(&self, party: &Party) -> Pubkey {
+        derive_arbiter_address(&self.program_id, &party.owner.pubkey()).0
+    }
+
+    pub fn arbitration(&self, offer: Pubkey) -> Pubkey {
+        derive_arbitration_address(&self.program_id, &offer, DisputeKind::Chargeback).0
+    }
+
+    pub fn wallet(&self, party: &Party) -> Pubkey {
+        derive_wallet_address(&self.program_id, &party.owner.pubkey()).0
+    }
+
+    pub fn vault(&self) -> Pubkey {
+        derive_vault_address(&self.program_id, &self.mint).0
+    }
+
+    pub fn vault_registry(&self) -> Pubkey {
+        derive_vault_registry_address(&self.program_id).0
+    }
+
+    // CONFIG
+
+    pub async fn set_fee(&mut self, fee_bps: u16) {
+        let instruction = DLUInstruction::SetFee { fee_bps, exempt_credible: false };
+        let accounts = vec![
+            AccountMeta::new(self.config(), false),
+            AccountMeta::new_readonly(self.context.payer.pubkey(), true),
+        ];
+        self.process(self.instruction(&instruction, accounts), &[]).await;
+    }
+
+    /// Builds governance's registration of the vault of the harness mint.
+    pub fn register_vault_instruction(&self) -> Instruction {
+        let accounts = vec![
+            AccountMeta::new_readonly(self.config(), false),
+            AccountMeta::new(self.context.payer.pubkey(), true),
+            AccountMeta::new(self.vault_registry(), false),
+            AccountMeta::new(self.vault(), false),
+            AccountMeta::new(self.treasury(), false),
+            AccountMeta::new(derive_penalty_vault_address(&self.program_id, &self.mint).0, false),
+            AccountMeta::new(derive_rewards_vault_address(&self.program_id, &self.mint).0, false),
+            AccountMeta::new_readonly(self.mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ];
+        self.instruction(&DLUInstruction::RegisterVault, accounts)
+    }
+
+    /// Creates and registers the vault of the harness mint, along with its treasury, penalty
+    /// vault and rewards vault.
+    pub async fn register_vault(&mut self) {
+        self.process(self.register_vault_instruction(), &[]).await;
+    }
+
+    // USERS
+
+    pub async fn create_user(&mut self, username: &str) -> Party {
+        self.register_user(username, None).await
+    }
+
+    /// Creates a user who names `referrer` as the one who referred them.
+    pub async fn create_referred_user(&mut self, username: &str, referrer: &Party) -> Party {
+        self.register_user(username, Some(referrer.owner.pubkey())).await
+    }
+
+    async fn register_user(&mut self, username: &str, referrer: Option<Pubkey>) -> Party {
+        let user = Keypair::new();
+        let party = Party {
+            owner: Keypair::new(),
+            user: user.pubkey(),
+            token: Pubkey::new_unique(),
+        };
+        self.set_token_account(party.token, party.owner.pubkey(), STARTING_BALANCE);
+
+        // The harness payer sponsors the owner, who holds no SOL.
+        let instruction = DLUInstruction::CreateUser { username: username.to_string(), terms_hash: self.terms_hash, referrer };
+        let accounts = vec![
+            AccountMeta::new(party.user, false),
+            AccountMeta::new_readonly(party.owner.pubkey(), true),
+            AccountMeta::new_readonly(self.config(), false),
+            AccountMeta::new(self.summary(&party), false),
+            AccountMeta::new(self.stats(), false),
+            AccountMeta::new(self.index(&party), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(self.context.payer.pubkey(), true),
+        ];
+        let instructions = [self.allocate_instruction(&user, USER_SPACE), self.instruction(&instruction, accounts)];
+        self.process_all(&instructions, &[&party.owner, &user]).await;
+        self.owners.insert(party.owner.pubkey(), party.owner.insecure_clone());
+        party
+    }
+
+    /// Creates a user whose registration also creates their DLU associated token account,
+    /// unless `existing_balance` sets one up beforehand.
+    pub async fn onboard_user(&mut self, username: &str, existing_balance: Option<u64>) -> Party {
+        let (owner, user) = (Keypair::new(), Keypair::new());
+        let token = get_associated_token_address(&owner.pubkey(), &self.mint);
+        let party = Party { owner, user: user.pubkey(), token };
+        self.fund(party.owner.pubkey(), 1_000_000_000);
+        if let Some(amount) = existing_balance {
+            self.set_token_account(party.token, party.owner.pubkey(), amount);
+        }
+
+        let instruction =
+            DLUInstruction::CreateUser { username: username.to_string(), terms_hash: self.terms_hash, referrer: None };
+        let accounts = vec![
+            AccountMeta::new(party.user, false),
+            AccountMeta::new(party.owner.pubkey(), true),
+            AccountMeta::new_readonly(self.config(), false),
+            AccountMeta::new(self.summary(&party), false),
+            AccountMeta::new(self.stats(), false),
+            AccountMeta::new(self.index(&party), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(party.token, false),
+            AccountMeta::new_readonly(self.mint, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        ];
+        let instructions = [self.allocate_instruction(&user, USER_SPACE), self.instruction(&instruction, accounts)];
+        self.process_all(&instructions, &[&party.owner, &user]).await;
+        self.owners.insert(party.owner.pubkey(), party.owner.insecure_clone());
+        party
+    }
+
+    // DEALS
+
+    /// Lists an offer from the seller or a request from the buyer, meeting a day from now.
+    pub async fn list_deal(&mut self, kind: DealKind, seller: &Party, buyer: &Party) -> (Pubkey, i64) {
+        self.list_described_deal(kind, seller, buyer, "City bike, barely used").await
+    }
+
+    pub async fn list_described_deal(
+        &mut self,
+        kind: DealKind,
+        seller: &Party,
+        buyer: &Party,
+        description: &str,
+    ) -> (Pubkey, i64) {
+        self.list_deal_with(kind, seller, buyer, description, None).await
+    }
+
+    /// Lists a deal whose exact meeting point is hidden behind `meeting_point_hash` when given.
+    pub async fn list_deal_with(
+        &mut self,
+        kind: DealKind,
+        seller: &Party,
+        buyer: &Party,
+        description: &str,
+        meeting_point_hash: Option<[u8; 32]>,
+    ) -> (Pubkey, i64) {
+        let deal = Keypair::new();
+        let meeting_datetime = self.now().await + 86_400;
+        let lister = match kind {
+            DealKind::Offer => seller,
+            DealKind::Request => buyer,
+        };
+        let instruction = self.list_deal_instruction(kind, deal.pubkey(), lister, description, meeting_point_hash, meeting_datetime);
+        let instructions = [self.allocate_instruction(&deal, ENTITY_SPACE), instruction];
+        self.process_all(&instructions, &[&lister.owner, &deal]).await;
+        (deal.pubkey(), meeting_datetime)
+    }
+
+    /// Builds the listing of a deal into `deal` by `lister`, the seller of an offer or the
+    /// buyer of a request.
+    pub fn list_deal_instruction(
+        &self,
+        kind: DealKind,
+        deal: Pubkey,
+        lister: &Party,
+        description: &str,
+        meeting_point_hash: Option<[u8; 32]>,
+        meeting_datetime: i64,
+    ) -> Instruction {
+        let meeting_point = Location::new("DE", "Berlin".to_string(), "Alexanderplatz 1".to_string()).unwrap();
+        let name = "Bicycle".to_string();
+        let description = description.to_string();
+
+        let instruction = match kind {
+            DealKind::Offer => DLUInstruction::ListOffer {
+                id: 1,
+                goodsorservice_name: name,
+                goodsorservice_description: description,
+                payment: PAYMENT,
+                meeting_point,
+                meeting_datetime,
+                kind: OfferKind::Described,
+                meeting_point_hash,
+                holdback: None,
+            },
+            DealKind::Request => DLUInstruction::ListRequest {
+                id: 1,
+                goodsorservice_name: name,
+                goodsorservice_description: description,
+                payment: PAYMENT,
+                meeting_point,
+                meeting_datetime,
+                meeting_point_hash,
+            },
+        };
+        let mut accounts = vec![
+            AccountMeta::new(deal, false),
+            AccountMeta::new_readonly(lister.user, false),
+            AccountMeta::new_readonly(lister.owner.pubkey(), true),
+            AccountMeta::new(lister.token, false),
+            AccountMeta::new(self.escrow, false),
+            AccountMeta::new_readonly(self.vault_registry(), false),
+            AccountMeta::new_readonly(self.config(), false),
+            AccountMeta::new(self.stats(), false),
+        ];
+        if let DealKind::Offer = kind {
+            accounts.push(AccountMeta::new(self.index(lister), false));
+        }
+        self.instruction(&instruction, accounts)
+    }
+
+    pub async fn accept_deal(&mut self, kind: DealKind, deal: Pubkey, seller: &Party, buyer: &Party) {
+        let acceptor = match kind {
+            DealKind::Offer => buyer,
+            DealKind::Request => seller,
+        };
+        let instruction = self.accept_deal_instruction(kind, deal, seller, buyer);
+        self.process(instruction, &[&acceptor.owner]).await;
+    }
+
+    /// Builds the acceptance of a deal by the buyer of an offer or the seller of a request.
+    pub fn accept_deal_instruction(&self, kind: DealKind, deal: Pubkey, seller: &Party, buyer: &Party) -> Instruction {
+        let (instruction, acceptor) = match kind {
+            DealKind::Offer => (DLUInstruction::AcceptOffer { quantity: 1, promo_code: None }, buyer),
+            DealKind::Request => (DLUInstruction::AcceptRequest, seller),
+        };
+        let mut accounts = vec![
+            AccountMeta::new(deal, false),
+            AccountMeta::new(acceptor.user, false),
+            AccountMeta::new_readonly(acceptor.owner.pubkey(), true),
+            AccountMeta::new(acceptor.token, false),
+            AccountMeta::new(self.escrow, false),
+            AccountMeta::new_readonly(self.vault_registry(), false),
+            AccountMeta::new_readonly(acceptor.owner.pubkey(), true),
+            AccountMeta::new_readonly(self.config(), false),
+            AccountMeta::new(self.stats(), false),
+        ];
+        if let DealKind::Offer = kind {
+            accounts.push(AccountMeta::new(self.index(lister), false));
+        }
+        self.instruction(&instruction, accounts)
+    }
+
+    pub async fn complete_deal(&mut self, kind: DealKind, deal: Pubkey, seller: &Party, buyer: &Party) {
+        let instruction = self.complete_deal_instruction(kind, deal, seller, buyer).await;
+        self.process(instruction, &[]).await;
+    }
+
+    /// Builds the completion of a deal with both parties' keys.
+    pub async fn complete_deal_instruction(
+        &mut self,
+        kind: DealKind,
+        deal: Pubkey,
+        seller: &Party,
+        buyer: &Party,
+    ) -> Instruction {
+        let (seller_key, buyer_key) = self.keys(kind, deal).await;
+        let nonce = self.deal_nonce(kind, deal).await;
+        let instruction = match kind {
+            DealKind::Offer => DLUInstruction::CompleteOffer { buyer_key, seller_key, nonce },
+            DealKind::Request => DLUInstruction::CompleteRequest { buyer_key, seller_key, nonce },
+        };
+        let accounts = vec![
+            AccountMeta::new(deal, false),
+            AccountMeta::new(seller.user, false),
+            AccountMeta::new(buyer.user, false),
+            AccountMeta::new(seller.token, false),
+            AccountMeta::new(buyer.token, false),
+            AccountMeta::new(self.escrow, false),
+            AccountMeta::new_readonly(self.vault_registry(), false),
+            AccountMeta::new(self.stats(), false),
+            AccountMeta::new(self.summary(seller), false),
+            AccountMeta::new(self.summary(buyer), false),
+            AccountMeta::new_readonly(self.config(), false),
+            AccountMeta::new(self.treasury(), false),
+        ];
+        self.instruction(&instruction, accounts)
+    }
+
+    pub async fn fail_deal(&mut self, kind: DealKind, deal: Pubkey, seller: &Party, buyer: &Party) {
+        let instruction = self.fail_deal_instruction(kind, deal, seller, buyer).await;
+        self.process(instruction, &[&seller.owner]).await;
+    }
+
+    /// Builds the failure of a deal with the seller's key, signed by the seller.
+    pub async fn fail_deal_instruction(&mut self, kind: DealKind, deal: Pubkey, seller: &Party, buyer: &Party) -> Instruction {
+        let (bond, _) = derive_bond_address(&self.program_id, &deal);
+        let (seller_key, _) = self.keys(kind, deal).await;
+        let nonce = self.deal_nonce(kind, deal).await;
+        let instruction = match kind {
+            DealKind::Offer => DLUInstruction::FailOffer { seller_key, nonce },
+            DealKind::Request => DLUInstruction::FailRequest { seller_key, nonce },
+        };
+        let accounts = vec![
+            AccountMeta::new(deal, false),
+            AccountMeta::new(buyer.user, false),
+            AccountMeta::new(self.escrow, false),
+            AccountMeta::new_readonly(self.vault_registry(), false),
+            AccountMeta::new(self.penalty, false),
+            AccountMeta::new(self.stats(), false),
+            AccountMeta::new(self.summary(buyer), false),
+            AccountMeta::new_readonly(self.config(), false),
+            AccountMeta::new_readonly(seller.owner.pubkey(), true),
+            AccountMeta::new(seller.token, false),
+            AccountMeta::new(bond, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(self.context.payer.pubkey(), true),
+            AccountMeta::new(buyer.token, false),
+        ];
+        self.instruction(&instruction, accounts)
+    }
+
+    pub async fn expire_deal(&mut self, kind: DealKind, deal: Pubkey, seller: &Party, buyer: &Party) {
+        let instruction = match kind {
+            DealKind::Offer => DLUInstruction::ExpireOffer,
+            DealKind::Request => DLUInstruction::ExpireRequest,
+        };
+        let accounts = vec![
+            AccountMeta::new(deal, false),
+            AccountMeta::new(self.escrow, false),
+            AccountMeta::new_readonly(self.vault_registry(), false),
+            AccountMeta::new(seller.token, false),
+            AccountMeta::new(buyer.token, false),
+            AccountMeta::new(self.stats(), false),
+        ];
+        self.process(self.instruction(&instruction, accounts), &[]).await;
+    }
+
+    pub async fn cancel_deal(&mut self, kind: DealKind, deal: Pubkey, seller: &Party, buyer: &Party) {
+        let lister = match kind {
+            DealKind::Offer => seller,
+            DealKind::Request => buyer,
+        };
+        let instruction = self.cancel_deal_instruction(kind, deal, seller, buyer);
+        self.process(instruction, &[&lister.owner]).await;
+    }
+
+    /// Builds the cancellation of a listed deal, signed by its lister.
+    pub fn cancel_deal_instruction(&self, kind: DealKind, deal: Pubkey, seller: &Party, buyer: &Party) -> Instruction {
+        let (instruction, lister) = match kind {
+            DealKind::Offer => (DLUInstruction::CancelOffer, seller),
+            DealKind::Request => (DLUInstruction::CancelRequest, buyer),
+        };
+        let mut accounts = vec![
+            AccountMeta::new(deal, false),
+            AccountMeta::new_readonly(lister.owner.pubkey(), true),
+            AccountMeta::new(lister.token, false),
+            AccountMeta::new(self.escrow, false),
+            AccountMeta::new_readonly(self.vault_registry(), false),
+            AccountMeta::new(self.stats(), false),
+        ];
+        if let DealKind::Offer = kind {
+            accounts.push(AccountMeta::new(self.index(lister), false));
+        }
+        self.instruction(&instruction, accounts)
+    }
+
+    // SHIPMENTS
+
+    /// Lists a shipment from the sender to the recipient, delivered a day from now.
+    pub async fn list_shipment(&mut self, sender: &Party, recipient: &Party) -> (Pubkey, i64) {
+        let shipment = Keypair::new();
+        let pickup_datetime = self.now().await + 3_600;
+        let drop_off_datetime = pickup_datetime + 86_400;
+
+        let instruction = DLUInstruction::ListShipment {
+            id: 1,
+            items_name: "Books".to_string(),
+            quantity: 3,
+            payment: PAYMENT,
+            insurance: INSURANCE,
+            pickup_point: Location::new("DE", "Berlin".to_string(), "Torstrasse 5".to_string()).unwrap(),
+            pickup_datetime,
+            drop_off_point: Location::new("DE", "Hamburg".to_string(), "Jungfernstieg 2".to_string()).unwrap(),
+            drop_off_datetime,
+        };
+        let accounts = vec![
+            AccountMeta::new(shipment.pubkey(), false),
+            AccountMeta::new_readonly(sender.user, false),
+            AccountMeta::new_readonly(sender.owner.pubkey(), true),
+            AccountMeta::new(sender.token, false),
+            AccountMeta::new(self.escrow, false),
+            AccountMeta::new_readonly(self.vault_registry(), false),
+            AccountMeta::new_readonly(recipient.user, false),
+            AccountMeta::new_readonly(self.config(), false),
+            AccountMeta::new(self.stats(), false),
+        ];
+        let instructions = [self.allocate_instruction(&shipment, ENTITY_SPACE), self.instruction(&instruction, accounts)];
+        self.process_all(&instructions, &[&sender.owner, &shipment]).await;
+        (shipment.pubkey(), drop_off_datetime)
+    }
+
+    pub async fn accept_shipment(&mut self, shipment: Pubkey, carrier: &Party) {
+        // The payment is below the stake threshold, so no CarrierStake account is passed.
+        let accounts = vec![
+            AccountMeta::new(shipment, false),
+            AccountMeta::new(carrier.user, false),
+            AccountMeta::new_readonly(carrier.owner.pubkey(), true),
+            AccountMeta::new(carrier.token, false),
+            AccountMeta::new(self.escrow, false),
+            AccountMeta::new_readonly(self.vault_registry(), false),
+            AccountMeta::new_readonly(carrier.owner.pubkey(), true),
+            AccountMeta::new_readonly(self.config(), false),
+            AccountMeta::new(self.stats(), false),
+        ];
+        self.process(self.instruction(&DLUInstruction::AcceptShipment, accounts), &[&carrier.owner]).await;
+    }
+
+    pub async fn confirm_pickup(&mut self, shipment: Pubkey, carrier: &Party) {
+        let (sender_key, _, _) = self.shipment_keys(shipment).await;
+        let accounts = vec![AccountMeta::new(shipment, false), AccountMeta::new_readonly(carrier.owner.pubkey(), true)];
+        self.process(self.instruction(&DLUInstruction::ConfirmPickup { sender_key }, accounts), &[&carrier.owner]).await;
+    }
+
+    pub async fn complete_shipment(&mut self, shipment: Pubkey, sender: &Party, carrier: &Party) {
+        let (_, carrier_key, recipient_key) = self.shipment_keys(shipment).await;
+        let accounts = vec![
+            AccountMeta::new(shipment, false),
+            AccountMeta::new(sender.user, false),
+            AccountMeta::new(carrier.user, false),
+            AccountMeta::new(sender.token, false),
+            AccountMeta::new(carrier.token, false),
+            AccountMeta::new(self.escrow, false),
+            AccountMeta::new_readonly(self.vault_registry(), false),
+            AccountMeta::new(self.stats(), false),
+            AccountMeta::new(self.summary(sender), false),
+            AccountMeta::new(self.summary(carrier), false),
+            AccountMeta::new_readonly(self.config(), false),
+            AccountMeta::new(self.treasury(), false),
+        ];
+        let nonce = self.shipment(shipment).await.nonce();
+        let instruction = DLUInstruction::CompleteShipment { carrier_key, recipient_key, proof_hash: None, nonce };
+        self.process(self.instruction(&instruction, accounts), &[]).await;
+    }
+
+    pub async fn fail_shipment(&mut self, shipment: Pubkey, sender: &Party, carrier: &Party) {
+        let (bond, _) = derive_bond_address(&self.program_id, &shipment);
+        let (sender_key, _, _) = self.shipment_keys(shipment).await;
+        let accounts = vec![
+            AccountMeta::new(shipment, false),
+            AccountMeta::new(carrier.user, false),
+            AccountMeta::new(self.escrow, false),
+            AccountMeta::new_readonly(self.vault_registry(), false),
+            AccountMeta::new(self.penalty, false),
+            AccountMeta::new(self.stats(), false),
+            AccountMeta::new(self.summary(carrier), false),
+            AccountMeta::new_readonly(self.config(), false),
+            AccountMeta::new_readonly(sender.owner.pubkey(), true),
+            AccountMeta::new(sender.token, false),
+            AccountMeta::new(bond, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(self.context.payer.pubkey(), true),
+        ];
+        let nonce = self.shipment(shipment).await.nonce();
+        let instruction = DLUInstruction::FailShipment { sender_key, nonce };
+        self.process(self.instruction(&instruction, accounts), &[&sender.owner]).await;
+    }
+
+    pub async fn expire_shipment(&mut self, shipment: Pubkey, sender: &Party, carrier: &Party) {
+        let accounts = vec![
+            AccountMeta::new(shipment, false),
+            AccountMeta::new(self.escrow, false),
+            AccountMeta::new_readonly(self.vault_registry(), false),
+            AccountMeta::new(sender.token, false),
+            AccountMeta::new(carrier.token, false),
+            AccountMeta::new(self.stats(), false),
+        ];
+        self.process(self.instruction(&DLUInstruction::ExpireShipment, accounts), &[]).await;
+    }
+
+    pub async fn cancel_shipment(&mut self, shipment: Pubkey, sender: &Party) {
+        let accounts = vec![
+            AccountMeta::new(shipment, false),
+            AccountMeta::new_readonly(sender.owner.pubkey(), true),
+            AccountMeta::new(sender.token, false),
+            AccountMeta::new(self.escrow, false),
+            AccountMeta::new_readonly(self.vault_registry(), false),
+            AccountMeta::new(self.stats(), false),
+        ];
+        self.process(self.instruction(&DLUInstruction::CancelShipment, accounts), &[&sender.owner]).await;
+    }
+
+    /// Locks `amount` of the carrier's tokens into their CarrierStake.
+    pub async fn stake(&mut self, carrier: &Party, amount: u64) {
+        let owner = carrier.owner.pubkey();
+        let accounts = vec![
+            AccountMeta::new(derive_stake_address(&self.program_id, &owner).0, false),
+            AccountMeta::new_readonly(owner, true),
+            AccountMeta::new(carrier.token, false),
+            AccountMeta::new(self.escrow, false),
+            AccountMeta::new_readonly(self.vault_registry(), false),
+            AccountMeta::new_readonly(self.config(), false),
+            AccountMeta::new(self.stats(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(self.context.payer.pubkey(), true),
+        ];
+        self.process(self.instruction(&DLUInstruction::Stake { amount }, accounts), &[&carrier.owner]).await;
+    }
+
+    // ARBITRATION
+
+    /// Registers the party as an arbiter with the minimum stake.
+    pub async fn register_arbiter(&mut self, party: &Party) {
+        let arbiter = self.arbiter(party);
+        let accounts = vec![
+            AccountMeta::new(arbiter, false),
+            AccountMeta::new(self.arbiter_registry(), false),
+            AccountMeta::new_readonly(party.user, false),
+            AccountMeta::new_readonly(party.owner.pubkey(), true),
+            AccountMeta::new(party.token, false),
+            AccountMeta::new(self.escrow, false),
+            AccountMeta::new_readonly(self.vault_registry(), false),
+            AccountMeta::new_readonly(self.config(), false),
+            AccountMeta::new(self.stats(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(self.context.payer.pubkey(), true),
+        ];
+        let instruction = DLUInstruction::RegisterArbiter { amount: DEFAULT_ARBITER_MIN_STAKE };
+        self.process(self.instruction(&instruction, accounts), &[&party.owner]).await;
+    }
+
+    /// Completes an offer under a day-long chargeback window and has the buyer claim it.
+    pub async fn charged_back_offer(&mut self, seller: &Party, buyer: &Party) -> Pubkey {
+        let accounts = vec![
+            AccountMeta::new(self.config(), false),
+            AccountMeta::new_readonly(self.context.payer.pubkey(), true),
+        ];
+        self.process(self.instruction(&DLUInstruction::SetChargebackWindow { window: 86_400 }, accounts), &[]).await;
+
+        let (offer, _) = self.list_deal(DealKind::Offer, seller, buyer).await;
+        self.accept_deal(DealKind::Offer, offer, seller, buyer).await;
+        self.complete_deal(DealKind::Offer, offer, seller, buyer).await;
+        let accounts = vec![AccountMeta::new(offer, false), AccountMeta::new_readonly(buyer.owner.pubkey(), true)];
+        self.process(self.instruction(&DLUInstruction::OpenChargeback, accounts), &[&buyer.owner]).await;
+        offer
+    }
+
+    pub fn assign_arbiter_instruction(&self, offer: Pubkey) -> Instruction {
+        let accounts = vec![
+            AccountMeta::new_readonly(offer, false),
+            AccountMeta::new(self.arbitration(offer), false),
+            AccountMeta::new_readonly(self.arbiter_registry(), false),
+            AccountMeta::new_readonly(sysvar::slot_hashes::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(self.context.payer.pubkey(), true),
+        ];
+        self.instruction(&DLUInstruction::AssignArbiter { kind: DisputeKind::Chargeback }, accounts)
+    }
+
+    /// Draws an arbiter for the offer's chargeback, returning the arbitration.
+    pub async fn assign_arbiter(&mut self, offer: Pubkey) -> Arbitration {
+        let instruction = self.assign_arbiter_instruction(offer);
+        self.process(instruction, &[]).await;
+        self.load_arbitration(offer).await
+    }
+
+    pub async fn load_arbitration(&mut self, offer: Pubkey) -> Arbitration {
+        self.decode(self.arbitration(offer), Arbitration::deserialize).await
+    }
+
+    pub fn rule_dispute_instruction(&self, offer: Pubkey, arbiter: &Party, for_buyer: bool) -> Instruction {
+        let accounts = vec![
+            AccountMeta::new_readonly(self.config(), false),
+            AccountMeta::new_readonly(arbiter.owner.pubkey(), true),
+            AccountMeta::new(self.arbiter(arbiter), false),
+            AccountMeta::new_readonly(self.arbiter_registry(), false),
+            AccountMeta::new_readonly(offer, false),
+            AccountMeta::new(self.arbitration(offer), false),
+            AccountMeta::new(arbiter.token, false),
+            AccountMeta::new(self.treasury(), false),
+        ];
+        self.instruction(&DLUInstruction::RuleDispute { kind: DisputeKind::Chargeback, for_buyer }, accounts)
+    }
+
+    pub async fn rule_dispute(&mut self, offer: Pubkey, arbiter: &Party, for_buyer: bool) {
+        self.process(self.rule_dispute_instruction(offer, arbiter, for_buyer), &[&arbiter.owner]).await;
+    }
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use common::{DealKind, Harness, Party};
+use luda::addressing::{derive_reward_emission_address, derive_rewards_vault_address};
+use luda::config::Config;
+use luda::emission::RewardEmission;
+use luda::errors::DLUError;
+use luda::instruction::DLUInstruction;
+use luda::time::SECONDS_PER_DAY;
+use solana_program::instruction::{AccountMeta, InstructionError};
+use solana_program::pubkey::Pubkey;
+use solana_program::system_program;
+use solana_sdk::signature::Signer;
+use solana_sdk::transaction::TransactionError;
+
+const REWARD: u64 = 1_000;
+const HALF_LIFE: u64 = SECONDS_PER_DAY as u64;
+
+fn custom(code: DLUError) -> TransactionError {
+    TransactionError::InstructionError(0, InstructionError::Custom(code.code()))
+}
+
+fn rewards_vault(h: &Harness) -> Pubkey {
+    derive_rewards_vault_address(&h.program_id, &h.mint).0
+}
+
+async fn emission(h: &mut Harness) -> RewardEmission {
+    h.decode(derive_reward_emission_address(&h.program_id, &h.mint).0, RewardEmission::deserialize).await
+}
+
+/// Sells an offer from `seller` to `buyer`, passing the completion rewards of the mint, whose
+/// emission the payer funds on the first reward.
+async fn sell(h: &mut Harness, seller: &Party, buyer: &Party) {
+    let (offer, _) = h.list_deal(DealKind::Offer, seller, buyer).await;
+    h.accept_deal(DealKind::Offer, offer, seller, buyer).await;
+    let mut instruction = h.complete_deal_instruction(DealKind::Offer, offer, seller, buyer).await;
+    instruction.accounts.extend([
+        AccountMeta::new(derive_reward_emission_address(&h.program_id, &h.mint).0, false),
+        AccountMeta::new(rewards_vault(h), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new(h.context.payer.pubkey(), true),
+    ]);
+    h.process(instruction, &[]).await;
+}
+
+#[tokio::test]
+async fn completions_pay_both_parties_until_the_emission_cap() {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    let vault = rewards_vault(&h);
+    h.set_token_account(vault, vault, 10 * REWARD);
+
+    let schedule = |half_life| DLUInstruction::SetCompletionRewards { reward: REWARD, half_life, emission_cap: 3 * REWARD };
+    let accounts = vec![AccountMeta::new(h.config(), false), AccountMeta::new_readonly(h.context.payer.pubkey(), true)];
+    let error = h.try_process_all(&[h.instruction(&schedule(0), accounts.clone())], &[]).await;
+    assert_eq!(error.unwrap_err(), custom(DLUError::InvalidRewardSchedule));
+    h.process(h.instruction(&schedule(HALF_LIFE), accounts), &[]).await;
+
+    sell(&mut h, &seller, &buyer).await;
+    assert_eq!(h.balance(vault).await, 8 * REWARD);
+
+    // The cap leaves half a reward for each party of the second deal, and none for the third.
+    sell(&mut h, &seller, &buyer).await;
+    sell(&mut h, &seller, &buyer).await;
+    assert_eq!(h.balance(vault).await, 7 * REWARD);
+    let emission = emission(&mut h).await;
+    assert_eq!((emission.emitted, emission.rewarded_completions), (3 * REWARD, 2));
+}
+
+#[test]
+fn rewards_halve_over_every_half_life() {
+    let mut config = Config::new(Pubkey::new_unique(), u64::MAX, [0; 32]);
+    let governance = config.governance;
+    config.set_completion_rewards(&governance, REWARD, HALF_LIFE, 2 * REWARD).unwrap();
+    let mut emission = RewardEmission::new(Pubkey::new_unique(), 0);
+    let day = SECONDS_PER_DAY;
+
+    assert_eq!(emission.scheduled_reward(&config, day / 2), REWARD * 3 / 4);
+    assert_eq!(emission.emit(&config, 2, u64::MAX, day).unwrap(), REWARD / 2);
+    assert_eq!(emission.emit(&config, 2, u64::MAX, 2 * day).unwrap(), REWARD / 4);
+
+    // Short of the cap, rewards are limited by what the vault holds.
+    assert_eq!(emission.emit(&config, 2, 101, 2 * day).unwrap(), 50);
+    assert_eq!(emission.emitted, REWARD + REWARD / 2 + 100);
+}
@@ -0,0 +1,53 @@
+mod common;
+
+use common::{Harness, PAYMENT};
+use luda::compression::CompressedOffer;
+use luda::deal::{DealStatus, Location};
+use luda::errors::DLUError;
+use luda::offer::Offer;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+
+fn listing(seller: Pubkey) -> CompressedOffer {
+    CompressedOffer {
+        seller,
+        id: 7,
+        goodsorservice_name: "Bike".to_string(),
+        goodsorservice_description: "City bike, barely used".to_string(),
+        payment: PAYMENT,
+        payment_mint: Pubkey::new_unique(),
+        meeting_point: Location::new("DE", "Berlin".to_string(), "Alexanderplatz 1".to_string()).unwrap(),
+        meeting_datetime: 1_700_000_000,
+        listed_slot: 42,
+    }
+}
+
+#[test]
+fn leaves_commit_to_every_field_of_the_listing() {
+    let listing = listing(Pubkey::new_unique());
+    assert_eq!(listing.leaf().unwrap(), listing.clone().leaf().unwrap());
+
+    let relisted = CompressedOffer { listed_slot: 43, ..listing.clone() };
+    assert_ne!(relisted.leaf().unwrap(), listing.leaf().unwrap());
+    let repriced = CompressedOffer { payment: PAYMENT + 1, ..listing.clone() };
+    assert_ne!(repriced.leaf().unwrap(), listing.leaf().unwrap());
+}
+
+#[tokio::test]
+async fn accepted_listings_materialize_as_listed_offers_of_their_seller() {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let other = h.create_user("other").await;
+    let seller_user = h.user(&seller).await;
+
+    let offer = Offer::materialize(listing(seller.owner.pubkey()), &seller_user).unwrap();
+    assert_eq!(offer.status(), DealStatus::Listed);
+    assert_eq!(offer.payment(), PAYMENT);
+    assert_eq!(offer.escrowed_amount(), PAYMENT);
+    let entries = offer.audit_log().chronological();
+    assert_eq!(entries.len(), 1);
+    assert_eq!((entries[0].status, entries[0].slot, entries[0].actor), (DealStatus::Listed, 42, seller.owner.pubkey()));
+
+    let result = Offer::materialize(listing(other.owner.pubkey()), &seller_user);
+    assert!(matches!(result, Err(DLUError::KeyMismatch)));
+}
@@ -0,0 +1,165 @@
+// Compute-unit benchmarks. They run the SBF build, so build it first:
+//
+//     cargo build-sbf && cargo test --test compute_units -- --ignored --nocapture
+//
+// Every measured instruction must fit the default per-instruction budget, and must not
+// grow more than `REGRESSION_TOLERANCE_PERCENT` over the recorded baseline. After an
+// intended change, record a new baseline with `LUDA_UPDATE_CU_BASELINE=1`.
+mod common;
+
+use std::collections::BTreeMap;
+use std::fs;
+
+use common::{DealKind, Harness};
+
+const COMPUTE_BUDGET: u64 = 200_000;
+const REGRESSION_TOLERANCE_PERCENT: u64 = 10;
+const BASELINE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/compute_units.baseline");
+
+/// Listings a seller already has before the crowded listing is measured.
+const CROWDED_LISTINGS: usize = 32;
+const LONG_DESCRIPTION_LEN: usize = 1_000;
+
+#[derive(Default)]
+struct Report(BTreeMap<String, u64>);
+
+impl Report {
+    /// Records the units of the harness's last transaction, keeping the worst run.
+    fn record(&mut self, h: &Harness, name: String) {
+        let units = self.0.entry(name).or_default();
+        *units = (*units).max(h.last_units);
+    }
+}
+
+fn read_baseline() -> BTreeMap<String, u64> {
+    let Ok(baseline) = fs::read_to_string(BASELINE) else {
+        return BTreeMap::new();
+    };
+    baseline
+        .lines()
+        .filter_map(|line| {
+            let (name, units) = line.rsplit_once(' ')?;
+            Some((name.to_string(), units.parse().ok()?))
+        })
+        .collect()
+}
+
+fn write_baseline(report: &Report) {
+    let lines: Vec<String> = report.0.iter().map(|(name, units)| format!("{} {}", name, units)).collect();
+    fs::write(BASELINE, lines.join("\n") + "\n").unwrap();
+}
+
+async fn measure_deals(h: &mut Harness, report: &mut Report, kind: DealKind) {
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    report.record(h, "CreateUser".to_string());
+
+    let (deal, _) = h.list_deal(kind, &seller, &buyer).await;
+    report.record(h, format!("List{:?}", kind));
+    h.accept_deal(kind, deal, &seller, &buyer).await;
+    report.record(h, format!("Accept{:?}", kind));
+    h.complete_deal(kind, deal, &seller, &buyer).await;
+    report.record(h, format!("Complete{:?}", kind));
+
+    let (deal, _) = h.list_deal(kind, &seller, &buyer).await;
+    h.cancel_deal(kind, deal, &seller, &buyer).await;
+    report.record(h, format!("Cancel{:?}", kind));
+
+    let (deal, _) = h.list_deal(kind, &seller, &buyer).await;
+    h.accept_deal(kind, deal, &seller, &buyer).await;
+    h.fail_deal(kind, deal, &seller, &buyer).await;
+    report.record(h, format!("Fail{:?}", kind));
+
+    let (deal, meeting_datetime) = h.list_deal(kind, &seller, &buyer).await;
+    h.accept_deal(kind, deal, &seller, &buyer).await;
+    h.expire_clock(meeting_datetime).await;
+    h.expire_deal(kind, deal, &seller, &buyer).await;
+    report.record(h, format!("Expire{:?}", kind));
+}
+
+async fn measure_shipments(h: &mut Harness, report: &mut Report) {
+    let sender = h.create_user("sender").await;
+    let recipient = h.create_user("recipient").await;
+    let carrier = h.create_user("carrier").await;
+
+    let (shipment, _) = h.list_shipment(&sender, &recipient).await;
+    report.record(h, "ListShipment".to_string());
+    h.accept_shipment(shipment, &carrier).await;
+    report.record(h, "AcceptShipment".to_string());
+    h.confirm_pickup(shipment, &carrier).await;
+    report.record(h, "ConfirmPickup".to_string());
+    h.complete_shipment(shipment, &sender, &carrier).await;
+    report.record(h, "CompleteShipment".to_string());
+
+    let (shipment, _) = h.list_shipment(&sender, &recipient).await;
+    h.cancel_shipment(shipment, &sender).await;
+    report.record(h, "CancelShipment".to_string());
+
+    let (shipment, _) = h.list_shipment(&sender, &recipient).await;
+    h.accept_shipment(shipment, &carrier).await;
+    h.confirm_pickup(shipment, &carrier).await;
+    h.fail_shipment(shipment, &sender, &carrier).await;
+    report.record(h, "FailShipment".to_string());
+
+    let (shipment, drop_off_datetime) = h.list_shipment(&sender, &recipient).await;
+    h.accept_shipment(shipment, &carrier).await;
+    h.expire_clock(drop_off_datetime).await;
+    h.expire_shipment(shipment, &sender, &carrier).await;
+    report.record(h, "ExpireShipment".to_string());
+}
+
+/// Measures the listings whose cost grows with the data they carry: a long description,
+/// and a seller whose index already holds many offers.
+async fn measure_large_offers(h: &mut Harness, report: &mut Report) {
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+
+    let description = "x".repeat(LONG_DESCRIPTION_LEN);
+    let (offer, _) = h.list_described_deal(DealKind::Offer, &seller, &buyer, &description).await;
+    report.record(h, format!("ListOffer ({} byte description)", LONG_DESCRIPTION_LEN));
+    h.accept_deal(DealKind::Offer, offer, &seller, &buyer).await;
+    h.complete_deal(DealKind::Offer, offer, &seller, &buyer).await;
+    report.record(h, format!("CompleteOffer ({} byte description)", LONG_DESCRIPTION_LEN));
+
+    for _ in 0..CROWDED_LISTINGS {
+        h.list_deal(DealKind::Offer, &seller, &buyer).await;
+    }
+    let (offer, _) = h.list_deal(DealKind::Offer, &seller, &buyer).await;
+    report.record(h, format!("ListOffer ({} listed)", CROWDED_LISTINGS));
+    h.accept_deal(DealKind::Offer, offer, &seller, &buyer).await;
+    report.record(h, format!("AcceptOffer ({} listed)", CROWDED_LISTINGS));
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF build from `cargo build-sbf`"]
+async fn instructions_stay_within_compute_budget() {
+    let mut h = Harness::start_metered().await;
+    let mut report = Report::default();
+    measure_deals(&mut h, &mut report, DealKind::Offer).await;
+    measure_deals(&mut h, &mut report, DealKind::Request).await;
+    measure_shipments(&mut h, &mut report).await;
+    measure_large_offers(&mut h, &mut report).await;
+
+    if std::env::var_os("LUDA_UPDATE_CU_BASELINE").is_some() {
+        write_baseline(&report);
+    }
+    let baseline = read_baseline();
+
+    let mut failures = Vec::new();
+    println!("{:<40} {:>8} {:>8}", "instruction", "units", "baseline");
+    for (name, &units) in &report.0 {
+        let recorded = baseline.get(name).copied();
+        println!("{:<40} {:>8} {:>8}", name, units, recorded.map_or("-".to_string(), |units| units.to_string()));
+
+        if units > COMPUTE_BUDGET {
+            failures.push(format!("{} uses {} units, over the {} budget", name, units, COMPUTE_BUDGET));
+        }
+        if let Some(recorded) = recorded {
+            let allowed = recorded + recorded * REGRESSION_TOLERANCE_PERCENT / 100;
+            if units > allowed {
+                failures.push(format!("{} regressed from {} to {} units", name, recorded, units));
+            }
+        }
+    }
+    assert!(failures.is_empty(), "compute unit regressions:\n{}", failures.join("\n"));
+}
@@ -0,0 +1,113 @@
+mod common;
+
+use common::Harness;
+use luda::chargeback::MAX_CHARGEBACK_WINDOW;
+use luda::config::Config;
+use luda::errors::DLUError;
+use solana_program::instruction::InstructionError;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::TransactionError;
+
+fn custom(code: DLUError) -> TransactionError {
+    TransactionError::InstructionError(0, InstructionError::Custom(code.code()))
+}
+
+#[tokio::test]
+async fn only_the_upgrade_authority_initializes_the_config_and_only_once() {
+    let mut h = Harness::start().await;
+    let config = h.account(h.config()).await;
+    assert_eq!(config.owner, h.program_id);
+
+    let stranger = Keypair::new();
+    let instruction = h.initialize_config_instruction(stranger.pubkey());
+    let error = h.try_process_all(&[instruction], &[&stranger]).await;
+    assert_eq!(error, Err(custom(DLUError::NotAuthorized)));
+
+    // The harness initialized it already, with this very instruction.
+    h.next_blockhash().await;
+    let instruction = h.initialize_config_instruction(h.context.payer.pubkey());
+    let error = h.try_process_all(&[instruction], &[]).await;
+    assert_eq!(error, Err(custom(DLUError::AlreadyInitialized)));
+}
+
+#[test]
+fn penalty_split_keeps_the_whole_insurance() {
+    let governance = Pubkey::new_unique();
+    let mut config = Config::new(governance, 1_000, [0; 32]);
+    assert_eq!(config.split_penalty(101).unwrap(), (50, 51));
+
+    config.set_penalty_share(&governance, 10_000).unwrap();
+    assert_eq!(config.split_penalty(101).unwrap(), (101, 0));
+
+    assert!(matches!(config.set_penalty_share(&governance, 10_001), Err(DLUError::InvalidPenaltyShare)));
+}
+
+#[test]
+fn only_governance_sets_the_crank_bounty() {
+    let governance = Pubkey::new_unique();
+    let mut config = Config::new(governance, 1_000, [0; 32]);
+    assert_eq!(config.crank_bounty, luda::config::DEFAULT_CRANK_BOUNTY);
+
+    assert!(matches!(config.set_crank_bounty(&Pubkey::new_unique(), 5), Err(DLUError::NotAuthorized)));
+    config.set_crank_bounty(&governance, 5).unwrap();
+    assert_eq!(config.crank_bounty, 5);
+}
+
+#[test]
+fn chargeback_windows_are_bounded() {
+    let governance = Pubkey::new_unique();
+    let mut config = Config::new(governance, 1_000, [0; 32]);
+    assert_eq!(config.chargeback_window, 0);
+
+    config.set_chargeback_window(&governance, MAX_CHARGEBACK_WINDOW).unwrap();
+    assert_eq!(config.chargeback_window, MAX_CHARGEBACK_WINDOW);
+    assert!(matches!(config.set_chargeback_window(&governance, MAX_CHARGEBACK_WINDOW + 1), Err(DLUError::InvalidChargebackWindow)));
+    assert!(matches!(config.set_chargeback_window(&governance, -1), Err(DLUError::InvalidChargebackWindow)));
+}
+
+#[test]
+fn only_governance_sets_the_arbitration_terms() {
+    let governance = Pubkey::new_unique();
+    let mut config = Config::new(governance, 1_000, [0; 32]);
+    assert_eq!(config.arbiter_min_stake, luda::config::DEFAULT_ARBITER_MIN_STAKE);
+    assert_eq!(config.arbitration_fee, luda::config::DEFAULT_ARBITRATION_FEE);
+
+    assert!(matches!(config.set_arbitration_terms(&Pubkey::new_unique(), 1, 1), Err(DLUError::NotAuthorized)));
+    config.set_arbitration_terms(&governance, 10, 2).unwrap();
+    assert_eq!((config.arbiter_min_stake, config.arbitration_fee), (10, 2));
+}
+
+#[test]
+fn only_governance_sets_the_appeal_bond() {
+    let governance = Pubkey::new_unique();
+    let mut config = Config::new(governance, 1_000, [0; 32]);
+    assert_eq!(config.appeal_bond, luda::config::DEFAULT_APPEAL_BOND);
+
+    assert!(matches!(config.set_appeal_bond(&Pubkey::new_unique(), 1), Err(DLUError::NotAuthorized)));
+    config.set_appeal_bond(&governance, 0).unwrap();
+    assert_eq!(config.appeal_bond, 0);
+}
+
+#[test]
+fn kyc_is_off_until_governance_sets_a_threshold() {
+    let governance = Pubkey::new_unique();
+    let mut config = Config::new(governance, 1_000, [0; 32]);
+    assert!(!config.requires_attestation(u64::MAX));
+
+    assert!(matches!(config.set_kyc_threshold(&Pubkey::new_unique(), 5), Err(DLUError::NotAuthorized)));
+    config.set_kyc_threshold(&governance, 5).unwrap();
+    assert!(!config.requires_attestation(5));
+    assert!(config.requires_attestation(6));
+}
+
+#[test]
+fn referral_shares_are_bounded() {
+    let governance = Pubkey::new_unique();
+    let mut config = Config::new(governance, 1_000, [0; 32]);
+    assert_eq!(config.referral_share(1_000).unwrap(), 200);
+
+    assert!(matches!(config.set_referral_share(&governance, 10_001), Err(DLUError::InvalidReferralShare)));
+    config.set_referral_share(&governance, 10_000).unwrap();
+    assert_eq!(config.referral_share(1_000).unwrap(), 1_000);
+}
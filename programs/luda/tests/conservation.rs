@@ -0,0 +1,214 @@
+mod common;
+
+use common::{DealKind, Harness, Party};
+use luda::config::{DEFAULT_FAIL_BOND, MAX_FEE_BPS};
+use proptest::prelude::*;
+use solana_program::pubkey::Pubkey;
+
+const USERS: usize = 4;
+const SLOTS: usize = 4;
+
+#[derive(Clone, Copy, Debug)]
+enum Flow {
+    Offer,
+    Request,
+    Shipment,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Stage {
+    Listed,
+    Accepted,
+    Closed,
+}
+
+/// An entity the scenario is driving, with the users playing its roles: seller and buyer
+/// for deals, sender, recipient and carrier for shipments.
+struct Tracked {
+    flow: Flow,
+    address: Pubkey,
+    deadline: i64,
+    roles: [usize; 3],
+    stage: Stage,
+}
+
+/// One scenario step: advance the entity in `slot` along the transition picked by
+/// `choice`, or list a new one there with the users starting at `first_user`.
+#[derive(Clone, Debug)]
+struct Step {
+    slot: usize,
+    choice: u8,
+    first_user: usize,
+}
+
+fn steps() -> impl Strategy<Value = Vec<Step>> {
+    prop::collection::vec(
+        (0..SLOTS, any::<u8>(), 0..USERS).prop_map(|(slot, choice, first_user)| Step { slot, choice, first_user }),
+        1..24,
+    )
+}
+
+fn flow_of(slot: usize) -> Flow {
+    match slot % 3 {
+        0 => Flow::Offer,
+        1 => Flow::Request,
+        _ => Flow::Shipment,
+    }
+}
+
+fn deal_kind(flow: Flow) -> DealKind {
+    match flow {
+        Flow::Offer => DealKind::Offer,
+        _ => DealKind::Request,
+    }
+}
+
+struct Scenario {
+    h: Harness,
+    users: Vec<Party>,
+    slots: Vec<Option<Tracked>>,
+    bonds: u64,  // Fail bonds posted, which stay in escrow after their entity closes.
+    supply: u64,
+}
+
+impl Scenario {
+    async fn start(fee_bps: u16) -> Self {
+        let mut h = Harness::start().await;
+        h.set_fee(fee_bps).await;
+        let mut users = Vec::new();
+        for i in 0..USERS {
+            users.push(h.create_user(&format!("user{}", i)).await);
+        }
+        let mut scenario = Scenario { h, users, slots: (0..SLOTS).map(|_| None).collect(), bonds: 0, supply: 0 };
+        scenario.supply = scenario.total_tokens().await;
+        scenario
+    }
+
+    /// Sums every token account that can hold DLU in the scenario.
+    async fn total_tokens(&mut self) -> u64 {
+        let mut accounts: Vec<Pubkey> = self.users.iter().map(|user| user.token).collect();
+        accounts.extend([self.h.escrow, self.h.penalty, self.h.treasury()]);
+        let mut total = 0;
+        for account in accounts {
+            total += self.h.balance(account).await;
+        }
+        total
+    }
+
+    /// Sums what the program believes it holds in escrow across all live entities.
+    async fn locked(&mut self) -> u64 {
+        let mut locked = self.bonds * DEFAULT_FAIL_BOND;
+        for tracked in self.slots.iter().flatten() {
+            locked += match tracked.flow {
+                Flow::Shipment => self.h.shipment(tracked.address).await.escrowed_amount(),
+                flow => self.h.locked(deal_kind(flow), tracked.address).await,
+            };
+        }
+        locked
+    }
+
+    async fn apply(&mut self, step: &Step) {
+        let stage = self.slots[step.slot].as_ref().map_or(Stage::Closed, |tracked| tracked.stage);
+        match stage {
+            Stage::Closed => self.list(step).await,
+            Stage::Listed if step.choice % 2 == 0 => self.accept(step.slot).await,
+            Stage::Listed => self.cancel(step.slot).await,
+            Stage::Accepted => self.settle(step.slot, step.choice % 3).await,
+        }
+    }
+
+    async fn list(&mut self, step: &Step) {
+        let flow = flow_of(step.slot);
+        let roles = [0, 1, 2].map(|offset| (step.first_user + offset) % USERS);
+        let [first, second, _] = roles;
+        let (address, deadline) = match flow {
+            Flow::Shipment => self.h.list_shipment(&self.users[first], &self.users[second]).await,
+            flow => self.h.list_deal(deal_kind(flow), &self.users[first], &self.users[second]).await,
+        };
+        self.slots[step.slot] = Some(Tracked { flow, address, deadline, roles, stage: Stage::Listed });
+    }
+
+    async fn accept(&mut self, slot: usize) {
+        let tracked = self.slots[slot].as_mut().unwrap();
+        let [first, second, third] = tracked.roles;
+        match tracked.flow {
+            Flow::Shipment => self.h.accept_shipment(tracked.address, &self.users[third]).await,
+            flow => {
+                self.h.accept_deal(deal_kind(flow), tracked.address, &self.users[first], &self.users[second]).await
+            }
+        }
+        tracked.stage = Stage::Accepted;
+    }
+
+    async fn cancel(&mut self, slot: usize) {
+        let tracked = self.slots[slot].as_mut().unwrap();
+        let [first, second, _] = tracked.roles;
+        match tracked.flow {
+            Flow::Shipment => self.h.cancel_shipment(tracked.address, &self.users[first]).await,
+            flow => {
+                self.h.cancel_deal(deal_kind(flow), tracked.address, &self.users[first], &self.users[second]).await
+            }
+        }
+        tracked.stage = Stage::Closed;
+    }
+
+    /// Completes (0), fails (1) or expires (2) an accepted entity.
+    async fn settle(&mut self, slot: usize, outcome: u8) {
+        let tracked = self.slots[slot].as_mut().unwrap();
+        let [first, second, third] = tracked.roles;
+        let (address, users) = (tracked.address, &self.users);
+        match (tracked.flow, outcome) {
+            (Flow::Shipment, 0) => {
+                self.h.confirm_pickup(address, &users[third]).await;
+                self.h.complete_shipment(address, &users[first], &users[third]).await
+            }
+            (Flow::Shipment, 1) => {
+                self.h.confirm_pickup(address, &users[third]).await;
+                self.h.fail_shipment(address, &users[first], &users[third]).await
+            }
+            (Flow::Shipment, _) => {
+                self.h.expire_clock(tracked.deadline).await;
+                self.h.expire_shipment(address, &users[first], &users[third]).await
+            }
+            (flow, 0) => self.h.complete_deal(deal_kind(flow), address, &users[first], &users[second]).await,
+            (flow, 1) => self.h.fail_deal(deal_kind(flow), address, &users[first], &users[second]).await,
+            (flow, _) => {
+                self.h.expire_clock(tracked.deadline).await;
+                self.h.expire_deal(deal_kind(flow), address, &users[first], &users[second]).await
+            }
+        }
+        if outcome == 1 {
+            self.bonds += 1;
+        }
+        tracked.stage = Stage::Closed;
+    }
+}
+
+/// Runs the steps, checking after each one that no DLU was minted or lost and that the
+/// escrow holds exactly what the program accounts for: releasing twice drains it below
+/// the locked amount, locking without a deposit leaves it short.
+async fn run(fee_bps: u16, steps: Vec<Step>) {
+    let mut scenario = Scenario::start(fee_bps).await;
+    for step in &steps {
+        scenario.apply(step).await;
+
+        let total = scenario.total_tokens().await;
+        assert_eq!(total, scenario.supply, "DLU supply changed after {:?}", step);
+        let escrow = scenario.h.escrow_balance().await;
+        let locked = scenario.locked().await;
+        assert_eq!(escrow, locked, "escrow diverged from the locked amount after {:?}", step);
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn escrow_is_conserved(fee_bps in 0..=MAX_FEE_BPS, steps in steps()) {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(run(fee_bps, steps));
+    }
+}
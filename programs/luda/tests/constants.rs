@@ -0,0 +1,94 @@
+use luda::addressing::EntityType;
+use luda::bond::FailBond;
+use luda::config::Config;
+use luda::constants::{self, CONFIG_SIZE, FAIL_BOND_SIZE, STATS_SIZE};
+use luda::stats::{Stats, VaultLock};
+use luda::vault_registry::MAX_REGISTERED_VAULTS;
+use solana_program::pubkey::Pubkey;
+
+#[test]
+fn sizes_match_serialized_accounts() {
+    let config = Config::new(Pubkey::new_unique(), 1_000, [0; 32]);
+    assert_eq!(config.serialize().unwrap().len(), CONFIG_SIZE);
+    let mut stats = Stats::new();
+    stats.vault_locks = (0..MAX_REGISTERED_VAULTS).map(|_| VaultLock { vault: Pubkey::new_unique(), amount: 1 }).collect();
+    assert_eq!(stats.serialize().unwrap().len(), STATS_SIZE);
+
+    let bond = FailBond::post(EntityType::Offer, Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), 1, 0);
+    assert_eq!(bond.serialize().unwrap().len(), FAIL_BOND_SIZE);
+}
+
+#[test]
+fn limits_follow_config_overrides() {
+    let governance = Pubkey::new_unique();
+    let mut config = Config::new(governance, 1_000, [0; 32]);
+    config.set_fail_bond(&governance, 42).unwrap();
+    config.set_fee(&governance, 25, true).unwrap();
+
+    let limits = constants::limits(&config);
+    assert_eq!(limits.tvl_cap, 1_000);
+    assert_eq!(limits.fail_bond, 42);
+    assert_eq!(limits.fee_bps, 25);
+    assert_eq!(limits.max_entity_id_len, constants::MAX_ENTITY_ID_LEN);
+}
+
+#[test]
+fn carrier_stake_size_matches_serialized_account() {
+    let stake = luda::stake::CarrierStake::new(Pubkey::new_unique(), Pubkey::new_unique());
+    assert_eq!(stake.serialize().unwrap().len(), constants::CARRIER_STAKE_SIZE);
+}
+
+#[test]
+fn review_size_matches_serialized_account() {
+    let review = luda::review::Review::submit(
+        EntityType::Shipment, 7, Pubkey::new_unique(), Pubkey::new_unique(), 5, [1; 32], 0,
+    ).unwrap();
+    assert_eq!(review.serialize().unwrap().len(), constants::REVIEW_SIZE);
+}
+
+#[test]
+fn quote_size_fits_the_longest_note() {
+    let quote = luda::quote::Quote {
+        request: Pubkey::new_unique(),
+        seller: Pubkey::new_unique(),
+        mint: Pubkey::new_unique(),
+        price: 1,
+        note: "x".repeat(constants::MAX_QUOTE_NOTE_LEN),
+        request_generation: 0,
+        submitted_at: 0,
+        status: luda::quote::QuoteStatus::Open,
+    };
+    assert_eq!(quote.serialize().unwrap().len(), constants::QUOTE_SIZE);
+}
+
+#[test]
+fn region_index_size_fits_a_full_ring() {
+    let mut index = luda::region::RegionIndex::default();
+    for listed_at in 0..=constants::MAX_REGION_LISTINGS as i64 {
+        let listing = luda::region::RegionListing { address: Pubkey::new_unique(), entity_type: EntityType::Offer, listed_at };
+        index.add(*b"u33d", listing);
+    }
+    assert_eq!(index.serialize().unwrap().len(), constants::REGION_INDEX_SIZE);
+    // The listing past the capacity replaced the oldest one.
+    assert_eq!(index.listings[0].listed_at, constants::MAX_REGION_LISTINGS as i64);
+}
+
+#[test]
+fn message_log_size_fits_a_full_ring() {
+    let mut log = luda::messaging::MessageLog::default();
+    let (entity, sender) = (Pubkey::new_unique(), Pubkey::new_unique());
+    for sent_at in 0..=constants::MAX_MESSAGES as i64 {
+        log.send(entity, &[sender], sender, vec![0; constants::MAX_MESSAGE_LEN], sent_at).unwrap();
+    }
+    assert_eq!(log.serialize().unwrap().len(), constants::MESSAGE_LOG_SIZE);
+    // The message past the capacity replaced the oldest one.
+    assert_eq!(log.messages[0].sent_at, constants::MAX_MESSAGES as i64);
+}
+
+#[test]
+fn penalty_pool_sizes_match_serialized_accounts() {
+    let pool = luda::penalty_pool::PenaltyPool::new(Pubkey::new_unique());
+    assert_eq!(pool.serialize().unwrap().len(), constants::PENALTY_POOL_SIZE);
+    let rewards = luda::penalty_pool::PenaltyRewards::new(Pubkey::new_unique());
+    assert_eq!(rewards.serialize().unwrap().len(), constants::PENALTY_REWARDS_SIZE);
+}
@@ -0,0 +1,170 @@
+mod common;
+
+use common::{Harness, Party, PAYMENT};
+use luda::addressing::{
+    derive_dao_address, derive_proposal_address, derive_timelock_address, derive_vote_lock_address, derive_vote_record_address,
+};
+use luda::config::Config;
+use luda::dao::{Proposal, ProposalStatus};
+use luda::errors::DLUError;
+use luda::instruction::DLUInstruction;
+use luda::time::SECONDS_PER_DAY;
+use luda::timelock::{GovernanceAction, GOVERNANCE_TIMELOCK};
+use solana_program::instruction::{AccountMeta, Instruction, InstructionError};
+use solana_program::pubkey::Pubkey;
+use solana_program::system_program;
+use solana_sdk::signature::Signer;
+use solana_sdk::transaction::TransactionError;
+
+const VOTING_PERIOD: i64 = SECONDS_PER_DAY;
+
+fn custom(code: DLUError) -> TransactionError {
+    TransactionError::InstructionError(0, InstructionError::Custom(code.code()))
+}
+
+fn dao(h: &Harness) -> Pubkey {
+    derive_dao_address(&h.program_id).0
+}
+
+/// The system program and the harness payer, funding the accounts a DAO instruction creates.
+fn rent_metas(h: &Harness) -> [AccountMeta; 2] {
+    [AccountMeta::new_readonly(system_program::id(), false), AccountMeta::new(h.context.payer.pubkey(), true)]
+}
+
+fn vote_lock_instruction(h: &Harness, party: &Party, instruction: &DLUInstruction) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new_readonly(dao(h), false),
+        AccountMeta::new(derive_vote_lock_address(&h.program_id, &party.owner.pubkey()).0, false),
+        AccountMeta::new_readonly(party.owner.pubkey(), true),
+        AccountMeta::new(party.token, false),
+        AccountMeta::new(h.vault(), false),
+        AccountMeta::new_readonly(h.vault_registry(), false),
+    ];
+    if matches!(instruction, DLUInstruction::LockVotes { .. }) {
+        accounts.extend(rent_metas(h));
+    }
+    h.instruction(instruction, accounts)
+}
+
+fn vote_instruction(h: &Harness, voter: &Party, support: bool) -> Instruction {
+    let proposal = derive_proposal_address(&h.program_id, 0).0;
+    let mut accounts = vec![
+        AccountMeta::new(proposal, false),
+        AccountMeta::new(derive_vote_lock_address(&h.program_id, &voter.owner.pubkey()).0, false),
+        AccountMeta::new_readonly(voter.owner.pubkey(), true),
+        AccountMeta::new(derive_vote_record_address(&h.program_id, &proposal, &voter.owner.pubkey()).0, false),
+    ];
+    accounts.extend(rent_metas(h));
+    h.instruction(&DLUInstruction::CastVote { support }, accounts)
+}
+
+fn finalize_instruction(h: &Harness) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(dao(h), false),
+        AccountMeta::new(derive_proposal_address(&h.program_id, 0).0, false),
+        AccountMeta::new(derive_timelock_address(&h.program_id).0, false),
+    ];
+    h.instruction(&DLUInstruction::FinalizeProposal, accounts)
+}
+
+/// Sets up a DAO voting with the harness mint.
+async fn start() -> Harness {
+    let mut h = Harness::start().await;
+
+    let instruction =
+        DLUInstruction::InitializeDao { vote_mint: h.mint, quorum: PAYMENT, proposal_threshold: PAYMENT / 2, voting_period: VOTING_PERIOD };
+    let accounts = vec![
+        AccountMeta::new_readonly(h.config(), false),
+        AccountMeta::new(h.context.payer.pubkey(), true),
+        AccountMeta::new(dao(&h), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    h.process(h.instruction(&instruction, accounts), &[]).await;
+    h
+}
+
+async fn proposal(h: &mut Harness) -> Proposal {
+    h.decode(derive_proposal_address(&h.program_id, 0).0, Proposal::deserialize).await
+}
+
+fn propose_instruction(h: &Harness, proposer: &Party, action: GovernanceAction) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(dao(h), false),
+        AccountMeta::new(derive_proposal_address(&h.program_id, 0).0, false),
+        AccountMeta::new_readonly(derive_vote_lock_address(&h.program_id, &proposer.owner.pubkey()).0, false),
+        AccountMeta::new_readonly(proposer.owner.pubkey(), true),
+    ];
+    accounts.extend(rent_metas(h));
+    h.instruction(&DLUInstruction::CreateProposal { action }, accounts)
+}
+
+#[tokio::test]
+async fn passed_proposals_update_the_config_through_the_timelock() {
+    let mut h = start().await;
+    let vault = h.vault();
+    let alice = h.create_user("alice").await;
+    let bob = h.create_user("bob").await;
+    let before = h.balance(alice.token).await;
+
+    h.process(vote_lock_instruction(&h, &alice, &DLUInstruction::LockVotes { amount: PAYMENT }), &[&alice.owner]).await;
+    h.process(vote_lock_instruction(&h, &bob, &DLUInstruction::LockVotes { amount: PAYMENT / 4 }), &[&bob.owner]).await;
+    assert_eq!(h.balance(vault).await, PAYMENT + PAYMENT / 4);
+
+    // Bob hasn't locked enough to propose.
+    let action = GovernanceAction::SetFee { fee_bps: 150, exempt_credible: false };
+    let error = h.try_process_all(&[propose_instruction(&h, &bob, action.clone())], &[&bob.owner]).await;
+    assert_eq!(error.unwrap_err(), custom(DLUError::InsufficientVotingPower));
+    h.process(propose_instruction(&h, &alice, action), &[&alice.owner]).await;
+    let proposed_at = h.now().await;
+
+    h.process(vote_instruction(&h, &alice, true), &[&alice.owner]).await;
+    h.process(vote_instruction(&h, &bob, false), &[&bob.owner]).await;
+    h.next_blockhash().await;
+    let error = h.try_process_all(&[vote_instruction(&h, &alice, true)], &[&alice.owner]).await;
+    assert_eq!(error.unwrap_err(), custom(DLUError::AlreadyVoted));
+
+    // Votes stay locked while the vote is open.
+    let unlock = vote_lock_instruction(&h, &alice, &DLUInstruction::UnlockVotes { amount: PAYMENT });
+    let error = h.try_process_all(&[unlock], &[&alice.owner]).await;
+    assert_eq!(error.unwrap_err(), custom(DLUError::VotesLocked));
+    let error = h.try_process_all(&[finalize_instruction(&h)], &[]).await;
+    assert_eq!(error.unwrap_err(), custom(DLUError::VotingNotEnded));
+
+    h.warp_to(proposed_at + VOTING_PERIOD).await;
+    h.next_blockhash().await;
+    h.process(finalize_instruction(&h), &[]).await;
+    let passed = proposal(&mut h).await;
+    assert_eq!((passed.status, passed.queued_action), (ProposalStatus::Passed, Some(0)));
+    assert_eq!((passed.votes_for, passed.votes_against), (PAYMENT, PAYMENT / 4));
+
+    // The update waits out the timelock like governance's own.
+    h.warp_to(proposed_at + VOTING_PERIOD + GOVERNANCE_TIMELOCK).await;
+    let accounts = vec![AccountMeta::new(h.config(), false), AccountMeta::new(derive_timelock_address(&h.program_id).0, false)];
+    h.process(h.instruction(&DLUInstruction::ExecuteGovernanceAction { id: 0 }, accounts), &[]).await;
+    assert_eq!(h.decode(h.config(), Config::deserialize).await.fee_bps, 150);
+
+    h.next_blockhash().await;
+    h.process(vote_lock_instruction(&h, &alice, &DLUInstruction::UnlockVotes { amount: PAYMENT }), &[&alice.owner]).await;
+    assert_eq!(h.balance(alice.token).await, before);
+}
+
+#[tokio::test]
+async fn proposals_short_of_quorum_are_rejected() {
+    let mut h = start().await;
+    let alice = h.create_user("alice").await;
+
+    h.process(vote_lock_instruction(&h, &alice, &DLUInstruction::LockVotes { amount: PAYMENT / 2 }), &[&alice.owner]).await;
+    let action = GovernanceAction::SetPenaltyShare { share_bps: 10_000 };
+    h.process(propose_instruction(&h, &alice, action), &[&alice.owner]).await;
+    let proposed_at = h.now().await;
+    h.process(vote_instruction(&h, &alice, true), &[&alice.owner]).await;
+
+    h.warp_to(proposed_at + VOTING_PERIOD).await;
+    h.process(finalize_instruction(&h), &[]).await;
+    let rejected = proposal(&mut h).await;
+    assert_eq!((rejected.status, rejected.queued_action), (ProposalStatus::Rejected, None));
+
+    h.next_blockhash().await;
+    let error = h.try_process_all(&[finalize_instruction(&h)], &[]).await;
+    assert_eq!(error.unwrap_err(), custom(DLUError::VotingClosed));
+}
@@ -0,0 +1,89 @@
+mod common;
+
+use common::{DealKind, Harness};
+use luda::addressing::EntityType;
+use luda::errors::DLUError;
+use luda::instruction::DLUInstruction;
+use luda::time::{self, SECONDS_PER_DAY};
+use solana_program::instruction::{AccountMeta, Instruction, InstructionError};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::TransactionError;
+
+fn extend_instruction(
+    h: &Harness,
+    entity_type: EntityType,
+    entity: Pubkey,
+    first: &Keypair,
+    second: &Keypair,
+    deadline: i64,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new(entity, false),
+        AccountMeta::new_readonly(first.pubkey(), true),
+        AccountMeta::new_readonly(second.pubkey(), true),
+    ];
+    h.instruction(&DLUInstruction::ExtendDeadline { entity_type, deadline }, accounts)
+}
+
+fn custom(code: DLUError) -> TransactionError {
+    TransactionError::InstructionError(0, InstructionError::Custom(code.code()))
+}
+
+#[tokio::test]
+async fn both_parties_reschedule_a_meeting() {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    let (offer, meeting_datetime) = h.list_deal(DealKind::Offer, &seller, &buyer).await;
+    h.accept_deal(DealKind::Offer, offer, &seller, &buyer).await;
+    let rescheduled = meeting_datetime + 2 * SECONDS_PER_DAY;
+
+    // Neither party moves the meeting alone.
+    let alone = extend_instruction(&h, EntityType::Offer, offer, &seller.owner, &seller.owner, rescheduled);
+    let error = h.try_process_all(&[alone], &[&seller.owner]).await;
+    assert_eq!(error.unwrap_err(), custom(DLUError::NotDealParty));
+
+    let earlier = extend_instruction(&h, EntityType::Offer, offer, &seller.owner, &buyer.owner, meeting_datetime - 1);
+    let error = h.try_process_all(&[earlier], &[&seller.owner, &buyer.owner]).await;
+    assert_eq!(error.unwrap_err(), custom(DLUError::InvalidDeadline));
+
+    let extend = extend_instruction(&h, EntityType::Offer, offer, &seller.owner, &buyer.owner, rescheduled);
+    h.process(extend, &[&seller.owner, &buyer.owner]).await;
+    assert_eq!(h.offer(offer).await.meeting_datetime(), rescheduled);
+
+    // The old meeting's grace period no longer expires the deal.
+    h.expire_clock(meeting_datetime).await;
+    let accounts = vec![
+        AccountMeta::new(offer, false),
+        AccountMeta::new(h.escrow, false),
+        AccountMeta::new_readonly(h.vault_registry(), false),
+        AccountMeta::new(seller.token, false),
+        AccountMeta::new(buyer.token, false),
+        AccountMeta::new(h.stats(), false),
+    ];
+    let expire = h.instruction(&DLUInstruction::ExpireOffer, accounts);
+    let error = h.try_process_all(&[expire], &[]).await;
+    assert_eq!(error.unwrap_err(), custom(DLUError::DealNotExpired));
+    h.complete_deal(DealKind::Offer, offer, &seller, &buyer).await;
+}
+
+#[tokio::test]
+async fn deadlines_that_passed_stay_passed() {
+    let mut h = Harness::start().await;
+    let sender = h.create_user("sender").await;
+    let carrier = h.create_user("carrier").await;
+    let recipient = h.create_user("recipient").await;
+    let (shipment, drop_off_datetime) = h.list_shipment(&sender, &recipient).await;
+    h.accept_shipment(shipment, &carrier).await;
+
+    let later = drop_off_datetime + SECONDS_PER_DAY;
+    let extend = extend_instruction(&h, EntityType::Shipment, shipment, &sender.owner, &carrier.owner, later);
+    h.process(extend, &[&sender.owner, &carrier.owner]).await;
+    assert_eq!(h.shipment(shipment).await.delivery_deadline(), later);
+
+    h.warp_to(time::expiry_time(later) + 1).await;
+    let late = extend_instruction(&h, EntityType::Shipment, shipment, &sender.owner, &carrier.owner, later + SECONDS_PER_DAY);
+    let error = h.try_process_all(&[late], &[&sender.owner, &carrier.owner]).await;
+    assert_eq!(error.unwrap_err(), custom(DLUError::InvalidDeadline));
+}
@@ -0,0 +1,39 @@
+mod common;
+
+use common::{Harness, Party};
+use luda::instruction::DLUInstruction;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+
+const PHOTO_DIGEST: [u8; 32] = [9; 32];
+
+fn proof_instruction(h: &Harness, shipment: Pubkey, carrier: &Party) -> Instruction {
+    let instruction = DLUInstruction::SubmitProof { proof_hash: PHOTO_DIGEST };
+    let accounts = vec![AccountMeta::new(shipment, false), AccountMeta::new_readonly(carrier.owner.pubkey(), true)];
+    h.instruction(&instruction, accounts)
+}
+
+#[tokio::test]
+async fn carriers_record_a_proof_that_outlives_completion() {
+    let mut h = Harness::start().await;
+    let sender = h.create_user("sender").await;
+    let recipient = h.create_user("recipient").await;
+    let carrier = h.create_user("carrier").await;
+    let (shipment, _) = h.list_shipment(&sender, &recipient).await;
+    h.accept_shipment(shipment, &carrier).await;
+
+    // There is nothing to prove before the carrier took custody.
+    let early = proof_instruction(&h, shipment, &carrier);
+    assert!(h.try_process_all(&[early], &[&carrier.owner]).await.is_err());
+
+    h.confirm_pickup(shipment, &carrier).await;
+    let impostor = proof_instruction(&h, shipment, &sender);
+    assert!(h.try_process_all(&[impostor], &[&sender.owner]).await.is_err());
+
+    h.process(proof_instruction(&h, shipment, &carrier), &[&carrier.owner]).await;
+    h.complete_shipment(shipment, &sender, &carrier).await;
+    let shipment = h.shipment(shipment).await;
+    assert!(shipment.is_completed());
+    assert_eq!(shipment.delivery_proof(), Some(PHOTO_DIGEST));
+}
@@ -0,0 +1,110 @@
+mod common;
+
+use common::{DealKind, Harness, PAYMENT};
+use luda::addressing::{derive_emergency_address, EntityType};
+use luda::deal::DealStatus;
+use luda::emergency::EMERGENCY_TIMELOCK;
+use luda::errors::DLUError;
+use luda::instruction::DLUInstruction;
+use solana_program::instruction::{AccountMeta, Instruction, InstructionError};
+use solana_program::pubkey::Pubkey;
+use solana_program::system_program;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::TransactionError;
+
+fn propose_instruction(h: &Harness, governance: &Pubkey, offer: Pubkey) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(h.config(), false),
+        AccountMeta::new(*governance, true),
+        AccountMeta::new_readonly(offer, false),
+        AccountMeta::new(derive_emergency_address(&h.program_id, &offer).0, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    h.instruction(&DLUInstruction::ProposeEmergencyResolve { entity_type: EntityType::Offer }, accounts)
+}
+
+fn resolve_instruction(h: &Harness, governance: &Pubkey, offer: Pubkey, seller_account: Pubkey, buyer_account: Pubkey) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(h.config(), false),
+        AccountMeta::new_readonly(*governance, true),
+        AccountMeta::new(offer, false),
+        AccountMeta::new(derive_emergency_address(&h.program_id, &offer).0, false),
+        AccountMeta::new(h.vault(), false),
+        AccountMeta::new_readonly(h.vault_registry(), false),
+        AccountMeta::new(seller_account, false),
+        AccountMeta::new(buyer_account, false),
+        AccountMeta::new(h.stats(), false),
+    ];
+    h.instruction(&DLUInstruction::EmergencyResolve { entity_type: EntityType::Offer }, accounts)
+}
+
+fn custom(code: DLUError) -> TransactionError {
+    TransactionError::InstructionError(0, InstructionError::Custom(code.code()))
+}
+
+#[tokio::test]
+async fn governance_unwinds_a_stuck_listing_after_the_timelock() {
+    let mut h = Harness::start().await;
+    let governance = h.context.payer.pubkey();
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    let (offer, _) = h.list_deal(DealKind::Offer, &seller, &buyer).await;
+    let vault = h.vault();
+    let before = h.balance(seller.token).await;
+
+    // Nothing moves without a proposal.
+    let unproposed = resolve_instruction(&h, &governance, offer, seller.token, buyer.token);
+    let error = h.try_process_all(&[unproposed], &[]).await;
+    assert_eq!(error.unwrap_err(), custom(DLUError::EmergencyNotProposed));
+
+    // Only governance proposes.
+    let outsider = Keypair::new();
+    let hijack = propose_instruction(&h, &outsider.pubkey(), offer);
+    let error = h.try_process_all(&[hijack], &[&outsider]).await;
+    assert_eq!(error.unwrap_err(), custom(DLUError::NotAuthorized));
+
+    h.process(propose_instruction(&h, &governance, offer), &[]).await;
+    let proposed_at = h.now().await;
+
+    h.next_blockhash().await;
+    let early = resolve_instruction(&h, &governance, offer, seller.token, buyer.token);
+    let error = h.try_process_all(&[early], &[]).await;
+    assert_eq!(error.unwrap_err(), custom(DLUError::EmergencyTimelocked));
+
+    h.warp_to(proposed_at + EMERGENCY_TIMELOCK).await;
+    h.process(resolve_instruction(&h, &governance, offer, seller.token, buyer.token), &[]).await;
+    assert_eq!(h.balance(seller.token).await, before + PAYMENT);
+    assert_eq!(h.balance(vault).await, 0);
+    assert_eq!(h.offer(offer).await.status(), DealStatus::Canceled);
+
+    // The proposal is consumed by the unwind.
+    h.next_blockhash().await;
+    let again = resolve_instruction(&h, &governance, offer, seller.token, buyer.token);
+    let error = h.try_process_all(&[again], &[]).await;
+    assert_eq!(error.unwrap_err(), custom(DLUError::EmergencyNotProposed));
+}
+
+#[tokio::test]
+async fn canceled_proposals_never_unwind() {
+    let mut h = Harness::start().await;
+    let governance = h.context.payer.pubkey();
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    let (offer, _) = h.list_deal(DealKind::Offer, &seller, &buyer).await;
+
+    h.process(propose_instruction(&h, &governance, offer), &[]).await;
+    let proposed_at = h.now().await;
+    let accounts = vec![
+        AccountMeta::new_readonly(h.config(), false),
+        AccountMeta::new_readonly(governance, true),
+        AccountMeta::new_readonly(offer, false),
+        AccountMeta::new(derive_emergency_address(&h.program_id, &offer).0, false),
+    ];
+    h.process(h.instruction(&DLUInstruction::CancelEmergencyResolve, accounts), &[]).await;
+
+    h.warp_to(proposed_at + EMERGENCY_TIMELOCK).await;
+    let resolve = resolve_instruction(&h, &governance, offer, seller.token, buyer.token);
+    let error = h.try_process_all(&[resolve], &[]).await;
+    assert_eq!(error.unwrap_err(), custom(DLUError::EmergencyNotProposed));
+    assert_eq!(h.offer(offer).await.status(), DealStatus::Listed);
+}
@@ -0,0 +1,17 @@
+use anchor_lang::error::ERROR_CODE_OFFSET;
+use luda::errors::DLUError;
+use solana_program::program_error::ProgramError;
+
+#[test]
+fn codes_are_offset_like_anchor_errors() {
+    assert_eq!(ProgramError::from(DLUError::InvalidInstruction), ProgramError::Custom(ERROR_CODE_OFFSET));
+    assert_eq!(DLUError::TvlCapExceeded.code(), ERROR_CODE_OFFSET + 17);
+    assert_eq!(DLUError::InsufficientFundsForInsurance.code(), ERROR_CODE_OFFSET + 25);
+}
+
+#[test]
+fn helper_messages_map_to_variants() {
+    assert_eq!(DLUError::from("Entity ID too long"), DLUError::EntityIdTooLong);
+    assert_eq!(DLUError::from("Failed to serialize User"), DLUError::SerializationFailed);
+    assert_eq!(DLUError::from("something unexpected"), DLUError::InvalidOperation);
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use common::{Harness, Party};
+use luda::addressing::derive_faucet_address;
+use luda::config::CAP_TEST_CLUSTER;
+use luda::errors::DLUError;
+use luda::instruction::DLUInstruction;
+use luda::time::FAUCET_COOLDOWN;
+use luda::user::FAUCET_AMOUNT;
+use solana_program::instruction::{AccountMeta, Instruction, InstructionError};
+use solana_program::program_option::COption;
+use solana_program::program_pack::Pack;
+use solana_sdk::signature::Signer;
+use solana_sdk::transaction::TransactionError;
+use spl_token::state::Mint;
+
+fn custom(code: DLUError) -> TransactionError {
+    TransactionError::InstructionError(0, InstructionError::Custom(code.code()))
+}
+
+fn faucet_instruction(h: &Harness, party: &Party) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new(party.user, false),
+        AccountMeta::new_readonly(party.owner.pubkey(), true),
+        AccountMeta::new_readonly(h.config(), false),
+        AccountMeta::new(party.token, false),
+        AccountMeta::new(h.mint, false),
+        AccountMeta::new_readonly(derive_faucet_address(&h.program_id).0, false),
+    ];
+    h.instruction(&DLUInstruction::RequestFaucet, accounts)
+}
+
+#[tokio::test]
+async fn test_clusters_hand_out_dlu_once_per_cooldown() {
+    let mut h = Harness::start().await;
+    let alice = h.onboard_user("alice", Some(0)).await;
+
+    // The test cluster's DLU mint is minted by the faucet.
+    let mut data = vec![0; Mint::LEN];
+    let faucet = derive_faucet_address(&h.program_id).0;
+    let mint = Mint { mint_authority: COption::Some(faucet), supply: 0, decimals: 6, is_initialized: true, freeze_authority: COption::None };
+    Mint::pack(mint, &mut data).unwrap();
+    h.set_account(h.mint, spl_token::id(), data);
+
+    // Mainnet never enables the faucet.
+    let error = h.try_process_all(&[faucet_instruction(&h, &alice)], &[&alice.owner]).await;
+    assert_eq!(error.unwrap_err(), custom(DLUError::FeatureDisabled));
+
+    let accounts = vec![AccountMeta::new(h.config(), false), AccountMeta::new_readonly(h.context.payer.pubkey(), true)];
+    h.process(h.instruction(&DLUInstruction::SetCapabilities { capabilities: CAP_TEST_CLUSTER }, accounts), &[]).await;
+    h.next_blockhash().await;
+    h.process(faucet_instruction(&h, &alice), &[&alice.owner]).await;
+    assert_eq!(h.balance(alice.token).await, FAUCET_AMOUNT);
+    let drawn_at = h.user(&alice).await.last_faucet_at;
+
+    h.next_blockhash().await;
+    let error = h.try_process_all(&[faucet_instruction(&h, &alice)], &[&alice.owner]).await;
+    assert_eq!(error.unwrap_err(), custom(DLUError::FaucetCoolingDown));
+
+    h.warp_to(drawn_at + FAUCET_COOLDOWN).await;
+    h.next_blockhash().await;
+    h.process(faucet_instruction(&h, &alice), &[&alice.owner]).await;
+    assert_eq!(h.balance(alice.token).await, 2 * FAUCET_AMOUNT);
+}
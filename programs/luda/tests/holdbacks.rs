@@ -0,0 +1,120 @@
+mod common;
+
+use common::{DealKind, Harness, Party, PAYMENT, STARTING_BALANCE};
+use luda::deal::Location;
+use luda::holdback::{HoldbackStatus, HoldbackTerms};
+use luda::instruction::DLUInstruction;
+use luda::offer::OfferKind;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+
+const OFFER_SPACE: usize = 4_096;
+const TERMS: HoldbackTerms = HoldbackTerms { bps: 1_000, window: 30 * 86_400 };
+const HELD: u64 = PAYMENT / 10;
+
+async fn list_warranted_offer(h: &mut Harness, seller: &Party, buyer: &Party) -> Pubkey {
+    let offer = Pubkey::new_unique();
+    h.set_program_account(offer, OFFER_SPACE);
+    let instruction = DLUInstruction::ListOffer {
+        id: 1,
+        goodsorservice_name: "Bicycle".to_string(),
+        goodsorservice_description: "Warranted for a month".to_string(),
+        payment: PAYMENT,
+        meeting_point: Location::new("DE", "Berlin".to_string(), "Alexanderplatz 1".to_string()).unwrap(),
+        meeting_datetime: h.now().await + 86_400,
+        kind: OfferKind::Described,
+        meeting_point_hash: None,
+        holdback: Some(TERMS),
+    };
+    let accounts = vec![
+        AccountMeta::new(offer, false),
+        AccountMeta::new_readonly(seller.user, false),
+        AccountMeta::new(seller.owner.pubkey(), true),
+        AccountMeta::new(seller.token, false),
+        AccountMeta::new(h.escrow, false),
+        AccountMeta::new_readonly(h.vault_registry(), false),
+        AccountMeta::new_readonly(h.config(), false),
+        AccountMeta::new(h.stats(), false),
+        AccountMeta::new(h.index(seller), false),
+    ];
+    h.process(h.instruction(&instruction, accounts), &[&seller.owner]).await;
+    h.accept_deal(DealKind::Offer, offer, seller, buyer).await;
+    h.complete_deal(DealKind::Offer, offer, seller, buyer).await;
+    offer
+}
+
+fn release_instruction(h: &Harness, offer: Pubkey, seller: &Party) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new(offer, false),
+        AccountMeta::new_readonly(seller.owner.pubkey(), true),
+        AccountMeta::new(seller.token, false),
+        AccountMeta::new(h.escrow, false),
+        AccountMeta::new_readonly(h.vault_registry(), false),
+        AccountMeta::new(h.stats(), false),
+    ];
+    h.instruction(&DLUInstruction::ReleaseHoldback, accounts)
+}
+
+fn dispute_instruction(h: &Harness, offer: Pubkey, buyer: &Party) -> Instruction {
+    let accounts = vec![AccountMeta::new(offer, false), AccountMeta::new_readonly(buyer.owner.pubkey(), true)];
+    h.instruction(&DLUInstruction::DisputeHoldback, accounts)
+}
+
+#[tokio::test]
+async fn the_seller_claims_an_undisputed_holdback_after_its_window() {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    let offer = list_warranted_offer(&mut h, &seller, &buyer).await;
+    assert_eq!(h.offer(offer).await.escrowed_amount(), HELD);
+    assert_eq!(h.balance(seller.token).await, STARTING_BALANCE + PAYMENT - HELD);
+
+    let early = release_instruction(&h, offer, &seller);
+    assert!(h.try_process_all(&[early], &[&seller.owner]).await.is_err());
+
+    let release_at = h.offer(offer).await.holdback().unwrap().release_at;
+    h.expire_clock(release_at).await;
+    // Once the window has passed, the buyer can no longer dispute the goods.
+    let late = dispute_instruction(&h, offer, &buyer);
+    assert!(h.try_process_all(&[late], &[&buyer.owner]).await.is_err());
+
+    h.process(release_instruction(&h, offer, &seller), &[&seller.owner]).await;
+    assert_eq!(h.offer(offer).await.escrowed_amount(), 0);
+    assert_eq!(h.balance(seller.token).await, STARTING_BALANCE + PAYMENT);
+    assert_eq!(h.escrow_balance().await, 0);
+}
+
+#[tokio::test]
+async fn a_defective_ruling_refunds_the_holdback_to_the_buyer() {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    let offer = list_warranted_offer(&mut h, &seller, &buyer).await;
+
+    let impostor = dispute_instruction(&h, offer, &seller);
+    assert!(h.try_process_all(&[impostor], &[&seller.owner]).await.is_err());
+    h.process(dispute_instruction(&h, offer, &buyer), &[&buyer.owner]).await;
+
+    // A disputed holdback stays frozen past its window.
+    let release_at = h.offer(offer).await.holdback().unwrap().release_at;
+    h.expire_clock(release_at).await;
+    let frozen = release_instruction(&h, offer, &seller);
+    assert!(h.try_process_all(&[frozen], &[&seller.owner]).await.is_err());
+
+    let accounts = vec![
+        AccountMeta::new_readonly(h.config(), false),
+        AccountMeta::new_readonly(h.context.payer.pubkey(), true),
+        AccountMeta::new(offer, false),
+        AccountMeta::new(h.escrow, false),
+        AccountMeta::new_readonly(h.vault_registry(), false),
+        AccountMeta::new(buyer.token, false),
+        AccountMeta::new(h.stats(), false),
+    ];
+    let ruling = h.instruction(&DLUInstruction::ResolveHoldbackDispute { defective: true }, accounts);
+    h.process(ruling, &[]).await;
+
+    assert_eq!(h.offer(offer).await.holdback().unwrap().status, HoldbackStatus::Refunded);
+    assert_eq!(h.balance(buyer.token).await, STARTING_BALANCE - PAYMENT + HELD);
+    assert_eq!(h.escrow_balance().await, 0);
+}
@@ -0,0 +1,61 @@
+use luda::errors::DLUError;
+use luda::instruction::{DLUInstruction, INSTRUCTION_VERSION};
+use solana_program::borsh::BorshSerialize;
+use serde_json::Value;
+
+fn idl() -> Value {
+    serde_json::from_str(include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/../../idl/luda.json"))).unwrap()
+}
+
+fn pascal_case(name: &str) -> String {
+    name.split('_').map(|word| word[..1].to_uppercase() + &word[1..]).collect()
+}
+
+#[test]
+fn discriminators_follow_the_instruction_enum() {
+    let idl = idl();
+    let instructions = idl["instructions"].as_array().unwrap();
+
+    for (index, instruction) in instructions.iter().enumerate() {
+        assert_eq!(instruction["discriminator"], serde_json::json!([INSTRUCTION_VERSION, index]));
+
+        // Instructions without arguments decode from the tag alone, which pins the name too.
+        if instruction["args"].as_array().unwrap().is_empty() {
+            let decoded = DLUInstruction::unpack(&[INSTRUCTION_VERSION, index as u8]).unwrap();
+            assert_eq!(format!("{:?}", decoded), pascal_case(instruction["name"].as_str().unwrap()));
+        }
+    }
+
+    assert!(DLUInstruction::unpack(&[INSTRUCTION_VERSION, instructions.len() as u8]).is_err());
+}
+
+#[test]
+fn only_the_current_version_decodes() {
+    let instruction = DLUInstruction::CompleteShipment {
+        carrier_key: "carrier".into(),
+        recipient_key: "recipient".into(),
+        proof_hash: None,
+        nonce: 3,
+    };
+    let packed = instruction.pack().unwrap();
+    assert_eq!(packed, [&[INSTRUCTION_VERSION][..], &instruction.try_to_vec().unwrap()].concat());
+    assert_eq!(format!("{:?}", DLUInstruction::unpack(&packed).unwrap()), format!("{:?}", instruction));
+
+    // Input without the version byte, or behind any other version byte, is rejected.
+    assert!(DLUInstruction::unpack(&packed[1..]).is_err());
+    assert!(DLUInstruction::unpack(&[INSTRUCTION_VERSION + 1, 0]).is_err());
+    assert!(DLUInstruction::unpack(&[INSTRUCTION_VERSION - 1, 0]).is_err());
+    assert!(DLUInstruction::unpack(&[]).is_err());
+}
+
+#[test]
+fn error_codes_match_the_program() {
+    let idl = idl();
+    let errors = idl["errors"].as_array().unwrap();
+
+    for error in [DLUError::InvalidInstruction, DLUError::TvlCapExceeded, DLUError::EscrowNotInVault] {
+        let entry = errors.iter().find(|entry| entry["name"] == format!("{:?}", error)).unwrap();
+        assert_eq!(entry["code"], error.code());
+        assert_eq!(entry["msg"], error.to_string());
+    }
+}
@@ -0,0 +1,102 @@
+mod common;
+
+use common::{DealKind, Harness, Party, ENTITY_SPACE, PAYMENT, STARTING_BALANCE};
+use luda::deal::Location;
+use luda::instruction::DLUInstruction;
+use luda::offer::{price_tiers, OfferKind, PriceTier};
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+
+const MAX_QUANTITY: u32 = 20;
+const UNIT_PRICE: u64 = 100_000;
+const BULK_PRICE: u64 = 80_000;  // From 10 units on.
+const LOT_INSURANCE: u64 = MAX_QUANTITY as u64 * BULK_PRICE;
+
+fn inventory(tiers: &[PriceTier]) -> OfferKind {
+    OfferKind::Inventory { max_quantity: MAX_QUANTITY, tiers: price_tiers(tiers).unwrap(), quantity: 0 }
+}
+
+fn bulk_inventory() -> OfferKind {
+    inventory(&[
+        PriceTier { min_quantity: 1, unit_price: UNIT_PRICE },
+        PriceTier { min_quantity: 10, unit_price: BULK_PRICE },
+    ])
+}
+
+fn list_instruction(h: &mut Harness, seller: &Party, kind: OfferKind, payment: u64, now: i64) -> (Pubkey, Instruction) {
+    let offer = Pubkey::new_unique();
+    h.set_program_account(offer, ENTITY_SPACE);
+    let instruction = DLUInstruction::ListOffer {
+        id: 1,
+        goodsorservice_name: "Paving stones".to_string(),
+        goodsorservice_description: "Granite, 10 cm".to_string(),
+        payment,
+        meeting_point: Location::new("DE", "Berlin".to_string(), "Alexanderplatz 1".to_string()).unwrap(),
+        meeting_datetime: now + 86_400,
+        kind,
+        meeting_point_hash: None,
+        holdback: None,
+    };
+    let accounts = vec![
+        AccountMeta::new(offer, false),
+        AccountMeta::new_readonly(seller.user, false),
+        AccountMeta::new(seller.owner.pubkey(), true),
+        AccountMeta::new(seller.token, false),
+        AccountMeta::new(h.escrow, false),
+        AccountMeta::new_readonly(h.vault_registry(), false),
+        AccountMeta::new_readonly(h.config(), false),
+        AccountMeta::new(h.stats(), false),
+        AccountMeta::new(h.index(seller), false),
+    ];
+    (offer, h.instruction(&instruction, accounts))
+}
+
+fn accept_instruction(h: &Harness, offer: Pubkey, seller: &Party, buyer: &Party, quantity: u32) -> Instruction {
+    let accounts = h.accept_deal_instruction(DealKind::Offer, offer, seller, buyer).accounts;
+    h.instruction(&DLUInstruction::AcceptOffer { quantity, promo_code: None }, accounts)
+}
+
+#[tokio::test]
+async fn buyers_pay_the_tier_price_of_their_quantity() {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    let now = h.now().await;
+    let (offer, listing) = list_instruction(&mut h, &seller, bulk_inventory(), 0, now);
+    h.process(listing, &[&seller.owner]).await;
+    assert_eq!(h.balance(seller.token).await, STARTING_BALANCE - LOT_INSURANCE);
+
+    let oversized = accept_instruction(&h, offer, &seller, &buyer, MAX_QUANTITY + 1);
+    assert!(h.try_process_all(&[oversized], &[&buyer.owner]).await.is_err());
+
+    let payment = 12 * BULK_PRICE;
+    h.process(accept_instruction(&h, offer, &seller, &buyer, 12), &[&buyer.owner]).await;
+    let accepted = h.offer(offer).await;
+    assert_eq!(accepted.payment(), payment);
+    assert!(matches!(accepted.kind(), OfferKind::Inventory { quantity: 12, .. }));
+    assert_eq!(h.balance(buyer.token).await, STARTING_BALANCE - payment - LOT_INSURANCE);
+
+    h.complete_deal(DealKind::Offer, offer, &seller, &buyer).await;
+    assert_eq!(h.balance(seller.token).await, STARTING_BALANCE + payment);
+    assert_eq!(h.balance(buyer.token).await, STARTING_BALANCE - payment);
+}
+
+#[tokio::test]
+async fn tiers_must_cover_every_quantity_and_other_offers_sell_one_unit() {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    let now = h.now().await;
+
+    let from_ten = inventory(&[PriceTier { min_quantity: 10, unit_price: BULK_PRICE }]);
+    let (_, gapped) = list_instruction(&mut h, &seller, from_ten, 0, now);
+    assert!(h.try_process_all(&[gapped], &[&seller.owner]).await.is_err());
+    let (_, paid) = list_instruction(&mut h, &seller, bulk_inventory(), PAYMENT, now);
+    assert!(h.try_process_all(&[paid], &[&seller.owner]).await.is_err());
+
+    let (offer, _) = h.list_deal(DealKind::Offer, &seller, &buyer).await;
+    let several = accept_instruction(&h, offer, &seller, &buyer, 2);
+    assert!(h.try_process_all(&[several], &[&buyer.owner]).await.is_err());
+    h.process(accept_instruction(&h, offer, &seller, &buyer, 1), &[&buyer.owner]).await;
+}
@@ -0,0 +1,64 @@
+use luda::addressing::EntityType;
+use luda::instruction::DLUInstruction;
+use luda::keeper::{self, CrankAccounts, ExpiryBatchEntry};
+use luda::time;
+use solana_program::pubkey::Pubkey;
+
+fn crank_accounts() -> CrankAccounts {
+    CrankAccounts {
+        entity_type: EntityType::Shipment,
+        entity: Pubkey::new_unique(),
+        payment_mint: Pubkey::new_unique(),
+        seller_or_sender_account: Pubkey::new_unique(),
+        buyer_or_carrier_account: Pubkey::new_unique(),
+        keeper_account: Pubkey::new_unique(),
+        seller_nft_account: None,
+    }
+}
+
+#[test]
+fn cranks_are_scheduled_right_after_the_grace_period() {
+    let deadline = 1_700_000_000;
+    let at = keeper::crank_at(deadline);
+    assert!(!time::is_expired(deadline, at - 1));
+    assert!(time::is_expired(deadline, at));
+}
+
+#[test]
+fn crank_instruction_needs_no_signer() {
+    let program_id = Pubkey::new_unique();
+    let instruction = keeper::crank_expire_instruction(&program_id, &crank_accounts()).unwrap();
+
+    assert_eq!(instruction.accounts.len(), 8);
+    assert!(instruction.accounts.iter().all(|meta| !meta.is_signer));
+    assert!(matches!(
+        DLUInstruction::unpack(&instruction.data).unwrap(),
+        DLUInstruction::CrankExpire { entity_type: EntityType::Shipment }
+    ));
+}
+
+#[test]
+fn batch_expiry_lists_every_entity_with_its_refund_accounts() {
+    let program_id = Pubkey::new_unique();
+    let entries: Vec<ExpiryBatchEntry> = (1..=3)
+        .map(|id| ExpiryBatchEntry {
+            id,
+            entity: Pubkey::new_unique(),
+            seller_or_sender_account: Pubkey::new_unique(),
+            buyer_or_carrier_account: Pubkey::new_unique(),
+        })
+        .collect();
+    let keeper_account = Pubkey::new_unique();
+    let instruction =
+        keeper::batch_expire_instruction(&program_id, EntityType::Offer, &Pubkey::new_unique(), &keeper_account, &entries).unwrap();
+
+    assert_eq!(instruction.accounts.len(), 5 + 3 * entries.len());
+    assert_eq!(instruction.accounts[0].pubkey, keeper_account);
+    assert_eq!(instruction.accounts[8].pubkey, entries[1].entity);
+    assert_eq!(instruction.accounts[10].pubkey, entries[1].buyer_or_carrier_account);
+    assert!(instruction.accounts.iter().all(|meta| !meta.is_signer));
+    assert!(matches!(
+        DLUInstruction::unpack(&instruction.data).unwrap(),
+        DLUInstruction::BatchExpire { entity_type: EntityType::Offer, ids } if ids == [1, 2, 3]
+    ));
+}
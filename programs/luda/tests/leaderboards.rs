@@ -0,0 +1,80 @@
+mod common;
+
+use common::{DealKind, Harness, Party, PAYMENT};
+use luda::addressing::derive_leaderboard_address;
+use luda::constants::{LEADERBOARD_ENTRIES, LEADERBOARD_SIZE};
+use luda::leaderboard::{Leaderboard, LeaderboardKind, SELLER_LEADERBOARDS};
+use solana_program::instruction::AccountMeta;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_program;
+use solana_sdk::signature::Signer;
+
+fn leaderboard(h: &Harness, kind: LeaderboardKind) -> Pubkey {
+    derive_leaderboard_address(&h.program_id, kind).0
+}
+
+async fn read_leaderboard(h: &mut Harness, kind: LeaderboardKind) -> Vec<(Pubkey, u64)> {
+    let leaderboard = h.decode(leaderboard(h, kind), Leaderboard::deserialize).await;
+    leaderboard.entries.iter().map(|entry| (entry.user, entry.score)).collect()
+}
+
+/// Sells an offer from `seller` to `buyer`, passing the seller leaderboards on completion
+/// when `ranked`.
+async fn sell(h: &mut Harness, seller: &Party, buyer: &Party, ranked: bool) {
+    let (offer, _) = h.list_deal(DealKind::Offer, seller, buyer).await;
+    h.accept_deal(DealKind::Offer, offer, seller, buyer).await;
+    let mut instruction = h.complete_deal_instruction(DealKind::Offer, offer, seller, buyer).await;
+    if ranked {
+        instruction.accounts.extend(SELLER_LEADERBOARDS.iter().map(|kind| AccountMeta::new(leaderboard(h, *kind), false)));
+        instruction.accounts.extend([
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(h.context.payer.pubkey(), true),
+        ]);
+    }
+    h.process(instruction, &[]).await;
+}
+
+#[tokio::test]
+async fn completions_rank_sellers_when_they_pass_the_leaderboards() {
+    let mut h = Harness::start().await;
+    let top = h.create_user("top").await;
+    let runner_up = h.create_user("runner-up").await;
+    let unranked = h.create_user("unranked").await;
+    let buyer = h.create_user("buyer").await;
+
+    sell(&mut h, &runner_up, &buyer, true).await;
+    sell(&mut h, &top, &buyer, true).await;
+    sell(&mut h, &top, &buyer, true).await;
+    sell(&mut h, &unranked, &buyer, false).await;
+
+    let (top, runner_up) = (top.owner.pubkey(), runner_up.owner.pubkey());
+    let volume = read_leaderboard(&mut h, LeaderboardKind::SellerVolume).await;
+    assert_eq!(volume, vec![(top, 2 * PAYMENT), (runner_up, PAYMENT)]);
+    let streak = read_leaderboard(&mut h, LeaderboardKind::SellerStreak).await;
+    assert_eq!(streak, vec![(top, 2), (runner_up, 1)]);
+}
+
+#[test]
+fn leaderboards_keep_the_best_scores_in_order() {
+    let mut leaderboard = Leaderboard::default();
+    let users: Vec<Pubkey> = (0..LEADERBOARD_ENTRIES).map(|_| Pubkey::new_unique()).collect();
+    for (score, user) in users.iter().enumerate() {
+        leaderboard.rank(*user, score as u64 + 1);
+    }
+    assert_eq!(leaderboard.serialize().unwrap().len(), LEADERBOARD_SIZE);
+    assert_eq!(leaderboard.entries[0].user, users[LEADERBOARD_ENTRIES - 1]);
+
+    // A full board only takes scores above its last entry, and ties rank after the holder.
+    assert_eq!(leaderboard.rank(Pubkey::new_unique(), 1), None);
+    let newcomer = Pubkey::new_unique();
+    assert_eq!(leaderboard.rank(newcomer, LEADERBOARD_ENTRIES as u64), Some(1));
+    assert_eq!(leaderboard.entries.len(), LEADERBOARD_ENTRIES);
+    assert_eq!(leaderboard.entries.last().unwrap().score, 2);
+
+    // Users come back once they outscore the board, and a zero score takes them off it.
+    assert_eq!(leaderboard.rank(users[0], 1_000), Some(0));
+    assert_eq!(leaderboard.rank(users[1], 1_000), Some(1));
+    assert_eq!(leaderboard.rank(newcomer, 0), None);
+    assert!(leaderboard.entries.iter().all(|entry| entry.user != newcomer));
+    assert_eq!(leaderboard.entries.len(), LEADERBOARD_ENTRIES - 1);
+}
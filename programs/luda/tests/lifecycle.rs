@@ -0,0 +1,262 @@
+mod common;
+
+use common::{DealKind, Harness, Party, INSURANCE, PAYMENT, STARTING_BALANCE};
+use luda::config::{DEFAULT_FAIL_BOND, DEFAULT_PENALTY_SHARE_BPS};
+use luda::errors::DLUError;
+use solana_program::instruction::InstructionError;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::transaction::TransactionError;
+
+fn custom(code: DLUError) -> TransactionError {
+    TransactionError::InstructionError(0, InstructionError::Custom(code.code()))
+}
+
+/// Share of the insured amount a failing claimant keeps; the rest is the penalty.
+fn penalty_share(insurance: u64) -> u64 {
+    insurance * DEFAULT_PENALTY_SHARE_BPS as u64 / 10_000
+}
+
+/// Lists and accepts a deal, checking the escrow after each step.
+async fn accepted_deal(h: &mut Harness, kind: DealKind, seller: &Party, buyer: &Party) -> (Pubkey, i64) {
+    let (deal, meeting_datetime) = h.list_deal(kind, seller, buyer).await;
+    let lister_deposit = match kind {
+        DealKind::Offer => PAYMENT,
+        DealKind::Request => 2 * PAYMENT,
+    };
+    assert_eq!(h.escrow_balance().await, lister_deposit);
+    assert_eq!(h.locked(kind, deal).await, lister_deposit);
+
+    h.accept_deal(kind, deal, seller, buyer).await;
+    assert_eq!(h.escrow_balance().await, 3 * PAYMENT);
+    assert_eq!(h.locked(kind, deal).await, 3 * PAYMENT);
+    (deal, meeting_datetime)
+}
+
+async fn deal_completes(kind: DealKind) {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+
+    let (deal, _) = accepted_deal(&mut h, kind, &seller, &buyer).await;
+    h.complete_deal(kind, deal, &seller, &buyer).await;
+
+    assert_eq!(h.escrow_balance().await, 0);
+    assert_eq!(h.locked(kind, deal).await, 0);
+    assert_eq!(h.balance(seller.token).await, STARTING_BALANCE + PAYMENT);
+    assert_eq!(h.balance(buyer.token).await, STARTING_BALANCE - PAYMENT);
+}
+
+async fn deal_fails(kind: DealKind) {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+
+    let (deal, _) = accepted_deal(&mut h, kind, &seller, &buyer).await;
+    h.fail_deal(kind, deal, &seller, &buyer).await;
+
+    // Only the seller's fail bond stays in escrow until it is reclaimed or disputed.
+    let share = penalty_share(PAYMENT);
+    assert_eq!(h.escrow_balance().await, DEFAULT_FAIL_BOND);
+    assert_eq!(h.locked(kind, deal).await, 0);
+    assert_eq!(h.balance(seller.token).await, STARTING_BALANCE + share - DEFAULT_FAIL_BOND);
+    assert_eq!(h.balance(buyer.token).await, STARTING_BALANCE - PAYMENT);
+    assert_eq!(h.balance(h.penalty).await, PAYMENT - share);
+}
+
+async fn deal_expires(kind: DealKind) {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+
+    let (deal, meeting_datetime) = accepted_deal(&mut h, kind, &seller, &buyer).await;
+    h.expire_clock(meeting_datetime).await;
+    h.expire_deal(kind, deal, &seller, &buyer).await;
+
+    assert_eq!(h.escrow_balance().await, 0);
+    assert_eq!(h.locked(kind, deal).await, 0);
+    assert_eq!(h.balance(seller.token).await, STARTING_BALANCE);
+    assert_eq!(h.balance(buyer.token).await, STARTING_BALANCE);
+}
+
+async fn deal_cancels(kind: DealKind) {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+
+    let (deal, _) = h.list_deal(kind, &seller, &buyer).await;
+    assert!(h.escrow_balance().await > 0);
+    h.cancel_deal(kind, deal, &seller, &buyer).await;
+
+    assert_eq!(h.escrow_balance().await, 0);
+    assert_eq!(h.locked(kind, deal).await, 0);
+    assert_eq!(h.balance(seller.token).await, STARTING_BALANCE);
+    assert_eq!(h.balance(buyer.token).await, STARTING_BALANCE);
+}
+
+#[tokio::test]
+async fn offer_completes() {
+    deal_completes(DealKind::Offer).await;
+}
+
+#[tokio::test]
+async fn offer_fails() {
+    deal_fails(DealKind::Offer).await;
+}
+
+#[tokio::test]
+async fn offer_expires() {
+    deal_expires(DealKind::Offer).await;
+}
+
+#[tokio::test]
+async fn offer_cancels() {
+    deal_cancels(DealKind::Offer).await;
+}
+
+#[tokio::test]
+async fn request_completes() {
+    deal_completes(DealKind::Request).await;
+}
+
+#[tokio::test]
+async fn request_fails() {
+    deal_fails(DealKind::Request).await;
+}
+
+#[tokio::test]
+async fn request_expires() {
+    deal_expires(DealKind::Request).await;
+}
+
+#[tokio::test]
+async fn request_cancels() {
+    deal_cancels(DealKind::Request).await;
+}
+
+#[tokio::test]
+async fn completions_pay_only_the_deals_parties() {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    let stranger = h.create_user("stranger").await;
+    let (deal, _) = accepted_deal(&mut h, DealKind::Offer, &seller, &buyer).await;
+
+    // Someone else's user cannot stand in for the buyer's, nor their token account for the buyer's own.
+    let mut instruction = h.complete_deal_instruction(DealKind::Offer, deal, &seller, &buyer).await;
+    instruction.accounts[2].pubkey = stranger.user;
+    instruction.accounts[9].pubkey = h.summary(&stranger);
+    let error = h.try_process_all(&[instruction], &[]).await;
+    assert_eq!(error, Err(custom(DLUError::NotDealParty)));
+    let mut instruction = h.complete_deal_instruction(DealKind::Offer, deal, &seller, &buyer).await;
+    instruction.accounts[4].pubkey = stranger.token;
+    let error = h.try_process_all(&[instruction], &[]).await;
+    assert_eq!(error, Err(custom(DLUError::KeyMismatch)));
+
+    h.complete_deal(DealKind::Offer, deal, &seller, &buyer).await;
+    assert_eq!(h.balance(seller.token).await, STARTING_BALANCE + PAYMENT);
+    assert_eq!(h.balance(stranger.token).await, STARTING_BALANCE);
+}
+
+#[tokio::test]
+async fn failures_only_forfeit_the_acceptors_insurance() {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    let stranger = h.create_user("stranger").await;
+    let (deal, _) = accepted_deal(&mut h, DealKind::Offer, &seller, &buyer).await;
+    let signers = [&seller.owner];
+
+    // The seller cannot name another user as the buyer, nor take the buyer's refund themselves.
+    let mut instruction = h.fail_deal_instruction(DealKind::Offer, deal, &seller, &buyer).await;
+    instruction.accounts[1].pubkey = stranger.user;
+    instruction.accounts[6].pubkey = h.summary(&stranger);
+    let error = h.try_process_all(&[instruction], &signers).await;
+    assert_eq!(error, Err(custom(DLUError::NotDealParty)));
+    let mut instruction = h.fail_deal_instruction(DealKind::Offer, deal, &seller, &buyer).await;
+    instruction.accounts[13].pubkey = seller.token;
+    let error = h.try_process_all(&[instruction], &signers).await;
+    assert_eq!(error, Err(custom(DLUError::KeyMismatch)));
+
+    h.fail_deal(DealKind::Offer, deal, &seller, &buyer).await;
+    assert_eq!(h.balance(buyer.token).await, STARTING_BALANCE - PAYMENT);
+}
+
+/// Lists and accepts a shipment, checking the escrow after each step.
+async fn accepted_shipment(h: &mut Harness, sender: &Party, recipient: &Party, carrier: &Party) -> (Pubkey, i64) {
+    let (shipment, drop_off_datetime) = h.list_shipment(sender, recipient).await;
+    assert_eq!(h.escrow_balance().await, PAYMENT);
+    assert_eq!(h.shipment(shipment).await.escrowed_amount(), PAYMENT);
+
+    h.accept_shipment(shipment, carrier).await;
+    assert_eq!(h.escrow_balance().await, PAYMENT + INSURANCE);
+    assert_eq!(h.shipment(shipment).await.escrowed_amount(), PAYMENT + INSURANCE);
+    (shipment, drop_off_datetime)
+}
+
+#[tokio::test]
+async fn shipment_completes() {
+    let mut h = Harness::start().await;
+    let sender = h.create_user("sender").await;
+    let recipient = h.create_user("recipient").await;
+    let carrier = h.create_user("carrier").await;
+
+    let (shipment, _) = accepted_shipment(&mut h, &sender, &recipient, &carrier).await;
+    h.confirm_pickup(shipment, &carrier).await;
+    h.complete_shipment(shipment, &sender, &carrier).await;
+
+    assert_eq!(h.escrow_balance().await, 0);
+    assert_eq!(h.shipment(shipment).await.escrowed_amount(), 0);
+    assert_eq!(h.balance(sender.token).await, STARTING_BALANCE - PAYMENT);
+    assert_eq!(h.balance(carrier.token).await, STARTING_BALANCE + PAYMENT);
+}
+
+#[tokio::test]
+async fn shipment_fails() {
+    let mut h = Harness::start().await;
+    let sender = h.create_user("sender").await;
+    let recipient = h.create_user("recipient").await;
+    let carrier = h.create_user("carrier").await;
+
+    let (shipment, _) = accepted_shipment(&mut h, &sender, &recipient, &carrier).await;
+    h.confirm_pickup(shipment, &carrier).await;
+    h.fail_shipment(shipment, &sender, &carrier).await;
+
+    let share = penalty_share(INSURANCE);
+    assert_eq!(h.escrow_balance().await, DEFAULT_FAIL_BOND);
+    assert_eq!(h.shipment(shipment).await.escrowed_amount(), 0);
+    assert_eq!(h.balance(sender.token).await, STARTING_BALANCE + share - DEFAULT_FAIL_BOND);
+    assert_eq!(h.balance(carrier.token).await, STARTING_BALANCE - INSURANCE);
+    assert_eq!(h.balance(h.penalty).await, INSURANCE - share);
+}
+
+#[tokio::test]
+async fn shipment_expires() {
+    let mut h = Harness::start().await;
+    let sender = h.create_user("sender").await;
+    let recipient = h.create_user("recipient").await;
+    let carrier = h.create_user("carrier").await;
+
+    let (shipment, drop_off_datetime) = accepted_shipment(&mut h, &sender, &recipient, &carrier).await;
+    h.expire_clock(drop_off_datetime).await;
+    h.expire_shipment(shipment, &sender, &carrier).await;
+
+    assert_eq!(h.escrow_balance().await, 0);
+    assert_eq!(h.shipment(shipment).await.escrowed_amount(), 0);
+    assert_eq!(h.balance(sender.token).await, STARTING_BALANCE);
+    assert_eq!(h.balance(carrier.token).await, STARTING_BALANCE);
+}
+
+#[tokio::test]
+async fn shipment_cancels() {
+    let mut h = Harness::start().await;
+    let sender = h.create_user("sender").await;
+    let recipient = h.create_user("recipient").await;
+
+    let (shipment, _) = h.list_shipment(&sender, &recipient).await;
+    assert_eq!(h.escrow_balance().await, PAYMENT);
+    h.cancel_shipment(shipment, &sender).await;
+
+    assert_eq!(h.escrow_balance().await, 0);
+    assert_eq!(h.shipment(shipment).await.escrowed_amount(), 0);
+    assert_eq!(h.balance(sender.token).await, STARTING_BALANCE);
+}
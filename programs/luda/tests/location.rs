@@ -0,0 +1,33 @@
+use luda::constants::{MAX_GEOHASH_LEN, MAX_TOWN_LEN};
+use luda::errors::DLUError;
+use luda::location::Location;
+
+fn berlin() -> Location {
+    Location::new("de", "Berlin".to_string(), "Alexanderplatz 1".to_string()).unwrap()
+}
+
+#[test]
+fn country_codes_are_normalized_and_checked() {
+    assert_eq!(berlin().country(), "DE");
+    assert!(berlin().same_region(&Location::new("DE", "berlin".to_string(), String::new()).unwrap()));
+
+    for country in ["XX", "ZZ", "DEU", "Germany", ""] {
+        let location = Location::new(country, "Berlin".to_string(), String::new());
+        assert_eq!(location.unwrap_err(), DLUError::InvalidCountryCode);
+    }
+}
+
+#[test]
+fn fields_are_bounded() {
+    let town = "x".repeat(MAX_TOWN_LEN + 1);
+    assert_eq!(Location::new("DE", town, String::new()).unwrap_err(), DLUError::LocationFieldTooLong);
+}
+
+#[test]
+fn geohashes_use_the_base32_alphabet() {
+    assert_eq!(berlin().with_geohash("U33DC0").unwrap().geohash(), Some("u33dc0"));
+
+    for geohash in ["", "u33a", &"u".repeat(MAX_GEOHASH_LEN + 1)] {
+        assert_eq!(berlin().with_geohash(geohash).unwrap_err(), DLUError::InvalidGeohash);
+    }
+}
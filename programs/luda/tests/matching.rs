@@ -0,0 +1,102 @@
+mod common;
+
+use common::{DealKind, Harness, Party, PAYMENT, STARTING_BALANCE};
+use luda::constants::{BPS_DENOMINATOR, FINDER_FEE_BPS};
+use luda::deal::Location;
+use luda::instruction::DLUInstruction;
+use luda::offer::OfferStatus;
+use luda::request::RequestStatus;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+
+const REQUEST_SPACE: usize = 4_096;
+const ASKED_PAYMENT: u64 = PAYMENT + 1_000_000;
+
+async fn list_request(h: &mut Harness, buyer: &Party, payment: u64) -> Pubkey {
+    let request = Pubkey::new_unique();
+    h.set_program_account(request, REQUEST_SPACE);
+    let instruction = DLUInstruction::ListRequest {
+        id: 2,
+        goodsorservice_name: " bicycle".to_string(),
+        goodsorservice_description: "Any color".to_string(),
+        payment,
+        meeting_point: Location::new("de", "Berlin".to_string(), "Hauptbahnhof".to_string()).unwrap(),
+        meeting_datetime: h.now().await + 86_400,
+        meeting_point_hash: None,
+    };
+    let accounts = vec![
+        AccountMeta::new(request, false),
+        AccountMeta::new_readonly(buyer.user, false),
+        AccountMeta::new_readonly(buyer.owner.pubkey(), true),
+        AccountMeta::new(buyer.token, false),
+        AccountMeta::new(h.escrow, false),
+        AccountMeta::new_readonly(h.vault_registry(), false),
+        AccountMeta::new_readonly(h.config(), false),
+        AccountMeta::new(h.stats(), false),
+    ];
+    h.process(h.instruction(&instruction, accounts), &[&buyer.owner]).await;
+    request
+}
+
+fn match_instruction(
+    h: &Harness,
+    offer: Pubkey,
+    request: Pubkey,
+    seller: &Party,
+    buyer: &Party,
+    matcher: Pubkey,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new(offer, false),
+        AccountMeta::new(request, false),
+        AccountMeta::new(buyer.user, false),
+        AccountMeta::new(buyer.token, false),
+        AccountMeta::new(h.escrow, false),
+        AccountMeta::new_readonly(h.vault_registry(), false),
+        AccountMeta::new(matcher, false),
+        AccountMeta::new_readonly(h.config(), false),
+        AccountMeta::new(h.stats(), false),
+        AccountMeta::new(h.index(seller), false),
+    ];
+    h.instruction(&DLUInstruction::MatchRequestToOffer, accounts)
+}
+
+#[tokio::test]
+async fn matching_accepts_the_offer_at_its_price_and_pays_the_matcher() {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    let matcher = Pubkey::new_unique();
+    h.set_token_account(matcher, Pubkey::new_unique(), 0);
+
+    let (offer, _) = h.list_deal(DealKind::Offer, &seller, &buyer).await;
+    let request = list_request(&mut h, &buyer, ASKED_PAYMENT).await;
+    let instruction = match_instruction(&h, offer, request, &seller, &buyer, matcher);
+    h.process(instruction, &[]).await;
+
+    let fee = PAYMENT * FINDER_FEE_BPS as u64 / BPS_DENOMINATOR;
+    assert_eq!(h.offer(offer).await.status(), OfferStatus::Accepted);
+    assert_eq!(h.request(request).await.status(), RequestStatus::Matched);
+    assert_eq!(h.balance(matcher).await, fee);
+    assert_eq!(h.balance(buyer.token).await, STARTING_BALANCE - 2 * PAYMENT - fee);
+    assert_eq!(h.escrow_balance().await, 3 * PAYMENT);
+
+    h.complete_deal(DealKind::Offer, offer, &seller, &buyer).await;
+    assert_eq!(h.balance(seller.token).await, STARTING_BALANCE + PAYMENT);
+    assert_eq!(h.balance(buyer.token).await, STARTING_BALANCE - PAYMENT - fee);
+}
+
+#[tokio::test]
+async fn a_request_that_cannot_cover_the_finder_fee_does_not_match() {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    let matcher = Pubkey::new_unique();
+    h.set_token_account(matcher, Pubkey::new_unique(), 0);
+
+    let (offer, _) = h.list_deal(DealKind::Offer, &seller, &buyer).await;
+    let request = list_request(&mut h, &buyer, PAYMENT).await;
+    let instruction = match_instruction(&h, offer, request, &seller, &buyer, matcher);
+    assert!(h.try_process_all(&[instruction], &[]).await.is_err());
+}
@@ -0,0 +1,45 @@
+mod common;
+
+use common::{DealKind, Harness, Party};
+use luda::addressing::EntityType;
+use luda::deal::Location;
+use luda::instruction::DLUInstruction;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+
+const SALT: [u8; 32] = [7; 32];
+
+fn exact_point() -> Location {
+    Location::new("DE", "Berlin".to_string(), "Torstrasse 5, back entrance".to_string()).unwrap()
+}
+
+fn reveal_instruction(h: &Harness, offer: Pubkey, seller: &Party, salt: [u8; 32]) -> Instruction {
+    let instruction =
+        DLUInstruction::RevealMeetingPoint { entity_type: EntityType::Offer, meeting_point: exact_point(), salt };
+    let accounts = vec![AccountMeta::new(offer, false), AccountMeta::new_readonly(seller.owner.pubkey(), true)];
+    h.instruction(&instruction, accounts)
+}
+
+#[tokio::test]
+async fn a_hidden_meeting_point_is_revealed_once_accepted() {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    let commitment = exact_point().commitment(&SALT).unwrap();
+    let (offer, _) = h.list_deal_with(DealKind::Offer, &seller, &buyer, "City bike", Some(commitment)).await;
+    assert!(h.offer(offer).await.is_meeting_point_hidden());
+
+    // The seller cannot publish it before a buyer committed to the deal.
+    let early = reveal_instruction(&h, offer, &seller, SALT);
+    assert!(h.try_process_all(&[early], &[&seller.owner]).await.is_err());
+
+    h.accept_deal(DealKind::Offer, offer, &seller, &buyer).await;
+    let wrong_salt = reveal_instruction(&h, offer, &seller, [8; 32]);
+    assert!(h.try_process_all(&[wrong_salt], &[&seller.owner]).await.is_err());
+
+    h.process(reveal_instruction(&h, offer, &seller, SALT), &[&seller.owner]).await;
+    let offer = h.offer(offer).await;
+    assert!(!offer.is_meeting_point_hidden());
+    assert_eq!(offer.meeting_point().address(), "Torstrasse 5, back entrance");
+}
@@ -0,0 +1,70 @@
+mod common;
+
+use common::{DealKind, Harness, Party};
+use luda::addressing::{derive_message_log_address, EntityType};
+use luda::constants::MAX_MESSAGE_LEN;
+use luda::instruction::DLUInstruction;
+use luda::messaging::MessageLog;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_program::system_program;
+use solana_sdk::signature::Signer;
+
+fn send_instruction(h: &Harness, entity_type: EntityType, entity: Pubkey, sender: &Party, payload: Vec<u8>) -> Instruction {
+    let instruction = DLUInstruction::SendMessage { entity_type, payload };
+    let accounts = vec![
+        AccountMeta::new_readonly(entity, false),
+        AccountMeta::new(derive_message_log_address(&h.program_id, &entity).0, false),
+        AccountMeta::new_readonly(sender.owner.pubkey(), true),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new(h.context.payer.pubkey(), true),
+    ];
+    h.instruction(&instruction, accounts)
+}
+
+#[tokio::test]
+async fn deal_parties_exchange_messages_once_accepted() {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    let (offer, _) = h.list_deal(DealKind::Offer, &seller, &buyer).await;
+    let log = derive_message_log_address(&h.program_id, &offer).0;
+
+    // Nobody can message about an offer before a buyer committed to it.
+    let early = send_instruction(&h, EntityType::Offer, offer, &seller, b"sealed".to_vec());
+    assert!(h.try_process_all(&[early], &[&seller.owner]).await.is_err());
+
+    h.accept_deal(DealKind::Offer, offer, &seller, &buyer).await;
+    h.process(send_instruction(&h, EntityType::Offer, offer, &buyer, b"sealed hello".to_vec()), &[&buyer.owner]).await;
+    h.process(send_instruction(&h, EntityType::Offer, offer, &seller, b"sealed reply".to_vec()), &[&seller.owner]).await;
+
+    let outsider = h.create_user("outsider").await;
+    let intrusion = send_instruction(&h, EntityType::Offer, offer, &outsider, b"sealed".to_vec());
+    assert!(h.try_process_all(&[intrusion], &[&outsider.owner]).await.is_err());
+    let oversized = send_instruction(&h, EntityType::Offer, offer, &buyer, vec![0; MAX_MESSAGE_LEN + 1]);
+    assert!(h.try_process_all(&[oversized], &[&buyer.owner]).await.is_err());
+
+    let log = h.decode(log, MessageLog::deserialize).await;
+    assert_eq!(log.entity, offer);
+    let sent: Vec<_> = log.messages.iter().map(|message| (message.sender, message.payload.clone())).collect();
+    assert_eq!(sent, [
+        (buyer.owner.pubkey(), b"sealed hello".to_vec()),
+        (seller.owner.pubkey(), b"sealed reply".to_vec()),
+    ]);
+}
+
+#[tokio::test]
+async fn shipment_recipients_can_message_the_carrier() {
+    let mut h = Harness::start().await;
+    let sender = h.create_user("sender").await;
+    let recipient = h.create_user("recipient").await;
+    let carrier = h.create_user("carrier").await;
+    let (shipment, _) = h.list_shipment(&sender, &recipient).await;
+    h.accept_shipment(shipment, &carrier).await;
+    let log = derive_message_log_address(&h.program_id, &shipment).0;
+
+    h.process(send_instruction(&h, EntityType::Shipment, shipment, &recipient, b"sealed".to_vec()), &[&recipient.owner]).await;
+
+    let log = h.decode(log, MessageLog::deserialize).await;
+    assert_eq!(log.messages[0].sender, recipient.owner.pubkey());
+}
@@ -0,0 +1,89 @@
+mod common;
+
+use common::{DealKind, Harness, Party, ENTITY_SPACE, INSURANCE, PAYMENT, STARTING_BALANCE};
+use luda::instruction::DLUInstruction;
+use luda::location::Location;
+use luda::user::USER_BANNED;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+
+fn ban_instruction(h: &Harness, governance: Pubkey, user: &Party, banned: bool) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(h.config(), false),
+        AccountMeta::new_readonly(governance, true),
+        AccountMeta::new(user.user, false),
+    ];
+    h.instruction(&DLUInstruction::SetUserBanned { banned }, accounts)
+}
+
+async fn set_banned(h: &mut Harness, user: &Party, banned: bool) {
+    let governance = h.context.payer.pubkey();
+    h.process(ban_instruction(h, governance, user, banned), &[]).await;
+}
+
+fn list_shipment_instruction(h: &mut Harness, sender: &Party, recipient: &Party, now: i64) -> Instruction {
+    let shipment = Pubkey::new_unique();
+    h.set_program_account(shipment, ENTITY_SPACE);
+    let instruction = DLUInstruction::ListShipment {
+        id: 2,
+        items_name: "Lamp".to_string(),
+        quantity: 1,
+        payment: PAYMENT,
+        insurance: INSURANCE,
+        pickup_point: Location::new("DE", "Berlin".to_string(), "Torstrasse 5".to_string()).unwrap(),
+        pickup_datetime: now + 3_600,
+        drop_off_point: Location::new("DE", "Hamburg".to_string(), "Jungfernstieg 2".to_string()).unwrap(),
+        drop_off_datetime: now + 90_000,
+    };
+    let accounts = vec![
+        AccountMeta::new(shipment, false),
+        AccountMeta::new_readonly(sender.user, false),
+        AccountMeta::new_readonly(sender.owner.pubkey(), true),
+        AccountMeta::new(sender.token, false),
+        AccountMeta::new(h.escrow, false),
+        AccountMeta::new_readonly(h.vault_registry(), false),
+        AccountMeta::new_readonly(recipient.user, false),
+        AccountMeta::new_readonly(h.config(), false),
+        AccountMeta::new(h.stats(), false),
+    ];
+    h.instruction(&instruction, accounts)
+}
+
+#[tokio::test]
+async fn banned_users_finish_their_deals_but_cannot_start_new_ones() {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    let (offer, _) = h.list_deal(DealKind::Offer, &seller, &buyer).await;
+    set_banned(&mut h, &seller, true).await;
+    assert!(h.user(&seller).await.has_flag(USER_BANNED));
+
+    // The offer listed before the ban goes through.
+    h.accept_deal(DealKind::Offer, offer, &seller, &buyer).await;
+    h.complete_deal(DealKind::Offer, offer, &seller, &buyer).await;
+    assert_eq!(h.balance(seller.token).await, STARTING_BALANCE + PAYMENT);
+
+    let recipient = h.create_user("recipient").await;
+    let now = h.now().await;
+    let listing = list_shipment_instruction(&mut h, &seller, &recipient, now);
+    assert!(h.try_process_all(&[listing], &[&seller.owner]).await.is_err());
+    let (other, _) = h.list_deal(DealKind::Offer, &buyer, &seller).await;
+    let accept = h.accept_deal_instruction(DealKind::Offer, other, &buyer, &seller);
+    assert!(h.try_process_all(&[accept], &[&seller.owner]).await.is_err());
+
+    set_banned(&mut h, &seller, false).await;
+    let listing = list_shipment_instruction(&mut h, &seller, &recipient, now);
+    h.process(listing, &[&seller.owner]).await;
+}
+
+#[tokio::test]
+async fn only_governance_bans_users() {
+    let mut h = Harness::start().await;
+    let user = h.create_user("user").await;
+    let impostor = Keypair::new();
+
+    let ban = ban_instruction(&h, impostor.pubkey(), &user, true);
+    assert!(h.try_process_all(&[ban], &[&impostor]).await.is_err());
+    assert!(!h.user(&user).await.has_flag(USER_BANNED));
+}
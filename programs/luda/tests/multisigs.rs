@@ -0,0 +1,114 @@
+mod common;
+
+use common::{Harness, Party, PAYMENT, STARTING_BALANCE};
+use luda::addressing::derive_multisig_address;
+use luda::instruction::DLUInstruction;
+use luda::user::USER_MULTISIG;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_program::system_program;
+use solana_sdk::signature::{Keypair, Signer};
+
+const DEPOSIT: u64 = 5_000_000;
+const HIGH_VALUE: u64 = 2_000_000;
+
+fn multisig(h: &Harness, party: &Party) -> Pubkey {
+    derive_multisig_address(&h.program_id, &party.owner.pubkey()).0
+}
+
+/// Creates a user with a funded wallet, governed by a 2-of-2 multisig with `partner`.
+async fn shared_user(h: &mut Harness, partner: &Keypair) -> Party {
+    let party = h.create_user("shared").await;
+    let wallet = h.wallet(&party);
+    h.set_token_account(wallet, wallet, DEPOSIT);
+
+    let instruction = DLUInstruction::CreateMultisig {
+        threshold: 2,
+        signers: vec![party.owner.pubkey(), partner.pubkey()],
+        high_value: HIGH_VALUE,
+    };
+    let accounts = vec![
+        AccountMeta::new(party.user, false),
+        AccountMeta::new_readonly(party.owner.pubkey(), true),
+        AccountMeta::new(multisig(h, &party), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new(h.context.payer.pubkey(), true),
+    ];
+    h.process(h.instruction(&instruction, accounts), &[&party.owner]).await;
+    party
+}
+
+fn withdraw_instruction(h: &Harness, party: &Party, amount: u64, cosigners: &[&Keypair]) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(party.user, false),
+        AccountMeta::new_readonly(party.owner.pubkey(), true),
+        AccountMeta::new(party.token, false),
+        AccountMeta::new(h.wallet(party), false),
+        AccountMeta::new_readonly(multisig(h, party), false),
+    ];
+    accounts.extend(cosigners.iter().map(|cosigner| AccountMeta::new_readonly(cosigner.pubkey(), true)));
+    h.instruction(&DLUInstruction::WithdrawDLU { amount }, accounts)
+}
+
+#[tokio::test]
+async fn high_value_withdrawals_need_the_threshold() {
+    let mut h = Harness::start().await;
+    let partner = Keypair::new();
+    let party = shared_user(&mut h, &partner).await;
+    assert!(h.user(&party).await.has_flag(USER_MULTISIG));
+
+    // Small amounts only need the owner, yet the multisig cannot be left out.
+    let mut bypass = withdraw_instruction(&h, &party, HIGH_VALUE - 1, &[]);
+    bypass.accounts.pop();
+    assert!(h.try_process_all(&[bypass], &[&party.owner]).await.is_err());
+    h.process(withdraw_instruction(&h, &party, HIGH_VALUE - 1, &[]), &[&party.owner]).await;
+
+    let alone = withdraw_instruction(&h, &party, HIGH_VALUE, &[]);
+    assert!(h.try_process_all(&[alone], &[&party.owner]).await.is_err());
+    let outsider = Keypair::new();
+    let unknown = withdraw_instruction(&h, &party, HIGH_VALUE, &[&outsider]);
+    assert!(h.try_process_all(&[unknown], &[&party.owner, &outsider]).await.is_err());
+    h.process(withdraw_instruction(&h, &party, HIGH_VALUE, &[&partner]), &[&party.owner, &partner]).await;
+
+    assert_eq!(h.balance(party.token).await, STARTING_BALANCE + 2 * HIGH_VALUE - 1);
+    assert_eq!(h.balance(h.wallet(&party)).await, DEPOSIT - 2 * HIGH_VALUE + 1);
+}
+
+#[tokio::test]
+async fn only_the_threshold_can_change_the_multisig() {
+    let mut h = Harness::start().await;
+    let partner = Keypair::new();
+    let party = shared_user(&mut h, &partner).await;
+
+    let update = |h: &Harness, signers: &[&Keypair]| {
+        let mut accounts = vec![
+            AccountMeta::new_readonly(party.user, false),
+            AccountMeta::new(multisig(h, &party), false),
+        ];
+        accounts.extend(signers.iter().map(|signer| AccountMeta::new_readonly(signer.pubkey(), true)));
+        let instruction = DLUInstruction::UpdateMultisig {
+            threshold: 1,
+            signers: vec![party.owner.pubkey(), partner.pubkey()],
+            high_value: PAYMENT,
+        };
+        h.instruction(&instruction, accounts)
+    };
+    let owner_alone = update(&h, &[&party.owner]);
+    assert!(h.try_process_all(&[owner_alone], &[&party.owner]).await.is_err());
+
+    // Creating the multisig again cannot replace it either.
+    let accounts = vec![
+        AccountMeta::new(party.user, false),
+        AccountMeta::new_readonly(party.owner.pubkey(), true),
+        AccountMeta::new(multisig(&h, &party), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new(h.context.payer.pubkey(), true),
+    ];
+    let instruction = DLUInstruction::CreateMultisig { threshold: 1, signers: vec![party.owner.pubkey()], high_value: 0 };
+    let recreate = h.instruction(&instruction, accounts);
+    assert!(h.try_process_all(&[recreate], &[&party.owner]).await.is_err());
+
+    h.process(update(&h, &[&party.owner, &partner]), &[&party.owner, &partner]).await;
+    h.process(withdraw_instruction(&h, &party, DEPOSIT, &[]), &[&party.owner]).await;
+    assert_eq!(h.balance(party.token).await, STARTING_BALANCE + DEPOSIT);
+}
@@ -0,0 +1,117 @@
+mod common;
+
+use common::{DealKind, Harness, Party, PAYMENT};
+use luda::addressing::derive_nft_escrow_address;
+use luda::deal::Location;
+use luda::instruction::DLUInstruction;
+use luda::offer::OfferKind;
+use solana_program::instruction::AccountMeta;
+use solana_program::program_option::COption;
+use solana_program::program_pack::Pack;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_program;
+use solana_sdk::signature::Signer;
+use spl_token::state::Mint;
+
+const OFFER_SPACE: usize = 4_096;
+
+/// An NFT held by the seller, with the account the buyer receives it in.
+struct Nft {
+    mint: Pubkey,
+    seller_account: Pubkey,
+    buyer_account: Pubkey,
+}
+
+fn mint_nft(h: &mut Harness, seller: &Party, buyer: &Party) -> Nft {
+    let nft = Nft { mint: Pubkey::new_unique(), seller_account: Pubkey::new_unique(), buyer_account: Pubkey::new_unique() };
+    let mut data = vec![0; Mint::LEN];
+    let mint = Mint { mint_authority: COption::None, supply: 1, decimals: 0, is_initialized: true, freeze_authority: COption::None };
+    Mint::pack(mint, &mut data).unwrap();
+    h.set_account(nft.mint, spl_token::id(), data);
+    h.set_token_account_of(nft.seller_account, nft.mint, seller.owner.pubkey(), 1);
+    h.set_token_account_of(nft.buyer_account, nft.mint, buyer.owner.pubkey(), 0);
+    // The seller pays the rent of the offer's NFT escrow.
+    h.fund(seller.owner.pubkey(), 1_000_000_000);
+    nft
+}
+
+async fn list_nft_offer(h: &mut Harness, seller: &Party, nft: &Nft) -> Pubkey {
+    let offer = Pubkey::new_unique();
+    h.set_program_account(offer, OFFER_SPACE);
+    let instruction = DLUInstruction::ListOffer {
+        id: 1,
+        goodsorservice_name: "Artwork".to_string(),
+        goodsorservice_description: "One of one".to_string(),
+        payment: PAYMENT,
+        meeting_point: Location::new("DE", "Berlin".to_string(), "Online".to_string()).unwrap(),
+        meeting_datetime: h.now().await + 86_400,
+        kind: OfferKind::Nft { mint: nft.mint },
+        meeting_point_hash: None,
+        holdback: None,
+    };
+    let accounts = vec![
+        AccountMeta::new(offer, false),
+        AccountMeta::new_readonly(seller.user, false),
+        AccountMeta::new(seller.owner.pubkey(), true),
+        AccountMeta::new(seller.token, false),
+        AccountMeta::new(h.escrow, false),
+        AccountMeta::new_readonly(h.vault_registry(), false),
+        AccountMeta::new_readonly(h.config(), false),
+        AccountMeta::new(h.stats(), false),
+        AccountMeta::new(h.index(seller), false),
+        AccountMeta::new(nft.seller_account, false),
+        AccountMeta::new(nft_escrow(h, offer), false),
+        AccountMeta::new_readonly(nft.mint, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+    h.process(h.instruction(&instruction, accounts), &[&seller.owner]).await;
+    offer
+}
+
+fn nft_escrow(h: &Harness, offer: Pubkey) -> Pubkey {
+    derive_nft_escrow_address(&h.program_id, &offer).0
+}
+
+#[tokio::test]
+async fn completing_an_nft_offer_hands_the_nft_to_the_buyer() {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    let nft = mint_nft(&mut h, &seller, &buyer);
+
+    let offer = list_nft_offer(&mut h, &seller, &nft).await;
+    assert_eq!(h.offer(offer).await.kind(), OfferKind::Nft { mint: nft.mint });
+    assert_eq!(h.balance(nft.seller_account).await, 0);
+    assert_eq!(h.balance(nft_escrow(&h, offer)).await, 1);
+
+    h.accept_deal(DealKind::Offer, offer, &seller, &buyer).await;
+    let mut instruction = h.complete_deal_instruction(DealKind::Offer, offer, &seller, &buyer).await;
+    instruction.accounts.extend([
+        AccountMeta::new(nft_escrow(&h, offer), false),
+        AccountMeta::new(nft.buyer_account, false),
+    ]);
+    h.process(instruction, &[]).await;
+
+    assert_eq!(h.balance(nft_escrow(&h, offer)).await, 0);
+    assert_eq!(h.balance(nft.buyer_account).await, 1);
+}
+
+#[tokio::test]
+async fn canceling_an_nft_offer_returns_the_nft_to_the_seller() {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    let nft = mint_nft(&mut h, &seller, &buyer);
+    let offer = list_nft_offer(&mut h, &seller, &nft).await;
+
+    let mut instruction = h.cancel_deal_instruction(DealKind::Offer, offer, &seller, &buyer);
+    instruction.accounts.extend([
+        AccountMeta::new(nft_escrow(&h, offer), false),
+        AccountMeta::new(nft.seller_account, false),
+    ]);
+    h.process(instruction, &[&seller.owner]).await;
+
+    assert_eq!(h.balance(nft_escrow(&h, offer)).await, 0);
+    assert_eq!(h.balance(nft.seller_account).await, 1);
+}
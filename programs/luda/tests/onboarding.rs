@@ -0,0 +1,67 @@
+mod common;
+
+use common::Harness;
+use luda::addressing::{derive_index_address, derive_profile_summary_address};
+use luda::instruction::DLUInstruction;
+use solana_program::instruction::AccountMeta;
+use solana_program::program_pack::Pack;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_program;
+use solana_sdk::signature::{Keypair, Signer};
+use spl_associated_token_account::get_associated_token_address;
+use spl_token::state::Account as TokenAccount;
+
+#[tokio::test]
+async fn registering_creates_the_associated_token_account() {
+    let mut h = Harness::start().await;
+    let user = h.onboard_user("user", None).await;
+
+    let token = TokenAccount::unpack(&h.account(user.token).await.data).unwrap();
+    assert_eq!(token.owner, user.owner.pubkey());
+    assert_eq!(token.mint, h.mint);
+    assert_eq!(token.amount, 0);
+}
+
+#[tokio::test]
+async fn registering_keeps_an_existing_token_account() {
+    let mut h = Harness::start().await;
+    let user = h.onboard_user("user", Some(1_000)).await;
+
+    assert_eq!(h.balance(user.token).await, 1_000);
+}
+
+#[tokio::test]
+async fn relayers_onboard_owners_without_sol() {
+    let mut h = Harness::start().await;
+    let (owner, relayer, user) = (Keypair::new(), Keypair::new(), Pubkey::new_unique());
+    let summary = derive_profile_summary_address(&h.program_id, &owner.pubkey()).0;
+    let index = derive_index_address(&h.program_id, &owner.pubkey()).0;
+    let token = get_associated_token_address(&owner.pubkey(), &h.mint);
+    h.set_program_account(user, 1_024);
+    h.fund(relayer.pubkey(), 1_000_000_000);
+
+    // The owner only signs: the harness payer pays the fees and the relayer the rent.
+    let instruction = DLUInstruction::CreateUser { username: "user".to_string(), terms_hash: h.terms_hash, referrer: None };
+    let accounts = vec![
+        AccountMeta::new(user, false),
+        AccountMeta::new_readonly(owner.pubkey(), true),
+        AccountMeta::new_readonly(h.config(), false),
+        AccountMeta::new(summary, false),
+        AccountMeta::new(h.stats(), false),
+        AccountMeta::new(index, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new(relayer.pubkey(), true),
+        AccountMeta::new(token, false),
+        AccountMeta::new_readonly(h.mint, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+    ];
+    h.process(h.instruction(&instruction, accounts), &[&owner, &relayer]).await;
+
+    let token = TokenAccount::unpack(&h.account(token).await.data).unwrap();
+    assert_eq!(token.owner, owner.pubkey());
+    assert_eq!(h.account(summary).await.owner, h.program_id);
+    assert_eq!(h.account(index).await.owner, h.program_id);
+    assert!(h.context.banks_client.get_account(owner.pubkey()).await.unwrap().is_none());
+    assert!(h.account(relayer.pubkey()).await.lamports < 1_000_000_000);
+}
@@ -0,0 +1,163 @@
+mod common;
+
+use common::{DealKind, Harness};
+use luda::addressing::{derive_key_manager_address, EntityType};
+use luda::errors::DLUError;
+use luda::instruction::DLUInstruction;
+use luda::onetimekeys::{self, KeyManager, OneTimeKey, KEY_LIFETIME, NO_KEY};
+use solana_program::instruction::{AccountMeta, Instruction, InstructionError};
+use solana_program::pubkey::Pubkey;
+use solana_program::system_program;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::TransactionError;
+
+fn key_manager(h: &Harness, offer: Pubkey) -> Pubkey {
+    derive_key_manager_address(&h.program_id, &offer).0
+}
+
+fn commit_instruction(h: &Harness, offer: Pubkey, party: &Keypair, key: &str) -> Instruction {
+    let instruction = DLUInstruction::CommitKey { entity_type: EntityType::Offer, key_hash: onetimekeys::hash_key(key), nonce: 7 };
+    let accounts = vec![
+        AccountMeta::new(offer, false),
+        AccountMeta::new_readonly(party.pubkey(), true),
+        AccountMeta::new(key_manager(h, offer), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new(h.context.payer.pubkey(), true),
+    ];
+    h.instruction(&instruction, accounts)
+}
+
+fn regenerate_instruction(h: &Harness, offer: Pubkey, seller: &Keypair, buyer: &Keypair, nonce: u64) -> Instruction {
+    let instruction = DLUInstruction::RegenerateKeys {
+        entity_type: EntityType::Offer,
+        seller_key_hash: onetimekeys::hash_key(&Harness::one_time_key(offer, &seller.pubkey(), nonce)),
+        buyer_key_hash: onetimekeys::hash_key(&Harness::one_time_key(offer, &buyer.pubkey(), nonce)),
+        nonce,
+    };
+    let accounts = vec![
+        AccountMeta::new(offer, false),
+        AccountMeta::new_readonly(seller.pubkey(), true),
+        AccountMeta::new_readonly(buyer.pubkey(), true),
+        AccountMeta::new(key_manager(h, offer), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new(h.context.payer.pubkey(), true),
+    ];
+    h.instruction(&instruction, accounts)
+}
+
+fn custom(code: DLUError) -> TransactionError {
+    TransactionError::InstructionError(0, InstructionError::Custom(code.code()))
+}
+
+#[test]
+fn only_the_committed_key_verifies() {
+    let stored = onetimekeys::hash_key("k3y");
+    assert!(onetimekeys::verify("k3y", &stored));
+    assert!(!onetimekeys::verify("k3Y", &stored));
+    assert!(!onetimekeys::verify("", &stored));
+
+    // Emptied keys are cleared to no key at all, which nothing matches.
+    assert_eq!(onetimekeys::hash_key(""), NO_KEY);
+    assert!(!onetimekeys::verify("", &NO_KEY));
+    assert!(!onetimekeys::verify("k3y", &NO_KEY));
+}
+
+#[test]
+fn key_codes_verify_however_they_were_typed() {
+    let code = onetimekeys::encode_key_code(&[0x3a, 0x91, 0x07, 0xfe, 0x42]);
+    assert_eq!(code.len(), onetimekeys::KEY_CODE_LEN);
+    assert_eq!(onetimekeys::decode_key_code(&code), Ok([0x3a, 0x91, 0x07, 0xfe, 0x42]));
+
+    let key = OneTimeKey::commit(onetimekeys::hash_key(&code), 0).unwrap();
+    let typed = format!("{}-{}", &code[..5], &code[5..]).to_lowercase();
+    assert_eq!(key.check(&typed, 1, DLUError::InvalidBuyerKey), Ok(()));
+
+    // Every mistyped digit and every swap of neighbours fails the checksum.
+    for i in 0..code.len() {
+        let mut digits = code.clone().into_bytes();
+        digits[i] = if digits[i] == b'Z' { b'Y' } else { b'Z' };
+        assert_eq!(onetimekeys::decode_key_code(std::str::from_utf8(&digits).unwrap()), Err(DLUError::InvalidKeyCode));
+        if i + 1 < code.len() && code.as_bytes()[i] != code.as_bytes()[i + 1] {
+            let mut digits = code.clone().into_bytes();
+            digits.swap(i, i + 1);
+            assert_eq!(onetimekeys::decode_key_code(std::str::from_utf8(&digits).unwrap()), Err(DLUError::InvalidKeyCode));
+        }
+    }
+    assert_eq!(key.check(&code[1..], 1, DLUError::InvalidBuyerKey), Err(DLUError::InvalidBuyerKey));
+}
+
+#[test]
+fn key_managers_never_take_a_retired_key_back() {
+    let (seller, buyer) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mut manager = KeyManager::default();
+    manager.record(&seller, 0, onetimekeys::hash_key("first")).unwrap();
+    manager.record(&buyer, 0, onetimekeys::hash_key("other")).unwrap();
+    manager.record(&seller, 1, onetimekeys::hash_key("second")).unwrap();
+    assert!(manager.is_used(0) && !manager.is_used(1));
+    assert_eq!(manager.active_key(&seller).unwrap().nonce, 1);
+
+    assert_eq!(manager.record(&seller, 0, onetimekeys::hash_key("third")), Err(DLUError::KeyAlreadyUsed));
+    assert_eq!(manager.record(&seller, 2, onetimekeys::hash_key("first")), Err(DLUError::KeyAlreadyUsed));
+
+    // Seeds are bound to the deal, the party and the nonce.
+    assert_ne!(onetimekeys::key_seed(1, &seller, 0), onetimekeys::key_seed(1, &seller, 1));
+    assert_ne!(onetimekeys::key_seed(1, &seller, 0), onetimekeys::key_seed(1, &buyer, 0));
+    assert_ne!(onetimekeys::key_seed(1, &seller, 0), onetimekeys::key_seed(2, &seller, 0));
+}
+
+#[tokio::test]
+async fn parties_commit_their_keys_once_and_complete_with_them() {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    let outsider = h.create_user("outsider").await;
+    let (offer, _) = h.list_deal(DealKind::Offer, &seller, &buyer).await;
+    h.accept_deal(DealKind::Offer, offer, &seller, &buyer).await;
+    assert_eq!(h.offer(offer).await.key_hashes(), (NO_KEY, NO_KEY));
+
+    let error = h.try_process_all(&[commit_instruction(&h, offer, &outsider.owner, "guess")], &[&outsider.owner]).await;
+    assert_eq!(error.unwrap_err(), custom(DLUError::NotDealParty));
+
+    let (seller_key, buyer_key) = h.keys(DealKind::Offer, offer).await;
+    let hashes = (onetimekeys::hash_key(&seller_key), onetimekeys::hash_key(&buyer_key));
+    assert_eq!(h.offer(offer).await.key_hashes(), hashes);
+    let error = h.try_process_all(&[commit_instruction(&h, offer, &buyer.owner, "other")], &[&buyer.owner]).await;
+    assert_eq!(error.unwrap_err(), custom(DLUError::KeyAlreadyCommitted));
+
+    h.complete_deal(DealKind::Offer, offer, &seller, &buyer).await;
+    assert_eq!(h.offer(offer).await.key_hashes(), (NO_KEY, NO_KEY));
+}
+
+#[tokio::test]
+async fn expired_keys_are_regenerated_by_both_parties() {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    let (offer, _) = h.list_deal(DealKind::Offer, &seller, &buyer).await;
+    h.accept_deal(DealKind::Offer, offer, &seller, &buyer).await;
+    h.keys(DealKind::Offer, offer).await;
+    let committed_at = h.now().await;
+    assert_eq!(h.offer(offer).await.keys().0.expires_at, committed_at + KEY_LIFETIME);
+
+    h.warp_to(committed_at + KEY_LIFETIME).await;
+    let complete = h.complete_deal_instruction(DealKind::Offer, offer, &seller, &buyer).await;
+    let error = h.try_process_all(&[complete], &[]).await;
+    assert_eq!(error.unwrap_err(), custom(DLUError::KeyExpired));
+
+    // Neither party rotates the keys alone.
+    let alone = regenerate_instruction(&h, offer, &seller.owner, &seller.owner, 1);
+    let error = h.try_process_all(&[alone], &[&seller.owner]).await;
+    assert_eq!(error.unwrap_err(), custom(DLUError::NotDealParty));
+
+    // The expired keys were derived with nonce 0, so they can't be committed again.
+    let reused = regenerate_instruction(&h, offer, &seller.owner, &buyer.owner, 0);
+    let error = h.try_process_all(&[reused], &[&seller.owner, &buyer.owner]).await;
+    assert_eq!(error.unwrap_err(), custom(DLUError::KeyAlreadyUsed));
+
+    let regenerate = regenerate_instruction(&h, offer, &seller.owner, &buyer.owner, 1);
+    h.process_all(&[regenerate], &[&seller.owner, &buyer.owner]).await;
+    assert!(h.offer(offer).await.keys().1.expires_at > committed_at + KEY_LIFETIME);
+    let manager = h.key_manager(offer).await;
+    assert_eq!((manager.keys.len(), manager.used), (4, 0b11));
+    h.complete_deal(DealKind::Offer, offer, &seller, &buyer).await;
+}
@@ -0,0 +1,96 @@
+mod common;
+
+use common::{Harness, Party, INSURANCE, STARTING_BALANCE};
+use luda::addressing::derive_organization_address;
+use luda::constants::{MEMBER_ACCEPTOR, MEMBER_FINISHER};
+use luda::instruction::DLUInstruction;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_program::system_program;
+use solana_sdk::signature::Signer;
+
+fn organization(h: &Harness, owner: &Party) -> Pubkey {
+    derive_organization_address(&h.program_id, &owner.owner.pubkey()).0
+}
+
+fn create_organization_instruction(h: &Harness, owner: &Party) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(owner.owner.pubkey(), true),
+        AccountMeta::new(organization(h, owner), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new(h.context.payer.pubkey(), true),
+    ];
+    h.instruction(&DLUInstruction::CreateOrganization, accounts)
+}
+
+async fn create_organization(h: &mut Harness, owner: &Party) {
+    h.process(create_organization_instruction(h, owner), &[&owner.owner]).await;
+}
+
+async fn set_member(h: &mut Harness, owner: &Party, member: &Party, roles: u8) {
+    let accounts = vec![
+        AccountMeta::new_readonly(owner.owner.pubkey(), true),
+        AccountMeta::new(organization(h, owner), false),
+    ];
+    let instruction = DLUInstruction::SetMember { member: member.owner.pubkey(), roles };
+    h.process(h.instruction(&instruction, accounts), &[&owner.owner]).await;
+}
+
+/// Accepts the shipment for the carrier organization, signed and insured by the member.
+fn member_accept_instruction(h: &Harness, shipment: Pubkey, carrier: &Party, member: &Party) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new(shipment, false),
+        AccountMeta::new(carrier.user, false),
+        AccountMeta::new_readonly(member.owner.pubkey(), true),
+        AccountMeta::new(member.token, false),
+        AccountMeta::new(h.escrow, false),
+        AccountMeta::new_readonly(h.vault_registry(), false),
+        AccountMeta::new_readonly(member.owner.pubkey(), true),
+        AccountMeta::new_readonly(h.config(), false),
+        AccountMeta::new(h.stats(), false),
+        AccountMeta::new_readonly(organization(h, carrier), false),
+    ];
+    h.instruction(&DLUInstruction::AcceptShipment, accounts)
+}
+
+#[tokio::test]
+async fn members_accept_shipments_for_their_organization() {
+    let mut h = Harness::start().await;
+    let sender = h.create_user("sender").await;
+    let recipient = h.create_user("recipient").await;
+    let courier = h.create_user("courier").await;
+    let driver = h.create_user("driver").await;
+    create_organization(&mut h, &courier).await;
+    let (shipment, _) = h.list_shipment(&sender, &recipient).await;
+
+    // Without the acceptor role the driver cannot take on shipments for the courier.
+    set_member(&mut h, &courier, &driver, MEMBER_FINISHER).await;
+    let finisher = member_accept_instruction(&h, shipment, &courier, &driver);
+    assert!(h.try_process_all(&[finisher], &[&driver.owner]).await.is_err());
+
+    set_member(&mut h, &courier, &driver, MEMBER_ACCEPTOR | MEMBER_FINISHER).await;
+    h.process(member_accept_instruction(&h, shipment, &courier, &driver), &[&driver.owner]).await;
+    assert_eq!(h.shipment(shipment).await.carrier_pubkey(), Some(courier.owner.pubkey()));
+    assert_eq!(h.balance(driver.token).await, STARTING_BALANCE - INSURANCE);
+    assert_eq!(h.balance(courier.token).await, STARTING_BALANCE);
+}
+
+#[tokio::test]
+async fn removed_members_no_longer_act_for_the_organization() {
+    let mut h = Harness::start().await;
+    let sender = h.create_user("sender").await;
+    let recipient = h.create_user("recipient").await;
+    let courier = h.create_user("courier").await;
+    let driver = h.create_user("driver").await;
+    create_organization(&mut h, &courier).await;
+    set_member(&mut h, &courier, &driver, MEMBER_ACCEPTOR).await;
+    set_member(&mut h, &courier, &driver, 0).await;
+    let (shipment, _) = h.list_shipment(&sender, &recipient).await;
+
+    let removed = member_accept_instruction(&h, shipment, &courier, &driver);
+    assert!(h.try_process_all(&[removed], &[&driver.owner]).await.is_err());
+
+    // Recreating the organization cannot wipe its members.
+    let recreate = create_organization_instruction(&h, &courier);
+    assert!(h.try_process_all(&[recreate], &[&courier.owner]).await.is_err());
+}
@@ -0,0 +1,59 @@
+mod common;
+
+use common::{DealKind, Harness, Party, PAYMENT, STARTING_BALANCE};
+use luda::errors::DLUError;
+use solana_program::instruction::InstructionError;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+use solana_sdk::transaction::TransactionError;
+
+/// Switches the harness to paying in a second mint, standing in for USDC: the shared
+/// penalty account and the given parties' token accounts hold it, and it gets its own
+/// registered vault and treasury.
+async fn pay_in_usdc(h: &mut Harness, parties: &[&Party]) -> Pubkey {
+    let usdc = Pubkey::new_unique();
+    h.mint = usdc;
+    h.set_mint();
+    h.escrow = h.vault();
+    h.register_vault().await;
+    let penalty = h.penalty;
+    h.set_token_account(penalty, Pubkey::new_unique(), 0);
+    for party in parties {
+        h.set_token_account(party.token, party.owner.pubkey(), STARTING_BALANCE);
+    }
+    usdc
+}
+
+#[tokio::test]
+async fn deals_are_paid_in_the_mint_they_were_listed_in() {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    let usdc = pay_in_usdc(&mut h, &[&seller, &buyer]).await;
+
+    let (offer, _) = h.list_deal(DealKind::Offer, &seller, &buyer).await;
+    assert_eq!(h.offer(offer).await.payment_mint(), usdc);
+    h.accept_deal(DealKind::Offer, offer, &seller, &buyer).await;
+    h.complete_deal(DealKind::Offer, offer, &seller, &buyer).await;
+
+    assert_eq!(h.balance(seller.token).await, STARTING_BALANCE + PAYMENT);
+    assert_eq!(h.balance(buyer.token).await, STARTING_BALANCE - PAYMENT);
+    assert_eq!(h.escrow_balance().await, 0);
+}
+
+#[tokio::test]
+async fn accounts_of_another_mint_are_rejected() {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    let (offer, _) = h.list_deal(DealKind::Offer, &seller, &buyer).await;
+
+    // The buyer tries to pay for the DLU offer with another mint.
+    h.set_token_account_of(buyer.token, Pubkey::new_unique(), buyer.owner.pubkey(), STARTING_BALANCE);
+    let instruction = h.accept_deal_instruction(DealKind::Offer, offer, &seller, &buyer);
+    let error = h.try_process_all(&[instruction], &[&buyer.owner]).await.unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(0, InstructionError::Custom(DLUError::MintMismatch.code()))
+    );
+}
@@ -0,0 +1,119 @@
+mod common;
+
+use common::{Harness, Party};
+use luda::addressing::{derive_penalty_pool_address, derive_penalty_rewards_address, derive_penalty_vault_address, derive_stake_address};
+use luda::errors::DLUError;
+use luda::instruction::DLUInstruction;
+use luda::penalty_pool::{PenaltyPool, PenaltyRewards, PENALTY_DISTRIBUTION_INTERVAL};
+use solana_program::instruction::{AccountMeta, Instruction, InstructionError};
+use solana_program::program_option::COption;
+use solana_program::program_pack::Pack;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_program;
+use solana_sdk::signature::Signer;
+use solana_sdk::transaction::TransactionError;
+use spl_token::state::Mint;
+
+const PENALTIES: u64 = 1_000_000;
+
+fn custom(code: DLUError) -> TransactionError {
+    TransactionError::InstructionError(0, InstructionError::Custom(code.code()))
+}
+
+fn penalty_vault(h: &Harness) -> Pubkey {
+    derive_penalty_vault_address(&h.program_id, &h.mint).0
+}
+
+fn distribute_instruction(h: &Harness) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(h.config(), false),
+        AccountMeta::new(derive_penalty_pool_address(&h.program_id, &h.mint).0, false),
+        AccountMeta::new(penalty_vault(h), false),
+        AccountMeta::new(h.mint, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new(h.context.payer.pubkey(), true),
+    ];
+    h.instruction(&DLUInstruction::DistributePenalties, accounts)
+}
+
+fn claim_instruction(h: &Harness, carrier: &Party) -> Instruction {
+    let owner = carrier.owner.pubkey();
+    let accounts = vec![
+        AccountMeta::new(derive_penalty_pool_address(&h.program_id, &h.mint).0, false),
+        AccountMeta::new(derive_penalty_rewards_address(&h.program_id, &h.mint, &owner).0, false),
+        AccountMeta::new_readonly(derive_stake_address(&h.program_id, &owner).0, false),
+        AccountMeta::new_readonly(owner, true),
+        AccountMeta::new(penalty_vault(h), false),
+        AccountMeta::new(carrier.token, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new(h.context.payer.pubkey(), true),
+    ];
+    h.instruction(&DLUInstruction::ClaimPenaltyRewards, accounts)
+}
+
+/// Creates a carrier with `amount` staked.
+async fn create_staker(h: &mut Harness, username: &str, amount: u64) -> Party {
+    let carrier = h.create_user(username).await;
+    h.stake(&carrier, amount).await;
+    carrier
+}
+
+#[tokio::test]
+async fn penalties_stream_to_enrolled_stakers_after_the_burn() {
+    let mut h = Harness::start().await;
+    let vault = penalty_vault(&h);
+    let alice = create_staker(&mut h, "alice", 300).await;
+    let bob = create_staker(&mut h, "bob", 100).await;
+
+    // The first distribution creates the pool, and the first claims only enroll the stakes.
+    h.process(distribute_instruction(&h), &[]).await;
+    h.process(claim_instruction(&h, &alice), &[&alice.owner]).await;
+    h.process(claim_instruction(&h, &bob), &[&bob.owner]).await;
+    let before = (h.balance(alice.token).await, h.balance(bob.token).await);
+
+    // Penalties arrive, and governance burns a fifth of every distribution.
+    h.set_token_account(vault, vault, PENALTIES);
+    let mut mint = vec![0; Mint::LEN];
+    let state = Mint { mint_authority: COption::None, supply: PENALTIES, decimals: 6, is_initialized: true, freeze_authority: COption::None };
+    Mint::pack(state, &mut mint).unwrap();
+    h.set_account(h.mint, spl_token::id(), mint);
+    let accounts = vec![AccountMeta::new(h.config(), false), AccountMeta::new_readonly(h.context.payer.pubkey(), true)];
+    h.process(h.instruction(&DLUInstruction::SetPenaltyBurnShare { share_bps: 2_000 }, accounts), &[]).await;
+
+    h.next_blockhash().await;
+    let error = h.try_process_all(&[distribute_instruction(&h)], &[]).await;
+    assert_eq!(error.unwrap_err(), custom(DLUError::DistributionTooSoon));
+
+    let now = h.now().await;
+    h.warp_to(now + PENALTY_DISTRIBUTION_INTERVAL).await;
+    h.next_blockhash().await;
+    h.process(distribute_instruction(&h), &[]).await;
+    assert_eq!(h.balance(vault).await, PENALTIES * 4 / 5);
+
+    h.next_blockhash().await;
+    h.process(claim_instruction(&h, &alice), &[&alice.owner]).await;
+    h.next_blockhash().await;
+    h.process(claim_instruction(&h, &bob), &[&bob.owner]).await;
+    assert_eq!(h.balance(alice.token).await, before.0 + PENALTIES * 3 / 5);
+    assert_eq!(h.balance(bob.token).await, before.1 + PENALTIES / 5);
+    assert_eq!(h.balance(vault).await, 0);
+
+    let pool = h.decode(derive_penalty_pool_address(&h.program_id, &h.mint).0, PenaltyPool::deserialize).await;
+    assert_eq!((pool.total_staked, pool.accounted, pool.total_burned), (400, 0, PENALTIES / 5));
+}
+
+#[test]
+fn rewards_of_withdrawn_stake_go_back_to_the_pool() {
+    let mut pool = PenaltyPool::new(Pubkey::new_unique());
+    let mut rewards = PenaltyRewards::new(Pubkey::new_unique());
+    assert_eq!(pool.claim(&mut rewards, 100).unwrap(), 0);
+
+    assert_eq!(pool.distribute(1_000, 0, PENALTY_DISTRIBUTION_INTERVAL).unwrap(), (0, 1_000));
+    // Half the enrolled stake was unstaked before the claim.
+    assert_eq!(pool.claim(&mut rewards, 50).unwrap(), 500);
+    assert_eq!((pool.pending, pool.total_staked, rewards.staked), (500, 50, 50));
+
+    assert_eq!(pool.distribute(500, 0, 2 * PENALTY_DISTRIBUTION_INTERVAL).unwrap(), (0, 500));
+    assert_eq!(pool.claim(&mut rewards, 50).unwrap(), 500);
+    assert_eq!(pool.pending, 0);
+}
@@ -0,0 +1,54 @@
+mod common;
+
+use common::{Harness, Party};
+use luda::instruction::DLUInstruction;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+
+fn pickup_instruction(h: &Harness, shipment: Pubkey, carrier: &Party, sender_key: &str) -> Instruction {
+    let instruction = DLUInstruction::ConfirmPickup { sender_key: sender_key.to_string() };
+    let accounts = vec![AccountMeta::new(shipment, false), AccountMeta::new_readonly(carrier.owner.pubkey(), true)];
+    h.instruction(&instruction, accounts)
+}
+
+#[tokio::test]
+async fn pickup_needs_the_carrier_and_the_senders_key() {
+    let mut h = Harness::start().await;
+    let sender = h.create_user("sender").await;
+    let recipient = h.create_user("recipient").await;
+    let carrier = h.create_user("carrier").await;
+    let (shipment, _) = h.list_shipment(&sender, &recipient).await;
+    h.accept_shipment(shipment, &carrier).await;
+    let (sender_key, _, _) = h.shipment_keys(shipment).await;
+
+    let wrong_key = pickup_instruction(&h, shipment, &carrier, "not the key");
+    assert!(h.try_process_all(&[wrong_key], &[&carrier.owner]).await.is_err());
+    let impostor = pickup_instruction(&h, shipment, &recipient, &sender_key);
+    assert!(h.try_process_all(&[impostor], &[&recipient.owner]).await.is_err());
+
+    h.process(pickup_instruction(&h, shipment, &carrier, &sender_key), &[&carrier.owner]).await;
+    assert_eq!(h.shipment(shipment).await.picked_up_at(), Some(h.now().await));
+}
+
+#[tokio::test]
+async fn the_delivery_clock_starts_at_pickup() {
+    let mut h = Harness::start().await;
+    let sender = h.create_user("sender").await;
+    let recipient = h.create_user("recipient").await;
+    let carrier = h.create_user("carrier").await;
+    let (shipment, drop_off_datetime) = h.list_shipment(&sender, &recipient).await;
+    let scheduled = h.shipment(shipment).await.delivery_deadline();
+    assert_eq!(scheduled, drop_off_datetime);
+
+    h.accept_shipment(shipment, &carrier).await;
+    h.confirm_pickup(shipment, &carrier).await;
+    let picked_up = h.shipment(shipment).await;
+    // Picked up ahead of schedule, so the carrier is due the same window earlier.
+    assert!(picked_up.delivery_deadline() < scheduled);
+
+    // The shipment is overdue once the window from the actual pickup has passed.
+    h.expire_clock(picked_up.delivery_deadline()).await;
+    h.expire_shipment(shipment, &sender, &carrier).await;
+    assert_eq!(h.shipment(shipment).await.escrowed_amount(), 0);
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use common::{DealKind, Harness, Party, PAYMENT, STARTING_BALANCE};
+use luda::addressing::derive_promo_address;
+use luda::errors::DLUError;
+use luda::instruction::DLUInstruction;
+use luda::promo::{promo_code_hash, PromoCodes};
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_program::system_program;
+use solana_sdk::signature::Signer;
+
+fn promo_codes(h: &Harness, offer: Pubkey) -> Pubkey {
+    derive_promo_address(&h.program_id, &offer).0
+}
+
+fn set_promo_code_instruction(h: &Harness, offer: Pubkey, signer: &Party, code: &str, percent: u8, uses: u32) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(offer, false),
+        AccountMeta::new_readonly(signer.owner.pubkey(), true),
+        AccountMeta::new(promo_codes(h, offer), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new(h.context.payer.pubkey(), true),
+    ];
+    let instruction = DLUInstruction::SetPromoCode {
+        code_hash: promo_code_hash(&offer, code),
+        discount_percent: percent,
+        uses,
+    };
+    h.instruction(&instruction, accounts)
+}
+
+/// Accepts the offer for the buyer, presenting `code`.
+fn promo_accept_instruction(h: &Harness, offer: Pubkey, seller: &Party, buyer: &Party, code: &str) -> Instruction {
+    let mut accounts = h.accept_deal_instruction(DealKind::Offer, offer, seller, buyer).accounts;
+    accounts.push(AccountMeta::new(promo_codes(h, offer), false));
+    h.instruction(&DLUInstruction::AcceptOffer { quantity: 1, promo_code: Some(code.to_string()) }, accounts)
+}
+
+#[tokio::test]
+async fn promo_codes_discount_the_escrowed_payment() {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    let (offer, _) = h.list_deal(DealKind::Offer, &seller, &buyer).await;
+
+    // Only the seller attaches codes to their offer.
+    let forged = set_promo_code_instruction(&h, offer, &buyer, "SPRING", 100, 1);
+    assert!(h.try_process_all(&[forged], &[&buyer.owner]).await.is_err());
+    h.process(set_promo_code_instruction(&h, offer, &seller, "SPRING", 25, 1), &[&seller.owner]).await;
+
+    let guessed = promo_accept_instruction(&h, offer, &seller, &buyer, "WINTER");
+    assert!(h.try_process_all(&[guessed], &[&buyer.owner]).await.is_err());
+
+    let discounted = PAYMENT * 3 / 4;
+    h.process(promo_accept_instruction(&h, offer, &seller, &buyer, "SPRING"), &[&buyer.owner]).await;
+    assert_eq!(h.offer(offer).await.payment(), discounted);
+    assert_eq!(h.balance(buyer.token).await, STARTING_BALANCE - discounted - PAYMENT);
+    let codes = h.decode(promo_codes(&h, offer), PromoCodes::deserialize).await;
+    assert_eq!(codes.codes[0].remaining_uses, 0);
+
+    h.complete_deal(DealKind::Offer, offer, &seller, &buyer).await;
+    assert_eq!(h.balance(seller.token).await, STARTING_BALANCE + discounted);
+    assert_eq!(h.balance(buyer.token).await, STARTING_BALANCE - discounted);
+}
+
+#[test]
+fn promo_codes_run_out_and_only_work_on_their_offer() {
+    let offer = Pubkey::new_unique();
+    let mut codes = PromoCodes::default();
+    assert!(matches!(codes.set(promo_code_hash(&offer, "FREE"), 101, 1), Err(DLUError::InvalidDiscount)));
+    codes.set(promo_code_hash(&offer, "SPRING"), 10, 2).unwrap();
+
+    assert!(matches!(codes.redeem(&Pubkey::new_unique(), "SPRING"), Err(DLUError::InvalidPromoCode)));
+    assert_eq!(codes.redeem(&offer, "SPRING").unwrap(), 10);
+    assert_eq!(codes.redeem(&offer, "SPRING").unwrap(), 10);
+    assert!(matches!(codes.redeem(&offer, "SPRING"), Err(DLUError::PromoCodeExhausted)));
+
+    // Setting a code again replaces it, and zero uses withdraws it.
+    codes.set(promo_code_hash(&offer, "SPRING"), 20, 1).unwrap();
+    assert_eq!(codes.redeem(&offer, "SPRING").unwrap(), 20);
+    codes.set(promo_code_hash(&offer, "SPRING"), 20, 0).unwrap();
+    assert!(codes.codes.is_empty());
+}
@@ -0,0 +1,88 @@
+mod common;
+
+use common::{DealKind, Harness, Party, PAYMENT, STARTING_BALANCE};
+use luda::addressing::derive_quote_address;
+use luda::constants::QUOTE_SIZE;
+use luda::instruction::DLUInstruction;
+use luda::quote::{Quote, QuoteStatus};
+use luda::request::RequestStatus;
+use solana_program::instruction::AccountMeta;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+
+const QUOTED_PRICE: u64 = 3_000_000;
+
+fn quote_address(h: &Harness, request: Pubkey, seller: &Party) -> Pubkey {
+    derive_quote_address(&h.program_id, &request, &seller.owner.pubkey()).0
+}
+
+async fn quote(h: &mut Harness, address: Pubkey) -> Quote {
+    h.decode(address, Quote::deserialize).await
+}
+
+async fn submit_quote(h: &mut Harness, request: Pubkey, seller: &Party, price: u64) -> Pubkey {
+    let quote = quote_address(h, request, seller);
+    h.set_program_account(quote, QUOTE_SIZE);
+    let instruction = DLUInstruction::SubmitQuote { price, note: "Delivered next week".to_string() };
+    let accounts = vec![
+        AccountMeta::new_readonly(request, false),
+        AccountMeta::new(quote, false),
+        AccountMeta::new_readonly(seller.user, false),
+        AccountMeta::new_readonly(seller.owner.pubkey(), true),
+        AccountMeta::new(seller.token, false),
+        AccountMeta::new(h.escrow, false),
+        AccountMeta::new_readonly(h.vault_registry(), false),
+        AccountMeta::new_readonly(h.config(), false),
+        AccountMeta::new(h.stats(), false),
+    ];
+    h.process(h.instruction(&instruction, accounts), &[&seller.owner]).await;
+    quote
+}
+
+#[tokio::test]
+async fn selecting_a_quote_accepts_the_request_at_its_price() {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let rival = h.create_user("rival").await;
+    let buyer = h.create_user("buyer").await;
+    let (request, _) = h.list_deal(DealKind::Request, &seller, &buyer).await;
+
+    let chosen = submit_quote(&mut h, request, &seller, QUOTED_PRICE).await;
+    let passed_over = submit_quote(&mut h, request, &rival, PAYMENT).await;
+    assert_eq!(h.balance(seller.token).await, STARTING_BALANCE - QUOTED_PRICE);
+
+    let accounts = vec![
+        AccountMeta::new(request, false),
+        AccountMeta::new_readonly(buyer.owner.pubkey(), true),
+        AccountMeta::new(buyer.token, false),
+        AccountMeta::new(h.escrow, false),
+        AccountMeta::new_readonly(h.vault_registry(), false),
+        AccountMeta::new(chosen, false),
+        AccountMeta::new(seller.user, false),
+        AccountMeta::new_readonly(h.config(), false),
+        AccountMeta::new(h.stats(), false),
+    ];
+    h.process(h.instruction(&DLUInstruction::SelectQuote, accounts), &[&buyer.owner]).await;
+
+    // The buyer's deposit shrinks to the quoted price, insured for as much.
+    assert_eq!(h.request(request).await.status(), RequestStatus::Accepted);
+    assert_eq!(h.balance(buyer.token).await, STARTING_BALANCE - 2 * QUOTED_PRICE);
+    assert_eq!(quote(&mut h, chosen).await.status, QuoteStatus::Selected);
+
+    // The seller who was passed over takes their insurance back.
+    let accounts = vec![
+        AccountMeta::new(passed_over, false),
+        AccountMeta::new_readonly(rival.owner.pubkey(), true),
+        AccountMeta::new(rival.token, false),
+        AccountMeta::new(h.escrow, false),
+        AccountMeta::new_readonly(h.vault_registry(), false),
+        AccountMeta::new(h.stats(), false),
+    ];
+    h.process(h.instruction(&DLUInstruction::WithdrawQuote, accounts), &[&rival.owner]).await;
+    assert_eq!(h.balance(rival.token).await, STARTING_BALANCE);
+    assert_eq!(h.escrow_balance().await, 3 * QUOTED_PRICE);
+
+    h.complete_deal(DealKind::Request, request, &seller, &buyer).await;
+    assert_eq!(h.balance(seller.token).await, STARTING_BALANCE + QUOTED_PRICE);
+    assert_eq!(h.balance(buyer.token).await, STARTING_BALANCE - QUOTED_PRICE);
+}
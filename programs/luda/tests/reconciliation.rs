@@ -0,0 +1,57 @@
+mod common;
+
+use common::{DealKind, Harness};
+use luda::addressing::EntityType;
+use luda::errors::DLUError;
+use luda::instruction::DLUInstruction;
+use solana_program::instruction::{AccountMeta, Instruction, InstructionError};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::transaction::TransactionError;
+
+fn verify_instruction(h: &Harness, entity_type: EntityType, entity: Pubkey) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new(entity, false),
+        AccountMeta::new_readonly(h.vault(), false),
+        AccountMeta::new_readonly(h.vault_registry(), false),
+        AccountMeta::new_readonly(h.stats(), false),
+    ];
+    h.instruction(&DLUInstruction::VerifyEscrow { entity_type }, accounts)
+}
+
+#[tokio::test]
+async fn vaults_short_of_their_escrow_flag_the_deal() {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    let (offer, _) = h.list_deal(DealKind::Offer, &seller, &buyer).await;
+    let (other, _) = h.list_described_deal(DealKind::Offer, &seller, &buyer, "Folding chair").await;
+    let escrowed = h.escrow_balance().await;
+    assert_eq!(escrowed, h.locked(DealKind::Offer, offer).await + h.locked(DealKind::Offer, other).await);
+
+    h.process(verify_instruction(&h, EntityType::Offer, offer), &[]).await;
+    assert!(!h.offer(offer).await.is_escrow_flagged());
+
+    // The vault pools both deals, so covering one of them alone is already short.
+    let vault = h.vault();
+    h.set_token_account(vault, vault, h.locked(DealKind::Offer, offer).await);
+    h.next_blockhash().await;
+    h.process(verify_instruction(&h, EntityType::Offer, offer), &[]).await;
+    assert!(h.offer(offer).await.is_escrow_flagged());
+
+    // The flag stays once the vault is topped up again, and blocks the deal's payouts.
+    h.set_token_account(vault, vault, escrowed);
+    h.next_blockhash().await;
+    h.process(verify_instruction(&h, EntityType::Offer, offer), &[]).await;
+    assert!(h.offer(offer).await.is_escrow_flagged());
+    let cancel = h.cancel_deal_instruction(DealKind::Offer, offer, &seller, &buyer);
+    let error = h.try_process_all(&[cancel], &[&seller.owner]).await;
+    assert_eq!(
+        error.unwrap_err(),
+        TransactionError::InstructionError(0, InstructionError::Custom(DLUError::EscrowFlagged.code()))
+    );
+
+    // A deal verified while the vault covered everything pays out as usual.
+    h.process(verify_instruction(&h, EntityType::Offer, other), &[]).await;
+    assert!(!h.offer(other).await.is_escrow_flagged());
+    h.cancel_deal(DealKind::Offer, other, &seller, &buyer).await;
+}
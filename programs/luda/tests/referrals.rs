@@ -0,0 +1,95 @@
+mod common;
+
+use common::{DealKind, Harness, Party, PAYMENT, STARTING_BALANCE};
+use luda::addressing::derive_referral_address;
+use luda::config::DEFAULT_REFERRAL_SHARE_BPS;
+use luda::instruction::DLUInstruction;
+use luda::referral::ReferralRewards;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_program::system_program;
+use solana_sdk::signature::Signer;
+
+const FEE_BPS: u16 = 100;
+const FEE: u64 = PAYMENT * FEE_BPS as u64 / 10_000;
+const SHARE: u64 = FEE * DEFAULT_REFERRAL_SHARE_BPS as u64 / 10_000;
+
+fn rewards(h: &Harness, referrer: &Party) -> Pubkey {
+    derive_referral_address(&h.program_id, &referrer.owner.pubkey(), &h.mint).0
+}
+
+async fn load_rewards(h: &mut Harness, referrer: &Party) -> ReferralRewards {
+    h.decode(rewards(h, referrer), ReferralRewards::deserialize).await
+}
+
+/// Completes an offer sold by `seller`, passing their referrer's rewards account, which the
+/// payer funds on the first credit.
+async fn complete_referred_offer(h: &mut Harness, seller: &Party, buyer: &Party, referrer: &Party) {
+    let (offer, _) = h.list_deal(DealKind::Offer, seller, buyer).await;
+    h.accept_deal(DealKind::Offer, offer, seller, buyer).await;
+    let mut complete = h.complete_deal_instruction(DealKind::Offer, offer, seller, buyer).await;
+    complete.accounts.extend([
+        AccountMeta::new(rewards(h, referrer), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new(h.context.payer.pubkey(), true),
+    ]);
+    h.process(complete, &[]).await;
+}
+
+fn claim_instruction(h: &Harness, referrer: &Party) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new(rewards(h, referrer), false),
+        AccountMeta::new_readonly(referrer.owner.pubkey(), true),
+        AccountMeta::new(h.treasury(), false),
+        AccountMeta::new(referrer.token, false),
+    ];
+    h.instruction(&DLUInstruction::ClaimReferralRewards, accounts)
+}
+
+#[tokio::test]
+async fn referrers_claim_a_share_of_referred_fees() {
+    let mut h = Harness::start().await;
+    h.set_fee(FEE_BPS).await;
+    let referrer = h.create_user("referrer").await;
+    let seller = h.create_referred_user("seller", &referrer).await;
+    let buyer = h.create_user("buyer").await;
+    assert_eq!(h.user(&seller).await.referrer, Some(referrer.owner.pubkey()));
+
+    // The referred seller cannot complete without crediting the referrer.
+    let (offer, _) = h.list_deal(DealKind::Offer, &seller, &buyer).await;
+    h.accept_deal(DealKind::Offer, offer, &seller, &buyer).await;
+    let uncredited = h.complete_deal_instruction(DealKind::Offer, offer, &seller, &buyer).await;
+    assert!(h.try_process_all(&[uncredited], &[]).await.is_err());
+
+    complete_referred_offer(&mut h, &seller, &buyer, &referrer).await;
+    complete_referred_offer(&mut h, &seller, &buyer, &referrer).await;
+    let earned = load_rewards(&mut h, &referrer).await;
+    assert_eq!((earned.unclaimed, earned.total_earned), (2 * SHARE, 2 * SHARE));
+    assert_eq!(h.balance(h.treasury()).await, 2 * FEE);
+
+    h.process(claim_instruction(&h, &referrer), &[&referrer.owner]).await;
+    assert_eq!(h.balance(referrer.token).await, STARTING_BALANCE + 2 * SHARE);
+    assert_eq!(h.balance(h.treasury()).await, 2 * (FEE - SHARE));
+    assert_eq!(load_rewards(&mut h, &referrer).await.unclaimed, 0);
+}
+
+#[tokio::test]
+async fn only_the_referrer_claims_and_only_once() {
+    let mut h = Harness::start().await;
+    h.set_fee(FEE_BPS).await;
+    let referrer = h.create_user("referrer").await;
+    let seller = h.create_referred_user("seller", &referrer).await;
+    let buyer = h.create_user("buyer").await;
+    complete_referred_offer(&mut h, &seller, &buyer, &referrer).await;
+
+    let mut stolen = claim_instruction(&h, &referrer);
+    stolen.accounts[1] = AccountMeta::new_readonly(seller.owner.pubkey(), true);
+    stolen.accounts[3] = AccountMeta::new(seller.token, false);
+    assert!(h.try_process_all(&[stolen], &[&seller.owner]).await.is_err());
+
+    h.process(claim_instruction(&h, &referrer), &[&referrer.owner]).await;
+    // The extra account only keeps the second claim distinct from the first transaction.
+    let mut again = claim_instruction(&h, &referrer);
+    again.accounts.push(AccountMeta::new_readonly(h.config(), false));
+    assert!(h.try_process_all(&[again], &[&referrer.owner]).await.is_err());
+}
@@ -0,0 +1,73 @@
+mod common;
+
+use common::{Harness, Party, PAYMENT};
+use luda::addressing::{derive_region_address, EntityType};
+use luda::deal::Location;
+use luda::instruction::DLUInstruction;
+use luda::offer::OfferKind;
+use luda::region::RegionIndex;
+use solana_program::instruction::AccountMeta;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_program;
+use solana_sdk::signature::Signer;
+
+const OFFER_SPACE: usize = 4_096;
+
+fn alexanderplatz() -> Location {
+    Location::new("DE", "Berlin".to_string(), "Alexanderplatz 1".to_string()).unwrap().with_geohash("u33dc1").unwrap()
+}
+
+async fn list_offer(h: &mut Harness, seller: &Party, meeting_point: Location, region: Option<Pubkey>) -> Pubkey {
+    let offer = Pubkey::new_unique();
+    h.set_program_account(offer, OFFER_SPACE);
+    let instruction = DLUInstruction::ListOffer {
+        id: 1,
+        goodsorservice_name: "Bicycle".to_string(),
+        goodsorservice_description: "City bike, barely used".to_string(),
+        payment: PAYMENT,
+        meeting_point,
+        meeting_datetime: h.now().await + 86_400,
+        kind: OfferKind::Described,
+        meeting_point_hash: None,
+        holdback: None,
+    };
+    let mut accounts = vec![
+        AccountMeta::new(offer, false),
+        AccountMeta::new_readonly(seller.user, false),
+        AccountMeta::new(seller.owner.pubkey(), true),
+        AccountMeta::new(seller.token, false),
+        AccountMeta::new(h.escrow, false),
+        AccountMeta::new_readonly(h.vault_registry(), false),
+        AccountMeta::new_readonly(h.config(), false),
+        AccountMeta::new(h.stats(), false),
+        AccountMeta::new(h.index(seller), false),
+    ];
+    if let Some(region) = region {
+        // The harness payer sponsors the region's first listing, which creates its index.
+        accounts.extend([
+            AccountMeta::new(region, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(h.context.payer.pubkey(), true),
+        ]);
+    }
+    h.process(h.instruction(&instruction, accounts), &[&seller.owner]).await;
+    offer
+}
+
+#[tokio::test]
+async fn offers_with_a_geohash_are_announced_in_their_region() {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let region = derive_region_address(&h.program_id, b"u33d").0;
+
+    let first = list_offer(&mut h, &seller, alexanderplatz(), Some(region)).await;
+    let second = list_offer(&mut h, &seller, alexanderplatz(), Some(region)).await;
+    // Listings without a geohash pass no region account and stay out of every index.
+    let elsewhere = Location::new("DE", "Berlin".to_string(), "Online".to_string()).unwrap();
+    list_offer(&mut h, &seller, elsewhere, None).await;
+
+    let index = h.decode(region, RegionIndex::deserialize).await;
+    assert_eq!(&index.prefix, b"u33d");
+    let listed: Vec<_> = index.listings.iter().map(|listing| (listing.address, listing.entity_type)).collect();
+    assert_eq!(listed, [(first, EntityType::Offer), (second, EntityType::Offer)]);
+}
@@ -0,0 +1,43 @@
+mod common;
+
+use common::{DealKind, Harness};
+use luda::errors::DLUError;
+use solana_program::instruction::InstructionError;
+use solana_sdk::transaction::TransactionError;
+
+#[tokio::test]
+async fn completions_only_execute_against_the_state_they_were_built_for() {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    let (offer, _) = h.list_deal(DealKind::Offer, &seller, &buyer).await;
+    assert_eq!(h.offer(offer).await.nonce(), 1);
+    h.accept_deal(DealKind::Offer, offer, &seller, &buyer).await;
+    assert_eq!(h.offer(offer).await.nonce(), 2);
+
+    let completion = h.complete_deal_instruction(DealKind::Offer, offer, &seller, &buyer).await;
+    h.process(completion.clone(), &[]).await;
+    assert_eq!(h.offer(offer).await.nonce(), 3);
+
+    // The completion named the accepted offer's nonce, which no longer matches.
+    h.next_blockhash().await;
+    let error = h.try_process_all(&[completion], &[]).await;
+    let code = DLUError::StaleNonce.code();
+    assert_eq!(error.unwrap_err(), TransactionError::InstructionError(0, InstructionError::Custom(code)));
+}
+
+#[tokio::test]
+async fn shipments_advance_their_nonce_on_every_transition() {
+    let mut h = Harness::start().await;
+    let sender = h.create_user("sender").await;
+    let recipient = h.create_user("recipient").await;
+    let carrier = h.create_user("carrier").await;
+    let (shipment, _) = h.list_shipment(&sender, &recipient).await;
+    assert_eq!(h.shipment(shipment).await.nonce(), 1);
+    h.accept_shipment(shipment, &carrier).await;
+    assert_eq!(h.shipment(shipment).await.nonce(), 2);
+    h.confirm_pickup(shipment, &carrier).await;
+    assert_eq!(h.shipment(shipment).await.nonce(), 3);
+    h.complete_shipment(shipment, &sender, &carrier).await;
+    assert_eq!(h.shipment(shipment).await.nonce(), 4);
+}
@@ -0,0 +1,67 @@
+mod common;
+
+use common::{Harness, Party, INSURANCE, PAYMENT};
+use luda::instruction::DLUInstruction;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+
+const SHIPMENT_SPACE: usize = 4_096;
+
+async fn return_instruction(h: &mut Harness, reverse: Pubkey, original: Pubkey, recipient: &Party, sender: &Party) -> Instruction {
+    let pickup_datetime = h.now().await + 3_600;
+    let instruction = DLUInstruction::CreateReturnShipment {
+        id: 2,
+        payment: PAYMENT,
+        pickup_datetime,
+        drop_off_datetime: pickup_datetime + 86_400,
+    };
+    let accounts = vec![
+        AccountMeta::new(reverse, false),
+        AccountMeta::new(original, false),
+        AccountMeta::new_readonly(recipient.user, false),
+        AccountMeta::new_readonly(recipient.owner.pubkey(), true),
+        AccountMeta::new(recipient.token, false),
+        AccountMeta::new(h.escrow, false),
+        AccountMeta::new_readonly(h.vault_registry(), false),
+        AccountMeta::new_readonly(sender.user, false),
+        AccountMeta::new_readonly(h.config(), false),
+        AccountMeta::new(h.stats(), false),
+    ];
+    h.instruction(&instruction, accounts)
+}
+
+#[tokio::test]
+async fn recipients_send_completed_shipments_back_once() {
+    let mut h = Harness::start().await;
+    let sender = h.create_user("sender").await;
+    let recipient = h.create_user("recipient").await;
+    let carrier = h.create_user("carrier").await;
+    let (original, _) = h.list_shipment(&sender, &recipient).await;
+    h.accept_shipment(original, &carrier).await;
+
+    // Nothing can be returned before it was delivered.
+    let reverse = Pubkey::new_unique();
+    h.set_program_account(reverse, SHIPMENT_SPACE);
+    let early = return_instruction(&mut h, reverse, original, &recipient, &sender).await;
+    assert!(h.try_process_all(&[early], &[&recipient.owner]).await.is_err());
+
+    h.confirm_pickup(original, &carrier).await;
+    h.complete_shipment(original, &sender, &carrier).await;
+    let instruction = return_instruction(&mut h, reverse, original, &recipient, &sender).await;
+    h.process(instruction, &[&recipient.owner]).await;
+
+    let shipment = h.shipment(reverse).await;
+    assert_eq!(shipment.return_of(), Some(h.shipment(original).await.id()));
+    assert_eq!((shipment.sender_pubkey(), shipment.recipient_pubkey()), (recipient.owner.pubkey(), sender.owner.pubkey()));
+    assert_eq!(shipment.escrowed_amount(), PAYMENT);
+
+    // The return's carrier insures it like the original's did.
+    h.accept_shipment(reverse, &carrier).await;
+    assert_eq!(h.shipment(reverse).await.escrowed_amount(), PAYMENT + INSURANCE);
+
+    let second = Pubkey::new_unique();
+    h.set_program_account(second, SHIPMENT_SPACE);
+    let again = return_instruction(&mut h, second, original, &recipient, &sender).await;
+    assert!(h.try_process_all(&[again], &[&recipient.owner]).await.is_err());
+}
@@ -0,0 +1,46 @@
+use luda::errors::DLUError;
+use luda::safe_math;
+
+#[test]
+fn add_at_boundary() {
+    assert_eq!(safe_math::add(u64::MAX - 1, 1).unwrap(), u64::MAX);
+    assert!(matches!(safe_math::add(u64::MAX, 1), Err(DLUError::ArithmeticOverflow)));
+}
+
+#[test]
+fn sub_at_boundary() {
+    assert_eq!(safe_math::sub(1, 1).unwrap(), 0);
+    assert!(matches!(safe_math::sub(0, 1), Err(DLUError::ArithmeticOverflow)));
+}
+
+#[test]
+fn mul_at_boundary() {
+    assert_eq!(safe_math::mul(u64::MAX / 2, 2).unwrap(), u64::MAX - 1);
+    assert!(matches!(safe_math::mul(u64::MAX / 2 + 1, 2), Err(DLUError::ArithmeticOverflow)));
+}
+
+#[test]
+fn escrow_total_with_double_insurance() {
+    assert_eq!(safe_math::escrow_total(100, 100, 2).unwrap(), 300);
+
+    // payment + 2 * insurance overflows through the multiplication...
+    assert!(matches!(safe_math::escrow_total(0, u64::MAX / 2 + 1, 2), Err(DLUError::ArithmeticOverflow)));
+    // ...and through the addition.
+    assert!(matches!(safe_math::escrow_total(u64::MAX, 1, 1), Err(DLUError::ArithmeticOverflow)));
+}
+
+#[test]
+fn bps_rounds_down_without_overflow() {
+    assert_eq!(safe_math::bps(10_000, 25).unwrap(), 25);
+    assert_eq!(safe_math::bps(399, 25).unwrap(), 0);
+    assert_eq!(safe_math::bps(u64::MAX, 10_000).unwrap(), u64::MAX);
+}
+
+#[test]
+fn decay_halves_per_half_life() {
+    assert_eq!(safe_math::decay(1_000, 0, 100), 1_000);
+    assert_eq!(safe_math::decay(1_000, 100, 100), 500);
+    assert_eq!(safe_math::decay(1_000, 250, 100), 188);
+    assert_eq!(safe_math::decay(1_000, 64 * 100, 100), 0);
+    assert_eq!(safe_math::decay(1_000, 50, 0), 1_000);
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use common::{DealKind, Harness, Party, PAYMENT, STARTING_BALANCE};
+use luda::addressing::EntityType;
+use luda::instruction::DLUInstruction;
+use luda::offer::OfferStatus;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+
+fn schedule_instruction(
+    h: &Harness,
+    entity_type: EntityType,
+    deal: Pubkey,
+    lister: &Party,
+    activate_at: i64,
+    delist_at: Option<i64>,
+) -> Instruction {
+    let accounts = vec![AccountMeta::new(deal, false), AccountMeta::new_readonly(lister.owner.pubkey(), true)];
+    h.instruction(&DLUInstruction::ScheduleListing { entity_type, activate_at, delist_at }, accounts)
+}
+
+#[tokio::test]
+async fn scheduled_listings_open_at_their_activation_time() {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    let (request, _) = h.list_deal(DealKind::Request, &seller, &buyer).await;
+    let now = h.now().await;
+
+    // Only the buyer who listed the request schedules it.
+    let forged = schedule_instruction(&h, EntityType::Request, request, &seller, now + 3_600, None);
+    assert!(h.try_process_all(&[forged], &[&seller.owner]).await.is_err());
+    let schedule = schedule_instruction(&h, EntityType::Request, request, &buyer, now + 3_600, Some(now + 7_200));
+    h.process(schedule, &[&buyer.owner]).await;
+    assert_eq!(h.request(request).await.listing_window(), (now + 3_600, Some(now + 7_200)));
+
+    let early = h.accept_deal_instruction(DealKind::Request, request, &seller, &buyer);
+    assert!(h.try_process_all(&[early], &[&seller.owner]).await.is_err());
+
+    h.warp_to(now + 3_600).await;
+    h.accept_deal(DealKind::Request, request, &seller, &buyer).await;
+}
+
+#[tokio::test]
+async fn listings_no_one_accepted_are_delisted_with_a_refund() {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    let (offer, _) = h.list_deal(DealKind::Offer, &seller, &buyer).await;
+    let now = h.now().await;
+    let schedule = schedule_instruction(&h, EntityType::Offer, offer, &seller, now, Some(now + 3_600));
+    h.process(schedule, &[&seller.owner]).await;
+    assert_eq!(h.balance(seller.token).await, STARTING_BALANCE - PAYMENT);
+
+    h.warp_to(now + 3_600).await;
+    let late = h.accept_deal_instruction(DealKind::Offer, offer, &seller, &buyer);
+    assert!(h.try_process_all(&[late], &[&buyer.owner]).await.is_err());
+
+    h.expire_deal(DealKind::Offer, offer, &seller, &buyer).await;
+    assert_eq!(h.offer(offer).await.status(), OfferStatus::Expired);
+    assert_eq!(h.balance(seller.token).await, STARTING_BALANCE);
+    assert_eq!(h.balance(buyer.token).await, STARTING_BALANCE);
+}
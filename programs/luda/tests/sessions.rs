@@ -0,0 +1,93 @@
+mod common;
+
+use common::{Harness, Party};
+use luda::addressing::derive_delegation_address;
+use luda::constants::MAX_SESSION_DURATION;
+use luda::instruction::DLUInstruction;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_program::system_program;
+use solana_sdk::signature::{Keypair, Signer};
+
+fn delegation(h: &Harness, owner: &Party, session: &Keypair) -> Pubkey {
+    derive_delegation_address(&h.program_id, &owner.owner.pubkey(), &session.pubkey()).0
+}
+
+fn create_session_instruction(h: &Harness, owner: &Party, session: &Keypair, entities: Vec<Pubkey>, expires_at: i64) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(owner.owner.pubkey(), true),
+        AccountMeta::new(delegation(h, owner, session), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new(h.context.payer.pubkey(), true),
+    ];
+    h.instruction(&DLUInstruction::CreateSession { session: session.pubkey(), entities, expires_at }, accounts)
+}
+
+fn delegated_pickup_instruction(h: &Harness, shipment: Pubkey, carrier: &Party, session: &Keypair, sender_key: &str) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new(shipment, false),
+        AccountMeta::new_readonly(session.pubkey(), true),
+        AccountMeta::new_readonly(delegation(h, carrier, session), false),
+    ];
+    h.instruction(&DLUInstruction::ConfirmPickup { sender_key: sender_key.to_string() }, accounts)
+}
+
+/// Lists a shipment and has the carrier accept it, returning it with the sender's key.
+async fn accepted_shipment(h: &mut Harness, carrier: &Party) -> (Pubkey, String) {
+    let sender = h.create_user("sender").await;
+    let recipient = h.create_user("recipient").await;
+    let (shipment, _) = h.list_shipment(&sender, &recipient).await;
+    h.accept_shipment(shipment, carrier).await;
+    let (sender_key, _, _) = h.shipment_keys(shipment).await;
+    (shipment, sender_key)
+}
+
+#[tokio::test]
+async fn a_session_key_confirms_pickups_it_was_scoped_to() {
+    let mut h = Harness::start().await;
+    let carrier = h.create_user("carrier").await;
+    let (shipment, sender_key) = accepted_shipment(&mut h, &carrier).await;
+    let session = Keypair::new();
+    let now = h.now().await;
+
+    let too_long = create_session_instruction(&h, &carrier, &session, vec![shipment], now + MAX_SESSION_DURATION + 1);
+    assert!(h.try_process_all(&[too_long], &[&carrier.owner]).await.is_err());
+
+    // A session scoped to another shipment cannot stand in for the carrier here.
+    let elsewhere = create_session_instruction(&h, &carrier, &session, vec![Pubkey::new_unique()], now + 3_600);
+    h.process(elsewhere, &[&carrier.owner]).await;
+    let out_of_scope = delegated_pickup_instruction(&h, shipment, &carrier, &session, &sender_key);
+    assert!(h.try_process_all(&[out_of_scope], &[&session]).await.is_err());
+
+    let scoped = create_session_instruction(&h, &carrier, &session, vec![shipment], now + 3_600);
+    h.process(scoped, &[&carrier.owner]).await;
+    h.process(delegated_pickup_instruction(&h, shipment, &carrier, &session, &sender_key), &[&session]).await;
+    assert_eq!(h.shipment(shipment).await.picked_up_at(), Some(h.now().await));
+}
+
+#[tokio::test]
+async fn expired_and_revoked_session_keys_are_refused() {
+    let mut h = Harness::start().await;
+    let carrier = h.create_user("carrier").await;
+    let (shipment, sender_key) = accepted_shipment(&mut h, &carrier).await;
+    let now = h.now().await;
+
+    let revoked = Keypair::new();
+    let create = create_session_instruction(&h, &carrier, &revoked, vec![shipment], now + 3_600);
+    h.process(create, &[&carrier.owner]).await;
+    let accounts = vec![
+        AccountMeta::new_readonly(carrier.owner.pubkey(), true),
+        AccountMeta::new(delegation(&h, &carrier, &revoked), false),
+    ];
+    let revoke = h.instruction(&DLUInstruction::RevokeSession { session: revoked.pubkey() }, accounts);
+    h.process(revoke, &[&carrier.owner]).await;
+    let pickup = delegated_pickup_instruction(&h, shipment, &carrier, &revoked, &sender_key);
+    assert!(h.try_process_all(&[pickup], &[&revoked]).await.is_err());
+
+    let expired = Keypair::new();
+    let create = create_session_instruction(&h, &carrier, &expired, vec![shipment], now + 3_600);
+    h.process(create, &[&carrier.owner]).await;
+    h.expire_clock(now + 3_600).await;
+    let pickup = delegated_pickup_instruction(&h, shipment, &carrier, &expired, &sender_key);
+    assert!(h.try_process_all(&[pickup], &[&expired]).await.is_err());
+}
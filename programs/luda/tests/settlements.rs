@@ -0,0 +1,86 @@
+mod common;
+
+use common::{DealKind, Harness, Party, PAYMENT, STARTING_BALANCE};
+use luda::instruction::DLUInstruction;
+use luda::offer::OfferStatus;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+
+/// Lists an offer and has the buyer accept it, escrowing the payment and both insurances.
+async fn accepted_offer(h: &mut Harness) -> (Party, Party, Pubkey) {
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    let (offer, _) = h.list_deal(DealKind::Offer, &seller, &buyer).await;
+    h.accept_deal(DealKind::Offer, offer, &seller, &buyer).await;
+    (seller, buyer, offer)
+}
+
+/// Nonce of an offer that was listed and accepted, and has not moved since.
+const ACCEPTED_NONCE: u64 = 2;
+
+/// Builds a settlement signed by `first` and, for mutual settlements, `second`; an
+/// arbiter's settlement passes the registry as `second`.
+fn settle_instruction(
+    h: &Harness,
+    offer: Pubkey,
+    seller: &Party,
+    buyer: &Party,
+    first: Pubkey,
+    second: AccountMeta,
+    seller_bps: u16,
+    buyer_bps: u16,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new(offer, false),
+        AccountMeta::new(h.escrow, false),
+        AccountMeta::new_readonly(h.vault_registry(), false),
+        AccountMeta::new(seller.token, false),
+        AccountMeta::new(buyer.token, false),
+        AccountMeta::new(h.stats(), false),
+        AccountMeta::new_readonly(first, true),
+        second,
+    ];
+    h.instruction(&DLUInstruction::SettleDeal { seller_bps, buyer_bps, nonce: ACCEPTED_NONCE }, accounts)
+}
+
+#[tokio::test]
+async fn both_parties_split_the_escrow_by_basis_points() {
+    let mut h = Harness::start().await;
+    let (seller, buyer, offer) = accepted_offer(&mut h).await;
+    let buyer_signs = AccountMeta::new_readonly(buyer.owner.pubkey(), true);
+
+    let partial = settle_instruction(&h, offer, &seller, &buyer, seller.owner.pubkey(), buyer_signs.clone(), 7_000, 2_000);
+    assert!(h.try_process_all(&[partial], &[&seller.owner, &buyer.owner]).await.is_err());
+
+    let settle = settle_instruction(&h, offer, &seller, &buyer, seller.owner.pubkey(), buyer_signs, 7_000, 3_000);
+    h.process(settle, &[&seller.owner, &buyer.owner]).await;
+
+    // The payment and both insurances are shared out 70/30.
+    let escrowed = 3 * PAYMENT;
+    assert_eq!(h.balance(seller.token).await, STARTING_BALANCE - PAYMENT + escrowed * 7 / 10);
+    assert_eq!(h.balance(buyer.token).await, STARTING_BALANCE - 2 * PAYMENT + escrowed * 3 / 10);
+    assert_eq!(h.escrow_balance().await, 0);
+    assert_eq!(h.offer(offer).await.status(), OfferStatus::Settled);
+}
+
+#[tokio::test]
+async fn only_a_registered_arbiter_settles_alone() {
+    let mut h = Harness::start().await;
+    let (seller, buyer, offer) = accepted_offer(&mut h).await;
+    let judge = h.create_user("judge").await;
+    let stranger = h.create_user("stranger").await;
+    h.register_arbiter(&judge).await;
+    let registry = AccountMeta::new_readonly(h.arbiter_registry(), false);
+
+    let unregistered = settle_instruction(&h, offer, &seller, &buyer, stranger.owner.pubkey(), registry.clone(), 5_000, 5_000);
+    assert!(h.try_process_all(&[unregistered], &[&stranger.owner]).await.is_err());
+    let party = settle_instruction(&h, offer, &seller, &buyer, buyer.owner.pubkey(), registry.clone(), 0, 10_000);
+    assert!(h.try_process_all(&[party], &[&buyer.owner]).await.is_err());
+
+    let settle = settle_instruction(&h, offer, &seller, &buyer, judge.owner.pubkey(), registry, 0, 10_000);
+    h.process(settle, &[&judge.owner]).await;
+    assert_eq!(h.balance(seller.token).await, STARTING_BALANCE - PAYMENT);
+    assert_eq!(h.balance(buyer.token).await, STARTING_BALANCE + PAYMENT);
+    assert_eq!(h.offer(offer).await.status(), OfferStatus::Settled);
+}
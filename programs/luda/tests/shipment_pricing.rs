@@ -0,0 +1,63 @@
+mod common;
+
+use common::{Harness, PAYMENT};
+use luda::errors::DLUError;
+use luda::instruction::DLUInstruction;
+use luda::pricing::{self, ShippingRates};
+use solana_program::instruction::{AccountMeta, Instruction, InstructionError};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+use solana_sdk::transaction::TransactionError;
+
+const RATES: ShippingRates = ShippingRates { base_rate: 1_000, rate_per_km: 50, rate_per_kg: 200 };
+
+fn measure_instruction(h: &Harness, shipment: Pubkey, sender: Pubkey) -> Instruction {
+    let measure = DLUInstruction::MeasureShipment { weight_grams: Some(2_500), volume_cm3: None, distance_km: Some(12) };
+    let accounts = vec![
+        AccountMeta::new(shipment, false),
+        AccountMeta::new_readonly(sender, true),
+        AccountMeta::new_readonly(h.config(), false),
+    ];
+    h.instruction(&measure, accounts)
+}
+
+#[tokio::test]
+async fn listed_shipments_keep_what_their_payment_was_quoted_on() {
+    let mut h = Harness::start().await;
+    let sender = h.create_user("sender").await;
+    let recipient = h.create_user("recipient").await;
+    let carrier = h.create_user("carrier").await;
+
+    let set_rates = DLUInstruction::SetShippingRates { base_rate: 1_000, rate_per_km: 50, rate_per_kg: 200 };
+    let accounts = vec![AccountMeta::new(h.config(), false), AccountMeta::new_readonly(h.context.payer.pubkey(), true)];
+    h.process(h.instruction(&set_rates, accounts), &[]).await;
+
+    let (shipment, _) = h.list_shipment(&sender, &recipient).await;
+    h.process(measure_instruction(&h, shipment, sender.owner.pubkey()), &[&sender.owner]).await;
+    let measured = h.shipment(shipment).await;
+    assert_eq!(measured.measurements(), (Some(2_500), None, Some(12)));
+    assert_eq!(measured.quoted_rates(), Some(RATES));
+    assert_eq!(measured.suggested_payment().unwrap(), Some(1_000 + 12 * 50 + 500));
+    assert_eq!(measured.payment(), PAYMENT);
+
+    // Once a carrier accepted, the quote inputs are part of the deal and stay as they are.
+    h.accept_shipment(shipment, &carrier).await;
+    h.next_blockhash().await;
+    let error = h.try_process_all(&[measure_instruction(&h, shipment, sender.owner.pubkey())], &[&sender.owner]).await;
+    let code = DLUError::NotListed.code();
+    assert_eq!(error.unwrap_err(), TransactionError::InstructionError(0, InstructionError::Custom(code)));
+}
+
+#[test]
+fn bulky_parcels_are_charged_on_their_volumetric_weight() {
+    // 20,000 cm³ weigh in as 4 kg, heavier than the parcel itself.
+    assert_eq!(pricing::chargeable_grams(Some(1_000), Some(20_000)), 4_000);
+    assert_eq!(pricing::chargeable_grams(Some(5_000), Some(20_000)), 5_000);
+    assert_eq!(pricing::suggested_payment(&RATES, Some(1_000), Some(20_000), None).unwrap(), 1_000 + 800);
+
+    // Partial kilograms round up, and nothing measured leaves the base rate.
+    assert_eq!(pricing::suggested_payment(&RATES, Some(1), None, None).unwrap(), 1_001);
+    assert_eq!(pricing::suggested_payment(&RATES, None, None, None).unwrap(), 1_000);
+    let steep = ShippingRates { rate_per_km: u64::MAX, ..RATES };
+    assert_eq!(pricing::suggested_payment(&steep, None, None, Some(2)), Err(DLUError::ArithmeticOverflow));
+}
@@ -0,0 +1,75 @@
+mod common;
+
+use common::{DealKind, Harness, PAYMENT};
+use luda::addressing::EntityType;
+use luda::deal::DealStatus;
+use luda::errors::DLUError;
+use luda::instruction::DLUInstruction;
+use luda::shipment::ShipmentStatus;
+use luda::time::MAX_LISTING_LIFETIME;
+use solana_program::instruction::{AccountMeta, Instruction, InstructionError};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::transaction::TransactionError;
+
+fn reclaim_instruction(h: &Harness, entity_type: EntityType, entity: Pubkey, lister_account: Pubkey) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new(entity, false),
+        AccountMeta::new(h.vault(), false),
+        AccountMeta::new_readonly(h.vault_registry(), false),
+        AccountMeta::new(lister_account, false),
+        AccountMeta::new(h.stats(), false),
+    ];
+    h.instruction(&DLUInstruction::ReclaimStaleListing { entity_type }, accounts)
+}
+
+fn custom(code: DLUError) -> TransactionError {
+    TransactionError::InstructionError(0, InstructionError::Custom(code.code()))
+}
+
+#[tokio::test]
+async fn anyone_reclaims_a_listing_no_one_accepted() {
+    let mut h = Harness::start().await;
+    let vault = h.vault();
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    let (offer, meeting_datetime) = h.list_deal(DealKind::Offer, &seller, &buyer).await;
+    let before = h.balance(seller.token).await;
+
+    // The listing stays open for its whole lifetime past the meeting.
+    h.warp_to(meeting_datetime + MAX_LISTING_LIFETIME - 1).await;
+    let early = reclaim_instruction(&h, EntityType::Offer, offer, seller.token);
+    let error = h.try_process_all(&[early], &[]).await;
+    assert_eq!(error.unwrap_err(), custom(DLUError::ListingNotStale));
+
+    // Nobody but the seller receives the refund.
+    h.warp_to(meeting_datetime + MAX_LISTING_LIFETIME).await;
+    let stolen = reclaim_instruction(&h, EntityType::Offer, offer, buyer.token);
+    let error = h.try_process_all(&[stolen], &[]).await;
+    assert_eq!(error.unwrap_err(), custom(DLUError::KeyMismatch));
+
+    // Identical to the early reclaim, so it needs a fresh blockhash.
+    h.next_blockhash().await;
+    h.process(reclaim_instruction(&h, EntityType::Offer, offer, seller.token), &[]).await;
+    assert_eq!(h.balance(seller.token).await, before + PAYMENT);
+    assert_eq!(h.balance(vault).await, 0);
+    assert_eq!(h.offer(offer).await.status(), DealStatus::Expired);
+
+    h.next_blockhash().await;
+    let again = reclaim_instruction(&h, EntityType::Offer, offer, seller.token);
+    let error = h.try_process_all(&[again], &[]).await;
+    assert_eq!(error.unwrap_err(), custom(DLUError::NotListed));
+}
+
+#[tokio::test]
+async fn stale_shipments_refund_the_sender() {
+    let mut h = Harness::start().await;
+    let sender = h.create_user("sender").await;
+    let recipient = h.create_user("recipient").await;
+    let (shipment, drop_off_datetime) = h.list_shipment(&sender, &recipient).await;
+    let before = h.balance(sender.token).await;
+
+    h.warp_to(drop_off_datetime + MAX_LISTING_LIFETIME).await;
+    h.process(reclaim_instruction(&h, EntityType::Shipment, shipment, sender.token), &[]).await;
+    assert_eq!(h.balance(sender.token).await, before + PAYMENT);
+    assert_eq!(h.shipment(shipment).await.status(), ShipmentStatus::Expired);
+}
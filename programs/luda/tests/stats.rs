@@ -0,0 +1,33 @@
+mod common;
+
+use common::{DealKind, Harness};
+use luda::stats::Stats;
+
+async fn read_stats(h: &mut Harness) -> Stats {
+    h.decode(h.stats(), Stats::deserialize).await
+}
+
+#[tokio::test]
+async fn stats_count_users_listings_and_outcomes() {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+
+    let (completed, _) = h.list_deal(DealKind::Offer, &seller, &buyer).await;
+    h.accept_deal(DealKind::Offer, completed, &seller, &buyer).await;
+    h.complete_deal(DealKind::Offer, completed, &seller, &buyer).await;
+    let (failed, _) = h.list_deal(DealKind::Request, &seller, &buyer).await;
+    h.accept_deal(DealKind::Request, failed, &seller, &buyer).await;
+    h.fail_deal(DealKind::Request, failed, &seller, &buyer).await;
+
+    let stats = read_stats(&mut h).await;
+    assert_eq!(stats.users_created, 2);
+    assert_eq!((stats.listings, stats.completions, stats.failures), (2, 1, 1));
+    assert!(stats.penalties > 0);
+    assert_eq!(stats.penalties, h.balance(h.penalty).await);
+
+    // Whatever is still escrowed, such as the fail bond, is the difference of the volumes.
+    assert_eq!(stats.volume_escrowed - stats.volume_released, stats.total_value_locked);
+    assert_eq!(stats.total_value_locked, h.escrow_balance().await);
+    assert_eq!(stats.locked_in(&h.vault()), stats.total_value_locked);
+}
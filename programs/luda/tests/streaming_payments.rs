@@ -0,0 +1,135 @@
+mod common;
+
+use common::{DealKind, Harness, Party, ENTITY_SPACE, PAYMENT, STARTING_BALANCE};
+use luda::deal::Location;
+use luda::errors::DLUError;
+use luda::instruction::DLUInstruction;
+use luda::offer::{OfferKind, OfferStatus};
+use luda::stream::PaymentStream;
+use luda::time::SECONDS_PER_DAY;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+
+const DURATION: i64 = 28 * SECONDS_PER_DAY;
+
+/// Lists a four-week streaming service starting a day from now and has the buyer accept
+/// it, returning the offer and when the service starts.
+async fn accepted_stream(h: &mut Harness, seller: &Party, buyer: &Party) -> (Pubkey, i64) {
+    let offer = Pubkey::new_unique();
+    h.set_program_account(offer, ENTITY_SPACE);
+    let start = h.now().await + SECONDS_PER_DAY;
+    let instruction = DLUInstruction::ListOffer {
+        id: 1,
+        goodsorservice_name: "Gardening".to_string(),
+        goodsorservice_description: "Weekly lawn care".to_string(),
+        payment: PAYMENT,
+        meeting_point: Location::new("DE", "Berlin".to_string(), "Alexanderplatz 1".to_string()).unwrap(),
+        meeting_datetime: start,
+        kind: OfferKind::Stream(PaymentStream::new(DURATION)),
+        meeting_point_hash: None,
+        holdback: None,
+    };
+    let accounts = vec![
+        AccountMeta::new(offer, false),
+        AccountMeta::new_readonly(seller.user, false),
+        AccountMeta::new(seller.owner.pubkey(), true),
+        AccountMeta::new(seller.token, false),
+        AccountMeta::new(h.escrow, false),
+        AccountMeta::new_readonly(h.vault_registry(), false),
+        AccountMeta::new_readonly(h.config(), false),
+        AccountMeta::new(h.stats(), false),
+        AccountMeta::new(h.index(seller), false),
+    ];
+    h.process(h.instruction(&instruction, accounts), &[&seller.owner]).await;
+    h.accept_deal(DealKind::Offer, offer, seller, buyer).await;
+    (offer, start)
+}
+
+fn withdraw_instruction(h: &Harness, offer: Pubkey, seller: &Party) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new(offer, false),
+        AccountMeta::new_readonly(seller.user, false),
+        AccountMeta::new_readonly(seller.owner.pubkey(), true),
+        AccountMeta::new(seller.token, false),
+        AccountMeta::new(h.escrow, false),
+        AccountMeta::new_readonly(h.vault_registry(), false),
+        AccountMeta::new_readonly(h.config(), false),
+        AccountMeta::new(h.treasury(), false),
+        AccountMeta::new(h.stats(), false),
+    ];
+    h.instruction(&DLUInstruction::WithdrawStream, accounts)
+}
+
+fn stop_instruction(h: &Harness, offer: Pubkey, party: &Party) -> Instruction {
+    let accounts = vec![AccountMeta::new(offer, false), AccountMeta::new_readonly(party.owner.pubkey(), true)];
+    h.instruction(&DLUInstruction::StopStream, accounts)
+}
+
+#[tokio::test]
+async fn sellers_withdraw_the_vested_payment_and_complete_for_the_rest() {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    let (offer, start) = accepted_stream(&mut h, &seller, &buyer).await;
+
+    h.warp_to(start + DURATION / 4).await;
+    h.process(withdraw_instruction(&h, offer, &seller), &[&seller.owner]).await;
+    assert_eq!(h.balance(seller.token).await, STARTING_BALANCE - PAYMENT + PAYMENT / 4);
+    assert_eq!(h.offer(offer).await.payment(), PAYMENT * 3 / 4);
+
+    h.complete_deal(DealKind::Offer, offer, &seller, &buyer).await;
+    assert_eq!(h.balance(seller.token).await, STARTING_BALANCE + PAYMENT);
+    assert_eq!(h.balance(buyer.token).await, STARTING_BALANCE - PAYMENT);
+}
+
+#[tokio::test]
+async fn stopped_streams_leave_the_unvested_remainder_to_a_settlement() {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    let stranger = h.create_user("stranger").await;
+    let (offer, start) = accepted_stream(&mut h, &seller, &buyer).await;
+
+    h.warp_to(start + DURATION / 2).await;
+    let forged = stop_instruction(&h, offer, &stranger);
+    assert!(h.try_process_all(&[forged], &[&stranger.owner]).await.is_err());
+    h.process(stop_instruction(&h, offer, &buyer), &[&buyer.owner]).await;
+
+    // Vesting stopped halfway, however long the seller waits to withdraw.
+    h.warp_to(start + DURATION).await;
+    h.process(withdraw_instruction(&h, offer, &seller), &[&seller.owner]).await;
+    assert_eq!(h.balance(seller.token).await, STARTING_BALANCE - PAYMENT + PAYMENT / 2);
+
+    // The remainder goes back to the buyer and each side recovers their insurance.
+    let nonce = h.offer(offer).await.nonce();
+    let settle = DLUInstruction::SettleDeal { seller_bps: 4_000, buyer_bps: 6_000, nonce };
+    let accounts = vec![
+        AccountMeta::new(offer, false),
+        AccountMeta::new(h.escrow, false),
+        AccountMeta::new_readonly(h.vault_registry(), false),
+        AccountMeta::new(seller.token, false),
+        AccountMeta::new(buyer.token, false),
+        AccountMeta::new(h.stats(), false),
+        AccountMeta::new_readonly(seller.owner.pubkey(), true),
+        AccountMeta::new_readonly(buyer.owner.pubkey(), true),
+    ];
+    h.process(h.instruction(&settle, accounts), &[&seller.owner, &buyer.owner]).await;
+    assert_eq!(h.balance(seller.token).await, STARTING_BALANCE + PAYMENT / 2);
+    assert_eq!(h.balance(buyer.token).await, STARTING_BALANCE - PAYMENT / 2);
+    assert_eq!(h.offer(offer).await.status(), OfferStatus::Settled);
+}
+
+#[test]
+fn streams_vest_linearly_until_stopped() {
+    let mut stream = PaymentStream::new(100);
+    assert!(matches!(stream.withdraw(1_000, 50, 50), Err(DLUError::NothingVested)));
+    assert_eq!(stream.withdraw(1_000, 50, 80).unwrap(), 300);
+    // What was withdrawn left escrow, but still counts towards the vested total.
+    assert_eq!(stream.withdraw(700, 50, 100).unwrap(), 200);
+
+    stream.stop(100).unwrap();
+    assert!(matches!(stream.stop(120), Err(DLUError::StreamStopped)));
+    assert!(matches!(stream.withdraw(500, 50, 200), Err(DLUError::NothingVested)));
+    assert_eq!(stream.vested(1_000, 50, 200), 500);
+}
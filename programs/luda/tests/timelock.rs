@@ -0,0 +1,145 @@
+mod common;
+
+use common::Harness;
+use luda::addressing::derive_timelock_address;
+use luda::config::{Config, CAP_TIMELOCKED_GOVERNANCE};
+use luda::errors::DLUError;
+use luda::instruction::DLUInstruction;
+use luda::timelock::{GovernanceAction, TimelockQueue, GOVERNANCE_TIMELOCK, MAX_QUEUED_ACTIONS};
+use solana_program::instruction::{AccountMeta, Instruction, InstructionError};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::TransactionError;
+
+fn governance_instruction(h: &Harness, instruction: &DLUInstruction) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new(h.config(), false),
+        AccountMeta::new_readonly(h.context.payer.pubkey(), true),
+        AccountMeta::new(derive_timelock_address(&h.program_id).0, false),
+    ];
+    h.instruction(instruction, accounts)
+}
+
+fn execute_instruction(h: &Harness, id: u64) -> Instruction {
+    let accounts = vec![AccountMeta::new(h.config(), false), AccountMeta::new(derive_timelock_address(&h.program_id).0, false)];
+    h.instruction(&DLUInstruction::ExecuteGovernanceAction { id }, accounts)
+}
+
+fn custom(code: DLUError) -> TransactionError {
+    TransactionError::InstructionError(0, InstructionError::Custom(code.code()))
+}
+
+async fn config(h: &mut Harness) -> Config {
+    h.decode(h.config(), Config::deserialize).await
+}
+
+#[test]
+fn queued_actions_wait_out_the_timelock() {
+    let mut queue = TimelockQueue::default();
+    let fee = GovernanceAction::SetFee { fee_bps: 100, exempt_credible: false };
+    let (id, executable_at) = queue.queue(fee.clone(), 1_000).unwrap();
+    assert_eq!(executable_at, 1_000 + GOVERNANCE_TIMELOCK);
+
+    assert!(matches!(queue.take_executable(id, executable_at - 1), Err(DLUError::ActionTimelocked)));
+    assert_eq!(queue.take_executable(id, executable_at).unwrap(), fee);
+    assert!(matches!(queue.take_executable(id, executable_at), Err(DLUError::ActionNotQueued)));
+
+    // IDs are never reused, so a cancel can't hit an action queued after it.
+    for _ in 0..MAX_QUEUED_ACTIONS {
+        queue.queue(fee.clone(), 1_000).unwrap();
+    }
+    assert!(matches!(queue.queue(fee.clone(), 1_000), Err(DLUError::TimelockQueueFull)));
+    assert!(matches!(queue.cancel(id), Err(DLUError::ActionNotQueued)));
+    queue.cancel(id + 1).unwrap();
+    assert_eq!(queue.queued.len(), MAX_QUEUED_ACTIONS - 1);
+}
+
+#[tokio::test]
+async fn config_updates_go_through_the_timelock() {
+    let mut h = Harness::start().await;
+
+    // Once governance routes its updates through the queue, it can't update directly.
+    let capabilities = DLUInstruction::SetCapabilities { capabilities: CAP_TIMELOCKED_GOVERNANCE };
+    h.process(governance_instruction(&h, &capabilities), &[]).await;
+    let direct = governance_instruction(&h, &DLUInstruction::SetFee { fee_bps: 100, exempt_credible: false });
+    let error = h.try_process_all(&[direct], &[]).await;
+    assert_eq!(error.unwrap_err(), custom(DLUError::GovernanceTimelocked));
+
+    let action = GovernanceAction::SetFee { fee_bps: 100, exempt_credible: false };
+    h.process(governance_instruction(&h, &DLUInstruction::QueueGovernanceAction { action }), &[]).await;
+    let queued_at = h.now().await;
+
+    let error = h.try_process_all(&[execute_instruction(&h, 0)], &[]).await;
+    assert_eq!(error.unwrap_err(), custom(DLUError::ActionTimelocked));
+
+    // Anyone executes it once the timelock has passed.
+    h.warp_to(queued_at + GOVERNANCE_TIMELOCK).await;
+    h.next_blockhash().await;
+    h.process(execute_instruction(&h, 0), &[]).await;
+    assert_eq!(config(&mut h).await.fee_bps, 100);
+
+    // A canceled action never applies.
+    let action = GovernanceAction::SetCapabilities { capabilities: 0 };
+    h.process(governance_instruction(&h, &DLUInstruction::QueueGovernanceAction { action }), &[]).await;
+    h.process(governance_instruction(&h, &DLUInstruction::CancelGovernanceAction { id: 1 }), &[]).await;
+    h.warp_to(queued_at + 2 * GOVERNANCE_TIMELOCK).await;
+    let error = h.try_process_all(&[execute_instruction(&h, 1)], &[]).await;
+    assert_eq!(error.unwrap_err(), custom(DLUError::ActionNotQueued));
+    assert!(config(&mut h).await.has_capability(CAP_TIMELOCKED_GOVERNANCE));
+}
+
+#[tokio::test]
+async fn only_governance_queues_actions() {
+    let mut h = Harness::start().await;
+    let outsider = Keypair::new();
+    let accounts = vec![
+        AccountMeta::new_readonly(h.config(), false),
+        AccountMeta::new_readonly(outsider.pubkey(), true),
+        AccountMeta::new(derive_timelock_address(&h.program_id).0, false),
+    ];
+    let action = GovernanceAction::SetCapabilities { capabilities: 0 };
+    let queue = h.instruction(&DLUInstruction::QueueGovernanceAction { action }, accounts);
+    let error = h.try_process_all(&[queue], &[&outsider]).await;
+    assert_eq!(error.unwrap_err(), custom(DLUError::NotAuthorized));
+}
+
+#[tokio::test]
+async fn treasury_withdrawals_go_through_the_timelock() {
+    let mut h = Harness::start().await;
+    let treasury = h.treasury();
+    let destination = Pubkey::new_unique();
+    h.set_token_account(treasury, treasury, 500);
+    h.set_token_account(destination, Pubkey::new_unique(), 0);
+    let capabilities = DLUInstruction::SetCapabilities { capabilities: CAP_TIMELOCKED_GOVERNANCE };
+    h.process(governance_instruction(&h, &capabilities), &[]).await;
+
+    // Governance can no longer drain the treasury at once.
+    let accounts = vec![
+        AccountMeta::new_readonly(h.config(), false),
+        AccountMeta::new_readonly(h.context.payer.pubkey(), true),
+        AccountMeta::new(treasury, false),
+        AccountMeta::new(destination, false),
+    ];
+    let direct = h.instruction(&DLUInstruction::WithdrawTreasury { amount: 500 }, accounts);
+    let error = h.try_process_all(&[direct], &[]).await;
+    assert_eq!(error.unwrap_err(), custom(DLUError::GovernanceTimelocked));
+
+    let action = GovernanceAction::WithdrawTreasury { mint: h.mint, destination, amount: 500 };
+    h.process(governance_instruction(&h, &DLUInstruction::QueueGovernanceAction { action }), &[]).await;
+    let queued_at = h.now().await;
+    h.warp_to(queued_at + GOVERNANCE_TIMELOCK).await;
+
+    // The executor can't redirect the withdrawal to another account.
+    let elsewhere = Pubkey::new_unique();
+    h.set_token_account(elsewhere, Pubkey::new_unique(), 0);
+    let mut redirected = execute_instruction(&h, 0);
+    redirected.accounts.extend([AccountMeta::new(treasury, false), AccountMeta::new(elsewhere, false)]);
+    let error = h.try_process_all(&[redirected], &[]).await;
+    assert_eq!(error.unwrap_err(), custom(DLUError::KeyMismatch));
+
+    let mut execute = execute_instruction(&h, 0);
+    execute.accounts.extend([AccountMeta::new(treasury, false), AccountMeta::new(destination, false)]);
+    h.process(execute, &[]).await;
+    assert_eq!(h.balance(destination).await, 500);
+    assert_eq!(h.balance(treasury).await, 0);
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use common::{Harness, Party, STARTING_BALANCE};
+use luda::instruction::DLUInstruction;
+use solana_program::instruction::AccountMeta;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+use spl_token_2022::extension::transfer_fee::{TransferFee, TransferFeeAmount, TransferFeeConfig};
+use spl_token_2022::extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions, StateWithExtensionsMut};
+use spl_token_2022::state::{Account as TokenAccount, AccountState, Mint};
+
+const FEE_BPS: u16 = 100;  // Charged by the mint on every transfer.
+const DEPOSIT: u64 = 5_000_000;
+
+fn fee() -> TransferFee {
+    TransferFee { epoch: 0.into(), maximum_fee: u64::MAX.into(), transfer_fee_basis_points: FEE_BPS.into() }
+}
+
+/// Sets up a Token-2022 mint charging `FEE_BPS` on every transfer.
+fn set_fee_mint(h: &mut Harness) -> Pubkey {
+    let len = ExtensionType::try_calculate_account_len::<Mint>(&[ExtensionType::TransferFeeConfig]).unwrap();
+    let mut data = vec![0; len];
+    let mut state = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut data).unwrap();
+    let config = state.init_extension::<TransferFeeConfig>(true).unwrap();
+    config.older_transfer_fee = fee();
+    config.newer_transfer_fee = fee();
+    state.base = Mint { decimals: 6, is_initialized: true, ..Mint::default() };
+    state.pack_base();
+    state.init_account_type().unwrap();
+
+    let mint = Pubkey::new_unique();
+    h.set_account(mint, spl_token_2022::id(), data);
+    mint
+}
+
+fn set_fee_token_account(h: &mut Harness, address: Pubkey, mint: Pubkey, owner: Pubkey, amount: u64) {
+    let len = ExtensionType::try_calculate_account_len::<TokenAccount>(&[ExtensionType::TransferFeeAmount]).unwrap();
+    let mut data = vec![0; len];
+    let mut state = StateWithExtensionsMut::<TokenAccount>::unpack_uninitialized(&mut data).unwrap();
+    state.init_extension::<TransferFeeAmount>(true).unwrap();
+    state.base = TokenAccount { mint, owner, amount, state: AccountState::Initialized, ..TokenAccount::default() };
+    state.pack_base();
+    state.init_account_type().unwrap();
+    h.set_account(address, spl_token_2022::id(), data);
+}
+
+async fn balance(h: &mut Harness, address: Pubkey) -> u64 {
+    let data = h.account(address).await.data;
+    StateWithExtensions::<TokenAccount>::unpack(&data).unwrap().base.amount
+}
+
+/// Creates a user whose token account and wallet hold DLU of the fee-charging mint.
+async fn fee_user(h: &mut Harness, mint: Pubkey) -> Party {
+    let party = h.create_user("user").await;
+    let wallet = h.wallet(&party);
+    set_fee_token_account(h, party.token, mint, party.owner.pubkey(), STARTING_BALANCE);
+    set_fee_token_account(h, wallet, mint, wallet, 0);
+    party
+}
+
+async fn move_dlu(h: &mut Harness, party: &Party, mint: Pubkey, instruction: DLUInstruction) {
+    let accounts = vec![
+        AccountMeta::new(party.user, false),
+        AccountMeta::new_readonly(party.owner.pubkey(), true),
+        AccountMeta::new(party.token, false),
+        AccountMeta::new(h.wallet(party), false),
+        AccountMeta::new_readonly(mint, false),
+    ];
+    h.process(h.instruction(&instruction, accounts), &[&party.owner]).await;
+}
+
+#[tokio::test]
+async fn deposits_arrive_in_full_despite_the_transfer_fee() {
+    let mut h = Harness::start().await;
+    let mint = set_fee_mint(&mut h);
+    let user = fee_user(&mut h, mint).await;
+
+    move_dlu(&mut h, &user, mint, DLUInstruction::DepositDLU { amount: DEPOSIT }).await;
+    let wallet = h.wallet(&user);
+    assert_eq!(balance(&mut h, wallet).await, DEPOSIT);
+    assert_eq!(h.user(&user).await.wallet.balance, DEPOSIT);
+    // The owner paid the fee on top of the deposit.
+    let paid = STARTING_BALANCE - balance(&mut h, user.token).await;
+    assert_eq!(paid, DEPOSIT + fee().calculate_inverse_fee(DEPOSIT).unwrap());
+}
+
+#[tokio::test]
+async fn withdrawals_have_the_transfer_fee_withheld() {
+    let mut h = Harness::start().await;
+    let mint = set_fee_mint(&mut h);
+    let user = fee_user(&mut h, mint).await;
+    move_dlu(&mut h, &user, mint, DLUInstruction::DepositDLU { amount: DEPOSIT }).await;
+    let before = balance(&mut h, user.token).await;
+
+    move_dlu(&mut h, &user, mint, DLUInstruction::WithdrawDLU { amount: DEPOSIT }).await;
+    assert_eq!(h.user(&user).await.wallet.balance, 0);
+    let withheld_fee = fee().calculate_fee(DEPOSIT).unwrap();
+    assert_eq!(balance(&mut h, user.token).await - before, DEPOSIT - withheld_fee);
+
+    let data = h.account(user.token).await.data;
+    let state = StateWithExtensions::<TokenAccount>::unpack(&data).unwrap();
+    let withheld: u64 = state.get_extension::<TransferFeeAmount>().unwrap().withheld_amount.into();
+    assert_eq!(withheld, withheld_fee);
+}
@@ -0,0 +1,54 @@
+use luda::dlu_token::DLUToken;
+use luda::errors::DLUError;
+use solana_program::account_info::AccountInfo;
+use solana_program::program_option::COption;
+use solana_program::program_pack::Pack;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_program;
+use spl_token::state::{Account as TokenAccount, AccountState};
+
+/// Packs a token account of `mint` owned by `owner`, with `delegate` approved for 100.
+fn token_data(mint: Pubkey, owner: Pubkey, delegate: Option<Pubkey>) -> Vec<u8> {
+    let mut data = vec![0; TokenAccount::LEN];
+    let account = TokenAccount {
+        mint,
+        owner,
+        amount: 1_000,
+        delegate: delegate.map_or(COption::None, COption::Some),
+        state: AccountState::Initialized,
+        is_native: COption::None,
+        delegated_amount: if delegate.is_some() { 100 } else { 0 },
+        close_authority: COption::None,
+    };
+    TokenAccount::pack(account, &mut data).unwrap();
+    data
+}
+
+#[test]
+fn transfers_need_matching_programs_mints_and_authorities() {
+    let (mint, other_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (owner, delegate, stranger) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let keys = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+    let (token_program, system) = (spl_token::id(), system_program::id());
+    let (mut src_lamports, mut dest_lamports, mut other_lamports) = (0, 0, 0);
+    let mut src_data = token_data(mint, owner, Some(delegate));
+    let mut dest_data = token_data(mint, stranger, None);
+    let mut other_data = token_data(other_mint, stranger, None);
+    let src = AccountInfo::new(&keys[0], false, true, &mut src_lamports, &mut src_data, &token_program, false, 0);
+    let dest = AccountInfo::new(&keys[1], false, true, &mut dest_lamports, &mut dest_data, &token_program, false, 0);
+    let other = AccountInfo::new(&keys[2], false, true, &mut other_lamports, &mut other_data, &token_program, false, 0);
+
+    DLUToken::token_guards(&src, &dest, &owner, 1_000, Some(&mint)).unwrap();
+    DLUToken::token_guards(&src, &dest, &delegate, 100, None).unwrap();
+    assert_eq!(DLUToken::token_guards(&src, &dest, &delegate, 101, None), Err(DLUError::TokenAuthorityMismatch));
+    assert_eq!(DLUToken::token_guards(&src, &dest, &stranger, 1, None), Err(DLUError::TokenAuthorityMismatch));
+    assert_eq!(DLUToken::token_guards(&src, &other, &owner, 1, None), Err(DLUError::MintMismatch));
+    assert_eq!(DLUToken::token_guards(&src, &dest, &owner, 1, Some(&other_mint)), Err(DLUError::MintMismatch));
+
+    // Data laid out like a token account proves nothing unless the token program owns it.
+    let mut fake_lamports = 0;
+    let mut fake_data = token_data(mint, stranger, None);
+    let fake = AccountInfo::new(&keys[2], false, true, &mut fake_lamports, &mut fake_data, &system, false, 0);
+    assert_eq!(DLUToken::token_guards(&src, &fake, &owner, 1, None), Err(DLUError::InvalidAccountOwner));
+    assert_eq!(DLUToken::token_guards(&fake, &dest, &stranger, 1, None), Err(DLUError::InvalidAccountOwner));
+}
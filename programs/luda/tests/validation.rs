@@ -0,0 +1,72 @@
+mod common;
+
+use common::Harness;
+use luda::addressing::{derive_index_address, derive_profile_summary_address};
+use luda::constants::{MAX_DESCRIPTION_LEN, MAX_ITEM_NAME_LEN, MAX_USERNAME_LEN};
+use luda::errors::DLUError;
+use luda::instruction::DLUInstruction;
+use luda::location::Location;
+use luda::validation;
+use solana_program::instruction::{AccountMeta, InstructionError};
+use solana_program::pubkey::Pubkey;
+use solana_program::system_program;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::TransactionError;
+
+#[test]
+fn usernames_keep_to_a_small_ascii_set() {
+    for username in ["seller", "runner-up", "j.doe_2", &"x".repeat(MAX_USERNAME_LEN)] {
+        assert_eq!(validation::username(username), Ok(()));
+    }
+    assert_eq!(validation::username(""), Err(DLUError::EmptyField));
+    assert_eq!(validation::username(&"x".repeat(MAX_USERNAME_LEN + 1)), Err(DLUError::FieldTooLong));
+    for username in ["j doe", "jürgen", "admin\n"] {
+        assert_eq!(validation::username(username), Err(DLUError::InvalidCharacters));
+    }
+}
+
+#[test]
+fn listing_text_is_bounded_and_free_of_control_characters() {
+    assert_eq!(validation::item_name("Fahrrad für Kinder"), Ok(()));
+    assert_eq!(validation::item_name(""), Err(DLUError::EmptyField));
+    assert_eq!(validation::item_name(&"x".repeat(MAX_ITEM_NAME_LEN + 1)), Err(DLUError::FieldTooLong));
+    assert_eq!(validation::item_name("Bike\nfor sale"), Err(DLUError::InvalidCharacters));
+
+    // Descriptions may be empty or span lines, but not carry other control characters.
+    assert_eq!(validation::description(""), Ok(()));
+    assert_eq!(validation::description("City bike.\n\tBarely used."), Ok(()));
+    assert_eq!(validation::description(&"x".repeat(MAX_DESCRIPTION_LEN + 1)), Err(DLUError::FieldTooLong));
+    assert_eq!(validation::description("City bike\u{1b}[2J"), Err(DLUError::InvalidCharacters));
+}
+
+#[test]
+fn meeting_points_are_checked_past_their_bounds() {
+    let berlin = Location::new("DE", "Berlin".to_string(), "Alexanderplatz 1".to_string()).unwrap();
+    assert_eq!(validation::location(&berlin), Ok(()));
+    let garbled = Location::new("DE", "Berlin".to_string(), "Alexanderplatz\r1".to_string()).unwrap();
+    assert_eq!(validation::location(&garbled), Err(DLUError::InvalidCharacters));
+}
+
+#[tokio::test]
+async fn users_cannot_register_with_unfit_usernames() {
+    let mut h = Harness::start().await;
+    let owner = Keypair::new();
+    let user = Pubkey::new_unique();
+    let summary = derive_profile_summary_address(&h.program_id, &owner.pubkey()).0;
+    h.set_program_account(user, 1_024);
+
+    let instruction = DLUInstruction::CreateUser { username: "j doe".to_string(), terms_hash: h.terms_hash, referrer: None };
+    let accounts = vec![
+        AccountMeta::new(user, false),
+        AccountMeta::new_readonly(owner.pubkey(), true),
+        AccountMeta::new_readonly(h.config(), false),
+        AccountMeta::new(summary, false),
+        AccountMeta::new(h.stats(), false),
+        AccountMeta::new(derive_index_address(&h.program_id, &owner.pubkey()).0, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new(h.context.payer.pubkey(), true),
+    ];
+    let error = h.try_process_all(&[h.instruction(&instruction, accounts)], &[&owner]).await.unwrap_err();
+    let code = DLUError::InvalidCharacters.code();
+    assert_eq!(error, TransactionError::InstructionError(0, InstructionError::Custom(code)));
+}
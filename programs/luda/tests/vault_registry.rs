@@ -0,0 +1,128 @@
+mod common;
+
+use common::{DealKind, Harness, PAYMENT};
+use luda::addressing::EntityType;
+use luda::config::CAP_ESCROW_MIGRATION;
+use luda::constants::{MAX_REGISTERED_VAULTS, VAULT_REGISTRY_SIZE};
+use luda::discriminator::AccountKind;
+use luda::errors::DLUError;
+use luda::instruction::DLUInstruction;
+use luda::migrations::{account_space, payload_of};
+use luda::vault_registry::VaultRegistry;
+use solana_program::instruction::{AccountMeta, Instruction, InstructionError};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::TransactionError;
+
+fn custom(code: DLUError) -> TransactionError {
+    TransactionError::InstructionError(0, InstructionError::Custom(code.code()))
+}
+
+fn migrate_instruction(h: &Harness, offer: Pubkey, legacy_escrow: Pubkey, legacy_authority: &Keypair) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(h.config(), false),
+        AccountMeta::new_readonly(h.context.payer.pubkey(), true),
+        AccountMeta::new(offer, false),
+        AccountMeta::new(legacy_escrow, false),
+        AccountMeta::new_readonly(legacy_authority.pubkey(), true),
+        AccountMeta::new(h.vault(), false),
+        AccountMeta::new_readonly(h.vault_registry(), false),
+    ];
+    h.instruction(&DLUInstruction::MigrateEscrow { entity_type: EntityType::Offer }, accounts)
+}
+
+/// Rewinds a listed offer to before the vaults: its deposit moves back to a legacy escrow
+/// account of `legacy_authority`, and the offer no longer names the vault.
+async fn unmigrate(h: &mut Harness, offer: Pubkey, legacy_escrow: Pubkey, legacy_authority: &Keypair) {
+    let vault = h.vault();
+    let data = h.account(offer).await.data;
+    let mut payload = payload_of(&data).unwrap().to_vec();
+    let named = [&[1][..], vault.as_ref()].concat();
+    let at = payload.windows(named.len()).position(|window| window == named).unwrap();
+    payload.splice(at..at + named.len(), [0]);
+    h.set_account_data(offer, AccountKind::Offer, &payload, data.len() - account_space(payload.len()));
+
+    let deposit = h.balance(vault).await;
+    h.set_token_account(legacy_escrow, legacy_authority.pubkey(), deposit);
+    h.set_token_account(vault, vault, 0);
+}
+
+#[tokio::test]
+async fn governance_creates_each_vault_once() {
+    let mut h = Harness::start().await;
+    // The harness mint already has its vault; governance creates the one of a second mint.
+    h.mint = Pubkey::new_unique();
+    h.set_mint();
+
+    h.register_vault().await;
+    assert_eq!(h.balance(h.vault()).await, 0);
+    assert_eq!(h.balance(h.treasury()).await, 0);
+    let vaults = h.decode(h.vault_registry(), VaultRegistry::deserialize).await;
+    assert_eq!(vaults.vault_of(&h.mint), Some(h.vault()));
+
+    h.next_blockhash().await;
+    let error = h.try_process_all(&[h.register_vault_instruction()], &[]).await;
+    assert_eq!(error.unwrap_err(), custom(DLUError::VaultAlreadyRegistered));
+}
+
+#[tokio::test]
+async fn escrow_only_migrates_into_registered_vaults() {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    let (offer, _) = h.list_deal(DealKind::Offer, &seller, &buyer).await;
+    let (legacy_escrow, legacy_authority) = (Pubkey::new_unique(), Keypair::new());
+    unmigrate(&mut h, offer, legacy_escrow, &legacy_authority).await;
+    let governance = h.context.payer.pubkey();
+    let accounts = vec![AccountMeta::new(h.config(), false), AccountMeta::new_readonly(governance, true)];
+    let capabilities = DLUInstruction::SetCapabilities { capabilities: CAP_ESCROW_MIGRATION };
+    h.process(h.instruction(&capabilities, accounts), &[]).await;
+
+    // Escrow still in the legacy account can't be reconciled against the vault.
+    let accounts = vec![
+        AccountMeta::new(offer, false),
+        AccountMeta::new_readonly(h.vault(), false),
+        AccountMeta::new_readonly(h.vault_registry(), false),
+        AccountMeta::new_readonly(h.stats(), false),
+    ];
+    let verify = h.instruction(&DLUInstruction::VerifyEscrow { entity_type: EntityType::Offer }, accounts);
+    let error = h.try_process_all(&[verify], &[]).await;
+    assert_eq!(error.unwrap_err(), custom(DLUError::EscrowNotInVault));
+
+    // Rewind the registry to one created for another mint only: the vault exists, but
+    // nothing vouches for it until governance registers it.
+    let mint = h.mint;
+    h.fund(h.vault_registry(), 0);
+    h.mint = Pubkey::new_unique();
+    h.set_mint();
+    h.register_vault().await;
+    h.mint = mint;
+    let migrate = migrate_instruction(&h, offer, legacy_escrow, &legacy_authority);
+    let error = h.try_process_all(&[migrate.clone()], &[&legacy_authority]).await;
+    assert_eq!(error.unwrap_err(), custom(DLUError::VaultNotRegistered));
+
+    // Registering creates the vault anew, so the planted one goes first.
+    h.fund(h.vault(), 0);
+    h.register_vault().await;
+    h.next_blockhash().await;
+    h.process(migrate, &[&legacy_authority]).await;
+    assert_eq!(h.balance(h.vault()).await, PAYMENT);
+    assert_eq!(h.balance(legacy_escrow).await, 0);
+}
+
+#[test]
+fn the_registry_holds_one_vault_per_mint() {
+    let mut registry = VaultRegistry::default();
+    let mint = Pubkey::new_unique();
+    let vault = Pubkey::new_unique();
+    registry.register(mint, vault, 0).unwrap();
+    assert_eq!(registry.register(mint, Pubkey::new_unique(), 1), Err(DLUError::VaultAlreadyRegistered));
+    assert_eq!(registry.check_registered(&mint, &Pubkey::new_unique()), Err(DLUError::VaultNotRegistered));
+    registry.check_registered(&mint, &vault).unwrap();
+
+    for _ in 1..MAX_REGISTERED_VAULTS {
+        registry.register(Pubkey::new_unique(), Pubkey::new_unique(), 0).unwrap();
+    }
+    assert_eq!(registry.register(Pubkey::new_unique(), Pubkey::new_unique(), 0), Err(DLUError::VaultRegistryFull));
+    assert!(registry.serialize().unwrap().len() <= VAULT_REGISTRY_SIZE);
+}
@@ -0,0 +1,100 @@
+mod common;
+
+use common::{DealKind, Harness, Party, PAYMENT, STARTING_BALANCE};
+use luda::errors::DLUError;
+use luda::instruction::DLUInstruction;
+use solana_program::instruction::{AccountMeta, Instruction, InstructionError};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+use solana_sdk::transaction::TransactionError;
+
+const DEPOSIT: u64 = 5_000_000;
+
+/// Creates a user along with their wallet token account, which is its own authority.
+async fn user_with_wallet(h: &mut Harness, username: &str) -> Party {
+    let party = h.create_user(username).await;
+    let wallet = h.wallet(&party);
+    h.set_token_account(wallet, wallet, 0);
+    party
+}
+
+async fn move_dlu(h: &mut Harness, party: &Party, instruction: DLUInstruction) {
+    let accounts = vec![
+        AccountMeta::new(party.user, false),
+        AccountMeta::new_readonly(party.owner.pubkey(), true),
+        AccountMeta::new(party.token, false),
+        AccountMeta::new(h.wallet(party), false),
+    ];
+    h.process(h.instruction(&instruction, accounts), &[&party.owner]).await;
+}
+
+fn deposit_and_accept(h: &Harness, offer: Pubkey, seller: &Party, buyer: &Party, amount: u64) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new(offer, false),
+        AccountMeta::new(buyer.user, false),
+        AccountMeta::new_readonly(buyer.owner.pubkey(), true),
+        AccountMeta::new(buyer.token, false),
+        AccountMeta::new(h.wallet(buyer), false),
+        AccountMeta::new(h.escrow, false),
+        AccountMeta::new_readonly(h.vault_registry(), false),
+        AccountMeta::new_readonly(h.config(), false),
+        AccountMeta::new(h.stats(), false),
+        AccountMeta::new(h.index(seller), false),
+    ];
+    h.instruction(&DLUInstruction::DepositAndAccept { amount, quantity: 1, promo_code: None }, accounts)
+}
+
+#[tokio::test]
+async fn deposit_and_withdraw_keep_the_wallet_in_sync() {
+    let mut h = Harness::start().await;
+    let user = user_with_wallet(&mut h, "user").await;
+
+    move_dlu(&mut h, &user, DLUInstruction::DepositDLU { amount: DEPOSIT }).await;
+    assert_eq!(h.balance(user.token).await, STARTING_BALANCE - DEPOSIT);
+    assert_eq!(h.balance(h.wallet(&user)).await, DEPOSIT);
+    assert_eq!(h.user(&user).await.wallet.balance, DEPOSIT);
+
+    move_dlu(&mut h, &user, DLUInstruction::WithdrawDLU { amount: DEPOSIT }).await;
+    assert_eq!(h.balance(user.token).await, STARTING_BALANCE);
+    assert_eq!(h.balance(h.wallet(&user)).await, 0);
+    assert_eq!(h.user(&user).await.wallet.balance, 0);
+}
+
+#[tokio::test]
+async fn reconcile_repairs_a_drifted_wallet() {
+    let mut h = Harness::start().await;
+    let user = user_with_wallet(&mut h, "user").await;
+    move_dlu(&mut h, &user, DLUInstruction::DepositDLU { amount: DEPOSIT }).await;
+
+    // Tokens sent straight to the wallet account bypass the cached balance.
+    let wallet = h.wallet(&user);
+    h.set_token_account(wallet, wallet, 2 * DEPOSIT);
+    assert_eq!(h.user(&user).await.wallet.balance, DEPOSIT);
+
+    let accounts = vec![AccountMeta::new(user.user, false), AccountMeta::new_readonly(wallet, false)];
+    h.process(h.instruction(&DLUInstruction::ReconcileWallet, accounts), &[]).await;
+    assert_eq!(h.user(&user).await.wallet.balance, 2 * DEPOSIT);
+}
+
+#[tokio::test]
+async fn buyers_top_up_their_wallet_and_accept_in_one_instruction() {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let buyer = user_with_wallet(&mut h, "buyer").await;
+    move_dlu(&mut h, &buyer, DLUInstruction::DepositDLU { amount: DEPOSIT }).await;
+    let (offer, _) = h.list_deal(DealKind::Offer, &seller, &buyer).await;
+    let escrowed = h.balance(h.escrow).await;
+
+    // The buyer locks the payment and its insurance, part of it already in the wallet.
+    let deposit = 2 * PAYMENT;
+    let error = h.try_process_all(&[deposit_and_accept(&h, offer, &seller, &buyer, deposit - DEPOSIT - 1)], &[&buyer.owner]).await;
+    let expected = TransactionError::InstructionError(0, InstructionError::Custom(DLUError::InsufficientFunds.code()));
+    assert_eq!(error.unwrap_err(), expected);
+    assert_eq!(h.balance(buyer.token).await, STARTING_BALANCE - DEPOSIT);
+
+    h.process(deposit_and_accept(&h, offer, &seller, &buyer, deposit - DEPOSIT), &[&buyer.owner]).await;
+    assert_eq!(h.balance(buyer.token).await, STARTING_BALANCE - deposit);
+    assert_eq!(h.balance(h.wallet(&buyer)).await, 0);
+    assert_eq!(h.user(&buyer).await.wallet.balance, 0);
+    assert_eq!(h.balance(h.escrow).await, escrowed + deposit);
+}
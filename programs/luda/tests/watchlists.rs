@@ -0,0 +1,97 @@
+mod common;
+
+use common::{DealKind, Harness, Party, ENTITY_SPACE};
+use luda::addressing::derive_watchlist_address;
+use luda::constants::MAX_WATCHED_OFFERS;
+use luda::errors::DLUError;
+use luda::instruction::DLUInstruction;
+use luda::migrations;
+use luda::offer::OfferStatus;
+use luda::watchlist::Watchlist;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_program::system_program;
+use solana_sdk::signature::Signer;
+
+fn watchlist(h: &Harness, owner: &Party) -> Pubkey {
+    derive_watchlist_address(&h.program_id, &owner.owner.pubkey()).0
+}
+
+fn follow_instruction(h: &Harness, follower: &Party, user: Pubkey, follow: bool) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(follower.owner.pubkey(), true),
+        AccountMeta::new(watchlist(h, follower), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new(h.context.payer.pubkey(), true),
+    ];
+    h.instruction(&DLUInstruction::FollowUser { user, follow }, accounts)
+}
+
+fn watch_instruction(h: &Harness, watcher: &Party, offer: Pubkey, watch: bool) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(watcher.owner.pubkey(), true),
+        AccountMeta::new(watchlist(h, watcher), false),
+        AccountMeta::new_readonly(offer, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new(h.context.payer.pubkey(), true),
+    ];
+    h.instruction(&DLUInstruction::WatchOffer { watch }, accounts)
+}
+
+async fn read_watchlist(h: &mut Harness, owner: &Party) -> Watchlist {
+    h.decode(watchlist(h, owner), Watchlist::deserialize).await
+}
+
+#[tokio::test]
+async fn wallets_follow_users_and_watch_offers() {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    let (offer, _) = h.list_deal(DealKind::Offer, &seller, &buyer).await;
+
+    let itself = follow_instruction(&h, &buyer, buyer.owner.pubkey(), true);
+    assert!(h.try_process_all(&[itself], &[&buyer.owner]).await.is_err());
+    let not_an_offer = Pubkey::new_unique();
+    h.set_program_account(not_an_offer, ENTITY_SPACE);
+    let unlisted = watch_instruction(&h, &buyer, not_an_offer, true);
+    assert!(h.try_process_all(&[unlisted], &[&buyer.owner]).await.is_err());
+
+    let follow = follow_instruction(&h, &buyer, seller.owner.pubkey(), true);
+    let watch = watch_instruction(&h, &buyer, offer, true);
+    h.process_all(&[follow, watch], &[&buyer.owner]).await;
+    let listed = read_watchlist(&mut h, &buyer).await;
+    assert_eq!(listed.followed, vec![seller.owner.pubkey()]);
+    assert_eq!(listed.watched, vec![offer]);
+
+    h.process(follow_instruction(&h, &buyer, seller.owner.pubkey(), false), &[&buyer.owner]).await;
+    assert!(read_watchlist(&mut h, &buyer).await.followed.is_empty());
+}
+
+#[tokio::test]
+async fn offer_statuses_are_read_in_place_for_watchers() {
+    let mut h = Harness::start().await;
+    let seller = h.create_user("seller").await;
+    let buyer = h.create_user("buyer").await;
+    let (offer, _) = h.list_deal(DealKind::Offer, &seller, &buyer).await;
+    let status = |data: &[u8]| migrations::deal_status_of(data);
+
+    assert_eq!(status(&h.account(offer).await.data), Some(OfferStatus::Listed));
+    h.accept_deal(DealKind::Offer, offer, &seller, &buyer).await;
+    assert_eq!(status(&h.account(offer).await.data), Some(OfferStatus::Accepted));
+    assert_eq!(status(&[0; ENTITY_SPACE]), None);
+}
+
+#[test]
+fn watchlists_stay_bounded() {
+    let mut watchlist = Watchlist::default();
+    for _ in 0..MAX_WATCHED_OFFERS {
+        watchlist.watch(Pubkey::new_unique(), true).unwrap();
+    }
+    // Watching an offer twice keeps a single entry, and a full list takes no new offer.
+    let watched = watchlist.watched[0];
+    watchlist.watch(watched, true).unwrap();
+    assert!(matches!(watchlist.watch(Pubkey::new_unique(), true), Err(DLUError::WatchlistFull)));
+
+    watchlist.watch(watched, false).unwrap();
+    assert_eq!(watchlist.watched.len(), MAX_WATCHED_OFFERS - 1);
+}